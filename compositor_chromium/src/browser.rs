@@ -1,3 +1,5 @@
+use std::os::raw::c_int;
+
 use crate::{
     cef_string::CefString,
     frame::Frame,
@@ -31,6 +33,195 @@ impl Browser {
             Ok(Frame::new(get_main_frame(browser)))
         }
     }
+
+    pub fn send_mouse_move_event(
+        &self,
+        event: MouseEvent,
+        mouse_leave: bool,
+    ) -> Result<(), BrowserError> {
+        unsafe {
+            let host = self.host()?;
+            let send_mouse_move_event = (*host).send_mouse_move_event.unwrap();
+            send_mouse_move_event(host, &event.into_raw(), mouse_leave as c_int);
+        }
+        Ok(())
+    }
+
+    pub fn send_mouse_click_event(
+        &self,
+        event: MouseEvent,
+        button: MouseButton,
+        mouse_up: bool,
+        click_count: i32,
+    ) -> Result<(), BrowserError> {
+        unsafe {
+            let host = self.host()?;
+            let send_mouse_click_event = (*host).send_mouse_click_event.unwrap();
+            send_mouse_click_event(
+                host,
+                &event.into_raw(),
+                button.into_raw(),
+                mouse_up as c_int,
+                click_count,
+            );
+        }
+        Ok(())
+    }
+
+    pub fn send_mouse_wheel_event(
+        &self,
+        event: MouseEvent,
+        delta_x: i32,
+        delta_y: i32,
+    ) -> Result<(), BrowserError> {
+        unsafe {
+            let host = self.host()?;
+            let send_mouse_wheel_event = (*host).send_mouse_wheel_event.unwrap();
+            send_mouse_wheel_event(host, &event.into_raw(), delta_x, delta_y);
+        }
+        Ok(())
+    }
+
+    /// Windowless browsers ignore key events until they are focused, so the browser
+    /// is focused before the event is sent.
+    pub fn send_key_event(&self, event: KeyEvent) -> Result<(), BrowserError> {
+        unsafe {
+            let host = self.host()?;
+            let set_focus = (*host).set_focus.unwrap();
+            set_focus(host, true as c_int);
+            let send_key_event = (*host).send_key_event.unwrap();
+            send_key_event(host, &event.into_raw());
+        }
+        Ok(())
+    }
+
+    pub fn set_audio_muted(&self, mute: bool) -> Result<(), BrowserError> {
+        unsafe {
+            let host = self.host()?;
+            let set_audio_muted = (*host).set_audio_muted.unwrap();
+            set_audio_muted(host, mute as c_int);
+        }
+        Ok(())
+    }
+
+    fn host(&self) -> Result<*mut chromium_sys::cef_browser_host_t, BrowserError> {
+        unsafe {
+            let browser = self.inner.get()?;
+            let get_host = (*browser).get_host.unwrap();
+            Ok(get_host(browser))
+        }
+    }
+}
+
+/// Position of the mouse in view coordinates (pixels relative to the top-left
+/// corner of the page).
+#[derive(Debug, Clone, Copy)]
+pub struct MouseEvent {
+    pub x: i32,
+    pub y: i32,
+    pub modifiers: EventModifiers,
+}
+
+impl MouseEvent {
+    fn into_raw(self) -> chromium_sys::cef_mouse_event_t {
+        chromium_sys::cef_mouse_event_t {
+            x: self.x,
+            y: self.y,
+            modifiers: self.modifiers.into_raw(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+impl MouseButton {
+    fn into_raw(self) -> chromium_sys::cef_mouse_button_type_t {
+        match self {
+            MouseButton::Left => chromium_sys::cef_mouse_button_type_t_MBT_LEFT,
+            MouseButton::Middle => chromium_sys::cef_mouse_button_type_t_MBT_MIDDLE,
+            MouseButton::Right => chromium_sys::cef_mouse_button_type_t_MBT_RIGHT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct KeyEvent {
+    pub event_type: KeyEventType,
+    /// Windows virtual key code (e.g. 0x0D for Enter), used by `RawKeyDown` and `KeyUp`.
+    pub key_code: i32,
+    /// UTF-16 code unit of the typed character, used by `Char`.
+    pub character: u16,
+    pub modifiers: EventModifiers,
+}
+
+impl KeyEvent {
+    fn into_raw(self) -> chromium_sys::cef_key_event_t {
+        chromium_sys::cef_key_event_t {
+            type_: self.event_type.into_raw(),
+            modifiers: self.modifiers.into_raw(),
+            windows_key_code: self.key_code,
+            native_key_code: 0,
+            is_system_key: false as c_int,
+            character: self.character,
+            unmodified_character: self.character,
+            focus_on_editable_field: false as c_int,
+        }
+    }
+}
+
+/// Typing a character consists of `RawKeyDown`, `Char` and `KeyUp` events.
+#[derive(Debug, Clone, Copy)]
+pub enum KeyEventType {
+    RawKeyDown,
+    KeyUp,
+    Char,
+}
+
+impl KeyEventType {
+    fn into_raw(self) -> chromium_sys::cef_key_event_type_t {
+        match self {
+            KeyEventType::RawKeyDown => chromium_sys::cef_key_event_type_t_KEYEVENT_RAWKEYDOWN,
+            KeyEventType::KeyUp => chromium_sys::cef_key_event_type_t_KEYEVENT_KEYUP,
+            KeyEventType::Char => chromium_sys::cef_key_event_type_t_KEYEVENT_CHAR,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EventModifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    pub command: bool,
+}
+
+impl EventModifiers {
+    fn into_raw(self) -> u32 {
+        let flags = [
+            (
+                self.shift,
+                chromium_sys::cef_event_flags_t_EVENTFLAG_SHIFT_DOWN,
+            ),
+            (
+                self.control,
+                chromium_sys::cef_event_flags_t_EVENTFLAG_CONTROL_DOWN,
+            ),
+            (self.alt, chromium_sys::cef_event_flags_t_EVENTFLAG_ALT_DOWN),
+            (
+                self.command,
+                chromium_sys::cef_event_flags_t_EVENTFLAG_COMMAND_DOWN,
+            ),
+        ];
+        flags
+            .into_iter()
+            .filter(|(is_set, _)| *is_set)
+            .fold(0, |modifiers, (_, flag)| modifiers | flag as u32)
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
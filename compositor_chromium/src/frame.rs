@@ -1,5 +1,6 @@
 use crate::{
     cef::{ProcessId, ProcessMessage, ThreadId, V8Context},
+    cef_string::CefString,
     validated::{Validatable, Validated, ValidatedError},
 };
 
@@ -31,6 +32,20 @@ impl Frame {
         Ok(())
     }
 
+    /// Executes JavaScript code in the frame. Exceptions thrown by the code are only
+    /// logged to the page's console.
+    pub fn execute_javascript(&self, code: &str) -> Result<(), FrameError> {
+        unsafe {
+            let frame = self.inner.get()?;
+            let execute_java_script = (*frame).execute_java_script.unwrap();
+            let code = CefString::new_raw(code);
+            let script_url = CefString::new_raw("");
+            execute_java_script(frame, &code, &script_url, 0);
+        }
+
+        Ok(())
+    }
+
     /// If called on the renderer process it returns `Ok(V8Context)`, otherwise it's `Err(FrameError::V8ContextWrongThread)`
     pub fn v8_context(&self) -> Result<V8Context, FrameError> {
         let frame = self.inner.get()?;
@@ -1,6 +1,6 @@
 use log::warn;
 
-use crate::cef::V8Value;
+use crate::cef::{Frame, V8Value};
 use crate::cef_string::CefString;
 use crate::{
     cef::V8Global,
@@ -21,6 +21,22 @@ impl V8Context {
         Self { inner }
     }
 
+    /// Context of the JavaScript code that is currently executing, e.g. the caller
+    /// of a native function
+    pub fn current() -> Self {
+        let inner = unsafe { chromium_sys::cef_v8context_get_current_context() };
+        Self::new(inner)
+    }
+
+    /// Frame that owns the context
+    pub fn frame(&self) -> Result<Frame, V8ContextError> {
+        unsafe {
+            let ctx = self.inner.get()?;
+            let get_frame = (*ctx).get_frame.unwrap();
+            Ok(Frame::new(get_frame(ctx)))
+        }
+    }
+
     pub fn enter(&self) -> Result<V8ContextEntered<'_>, V8ContextError> {
         unsafe {
             let ctx = self.inner.get()?;
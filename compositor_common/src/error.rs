@@ -5,8 +5,11 @@ use log::error;
 use crate::{
     renderer_spec::RendererId,
     scene::{
-        constraints::input_count::InputCountConstraint, transition::TransitionSpec, NodeId,
-        NodeParams, OutputId,
+        builtin_transformations::{BORDER_MAX_SIZE, PRIVACY_MASK_MAX_REGIONS_COUNT},
+        constraints::input_count::InputCountConstraint,
+        shader::MAX_PROCEDURAL_VERTEX_COUNT,
+        transition::TransitionSpec,
+        NodeId, NodeParams, OutputId,
     },
 };
 
@@ -95,6 +98,26 @@ impl Display for InputCountConstraintValidationError {
 pub enum NodeSpecValidationError {
     #[error(transparent)]
     Builtin(#[from] BuiltinSpecValidationError),
+    #[error(transparent)]
+    Sampler(#[from] SamplerSpecValidationError),
+    #[error(transparent)]
+    Geometry(#[from] ShaderGeometryValidationError),
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum SamplerSpecValidationError {
+    #[error("Sampler anisotropy has to be one of 1, 2, 4, 8 or 16, but {0} was specified.")]
+    InvalidAnisotropy(u16),
+    #[error("Sampler anisotropy greater than 1 requires linear filtering.")]
+    AnisotropyRequiresLinearFilter,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ShaderGeometryValidationError {
+    #[error("Procedural geometry is drawn as a list of triangles, vertex count has to be a positive multiple of 3, but {0} was specified.")]
+    IncompleteTriangles(u32),
+    #[error("Procedural geometry can have at most {MAX_PROCEDURAL_VERTEX_COUNT} vertices, but {0} were specified.")]
+    TooManyVertices(u32),
 }
 
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
@@ -109,6 +132,24 @@ pub enum BuiltinSpecValidationError {
     FixedLayoutTopBottomOnlyOne,
     #[error("Fields \"left\" and \"right\" are mutually exclusive, you can only specify one in texture layout in \"fixed_position_layout\" transformation.")]
     FixedLayoutLeftRightOnlyOne,
+    #[error("Transformation \"privacy_mask\" supports at most {} regions, but {regions_count} regions were specified.", PRIVACY_MASK_MAX_REGIONS_COUNT)]
+    PrivacyMaskTooManyRegions { regions_count: u32 },
+    #[error(
+        "Blur radius and block size in transformation \"privacy_mask\" have to be greater than 0."
+    )]
+    PrivacyMaskZeroEffectSize,
+    #[error("Posterize effect in transformation \"stylize\" requires at least 2 levels.")]
+    StylizePosterizeLevels,
+    #[error("Effect strength in transformation \"stylize\" has to be in range [0, 1].")]
+    StylizeStrengthOutOfRange,
+    #[error("Scale and anchor in transformation \"transform_2d\" have to be finite numbers.")]
+    Transform2dNotFinite,
+    #[error("Scale, anchor and translate_z in transformation \"perspective\" have to be finite numbers.")]
+    PerspectiveNotFinite,
+    #[error("Distance in transformation \"perspective\" has to be a positive number.")]
+    PerspectiveInvalidDistance,
+    #[error("Border width and size of the shadow (blur radius and offset) in transformation \"border\" can not exceed {} pixels.", BORDER_MAX_SIZE)]
+    BorderTooLarge,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -116,6 +157,9 @@ pub enum NodeIdentifier {
     WebRenderer(RendererId),
     Shader(RendererId),
     Text,
+    Captions,
+    Timecode,
+    Replay,
     Image(RendererId),
     Builtin(&'static str),
     Transition(&'static str, &'static str),
@@ -127,7 +171,10 @@ impl From<&NodeParams> for NodeIdentifier {
             NodeParams::WebRenderer { instance_id } => Self::WebRenderer(instance_id.clone()),
             NodeParams::Shader { shader_id, .. } => Self::Shader(shader_id.clone()),
             NodeParams::Text(_) => Self::Text,
-            NodeParams::Image { image_id } => Self::Image(image_id.clone()),
+            NodeParams::Captions(_) => Self::Captions,
+            NodeParams::Timecode(_) => Self::Timecode,
+            NodeParams::Replay(_) => Self::Replay,
+            NodeParams::Image { image_id, .. } => Self::Image(image_id.clone()),
             NodeParams::Builtin(transformation) => {
                 Self::Builtin(transformation.transformation_name())
             }
@@ -146,6 +193,9 @@ impl Display for NodeIdentifier {
             }
             NodeIdentifier::Shader(shader_id) => write!(f, "\"{}\" shader", shader_id),
             NodeIdentifier::Text => write!(f, "Text"),
+            NodeIdentifier::Captions => write!(f, "Captions"),
+            NodeIdentifier::Timecode => write!(f, "Timecode"),
+            NodeIdentifier::Replay => write!(f, "Replay"),
             NodeIdentifier::Image(image_id) => write!(f, "\"{}\" image", image_id),
             NodeIdentifier::Builtin(builtin_name) => {
                 write!(f, "\"{}\" builtin transformation", builtin_name)
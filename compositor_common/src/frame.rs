@@ -1,17 +1,165 @@
 use std::time::Duration;
 
-use crate::scene::Resolution;
+use crate::{scene::Resolution, util::colors::RGBColor};
 
 #[derive(Debug, Clone)]
 pub struct Frame {
-    pub data: YuvData,
+    pub data: FrameData,
     pub resolution: Resolution,
+    pub format: FrameFormat,
     pub pts: Duration,
 }
 
+#[derive(Debug, Clone)]
+pub enum FrameData {
+    /// Planar YUV 4:2:0, layout of samples is described by [`PixelFormat`].
+    /// Frames produced by the renderer always use it.
+    PlanarYuv(YuvData),
+    /// 8-bit YUV 4:2:0 with interleaved chroma samples, common output of
+    /// hardware decoders. Pixel format of the frame is always
+    /// [`PixelFormat::Yuv420p`].
+    Nv12(Nv12Data),
+    /// Interleaved 8-bit RGBA samples with sRGB transfer, e.g. from screen
+    /// capture or web sources. Color space of the frame format is ignored.
+    Rgba(bytes::Bytes),
+}
+
 #[derive(Debug, Clone)]
 pub struct YuvData {
     pub y_plane: bytes::Bytes,
     pub u_plane: bytes::Bytes,
     pub v_plane: bytes::Bytes,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FrameFormat {
+    pub pixel_format: PixelFormat,
+    pub color_space: ColorSpace,
+}
+
+#[derive(Debug, Clone)]
+pub struct Nv12Data {
+    pub y_plane: bytes::Bytes,
+    /// U and V samples alternating in a single plane subsampled to half of the
+    /// width and height.
+    pub uv_plane: bytes::Bytes,
+}
+
+/// Layout of samples in [`YuvData`] planes. Chroma planes are always subsampled
+/// to half of the width and height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PixelFormat {
+    /// 8 bits per sample
+    #[default]
+    Yuv420p,
+    /// 10 bits per sample, each sample is stored as little-endian u16
+    Yuv420p10le,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorSpace {
+    /// Full range YCbCr with BT.601 coefficients and sRGB transfer.
+    #[default]
+    Bt601,
+    /// Limited range YCbCr with BT.709 coefficients (e.g. 16-235 for Y in 8-bit).
+    /// Most HD streams use it.
+    Bt709,
+    /// Limited range YCbCr with BT.2020 primaries and SMPTE ST 2084 (PQ) transfer
+    /// (HDR10). Rendering happens in SDR, so HDR inputs are tone-mapped when they
+    /// are converted to RGBA and SDR content is placed at 203 nits on HDR outputs.
+    Bt2020Pq,
+}
+
+/// Luminance (in nits) that SDR white is mapped to in HDR content (ITU-R BT.2408).
+pub const SDR_REFERENCE_WHITE_NITS: f32 = 203.0;
+
+impl PixelFormat {
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            PixelFormat::Yuv420p => 1,
+            PixelFormat::Yuv420p10le => 2,
+        }
+    }
+
+    pub fn max_sample_value(&self) -> u16 {
+        match self {
+            PixelFormat::Yuv420p => 255,
+            PixelFormat::Yuv420p10le => 1023,
+        }
+    }
+}
+
+impl FrameFormat {
+    /// Returns Y, U and V sample values representing the color in this format.
+    pub fn rgb_to_yuv_samples(&self, color: RGBColor) -> [u16; 3] {
+        let max = self.pixel_format.max_sample_value() as f32;
+        let [y, u, v] = match self.color_space {
+            ColorSpace::Bt601 => {
+                let (y, u, v) = color.to_yuv();
+                [y * max, u * max, v * max]
+            }
+            ColorSpace::Bt709 => to_limited_range(rgb_to_bt709_ycbcr(color), max),
+            ColorSpace::Bt2020Pq => to_limited_range(rgb_to_bt2020_pq_ycbcr(color), max),
+        };
+        [y, u, v].map(|sample| sample.round().clamp(0.0, max) as u16)
+    }
+}
+
+/// Maps Y' in 0.0-1.0 range and Cb, Cr in -0.5-0.5 range to limited range sample
+/// values, e.g. 16-235 for Y in 8-bit and 64-940 in 10-bit.
+fn to_limited_range([y, cb, cr]: [f32; 3], max_sample_value: f32) -> [f32; 3] {
+    let step = (max_sample_value + 1.0) / 256.0;
+    [
+        16.0 * step + 219.0 * step * y,
+        128.0 * step + 224.0 * step * cb,
+        128.0 * step + 224.0 * step * cr,
+    ]
+}
+
+/// Same conversion as in `rgba_to_yuv.wgsl`. Returns Y' in 0.0-1.0 range and
+/// Cb, Cr in -0.5-0.5 range.
+fn rgb_to_bt709_ycbcr(color: RGBColor) -> [f32; 3] {
+    let (r, g, b) = (
+        color.0 as f32 / 255.0,
+        color.1 as f32 / 255.0,
+        color.2 as f32 / 255.0,
+    );
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    [y, (b - y) / 1.8556, (r - y) / 1.5748]
+}
+
+/// Same conversion as in `rgba_to_yuv.wgsl`. Returns Y' in 0.0-1.0 range and
+/// Cb, Cr in -0.5-0.5 range.
+fn rgb_to_bt2020_pq_ycbcr(color: RGBColor) -> [f32; 3] {
+    fn srgb_to_linear(value: u8) -> f32 {
+        let value = value as f32 / 255.0;
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    }
+    fn pq_oetf(linear: f32) -> f32 {
+        const M1: f32 = 0.159_301_76;
+        const M2: f32 = 78.84375;
+        const C1: f32 = 0.8359375;
+        const C2: f32 = 18.851_563;
+        const C3: f32 = 18.6875;
+        let y = linear.clamp(0.0, 1.0).powf(M1);
+        ((C1 + C2 * y) / (1.0 + C3 * y)).powf(M2)
+    }
+
+    let (r, g, b) = (
+        srgb_to_linear(color.0),
+        srgb_to_linear(color.1),
+        srgb_to_linear(color.2),
+    );
+    // BT.709 to BT.2020 primaries
+    let scale = SDR_REFERENCE_WHITE_NITS / 10000.0;
+    let r2020 = pq_oetf((0.6274 * r + 0.3293 * g + 0.0433 * b) * scale);
+    let g2020 = pq_oetf((0.0691 * r + 0.9195 * g + 0.0114 * b) * scale);
+    let b2020 = pq_oetf((0.0164 * r + 0.0880 * g + 0.8956 * b) * scale);
+
+    let y = 0.2627 * r2020 + 0.6780 * g2020 + 0.0593 * b2020;
+    [y, (b2020 - y) / 1.8814, (r2020 - y) / 1.4746]
+}
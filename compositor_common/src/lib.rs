@@ -12,7 +12,7 @@ pub mod util;
 pub type Frame = frame::Frame;
 pub type SceneSpecValidationError = error::SceneSpecValidationError;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Framerate {
     pub num: u32,
     pub den: u32,
@@ -22,4 +22,11 @@ impl Framerate {
     pub fn get_interval_duration(self) -> Duration {
         Duration::from_nanos(1_000_000_000u64 * self.den as u64 / self.num as u64)
     }
+
+    /// PTS of the `frame_index`-th frame. Calculated without accumulating rounding
+    /// errors, so fractional framerates (e.g. 30000/1001) do not drift.
+    pub fn frame_pts(self, frame_index: u64) -> Duration {
+        let nanos = frame_index as u128 * 1_000_000_000 * self.den as u128 / self.num as u128;
+        Duration::from_nanos(nanos as u64)
+    }
 }
@@ -26,6 +26,7 @@ pub enum RendererSpec {
     Shader(ShaderSpec),
     WebRenderer(WebRendererSpec),
     Image(ImageSpec),
+    Mesh(MeshSpec),
 }
 
 #[derive(Debug)]
@@ -39,10 +40,58 @@ pub struct ShaderSpec {
 #[derive(Debug)]
 pub struct WebRendererSpec {
     pub instance_id: RendererId,
-    pub url: String,
+    pub src: WebRendererSrc,
     pub resolution: Resolution,
     pub fallback_strategy: FallbackStrategy,
     pub constraints: NodeConstraints,
+    pub transparent_background: bool,
+    pub mute_audio: bool,
+    pub compositing_mode: WebCompositingMode,
+}
+
+/// How the page is combined with the input sources of a web renderer node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebCompositingMode {
+    /// Sources are embedded in the page and can be placed and styled by it.
+    Embed,
+    /// Sources are stretched to the node resolution and stacked in order. The page
+    /// is drawn on top of them.
+    Overlay,
+    /// Same as `Overlay`, but the page is drawn below the sources.
+    Underlay,
+}
+
+#[derive(Debug)]
+pub enum WebRendererSrc {
+    Url {
+        url: String,
+    },
+    /// HTML file, directory with "index.html" or a zip archive of such directory.
+    LocalPath {
+        path: String,
+    },
+}
+
+/// Geometry that shader nodes can draw instead of the planes of their inputs.
+#[derive(Debug)]
+pub struct MeshSpec {
+    pub mesh_id: RendererId,
+    pub vertices: Vec<MeshVertex>,
+    /// Triangle list indexing `vertices`. If not specified, every 3 consecutive
+    /// vertices form a triangle.
+    pub indices: Option<Vec<u32>>,
+}
+
+/// Attributes passed to the vertex shader as `VertexInput`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshVertex {
+    /// Clip space position, triangles are front facing if counter-clockwise.
+    pub position: [f32; 3],
+    pub texture_coords: [f32; 2],
+    /// Index of the input texture, -1 if the vertex is not textured.
+    pub input_id: i32,
+    /// Additional values for the shader, e.g. per-particle attributes.
+    pub data: [f32; 4],
 }
 
 #[derive(Debug)]
@@ -50,6 +99,19 @@ pub struct ImageSpec {
     pub src: ImageSrc,
     pub image_id: RendererId,
     pub image_type: ImageType,
+    /// Render image as a nine-slice, stretching only the center part to the node
+    /// resolution. Not supported for SVG images.
+    pub nine_slice: Option<NineSliceInsets>,
+}
+
+/// Size of the image borders (in pixels) that are not stretched when image is
+/// rendered as a nine-slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NineSliceInsets {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
 }
 
 #[derive(Debug)]
@@ -60,8 +122,39 @@ pub enum ImageSrc {
 
 #[derive(Debug)]
 pub enum ImageType {
-    Png,
+    /// Static PNG or APNG.
+    Png {
+        animation: AnimationOptions,
+    },
     Jpeg,
-    Svg { resolution: Option<Resolution> },
-    Gif,
+    Svg {
+        resolution: Option<Resolution>,
+    },
+    Gif {
+        animation: AnimationOptions,
+    },
+    /// Static or animated WebP.
+    Webp {
+        animation: AnimationOptions,
+    },
+}
+
+/// Playback options for animated images. Ignored if image has only one frame.
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationOptions {
+    /// How many times the animation should be played. If not specified
+    /// the animation loops indefinitely.
+    pub loop_count: Option<u32>,
+    /// If true, the last frame stays visible after the last loop ends,
+    /// otherwise node produces an empty output.
+    pub hold_last_frame: bool,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            loop_count: None,
+            hold_last_frame: true,
+        }
+    }
 }
@@ -1,11 +1,20 @@
+use std::time::Duration;
+
 pub mod builtin_transformations;
+pub mod captions;
 pub mod constraints;
 pub mod id;
+pub mod input_fallback;
 pub mod node;
+pub mod replay;
 pub mod shader;
+pub mod slate;
+pub mod test_pattern;
 pub mod text_spec;
+pub mod timecode;
 pub mod transition;
 pub mod validation;
+pub mod watermark;
 
 #[cfg(test)]
 mod validation_test;
@@ -13,7 +22,11 @@ mod validation_test;
 pub use id::InputId;
 pub use id::NodeId;
 pub use id::OutputId;
+pub use input_fallback::InputFallback;
 pub use node::NodeParams;
+pub use slate::OutputSlate;
+pub use test_pattern::TestPatternSpec;
+pub use watermark::Watermark;
 
 pub const MAX_NODE_RESOLUTION: Resolution = Resolution {
     width: 7682,
@@ -60,5 +73,9 @@ pub struct NodeSpec {
     pub node_id: NodeId,
     pub input_pads: Vec<NodeId>,
     pub fallback_id: Option<NodeId>,
+    /// Overrides `stream_fallback_timeout` for input streams used directly by this
+    /// node, e.g. a small thumbnail can keep showing a stale frame for longer than
+    /// the main view of the same input.
+    pub input_fallback_timeout: Option<Duration>,
     pub params: NodeParams,
 }
@@ -12,25 +12,53 @@ use super::NodeSpec;
 use super::Resolution;
 
 pub(crate) mod fixed_postion_layout;
+pub(crate) mod perspective;
 pub mod tiled_layout;
+pub(crate) mod transform_2d;
 
 pub use fixed_postion_layout::FixedPositionLayoutSpec;
 pub use fixed_postion_layout::HorizontalPosition;
 pub use fixed_postion_layout::TextureLayout;
 pub use fixed_postion_layout::VerticalPosition;
+pub use perspective::PerspectiveSpec;
+pub use transform_2d::Transform2dSpec;
 
-pub const TILED_LAYOUT_MAX_INPUTS_COUNT: u32 = 16;
-pub const FIXED_POSITION_LAYOUT_MAX_INPUTS_COUNT: u32 = 16;
+pub const TILED_LAYOUT_MAX_INPUTS_COUNT: u32 = 64;
+pub const FIXED_POSITION_LAYOUT_MAX_INPUTS_COUNT: u32 = 64;
+pub const PRIVACY_MASK_MAX_REGIONS_COUNT: u32 = 16;
+/// Max border width and max space reserved for the border shadow, in pixels.
+pub const BORDER_MAX_SIZE: u32 = 512;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum BuiltinSpec {
     FitToResolution(FitToResolutionSpec),
-    FillToResolution { resolution: Resolution },
-    StretchToResolution { resolution: Resolution },
+    FillToResolution {
+        resolution: Resolution,
+        scaling_quality: ScalingQuality,
+    },
+    StretchToResolution {
+        resolution: Resolution,
+        scaling_quality: ScalingQuality,
+    },
     FixedPositionLayout(FixedPositionLayoutSpec),
     TiledLayout(TiledLayoutSpec),
-    MirrorImage { mode: MirrorMode },
-    CornersRounding { border_radius: Coord },
+    MirrorImage {
+        mode: MirrorMode,
+    },
+    CornersRounding {
+        border_radius: Coord,
+    },
+    PrivacyMask(PrivacyMaskSpec),
+    Stylize {
+        effect: StylizeEffect,
+    },
+    Scope {
+        mode: ScopeMode,
+        resolution: Resolution,
+    },
+    Transform2d(Transform2dSpec),
+    Perspective(PerspectiveSpec),
+    Border(BorderSpec),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -39,6 +67,69 @@ pub struct FitToResolutionSpec {
     pub background_color_rgba: RGBAColor,
     pub horizontal_alignment: HorizontalAlign,
     pub vertical_alignment: VerticalAlign,
+    /// Sample inputs from generated mipmaps to reduce aliasing when downscaling.
+    pub mipmaps: bool,
+    pub scaling_quality: ScalingQuality,
+}
+
+/// Filter used to resample the input when it's scaled. Bicubic and Lanczos read
+/// all texels covered by an output pixel, so they don't need mipmaps to downscale
+/// without aliasing, but they are more expensive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScalingQuality {
+    Nearest,
+    /// Uses mipmaps if they are enabled.
+    #[default]
+    Bilinear,
+    Bicubic,
+    Lanczos,
+}
+
+/// Border drawn around the input, optionally with a shadow. Output is larger than
+/// the input by the border width and the space needed by the shadow on every side,
+/// so the input stays in the middle of the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorderSpec {
+    /// Width in pixels.
+    pub width: u32,
+    pub color: RGBAColor,
+    /// Outer radius of the border, the input is rounded with the radius reduced
+    /// by the border width. Percentages are relative to the smaller side of the border.
+    pub corner_radius: Coord,
+    pub shadow: Option<BorderShadow>,
+}
+
+/// Shadow of the border, with zero offset it can be used as an outer glow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorderShadow {
+    pub color: RGBAColor,
+    /// Blur radius in pixels.
+    pub blur_radius: u32,
+    /// Offset in pixels, positive values move the shadow to the right and to the bottom.
+    pub offset_x: i32,
+    pub offset_y: i32,
+}
+
+impl BorderSpec {
+    /// Space reserved for the shadow on the left and right, and on the top and
+    /// bottom of the border.
+    pub fn shadow_margins(&self) -> (u32, u32) {
+        match self.shadow {
+            Some(shadow) => (
+                shadow.blur_radius + shadow.offset_x.unsigned_abs(),
+                shadow.blur_radius + shadow.offset_y.unsigned_abs(),
+            ),
+            None => (0, 0),
+        }
+    }
+
+    pub fn output_resolution(&self, input_resolution: Resolution) -> Resolution {
+        let (margin_x, margin_y) = self.shadow_margins();
+        Resolution {
+            width: input_resolution.width + 2 * (self.width + margin_x) as usize,
+            height: input_resolution.height + 2 * (self.width + margin_y) as usize,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -48,6 +139,59 @@ pub enum MirrorMode {
     HorizontalAndVertical,
 }
 
+/// Hides content of the input inside of the specified regions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivacyMaskSpec {
+    pub effect: PrivacyMaskEffect,
+    /// Static regions for now. Tracking could provide regions updated per frame
+    /// in the future.
+    pub regions: Vec<MaskRegion>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivacyMaskEffect {
+    /// Blur radius in pixels.
+    Blur { radius: u32 },
+    /// Size of a single block in pixels.
+    Pixelate { block_size: u32 },
+}
+
+/// Rectangle positioned relative to the top-left corner of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaskRegion {
+    pub top: Coord,
+    pub left: Coord,
+    pub width: Coord,
+    pub height: Coord,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StylizeEffect {
+    /// Grayscale edges of the input (Sobel operator).
+    EdgeDetect,
+    /// Reduce number of color values per channel to `levels`.
+    Posterize { levels: u32 },
+    /// Darken edges of the image, strength in range [0, 1].
+    Vignette { strength: f32 },
+    /// Animated noise, strength in range [0, 1].
+    FilmGrain { strength: f32 },
+    /// Shift red and blue channels in opposite directions, offset in pixels
+    /// at the edges of the image.
+    ChromaticAberration { offset: u32 },
+}
+
+/// Scopes visualize color distribution of the input for exposure and color
+/// correction monitoring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeMode {
+    /// Luma distribution (vertical axis) for every column of the input.
+    LumaWaveform,
+    /// Waveforms of red, green and blue channels rendered side by side.
+    RgbParade,
+    /// Chroma (Cb/Cr) distribution plotted on a circular graph.
+    Vectorscope,
+}
+
 lazy_static! {
     static ref FIT_TO_RESOLUTION_CONSTRAINTS: NodeConstraints =
         NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
@@ -69,7 +213,7 @@ lazy_static! {
     static ref TILED_LAYOUT_CONSTRAINTS: NodeConstraints =
         NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Range {
             lower_bound: 1,
-            upper_bound: FIXED_POSITION_LAYOUT_MAX_INPUTS_COUNT,
+            upper_bound: TILED_LAYOUT_MAX_INPUTS_COUNT,
         })]);
     static ref MIRROR_IMAGE_CONSTRAINTS: NodeConstraints =
         NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
@@ -79,6 +223,30 @@ lazy_static! {
         NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
             fixed_count: 1
         })]);
+    static ref PRIVACY_MASK_CONSTRAINTS: NodeConstraints =
+        NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
+            fixed_count: 1
+        })]);
+    static ref STYLIZE_CONSTRAINTS: NodeConstraints =
+        NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
+            fixed_count: 1
+        })]);
+    static ref SCOPE_CONSTRAINTS: NodeConstraints =
+        NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
+            fixed_count: 1
+        })]);
+    static ref TRANSFORM_2D_CONSTRAINTS: NodeConstraints =
+        NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
+            fixed_count: 1
+        })]);
+    static ref PERSPECTIVE_CONSTRAINTS: NodeConstraints =
+        NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
+            fixed_count: 1
+        })]);
+    static ref BORDER_CONSTRAINTS: NodeConstraints =
+        NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
+            fixed_count: 1
+        })]);
 }
 
 impl BuiltinSpec {
@@ -88,12 +256,61 @@ impl BuiltinSpec {
             BuiltinSpec::TiledLayout { .. } => "tiled_layout",
             BuiltinSpec::MirrorImage { .. } => "mirror_image",
             BuiltinSpec::CornersRounding { .. } => "corners_rounding",
+            BuiltinSpec::PrivacyMask(_) => "privacy_mask",
+            BuiltinSpec::Stylize { .. } => "stylize",
+            BuiltinSpec::Scope { .. } => "scope",
+            BuiltinSpec::Transform2d(_) => "transform_2d",
+            BuiltinSpec::Perspective(_) => "perspective",
+            BuiltinSpec::Border(_) => "border",
             BuiltinSpec::FitToResolution(_) => "fit_to_resolution",
             BuiltinSpec::FillToResolution { .. } => "fill_to_resolution",
             BuiltinSpec::StretchToResolution { .. } => "stretch_to_resolution",
         }
     }
 
+    /// Returns true if input textures of this transformation should have mipmaps generated.
+    /// Quality of scaling the input, fixed to bilinear for transformations
+    /// that don't scale it or use a different shader.
+    pub fn scaling_quality(&self) -> ScalingQuality {
+        match self {
+            BuiltinSpec::FitToResolution(spec) => spec.scaling_quality,
+            BuiltinSpec::FillToResolution {
+                scaling_quality, ..
+            }
+            | BuiltinSpec::StretchToResolution {
+                scaling_quality, ..
+            } => *scaling_quality,
+            BuiltinSpec::FixedPositionLayout(_)
+            | BuiltinSpec::TiledLayout(_)
+            | BuiltinSpec::MirrorImage { .. }
+            | BuiltinSpec::CornersRounding { .. }
+            | BuiltinSpec::PrivacyMask(_)
+            | BuiltinSpec::Stylize { .. }
+            | BuiltinSpec::Scope { .. }
+            | BuiltinSpec::Transform2d(_)
+            | BuiltinSpec::Perspective(_)
+            | BuiltinSpec::Border(_) => ScalingQuality::Bilinear,
+        }
+    }
+
+    pub fn uses_mipmaps(&self) -> bool {
+        match self {
+            BuiltinSpec::FitToResolution(spec) => spec.mipmaps,
+            BuiltinSpec::FixedPositionLayout(spec) => spec.mipmaps,
+            BuiltinSpec::TiledLayout(spec) => spec.mipmaps,
+            BuiltinSpec::FillToResolution { .. }
+            | BuiltinSpec::StretchToResolution { .. }
+            | BuiltinSpec::MirrorImage { .. }
+            | BuiltinSpec::CornersRounding { .. }
+            | BuiltinSpec::PrivacyMask(_)
+            | BuiltinSpec::Stylize { .. }
+            | BuiltinSpec::Scope { .. }
+            | BuiltinSpec::Transform2d(_)
+            | BuiltinSpec::Perspective(_)
+            | BuiltinSpec::Border(_) => false,
+        }
+    }
+
     pub fn validate_params(&self, node_spec: &NodeSpec) -> Result<(), BuiltinSpecValidationError> {
         match self {
             BuiltinSpec::FixedPositionLayout(FixedPositionLayoutSpec {
@@ -107,9 +324,61 @@ impl BuiltinSpec {
                 }
                 Ok(())
             }
+            BuiltinSpec::PrivacyMask(PrivacyMaskSpec { effect, regions }) => {
+                if regions.len() > PRIVACY_MASK_MAX_REGIONS_COUNT as usize {
+                    return Err(BuiltinSpecValidationError::PrivacyMaskTooManyRegions {
+                        regions_count: regions.len() as u32,
+                    });
+                }
+                match effect {
+                    PrivacyMaskEffect::Blur { radius: 0 }
+                    | PrivacyMaskEffect::Pixelate { block_size: 0 } => {
+                        Err(BuiltinSpecValidationError::PrivacyMaskZeroEffectSize)
+                    }
+                    PrivacyMaskEffect::Blur { .. } | PrivacyMaskEffect::Pixelate { .. } => Ok(()),
+                }
+            }
+            BuiltinSpec::Stylize { effect } => match effect {
+                StylizeEffect::Posterize { levels } if *levels < 2 => {
+                    Err(BuiltinSpecValidationError::StylizePosterizeLevels)
+                }
+                StylizeEffect::Vignette { strength } | StylizeEffect::FilmGrain { strength }
+                    if !(0.0..=1.0).contains(strength) =>
+                {
+                    Err(BuiltinSpecValidationError::StylizeStrengthOutOfRange)
+                }
+                _ => Ok(()),
+            },
+            BuiltinSpec::Transform2d(spec) => {
+                let values = [spec.scale_x, spec.scale_y, spec.anchor.0, spec.anchor.1];
+                match values.iter().all(|value| value.is_finite()) {
+                    true => Ok(()),
+                    false => Err(BuiltinSpecValidationError::Transform2dNotFinite),
+                }
+            }
+            BuiltinSpec::Perspective(spec) => {
+                let values = [spec.scale, spec.translate_z, spec.anchor.0, spec.anchor.1];
+                if !values.iter().all(|value| value.is_finite()) {
+                    return Err(BuiltinSpecValidationError::PerspectiveNotFinite);
+                }
+                match spec.distance {
+                    Some(distance) if !(distance.is_finite() && distance > 0.0) => {
+                        Err(BuiltinSpecValidationError::PerspectiveInvalidDistance)
+                    }
+                    _ => Ok(()),
+                }
+            }
+            BuiltinSpec::Border(spec) => {
+                let (margin_x, margin_y) = spec.shadow_margins();
+                match spec.width.max(margin_x).max(margin_y) > BORDER_MAX_SIZE {
+                    true => Err(BuiltinSpecValidationError::BorderTooLarge),
+                    false => Ok(()),
+                }
+            }
             BuiltinSpec::TiledLayout { .. }
             | BuiltinSpec::MirrorImage { .. }
             | BuiltinSpec::CornersRounding { .. }
+            | BuiltinSpec::Scope { .. }
             | BuiltinSpec::FitToResolution(_)
             | BuiltinSpec::FillToResolution { .. }
             | BuiltinSpec::StretchToResolution { .. } => Ok(()),
@@ -122,6 +391,12 @@ impl BuiltinSpec {
             BuiltinSpec::TiledLayout { .. } => &TILED_LAYOUT_CONSTRAINTS,
             BuiltinSpec::MirrorImage { .. } => &MIRROR_IMAGE_CONSTRAINTS,
             BuiltinSpec::CornersRounding { .. } => &CORNERS_ROUNDING_CONSTRAINTS,
+            BuiltinSpec::PrivacyMask(_) => &PRIVACY_MASK_CONSTRAINTS,
+            BuiltinSpec::Stylize { .. } => &STYLIZE_CONSTRAINTS,
+            BuiltinSpec::Scope { .. } => &SCOPE_CONSTRAINTS,
+            BuiltinSpec::Transform2d(_) => &TRANSFORM_2D_CONSTRAINTS,
+            BuiltinSpec::Perspective(_) => &PERSPECTIVE_CONSTRAINTS,
+            BuiltinSpec::Border(_) => &BORDER_CONSTRAINTS,
             BuiltinSpec::FitToResolution(_) => &FIT_TO_RESOLUTION_CONSTRAINTS,
             BuiltinSpec::FillToResolution { .. } => &FILL_TO_RESOLUTION_CONSTRAINTS,
             BuiltinSpec::StretchToResolution { .. } => &STRETCH_TO_RESOLUTION_CONSTRAINTS,
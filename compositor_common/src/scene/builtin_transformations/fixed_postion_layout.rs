@@ -28,6 +28,8 @@ pub struct FixedPositionLayoutSpec {
     pub resolution: Resolution,
     pub texture_layouts: Vec<TextureLayout>,
     pub background_color_rgba: RGBAColor,
+    /// Sample inputs from generated mipmaps to reduce aliasing when downscaling.
+    pub mipmaps: bool,
 }
 
 impl FixedPositionLayoutSpec {
@@ -0,0 +1,55 @@
+use crate::{
+    scene::{transition::TransitionValidationError, Resolution},
+    util::{colors::RGBAColor, coord::Coord, degree::Degree},
+};
+
+/// Rotates the input in 3D around the anchor point and projects it with
+/// perspective onto the output. The camera looks at the middle of the output from
+/// `distance` pixels. Without rotation and translation the input is placed in the
+/// top-left corner of the output, the same as in the 2D transform.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerspectiveSpec {
+    /// Defaults to the resolution of the input.
+    pub resolution: Option<Resolution>,
+    /// Positive values move the top edge toward the viewer.
+    pub rotation_x: Degree,
+    /// Positive values move the right edge away from the viewer.
+    pub rotation_y: Degree,
+    /// The same direction as rotation in the 2D transform.
+    pub rotation_z: Degree,
+    /// Distance from the camera to the output plane in pixels, defaults to the
+    /// output width. Smaller values exaggerate perspective.
+    pub distance: Option<f32>,
+    pub scale: f32,
+    /// Offset of the anchor point, percentages are relative to the output resolution.
+    pub translate_x: Coord,
+    pub translate_y: Coord,
+    /// Offset of the anchor point toward the viewer in pixels.
+    pub translate_z: f32,
+    /// Position of the anchor point relative to the input size, (0, 0) is the
+    /// top-left corner and (1, 1) is the bottom-right corner of the input.
+    pub anchor: (f32, f32),
+    pub background_color_rgba: RGBAColor,
+}
+
+impl PerspectiveSpec {
+    pub(crate) fn validate_transition(
+        start: &Self,
+        end: &Self,
+    ) -> Result<(), TransitionValidationError> {
+        let transformation = "perspective";
+        if start.resolution != end.resolution {
+            return Err(TransitionValidationError::UnsupportedFieldInterpolation(
+                "resolution",
+                transformation,
+            ));
+        }
+        if start.background_color_rgba != end.background_color_rgba {
+            return Err(TransitionValidationError::UnsupportedFieldInterpolation(
+                "background_color_rgba",
+                transformation,
+            ));
+        }
+        Ok(())
+    }
+}
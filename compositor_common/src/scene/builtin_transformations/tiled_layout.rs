@@ -13,4 +13,6 @@ pub struct TiledLayoutSpec {
     pub padding: u32,
     pub horizontal_alignment: HorizontalAlign,
     pub vertical_alignment: VerticalAlign,
+    /// Sample inputs from generated mipmaps to reduce aliasing when downscaling.
+    pub mipmaps: bool,
 }
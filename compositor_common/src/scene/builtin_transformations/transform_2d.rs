@@ -0,0 +1,46 @@
+use crate::{
+    scene::{transition::TransitionValidationError, Resolution},
+    util::{colors::RGBAColor, coord::Coord, degree::Degree},
+};
+
+/// Rotates, scales and moves the input around the anchor point. Without
+/// translation the input is placed in the top-left corner of the output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transform2dSpec {
+    /// Defaults to the resolution of the input.
+    pub resolution: Option<Resolution>,
+    /// Rotation around the anchor point, in the same direction as rotation of
+    /// textures in the fixed position layout.
+    pub rotation: Degree,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    /// Offset of the anchor point, percentages are relative to the output resolution.
+    pub translate_x: Coord,
+    pub translate_y: Coord,
+    /// Position of the anchor point relative to the input size, (0, 0) is the
+    /// top-left corner and (1, 1) is the bottom-right corner of the input.
+    pub anchor: (f32, f32),
+    pub background_color_rgba: RGBAColor,
+}
+
+impl Transform2dSpec {
+    pub(crate) fn validate_transition(
+        start: &Self,
+        end: &Self,
+    ) -> Result<(), TransitionValidationError> {
+        let transformation = "transform_2d";
+        if start.resolution != end.resolution {
+            return Err(TransitionValidationError::UnsupportedFieldInterpolation(
+                "resolution",
+                transformation,
+            ));
+        }
+        if start.background_color_rgba != end.background_color_rgba {
+            return Err(TransitionValidationError::UnsupportedFieldInterpolation(
+                "background_color_rgba",
+                transformation,
+            ));
+        }
+        Ok(())
+    }
+}
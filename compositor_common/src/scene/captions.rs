@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use crate::util::{align::HorizontalAlign, colors::RGBAColor};
+
+use super::{text_spec::Weight, Resolution};
+
+/// Timed text from a subtitle file or a live caption feed, rendered with the text
+/// renderer on a transparent background, so it can be placed over the video.
+#[derive(Debug, Clone)]
+pub struct CaptionsSpec {
+    pub source: CaptionsSource,
+    pub resolution: Resolution,
+    pub font_size: f32,
+    pub line_height: Option<f32>,
+    pub font_family: String,
+    pub weight: Weight,
+    pub color_rgba: RGBAColor,
+    pub align: HorizontalAlign,
+    pub position: CaptionsPosition,
+    /// Distance (in pixels) between the text and the edges of the node, so captions
+    /// stay in the safe area of displays that crop the picture.
+    pub safe_area_margin: u32,
+    /// Added to cue timestamps before they are compared with the output PTS.
+    pub offset: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub enum CaptionsSource {
+    /// SRT or WebVTT file, the format is detected from the content.
+    File { path: String },
+    /// WebVTT document downloaded again every `refresh_interval`, so a live
+    /// captioning service can keep appending cues.
+    Url {
+        url: String,
+        refresh_interval: Duration,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptionsPosition {
+    Top,
+    Bottom,
+}
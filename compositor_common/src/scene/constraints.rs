@@ -10,7 +10,7 @@ use super::NodeSpec;
 pub mod input_count;
 
 // TODO validate constraints aren't self-contradictory
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NodeConstraints(pub Vec<Constraint>);
 
 impl NodeConstraints {
@@ -0,0 +1,17 @@
+use std::time::Duration;
+
+use crate::{renderer_spec::RendererId, util::colors::RGBAColor};
+
+/// Content shown in place of an input when its frames are missing for longer than
+/// `stream_fallback_timeout`. Without it, the input node is empty and nodes that
+/// use it render their `fallback_id` node instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputFallback {
+    /// Solid color at the resolution of the last received frame.
+    Color(RGBAColor),
+    /// Registered image rendered at its original resolution.
+    Image(RendererId),
+    /// Keep showing the last received frame for `duration` before the input is
+    /// considered missing.
+    FreezeLastFrame { duration: Duration },
+}
@@ -2,9 +2,12 @@ use crate::{error::NodeSpecValidationError, renderer_spec::RendererId};
 
 use super::{
     builtin_transformations::BuiltinSpec,
+    captions::CaptionsSpec,
     constraints::{input_count::InputCountConstraint, Constraint, NodeConstraints},
-    shader::ShaderParam,
+    replay::ReplaySpec,
+    shader::{SamplerSpec, ShaderGeometry, ShaderParam},
     text_spec::TextSpec,
+    timecode::TimecodeSpec,
     transition::TransitionSpec,
     NodeSpec, Resolution,
 };
@@ -17,11 +20,23 @@ pub enum NodeParams {
     Shader {
         shader_id: RendererId,
         shader_params: Option<ShaderParam>,
+        /// Params written to the shader's read-only storage buffer.
+        storage_params: Option<ShaderParam>,
+        /// Registered images bound to the shader's images binding array, in order.
+        image_ids: Vec<RendererId>,
         resolution: Resolution,
+        sampler: SamplerSpec,
+        geometry: ShaderGeometry,
     },
     Text(TextSpec),
+    Captions(CaptionsSpec),
+    Timecode(TimecodeSpec),
+    Replay(ReplaySpec),
     Image {
         image_id: RendererId,
+        /// Resolution used to rasterize SVG images and as the output size of nine-slice
+        /// images. Ignored for other image types.
+        resolution: Option<Resolution>,
     },
     Builtin(BuiltinSpec),
     Transition(TransitionSpec),
@@ -31,6 +46,13 @@ impl NodeSpec {
     pub fn validate_params(&self) -> Result<(), NodeSpecValidationError> {
         match &self.params {
             NodeParams::Builtin(transformation) => Ok(transformation.validate_params(self)?),
+            NodeParams::Shader {
+                sampler, geometry, ..
+            } => {
+                sampler.validate()?;
+                geometry.validate()?;
+                Ok(())
+            }
             NodeParams::Transition(TransitionSpec { start, end, .. }) => {
                 start.validate_params(self)?;
                 end.validate_params(self)?;
@@ -50,6 +72,18 @@ lazy_static! {
         NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
             fixed_count: 0
         })]);
+    static ref CAPTIONS_CONSTRAINTS: NodeConstraints =
+        NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
+            fixed_count: 0
+        })]);
+    static ref TIMECODE_CONSTRAINTS: NodeConstraints =
+        NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
+            fixed_count: 0
+        })]);
+    static ref REPLAY_CONSTRAINTS: NodeConstraints =
+        NodeConstraints(vec![Constraint::InputCount(InputCountConstraint::Exact {
+            fixed_count: 0
+        })]);
 }
 
 impl NodeParams {
@@ -60,4 +94,16 @@ impl NodeParams {
     pub fn image_constraints() -> &'static NodeConstraints {
         &IMAGE_CONSTRAINTS
     }
+
+    pub fn captions_constraints() -> &'static NodeConstraints {
+        &CAPTIONS_CONSTRAINTS
+    }
+
+    pub fn timecode_constraints() -> &'static NodeConstraints {
+        &TIMECODE_CONSTRAINTS
+    }
+
+    pub fn replay_constraints() -> &'static NodeConstraints {
+        &REPLAY_CONSTRAINTS
+    }
 }
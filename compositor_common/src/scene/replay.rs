@@ -0,0 +1,9 @@
+use super::InputId;
+
+/// Plays back frames recorded in the replay buffer of the input, when a replay
+/// of that input is started. Node is empty when no replay is playing, so its
+/// fallback is shown instead.
+#[derive(Debug, Clone)]
+pub struct ReplaySpec {
+    pub input_id: InputId,
+}
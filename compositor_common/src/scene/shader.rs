@@ -1,3 +1,13 @@
+use std::{collections::HashMap, fmt::Display, time::Duration};
+
+use crate::{
+    error::{SamplerSpecValidationError, ShaderGeometryValidationError},
+    renderer_spec::RendererId,
+    util::{ContinuousValue, InterpolationState},
+};
+
+use super::transition::Interpolation;
+
 #[derive(Debug, Clone)]
 pub enum ShaderParam {
     F32(f32),
@@ -5,6 +15,24 @@ pub enum ShaderParam {
     I32(i32),
     List(Vec<ShaderParam>),
     Struct(Vec<ShaderParamStructField>),
+    /// f32 value animated over time. Keyframes are sorted by time and the list
+    /// is never empty.
+    Keyframes(Vec<ShaderParamKeyframe>),
+    /// Struct fields keyed by name, in any order. Replaced with [`ShaderParam::Struct`]
+    /// in the layout of the shader's uniform type when the node is created, fields
+    /// that are not specified are filled with zeros.
+    Object(HashMap<String, ShaderParamValue>),
+}
+
+/// Value of a [`ShaderParam::Object`] field. Numbers and lists get the type of the
+/// matching field in the shader.
+#[derive(Debug, Clone)]
+pub enum ShaderParamValue {
+    Number(f64),
+    List(Vec<ShaderParamValue>),
+    Object(HashMap<String, ShaderParamValue>),
+    /// Value with an explicit type, e.g. keyframes.
+    Typed(ShaderParam),
 }
 
 #[derive(Debug, Clone)]
@@ -13,6 +41,131 @@ pub struct ShaderParamStructField {
     pub value: ShaderParam,
 }
 
+#[derive(Debug, Clone)]
+pub struct ShaderParamKeyframe {
+    /// Time relative to the first frame rendered by the node.
+    pub time: Duration,
+    pub value: f32,
+    /// Interpolation used between this keyframe and the next one.
+    pub interpolation: Interpolation,
+}
+
+/// Structure of params expected by a shader, derived from the type of its
+/// user-defined uniform buffer. Describes which [`ShaderParam`] values pass the
+/// validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShaderParamType {
+    /// Accepts [`ShaderParam::F32`] and [`ShaderParam::Keyframes`].
+    F32,
+    U32,
+    I32,
+    /// List with exactly `size` scalar elements.
+    Vector {
+        element: Box<ShaderParamType>,
+        size: usize,
+    },
+    /// List of `rows` vectors with `columns` f32 elements each.
+    Matrix {
+        columns: usize,
+        rows: usize,
+    },
+    /// List with at most `max_length` elements. Runtime-sized arrays, declared
+    /// in storage buffers, have no max length.
+    Array {
+        element: Box<ShaderParamType>,
+        max_length: Option<usize>,
+    },
+    /// Struct with exactly these fields, in this order.
+    Struct(Vec<ShaderParamStructFieldType>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderParamStructFieldType {
+    pub field_name: String,
+    pub field_type: ShaderParamType,
+}
+
+/// Sampler used by the shader to read input textures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerSpec {
+    pub filter: SamplerFilter,
+    pub address_mode: SamplerAddressMode,
+    /// Max anisotropy, supported values are 1, 2, 4, 8 and 16. Values other than 1
+    /// require linear filtering.
+    pub anisotropy: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SamplerFilter {
+    Linear,
+    Nearest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SamplerAddressMode {
+    ClampToEdge,
+    Repeat,
+    MirrorRepeat,
+}
+
+impl Default for SamplerSpec {
+    fn default() -> Self {
+        Self {
+            filter: SamplerFilter::Linear,
+            address_mode: SamplerAddressMode::ClampToEdge,
+            anisotropy: 1,
+        }
+    }
+}
+
+impl SamplerSpec {
+    pub fn validate(&self) -> Result<(), SamplerSpecValidationError> {
+        if ![1, 2, 4, 8, 16].contains(&self.anisotropy) {
+            return Err(SamplerSpecValidationError::InvalidAnisotropy(
+                self.anisotropy,
+            ));
+        }
+        if self.anisotropy > 1 && self.filter != SamplerFilter::Linear {
+            return Err(SamplerSpecValidationError::AnisotropyRequiresLinearFilter);
+        }
+        Ok(())
+    }
+}
+
+/// Max number of vertices drawn by a shader node with procedural geometry.
+pub const MAX_PROCEDURAL_VERTEX_COUNT: u32 = 1 << 18;
+
+/// Geometry drawn by a shader node.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ShaderGeometry {
+    /// Plane covering the whole output for every input, or a single plane with
+    /// input id -1 if the node has no inputs.
+    #[default]
+    InputPlanes,
+    /// Triangles of a registered mesh.
+    Mesh(RendererId),
+    /// Triangle list of `vertex_count` vertices with all attributes set to zero.
+    /// Positions are expected to be computed from `@builtin(vertex_index)`.
+    Procedural { vertex_count: u32 },
+}
+
+impl ShaderGeometry {
+    pub fn validate(&self) -> Result<(), ShaderGeometryValidationError> {
+        let ShaderGeometry::Procedural { vertex_count } = *self else {
+            return Ok(());
+        };
+        if vertex_count == 0 || vertex_count % 3 != 0 {
+            return Err(ShaderGeometryValidationError::IncompleteTriangles(
+                vertex_count,
+            ));
+        }
+        if vertex_count > MAX_PROCEDURAL_VERTEX_COUNT {
+            return Err(ShaderGeometryValidationError::TooManyVertices(vertex_count));
+        }
+        Ok(())
+    }
+}
+
 impl From<(&'static str, ShaderParam)> for ShaderParamStructField {
     fn from(value: (&'static str, ShaderParam)) -> Self {
         Self {
@@ -21,3 +174,166 @@ impl From<(&'static str, ShaderParam)> for ShaderParamStructField {
         }
     }
 }
+
+impl ShaderParam {
+    pub fn has_keyframes(&self) -> bool {
+        match self {
+            ShaderParam::F32(_) | ShaderParam::U32(_) | ShaderParam::I32(_) => false,
+            ShaderParam::List(list) => list.iter().any(ShaderParam::has_keyframes),
+            ShaderParam::Struct(fields) => fields.iter().any(|field| field.value.has_keyframes()),
+            ShaderParam::Keyframes(_) => true,
+            ShaderParam::Object(fields) => fields.values().any(ShaderParamValue::has_keyframes),
+        }
+    }
+
+    /// Params contain [`ShaderParam::Object`] values that have to be resolved
+    /// against the shader's uniform type.
+    pub fn has_object_fields(&self) -> bool {
+        match self {
+            ShaderParam::F32(_)
+            | ShaderParam::U32(_)
+            | ShaderParam::I32(_)
+            | ShaderParam::Keyframes(_) => false,
+            ShaderParam::List(list) => list.iter().any(ShaderParam::has_object_fields),
+            ShaderParam::Struct(fields) => {
+                fields.iter().any(|field| field.value.has_object_fields())
+            }
+            ShaderParam::Object(_) => true,
+        }
+    }
+
+    /// Returns params with all keyframes replaced by values at the specified time.
+    pub fn value_at(&self, time: Duration) -> ShaderParam {
+        match self {
+            ShaderParam::F32(_) | ShaderParam::U32(_) | ShaderParam::I32(_) => self.clone(),
+            ShaderParam::List(list) => {
+                ShaderParam::List(list.iter().map(|param| param.value_at(time)).collect())
+            }
+            ShaderParam::Struct(fields) => ShaderParam::Struct(
+                fields
+                    .iter()
+                    .map(|field| ShaderParamStructField {
+                        field_name: field.field_name.clone(),
+                        value: field.value.value_at(time),
+                    })
+                    .collect(),
+            ),
+            ShaderParam::Keyframes(keyframes) => {
+                ShaderParam::F32(keyframes_value_at(keyframes, time))
+            }
+            ShaderParam::Object(fields) => ShaderParam::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.value_at(time)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl ShaderParamValue {
+    fn has_keyframes(&self) -> bool {
+        match self {
+            ShaderParamValue::Number(_) => false,
+            ShaderParamValue::List(list) => list.iter().any(ShaderParamValue::has_keyframes),
+            ShaderParamValue::Object(fields) => {
+                fields.values().any(ShaderParamValue::has_keyframes)
+            }
+            ShaderParamValue::Typed(param) => param.has_keyframes(),
+        }
+    }
+
+    fn value_at(&self, time: Duration) -> ShaderParamValue {
+        match self {
+            ShaderParamValue::Number(_) => self.clone(),
+            ShaderParamValue::List(list) => {
+                ShaderParamValue::List(list.iter().map(|value| value.value_at(time)).collect())
+            }
+            ShaderParamValue::Object(fields) => ShaderParamValue::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.value_at(time)))
+                    .collect(),
+            ),
+            ShaderParamValue::Typed(param) => ShaderParamValue::Typed(param.value_at(time)),
+        }
+    }
+}
+
+impl Display for ShaderParamType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderParamType::F32 => write!(f, "f32"),
+            ShaderParamType::U32 => write!(f, "u32"),
+            ShaderParamType::I32 => write!(f, "i32"),
+            ShaderParamType::Vector { element, size } => write!(f, "vec{size}<{element}>"),
+            ShaderParamType::Matrix { columns, rows } => write!(f, "mat{columns}x{rows}<f32>"),
+            ShaderParamType::Array {
+                element,
+                max_length: Some(max_length),
+            } => write!(f, "array<{element}, {max_length}>"),
+            ShaderParamType::Array {
+                element,
+                max_length: None,
+            } => write!(f, "array<{element}>"),
+            ShaderParamType::Struct(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|field| format!("{}: {}", field.field_name, field.field_type))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "struct {{ {fields} }}")
+            }
+        }
+    }
+}
+
+impl ShaderParamType {
+    /// Params of this type with all values set to zero.
+    pub fn zeroed(&self) -> ShaderParam {
+        match self {
+            ShaderParamType::F32 => ShaderParam::F32(0.0),
+            ShaderParamType::U32 => ShaderParam::U32(0),
+            ShaderParamType::I32 => ShaderParam::I32(0),
+            ShaderParamType::Vector { element, size } => {
+                ShaderParam::List(vec![element.zeroed(); *size])
+            }
+            ShaderParamType::Matrix { columns, rows } => {
+                ShaderParam::List(vec![
+                    ShaderParam::List(vec![ShaderParam::F32(0.0); *columns]);
+                    *rows
+                ])
+            }
+            ShaderParamType::Array {
+                element,
+                max_length,
+            } => ShaderParam::List(vec![element.zeroed(); max_length.unwrap_or(0)]),
+            ShaderParamType::Struct(fields) => ShaderParam::Struct(
+                fields
+                    .iter()
+                    .map(|field| ShaderParamStructField {
+                        field_name: field.field_name.clone(),
+                        value: field.field_type.zeroed(),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn keyframes_value_at(keyframes: &[ShaderParamKeyframe], time: Duration) -> f32 {
+    let next_index = keyframes.partition_point(|keyframe| keyframe.time <= time);
+    match (
+        next_index.checked_sub(1).and_then(|i| keyframes.get(i)),
+        keyframes.get(next_index),
+    ) {
+        (Some(start), Some(end)) => {
+            let state = (time - start.time).as_secs_f64() / (end.time - start.time).as_secs_f64();
+            let state = start.interpolation.interpolate(InterpolationState(state));
+            f32::interpolate(&start.value, &end.value, state)
+        }
+        (Some(last), None) => last.value,
+        (None, Some(first)) => first.value,
+        (None, None) => 0.0,
+    }
+}
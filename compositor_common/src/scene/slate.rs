@@ -0,0 +1,25 @@
+use crate::{renderer_spec::RendererId, util::colors::RGBColor};
+
+/// Content that replaces the rendered scene on an output. Frames are still
+/// produced for every tick, so the output keeps streaming while it is shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputSlate {
+    pub content: SlateContent,
+    pub mode: SlateMode,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlateContent {
+    Color(RGBColor),
+    /// Registered image scaled to fit the output, remaining area is black.
+    Image(RendererId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlateMode {
+    /// Slate is always shown.
+    On,
+    /// Slate is shown only while none of the inputs delivers frames, i.e. all of
+    /// them are missing for longer than `stream_fallback_timeout`.
+    WhenInputsMissing,
+}
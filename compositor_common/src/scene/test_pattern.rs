@@ -0,0 +1,24 @@
+use crate::{util::colors::RGBColor, Framerate};
+
+use super::Resolution;
+
+/// Input that is not backed by a stream, its frames are generated by the renderer.
+/// Useful to test pipelines without external sources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestPatternSpec {
+    pub pattern: TestPattern,
+    pub resolution: Resolution,
+    /// Rate at which the timecode is updated, independent of the output framerate.
+    pub framerate: Framerate,
+    /// Draw `HH:MM:SS:FF` timecode of the frame in the middle of the pattern.
+    pub timecode: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestPattern {
+    /// SMPTE color bars (ECR 1-1978).
+    SmpteBars,
+    /// Black and white squares, 1/16 of the frame height each.
+    Checkerboard,
+    Color(RGBColor),
+}
@@ -0,0 +1,24 @@
+use crate::util::{align::HorizontalAlign, colors::RGBAColor};
+
+use super::Resolution;
+
+/// Time of the rendered frame drawn with the text renderer, e.g. to compare
+/// timestamps of the same frame at different points of the distribution chain.
+#[derive(Debug, Clone)]
+pub struct TimecodeSpec {
+    pub source: TimecodeSource,
+    pub resolution: Resolution,
+    pub font_size: f32,
+    pub font_family: String,
+    pub color_rgba: RGBAColor,
+    pub background_color_rgba: RGBAColor,
+    pub align: HorizontalAlign,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimecodeSource {
+    /// PTS of the output frame, formatted as `HH:MM:SS.mmm`.
+    Pts,
+    /// UTC time when the frame is rendered, formatted as `HH:MM:SS.mmm`.
+    WallClock,
+}
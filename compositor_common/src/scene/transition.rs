@@ -2,7 +2,9 @@ use std::{f64::consts::PI, time::Duration};
 
 use crate::util::InterpolationState;
 
-use super::builtin_transformations::{BuiltinSpec, FixedPositionLayoutSpec};
+use super::builtin_transformations::{
+    BuiltinSpec, FixedPositionLayoutSpec, PerspectiveSpec, Transform2dSpec,
+};
 
 #[derive(Debug, Clone)]
 pub struct TransitionSpec {
@@ -42,6 +44,8 @@ impl Interpolation {
 #[derive(Debug)]
 pub enum TransitionType {
     FixedPositionLayout(FixedPositionLayoutSpec, FixedPositionLayoutSpec),
+    Transform2d(Transform2dSpec, Transform2dSpec),
+    Perspective(PerspectiveSpec, PerspectiveSpec),
 }
 
 impl TransitionType {
@@ -50,6 +54,12 @@ impl TransitionType {
             TransitionType::FixedPositionLayout(start, end) => {
                 FixedPositionLayoutSpec::validate_transition(start, end)
             }
+            TransitionType::Transform2d(start, end) => {
+                Transform2dSpec::validate_transition(start, end)
+            }
+            TransitionType::Perspective(start, end) => {
+                PerspectiveSpec::validate_transition(start, end)
+            }
         }
     }
 }
@@ -62,6 +72,12 @@ impl TryFrom<(&BuiltinSpec, &BuiltinSpec)> for TransitionType {
             (BuiltinSpec::FixedPositionLayout(s1), BuiltinSpec::FixedPositionLayout(s2)) => {
                 Self::FixedPositionLayout(s1.clone(), s2.clone())
             }
+            (BuiltinSpec::Transform2d(s1), BuiltinSpec::Transform2d(s2)) => {
+                Self::Transform2d(s1.clone(), s2.clone())
+            }
+            (BuiltinSpec::Perspective(s1), BuiltinSpec::Perspective(s2)) => {
+                Self::Perspective(s1.clone(), s2.clone())
+            }
             (start, end) => {
                 return Err(TransitionValidationError::IncompatibleStartAndEnd(
                     start.transformation_name(),
@@ -3,7 +3,12 @@ use std::{collections::HashSet, sync::Arc};
 use crate::{
     error::{SceneSpecValidationError, UnusedNodesError},
     renderer_spec::RendererId,
-    scene::{id::NodeId, id::OutputId, NodeParams, NodeSpec, OutputSpec, Resolution, SceneSpec},
+    scene::{
+        id::NodeId,
+        id::OutputId,
+        shader::{SamplerSpec, ShaderGeometry},
+        NodeParams, NodeSpec, OutputSpec, Resolution, SceneSpec,
+    },
 };
 
 #[test]
@@ -15,7 +20,11 @@ fn scene_validation_finds_cycle() {
     let trans_params = NodeParams::Shader {
         shader_id: RendererId(Arc::from("shader")),
         shader_params: None,
+        storage_params: None,
+        image_ids: Vec::new(),
         resolution,
+        sampler: SamplerSpec::default(),
+        geometry: ShaderGeometry::default(),
     };
 
     let input_id = NodeId(Arc::from("input"));
@@ -29,6 +38,7 @@ fn scene_validation_finds_cycle() {
         input_pads: vec![input_id.clone(), c_id.clone()],
         params: trans_params.clone(),
         fallback_id: None,
+        input_fallback_timeout: None,
     };
 
     let b = NodeSpec {
@@ -36,6 +46,7 @@ fn scene_validation_finds_cycle() {
         input_pads: vec![a_id],
         params: trans_params.clone(),
         fallback_id: None,
+        input_fallback_timeout: None,
     };
 
     let c = NodeSpec {
@@ -43,6 +54,7 @@ fn scene_validation_finds_cycle() {
         input_pads: vec![b_id],
         params: trans_params,
         fallback_id: None,
+        input_fallback_timeout: None,
     };
 
     let output = OutputSpec {
@@ -74,7 +86,11 @@ fn scene_validation_finds_unused_nodes() {
     let trans_params = NodeParams::Shader {
         shader_id: RendererId(Arc::from("shader")),
         shader_params: None,
+        storage_params: None,
+        image_ids: Vec::new(),
         resolution,
+        sampler: SamplerSpec::default(),
+        geometry: ShaderGeometry::default(),
     };
 
     let input_id = NodeId(Arc::from("input"));
@@ -90,6 +106,7 @@ fn scene_validation_finds_unused_nodes() {
         input_pads: vec![input_id.clone()],
         params: trans_params.clone(),
         fallback_id: Some(d_id.clone()),
+        input_fallback_timeout: None,
     };
 
     let b = NodeSpec {
@@ -97,6 +114,7 @@ fn scene_validation_finds_unused_nodes() {
         input_pads: vec![c_id.clone()],
         params: trans_params.clone(),
         fallback_id: None,
+        input_fallback_timeout: None,
     };
 
     let c = NodeSpec {
@@ -104,6 +122,7 @@ fn scene_validation_finds_unused_nodes() {
         input_pads: vec![b_id.clone()],
         params: trans_params.clone(),
         fallback_id: None,
+        input_fallback_timeout: None,
     };
 
     let d = NodeSpec {
@@ -111,6 +130,7 @@ fn scene_validation_finds_unused_nodes() {
         input_pads: vec![],
         params: trans_params,
         fallback_id: None,
+        input_fallback_timeout: None,
     };
 
     let output = OutputSpec {
@@ -0,0 +1,24 @@
+use crate::renderer_spec::RendererId;
+
+/// Image composited onto every frame of an output, independently of the scene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    pub image_id: RendererId,
+    pub anchor: WatermarkAnchor,
+    /// Distance in pixels between the image and the horizontal edge of the
+    /// output it is anchored to.
+    pub offset_x: u32,
+    /// Distance in pixels between the image and the vertical edge of the output
+    /// it is anchored to.
+    pub offset_y: u32,
+    /// Value between 0.0 (transparent) and 1.0 (opaque).
+    pub opacity: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatermarkAnchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
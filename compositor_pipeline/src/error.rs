@@ -1,13 +1,25 @@
-use compositor_common::scene::{InputId, OutputId};
+use compositor_common::{
+    error::NodeSpecValidationError,
+    scene::{InputId, NodeId, OutputId},
+    SceneSpecValidationError,
+};
 use compositor_render::error::{
-    InitRendererEngineError, RegisterError, RegisterRendererError, UnregisterRendererError,
-    UpdateSceneError, WgpuError,
+    CreateNodeError, FreezeFrameError, InitRendererEngineError, PreloadImageError, RegisterError,
+    RegisterRendererError, RegisterWatermarkError, RendererInfoError, ReplayBufferError,
+    SetOutputSlateError, SnapshotError, UnregisterRendererError, UpdateSceneError,
+    UpdateShaderError, WebRendererEventError, WgpuError,
 };
 
 #[derive(Debug, thiserror::Error)]
 pub enum RegisterInputError {
     #[error("Failed to register input stream. Stream \"{0}\" is already registered.")]
     AlreadyRegistered(InputId),
+
+    #[error("Decoder error while registering input stream for stream \"{0}\".")]
+    DecoderError(InputId, #[source] InputInitError),
+
+    #[error("Failed to start recording of input stream \"{0}\".")]
+    RecordingError(InputId, #[source] RecordingError),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -20,6 +32,27 @@ pub enum RegisterOutputError {
 
     #[error("Failed to register output stream \"{0}\". Resolution in each dimension has to be divisible by 2.")]
     UnsupportedResolution(OutputId),
+
+    #[error("Failed to register watermark of output stream \"{0}\".")]
+    Watermark(OutputId, #[source] RegisterWatermarkError),
+
+    #[error("Failed to register output stream \"{0}\". Captions source input stream \"{1}\" does not exist.")]
+    CaptionsInputNotFound(OutputId, InputId),
+
+    #[error("Failed to register key output stream \"{0}\". Fill output stream \"{1}\" does not exist or is a key output itself.")]
+    KeyFillNotFound(OutputId, OutputId),
+
+    #[error("Failed to register key output stream \"{0}\". Resolution has to be the same as resolution of the fill output stream \"{1}\".")]
+    KeyResolutionMismatch(OutputId, OutputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateOutputError {
+    #[error("Failed to update output stream. Stream \"{0}\" does not exist.")]
+    NotFound(OutputId),
+
+    #[error("Failed to reopen the encoder of output stream \"{0}\" with new settings.")]
+    EncoderError(OutputId, #[source] OutputInitError),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -42,6 +75,99 @@ pub enum UnregisterOutputError {
         "Failed to unregister output stream. Stream \"{0}\" is still used in the current scene."
     )]
     StillInUse(OutputId),
+
+    #[error("Failed to unregister output stream. Stream \"{0}\" is the fill of key output stream \"{1}\", unregister the key first.")]
+    KeyStillRegistered(OutputId, OutputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StartOutputError {
+    #[error("Failed to start output stream. Stream \"{0}\" does not exist.")]
+    NotFound(OutputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StopOutputError {
+    #[error("Failed to stop output stream. Stream \"{0}\" does not exist.")]
+    NotFound(OutputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateOutputSlateError {
+    #[error("Failed to update slate of output stream. Stream \"{0}\" does not exist.")]
+    NotFound(OutputId),
+
+    #[error("Failed to update slate of output stream \"{0}\".")]
+    Slate(OutputId, #[source] SetOutputSlateError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RequestKeyframeError {
+    #[error("Failed to request keyframe. Output stream \"{0}\" does not exist.")]
+    NotFound(OutputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SendCaptionsError {
+    #[error("Failed to send captions. Output stream \"{0}\" does not exist.")]
+    NotFound(OutputId),
+
+    #[error("Failed to send captions. Output stream \"{0}\" was not registered with captions sent over the API.")]
+    NotEnabled(OutputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InsertScte35Error {
+    #[error("Failed to insert SCTE-35 marker. Output stream \"{0}\" does not exist.")]
+    NotFound(OutputId),
+
+    #[error("Failed to insert SCTE-35 marker. Output stream \"{0}\" was not registered with SCTE-35 enabled.")]
+    NotEnabled(OutputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("Input stream \"{0}\" does not exist.")]
+    InputNotFound(InputId),
+
+    #[error(transparent)]
+    Buffer(#[from] ReplayBufferError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TimelineError {
+    #[error("Timeline has to contain at least one entry.")]
+    NoEntries,
+
+    #[error("Duration of timeline entry {0} has to be greater than zero.")]
+    ZeroDuration(usize),
+
+    #[error("Scene of timeline entry {0} is invalid.")]
+    InvalidScene(usize, #[source] UpdateSceneError),
+
+    #[error("Timeline is not running.")]
+    NotRunning,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InputInitError {
+    #[error(transparent)]
+    FfmpegError(#[from] ffmpeg_next::Error),
+
+    #[error(transparent)]
+    InputError(#[from] Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecordingError {
+    #[error("Passthrough recording is not supported for uncompressed inputs.")]
+    PassthroughNotSupported,
+
+    #[error(transparent)]
+    FfmpegError(#[from] ffmpeg_next::Error),
+
+    #[error(transparent)]
+    EncoderError(#[from] OutputInitError),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -65,6 +191,12 @@ pub enum ErrorType {
 pub struct PipelineErrorInfo {
     pub error_code: &'static str,
     pub error_type: ErrorType,
+    /// Ids of inputs, outputs, renderers or nodes the error refers to.
+    pub entity_ids: Vec<String>,
+    /// Node of the scene that failed validation.
+    pub node_id: Option<String>,
+    /// Field of the node (e.g. "input_pads") that failed validation, if known.
+    pub field: Option<&'static str>,
 }
 
 impl PipelineErrorInfo {
@@ -72,17 +204,44 @@ impl PipelineErrorInfo {
         Self {
             error_code,
             error_type,
+            entity_ids: vec![],
+            node_id: None,
+            field: None,
         }
     }
+
+    fn with_entity(mut self, entity_id: &impl ToString) -> Self {
+        self.entity_ids.push(entity_id.to_string());
+        self
+    }
+
+    fn with_node(mut self, node_id: &NodeId, field: Option<&'static str>) -> Self {
+        self.node_id = Some(node_id.to_string());
+        self.field = field;
+        self
+    }
 }
 
 const INPUT_STREAM_ALREADY_REGISTERED: &str = "INPUT_STREAM_ALREADY_REGISTERED";
 
+const DECODER_ERROR: &str = "DECODER_ERROR";
+const INPUT_RECORDING_ERROR: &str = "INPUT_RECORDING_ERROR";
+
 impl From<&RegisterInputError> for PipelineErrorInfo {
     fn from(err: &RegisterInputError) -> Self {
         match err {
-            RegisterInputError::AlreadyRegistered(_) => {
+            RegisterInputError::AlreadyRegistered(input_id) => {
                 PipelineErrorInfo::new(INPUT_STREAM_ALREADY_REGISTERED, ErrorType::UserError)
+                    .with_entity(input_id)
+            }
+
+            RegisterInputError::DecoderError(input_id, _) => {
+                PipelineErrorInfo::new(DECODER_ERROR, ErrorType::ServerError).with_entity(input_id)
+            }
+
+            RegisterInputError::RecordingError(input_id, _) => {
+                PipelineErrorInfo::new(INPUT_RECORDING_ERROR, ErrorType::UserError)
+                    .with_entity(input_id)
             }
         }
     }
@@ -91,19 +250,44 @@ impl From<&RegisterInputError> for PipelineErrorInfo {
 const OUTPUT_STREAM_ALREADY_REGISTERED: &str = "OUTPUT_STREAM_ALREADY_REGISTERED";
 const ENCODER_ERROR: &str = "ENCODER_ERROR";
 const UNSUPPORTED_RESOLUTION: &str = "UNSUPPORTED_RESOLUTION";
+const REGISTER_WATERMARK_ERROR: &str = "REGISTER_WATERMARK_ERROR";
+const CAPTIONS_INPUT_NOT_FOUND: &str = "CAPTIONS_INPUT_NOT_FOUND";
+const KEY_FILL_OUTPUT_NOT_FOUND: &str = "KEY_FILL_OUTPUT_NOT_FOUND";
+const KEY_RESOLUTION_MISMATCH: &str = "KEY_RESOLUTION_MISMATCH";
 
 impl From<&RegisterOutputError> for PipelineErrorInfo {
     fn from(err: &RegisterOutputError) -> Self {
         match err {
-            RegisterOutputError::AlreadyRegistered(_) => {
+            RegisterOutputError::AlreadyRegistered(output_id) => {
                 PipelineErrorInfo::new(OUTPUT_STREAM_ALREADY_REGISTERED, ErrorType::UserError)
+                    .with_entity(output_id)
             }
 
-            RegisterOutputError::EncoderError(_, _) => {
-                PipelineErrorInfo::new(ENCODER_ERROR, ErrorType::ServerError)
+            RegisterOutputError::EncoderError(output_id, _) => {
+                PipelineErrorInfo::new(ENCODER_ERROR, ErrorType::ServerError).with_entity(output_id)
             }
-            RegisterOutputError::UnsupportedResolution(_) => {
+            RegisterOutputError::UnsupportedResolution(output_id) => {
                 PipelineErrorInfo::new(UNSUPPORTED_RESOLUTION, ErrorType::UserError)
+                    .with_entity(output_id)
+            }
+            RegisterOutputError::Watermark(output_id, _) => {
+                PipelineErrorInfo::new(REGISTER_WATERMARK_ERROR, ErrorType::UserError)
+                    .with_entity(output_id)
+            }
+            RegisterOutputError::CaptionsInputNotFound(output_id, input_id) => {
+                PipelineErrorInfo::new(CAPTIONS_INPUT_NOT_FOUND, ErrorType::UserError)
+                    .with_entity(output_id)
+                    .with_entity(input_id)
+            }
+            RegisterOutputError::KeyFillNotFound(output_id, fill_output_id) => {
+                PipelineErrorInfo::new(KEY_FILL_OUTPUT_NOT_FOUND, ErrorType::UserError)
+                    .with_entity(output_id)
+                    .with_entity(fill_output_id)
+            }
+            RegisterOutputError::KeyResolutionMismatch(output_id, fill_output_id) => {
+                PipelineErrorInfo::new(KEY_RESOLUTION_MISMATCH, ErrorType::UserError)
+                    .with_entity(output_id)
+                    .with_entity(fill_output_id)
             }
         }
     }
@@ -115,27 +299,156 @@ const INPUT_STREAM_NOT_FOUND: &str = "INPUT_STREAM_NOT_FOUND";
 impl From<&UnregisterInputError> for PipelineErrorInfo {
     fn from(err: &UnregisterInputError) -> Self {
         match err {
-            UnregisterInputError::NotFound(_) => {
+            UnregisterInputError::NotFound(input_id) => {
                 PipelineErrorInfo::new(INPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(input_id)
             }
-            UnregisterInputError::StillInUse(_) => {
+            UnregisterInputError::StillInUse(input_id) => {
                 PipelineErrorInfo::new(INPUT_STREAM_STILL_IN_USE, ErrorType::UserError)
+                    .with_entity(input_id)
             }
         }
     }
 }
 
 const OUTPUT_STREAM_STILL_IN_USE: &str = "OUTPUT_STREAM_STILL_IN_USE";
+const KEY_OUTPUT_STILL_REGISTERED: &str = "KEY_OUTPUT_STILL_REGISTERED";
 const OUTPUT_STREAM_NOT_FOUND: &str = "OUTPUT_STREAM_NOT_FOUND";
 
 impl From<&UnregisterOutputError> for PipelineErrorInfo {
     fn from(err: &UnregisterOutputError) -> Self {
         match err {
-            UnregisterOutputError::NotFound(_) => {
+            UnregisterOutputError::NotFound(output_id) => {
                 PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(output_id)
             }
-            UnregisterOutputError::StillInUse(_) => {
+            UnregisterOutputError::StillInUse(output_id) => {
                 PipelineErrorInfo::new(OUTPUT_STREAM_STILL_IN_USE, ErrorType::UserError)
+                    .with_entity(output_id)
+            }
+            UnregisterOutputError::KeyStillRegistered(output_id, key_output_id) => {
+                PipelineErrorInfo::new(KEY_OUTPUT_STILL_REGISTERED, ErrorType::UserError)
+                    .with_entity(output_id)
+                    .with_entity(key_output_id)
+            }
+        }
+    }
+}
+
+impl From<&StartOutputError> for PipelineErrorInfo {
+    fn from(err: &StartOutputError) -> Self {
+        match err {
+            StartOutputError::NotFound(output_id) => {
+                PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(output_id)
+            }
+        }
+    }
+}
+
+impl From<&StopOutputError> for PipelineErrorInfo {
+    fn from(err: &StopOutputError) -> Self {
+        match err {
+            StopOutputError::NotFound(output_id) => {
+                PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(output_id)
+            }
+        }
+    }
+}
+
+impl From<&UpdateOutputError> for PipelineErrorInfo {
+    fn from(err: &UpdateOutputError) -> Self {
+        match err {
+            UpdateOutputError::NotFound(output_id) => {
+                PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(output_id)
+            }
+            UpdateOutputError::EncoderError(output_id, _) => {
+                PipelineErrorInfo::new(ENCODER_ERROR, ErrorType::ServerError).with_entity(output_id)
+            }
+        }
+    }
+}
+
+const SET_SLATE_ERROR: &str = "SET_SLATE_ERROR";
+
+impl From<&UpdateOutputSlateError> for PipelineErrorInfo {
+    fn from(err: &UpdateOutputSlateError) -> Self {
+        match err {
+            UpdateOutputSlateError::NotFound(output_id) => {
+                PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(output_id)
+            }
+            UpdateOutputSlateError::Slate(output_id, _) => {
+                PipelineErrorInfo::new(SET_SLATE_ERROR, ErrorType::UserError).with_entity(output_id)
+            }
+        }
+    }
+}
+
+impl From<&RequestKeyframeError> for PipelineErrorInfo {
+    fn from(err: &RequestKeyframeError) -> Self {
+        match err {
+            RequestKeyframeError::NotFound(output_id) => {
+                PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(output_id)
+            }
+        }
+    }
+}
+
+const CAPTIONS_NOT_ENABLED: &str = "CAPTIONS_NOT_ENABLED";
+
+impl From<&SendCaptionsError> for PipelineErrorInfo {
+    fn from(err: &SendCaptionsError) -> Self {
+        match err {
+            SendCaptionsError::NotFound(output_id) => {
+                PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(output_id)
+            }
+            SendCaptionsError::NotEnabled(output_id) => {
+                PipelineErrorInfo::new(CAPTIONS_NOT_ENABLED, ErrorType::UserError)
+                    .with_entity(output_id)
+            }
+        }
+    }
+}
+
+const SCTE35_NOT_ENABLED: &str = "SCTE35_NOT_ENABLED";
+
+impl From<&InsertScte35Error> for PipelineErrorInfo {
+    fn from(err: &InsertScte35Error) -> Self {
+        match err {
+            InsertScte35Error::NotFound(output_id) => {
+                PipelineErrorInfo::new(OUTPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(output_id)
+            }
+            InsertScte35Error::NotEnabled(output_id) => {
+                PipelineErrorInfo::new(SCTE35_NOT_ENABLED, ErrorType::UserError)
+                    .with_entity(output_id)
+            }
+        }
+    }
+}
+
+const REPLAY_BUFFER_NOT_ENABLED: &str = "REPLAY_BUFFER_NOT_ENABLED";
+const REPLAY_BUFFER_EMPTY: &str = "REPLAY_BUFFER_EMPTY";
+
+impl From<&ReplayError> for PipelineErrorInfo {
+    fn from(err: &ReplayError) -> Self {
+        match err {
+            ReplayError::InputNotFound(input_id) => {
+                PipelineErrorInfo::new(INPUT_STREAM_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(input_id)
+            }
+            ReplayError::Buffer(ReplayBufferError::NotFound(input_id)) => {
+                PipelineErrorInfo::new(REPLAY_BUFFER_NOT_ENABLED, ErrorType::UserError)
+                    .with_entity(input_id)
+            }
+            ReplayError::Buffer(ReplayBufferError::Empty(input_id)) => {
+                PipelineErrorInfo::new(REPLAY_BUFFER_EMPTY, ErrorType::UserError)
+                    .with_entity(input_id)
             }
         }
     }
@@ -146,28 +459,104 @@ const SCENE_SPEC_VALIDATION_ERROR: &str = "SCENE_SPEC_VALIDATION_ERROR";
 const MISSING_NODE_WITH_ID: &str = "MISSING_NODE_WITH_ID";
 const UNKNOWN_RESOLUTION_ON_OUTPUT_NODE: &str = "UNKNOWN_RESOLUTION_ON_OUTPUT_NODE";
 const CONSTRAINTS_VALIDATION_ERROR: &str = "CONSTRAINTS_VALIDATION_ERROR";
+const INVALID_NODE_RESOLUTION: &str = "INVALID_NODE_RESOLUTION";
+const KEY_OUTPUT_IN_SCENE: &str = "KEY_OUTPUT_IN_SCENE";
+const RESOLUTION_EXCEEDS_GPU_LIMITS: &str = "RESOLUTION_EXCEEDS_GPU_LIMITS";
 
 impl From<&UpdateSceneError> for PipelineErrorInfo {
     fn from(err: &UpdateSceneError) -> Self {
         match err {
-            UpdateSceneError::CreateNodeError(_, _) => {
-                PipelineErrorInfo::new(FAILED_TO_CREATE_NODE, ErrorType::UserError)
+            UpdateSceneError::CreateNodeError(err, node_id) => {
+                let info = PipelineErrorInfo::new(FAILED_TO_CREATE_NODE, ErrorType::UserError);
+                match err {
+                    CreateNodeError::ShaderNotFound(shader_id) => info
+                        .with_entity(shader_id)
+                        .with_node(node_id, Some("shader_id")),
+                    CreateNodeError::ShaderNodeParametersValidationError(_, shader_id) => info
+                        .with_entity(shader_id)
+                        .with_node(node_id, Some("shader_params")),
+                    CreateNodeError::WebRendererNotFound(instance_id) => info
+                        .with_entity(instance_id)
+                        .with_node(node_id, Some("instance_id")),
+                    CreateNodeError::ImageNotFound(image_id)
+                    | CreateNodeError::SvgRasterization(_, image_id) => info
+                        .with_entity(image_id)
+                        .with_node(node_id, Some("image_id")),
+                    CreateNodeError::MeshNotFound(mesh_id) => info
+                        .with_entity(mesh_id)
+                        .with_node(node_id, Some("geometry")),
+                    CreateNodeError::TransitionValidation(_) => {
+                        info.with_node(node_id, Some("transition"))
+                    }
+                    CreateNodeError::Captions(_) => info.with_node(node_id, Some("source")),
+                    CreateNodeError::ReplayBufferNotFound(input_id) => info
+                        .with_entity(input_id)
+                        .with_node(node_id, Some("input_id")),
+                }
             }
-            UpdateSceneError::InvalidSpec(_) => {
-                PipelineErrorInfo::new(SCENE_SPEC_VALIDATION_ERROR, ErrorType::UserError)
+            UpdateSceneError::InvalidSpec(err) => {
+                let info =
+                    PipelineErrorInfo::new(SCENE_SPEC_VALIDATION_ERROR, ErrorType::UserError);
+                match err {
+                    SceneSpecValidationError::UnknownInputPadOnNode { missing_node, node } => info
+                        .with_entity(missing_node)
+                        .with_node(node, Some("input_pads")),
+                    SceneSpecValidationError::UnknownInputPadOnOutput {
+                        missing_node,
+                        output,
+                    } => info.with_entity(output).with_entity(missing_node),
+                    SceneSpecValidationError::UnknownOutput(output) => info.with_entity(output),
+                    SceneSpecValidationError::DuplicateNodeNames(node)
+                    | SceneSpecValidationError::DuplicateNodeAndInputNames(node) => {
+                        info.with_node(node, Some("node_id"))
+                    }
+                    SceneSpecValidationError::CycleDetected(node) => {
+                        info.with_node(node, Some("input_pads"))
+                    }
+                    SceneSpecValidationError::UnusedNodes(err) => {
+                        let mut nodes: Vec<_> = err.0.iter().collect();
+                        nodes.sort_by_key(|node| node.to_string());
+                        nodes
+                            .into_iter()
+                            .fold(info, |info, node| info.with_entity(node))
+                    }
+                    SceneSpecValidationError::InvalidNodeSpec(err, node) => match err {
+                        NodeSpecValidationError::Sampler(_) => {
+                            info.with_node(node, Some("sampler"))
+                        }
+                        NodeSpecValidationError::Geometry(_) => {
+                            info.with_node(node, Some("geometry"))
+                        }
+                        NodeSpecValidationError::Builtin(_) => info.with_node(node, None),
+                    },
+                }
             }
-            UpdateSceneError::NoNodeWithIdError(_) => {
+            UpdateSceneError::NoNodeWithIdError(node_id) => {
                 // ServerError because it should be validated is spec validation
                 PipelineErrorInfo::new(MISSING_NODE_WITH_ID, ErrorType::ServerError)
+                    .with_entity(node_id)
             }
             UpdateSceneError::WgpuError(err) => err.into(),
-            UpdateSceneError::UnknownResolutionOnOutput(_) => {
+            UpdateSceneError::UnknownResolutionOnOutput(node_id) => {
                 PipelineErrorInfo::new(UNKNOWN_RESOLUTION_ON_OUTPUT_NODE, ErrorType::ServerError)
+                    .with_node(node_id, Some("resolution"))
+            }
+            UpdateSceneError::ConstraintsValidationError(_, node_id) => {
+                PipelineErrorInfo::new(CONSTRAINTS_VALIDATION_ERROR, ErrorType::UserError)
+                    .with_node(node_id, Some("input_pads"))
+            }
+            UpdateSceneError::EmptyResolution(node_id) => {
+                PipelineErrorInfo::new(INVALID_NODE_RESOLUTION, ErrorType::UserError)
+                    .with_node(node_id, None)
+            }
+            UpdateSceneError::ResolutionExceedsGpuLimits(node_id, _, _) => {
+                PipelineErrorInfo::new(RESOLUTION_EXCEEDS_GPU_LIMITS, ErrorType::UserError)
+                    .with_node(node_id, None)
+            }
+            UpdateSceneError::KeyOutputInScene(output_id) => {
+                PipelineErrorInfo::new(KEY_OUTPUT_IN_SCENE, ErrorType::UserError)
+                    .with_entity(output_id)
             }
-            UpdateSceneError::ConstraintsValidationError(_, _) => PipelineErrorInfo {
-                error_code: CONSTRAINTS_VALIDATION_ERROR,
-                error_type: ErrorType::UserError,
-            },
         }
     }
 }
@@ -195,20 +584,32 @@ impl From<&InitRendererEngineError> for PipelineErrorInfo {
 const ENTITY_ALREADY_REGISTERED: &str = "ENTITY_ALREADY_REGISTERED";
 const INVALID_SHADER: &str = "INVALID_SHADER";
 const REGISTER_IMAGE_ERROR: &str = "REGISTER_IMAGE_ERROR";
+const REGISTER_MESH_ERROR: &str = "REGISTER_MESH_ERROR";
+const REGISTER_WEB_RENDERER_ERROR: &str = "REGISTER_WEB_RENDERER_ERROR";
 
 impl From<&RegisterRendererError> for PipelineErrorInfo {
     fn from(err: &RegisterRendererError) -> Self {
         match err {
             RegisterRendererError::RendererRegistry(err) => match err {
-                RegisterError::KeyTaken { .. } => {
+                RegisterError::KeyTaken { renderer_id, .. } => {
                     PipelineErrorInfo::new(ENTITY_ALREADY_REGISTERED, ErrorType::UserError)
+                        .with_entity(renderer_id)
                 }
             },
-            RegisterRendererError::Shader(_, _) => {
-                PipelineErrorInfo::new(INVALID_SHADER, ErrorType::UserError)
+            RegisterRendererError::Shader(_, shader_id) => {
+                PipelineErrorInfo::new(INVALID_SHADER, ErrorType::UserError).with_entity(shader_id)
             }
-            RegisterRendererError::Image(_, _) => {
+            RegisterRendererError::Image(_, image_id) => {
                 PipelineErrorInfo::new(REGISTER_IMAGE_ERROR, ErrorType::UserError)
+                    .with_entity(image_id)
+            }
+            RegisterRendererError::Mesh(_, mesh_id) => {
+                PipelineErrorInfo::new(REGISTER_MESH_ERROR, ErrorType::UserError)
+                    .with_entity(mesh_id)
+            }
+            RegisterRendererError::WebRenderer(_, instance_id) => {
+                PipelineErrorInfo::new(REGISTER_WEB_RENDERER_ERROR, ErrorType::UserError)
+                    .with_entity(instance_id)
             }
         }
     }
@@ -217,20 +618,125 @@ impl From<&RegisterRendererError> for PipelineErrorInfo {
 const ENTITY_NOT_FOUND: &str = "ENTITY_NOT_FOUND";
 const ENTITY_STILL_IN_USE: &str = "ENTITY_STILL_IN_USE";
 
+impl From<&UpdateShaderError> for PipelineErrorInfo {
+    fn from(err: &UpdateShaderError) -> Self {
+        match err {
+            UpdateShaderError::RendererRegistry(err) => {
+                PipelineErrorInfo::new(ENTITY_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(err.renderer_id())
+            }
+            UpdateShaderError::Shader(_, shader_id) => {
+                PipelineErrorInfo::new(INVALID_SHADER, ErrorType::UserError).with_entity(shader_id)
+            }
+            UpdateShaderError::ParametersValidationError(_, shader_id, node_id) => {
+                PipelineErrorInfo::new(INVALID_SHADER, ErrorType::UserError)
+                    .with_entity(shader_id)
+                    .with_node(node_id, Some("shader_params"))
+            }
+            UpdateShaderError::ConstraintsValidationError(_, shader_id, node_id) => {
+                PipelineErrorInfo::new(CONSTRAINTS_VALIDATION_ERROR, ErrorType::UserError)
+                    .with_entity(shader_id)
+                    .with_node(node_id, Some("input_pads"))
+            }
+        }
+    }
+}
+
+const PRELOAD_IMAGE_ERROR: &str = "PRELOAD_IMAGE_ERROR";
+
+impl From<&PreloadImageError> for PipelineErrorInfo {
+    fn from(err: &PreloadImageError) -> Self {
+        match err {
+            PreloadImageError::NotFound(image_id) => {
+                PipelineErrorInfo::new(ENTITY_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(image_id)
+            }
+            PreloadImageError::SvgRasterization(_, image_id) => {
+                PipelineErrorInfo::new(PRELOAD_IMAGE_ERROR, ErrorType::UserError)
+                    .with_entity(image_id)
+            }
+        }
+    }
+}
+
 impl From<&UnregisterRendererError> for PipelineErrorInfo {
     fn from(err: &UnregisterRendererError) -> Self {
         match err {
-            UnregisterRendererError::RendererRegistry(_) => {
+            UnregisterRendererError::RendererRegistry(err) => {
                 PipelineErrorInfo::new(ENTITY_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(err.renderer_id())
             }
-            UnregisterRendererError::ImageStillInUse(_, _) => {
+            UnregisterRendererError::ImageStillInUse(renderer_id, node_id)
+            | UnregisterRendererError::ShaderStillInUse(renderer_id, node_id)
+            | UnregisterRendererError::MeshStillInUse(renderer_id, node_id)
+            | UnregisterRendererError::WebRendererInstanceStillInUse(renderer_id, node_id) => {
                 PipelineErrorInfo::new(ENTITY_STILL_IN_USE, ErrorType::EntityNotFound)
+                    .with_entity(renderer_id)
+                    .with_entity(node_id)
             }
-            UnregisterRendererError::ShaderStillInUse(_, _) => {
-                PipelineErrorInfo::new(ENTITY_STILL_IN_USE, ErrorType::EntityNotFound)
+        }
+    }
+}
+
+impl From<&WebRendererEventError> for PipelineErrorInfo {
+    fn from(err: &WebRendererEventError) -> Self {
+        match err {
+            WebRendererEventError::NotFound(instance_id) => {
+                PipelineErrorInfo::new(ENTITY_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(instance_id)
             }
-            UnregisterRendererError::WebRendererInstanceStillInUse(_, _) => {
-                PipelineErrorInfo::new(ENTITY_STILL_IN_USE, ErrorType::EntityNotFound)
+        }
+    }
+}
+
+const UNSUPPORTED_SHADER_PARAMS: &str = "UNSUPPORTED_SHADER_PARAMS";
+
+impl From<&RendererInfoError> for PipelineErrorInfo {
+    fn from(err: &RendererInfoError) -> Self {
+        match err {
+            RendererInfoError::NotFound { renderer_id, .. } => {
+                PipelineErrorInfo::new(ENTITY_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(renderer_id)
+            }
+            RendererInfoError::UnsupportedShaderParams(_, shader_id) => {
+                PipelineErrorInfo::new(UNSUPPORTED_SHADER_PARAMS, ErrorType::UserError)
+                    .with_entity(shader_id)
+            }
+        }
+    }
+}
+
+const SNAPSHOT_NOT_AVAILABLE: &str = "SNAPSHOT_NOT_AVAILABLE";
+const SNAPSHOT_DOWNLOAD_ERROR: &str = "SNAPSHOT_DOWNLOAD_ERROR";
+
+impl From<&FreezeFrameError> for PipelineErrorInfo {
+    fn from(err: &FreezeFrameError) -> Self {
+        match err {
+            FreezeFrameError::Snapshot(err, _) => err.into(),
+            FreezeFrameError::RendererRegistry(RegisterError::KeyTaken { renderer_id, .. }) => {
+                PipelineErrorInfo::new(ENTITY_ALREADY_REGISTERED, ErrorType::UserError)
+                    .with_entity(renderer_id)
+            }
+        }
+    }
+}
+
+impl From<&SnapshotError> for PipelineErrorInfo {
+    fn from(err: &SnapshotError) -> Self {
+        match err {
+            SnapshotError::NodeNotFound(node_id) => {
+                PipelineErrorInfo::new(ENTITY_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(node_id)
+            }
+            SnapshotError::OutputNotFound(output_id) => {
+                PipelineErrorInfo::new(ENTITY_NOT_FOUND, ErrorType::EntityNotFound)
+                    .with_entity(output_id)
+            }
+            SnapshotError::NoFrame => {
+                PipelineErrorInfo::new(SNAPSHOT_NOT_AVAILABLE, ErrorType::UserError)
+            }
+            SnapshotError::Download(_) => {
+                PipelineErrorInfo::new(SNAPSHOT_DOWNLOAD_ERROR, ErrorType::ServerError)
             }
         }
     }
@@ -251,3 +757,20 @@ impl From<&WgpuError> for PipelineErrorInfo {
         }
     }
 }
+
+const INVALID_TIMELINE: &str = "INVALID_TIMELINE";
+const TIMELINE_NOT_RUNNING: &str = "TIMELINE_NOT_RUNNING";
+
+impl From<&TimelineError> for PipelineErrorInfo {
+    fn from(err: &TimelineError) -> Self {
+        match err {
+            TimelineError::NoEntries | TimelineError::ZeroDuration(_) => {
+                PipelineErrorInfo::new(INVALID_TIMELINE, ErrorType::UserError)
+            }
+            TimelineError::InvalidScene(_, err) => PipelineErrorInfo::from(err),
+            TimelineError::NotRunning => {
+                PipelineErrorInfo::new(TIMELINE_NOT_RUNNING, ErrorType::UserError)
+            }
+        }
+    }
+}
@@ -1,40 +1,75 @@
-use std::collections::{hash_map, HashMap};
+use std::any::Any;
+use std::collections::{hash_map, HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
-use std::sync::{Mutex, MutexGuard};
+use std::sync::{Mutex, MutexGuard, PoisonError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use compositor_common::error::ErrorStack;
-use compositor_common::renderer_spec::{RendererId, RendererSpec};
-use compositor_common::scene::{InputId, OutputId, Resolution, SceneSpec};
+use compositor_common::frame::FrameFormat;
+use compositor_common::renderer_spec::{RendererId, RendererSpec, ShaderSpec};
+use compositor_common::scene::{
+    InputFallback, InputId, NodeId, NodeParams, OutputId, OutputSlate, Resolution, SceneSpec,
+    TestPatternSpec, Watermark,
+};
+use compositor_common::util::colors::RGBColor;
 use compositor_common::Framerate;
 use compositor_render::error::{
-    InitRendererEngineError, RegisterRendererError, UnregisterRendererError,
+    FreezeFrameError, InitRendererEngineError, PreloadImageError, RegisterRendererError,
+    UnregisterRendererError, UpdateShaderError, WebRendererEventError,
 };
-use compositor_render::renderer::RendererOptions;
-use compositor_render::EventLoop;
+use compositor_render::renderer::{RendererOptions, SnapshotTarget};
 use compositor_render::{error::UpdateSceneError, Renderer};
-use compositor_render::{RegistryType, WebRendererOptions};
-use crossbeam_channel::unbounded;
+use compositor_render::{EventLoop, FrameSet};
+use compositor_render::{GpuOptions, RegistryType, WebRendererEvent, WebRendererOptions};
+use crossbeam_channel::{bounded, Sender};
 use ffmpeg_next::Packet;
-use log::{error, warn};
+use log::{error, info};
 
 use crate::error::{
-    RegisterInputError, RegisterOutputError, UnregisterInputError, UnregisterOutputError,
+    InsertScte35Error, RegisterInputError, RegisterOutputError, ReplayError, RequestKeyframeError,
+    SendCaptionsError, StartOutputError, StopOutputError, TimelineError, UnregisterInputError,
+    UnregisterOutputError, UpdateOutputError, UpdateOutputSlateError,
 };
-use crate::queue::Queue;
+use crate::queue::{self, Queue};
 
-use self::decoder::Decoder;
-use self::encoder::{Encoder, EncoderSettings};
+use self::backpressure::RenderQueue;
+use self::captions::OutputCaptions;
+use self::decoder::{Decoder, DecoderOptions};
+use self::encoder::{Encoder, EncoderSettings, StoppedOutputFrames};
+use self::placeholder::{spawn_pre_start_sender, PlaceholderFrames};
+use self::scte35::Scte35Splice;
+use self::timeline::Timeline;
+use self::watchdog::{RenderFailureOutcome, RenderWatchdog};
 
+mod backpressure;
+pub mod captions;
 pub mod decoder;
 pub mod encoder;
+mod events;
+mod latency;
+mod placeholder;
+pub mod recording;
+pub mod scte35;
+mod timeline;
+mod watchdog;
+
+pub use self::backpressure::{Backpressure, RenderStats};
+pub use self::events::{PipelineEvent, PipelineEvents};
+pub use self::latency::{LatencyPercentiles, LatencyStats};
+pub use self::placeholder::PreStartOutput;
+pub use self::timeline::{EntryTransition, TimelineEntry, TimelineSpec, TimelineStatus};
+pub use self::watchdog::{RenderFailureAction, RenderFailurePolicy};
 
 pub trait PipelineOutput: Send + Sync + Sized + Clone + 'static {
     type Opts: Send + Sync + 'static;
     type Context: 'static;
 
     fn send_packet(&self, context: &mut Self::Context, packet: Packet);
+    /// Writes SCTE-35 `splice_info_section` with the PTS of the frame it's sent
+    /// with. Outputs that can't carry SCTE-35 ignore it.
+    fn send_scte35(&self, _context: &mut Self::Context, _section: &[u8], _pts: Duration) {}
     fn new(
         opts: Self::Opts,
         codec: ffmpeg_next::Codec,
@@ -45,43 +80,129 @@ pub trait PipelineInput: Send + Sync + Sized + 'static {
     type Opts: Send + Sync;
     type PacketIterator: Iterator<Item = Packet> + Send;
 
-    fn new(opts: Self::Opts) -> (Self, Self::PacketIterator);
+    fn new(
+        opts: Self::Opts,
+    ) -> Result<(Self, Self::PacketIterator), Box<dyn std::error::Error + Send + Sync + 'static>>;
     fn decoder_parameters(&self) -> decoder::DecoderParameters;
+    /// Clock of the sender, if the input protocol can provide it.
+    fn sender_clock(&self) -> Option<decoder::SenderClock>;
 }
 
 pub struct OutputOptions<Output: PipelineOutput> {
     pub receiver_options: Output::Opts,
     pub encoder_settings: EncoderSettings,
     pub resolution: Resolution,
+    /// Frame composed for the output is scaled on the GPU to `resolution`, so the
+    /// scene can render it at a different resolution, e.g. a proxy of a full
+    /// resolution output. Otherwise the output node has to match `resolution`.
+    pub scale_to_resolution: bool,
+    /// Output sends the alpha matte (key) of the frame sent to this output (fill)
+    /// instead of a frame of the scene, so the graphics can be keyed downstream,
+    /// e.g. by a hardware switcher. Key output can't be used in the scene and
+    /// must have the same resolution as the fill.
+    pub key_of: Option<OutputId>,
+    pub format: FrameFormat,
+    pub watermark: Option<Watermark>,
+    /// Closed captions embedded in the encoded stream (CEA-708 in H264 SEI).
+    pub captions: Option<OutputCaptions>,
+    /// Timecode of every frame written to the encoded stream (user data unregistered SEI).
+    pub timecode_sei: bool,
+    /// Splice commands are accepted by [`Pipeline::insert_scte35`].
+    pub scte35: bool,
 }
 
 pub struct Pipeline<Input: PipelineInput, Output: PipelineOutput> {
     inputs: HashMap<InputId, Arc<Decoder<Input>>>,
+    /// Inputs generated by the renderer, they are not added to the queue.
+    test_patterns: HashSet<InputId>,
     outputs: OutputRegistry<Encoder<Output>>,
     queue: Arc<Queue>,
     renderer: Renderer,
     is_started: bool,
+    /// Start the pipeline automatically when the first scene is set.
+    auto_start: bool,
+    /// Stops the thread that is sending placeholder frames before the
+    /// pipeline is started.
+    pre_start_stop: Option<Sender<()>>,
+    /// Color of the frames sent to outputs registered after the pipeline is started.
+    backfill_color: RGBColor,
+    render_failure_policy: RenderFailurePolicy,
+    events: PipelineEvents,
+    backpressure: Backpressure,
+    render_stats: Arc<RenderStats>,
+    latency_stats: Arc<LatencyStats>,
+    /// Held by the render thread while a frame is rendered.
+    render_lock: Arc<Mutex<()>>,
+    stream_fallback_timeout: Duration,
+    /// Advanced by the render thread, it replaces the scene when an entry starts.
+    timeline: Arc<Mutex<Option<Timeline>>>,
 }
 
 pub struct Options {
     pub framerate: Framerate,
     pub stream_fallback_timeout: Duration,
     pub web_renderer: WebRendererOptions,
+    pub gpu: GpuOptions,
+    pub auto_start: bool,
+    pub pre_start_output: PreStartOutput,
+    /// What happens to outputs when rendering fails repeatedly.
+    pub render_failure_policy: RenderFailurePolicy,
+    /// Buffering and synchronization of inputs.
+    pub queue: queue::Options,
+    pub backpressure: Backpressure,
 }
 
 impl<Input: PipelineInput, Output: PipelineOutput> Pipeline<Input, Output> {
     pub fn new(opts: Options) -> Result<(Self, EventLoop), InitRendererEngineError> {
         let (renderer, event_loop) = Renderer::new(RendererOptions {
             web_renderer: opts.web_renderer,
+            gpu: opts.gpu,
             framerate: opts.framerate,
             stream_fallback_timeout: opts.stream_fallback_timeout,
+            shader_random_seed: None,
         })?;
+        let outputs = OutputRegistry::new();
+        let queue = Arc::new(Queue::new(opts.framerate, opts.queue));
+        // Queue can't produce frames faster than they are rendered, otherwise
+        // offline rendering would drop them.
+        let backpressure = match queue.is_offline() {
+            true => Backpressure::Block,
+            false => opts.backpressure,
+        };
+
+        let (pre_start_stop_sender, pre_start_stop_receiver) = bounded(1);
+        if !queue.is_offline() {
+            spawn_pre_start_sender(
+                opts.pre_start_output,
+                outputs.clone(),
+                opts.framerate,
+                queue.clock_start(),
+                pre_start_stop_receiver,
+            );
+        }
+
+        let latency_stats = Arc::new(LatencyStats::new(
+            (!queue.is_offline()).then(|| queue.clock_start()),
+        ));
+
         let pipeline = Pipeline {
-            outputs: OutputRegistry::new(),
+            outputs,
             inputs: HashMap::new(),
-            queue: Arc::new(Queue::new(opts.framerate)),
+            test_patterns: HashSet::new(),
+            queue,
             renderer,
             is_started: false,
+            auto_start: opts.auto_start,
+            pre_start_stop: Some(pre_start_stop_sender),
+            backfill_color: opts.pre_start_output.backfill_color(),
+            render_failure_policy: opts.render_failure_policy,
+            events: PipelineEvents::default(),
+            backpressure,
+            render_stats: Arc::new(RenderStats::default()),
+            latency_stats,
+            render_lock: Arc::new(Mutex::new(())),
+            stream_fallback_timeout: opts.stream_fallback_timeout,
+            timeline: Arc::new(Mutex::new(None)),
         };
 
         Ok((pipeline, event_loop))
@@ -95,41 +216,133 @@ impl<Input: PipelineInput, Output: PipelineOutput> Pipeline<Input, Output> {
         &self.queue
     }
 
+    pub fn render_stats(&self) -> &RenderStats {
+        &self.render_stats
+    }
+
+    pub fn latency_stats(&self) -> &LatencyStats {
+        &self.latency_stats
+    }
+
+    /// Events emitted since the previous call, oldest first.
+    pub fn take_events(&self) -> Vec<PipelineEvent> {
+        self.events.take()
+    }
+
+    /// Frames are not rendered while the returned lock is held, so a sequence of
+    /// changes (e.g. registering a renderer and updating the scene to use it) is
+    /// never observed half-applied. Frames produced in the meantime are subject
+    /// to the backpressure policy.
+    pub fn render_lock(&self) -> Arc<Mutex<()>> {
+        self.render_lock.clone()
+    }
+
+    /// Changes framerate of all outputs. Web renderers registered before the
+    /// change keep rendering at the initial framerate.
+    pub fn update_framerate(&self, framerate: Framerate) {
+        self.queue.set_output_framerate(framerate);
+    }
+
+    /// Decoded frames from the last `replay_buffer` are kept in memory, so they
+    /// can be played back by replay nodes after [`Pipeline::start_replay`].
     pub fn register_input(
         &mut self,
         input_id: InputId,
         input_opts: Input::Opts,
+        decoder_opts: DecoderOptions,
+        queue_opts: queue::InputOptions,
+        fallback: Option<InputFallback>,
+        replay_buffer: Option<Duration>,
     ) -> Result<(), RegisterInputError> {
-        if self.inputs.contains_key(&input_id) {
+        if self.is_input_registered(&input_id) {
             return Err(RegisterInputError::AlreadyRegistered(input_id));
         }
 
-        self.inputs.insert(
+        let decoder = Decoder::new(
+            self.queue.clone(),
+            input_opts,
+            decoder_opts,
             input_id.clone(),
-            Decoder::new(self.queue.clone(), input_opts, input_id.clone()).into(),
-        );
-        self.queue.add_input(input_id);
+        )?;
+
+        self.inputs.insert(input_id.clone(), decoder.into());
+        self.queue.add_input(input_id.clone(), queue_opts);
+        if let Some(duration) = replay_buffer {
+            self.renderer
+                .register_replay_buffer(input_id.clone(), duration);
+        }
+        if let Some(fallback) = fallback {
+            self.renderer.register_input_fallback(input_id, fallback);
+        }
+        Ok(())
+    }
+
+    /// Frames of the input are generated by the renderer, so it doesn't depend on
+    /// the queue and never ends.
+    pub fn register_test_pattern(
+        &mut self,
+        input_id: InputId,
+        spec: TestPatternSpec,
+    ) -> Result<(), RegisterInputError> {
+        if self.is_input_registered(&input_id) {
+            return Err(RegisterInputError::AlreadyRegistered(input_id));
+        }
+
+        self.test_patterns.insert(input_id.clone());
+        self.renderer.register_test_pattern(input_id, spec);
         Ok(())
     }
 
     pub fn unregister_input(&mut self, input_id: &InputId) -> Result<(), UnregisterInputError> {
-        if !self.inputs.contains_key(input_id) {
+        if !self.is_input_registered(input_id) {
             return Err(UnregisterInputError::NotFound(input_id.clone()));
         }
+        self.validate_input_not_in_use(input_id, &self.renderer.scene_spec())?;
 
-        let scene_spec = self.renderer.scene_spec();
+        if self.test_patterns.remove(input_id) {
+            self.renderer.unregister_test_pattern(input_id);
+            return Ok(());
+        }
+        self.inputs.remove(input_id);
+        self.queue.remove_input(input_id);
+        self.renderer.unregister_input_fallback(input_id);
+        self.renderer.unregister_replay_buffer(input_id);
+        Ok(())
+    }
+
+    /// Checks that the input could be unregistered once `scene_spec` is applied,
+    /// without changing anything. The input is also in use if the timeline
+    /// refers to it.
+    pub fn validate_input_not_in_use(
+        &self,
+        input_id: &InputId,
+        scene_spec: &SceneSpec,
+    ) -> Result<(), UnregisterInputError> {
         let is_still_in_use = scene_spec
             .nodes
             .iter()
             .flat_map(|node| &node.input_pads)
-            .any(|input_pad_id| &input_id.0 == input_pad_id);
-        if is_still_in_use {
-            return Err(UnregisterInputError::StillInUse(input_id.clone()));
+            .any(|input_pad_id| &input_id.0 == input_pad_id)
+            || scene_spec.nodes.iter().any(|node| {
+                matches!(&node.params, NodeParams::Replay(spec) if &spec.input_id == input_id)
+            })
+            || self.with_timeline(|timeline| timeline.is_some_and(|t| t.uses_node(&input_id.0)));
+        match is_still_in_use {
+            true => Err(UnregisterInputError::StillInUse(input_id.clone())),
+            false => Ok(()),
         }
+    }
 
-        self.inputs.remove(input_id);
-        self.queue.remove_input(input_id);
-        Ok(())
+    fn is_input_registered(&self, input_id: &InputId) -> bool {
+        self.inputs.contains_key(input_id) || self.test_patterns.contains(input_id)
+    }
+
+    fn registered_input_ids(&self) -> HashSet<&NodeId> {
+        self.inputs
+            .keys()
+            .chain(self.test_patterns.iter())
+            .map(|i| &i.0)
+            .collect()
     }
 
     pub fn register_output(
@@ -145,8 +358,76 @@ impl<Input: PipelineInput, Output: PipelineOutput> Pipeline<Input, Output> {
             return Err(RegisterOutputError::UnsupportedResolution(output_id));
         }
 
-        let output = Encoder::new(output_opts)
-            .map_err(|e| RegisterOutputError::EncoderError(output_id.clone(), e))?;
+        let captions_subscribers = match &output_opts.captions {
+            Some(OutputCaptions::Input(input_id)) => {
+                let input = self.inputs.get(input_id).ok_or_else(|| {
+                    RegisterOutputError::CaptionsInputNotFound(output_id.clone(), input_id.clone())
+                })?;
+                Some(input.captions().clone())
+            }
+            Some(OutputCaptions::Api) | None => None,
+        };
+
+        if let Some(fill_output_id) = &output_opts.key_of {
+            let fill = self
+                .outputs
+                .get(fill_output_id)
+                .filter(|_| !self.renderer.is_key_output(fill_output_id))
+                .ok_or_else(|| {
+                    RegisterOutputError::KeyFillNotFound(output_id.clone(), fill_output_id.clone())
+                })?;
+            if fill.resolution() != output_opts.resolution {
+                return Err(RegisterOutputError::KeyResolutionMismatch(
+                    output_id,
+                    fill_output_id.clone(),
+                ));
+            }
+        }
+
+        let format = output_opts.format;
+        let scaled_resolution = output_opts
+            .scale_to_resolution
+            .then_some(output_opts.resolution);
+        let watermark = output_opts.watermark.clone();
+        let key_of = output_opts.key_of.clone();
+        let output = Encoder::new(
+            output_opts,
+            self.queue.output_framerate(),
+            self.queue.is_offline(),
+            self.latency_stats.clone(),
+        )
+        .map_err(|e| RegisterOutputError::EncoderError(output_id.clone(), e))?;
+        if let Some(watermark) = watermark {
+            self.renderer
+                .register_output_watermark(output_id.clone(), watermark)
+                .map_err(|e| RegisterOutputError::Watermark(output_id.clone(), e))?;
+        }
+        match key_of {
+            Some(fill_output_id) => {
+                self.renderer
+                    .register_output_key(output_id.clone(), fill_output_id, format)
+            }
+            None => self
+                .renderer
+                .register_output(output_id.clone(), format, scaled_resolution),
+        }
+        if let (Some(subscribers), Some((_, queue))) = (captions_subscribers, output.captions()) {
+            subscribers.subscribe(queue);
+        }
+
+        // Offline outputs start at PTS=0, so there is no wall clock based PTS for
+        // the placeholder frame.
+        if self.is_started && !self.queue.is_offline() {
+            // Start the stream right away instead of waiting for the first rendered frame,
+            // so receivers can initialize their decoders as soon as possible.
+            let pts = self.queue.clock_start().elapsed();
+            let frame = PlaceholderFrames::new(self.backfill_color).frame(
+                output.resolution(),
+                output.format(),
+                pts,
+            );
+            output.send_frame(frame);
+        }
 
         self.outputs.insert(output_id, output.into());
         Ok(())
@@ -157,19 +438,171 @@ impl<Input: PipelineInput, Output: PipelineOutput> Pipeline<Input, Output> {
             return Err(UnregisterOutputError::NotFound(output_id.clone()));
         }
 
-        let scene_spec = self.renderer.scene_spec();
+        self.validate_output_not_in_use(output_id, &self.renderer.scene_spec())?;
+        if let Some(key_output_id) = self.renderer.key_outputs_of(output_id).into_iter().next() {
+            return Err(UnregisterOutputError::KeyStillRegistered(
+                output_id.clone(),
+                key_output_id,
+            ));
+        }
+
+        self.outputs.remove(output_id);
+        self.renderer.unregister_output(output_id);
+        Ok(())
+    }
+
+    /// Checks that the output could be unregistered once `scene_spec` is applied,
+    /// without changing anything. Key outputs that use it as a fill are not
+    /// checked.
+    pub fn validate_output_not_in_use(
+        &self,
+        output_id: &OutputId,
+        scene_spec: &SceneSpec,
+    ) -> Result<(), UnregisterOutputError> {
         let is_still_in_use = scene_spec
             .outputs
             .iter()
-            .any(|node| &node.output_id == output_id);
-        if is_still_in_use {
-            return Err(UnregisterOutputError::StillInUse(output_id.clone()));
+            .any(|node| &node.output_id == output_id)
+            || self.with_timeline(|timeline| timeline.is_some_and(|t| t.uses_output(output_id)));
+        match is_still_in_use {
+            true => Err(UnregisterOutputError::StillInUse(output_id.clone())),
+            false => Ok(()),
         }
+    }
 
-        self.outputs.remove(output_id);
+    /// Changes encoder settings of the output without interrupting the stream.
+    pub fn update_output(
+        &self,
+        output_id: &OutputId,
+        encoder_settings: EncoderSettings,
+    ) -> Result<(), UpdateOutputError> {
+        let Some(output) = self.outputs.get(output_id) else {
+            return Err(UpdateOutputError::NotFound(output_id.clone()));
+        };
+        output
+            .update_settings(encoder_settings, self.queue.output_framerate())
+            .map_err(|e| UpdateOutputError::EncoderError(output_id.clone(), e))
+    }
+
+    /// Forces the encoder of the output to produce a keyframe from the next frame.
+    pub fn request_keyframe(&self, output_id: &OutputId) -> Result<(), RequestKeyframeError> {
+        let Some(output) = self.outputs.get(output_id) else {
+            return Err(RequestKeyframeError::NotFound(output_id.clone()));
+        };
+        output.request_keyframe();
         Ok(())
     }
 
+    /// Queues text embedded as closed captions in the stream of the output. Output
+    /// has to be registered with captions sent over the API.
+    pub fn send_captions(&self, output_id: &OutputId, text: &str) -> Result<(), SendCaptionsError> {
+        let Some(output) = self.outputs.get(output_id) else {
+            return Err(SendCaptionsError::NotFound(output_id.clone()));
+        };
+        match output.captions() {
+            Some((OutputCaptions::Api, queue)) => {
+                queue.push_text(text);
+                Ok(())
+            }
+            _ => Err(SendCaptionsError::NotEnabled(output_id.clone())),
+        }
+    }
+
+    /// Queues the splice command, it's written to the output with the next encoded frame.
+    pub fn insert_scte35(
+        &self,
+        output_id: &OutputId,
+        splice: Scte35Splice,
+    ) -> Result<(), InsertScte35Error> {
+        let Some(output) = self.outputs.get(output_id) else {
+            return Err(InsertScte35Error::NotFound(output_id.clone()));
+        };
+        match output.scte35() {
+            Some(queue) => {
+                queue.push(splice);
+                Ok(())
+            }
+            None => Err(InsertScte35Error::NotEnabled(output_id.clone())),
+        }
+    }
+
+    /// Replay nodes of the input play back frames from its replay buffer, starting
+    /// `offset` before the latest frame, at `speed` relative to real time (e.g. 0.5
+    /// for slow motion). Playback stops after `duration` of recorded frames, or
+    /// at the frame that was the latest one when the replay started.
+    pub fn start_replay(
+        &self,
+        input_id: &InputId,
+        offset: Duration,
+        duration: Option<Duration>,
+        speed: f64,
+    ) -> Result<(), ReplayError> {
+        if !self.inputs.contains_key(input_id) {
+            return Err(ReplayError::InputNotFound(input_id.clone()));
+        }
+        self.renderer
+            .start_replay(input_id, offset, duration, speed)?;
+        Ok(())
+    }
+
+    /// Replay nodes of the input show their fallbacks again.
+    pub fn stop_replay(&self, input_id: &InputId) -> Result<(), ReplayError> {
+        if !self.inputs.contains_key(input_id) {
+            return Err(ReplayError::InputNotFound(input_id.clone()));
+        }
+        self.renderer.stop_replay(input_id)?;
+        Ok(())
+    }
+
+    /// Resume sending frames to the output. Outputs are running by default
+    /// after registration.
+    pub fn start_output(&self, output_id: &OutputId) -> Result<(), StartOutputError> {
+        let output = self.outputs.get(output_id);
+        let Some(output) = output else {
+            return Err(StartOutputError::NotFound(output_id.clone()));
+        };
+        output.start();
+        Ok(())
+    }
+
+    /// Stop sending rendered frames to the output without unregistering it, e.g.
+    /// while its destination is unavailable. `frames` are encoded and sent instead.
+    /// Port, encoder and other resources stay reserved until the output is unregistered.
+    pub fn stop_output(
+        &self,
+        output_id: &OutputId,
+        frames: StoppedOutputFrames,
+    ) -> Result<(), StopOutputError> {
+        let output = self.outputs.get(output_id);
+        let Some(output) = output else {
+            return Err(StopOutputError::NotFound(output_id.clone()));
+        };
+        output.stop(frames);
+        Ok(())
+    }
+
+    /// Replace the scene rendered for the output with the slate, `None` switches
+    /// back to the scene. Frames keep being encoded while the slate is shown.
+    pub fn update_output_slate(
+        &self,
+        output_id: &OutputId,
+        slate: Option<OutputSlate>,
+    ) -> Result<(), UpdateOutputSlateError> {
+        if !self.outputs.contains_key(output_id) {
+            return Err(UpdateOutputSlateError::NotFound(output_id.clone()));
+        }
+        match slate {
+            Some(slate) => self
+                .renderer
+                .set_output_slate(output_id.clone(), slate)
+                .map_err(|err| UpdateOutputSlateError::Slate(output_id.clone(), err)),
+            None => {
+                self.renderer.remove_output_slate(output_id);
+                Ok(())
+            }
+        }
+    }
+
     pub fn register_renderer(
         &self,
         transformation_spec: RendererSpec,
@@ -178,6 +611,53 @@ impl<Input: PipelineInput, Output: PipelineOutput> Pipeline<Input, Output> {
         Ok(())
     }
 
+    /// Registers the current frame of the node (or input) as an image with
+    /// `image_id`, it can be used in the scene like any other registered image.
+    pub fn freeze_frame(
+        &self,
+        image_id: RendererId,
+        target: &SnapshotTarget,
+    ) -> Result<(), FreezeFrameError> {
+        self.renderer.freeze_frame(image_id, target)
+    }
+
+    pub fn update_shader(&self, spec: ShaderSpec) -> Result<(), UpdateShaderError> {
+        self.renderer.update_shader(spec)
+    }
+
+    pub fn preload_image(
+        &self,
+        image_id: &RendererId,
+        resolutions: &[Option<Resolution>],
+    ) -> Result<(), PreloadImageError> {
+        self.renderer.preload_image(image_id, resolutions)
+    }
+
+    pub fn dispatch_web_renderer_event(
+        &self,
+        instance_id: &RendererId,
+        event: WebRendererEvent,
+    ) -> Result<(), WebRendererEventError> {
+        self.renderer
+            .dispatch_web_renderer_event(instance_id, event)
+    }
+
+    pub fn execute_web_renderer_javascript(
+        &self,
+        instance_id: &RendererId,
+        code: String,
+    ) -> Result<(), WebRendererEventError> {
+        self.renderer
+            .execute_web_renderer_javascript(instance_id, code)
+    }
+
+    pub fn take_web_renderer_messages(
+        &self,
+        instance_id: &RendererId,
+    ) -> Result<Vec<String>, WebRendererEventError> {
+        self.renderer.take_web_renderer_messages(instance_id)
+    }
+
     pub fn unregister_renderer(
         &self,
         renderer_id: &RendererId,
@@ -187,14 +667,81 @@ impl<Input: PipelineInput, Output: PipelineOutput> Pipeline<Input, Output> {
             .unregister_renderer(renderer_id, registry_type)
     }
 
+    /// Checks if the scene could be applied with currently registered inputs, outputs
+    /// and renderers without changing anything.
+    pub fn validate_scene(&self, scene_spec: &SceneSpec) -> Result<(), UpdateSceneError> {
+        scene_spec
+            .validate(
+                &self.registered_input_ids(),
+                &self.outputs.lock().keys().map(|i| &i.0).collect(),
+            )
+            .map_err(UpdateSceneError::InvalidSpec)?;
+        self.renderer.validate_scene(scene_spec)
+    }
+
     pub fn update_scene(&mut self, scene_spec: Arc<SceneSpec>) -> Result<(), UpdateSceneError> {
         scene_spec
             .validate(
-                &self.inputs.keys().map(|i| &i.0).collect(),
+                &self.registered_input_ids(),
                 &self.outputs.lock().keys().map(|i| &i.0).collect(),
             )
             .map_err(UpdateSceneError::InvalidSpec)?;
-        self.renderer.update_scene(scene_spec)
+        self.renderer.update_scene(scene_spec)?;
+
+        if self.auto_start && !self.is_started {
+            self.start();
+        }
+        Ok(())
+    }
+
+    /// Scenes of the timeline entries are applied by the render thread, following
+    /// PTS of rendered frames. Replaces the running timeline. Scene set with
+    /// `update_scene` is shown until the next entry starts.
+    pub fn start_timeline(&mut self, spec: TimelineSpec) -> Result<(), TimelineError> {
+        let timeline = Timeline::new(spec)?;
+        for (entry_index, scene) in timeline.scenes() {
+            self.validate_scene(scene)
+                .map_err(|err| TimelineError::InvalidScene(entry_index, err))?;
+        }
+        *self.timeline.lock().unwrap() = Some(timeline);
+
+        if self.auto_start && !self.is_started {
+            self.start();
+        }
+        Ok(())
+    }
+
+    /// The scene of the current entry is kept.
+    pub fn stop_timeline(&self) -> Result<(), TimelineError> {
+        match self.timeline.lock().unwrap().take() {
+            Some(_) => Ok(()),
+            None => Err(TimelineError::NotRunning),
+        }
+    }
+
+    /// `seek` is relative to the start of the timeline.
+    pub fn update_timeline_playback(
+        &self,
+        paused: Option<bool>,
+        seek: Option<Duration>,
+    ) -> Result<(), TimelineError> {
+        let mut guard = self.timeline.lock().unwrap();
+        let timeline = guard.as_mut().ok_or(TimelineError::NotRunning)?;
+        if let Some(paused) = paused {
+            timeline.set_paused(paused);
+        }
+        if let Some(seek) = seek {
+            timeline.seek(seek);
+        }
+        Ok(())
+    }
+
+    pub fn timeline_status(&self) -> Option<TimelineStatus> {
+        self.with_timeline(|timeline| timeline.map(Timeline::status))
+    }
+
+    fn with_timeline<R>(&self, f: impl FnOnce(Option<&Timeline>) -> R) -> R {
+        f(self.timeline.lock().unwrap().as_ref())
     }
 
     pub fn start(&mut self) {
@@ -202,38 +749,90 @@ impl<Input: PipelineInput, Output: PipelineOutput> Pipeline<Input, Output> {
             error!("Pipeline already started.");
             return;
         }
-        let (frames_sender, frames_receiver) = unbounded();
-        let renderer = self.renderer.clone();
+        self.is_started = true;
+        if let Some(pre_start_stop) = self.pre_start_stop.take() {
+            // Fails only if pre-start thread was never spawned.
+            let _ = pre_start_stop.send(());
+        }
+        let (frames_sender, render_queue) =
+            RenderQueue::new(self.backpressure, self.render_stats.clone());
+        let mut renderer = self.renderer.clone();
+        let render_lock = self.render_lock.clone();
+        let timeline = self.timeline.clone();
         let outputs = self.outputs.clone();
+        let latency_stats = self.latency_stats.clone();
+        let events = self.events.clone();
+        let mut watchdog = RenderWatchdog::new(self.render_failure_policy, events.clone());
 
         self.queue.start(frames_sender);
 
         thread::spawn(move || {
-            for input_frames in frames_receiver.iter() {
-                if frames_receiver.len() > 20 {
-                    warn!("Dropping frame: render queue is too long.",);
+            let mut is_stopped = false;
+            for input_frames in render_queue {
+                // Frames are still received after rendering is stopped, so the queue
+                // is not blocked.
+                if is_stopped {
                     continue;
                 }
-
-                let output = renderer.render(input_frames);
-                let Ok(output_frames) = output else {
-                    error!(
-                        "Error while rendering: {}",
-                        ErrorStack::new(&output.unwrap_err()).into_string()
-                    );
-                    continue;
-                };
-
-                for (id, frame) in output_frames.frames {
-                    let output = outputs.lock().get(&id).map(Clone::clone);
-                    let Some(output) = output else {
-                        error!("no output with id {}", &id);
+                let pts = input_frames.pts;
+                let output = panic::catch_unwind(AssertUnwindSafe(|| {
+                    // Lock is poisoned if a previous frame panicked, rendering is
+                    // attempted anyway.
+                    let _guard = render_lock.lock().unwrap_or_else(PoisonError::into_inner);
+                    apply_timeline_entry(&timeline, &mut renderer, &events, pts);
+                    latency_stats.on_render_started(pts);
+                    let render_start = Instant::now();
+                    let output = renderer.render(input_frames);
+                    latency_stats.on_rendered(render_start.elapsed());
+                    output
+                }));
+                let error = match output {
+                    Ok(Ok(output_frames)) => {
+                        watchdog.on_success();
+                        send_rendered_frames(&outputs, output_frames);
                         continue;
-                    };
+                    }
+                    Ok(Err(err)) => ErrorStack::new(&err).into_string(),
+                    Err(panic) => {
+                        let message = panic_message(panic.as_ref());
+                        error!("Renderer panicked: {message}");
+                        events.push(PipelineEvent::RenderPanicked {
+                            message: message.clone(),
+                        });
+                        format!("Renderer panicked: {message}")
+                    }
+                };
 
-                    output.send_frame(frame);
+                match watchdog.on_failure(error) {
+                    RenderFailureOutcome::SkipFrame => {}
+                    RenderFailureOutcome::SendSlate(slate) => {
+                        for output in outputs.lock().values() {
+                            output.send_frame(slate.frame(
+                                output.resolution(),
+                                output.format(),
+                                pts,
+                            ));
+                        }
+                    }
+                    RenderFailureOutcome::StopRendering => {
+                        error!("Rendering stopped by the render failure policy. Closing outputs.");
+                        for output in outputs.lock().values() {
+                            output.finish();
+                        }
+                        events.push(PipelineEvent::RenderingStopped);
+                        is_stopped = true;
+                    }
                 }
             }
+            if is_stopped {
+                return;
+            }
+
+            // Queue stops sending frames only in offline mode, when all inputs ended.
+            info!("Rendering finished. Closing outputs.");
+            for output in outputs.lock().values() {
+                output.finish();
+            }
         });
     }
 
@@ -241,6 +840,23 @@ impl<Input: PipelineInput, Output: PipelineOutput> Pipeline<Input, Output> {
         self.inputs.iter().map(|(id, node)| (id, node.input()))
     }
 
+    pub fn test_patterns(&self) -> impl Iterator<Item = &InputId> {
+        self.test_patterns.iter()
+    }
+
+    /// Inputs that delivered a frame within `stream_fallback_timeout`. Test
+    /// patterns are always live.
+    pub fn live_inputs(&self) -> impl Iterator<Item = &InputId> {
+        self.inputs
+            .keys()
+            .filter(|input_id| {
+                self.queue
+                    .time_since_last_frame(input_id)
+                    .is_some_and(|elapsed| elapsed <= self.stream_fallback_timeout)
+            })
+            .chain(self.test_patterns.iter())
+    }
+
     pub fn with_outputs<F, R>(&self, f: F) -> R
     where
         F: Fn(OutputIterator<'_, Output>) -> R,
@@ -250,6 +866,55 @@ impl<Input: PipelineInput, Output: PipelineOutput> Pipeline<Input, Output> {
     }
 }
 
+fn send_rendered_frames<Output: PipelineOutput>(
+    outputs: &OutputRegistry<Encoder<Output>>,
+    output_frames: FrameSet<OutputId>,
+) {
+    for (id, frame) in output_frames.frames {
+        let output = outputs.lock().get(&id).map(Clone::clone);
+        let Some(output) = output else {
+            error!("no output with id {}", &id);
+            continue;
+        };
+
+        output.send_frame(frame);
+    }
+}
+
+/// Applies the scene of the timeline entry that starts at the PTS, if any.
+fn apply_timeline_entry(
+    timeline: &Mutex<Option<Timeline>>,
+    renderer: &mut Renderer,
+    events: &PipelineEvents,
+    pts: Duration,
+) {
+    let entry = match timeline.lock().unwrap().as_mut() {
+        Some(timeline) => timeline.advance(pts),
+        None => None,
+    };
+    let Some((entry_index, scene)) = entry else {
+        return;
+    };
+    match renderer.update_scene(scene) {
+        Ok(()) => events.push(PipelineEvent::TimelineEntryStarted { entry_index }),
+        Err(err) => {
+            let error = ErrorStack::new(&err).into_string();
+            error!("Failed to apply scene of timeline entry {entry_index}: {error}");
+            events.push(PipelineEvent::TimelineEntryFailed { entry_index, error });
+        }
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    if let Some(message) = panic.downcast_ref::<String>() {
+        return message.clone();
+    }
+    String::from("unknown panic payload")
+}
+
 struct OutputRegistry<T>(Arc<Mutex<HashMap<OutputId, Arc<T>>>>);
 
 impl<T> Clone for OutputRegistry<T> {
@@ -271,6 +936,10 @@ impl<T> OutputRegistry<T> {
         self.0.lock().unwrap().insert(key, value)
     }
 
+    fn get(&self, key: &OutputId) -> Option<Arc<T>> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+
     fn remove(&self, key: &OutputId) -> Option<Arc<T>> {
         self.0.lock().unwrap().remove(key)
     }
@@ -297,3 +966,172 @@ impl<'a, Output: PipelineOutput> Iterator for OutputIterator<'a, Output> {
         self.inner_iter.next().map(|(id, node)| (id, node.output()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    /// Pipeline in these tests has no inputs.
+    enum NoInput {}
+
+    impl PipelineInput for NoInput {
+        type Opts = ();
+        type PacketIterator = std::iter::Empty<Packet>;
+
+        fn new(
+            _opts: Self::Opts,
+        ) -> Result<(Self, Self::PacketIterator), Box<dyn std::error::Error + Send + Sync + 'static>>
+        {
+            Err("inputs are not supported".into())
+        }
+
+        fn decoder_parameters(&self) -> decoder::DecoderParameters {
+            match *self {}
+        }
+
+        fn sender_clock(&self) -> Option<decoder::SenderClock> {
+            match *self {}
+        }
+    }
+
+    /// Drops encoded packets.
+    #[derive(Clone)]
+    struct NullOutput;
+
+    impl PipelineOutput for NullOutput {
+        type Opts = ();
+        type Context = ();
+
+        fn send_packet(&self, _context: &mut Self::Context, _packet: Packet) {}
+
+        fn new(
+            _opts: Self::Opts,
+            _codec: ffmpeg_next::Codec,
+        ) -> Result<(Self, Self::Context), Box<dyn std::error::Error + Send + Sync + 'static>>
+        {
+            Ok((NullOutput, ()))
+        }
+    }
+
+    /// Pipelines need a GPU and an H264 encoder, so like snapshot tests they
+    /// are not created on CI.
+    fn new_pipeline(auto_start: bool) -> Option<Pipeline<NoInput, NullOutput>> {
+        if env::var("CI").is_ok() {
+            return None;
+        }
+        let (pipeline, _event_loop) = Pipeline::new(Options {
+            framerate: Framerate { num: 30, den: 1 },
+            stream_fallback_timeout: Duration::from_secs(1),
+            web_renderer: WebRendererOptions {
+                init: false,
+                disable_gpu: false,
+            },
+            gpu: GpuOptions::default(),
+            auto_start,
+            pre_start_output: PreStartOutput::default(),
+            render_failure_policy: RenderFailurePolicy::default(),
+            queue: queue::Options::default(),
+            backpressure: Backpressure::default(),
+        })
+        .unwrap();
+        Some(pipeline)
+    }
+
+    fn output_id(id: &str) -> OutputId {
+        OutputId(NodeId(id.into()))
+    }
+
+    fn register_output(pipeline: &Pipeline<NoInput, NullOutput>, id: &str) {
+        pipeline
+            .register_output(
+                output_id(id),
+                OutputOptions {
+                    receiver_options: (),
+                    encoder_settings: EncoderSettings::default(),
+                    resolution: Resolution {
+                        width: 640,
+                        height: 360,
+                    },
+                    scale_to_resolution: false,
+                    key_of: None,
+                    format: FrameFormat::default(),
+                    watermark: None,
+                    captions: None,
+                    timecode_sei: false,
+                    scte35: false,
+                },
+            )
+            .unwrap();
+    }
+
+    fn is_running(pipeline: &Pipeline<NoInput, NullOutput>, id: &str) -> bool {
+        pipeline.outputs.get(&output_id(id)).unwrap().is_running()
+    }
+
+    #[test]
+    fn start_output_that_is_already_running() {
+        let Some(pipeline) = new_pipeline(false) else {
+            return;
+        };
+        register_output(&pipeline, "out");
+        assert!(is_running(&pipeline, "out"));
+
+        pipeline.start_output(&output_id("out")).unwrap();
+        assert!(is_running(&pipeline, "out"));
+        assert!(matches!(
+            pipeline.start_output(&output_id("missing")),
+            Err(StartOutputError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn stop_output_that_is_not_running() {
+        let Some(pipeline) = new_pipeline(false) else {
+            return;
+        };
+        register_output(&pipeline, "out");
+
+        pipeline
+            .stop_output(&output_id("out"), StoppedOutputFrames::Nothing)
+            .unwrap();
+        assert!(!is_running(&pipeline, "out"));
+        pipeline
+            .stop_output(&output_id("out"), StoppedOutputFrames::FreezeFrame)
+            .unwrap();
+        assert!(!is_running(&pipeline, "out"));
+        assert!(matches!(
+            pipeline.stop_output(&output_id("missing"), StoppedOutputFrames::Nothing),
+            Err(StopOutputError::NotFound(_))
+        ));
+
+        pipeline.start_output(&output_id("out")).unwrap();
+        assert!(is_running(&pipeline, "out"));
+    }
+
+    #[test]
+    fn update_scene_starts_pipeline_only_with_auto_start() {
+        let empty_scene = || {
+            Arc::new(SceneSpec {
+                nodes: vec![],
+                outputs: vec![],
+            })
+        };
+
+        let Some(mut pipeline) = new_pipeline(false) else {
+            return;
+        };
+        pipeline.update_scene(empty_scene()).unwrap();
+        assert!(!pipeline.is_started);
+
+        let Some(mut pipeline) = new_pipeline(true) else {
+            return;
+        };
+        pipeline.update_scene(empty_scene()).unwrap();
+        assert!(pipeline.is_started);
+        // Scene updates after the start don't start the pipeline again.
+        pipeline.update_scene(empty_scene()).unwrap();
+        assert!(pipeline.is_started);
+    }
+}
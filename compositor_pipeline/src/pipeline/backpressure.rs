@@ -0,0 +1,132 @@
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicU64, Ordering},
+    sync::Arc,
+};
+
+use compositor_common::scene::InputId;
+use compositor_render::FrameSet;
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
+use log::warn;
+
+/// Max number of frame sets waiting for rendering before backpressure kicks in.
+const MAX_RENDER_QUEUE_LEN: usize = 20;
+
+/// What happens when rendering is slower than the queue produces frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backpressure {
+    /// Queue stops producing frames until the renderer catches up. Inputs are
+    /// not read in the meantime, so it's only suitable for non-realtime inputs
+    /// (e.g. files) where dropping frames is unacceptable.
+    Block,
+    /// Oldest waiting frames are dropped, so outputs stay close to realtime.
+    #[default]
+    DropOldest,
+    /// Newly produced frames are dropped until the renderer catches up.
+    DropNewest,
+    /// Every n-th waiting frame is rendered, where n grows with the length of
+    /// the render queue. Output framerate degrades gradually instead of skipping
+    /// long runs of frames.
+    Adaptive,
+}
+
+/// Counters of the render loop, shared with the pipeline.
+#[derive(Debug, Default)]
+pub struct RenderStats {
+    rendered_frames: AtomicU64,
+    dropped_frames: AtomicU64,
+}
+
+impl RenderStats {
+    pub fn rendered_frames(&self) -> u64 {
+        self.rendered_frames.load(Ordering::Relaxed)
+    }
+
+    /// Frames dropped because rendering was too slow.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames.load(Ordering::Relaxed)
+    }
+}
+
+/// Frame sets produced by the queue and waiting for rendering.
+pub(super) struct RenderQueue {
+    receiver: Receiver<FrameSet<InputId>>,
+    pending: VecDeque<FrameSet<InputId>>,
+    backpressure: Backpressure,
+    stats: Arc<RenderStats>,
+}
+
+impl RenderQueue {
+    pub fn new(
+        backpressure: Backpressure,
+        stats: Arc<RenderStats>,
+    ) -> (Sender<FrameSet<InputId>>, Self) {
+        let (sender, receiver) = match backpressure {
+            Backpressure::Block => bounded(MAX_RENDER_QUEUE_LEN),
+            _ => unbounded(),
+        };
+        let render_queue = Self {
+            receiver,
+            pending: VecDeque::new(),
+            backpressure,
+            stats,
+        };
+        (sender, render_queue)
+    }
+
+    fn next_frame_set(&mut self) -> Option<FrameSet<InputId>> {
+        if self.backpressure == Backpressure::Block {
+            // Channel is bounded, so the queue thread is blocked when it's full.
+            return self.receiver.recv().ok();
+        }
+
+        if self.pending.is_empty() {
+            self.pending.push_back(self.receiver.recv().ok()?);
+        }
+        self.pending.extend(self.receiver.try_iter());
+
+        let excess = self.pending.len().saturating_sub(MAX_RENDER_QUEUE_LEN);
+        let dropped = match self.backpressure {
+            Backpressure::DropOldest | Backpressure::Block => {
+                self.pending.drain(..excess);
+                excess
+            }
+            Backpressure::DropNewest => {
+                self.pending.truncate(MAX_RENDER_QUEUE_LEN);
+                excess
+            }
+            Backpressure::Adaptive => {
+                // Render every frame while the queue is less than half full, every
+                // second frame until it's full, and so on.
+                let stride = 1 + self.pending.len() / (MAX_RENDER_QUEUE_LEN / 2);
+                let frame_set = self.pending.pop_front();
+                let dropped = (stride - 1).min(self.pending.len());
+                self.pending.drain(..dropped);
+                self.on_dropped(dropped);
+                return frame_set;
+            }
+        };
+        self.on_dropped(dropped);
+        self.pending.pop_front()
+    }
+
+    fn on_dropped(&self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        warn!("Dropped {count} frames: render queue is too long.");
+        self.stats
+            .dropped_frames
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+}
+
+impl Iterator for RenderQueue {
+    type Item = FrameSet<InputId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame_set = self.next_frame_set()?;
+        self.stats.rendered_frames.fetch_add(1, Ordering::Relaxed);
+        Some(frame_set)
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, Weak},
+};
+
+use compositor_common::scene::InputId;
+use log::debug;
+
+/// Source of closed captions embedded in the encoded stream of an output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputCaptions {
+    /// Caption data decoded from the input stream is copied to the output.
+    Input(InputId),
+    /// Text is sent with [`crate::Pipeline::send_captions`].
+    Api,
+}
+
+/// Max number of cc_data constructs of each kind waiting for a frame. Caption data
+/// arriving faster than frames are encoded (e.g. input framerate higher than
+/// output framerate) is dropped above it.
+const MAX_QUEUED_CC_DATA: usize = 600;
+
+/// DTVCC (CEA-708) constructs sent with a single frame, leaves room for one
+/// CEA-608 pair of each field within the 20 constructs allowed at 29.97 fps.
+const MAX_DTVCC_PER_FRAME: usize = 18;
+
+const CC_TYPE_NTSC_FIELD_1: u8 = 0;
+const CC_TYPE_NTSC_FIELD_2: u8 = 1;
+const CC_VALID: u8 = 0b100;
+/// Reserved bits set in the first byte of each cc_data construct.
+const CC_MARKER_BITS: u8 = 0b1111_1000;
+
+/// CEA-708 `cc_data` constructs (3 bytes each) waiting to be embedded in
+/// encoded frames. CEA-608 pairs can only be sent one per field per frame, so
+/// they are queued separately from DTVCC packet data.
+#[derive(Debug, Clone, Default)]
+pub struct CaptionsQueue(Arc<Mutex<QueuedCcData>>);
+
+#[derive(Debug, Default)]
+struct QueuedCcData {
+    field_1: VecDeque<[u8; 3]>,
+    field_2: VecDeque<[u8; 3]>,
+    dtvcc: VecDeque<[u8; 3]>,
+}
+
+impl CaptionsQueue {
+    /// Queues `cc_data` in the format of A53 side data: a list of 3 byte
+    /// constructs. Invalid (padding) constructs are skipped.
+    pub fn push_cc_data(&self, cc_data: &[u8]) {
+        let mut queued = self.0.lock().unwrap();
+        for construct in cc_data.chunks_exact(3) {
+            if construct[0] & CC_VALID == 0 {
+                continue;
+            }
+            let construct = [construct[0], construct[1], construct[2]];
+            let queue = match construct[0] & 0b11 {
+                CC_TYPE_NTSC_FIELD_1 => &mut queued.field_1,
+                CC_TYPE_NTSC_FIELD_2 => &mut queued.field_2,
+                _ => &mut queued.dtvcc,
+            };
+            if queue.len() >= MAX_QUEUED_CC_DATA {
+                debug!("Dropping caption data: too much data waiting for frames.");
+                queue.pop_front();
+            }
+            queue.push_back(construct);
+        }
+    }
+
+    /// Queues text as CEA-608 roll-up captions on the CC1 channel.
+    pub fn push_text(&self, text: &str) {
+        let cc_data: Vec<u8> = cea608_roll_up(text)
+            .into_iter()
+            .flat_map(|[byte_1, byte_2]| {
+                [
+                    CC_MARKER_BITS | CC_VALID | CC_TYPE_NTSC_FIELD_1,
+                    byte_1,
+                    byte_2,
+                ]
+            })
+            .collect();
+        self.push_cc_data(&cc_data);
+    }
+
+    /// cc_data that should be attached to the next encoded frame, empty if there
+    /// is nothing to send.
+    pub fn pop_frame(&self) -> Vec<u8> {
+        let mut queued = self.0.lock().unwrap();
+        let QueuedCcData {
+            field_1,
+            field_2,
+            dtvcc,
+        } = &mut *queued;
+        let dtvcc_count = usize::min(dtvcc.len(), MAX_DTVCC_PER_FRAME);
+        field_1
+            .pop_front()
+            .into_iter()
+            .chain(field_2.pop_front())
+            .chain(dtvcc.drain(..dtvcc_count))
+            .flatten()
+            .collect()
+    }
+
+    fn downgrade(&self) -> Weak<Mutex<QueuedCcData>> {
+        Arc::downgrade(&self.0)
+    }
+}
+
+/// Queues of outputs that copy captions of an input. Outputs are removed from
+/// the list when they are unregistered.
+#[derive(Debug, Clone, Default)]
+pub struct CaptionsSubscribers(Arc<Mutex<Vec<Weak<Mutex<QueuedCcData>>>>>);
+
+impl CaptionsSubscribers {
+    pub fn subscribe(&self, queue: &CaptionsQueue) {
+        self.0.lock().unwrap().push(queue.downgrade());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let mut subscribers = self.0.lock().unwrap();
+        subscribers.retain(|queue| queue.strong_count() > 0);
+        subscribers.is_empty()
+    }
+
+    pub fn push_cc_data(&self, cc_data: &[u8]) {
+        let mut subscribers = self.0.lock().unwrap();
+        subscribers.retain(|queue| match queue.upgrade() {
+            Some(queue) => {
+                CaptionsQueue(queue).push_cc_data(cc_data);
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+/// Max number of characters in a row of CEA-608 captions.
+const CEA608_ROW_LENGTH: usize = 32;
+
+// Control codes of the CC1 channel (before adding parity).
+const RESUME_ROLL_UP_2: [u8; 2] = [0x14, 0x25];
+const CARRIAGE_RETURN: [u8; 2] = [0x14, 0x2D];
+/// Preamble address code: row 15, column 0, white text.
+const PAC_ROW_15: [u8; 2] = [0x14, 0x70];
+
+/// Encodes text as byte pairs (with parity) of CEA-608 roll-up captions with 2
+/// visible rows. Text is wrapped at 32 characters, each row is scrolled in with
+/// a carriage return. Control codes are doubled as required by the standard.
+fn cea608_roll_up(text: &str) -> Vec<[u8; 2]> {
+    let mut pairs = vec![RESUME_ROLL_UP_2, RESUME_ROLL_UP_2];
+    for row in wrap_rows(text) {
+        pairs.extend([CARRIAGE_RETURN, CARRIAGE_RETURN, PAC_ROW_15, PAC_ROW_15]);
+        let chars: Vec<u8> = row.chars().filter_map(cea608_char).collect();
+        pairs.extend(
+            chars
+                .chunks(2)
+                .map(|chunk| [chunk[0], chunk.get(1).copied().unwrap_or(0)]),
+        );
+    }
+    pairs
+        .into_iter()
+        .map(|[byte_1, byte_2]| [with_odd_parity(byte_1), with_odd_parity(byte_2)])
+        .collect()
+}
+
+fn wrap_rows(text: &str) -> Vec<String> {
+    let mut rows = Vec::new();
+    for line in text.lines() {
+        let mut row = String::new();
+        for word in line.split_whitespace() {
+            let row_len = row.chars().count();
+            let word_len = word.chars().count();
+            if row_len > 0 && row_len + 1 + word_len > CEA608_ROW_LENGTH {
+                rows.push(std::mem::take(&mut row));
+            }
+            if !row.is_empty() {
+                row.push(' ');
+            }
+            row.push_str(word);
+        }
+        if !row.is_empty() {
+            rows.push(row);
+        }
+    }
+    rows
+}
+
+/// Code of the character in the CEA-608 basic character set. A few ASCII codes
+/// are used for accented letters, ASCII characters replaced by them and
+/// characters outside of the set are skipped.
+fn cea608_char(char: char) -> Option<u8> {
+    match char {
+        'á' => Some(0x2A),
+        'é' => Some(0x5C),
+        'í' => Some(0x5E),
+        'ó' => Some(0x5F),
+        'ú' => Some(0x60),
+        'ç' => Some(0x7B),
+        '÷' => Some(0x7C),
+        'Ñ' => Some(0x7D),
+        'ñ' => Some(0x7E),
+        '*' | '\\' | '^' | '_' | '`' | '{' | '|' | '}' | '~' => None,
+        ' '..='\x7E' => Some(char as u8),
+        _ => None,
+    }
+}
+
+fn with_odd_parity(byte: u8) -> u8 {
+    let byte = byte & 0x7F;
+    match byte.count_ones() % 2 {
+        0 => byte | 0x80,
+        _ => byte,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roll_up_text() {
+        let pairs = cea608_roll_up("Hi!");
+
+        assert_eq!(
+            pairs,
+            vec![
+                [0x94, 0x25],
+                [0x94, 0x25],
+                [0x94, 0xAD],
+                [0x94, 0xAD],
+                [0x94, 0x70],
+                [0x94, 0x70],
+                [0xC8, 0xE9],
+                [0xA1, 0x80],
+            ]
+        );
+    }
+
+    #[test]
+    fn rows_are_wrapped() {
+        let rows = wrap_rows("The quick brown fox jumps over the lazy dog\nBye");
+
+        assert_eq!(
+            rows,
+            vec!["The quick brown fox jumps over", "the lazy dog", "Bye"]
+        );
+    }
+
+    #[test]
+    fn one_cea608_pair_per_field_is_sent_with_frame() {
+        let queue = CaptionsQueue::default();
+        queue.push_cc_data(&[
+            0xFC, 1, 2, 0xFC, 3, 4, 0xFD, 5, 6, 0xFA, 0, 0, 0xFF, 7, 8, 0xFE, 9, 10,
+        ]);
+
+        assert_eq!(
+            queue.pop_frame(),
+            vec![0xFC, 1, 2, 0xFD, 5, 6, 0xFF, 7, 8, 0xFE, 9, 10]
+        );
+        assert_eq!(queue.pop_frame(), vec![0xFC, 3, 4]);
+        assert!(queue.pop_frame().is_empty());
+    }
+}
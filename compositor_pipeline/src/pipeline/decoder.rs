@@ -1,22 +1,41 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    slice,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
 
-use crate::queue::Queue;
+use crate::{error::RegisterInputError, queue::Queue};
 
-use super::PipelineInput;
+use super::{
+    captions::CaptionsSubscribers,
+    recording::{InputRecorder, RecordingOptions},
+    PipelineInput,
+};
 use compositor_common::{
-    frame::YuvData,
+    frame::{ColorSpace, FrameData, FrameFormat, Nv12Data, PixelFormat, YuvData},
     scene::{InputId, Resolution},
     Frame,
 };
 use ffmpeg_next::{
     codec::{Context, Id},
-    frame::Video,
+    color,
+    format::Pixel,
+    frame::{side_data, Video},
     media::Type,
+    software::scaling,
 };
 use log::warn;
 
+use self::preprocess::Preprocessor;
+
+pub use preprocess::PreprocessStep;
+
+mod preprocess;
+
 pub struct Decoder<Input: PipelineInput> {
     input: Input,
+    /// Outputs that embed closed captions of the input in their streams.
+    captions: CaptionsSubscribers,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -24,22 +43,89 @@ pub struct DecoderParameters {
     pub codec: Codec,
 }
 
+/// Options provided when input is registered, unlike [`DecoderParameters`] that
+/// describe the stream.
+#[derive(Debug, Clone, Default)]
+pub struct DecoderOptions {
+    /// Pixel format of frames passed to the renderer. Decoded frames are converted
+    /// if their pixel format is different, except NV12 and RGBA frames that the
+    /// renderer converts on the GPU.
+    pub pixel_format: PixelFormat,
+    /// Color space of the stream. If not set, it is read from the stream metadata
+    /// of each decoded frame.
+    pub color_space: Option<ColorSpace>,
+    /// Applied in order to decoded frames before they are converted to `pixel_format`.
+    pub preprocess: Vec<PreprocessStep>,
+    /// Copy of the input recorded to a separate file.
+    pub recording: Option<RecordingOptions>,
+}
+
+/// Sender wall clock time of the packet with PTS=0, learned from RTCP sender
+/// reports. Input sets it when the first report arrives, decoder uses it to
+/// align the input with other inputs in the queue.
+#[derive(Debug, Clone, Default)]
+pub struct SenderClock(Arc<Mutex<Option<SystemTime>>>);
+
+impl SenderClock {
+    pub fn set(&self, pts_zero_time: SystemTime) {
+        *self.0.lock().unwrap() = Some(pts_zero_time);
+    }
+
+    pub fn get(&self) -> Option<SystemTime> {
+        *self.0.lock().unwrap()
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Codec {
     H264,
+    Mjpeg,
+    /// Uncompressed frames, e.g. produced by a local camera. Decoder needs to know
+    /// the exact layout of the data because it can't be inferred from the packets.
+    RawVideo {
+        pixel_format: Pixel,
+        resolution: Resolution,
+    },
 }
 
 #[derive(Debug, thiserror::Error)]
 enum DecoderError {
     #[error("Error converting frame: {0}")]
     FrameConversionError(String),
+
+    #[error("Failed to convert pixel format of the frame: {0}")]
+    PixelFormatConversionError(#[from] ffmpeg_next::Error),
+
+    #[error("Failed to preprocess the frame: {0}")]
+    PreprocessingError(#[source] ffmpeg_next::Error),
 }
 
 impl<Input: PipelineInput> Decoder<Input> {
-    pub fn new(queue: Arc<Queue>, input_options: Input::Opts, input_id: InputId) -> Self {
-        let (input, packets) = Input::new(input_options);
+    pub fn new(
+        queue: Arc<Queue>,
+        input_options: Input::Opts,
+        decoder_options: DecoderOptions,
+        input_id: InputId,
+    ) -> Result<Self, RegisterInputError> {
+        let (input, packets) = Input::new(input_options)
+            .map_err(|err| RegisterInputError::DecoderError(input_id.clone(), err.into()))?;
 
         let parameters = input.decoder_parameters();
+        let sender_clock = input.sender_clock();
+        let recorder = decoder_options
+            .recording
+            .map(|recording| {
+                InputRecorder::new(
+                    input_id.clone(),
+                    recording,
+                    parameters,
+                    queue.output_framerate(),
+                )
+            })
+            .transpose()
+            .map_err(|err| RegisterInputError::RecordingError(input_id.clone(), err))?;
+        let captions = CaptionsSubscribers::default();
+        let thread_captions = captions.clone();
 
         std::thread::spawn(move || {
             let decoder = Context::from_parameters(parameters).unwrap();
@@ -47,35 +133,207 @@ impl<Input: PipelineInput> Decoder<Input> {
             let mut decoder = decoder.open_as(Into::<Id>::into(parameters.codec)).unwrap();
 
             let mut decoded_frame = ffmpeg_next::frame::Video::empty();
+            let mut converter = FormatConverter::new(decoder_options.pixel_format);
+            let mut preprocessor = Preprocessor::new(decoder_options.preprocess);
             let mut pts_offset = None;
-            for packet in packets {
-                decoder.send_packet(&packet).unwrap();
+            let mut is_synced_to_sender_clock = false;
+            // `None` at the end flushes frames buffered in the decoder.
+            for packet in packets.map(Some).chain([None]) {
+                match packet {
+                    Some(packet) => {
+                        if let Some(recorder) = &recorder {
+                            recorder.record_packet(&packet);
+                        }
+                        decoder.send_packet(&packet).unwrap()
+                    }
+                    None => decoder.send_eof().unwrap(),
+                }
 
                 while decoder.receive_frame(&mut decoded_frame).is_ok() {
-                    let frame = match frame_from_av(&mut decoded_frame, &mut pts_offset) {
-                        Ok(frame) => frame,
-                        Err(err) => {
-                            warn!("Error converting frame: {}", err);
-                            continue;
+                    if !thread_captions.is_empty() {
+                        if let Some(cc_data) = decoded_frame.side_data(side_data::Type::A53CC) {
+                            thread_captions.push_cc_data(cc_data.data());
+                        }
+                    }
+                    let input_color_space = decoder_options
+                        .color_space
+                        .unwrap_or_else(|| color_space_from_av(&decoded_frame));
+                    let preprocessed;
+                    let frames = match preprocessor.as_mut() {
+                        Some(preprocessor) => {
+                            match preprocessor.process(&decoded_frame, input_color_space) {
+                                Ok(frames) => {
+                                    preprocessed = frames;
+                                    preprocessed.as_slice()
+                                }
+                                Err(err) => {
+                                    warn!("{err}");
+                                    continue;
+                                }
+                            }
                         }
+                        None => slice::from_ref(&decoded_frame),
                     };
-                    queue.enqueue_frame(input_id.clone(), frame).unwrap();
+                    let color_space = preprocessor
+                        .as_ref()
+                        .and_then(Preprocessor::color_space)
+                        .unwrap_or(input_color_space);
+
+                    for frame in frames {
+                        let frame = match frame_from_av(
+                            frame,
+                            &mut converter,
+                            color_space,
+                            &mut pts_offset,
+                        ) {
+                            Ok(frame) => frame,
+                            Err(err) => {
+                                warn!("Error converting frame: {}", err);
+                                continue;
+                            }
+                        };
+                        if let Some(recorder) = &recorder {
+                            recorder.record_frame(&frame);
+                        }
+                        if let Err(err) = queue.enqueue_frame(input_id.clone(), frame) {
+                            // Input was unregistered while the decoder was still running.
+                            warn!("Stopping decoder: {err}");
+                            return;
+                        }
+
+                        if is_synced_to_sender_clock {
+                            continue;
+                        }
+                        let pts_zero_time = sender_clock
+                            .as_ref()
+                            .and_then(SenderClock::get)
+                            .zip(pts_offset)
+                            .map(|(time, pts_offset)| frame_pts_zero_time(time, pts_offset));
+                        if let Some(pts_zero_time) = pts_zero_time {
+                            queue.sync_input_to_sender_clock(&input_id, pts_zero_time);
+                            is_synced_to_sender_clock = true;
+                        }
+                    }
                 }
             }
+            queue.mark_input_ended(&input_id);
         });
 
-        Self { input }
+        Ok(Self { input, captions })
     }
 
     pub fn input(&self) -> &Input {
         &self.input
     }
+
+    pub fn captions(&self) -> &CaptionsSubscribers {
+        &self.captions
+    }
+}
+
+/// Converts decoded frames in any pixel format into the pixel format expected
+/// by the renderer. NV12 and RGBA frames are passed as is. Scaling context is created lazily and recreated only when
+/// format or resolution of the decoded frames changes.
+struct FormatConverter {
+    pixel_format: PixelFormat,
+    scaler: Option<scaling::Context>,
+    converted_frame: Video,
+}
+
+impl FormatConverter {
+    fn new(pixel_format: PixelFormat) -> Self {
+        Self {
+            pixel_format,
+            scaler: None,
+            converted_frame: Video::empty(),
+        }
+    }
+
+    fn target_pixel_format(&self) -> Pixel {
+        match self.pixel_format {
+            PixelFormat::Yuv420p => Pixel::YUV420P,
+            PixelFormat::Yuv420p10le => Pixel::YUV420P10LE,
+        }
+    }
+
+    fn convert<'a>(&'a mut self, decoded: &'a Video) -> Result<&'a Video, DecoderError> {
+        let target_pixel_format = self.target_pixel_format();
+        let is_supported = decoded.format() == target_pixel_format
+            || matches!(decoded.format(), Pixel::NV12 | Pixel::RGBA);
+        if is_supported {
+            return Ok(decoded);
+        }
+
+        let is_outdated = |scaler: &scaling::Context| {
+            let input = scaler.input();
+            input.format != decoded.format()
+                || input.width != decoded.width()
+                || input.height != decoded.height()
+        };
+        let scaler = match self.scaler.take() {
+            Some(scaler) if !is_outdated(&scaler) => scaler,
+            _ => scaling::Context::get(
+                decoded.format(),
+                decoded.width(),
+                decoded.height(),
+                target_pixel_format,
+                decoded.width(),
+                decoded.height(),
+                scaling::Flags::BILINEAR,
+            )?,
+        };
+        let scaler = self.scaler.insert(scaler);
+
+        scaler.run(decoded, &mut self.converted_frame)?;
+        self.converted_frame.set_pts(decoded.pts());
+        Ok(&self.converted_frame)
+    }
 }
 
-fn frame_from_av(decoded: &mut Video, pts_offset: &mut Option<i64>) -> Result<Frame, DecoderError> {
-    if decoded.format() != ffmpeg_next::format::pixel::Pixel::YUV420P {
-        panic!("only YUV420P is supported");
+/// Frame PTS is relative to the first decoded frame (`pts_offset` is the negated
+/// PTS of that frame), so its wall clock time is shifted from the packet one.
+fn frame_pts_zero_time(packet_pts_zero_time: SystemTime, pts_offset: i64) -> SystemTime {
+    let shift = Duration::from_secs_f64(pts_offset.unsigned_abs() as f64 / 90000.0);
+    if pts_offset < 0 {
+        packet_pts_zero_time + shift
+    } else {
+        packet_pts_zero_time - shift
     }
+}
+
+fn frame_from_av(
+    decoded: &Video,
+    converter: &mut FormatConverter,
+    color_space: ColorSpace,
+    pts_offset: &mut Option<i64>,
+) -> Result<Frame, DecoderError> {
+    let decoded = converter.convert(decoded)?;
+    let (data, pixel_format) = match decoded.format() {
+        // NV12 and RGBA frames are 8-bit, planar pixel format only describes their
+        // sample values.
+        Pixel::NV12 => (
+            FrameData::Nv12(Nv12Data {
+                y_plane: copy_plane_from_av(decoded, 0, 1),
+                uv_plane: copy_plane_from_av(decoded, 1, 2),
+            }),
+            PixelFormat::Yuv420p,
+        ),
+        Pixel::RGBA => (
+            FrameData::Rgba(copy_plane_from_av(decoded, 0, 4)),
+            PixelFormat::Yuv420p,
+        ),
+        _ => {
+            let bytes_per_sample = converter.pixel_format.bytes_per_sample();
+            (
+                FrameData::PlanarYuv(YuvData {
+                    y_plane: copy_plane_from_av(decoded, 0, bytes_per_sample),
+                    u_plane: copy_plane_from_av(decoded, 1, bytes_per_sample),
+                    v_plane: copy_plane_from_av(decoded, 2, bytes_per_sample),
+                }),
+                converter.pixel_format,
+            )
+        }
+    };
     let original_pts = decoded.pts();
     if let (Some(pts), None) = (decoded.pts(), &pts_offset) {
         *pts_offset = Some(-pts)
@@ -85,47 +343,66 @@ fn frame_from_av(decoded: &mut Video, pts_offset: &mut Option<i64>) -> Result<Fr
         .ok_or_else(|| DecoderError::FrameConversionError("missing pts".to_owned()))?;
     let pts = Duration::from_secs_f64(f64::max((pts as f64) / 90000.0, 0.0));
     Ok(Frame {
-        data: YuvData {
-            y_plane: copy_plane_from_av(decoded, 0),
-            u_plane: copy_plane_from_av(decoded, 1),
-            v_plane: copy_plane_from_av(decoded, 2),
-        },
+        data,
         resolution: Resolution {
             width: decoded.width().try_into().unwrap(),
             height: decoded.height().try_into().unwrap(),
         },
+        format: FrameFormat {
+            pixel_format,
+            color_space,
+        },
         pts,
     })
 }
 
-fn copy_plane_from_av(decoded: &Video, plane: usize) -> bytes::Bytes {
-    let mut output_buffer = bytes::BytesMut::with_capacity(
-        decoded.plane_width(plane) as usize * decoded.plane_height(plane) as usize,
-    );
+/// `bytes_per_pixel` is the size of all samples of a single pixel in the plane.
+fn copy_plane_from_av(decoded: &Video, plane: usize, bytes_per_pixel: usize) -> bytes::Bytes {
+    let row_size = decoded.plane_width(plane) as usize * bytes_per_pixel;
+    let mut output_buffer =
+        bytes::BytesMut::with_capacity(row_size * decoded.plane_height(plane) as usize);
 
     decoded
         .data(plane)
         .chunks(decoded.stride(plane))
-        .map(|chunk| &chunk[..decoded.plane_width(plane) as usize])
+        .map(|chunk| &chunk[..row_size])
         .for_each(|chunk| output_buffer.extend_from_slice(chunk));
 
     output_buffer.freeze()
 }
 
+/// Streams without color space metadata are treated as BT.601, same as
+/// streams that use color space the renderer doesn't support.
+fn color_space_from_av(decoded: &Video) -> ColorSpace {
+    match (
+        decoded.color_space(),
+        decoded.color_transfer_characteristic(),
+    ) {
+        (color::Space::BT709, _) => ColorSpace::Bt709,
+        (color::Space::BT2020NCL, color::TransferCharacteristic::SMPTE2084) => ColorSpace::Bt2020Pq,
+        _ => ColorSpace::Bt601,
+    }
+}
+
 impl From<DecoderParameters> for ffmpeg_next::codec::Parameters {
     fn from(parameters: DecoderParameters) -> Self {
-        match parameters.codec {
-            Codec::H264 => {
-                let mut parameters = ffmpeg_next::codec::Parameters::new();
-                unsafe {
-                    let parameters = &mut *parameters.as_mut_ptr();
-
-                    parameters.codec_type = Type::Video.into();
-                    parameters.codec_id = Id::H264.into();
-                };
-                parameters
+        let mut av_parameters = ffmpeg_next::codec::Parameters::new();
+        unsafe {
+            let av_parameters = &mut *av_parameters.as_mut_ptr();
+
+            av_parameters.codec_type = Type::Video.into();
+            av_parameters.codec_id = Id::from(parameters.codec).into();
+            if let Codec::RawVideo {
+                pixel_format,
+                resolution,
+            } = parameters.codec
+            {
+                av_parameters.format = ffmpeg_next::ffi::AVPixelFormat::from(pixel_format) as i32;
+                av_parameters.width = resolution.width as i32;
+                av_parameters.height = resolution.height as i32;
             }
-        }
+        };
+        av_parameters
     }
 }
 
@@ -133,6 +410,8 @@ impl From<Codec> for ffmpeg_next::codec::Id {
     fn from(codec: Codec) -> Self {
         match codec {
             Codec::H264 => Id::H264,
+            Codec::Mjpeg => Id::MJPEG,
+            Codec::RawVideo { .. } => Id::RAWVIDEO,
         }
     }
 }
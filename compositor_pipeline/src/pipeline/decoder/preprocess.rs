@@ -0,0 +1,154 @@
+use std::mem;
+
+use compositor_common::{frame::ColorSpace, scene::Resolution};
+use ffmpeg_next::{filter, format::Pixel, frame::Video};
+
+use super::DecoderError;
+
+/// Step applied to decoded frames before they are passed to the queue, so the
+/// work is done once per input instead of in every node that uses it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreprocessStep {
+    /// Frames larger than `max_resolution` are downscaled to fit it, the aspect
+    /// ratio is preserved. Smaller frames are not changed.
+    Scale { max_resolution: Resolution },
+    /// Spatial and temporal denoising, `strength` is the luma spatial strength of
+    /// the hqdn3d filter (4.0 is its default).
+    Denoise { strength: f32 },
+    /// Frames flagged as interlaced are deinterlaced, progressive frames are passed
+    /// through. Output framerate is the same as the input framerate.
+    Deinterlace,
+    /// Converts primaries, transfer characteristics, matrix and range of frames.
+    /// Converting to BT.2020 PQ is not supported.
+    ConvertColorSpace(ColorSpace),
+}
+
+/// Input parameters the filter graph was created for. Graph is recreated when
+/// they change, frames buffered by the old graph are dropped.
+#[derive(Debug, PartialEq, Eq)]
+struct GraphInput {
+    format: Pixel,
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+}
+
+/// Runs preprocessing steps of an input as a single libavfilter graph.
+pub(super) struct Preprocessor {
+    steps: Vec<PreprocessStep>,
+    graph: Option<(filter::Graph, GraphInput)>,
+}
+
+impl Preprocessor {
+    /// Returns `None` if there is nothing to do.
+    pub fn new(steps: Vec<PreprocessStep>) -> Option<Self> {
+        if steps.is_empty() {
+            return None;
+        }
+        Some(Self { steps, graph: None })
+    }
+
+    /// Color space of processed frames if it's converted by one of the steps.
+    pub fn color_space(&self) -> Option<ColorSpace> {
+        self.steps.iter().rev().find_map(|step| match step {
+            PreprocessStep::ConvertColorSpace(color_space) => Some(*color_space),
+            _ => None,
+        })
+    }
+
+    /// Returns frames produced by the graph, steps like deinterlacing may buffer
+    /// frames, so it can be none. `color_space` describes the decoded frame.
+    pub fn process(
+        &mut self,
+        decoded: &Video,
+        color_space: ColorSpace,
+    ) -> Result<Vec<Video>, DecoderError> {
+        self.run(decoded, color_space)
+            .map_err(DecoderError::PreprocessingError)
+    }
+
+    fn run(
+        &mut self,
+        decoded: &Video,
+        color_space: ColorSpace,
+    ) -> Result<Vec<Video>, ffmpeg_next::Error> {
+        let input = GraphInput {
+            format: decoded.format(),
+            width: decoded.width(),
+            height: decoded.height(),
+            color_space,
+        };
+        let graph = match self.graph.take() {
+            Some((graph, graph_input)) if graph_input == input => (graph, graph_input),
+            _ => (create_graph(&self.steps, &input)?, input),
+        };
+        let (graph, _) = self.graph.insert(graph);
+
+        graph.get("in").unwrap().source().add(decoded)?;
+        let mut frames = Vec::new();
+        let mut filtered = Video::empty();
+        while graph
+            .get("out")
+            .unwrap()
+            .sink()
+            .frame(&mut filtered)
+            .is_ok()
+        {
+            frames.push(mem::replace(&mut filtered, Video::empty()));
+        }
+        Ok(frames)
+    }
+}
+
+fn create_graph(
+    steps: &[PreprocessStep],
+    input: &GraphInput,
+) -> Result<filter::Graph, ffmpeg_next::Error> {
+    let mut graph = filter::Graph::new();
+    // PTS of decoded frames are in 90kHz clock.
+    let args = format!(
+        "video_size={}x{}:pix_fmt={}:time_base=1/90000:pixel_aspect=1/1",
+        input.width,
+        input.height,
+        ffmpeg_next::ffi::AVPixelFormat::from(input.format) as i32,
+    );
+    let buffer = filter::find("buffer").ok_or(ffmpeg_next::Error::FilterNotFound)?;
+    let buffersink = filter::find("buffersink").ok_or(ffmpeg_next::Error::FilterNotFound)?;
+    graph.add(&buffer, "in", &args)?;
+    graph.add(&buffersink, "out", "")?;
+
+    let spec = steps
+        .iter()
+        .map(|step| filter_spec(step, input.color_space))
+        .collect::<Vec<_>>()
+        .join(",");
+    graph.output("in", 0)?.input("out", 0)?.parse(&spec)?;
+    graph.validate()?;
+    Ok(graph)
+}
+
+fn filter_spec(step: &PreprocessStep, input_color_space: ColorSpace) -> String {
+    match step {
+        PreprocessStep::Scale { max_resolution } => format!(
+            "scale=w='min(iw,{})':h='min(ih,{})':force_original_aspect_ratio=decrease:force_divisible_by=2",
+            max_resolution.width, max_resolution.height
+        ),
+        PreprocessStep::Denoise { strength } => format!("hqdn3d=luma_spatial={strength}"),
+        PreprocessStep::Deinterlace => "yadif=mode=send_frame:deint=interlaced".to_string(),
+        PreprocessStep::ConvertColorSpace(color_space) => {
+            let (input_name, input_range) = color_space_name(input_color_space);
+            let (name, range) = color_space_name(*color_space);
+            format!("colorspace=iall={input_name}:irange={input_range}:all={name}:range={range}")
+        }
+    }
+}
+
+/// Name and range used by the colorspace filter. It doesn't support the PQ
+/// transfer, so BT.2020 PQ is treated as BT.2020.
+fn color_space_name(color_space: ColorSpace) -> (&'static str, &'static str) {
+    match color_space {
+        ColorSpace::Bt601 => ("bt601-6-625", "pc"),
+        ColorSpace::Bt709 => ("bt709", "tv"),
+        ColorSpace::Bt2020Pq => ("bt2020", "tv"),
+    }
+}
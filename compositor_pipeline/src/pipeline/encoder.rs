@@ -1,13 +1,34 @@
-use compositor_common::{scene::Resolution, Frame};
-use crossbeam_channel::Sender;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use compositor_common::{
+    frame::{ColorSpace, FrameData, FrameFormat, Nv12Data, PixelFormat, YuvData},
+    scene::Resolution,
+    util::colors::RGBColor,
+    Frame, Framerate,
+};
+use crossbeam_channel::{select, Receiver, Sender};
 use ffmpeg_next::{
     codec::{packet::Packet, Context, Id},
+    color,
+    ffi::{self, AVColorPrimaries, AVColorTransferCharacteristic},
     format::Pixel,
-    frame, Codec, Dictionary, Rational,
+    frame, picture, Codec, Dictionary, Rational,
 };
-use log::{error, warn};
+use log::{debug, error, warn};
 
-use super::{OutputOptions, PipelineOutput};
+use super::{
+    captions::{CaptionsQueue, OutputCaptions},
+    placeholder::PlaceholderFrames,
+    scte35::Scte35Queue,
+    LatencyStats, OutputOptions, PipelineOutput,
+};
 use crate::error::OutputInitError;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
@@ -73,64 +94,230 @@ impl EncoderPreset {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EncoderTune {
+    Film,
+    Animation,
+    Grain,
+    StillImage,
+    FastDecode,
+    ZeroLatency,
+}
+
+impl EncoderTune {
+    fn to_str(&self) -> &'static str {
+        match self {
+            EncoderTune::Film => "film",
+            EncoderTune::Animation => "animation",
+            EncoderTune::Grain => "grain",
+            EncoderTune::StillImage => "stillimage",
+            EncoderTune::FastDecode => "fastdecode",
+            EncoderTune::ZeroLatency => "zerolatency",
+        }
+    }
+}
+
+/// Bitrates are in bits per second.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RateControl {
+    /// Constant quality (0-51, lower is better). Bitrate can be capped with
+    /// `max_bitrate`.
+    Crf { crf: u8, max_bitrate: Option<u32> },
+    /// Average bitrate. Peaks can be limited with `max_bitrate`.
+    Vbr {
+        bitrate: u32,
+        max_bitrate: Option<u32>,
+    },
+    /// Constant bitrate, padded with filler data when the content is simple.
+    Cbr { bitrate: u32 },
+}
+
+impl Default for RateControl {
+    fn default() -> Self {
+        Self::Crf {
+            crf: 23,
+            max_bitrate: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct EncoderSettings {
     pub preset: EncoderPreset,
+    pub rate_control: RateControl,
+    /// Max number of frames between keyframes.
+    pub keyframe_interval: Option<u32>,
+    /// Max number of consecutive B-frames. Defaults to the preset value.
+    pub b_frames: Option<u32>,
+    /// Size of the VBV buffer in bits. Only used if the bitrate is limited,
+    /// defaults to one second of the max bitrate.
+    pub vbv_buffer_size: Option<u32>,
+    pub tune: Option<EncoderTune>,
+}
+
+const DEFAULT_KEYFRAME_INTERVAL: u32 = 250;
+
+impl EncoderSettings {
+    /// libx264 options that depend on the settings.
+    fn encoder_options(&self) -> Vec<(&'static str, String)> {
+        let mut options = vec![
+            ("preset", self.preset.to_str().to_string()),
+            //  Maximum GOP (Group of Pictures) size - libx264 defaults to 250
+            (
+                "g",
+                self.keyframe_interval
+                    .unwrap_or(DEFAULT_KEYFRAME_INTERVAL)
+                    .to_string(),
+            ),
+        ];
+        if let Some(tune) = &self.tune {
+            options.push(("tune", tune.to_str().to_string()));
+        }
+        if let Some(b_frames) = self.b_frames {
+            options.push(("bf", b_frames.to_string()));
+        }
+
+        let max_bitrate = match self.rate_control {
+            RateControl::Crf { crf, max_bitrate } => {
+                options.push(("crf", crf.to_string()));
+                max_bitrate
+            }
+            RateControl::Vbr {
+                bitrate,
+                max_bitrate,
+            } => {
+                options.push(("b", bitrate.to_string()));
+                max_bitrate
+            }
+            RateControl::Cbr { bitrate } => {
+                options.push(("b", bitrate.to_string()));
+                options.push(("minrate", bitrate.to_string()));
+                options.push(("nal-hrd", "cbr".to_string()));
+                Some(bitrate)
+            }
+        };
+        if let Some(max_bitrate) = max_bitrate {
+            let vbv_buffer_size = self.vbv_buffer_size.unwrap_or(max_bitrate);
+            options.push(("maxrate", max_bitrate.to_string()));
+            options.push(("bufsize", vbv_buffer_size.to_string()));
+        }
+        options
+    }
 }
 
 pub(crate) struct LibavH264Encoder {
     encoder: ffmpeg_next::codec::encoder::video::Encoder,
     codec: Codec,
     resolution: Resolution,
+    format: FrameFormat,
+    framerate: Framerate,
+    timecode_sei: bool,
 }
 
+/// Identifies SEI messages with the timecode of the frame, see [`timecode_sei_payload`].
+const TIMECODE_SEI_UUID: [u8; 16] = [
+    0x4c, 0x56, 0x43, 0x54, 0x69, 0x6d, 0x65, 0x63, 0x6f, 0x64, 0x65, 0x53, 0x45, 0x49, 0x76, 0x31,
+];
+
 impl LibavH264Encoder {
-    pub fn new(settings: EncoderSettings, resolution: Resolution) -> Result<Self, OutputInitError> {
+    /// `timecode_sei` writes timecode of every frame to a user data unregistered
+    /// SEI message.
+    pub fn new(
+        settings: EncoderSettings,
+        resolution: Resolution,
+        format: FrameFormat,
+        framerate: Framerate,
+        timecode_sei: bool,
+    ) -> Result<Self, OutputInitError> {
         let codec = ffmpeg_next::codec::encoder::find(Id::H264).ok_or(OutputInitError::NoCodec)?;
         let mut encoder = Context::new().encoder().video()?;
         let pts_unit_secs = Rational::new(1, 90000);
         encoder.set_time_base(pts_unit_secs);
-        encoder.set_format(Pixel::YUV420P);
+        // Only a hint for rate control, frames are timestamped with 90kHz PTS, so
+        // the stream stays correct when the pipeline framerate changes later.
+        encoder.set_frame_rate(Some(Rational::new(
+            framerate.num as i32,
+            framerate.den as i32,
+        )));
+        encoder.set_format(pixel_format_to_av(format.pixel_format));
         encoder.set_width(resolution.width as u32);
         encoder.set_height(resolution.height as u32);
+        // Signal color space in the bitstream (VUI), so decoders use the same
+        // conversion as the renderer.
+        let (space, range, primaries, trc) = match format.color_space {
+            ColorSpace::Bt601 => (
+                color::Space::SMPTE170M,
+                color::Range::JPEG,
+                AVColorPrimaries::AVCOL_PRI_SMPTE170M,
+                AVColorTransferCharacteristic::AVCOL_TRC_SMPTE170M,
+            ),
+            ColorSpace::Bt709 => (
+                color::Space::BT709,
+                color::Range::MPEG,
+                AVColorPrimaries::AVCOL_PRI_BT709,
+                AVColorTransferCharacteristic::AVCOL_TRC_BT709,
+            ),
+            ColorSpace::Bt2020Pq => (
+                color::Space::BT2020NCL,
+                color::Range::MPEG,
+                AVColorPrimaries::AVCOL_PRI_BT2020,
+                AVColorTransferCharacteristic::AVCOL_TRC_SMPTE2084,
+            ),
+        };
+        encoder.set_colorspace(space);
+        encoder.set_color_range(range);
+        unsafe {
+            let encoder = &mut *encoder.as_mut_ptr();
+            encoder.color_primaries = primaries;
+            encoder.color_trc = trc;
+        }
 
+        let settings_options = settings.encoder_options();
         let encoder = encoder.open_as_with(
             codec,
             // TODO: audit settings bellow
             // Those values are copied from somewhere, they have to be set because libx264
             // is throwing an error if it detects default ffmpeg settings.
-            Dictionary::from_iter([
-                ("preset", settings.preset.to_str()),
-                // Quality-based VBR (0-51)
-                ("crf", "23"),
-                // Override ffmpeg defaults from https://github.com/mirror/x264/blob/eaa68fad9e5d201d42fde51665f2d137ae96baf0/encoder/encoder.c#L674
-                // QP curve compression - libx264 defaults to 0.6 (in case of tune=grain to 0.8)
-                ("qcomp", "0.6"),
-                //  Maximum motion vector search range - libx264 defaults to 16 (in case of placebo
-                //  or veryslow preset to 24)
-                ("me_range", "16"),
-                // Max QP step - libx264 defaults to 4
-                ("qdiff", "4"),
-                // Min QP - libx264 defaults to 0
-                ("qmin", "0"),
-                // Max QP - libx264 defaults to QP_MAX = 69
-                ("qmax", "69"),
-                //  Maximum GOP (Group of Pictures) size - libx264 defaults to 250
-                ("g", "250"),
-                // QP factor between I and P frames - libx264 defaults to 1.4 (in case of tune=grain to 1.1)
-                ("i_qfactor", "1.4"),
-                // QP factor between P and B frames - libx264 defaults to 1.4 (in case of tune=grain to 1.1)
-                ("f_pb_factor", "1.3"),
-                // A comma-separated list of partitions to consider. Possible values: p8x8, p4x4, b8x8, i8x8, i4x4, none, all
-                ("partitions", settings.preset.default_partitions()),
-                // Subpixel motion estimation and mode decision (decision quality: 1=fast, 11=best)
-                ("subq", settings.preset.default_subq_mode()),
-            ]),
+            Dictionary::from_iter(
+                settings_options
+                    .iter()
+                    .map(|(key, value)| (*key, value.as_str()))
+                    .chain([
+                        // Override ffmpeg defaults from https://github.com/mirror/x264/blob/eaa68fad9e5d201d42fde51665f2d137ae96baf0/encoder/encoder.c#L674
+                        // QP curve compression - libx264 defaults to 0.6 (in case of tune=grain to 0.8)
+                        ("qcomp", "0.6"),
+                        //  Maximum motion vector search range - libx264 defaults to 16 (in case of placebo
+                        //  or veryslow preset to 24)
+                        ("me_range", "16"),
+                        // Max QP step - libx264 defaults to 4
+                        ("qdiff", "4"),
+                        // Min QP - libx264 defaults to 0
+                        ("qmin", "0"),
+                        // Max QP - libx264 defaults to QP_MAX = 69
+                        ("qmax", "69"),
+                        // QP factor between I and P frames - libx264 defaults to 1.4 (in case of tune=grain to 1.1)
+                        ("i_qfactor", "1.4"),
+                        // QP factor between P and B frames - libx264 defaults to 1.4 (in case of tune=grain to 1.1)
+                        ("f_pb_factor", "1.3"),
+                        // A comma-separated list of partitions to consider. Possible values: p8x8, p4x4, b8x8, i8x8, i4x4, none, all
+                        ("partitions", settings.preset.default_partitions()),
+                        // Subpixel motion estimation and mode decision (decision quality: 1=fast, 11=best)
+                        ("subq", settings.preset.default_subq_mode()),
+                        // Frames forced to be keyframes are encoded as IDR frames, so
+                        // decoders can start decoding from them.
+                        ("forced-idr", "1"),
+                        // Write SEI_UNREGISTERED side data of frames to the stream.
+                        ("udu_sei", if timecode_sei { "1" } else { "0" }),
+                    ]),
+            ),
         )?;
 
         Ok(Self {
             encoder,
             codec,
             resolution,
+            format,
+            framerate,
+            timecode_sei,
         })
     }
 
@@ -138,14 +325,59 @@ impl LibavH264Encoder {
         self.codec
     }
 
-    pub fn send_frame(&mut self, frame: Frame) -> PacketIterator {
+    /// `keyframe` forces the frame to be encoded as an IDR frame. `cc_data` (CEA-708
+    /// closed captions) is written by libx264 to SEI of the frame.
+    pub fn send_frame(&mut self, frame: Frame, keyframe: bool, cc_data: &[u8]) -> PacketIterator {
         let mut av_frame = frame::Video::new(
-            Pixel::YUV420P,
+            pixel_format_to_av(self.format.pixel_format),
             self.resolution.width as u32,
             self.resolution.height as u32,
         );
 
-        frame_into_av(frame, &mut av_frame);
+        let bytes_per_sample = self.format.pixel_format.bytes_per_sample();
+        let timecode_sei = self
+            .timecode_sei
+            .then(|| timecode_sei_payload(frame.pts, self.framerate, SystemTime::now()));
+        frame_into_av(frame, &mut av_frame, bytes_per_sample);
+        if keyframe {
+            av_frame.set_kind(picture::Type::I);
+        }
+        if !cc_data.is_empty() {
+            unsafe {
+                let side_data = ffi::av_frame_new_side_data(
+                    av_frame.as_mut_ptr(),
+                    ffi::AVFrameSideDataType::AV_FRAME_DATA_A53_CC,
+                    cc_data.len() as _,
+                );
+                if side_data.is_null() {
+                    error!("Failed to allocate closed captions side data.");
+                } else {
+                    std::ptr::copy_nonoverlapping(
+                        cc_data.as_ptr(),
+                        (*side_data).data,
+                        cc_data.len(),
+                    );
+                }
+            }
+        }
+        if let Some(payload) = timecode_sei {
+            unsafe {
+                let side_data = ffi::av_frame_new_side_data(
+                    av_frame.as_mut_ptr(),
+                    ffi::AVFrameSideDataType::AV_FRAME_DATA_SEI_UNREGISTERED,
+                    payload.len() as _,
+                );
+                if side_data.is_null() {
+                    error!("Failed to allocate timecode side data.");
+                } else {
+                    std::ptr::copy_nonoverlapping(
+                        payload.as_ptr(),
+                        (*side_data).data,
+                        payload.len(),
+                    );
+                }
+            }
+        }
 
         if let Err(e) = self.encoder.send_frame(&av_frame) {
             error!("Encoder error: {e}.")
@@ -153,6 +385,16 @@ impl LibavH264Encoder {
 
         PacketIterator { encoder: self }
     }
+
+    /// Returns packets of frames still buffered in the encoder. Encoder can't
+    /// accept new frames afterwards.
+    pub fn flush(&mut self) -> PacketIterator {
+        if let Err(e) = self.encoder.send_eof() {
+            error!("Encoder error: {e}.")
+        }
+
+        PacketIterator { encoder: self }
+    }
 }
 
 pub struct PacketIterator<'a> {
@@ -173,65 +415,201 @@ impl<'a> Iterator for PacketIterator<'a> {
     }
 }
 
-fn frame_into_av(frame: Frame, av_frame: &mut frame::Video) {
-    let expected_y_plane_size = (av_frame.plane_width(0) * av_frame.plane_height(0)) as usize;
-    let expected_u_plane_size = (av_frame.plane_width(1) * av_frame.plane_height(1)) as usize;
-    let expected_v_plane_size = (av_frame.plane_width(2) * av_frame.plane_height(2)) as usize;
-    if expected_y_plane_size != frame.data.y_plane.len() {
+/// Payload of the user data unregistered SEI message: [`TIMECODE_SEI_UUID`]
+/// followed by ASCII text, e.g. `timecode=00:01:02:15;wallclock_ms=1700000000000`.
+/// Timecode (`HH:MM:SS:FF`) is based on the PTS of the frame, wall clock is the
+/// time when the frame is encoded. Picture timing SEI can't be used, libx264
+/// does not support writing timecodes there.
+fn timecode_sei_payload(pts: Duration, framerate: Framerate, now: SystemTime) -> Vec<u8> {
+    let secs = pts.as_secs();
+    let fps = (framerate.num as f64 / framerate.den as f64)
+        .round()
+        .max(1.0);
+    let frames = (pts.subsec_nanos() as f64 / 1e9 * fps).floor() as u64;
+    let wallclock_ms = now
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let text = format!(
+        "timecode={:02}:{:02}:{:02}:{:02};wallclock_ms={wallclock_ms}",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60,
+        frames
+    );
+    [TIMECODE_SEI_UUID.as_slice(), text.as_bytes()].concat()
+}
+
+fn pixel_format_to_av(pixel_format: PixelFormat) -> Pixel {
+    match pixel_format {
+        PixelFormat::Yuv420p => Pixel::YUV420P,
+        PixelFormat::Yuv420p10le => Pixel::YUV420P10LE,
+    }
+}
+
+fn frame_into_av(frame: Frame, av_frame: &mut frame::Video, bytes_per_sample: usize) {
+    let data = match frame.data {
+        FrameData::PlanarYuv(data) => data,
+        // Recorded input frames are passed as they were decoded.
+        FrameData::Nv12(data) => nv12_to_planar(data),
+        FrameData::Rgba(_) => {
+            error!("Encoder: RGBA frames can't be encoded");
+            return;
+        }
+    };
+    let plane_size = |plane| {
+        (av_frame.plane_width(plane) * av_frame.plane_height(plane)) as usize * bytes_per_sample
+    };
+    let expected_y_plane_size = plane_size(0);
+    let expected_u_plane_size = plane_size(1);
+    let expected_v_plane_size = plane_size(2);
+    if expected_y_plane_size != data.y_plane.len() {
         error!(
             "Encoder: Y plane is a wrong size, expected: {} received: {}",
             expected_y_plane_size,
-            frame.data.y_plane.len()
+            data.y_plane.len()
         );
         return;
     }
-    if expected_u_plane_size != frame.data.u_plane.len() {
+    if expected_u_plane_size != data.u_plane.len() {
         error!(
             "Encoder: U plane is a wrong size, expected: {} received: {}",
             expected_u_plane_size,
-            frame.data.u_plane.len()
+            data.u_plane.len()
         );
 
         return;
     }
-    if expected_v_plane_size != frame.data.v_plane.len() {
+    if expected_v_plane_size != data.v_plane.len() {
         error!(
             "Encoder: V plane is a wrong size, expected: {} received: {}",
             expected_v_plane_size,
-            frame.data.v_plane.len()
+            data.v_plane.len()
         );
         return;
     }
 
-    av_frame.set_pts(Some((frame.pts.as_secs_f64() * 90000.0) as i64));
+    av_frame.set_pts(Some(pts_to_av(frame.pts)));
 
-    write_plane_to_av(av_frame, 0, &frame.data.y_plane);
-    write_plane_to_av(av_frame, 1, &frame.data.u_plane);
-    write_plane_to_av(av_frame, 2, &frame.data.v_plane);
+    write_plane_to_av(av_frame, 0, &data.y_plane, bytes_per_sample);
+    write_plane_to_av(av_frame, 1, &data.u_plane, bytes_per_sample);
+    write_plane_to_av(av_frame, 2, &data.v_plane, bytes_per_sample);
     // Ok(())
 }
 
-fn write_plane_to_av(frame: &mut frame::Video, plane: usize, data: &[u8]) {
+fn nv12_to_planar(data: Nv12Data) -> YuvData {
+    YuvData {
+        y_plane: data.y_plane,
+        u_plane: data.uv_plane.iter().step_by(2).copied().collect(),
+        v_plane: data.uv_plane.iter().skip(1).step_by(2).copied().collect(),
+    }
+}
+
+/// Rounding (instead of truncating) keeps intervals between frames even for
+/// fractional framerates, e.g. always 3003 ticks for 30000/1001.
+fn pts_to_av(pts: Duration) -> i64 {
+    (pts.as_secs_f64() * 90000.0).round() as i64
+}
+
+fn write_plane_to_av(frame: &mut frame::Video, plane: usize, data: &[u8], bytes_per_sample: usize) {
     let stride = frame.stride(plane);
-    let width = frame.plane_width(plane) as usize;
+    let width = frame.plane_width(plane) as usize * bytes_per_sample;
 
     data.chunks(width)
         .zip(frame.data_mut(plane).chunks_mut(stride))
         .for_each(|(data, target)| target[..width].copy_from_slice(data));
 }
 
+/// Max number of frames waiting for encoding. In realtime mode newer frames are
+/// dropped above it, in offline mode sending blocks until encoder catches up.
+const MAX_ENCODER_QUEUE_LEN: usize = 20;
+
+/// Max number of frames buffered inside of the encoder (e.g. for lookahead) that
+/// are tracked to measure encoding latency.
+const MAX_PENDING_LATENCY_FRAMES: usize = 256;
+
+/// Frames sent to the output while it is stopped.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum StoppedOutputFrames {
+    /// Nothing is encoded or sent.
+    #[default]
+    Nothing,
+    /// Last frame sent before the output was stopped is repeated.
+    FreezeFrame,
+    /// Frames filled with a solid color.
+    Slate { color: RGBColor },
+}
+
+enum EncoderState {
+    /// Last frame is kept in case the output is stopped with a freeze frame.
+    Running {
+        last_frame: Option<Frame>,
+    },
+    Stopped(StoppedState),
+}
+
+enum StoppedState {
+    Nothing,
+    FreezeFrame(Option<Frame>),
+    Slate(PlaceholderFrames),
+}
+
 pub struct Encoder<Output: PipelineOutput> {
-    sender: Sender<Frame>,
+    /// Frames with the time they were sent to the encoder.
+    sender: Sender<(Frame, Instant)>,
+    finish_sender: Sender<()>,
+    /// Replaces the encoder used by the encoder thread.
+    encoder_sender: Sender<LibavH264Encoder>,
     output: Output,
+    /// Frames of a stopped output are replaced (or dropped) before they reach the
+    /// encoder thread. Encoder and output stay alive, so sending can be resumed later.
+    state: Mutex<EncoderState>,
+    /// Next frame is encoded as a keyframe when set. Reset by the encoder thread.
+    force_keyframe: Arc<AtomicBool>,
+    /// Closed captions embedded in encoded frames.
+    captions: Option<(OutputCaptions, CaptionsQueue)>,
+    /// Splice commands written to the output with the next encoded frame.
+    scte35: Option<Scte35Queue>,
+    timecode_sei: bool,
+    resolution: Resolution,
+    format: FrameFormat,
 }
 
 impl<Output: PipelineOutput> Encoder<Output> {
-    pub fn new(opts: OutputOptions<Output>) -> Result<Self, OutputInitError> {
-        let mut encoder = LibavH264Encoder::new(opts.encoder_settings, opts.resolution)?;
-        let (frame_sender, frame_receiver) = crossbeam_channel::unbounded();
+    /// In `offline` mode frames are never dropped, see [`crate::queue::Options::offline`].
+    pub fn new(
+        opts: OutputOptions<Output>,
+        framerate: Framerate,
+        offline: bool,
+        latency_stats: Arc<LatencyStats>,
+    ) -> Result<Self, OutputInitError> {
+        let resolution = opts.resolution;
+        let format = opts.format;
+        let timecode_sei = opts.timecode_sei;
+        let encoder = LibavH264Encoder::new(
+            opts.encoder_settings,
+            resolution,
+            format,
+            framerate,
+            timecode_sei,
+        )?;
+        let force_keyframe = Arc::new(AtomicBool::new(false));
+        let captions = opts
+            .captions
+            .map(|source| (source, CaptionsQueue::default()));
+        let scte35 = opts.scte35.then(Scte35Queue::default);
+        let (frame_sender, frame_receiver) = match offline {
+            true => crossbeam_channel::bounded(MAX_ENCODER_QUEUE_LEN),
+            false => crossbeam_channel::unbounded(),
+        };
+        let (finish_sender, finish_receiver) = crossbeam_channel::bounded(1);
+        let (encoder_sender, encoder_receiver) = crossbeam_channel::unbounded();
         // channel used to return information about the RtpSender initialization back to the API thread.
         let (output_sender, output_receiver) = crossbeam_channel::bounded(0);
 
+        let thread_force_keyframe = force_keyframe.clone();
+        let thread_captions = captions.as_ref().map(|(_, queue)| queue.clone());
+        let thread_scte35 = scte35.clone();
         std::thread::spawn(move || {
             let (output, mut context) = match Output::new(opts.receiver_options, encoder.codec()) {
                 Ok(r) => r,
@@ -243,29 +621,267 @@ impl<Output: PipelineOutput> Encoder<Output> {
 
             output_sender.send(Ok(output.clone())).unwrap();
 
-            for frame in frame_receiver.iter() {
-                if frame_receiver.len() > 20 {
-                    warn!("Dropping frame: encoder queue is too long.");
-                    continue;
-                }
-
-                for packet in encoder.send_frame(frame) {
-                    output.send_packet(&mut context, packet);
-                }
+            EncoderThread {
+                encoder,
+                output,
+                context,
+                drop_frames: !offline,
+                last_pts: None,
+                force_keyframe: thread_force_keyframe,
+                captions: thread_captions,
+                scte35: thread_scte35,
+                latency_stats,
+                pending_latency: VecDeque::new(),
             }
+            .run(frame_receiver, finish_receiver, encoder_receiver);
         });
 
         Ok(Self {
             sender: frame_sender,
+            finish_sender,
+            encoder_sender,
             output: output_receiver.recv().unwrap()?,
+            state: Mutex::new(EncoderState::Running { last_frame: None }),
+            force_keyframe,
+            captions,
+            scte35,
+            timecode_sei,
+            resolution,
+            format,
         })
     }
 
     pub fn send_frame(&self, frame: Frame) {
-        self.sender.send(frame).unwrap();
+        let frame = match &mut *self.state.lock().unwrap() {
+            EncoderState::Running { last_frame } => {
+                *last_frame = Some(frame.clone());
+                frame
+            }
+            EncoderState::Stopped(StoppedState::FreezeFrame(Some(last_frame))) => Frame {
+                pts: frame.pts,
+                ..last_frame.clone()
+            },
+            EncoderState::Stopped(StoppedState::Slate(slate)) => {
+                slate.frame(self.resolution, self.format, frame.pts)
+            }
+            EncoderState::Stopped(StoppedState::Nothing | StoppedState::FreezeFrame(None)) => {
+                return;
+            }
+        };
+        if self.sender.send((frame, Instant::now())).is_err() {
+            debug!("Dropping frame: output already finished.");
+        }
+    }
+
+    /// Encodes remaining frames, flushes the encoder and closes the output.
+    /// Frames sent afterwards are dropped.
+    pub fn finish(&self) {
+        let _ = self.finish_sender.try_send(());
+    }
+
+    /// Reopens the encoder with new settings. Frames buffered in the old encoder
+    /// are flushed first, so the stream continues without a gap and starts with
+    /// a keyframe after the change.
+    pub fn update_settings(
+        &self,
+        settings: EncoderSettings,
+        framerate: Framerate,
+    ) -> Result<(), OutputInitError> {
+        let encoder = LibavH264Encoder::new(
+            settings,
+            self.resolution,
+            self.format,
+            framerate,
+            self.timecode_sei,
+        )?;
+        // Fails only if the output already finished.
+        let _ = self.encoder_sender.send(encoder);
+        Ok(())
+    }
+
+    /// Forces the next encoded frame to be a keyframe (IDR), e.g. when a new
+    /// receiver joins the stream.
+    pub fn request_keyframe(&self) {
+        self.force_keyframe.store(true, Ordering::Relaxed);
+    }
+
+    pub fn captions(&self) -> Option<&(OutputCaptions, CaptionsQueue)> {
+        self.captions.as_ref()
+    }
+
+    pub fn scte35(&self) -> Option<&Scte35Queue> {
+        self.scte35.as_ref()
+    }
+
+    /// Resumes sending rendered frames. The first one is encoded as a keyframe, so
+    /// receivers that reconnected in the meantime can start decoding right away.
+    pub fn start(&self) {
+        let mut state = self.state.lock().unwrap();
+        if matches!(*state, EncoderState::Stopped(_)) {
+            *state = EncoderState::Running { last_frame: None };
+            self.request_keyframe();
+        }
+    }
+
+    /// Rendered frames are replaced with `frames` until the output is started again.
+    pub fn stop(&self, frames: StoppedOutputFrames) {
+        let mut state = self.state.lock().unwrap();
+        let last_frame = match &mut *state {
+            EncoderState::Running { last_frame } => last_frame.take(),
+            EncoderState::Stopped(StoppedState::FreezeFrame(last_frame)) => last_frame.take(),
+            EncoderState::Stopped(_) => None,
+        };
+        *state = EncoderState::Stopped(match frames {
+            StoppedOutputFrames::Nothing => StoppedState::Nothing,
+            StoppedOutputFrames::FreezeFrame => StoppedState::FreezeFrame(last_frame),
+            StoppedOutputFrames::Slate { color } => {
+                StoppedState::Slate(PlaceholderFrames::new(color))
+            }
+        });
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(*self.state.lock().unwrap(), EncoderState::Running { .. })
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    pub fn format(&self) -> FrameFormat {
+        self.format
     }
 
     pub fn output(&self) -> &Output {
         &self.output
     }
 }
+
+struct EncoderThread<Output: PipelineOutput> {
+    encoder: LibavH264Encoder,
+    output: Output,
+    context: Output::Context,
+    drop_frames: bool,
+    last_pts: Option<Duration>,
+    force_keyframe: Arc<AtomicBool>,
+    captions: Option<CaptionsQueue>,
+    scte35: Option<Scte35Queue>,
+    latency_stats: Arc<LatencyStats>,
+    /// Frames inside of the encoder: PTS in the encoder time base, PTS of the
+    /// frame and the time it was sent to the encoder.
+    pending_latency: VecDeque<(i64, Duration, Instant)>,
+}
+
+impl<Output: PipelineOutput> EncoderThread<Output> {
+    fn run(
+        mut self,
+        frame_receiver: Receiver<(Frame, Instant)>,
+        finish_receiver: Receiver<()>,
+        encoder_receiver: Receiver<LibavH264Encoder>,
+    ) {
+        loop {
+            select! {
+                recv(frame_receiver) -> frame => match frame {
+                    Ok((frame, sent_at)) => self.on_frame(frame, sent_at, frame_receiver.len()),
+                    Err(_) => return,
+                },
+                recv(encoder_receiver) -> encoder => {
+                    if let Ok(encoder) = encoder {
+                        self.flush();
+                        self.encoder = encoder;
+                    }
+                }
+                recv(finish_receiver) -> _ => break,
+            }
+        }
+
+        for (frame, sent_at) in frame_receiver.try_iter() {
+            self.on_frame(frame, sent_at, 0);
+        }
+        self.flush();
+        // Output context is dropped here, so outputs can finalize the stream (e.g.
+        // write a file trailer).
+    }
+
+    fn flush(&mut self) {
+        for packet in self.encoder.flush() {
+            Self::on_packet_latency(&mut self.pending_latency, &self.latency_stats, &packet);
+            self.output.send_packet(&mut self.context, packet);
+        }
+    }
+
+    /// Records latency of the frame encoded into the packet.
+    fn on_packet_latency(
+        pending_latency: &mut VecDeque<(i64, Duration, Instant)>,
+        latency_stats: &LatencyStats,
+        packet: &Packet,
+    ) {
+        let Some(packet_pts) = packet.pts() else {
+            return;
+        };
+        let index = pending_latency
+            .iter()
+            .position(|(av_pts, _, _)| *av_pts == packet_pts);
+        if let Some((_, pts, sent_at)) = index.and_then(|index| pending_latency.remove(index)) {
+            latency_stats.on_encoded(pts, sent_at);
+        }
+    }
+
+    fn on_frame(&mut self, frame: Frame, sent_at: Instant, queue_len: usize) {
+        if self.drop_frames && queue_len > MAX_ENCODER_QUEUE_LEN {
+            warn!("Dropping frame: encoder queue is too long.");
+            return;
+        }
+
+        // Placeholder frames sent on registration can be timestamped after
+        // the rendered frames that are still in flight.
+        if self.last_pts.is_some_and(|last_pts| frame.pts <= last_pts) {
+            debug!("Dropping frame: PTS is not greater than PTS of the previous frame.");
+            return;
+        }
+        self.last_pts = Some(frame.pts);
+
+        let keyframe = self.force_keyframe.swap(false, Ordering::Relaxed);
+        let cc_data = self
+            .captions
+            .as_ref()
+            .map(CaptionsQueue::pop_frame)
+            .unwrap_or_default();
+        let scte35_sections = self
+            .scte35
+            .as_ref()
+            .map(|queue| queue.pop_sections(frame.pts))
+            .unwrap_or_default();
+        for section in scte35_sections {
+            self.output
+                .send_scte35(&mut self.context, &section, frame.pts);
+        }
+        if self.pending_latency.len() == MAX_PENDING_LATENCY_FRAMES {
+            self.pending_latency.pop_front();
+        }
+        self.pending_latency
+            .push_back((pts_to_av(frame.pts), frame.pts, sent_at));
+        for packet in self.encoder.send_frame(frame, keyframe, &cc_data) {
+            Self::on_packet_latency(&mut self.pending_latency, &self.latency_stats, &packet);
+            self.output.send_packet(&mut self.context, packet);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timecode_sei_payload_contains_pts_timecode() {
+        let pts = Duration::from_millis(3_723_500);
+        let now = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let payload = timecode_sei_payload(pts, Framerate { num: 30, den: 1 }, now);
+
+        assert_eq!(payload[..16], TIMECODE_SEI_UUID);
+        assert_eq!(
+            &payload[16..],
+            b"timecode=01:02:03:15;wallclock_ms=1700000000123"
+        );
+    }
+}
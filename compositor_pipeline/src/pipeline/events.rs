@@ -0,0 +1,48 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// Max number of events kept until they are taken, older events are dropped.
+const MAX_EVENTS: usize = 100;
+
+/// Notable changes of the pipeline state that clients may want to react to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineEvent {
+    /// Rendering failed `consecutive_failures` times in a row, the render failure
+    /// policy is applied until it recovers. `error` is the last failure.
+    RenderFailing {
+        consecutive_failures: u32,
+        error: String,
+    },
+    /// Frame was rendered successfully after `failed_frames` consecutive failures.
+    RenderRecovered { failed_frames: u32 },
+    /// Renderer panicked while rendering a frame. The render thread keeps running,
+    /// the frame is handled as a failed one.
+    RenderPanicked { message: String },
+    /// Rendering stopped because of the render failure policy, outputs are finished.
+    RenderingStopped,
+    /// Scene of the timeline entry was applied.
+    TimelineEntryStarted { entry_index: usize },
+    /// Scene of the timeline entry could not be applied, the previous scene is kept.
+    TimelineEntryFailed { entry_index: usize, error: String },
+}
+
+/// Events waiting to be taken by the client, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineEvents(Arc<Mutex<VecDeque<PipelineEvent>>>);
+
+impl PipelineEvents {
+    pub(super) fn push(&self, event: PipelineEvent) {
+        let mut events = self.0.lock().unwrap();
+        if events.len() == MAX_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Events emitted since the previous call.
+    pub fn take(&self) -> Vec<PipelineEvent> {
+        self.0.lock().unwrap().drain(..).collect()
+    }
+}
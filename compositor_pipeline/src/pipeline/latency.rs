@@ -0,0 +1,153 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Number of most recent frames used to calculate percentiles of every stage.
+const MAX_SAMPLES: usize = 1000;
+
+/// Latency added by the compositor to frames of all outputs, split into stages:
+/// - queue - from the frame reaching the queue until rendering starts. In realtime
+///   mode PTS of input frames are normalized to the queue clock, so the ingress
+///   time of a frame set is the instant that corresponds to its PTS.
+/// - render - rendering of the frame set.
+/// - encode - from the rendered frame reaching the encoder until its packet is
+///   produced, including frames buffered by the encoder (e.g. lookahead).
+/// - total - from the queue ingress until the encoded packet.
+#[derive(Debug)]
+pub struct LatencyStats {
+    /// Instant that corresponds to PTS=0. Not set in offline mode, where PTS are
+    /// not related to the wall clock, so queue and total latency are not measured.
+    clock_start: Option<Instant>,
+    queue: Mutex<LatencySamples>,
+    render: Mutex<LatencySamples>,
+    encode: Mutex<LatencySamples>,
+    total: Mutex<LatencySamples>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyPercentiles {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    /// Number of frames the percentiles are calculated from.
+    pub samples: usize,
+}
+
+impl LatencyStats {
+    pub(super) fn new(clock_start: Option<Instant>) -> Self {
+        Self {
+            clock_start,
+            queue: Default::default(),
+            render: Default::default(),
+            encode: Default::default(),
+            total: Default::default(),
+        }
+    }
+
+    pub fn queue(&self) -> Option<LatencyPercentiles> {
+        self.queue.lock().unwrap().percentiles()
+    }
+
+    pub fn render(&self) -> Option<LatencyPercentiles> {
+        self.render.lock().unwrap().percentiles()
+    }
+
+    pub fn encode(&self) -> Option<LatencyPercentiles> {
+        self.encode.lock().unwrap().percentiles()
+    }
+
+    pub fn total(&self) -> Option<LatencyPercentiles> {
+        self.total.lock().unwrap().percentiles()
+    }
+
+    pub(super) fn on_render_started(&self, pts: Duration) {
+        if let Some(ingress) = self.ingress(pts) {
+            self.queue.lock().unwrap().push(ingress.elapsed());
+        }
+    }
+
+    pub(super) fn on_rendered(&self, render_duration: Duration) {
+        self.render.lock().unwrap().push(render_duration);
+    }
+
+    /// `sent_at` is the time when the frame was sent to the encoder.
+    pub(super) fn on_encoded(&self, pts: Duration, sent_at: Instant) {
+        self.encode.lock().unwrap().push(sent_at.elapsed());
+        if let Some(ingress) = self.ingress(pts) {
+            self.total.lock().unwrap().push(ingress.elapsed());
+        }
+    }
+
+    fn ingress(&self, pts: Duration) -> Option<Instant> {
+        self.clock_start.map(|clock_start| clock_start + pts)
+    }
+}
+
+#[derive(Debug, Default)]
+struct LatencySamples(VecDeque<Duration>);
+
+impl LatencySamples {
+    fn push(&mut self, latency: Duration) {
+        if self.0.len() == MAX_SAMPLES {
+            self.0.pop_front();
+        }
+        self.0.push_back(latency);
+    }
+
+    fn percentiles(&self) -> Option<LatencyPercentiles> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<_> = self.0.iter().copied().collect();
+        sorted.sort_unstable();
+        // Nearest-rank method, the percentile is always one of the samples.
+        let percentile = |p: f64| {
+            let rank = (p * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        Some(LatencyPercentiles {
+            p50: percentile(0.5),
+            p90: percentile(0.9),
+            p99: percentile(0.99),
+            max: sorted[sorted.len() - 1],
+            samples: sorted.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let mut samples = LatencySamples::default();
+        assert_eq!(samples.percentiles(), None);
+
+        for ms in (1..=100).rev() {
+            samples.push(Duration::from_millis(ms));
+        }
+        let percentiles = samples.percentiles().unwrap();
+        assert_eq!(percentiles.p50, Duration::from_millis(50));
+        assert_eq!(percentiles.p90, Duration::from_millis(90));
+        assert_eq!(percentiles.p99, Duration::from_millis(99));
+        assert_eq!(percentiles.max, Duration::from_millis(100));
+        assert_eq!(percentiles.samples, 100);
+    }
+
+    #[test]
+    fn only_recent_samples_are_kept() {
+        let mut samples = LatencySamples::default();
+        for _ in 0..MAX_SAMPLES {
+            samples.push(Duration::from_secs(1));
+        }
+        samples.push(Duration::from_millis(1));
+
+        let percentiles = samples.percentiles().unwrap();
+        assert_eq!(percentiles.samples, MAX_SAMPLES);
+        assert_eq!(percentiles.p50, Duration::from_secs(1));
+    }
+}
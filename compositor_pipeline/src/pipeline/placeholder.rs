@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bytes::{Bytes, BytesMut};
+use compositor_common::{
+    frame::{FrameData, FrameFormat, PixelFormat, YuvData},
+    scene::Resolution,
+    util::colors::RGBColor,
+    Frame, Framerate,
+};
+use crossbeam_channel::{select, tick, Receiver};
+
+use super::{encoder::Encoder, OutputRegistry, PipelineOutput};
+
+/// Defines what outputs are sending before the pipeline is started.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum PreStartOutput {
+    /// Outputs are not sending anything until the pipeline is started.
+    #[default]
+    Nothing,
+    /// Outputs are sending black frames.
+    BlackFrames,
+    /// Outputs are sending frames filled with a solid color.
+    Slate { color: RGBColor },
+}
+
+impl PreStartOutput {
+    fn color(&self) -> Option<RGBColor> {
+        match self {
+            PreStartOutput::Nothing => None,
+            PreStartOutput::BlackFrames => Some(RGBColor::BLACK),
+            PreStartOutput::Slate { color } => Some(*color),
+        }
+    }
+
+    /// Color used for outputs registered after the pipeline is started. Slate is
+    /// reused if configured, otherwise black frames are sent.
+    pub(super) fn backfill_color(&self) -> RGBColor {
+        self.color().unwrap_or(RGBColor::BLACK)
+    }
+}
+
+/// Generates frames filled with a single color. Planes are cached per resolution
+/// and format, so producing a frame for the same output again is just a cheap copy
+/// of `Bytes`.
+pub(super) struct PlaceholderFrames {
+    color: RGBColor,
+    cache: HashMap<(Resolution, FrameFormat), YuvData>,
+}
+
+impl PlaceholderFrames {
+    pub fn new(color: RGBColor) -> Self {
+        Self {
+            color,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn frame(&mut self, resolution: Resolution, format: FrameFormat, pts: Duration) -> Frame {
+        let color = self.color;
+        let data = self
+            .cache
+            .entry((resolution, format))
+            .or_insert_with(|| solid_color_planes(color, resolution, format))
+            .clone();
+        Frame {
+            data: FrameData::PlanarYuv(data),
+            resolution,
+            format,
+            pts,
+        }
+    }
+}
+
+fn solid_color_planes(color: RGBColor, resolution: Resolution, format: FrameFormat) -> YuvData {
+    let [y, u, v] = format.rgb_to_yuv_samples(color);
+    let plane = |value: u16, len: usize| -> Bytes {
+        let mut plane = BytesMut::with_capacity(len * format.pixel_format.bytes_per_sample());
+        for _ in 0..len {
+            match format.pixel_format {
+                PixelFormat::Yuv420p => plane.extend_from_slice(&[value as u8]),
+                PixelFormat::Yuv420p10le => plane.extend_from_slice(&value.to_le_bytes()),
+            }
+        }
+        plane.freeze()
+    };
+    let chroma_len = (resolution.width / 2) * (resolution.height / 2);
+    YuvData {
+        y_plane: plane(y, resolution.width * resolution.height),
+        u_plane: plane(u, chroma_len),
+        v_plane: plane(v, chroma_len),
+    }
+}
+
+/// Sends placeholder frames to all registered outputs until a message is received
+/// on `stop`. PTS values are based on the same clock as the queue uses, so frames
+/// produced after the pipeline is started continue the same timeline.
+pub(super) fn spawn_pre_start_sender<Output: PipelineOutput>(
+    mode: PreStartOutput,
+    outputs: OutputRegistry<Encoder<Output>>,
+    framerate: Framerate,
+    clock_start: Instant,
+    stop: Receiver<()>,
+) {
+    let Some(color) = mode.color() else {
+        return;
+    };
+
+    thread::spawn(move || {
+        let mut placeholder = PlaceholderFrames::new(color);
+        let ticker = tick(framerate.get_interval_duration());
+        loop {
+            select! {
+                recv(stop) -> _ => return,
+                recv(ticker) -> _ => {
+                    let pts = clock_start.elapsed();
+                    for output in outputs.lock().values() {
+                        output.send_frame(placeholder.frame(output.resolution(), output.format(), pts));
+                    }
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,285 @@
+use std::{path::Path, sync::Arc};
+
+use compositor_common::{
+    frame::FrameData,
+    scene::{InputId, Resolution},
+    Frame, Framerate,
+};
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use ffmpeg_next::{
+    codec,
+    format::{self, context::Output},
+    Dictionary, Packet, Rational,
+};
+use log::{error, warn};
+
+use super::{
+    decoder::{Codec, DecoderParameters},
+    encoder::{EncoderSettings, LibavH264Encoder},
+};
+use crate::error::RecordingError;
+
+/// Time base of packets received from inputs and produced by the encoder.
+const PTS_TIME_BASE: Rational = Rational(1, 90000);
+/// Packets or frames waiting to be written. When the file can't be written fast
+/// enough, data is dropped, so the recording never delays compositing.
+const QUEUE_SIZE: usize = 300;
+
+/// Copy of the input written to its own file in parallel with compositing
+/// (ISO recording).
+#[derive(Debug, Clone)]
+pub struct RecordingOptions {
+    /// Container format is deduced from the file extension.
+    pub path: Arc<Path>,
+    pub mode: RecordingMode,
+}
+
+#[derive(Debug, Clone)]
+pub enum RecordingMode {
+    /// Packets received from the input are written without decoding. Only
+    /// compressed (H264, MJPEG) inputs can be recorded this way.
+    Passthrough,
+    /// Frames passed to the scene (after preprocessing) are encoded again.
+    Decoded(EncoderSettings),
+}
+
+enum RecordedData {
+    Packet(Packet),
+    Frame(Frame),
+}
+
+/// Sends data of the input to the thread writing the file. File is finalized
+/// when the recorder is dropped.
+pub(super) struct InputRecorder {
+    input_id: InputId,
+    mode: RecordingMode,
+    sender: Sender<RecordedData>,
+}
+
+impl InputRecorder {
+    /// Output file is opened immediately, so invalid paths are reported when the
+    /// input is registered.
+    pub fn new(
+        input_id: InputId,
+        options: RecordingOptions,
+        parameters: DecoderParameters,
+        framerate: Framerate,
+    ) -> Result<Self, RecordingError> {
+        let stream = match &options.mode {
+            RecordingMode::Passthrough => {
+                if let Codec::RawVideo { .. } = parameters.codec {
+                    return Err(RecordingError::PassthroughNotSupported);
+                }
+                RecordedStream::Passthrough(parameters)
+            }
+            RecordingMode::Decoded(settings) => RecordedStream::Decoded {
+                settings: settings.clone(),
+                framerate,
+                encoder: None,
+                dropping_frames: false,
+            },
+        };
+        let writer = RecordingWriter {
+            output_ctx: format::output(&options.path)?,
+            path: options.path,
+            stream,
+            time_base: None,
+            pts_offset: None,
+        };
+
+        let (sender, receiver) = bounded(QUEUE_SIZE);
+        std::thread::spawn(move || writer.run(receiver));
+        Ok(Self {
+            input_id,
+            mode: options.mode,
+            sender,
+        })
+    }
+
+    /// Packet received from the input, recorded only in passthrough mode.
+    pub fn record_packet(&self, packet: &Packet) {
+        if let RecordingMode::Passthrough = self.mode {
+            self.send(RecordedData::Packet(packet.clone()));
+        }
+    }
+
+    /// Frame passed to the queue, recorded only in decoded mode.
+    pub fn record_frame(&self, frame: &Frame) {
+        if let RecordingMode::Decoded(_) = self.mode {
+            self.send(RecordedData::Frame(frame.clone()));
+        }
+    }
+
+    fn send(&self, data: RecordedData) {
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(data) {
+            warn!(
+                "Recording of input \"{}\" is too slow, dropping data.",
+                self.input_id
+            );
+        }
+    }
+}
+
+enum RecordedStream {
+    Passthrough(DecoderParameters),
+    /// Encoder is created with the resolution of the first frame.
+    Decoded {
+        settings: EncoderSettings,
+        framerate: Framerate,
+        encoder: Option<(LibavH264Encoder, Resolution)>,
+        /// Frames with resolution different than the first one and RGBA frames
+        /// are dropped.
+        dropping_frames: bool,
+    },
+}
+
+struct RecordingWriter {
+    output_ctx: Output,
+    path: Arc<Path>,
+    stream: RecordedStream,
+    /// Time base of the stream in the file, `None` until the header is written.
+    time_base: Option<Rational>,
+    /// Shifts timestamps, so the first packet in the file has DTS 0.
+    pts_offset: Option<i64>,
+}
+
+impl RecordingWriter {
+    fn run(mut self, receiver: Receiver<RecordedData>) {
+        for data in receiver {
+            let result = match data {
+                RecordedData::Packet(packet) => self.write_packet(packet),
+                RecordedData::Frame(frame) => self.encode_frame(frame),
+            };
+            if let Err(err) = result {
+                error!("Failed to write recording to {:?}: {err}", self.path);
+            }
+        }
+        self.finish();
+    }
+
+    fn encode_frame(&mut self, frame: Frame) -> Result<(), RecordingError> {
+        let RecordedStream::Decoded {
+            settings,
+            framerate,
+            encoder,
+            dropping_frames,
+        } = &mut self.stream
+        else {
+            return Ok(());
+        };
+        if let FrameData::Rgba(_) = frame.data {
+            if !*dropping_frames {
+                warn!(
+                    "RGBA frames can't be encoded, frames are not recorded to {:?}.",
+                    self.path
+                );
+                *dropping_frames = true;
+            }
+            return Ok(());
+        }
+        let (encoder, resolution) = match encoder {
+            Some(encoder) => encoder,
+            None => {
+                let new_encoder = LibavH264Encoder::new(
+                    settings.clone(),
+                    frame.resolution,
+                    frame.format,
+                    *framerate,
+                    false,
+                )?;
+                encoder.insert((new_encoder, frame.resolution))
+            }
+        };
+        if *resolution != frame.resolution {
+            if !*dropping_frames {
+                warn!(
+                    "Resolution of the input changed, frames are not recorded to {:?}.",
+                    self.path
+                );
+                *dropping_frames = true;
+            }
+            return Ok(());
+        }
+
+        let packets: Vec<_> = encoder.send_frame(frame, false, &[]).collect();
+        for packet in packets {
+            self.write_packet(packet)?;
+        }
+        Ok(())
+    }
+
+    fn write_packet(&mut self, mut packet: Packet) -> Result<(), RecordingError> {
+        let time_base = match self.time_base {
+            Some(time_base) => time_base,
+            None => self.write_header()?,
+        };
+        let Some(timestamp) = packet.dts().or(packet.pts()) else {
+            return Ok(());
+        };
+        let pts_offset = *self.pts_offset.get_or_insert(-timestamp);
+        packet.set_pts(packet.pts().map(|pts| pts + pts_offset));
+        packet.set_dts(packet.dts().map(|dts| dts + pts_offset));
+        packet.set_stream(0);
+        packet.rescale_ts(PTS_TIME_BASE, time_base);
+        packet.write_interleaved(&mut self.output_ctx)?;
+        Ok(())
+    }
+
+    /// Header is written with the first packet, "creation_time" metadata is the
+    /// wall clock time of that packet, so recordings of different inputs can be
+    /// aligned with each other. Returns time base of the stream.
+    fn write_header(&mut self) -> Result<Rational, RecordingError> {
+        let mut stream = self.output_ctx.add_stream(codec::Id::None)?;
+        stream.set_time_base(PTS_TIME_BASE);
+        match &self.stream {
+            RecordedStream::Passthrough(parameters) => {
+                stream.set_parameters(codec::Parameters::from(*parameters));
+            }
+            RecordedStream::Decoded { encoder, .. } => {
+                let resolution = encoder.as_ref().map(|(_, resolution)| *resolution);
+                unsafe {
+                    let codecpar = &mut *(*stream.as_mut_ptr()).codecpar;
+                    codecpar.codec_type = ffmpeg_next::ffi::AVMediaType::AVMEDIA_TYPE_VIDEO;
+                    codecpar.codec_id = codec::Id::H264.into();
+                    if let Some(resolution) = resolution {
+                        codecpar.width = resolution.width as i32;
+                        codecpar.height = resolution.height as i32;
+                    }
+                }
+            }
+        }
+
+        let mut metadata = Dictionary::new();
+        // Parsed by ffmpeg as the current time.
+        metadata.set("creation_time", "now");
+        self.output_ctx.set_metadata(metadata);
+        self.output_ctx.write_header()?;
+
+        // Muxer can change the time base of the stream when writing the header.
+        let time_base = self.output_ctx.stream(0).unwrap().time_base();
+        self.time_base = Some(time_base);
+        Ok(time_base)
+    }
+
+    fn finish(&mut self) {
+        if let RecordedStream::Decoded {
+            encoder: Some((encoder, _)),
+            ..
+        } = &mut self.stream
+        {
+            let packets: Vec<_> = encoder.flush().collect();
+            for packet in packets {
+                if let Err(err) = self.write_packet(packet) {
+                    error!("Failed to write recording to {:?}: {err}", self.path);
+                }
+            }
+        }
+        // Nothing was recorded, the file is left without a header.
+        if self.time_base.is_none() {
+            return;
+        }
+        if let Err(err) = self.output_ctx.write_trailer() {
+            error!("Failed to finalize recording {:?}: {err}", self.path);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Splice command signaled to downstream ad-insertion systems.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scte35Splice {
+    pub event_id: u32,
+    pub kind: SpliceKind,
+    /// Time from the frame the command is sent with to the splice point.
+    pub preroll: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpliceKind {
+    /// Start of a break, leaving the network feed.
+    Out {
+        break_duration: Option<Duration>,
+        /// Downstream returns to the network feed when the break ends, without
+        /// waiting for [`SpliceKind::In`].
+        auto_return: bool,
+    },
+    /// End of a break, returning to the network feed.
+    In,
+    /// Cancels a previously sent splice with the same event id.
+    Cancel,
+}
+
+const TABLE_ID: u8 = 0xFC;
+const SPLICE_INSERT: u8 = 0x05;
+/// 90kHz clock, wrapped at 33 bits.
+const PTS_MASK: u64 = (1 << 33) - 1;
+
+/// Splice commands waiting for the next encoded frame of the output.
+#[derive(Debug, Clone, Default)]
+pub struct Scte35Queue(Arc<Mutex<VecDeque<Scte35Splice>>>);
+
+impl Scte35Queue {
+    pub fn push(&self, splice: Scte35Splice) {
+        self.0.lock().unwrap().push_back(splice);
+    }
+
+    /// Returns `splice_info_section`s of all queued commands, with splice time
+    /// relative to `pts` of the frame they are sent with.
+    pub(super) fn pop_sections(&self, pts: Duration) -> Vec<Vec<u8>> {
+        self.0
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|splice| splice_info_section(&splice, pts))
+            .collect()
+    }
+}
+
+/// Encodes `splice_info_section` (SCTE 35 2022, section 9.6) with the
+/// `splice_insert` command. Splice time is `pts` + preroll.
+fn splice_info_section(splice: &Scte35Splice, pts: Duration) -> Vec<u8> {
+    let command = splice_insert(splice, pts + splice.preroll);
+
+    let mut section = vec![TABLE_ID, 0, 0, 0];
+    // encrypted_packet, encryption_algorithm and pts_adjustment
+    section.extend_from_slice(&[0; 5]);
+    // cw_index
+    section.push(0);
+    // tier (0xFFF, not used) and splice_command_length
+    section.extend_from_slice(&[0xFF, 0xF0 | (command.len() >> 8) as u8, command.len() as u8]);
+    section.push(SPLICE_INSERT);
+    section.extend_from_slice(&command);
+    // descriptor_loop_length
+    section.extend_from_slice(&[0, 0]);
+
+    // section_length counts bytes after the field, including CRC
+    let section_length = section.len() + 4 - 3;
+    // section_syntax_indicator = 0, private_indicator = 0, sap_type = 3 (not specified)
+    section[1] = 0x30 | (section_length >> 8) as u8;
+    section[2] = section_length as u8;
+
+    let crc = crc32_mpeg2(&section);
+    section.extend_from_slice(&crc.to_be_bytes());
+    section
+}
+
+fn splice_insert(splice: &Scte35Splice, splice_time: Duration) -> Vec<u8> {
+    let mut command = splice.event_id.to_be_bytes().to_vec();
+    if splice.kind == SpliceKind::Cancel {
+        // splice_event_cancel_indicator and reserved bits
+        command.push(0xFF);
+        return command;
+    }
+    command.push(0x7F);
+
+    let (out_of_network, break_duration) = match splice.kind {
+        SpliceKind::Out {
+            break_duration,
+            auto_return,
+        } => (true, break_duration.map(|d| (d, auto_return))),
+        SpliceKind::In | SpliceKind::Cancel => (false, None),
+    };
+    // program_splice_flag = 1, splice_immediate_flag = 0, reserved bits
+    command.push(
+        ((out_of_network as u8) << 7) | (1 << 6) | ((break_duration.is_some() as u8) << 5) | 0b1111,
+    );
+    // splice_time() with time_specified_flag
+    command.extend_from_slice(&time_field(0x80, splice_time));
+    if let Some((duration, auto_return)) = break_duration {
+        command.extend_from_slice(&time_field((auto_return as u8) << 7, duration));
+    }
+    // unique_program_id, avail_num, avails_expected
+    command.extend_from_slice(&[0, 0, 0, 0]);
+    command
+}
+
+/// 33 bit time in 90kHz clock preceded by a flag in the highest bit and 6
+/// reserved bits.
+fn time_field(flag: u8, time: Duration) -> [u8; 5] {
+    let ticks = (time.as_nanos() * 90_000 / 1_000_000_000) as u64 & PTS_MASK;
+    let [_, _, _, high, bytes @ ..] = ticks.to_be_bytes();
+    [flag | 0x7E | high, bytes[0], bytes[1], bytes[2], bytes[3]]
+}
+
+fn crc32_mpeg2(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= (*byte as u32) << 24;
+        for _ in 0..8 {
+            crc = match crc & 0x8000_0000 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x04C1_1DB7,
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splice_out_section() {
+        let splice = Scte35Splice {
+            event_id: 0x1234,
+            kind: SpliceKind::Out {
+                break_duration: Some(Duration::from_secs(30)),
+                auto_return: true,
+            },
+            preroll: Duration::from_secs(4),
+        };
+        let section = splice_info_section(&splice, Duration::from_secs(10));
+
+        assert_eq!(section.len(), 3 + 0x25);
+        assert_eq!(section[..3], [0xFC, 0x30, 0x25]);
+        assert_eq!(section[13], SPLICE_INSERT);
+        assert_eq!(section[14..18], [0x00, 0x00, 0x12, 0x34]);
+        // out_of_network, program_splice and duration flags
+        assert_eq!(section[19], 0b1110_1111);
+        // splice time: 14s * 90kHz = 1_260_000
+        assert_eq!(section[20..25], [0xFE, 0x00, 0x13, 0x39, 0xE0]);
+        // break duration: 30s * 90kHz = 2_700_000 with auto_return
+        assert_eq!(section[25..30], [0xFE, 0x00, 0x29, 0x32, 0xE0]);
+        // CRC of the section including its CRC is 0
+        assert_eq!(crc32_mpeg2(&section), 0);
+    }
+
+    #[test]
+    fn cancel_section() {
+        let splice = Scte35Splice {
+            event_id: 7,
+            kind: SpliceKind::Cancel,
+            preroll: Duration::ZERO,
+        };
+        let section = splice_info_section(&splice, Duration::from_secs(10));
+
+        assert_eq!(section[11..13], [0xF0, 0x05]);
+        assert_eq!(section[14..19], [0x00, 0x00, 0x00, 0x07, 0xFF]);
+        assert_eq!(crc32_mpeg2(&section), 0);
+    }
+}
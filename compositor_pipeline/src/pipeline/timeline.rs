@@ -0,0 +1,323 @@
+use std::{sync::Arc, time::Duration};
+
+use compositor_common::scene::{
+    builtin_transformations::BuiltinSpec,
+    transition::{Interpolation, TransitionSpec, TransitionType},
+    NodeId, NodeParams, NodeSpec, OutputId, SceneSpec,
+};
+
+use crate::error::TimelineError;
+
+/// Scenes applied one after another, following the PTS of rendered frames.
+#[derive(Debug, Clone)]
+pub struct TimelineSpec {
+    pub entries: Vec<TimelineEntry>,
+    /// Start again from the first entry after the last one ends. Otherwise the
+    /// scene of the last entry is kept after the timeline ends.
+    pub loop_playback: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimelineEntry {
+    pub scene: Arc<SceneSpec>,
+    pub duration: Duration,
+    pub transition: Option<EntryTransition>,
+}
+
+/// Builtin nodes of the entry that exist in the previous entry (with the same id
+/// and a compatible transformation) are animated from their previous params.
+#[derive(Debug, Clone)]
+pub struct EntryTransition {
+    pub duration: Duration,
+    pub interpolation: Interpolation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimelineStatus {
+    pub entry_index: usize,
+    /// Position in the current pass of the timeline.
+    pub position: Duration,
+    pub paused: bool,
+    /// Timeline is not looped and the last entry already ended.
+    pub finished: bool,
+}
+
+pub(super) struct Timeline {
+    /// Scenes of entries with transitions from the previous entry.
+    scenes: Vec<Arc<SceneSpec>>,
+    /// Scene of the first entry with transitions from the last entry, used when
+    /// the timeline starts another pass.
+    looped_first_scene: Option<Arc<SceneSpec>>,
+    loop_playback: bool,
+    /// End of each entry, relative to the start of the timeline.
+    entry_ends: Vec<Duration>,
+    /// Position from the start of the first pass, it's not wrapped when the
+    /// timeline is looped.
+    position: Duration,
+    paused: bool,
+    /// PTS of the last rendered frame, `None` until the first frame after the
+    /// timeline started.
+    last_pts: Option<Duration>,
+    /// Entry and pass with the scene currently applied, `None` if the scene has
+    /// to be applied on the next frame.
+    current_entry: Option<(usize, u64)>,
+}
+
+impl Timeline {
+    pub fn new(spec: TimelineSpec) -> Result<Self, TimelineError> {
+        let entries = &spec.entries;
+        let (Some(first), Some(last)) = (entries.first(), entries.last()) else {
+            return Err(TimelineError::NoEntries);
+        };
+        if let Some(index) = entries.iter().position(|entry| entry.duration.is_zero()) {
+            return Err(TimelineError::ZeroDuration(index));
+        }
+
+        let scenes = entries
+            .iter()
+            .enumerate()
+            .map(
+                |(index, entry)| match (index.checked_sub(1), &entry.transition) {
+                    (Some(previous), Some(transition)) => Arc::new(with_transition(
+                        &entry.scene,
+                        &entries[previous].scene,
+                        transition,
+                    )),
+                    _ => entry.scene.clone(),
+                },
+            )
+            .collect();
+        let looped_first_scene = match &first.transition {
+            Some(transition) if spec.loop_playback => Some(Arc::new(with_transition(
+                &first.scene,
+                &last.scene,
+                transition,
+            ))),
+            _ => None,
+        };
+        let entry_ends = entries
+            .iter()
+            .scan(Duration::ZERO, |end, entry| {
+                *end += entry.duration;
+                Some(*end)
+            })
+            .collect();
+
+        Ok(Self {
+            scenes,
+            looped_first_scene,
+            loop_playback: spec.loop_playback,
+            entry_ends,
+            position: Duration::ZERO,
+            paused: false,
+            last_pts: None,
+            current_entry: None,
+        })
+    }
+
+    /// Scenes that can be applied, with indexes of their entries.
+    pub fn scenes(&self) -> impl Iterator<Item = (usize, &Arc<SceneSpec>)> {
+        let looped_first_scene = self.looped_first_scene.iter().map(|scene| (0, scene));
+        self.scenes.iter().enumerate().chain(looped_first_scene)
+    }
+
+    /// Input or node is used by the scene of any entry.
+    pub fn uses_node(&self, node_id: &NodeId) -> bool {
+        self.scenes().any(|(_, scene)| {
+            scene
+                .nodes
+                .iter()
+                .flat_map(|node| &node.input_pads)
+                .any(|input_pad| input_pad == node_id)
+        })
+    }
+
+    pub fn uses_output(&self, output_id: &OutputId) -> bool {
+        self.scenes()
+            .any(|(_, scene)| scene.outputs.iter().any(|o| &o.output_id == output_id))
+    }
+
+    /// Called before a frame with the PTS is rendered. Returns the entry index and
+    /// its scene if the entry changed since the previous frame.
+    pub fn advance(&mut self, pts: Duration) -> Option<(usize, Arc<SceneSpec>)> {
+        if let (Some(last_pts), false) = (self.last_pts, self.paused) {
+            self.position += pts.saturating_sub(last_pts);
+        }
+        self.last_pts = Some(pts);
+
+        let (entry_index, pass) = self.entry_at(self.position);
+        if self.current_entry == Some((entry_index, pass)) {
+            return None;
+        }
+        self.current_entry = Some((entry_index, pass));
+        let scene = match (&self.looped_first_scene, entry_index, pass) {
+            (Some(looped_first_scene), 0, 1..) => looped_first_scene.clone(),
+            _ => self.scenes[entry_index].clone(),
+        };
+        Some((entry_index, scene))
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Position is relative to the start of the timeline. Scene of the entry
+    /// is applied again on the next frame, even if the entry didn't change.
+    pub fn seek(&mut self, position: Duration) {
+        self.position = position;
+        self.current_entry = None;
+    }
+
+    pub fn status(&self) -> TimelineStatus {
+        let (entry_index, pass) = self.entry_at(self.position);
+        let total = self.duration();
+        let finished = !self.loop_playback && self.position >= total;
+        let position = if finished {
+            total
+        } else {
+            self.position - total * pass as u32
+        };
+        TimelineStatus {
+            entry_index,
+            position,
+            paused: self.paused,
+            finished,
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        self.entry_ends[self.entry_ends.len() - 1]
+    }
+
+    /// Returns the entry index and the number of the timeline pass. Last entry
+    /// is kept after a timeline that is not looped ends.
+    fn entry_at(&self, position: Duration) -> (usize, u64) {
+        let total = self.duration().as_nanos();
+        let (pass, position) = if self.loop_playback {
+            (
+                (position.as_nanos() / total) as u64,
+                position.as_nanos() % total,
+            )
+        } else {
+            (0, position.as_nanos())
+        };
+        let entry_index = self
+            .entry_ends
+            .iter()
+            .position(|end| position < end.as_nanos())
+            .unwrap_or(self.entry_ends.len() - 1);
+        (entry_index, pass)
+    }
+}
+
+/// Replaces params of builtin nodes that were present in the previous scene with
+/// transitions from the previous params.
+fn with_transition(
+    scene: &SceneSpec,
+    previous_scene: &SceneSpec,
+    transition: &EntryTransition,
+) -> SceneSpec {
+    let nodes = scene
+        .nodes
+        .iter()
+        .map(|node| {
+            let NodeParams::Builtin(end) = &node.params else {
+                return node.clone();
+            };
+            let start = previous_scene
+                .nodes
+                .iter()
+                .find(|previous| previous.node_id == node.node_id)
+                .and_then(|previous| match &previous.params {
+                    NodeParams::Builtin(start) => Some(start),
+                    NodeParams::Transition(TransitionSpec { end, .. }) => Some(end),
+                    _ => None,
+                });
+            match start {
+                Some(start) if start != end && is_transition_supported(start, end) => NodeSpec {
+                    params: NodeParams::Transition(TransitionSpec {
+                        start: start.clone(),
+                        end: end.clone(),
+                        transition_duration: transition.duration,
+                        interpolation: transition.interpolation.clone(),
+                    }),
+                    ..node.clone()
+                },
+                _ => node.clone(),
+            }
+        })
+        .collect();
+    SceneSpec {
+        nodes,
+        outputs: scene.outputs.clone(),
+    }
+}
+
+fn is_transition_supported(start: &BuiltinSpec, end: &BuiltinSpec) -> bool {
+    TransitionType::try_from((start, end)).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline(durations_ms: &[u64], loop_playback: bool) -> Timeline {
+        let entries = durations_ms
+            .iter()
+            .map(|duration_ms| TimelineEntry {
+                scene: Arc::new(SceneSpec {
+                    nodes: vec![],
+                    outputs: vec![],
+                }),
+                duration: Duration::from_millis(*duration_ms),
+                transition: None,
+            })
+            .collect();
+        Timeline::new(TimelineSpec {
+            entries,
+            loop_playback,
+        })
+        .unwrap()
+    }
+
+    fn advance_ms(timeline: &mut Timeline, pts_ms: u64) -> Option<usize> {
+        timeline
+            .advance(Duration::from_millis(pts_ms))
+            .map(|(entry_index, _)| entry_index)
+    }
+
+    #[test]
+    fn entries_follow_pts() {
+        let mut timeline = timeline(&[100, 200], false);
+        // timeline starts with the first rendered frame, not at PTS 0
+        assert_eq!(advance_ms(&mut timeline, 1000), Some(0));
+        assert_eq!(advance_ms(&mut timeline, 1050), None);
+        assert_eq!(advance_ms(&mut timeline, 1100), Some(1));
+        assert_eq!(advance_ms(&mut timeline, 1500), None);
+        assert!(timeline.status().finished);
+        assert_eq!(timeline.status().entry_index, 1);
+    }
+
+    #[test]
+    fn looped_timeline_reapplies_first_entry() {
+        let mut timeline = timeline(&[100, 100], true);
+        assert_eq!(advance_ms(&mut timeline, 0), Some(0));
+        assert_eq!(advance_ms(&mut timeline, 100), Some(1));
+        assert_eq!(advance_ms(&mut timeline, 200), Some(0));
+        assert_eq!(timeline.status().position, Duration::ZERO);
+    }
+
+    #[test]
+    fn paused_timeline_keeps_position() {
+        let mut timeline = timeline(&[100, 100], false);
+        assert_eq!(advance_ms(&mut timeline, 0), Some(0));
+        timeline.set_paused(true);
+        assert_eq!(advance_ms(&mut timeline, 500), None);
+        timeline.set_paused(false);
+        assert_eq!(advance_ms(&mut timeline, 550), None);
+        assert_eq!(timeline.status().position, Duration::from_millis(50));
+
+        timeline.seek(Duration::from_millis(150));
+        assert_eq!(advance_ms(&mut timeline, 560), Some(1));
+    }
+}
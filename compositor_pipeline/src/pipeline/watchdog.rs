@@ -0,0 +1,152 @@
+use compositor_common::util::colors::RGBColor;
+use log::{debug, error, info, warn};
+
+use super::{
+    events::{PipelineEvent, PipelineEvents},
+    placeholder::PlaceholderFrames,
+};
+
+/// What happens when rendering fails repeatedly.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderFailurePolicy {
+    /// Number of consecutive failed frames after which `action` is taken and the
+    /// [`PipelineEvent::RenderFailing`] event is emitted.
+    pub max_consecutive_failures: u32,
+    pub action: RenderFailureAction,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum RenderFailureAction {
+    /// Outputs don't receive frames until rendering recovers.
+    #[default]
+    SkipFrames,
+    /// Outputs receive frames filled with the color until rendering recovers.
+    Slate { color: RGBColor },
+    /// Rendering stops and outputs are finished, the pipeline does not recover.
+    Stop,
+}
+
+impl Default for RenderFailurePolicy {
+    fn default() -> Self {
+        Self {
+            max_consecutive_failures: 10,
+            action: RenderFailureAction::default(),
+        }
+    }
+}
+
+/// What the render loop should do with a frame that failed to render.
+pub(super) enum RenderFailureOutcome<'a> {
+    SkipFrame,
+    SendSlate(&'a mut PlaceholderFrames),
+    StopRendering,
+}
+
+/// Tracks render failures and decides how outputs are affected. Rendering is
+/// still attempted for every frame (unless the policy stops it), outputs switch
+/// back as soon as a frame is rendered successfully.
+pub(super) struct RenderWatchdog {
+    policy: RenderFailurePolicy,
+    consecutive_failures: u32,
+    slate: Option<PlaceholderFrames>,
+    events: PipelineEvents,
+}
+
+impl RenderWatchdog {
+    pub fn new(policy: RenderFailurePolicy, events: PipelineEvents) -> Self {
+        let slate = match policy.action {
+            RenderFailureAction::Slate { color } => Some(PlaceholderFrames::new(color)),
+            RenderFailureAction::SkipFrames | RenderFailureAction::Stop => None,
+        };
+        Self {
+            policy,
+            consecutive_failures: 0,
+            slate,
+            events,
+        }
+    }
+
+    pub fn on_success(&mut self) {
+        if self.is_failing() {
+            info!(
+                "Rendering recovered after {} failed frames.",
+                self.consecutive_failures
+            );
+            self.events.push(PipelineEvent::RenderRecovered {
+                failed_frames: self.consecutive_failures,
+            });
+        }
+        self.consecutive_failures = 0;
+    }
+
+    /// `error` describes why the frame was not rendered. Failures are logged
+    /// only until the policy kicks in, so a broken renderer doesn't flood logs.
+    pub fn on_failure(&mut self, error: String) -> RenderFailureOutcome<'_> {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if !self.is_failing() {
+            error!("Error while rendering: {error}");
+            return RenderFailureOutcome::SkipFrame;
+        }
+        if self.consecutive_failures == self.policy.max_consecutive_failures {
+            warn!(
+                "Rendering failed {} times in a row, applying {:?} policy. Last error: {error}",
+                self.consecutive_failures, self.policy.action
+            );
+            self.events.push(PipelineEvent::RenderFailing {
+                consecutive_failures: self.consecutive_failures,
+                error,
+            });
+        } else {
+            debug!("Error while rendering: {error}");
+        }
+
+        match (self.policy.action, self.slate.as_mut()) {
+            (RenderFailureAction::Slate { .. }, Some(slate)) => {
+                RenderFailureOutcome::SendSlate(slate)
+            }
+            (RenderFailureAction::Stop, _) => RenderFailureOutcome::StopRendering,
+            _ => RenderFailureOutcome::SkipFrame,
+        }
+    }
+
+    fn is_failing(&self) -> bool {
+        self.consecutive_failures >= self.policy.max_consecutive_failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emits_events_when_policy_kicks_in_and_on_recovery() {
+        let events = PipelineEvents::default();
+        let policy = RenderFailurePolicy {
+            max_consecutive_failures: 2,
+            action: RenderFailureAction::Stop,
+        };
+        let mut watchdog = RenderWatchdog::new(policy, events.clone());
+
+        assert!(matches!(
+            watchdog.on_failure("first".into()),
+            RenderFailureOutcome::SkipFrame
+        ));
+        assert_eq!(events.take(), vec![]);
+        assert!(matches!(
+            watchdog.on_failure("second".into()),
+            RenderFailureOutcome::StopRendering
+        ));
+        watchdog.on_success();
+
+        assert_eq!(
+            events.take(),
+            vec![
+                PipelineEvent::RenderFailing {
+                    consecutive_failures: 2,
+                    error: "second".into()
+                },
+                PipelineEvent::RenderRecovered { failed_frames: 2 }
+            ]
+        );
+    }
+}
@@ -1,10 +1,12 @@
+mod framerate_conversion;
 mod internal_queue;
 mod queue_thread;
 
 use std::{
-    sync::{Arc, Mutex},
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex},
     thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
 use compositor_common::{error::ErrorStack, scene::InputId, Frame, Framerate};
@@ -15,13 +17,73 @@ use thiserror::Error;
 
 use self::{internal_queue::InternalQueue, queue_thread::QueueThread};
 
+pub use self::framerate_conversion::FramerateConversion;
+
 #[derive(Error, Debug)]
 pub enum QueueError {
     #[error("the input id `{:#?}` is unknown", 0)]
     UnknownInputId(InputId),
 }
 
-const DEFAULT_BUFFER_DURATION: Duration = Duration::from_millis(16 * 5); // about 5 frames at 60 fps
+pub const DEFAULT_BUFFER_DURATION: Duration = Duration::from_millis(16 * 5); // about 5 frames at 60 fps
+
+/// In offline mode, decoders are blocked when their input has that many frames
+/// waiting, so whole files are not decoded into memory.
+const MAX_OFFLINE_INPUT_QUEUE_LEN: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Default time each input is buffered for before its first frame is used.
+    /// Larger values absorb more network jitter at the cost of latency.
+    pub buffer_duration: Duration,
+    pub sync_policy: SyncPolicy,
+    /// Default for inputs that do not specify [`InputOptions::max_lateness`].
+    pub max_input_lateness: Option<Duration>,
+    /// Produce frame sets as soon as all inputs delivered frames for them, without
+    /// wall clock pacing. Inputs are not buffered and start at PTS=0, late frames
+    /// are never dropped, and the queue stops when all inputs ended.
+    pub offline: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            buffer_duration: DEFAULT_BUFFER_DURATION,
+            sync_policy: SyncPolicy::default(),
+            max_input_lateness: None,
+            offline: false,
+        }
+    }
+}
+
+/// Decides when a set of frames is produced if some inputs did not deliver a
+/// frame for it yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncPolicy {
+    /// Produce frames on schedule, using the latest frames that already arrived
+    /// from each input. Late inputs stutter, but never delay other inputs.
+    #[default]
+    RenderAvailable,
+    /// Wait until every input delivered a frame, but no longer than max lateness
+    /// of that input (buffer duration if not set). Late inputs delay all outputs.
+    /// Inputs that did not send any frame yet are not waited for.
+    WaitForAllInputs,
+}
+
+/// Per input settings of the queue.
+#[derive(Debug, Clone, Default)]
+pub struct InputOptions {
+    pub framerate_conversion: FramerateConversion,
+    /// Overrides [`Options::buffer_duration`] for this input.
+    pub buffer_duration: Option<Duration>,
+    /// Frames that arrive later than that after their presentation time are
+    /// dropped. Overrides [`Options::max_input_lateness`] for this input.
+    pub max_lateness: Option<Duration>,
+    /// Align frames with other inputs that enable this option based on the sender
+    /// clock (RTCP sender reports), instead of the arrival time of the first frame.
+    /// Senders' clocks have to be synchronized (e.g. with NTP).
+    pub sync_with_sender_clock: bool,
+}
 
 /// Queue is responsible for consuming frames from different inputs and producing
 /// sets of frames from all inputs in a single batch.
@@ -36,13 +98,17 @@ const DEFAULT_BUFFER_DURATION: Duration = Duration::from_millis(16 * 5); // abou
 pub struct Queue {
     internal_queue: Mutex<InternalQueue>,
     check_queue_channel: (Sender<()>, Receiver<()>),
-    output_framerate: Framerate,
+    /// Can be changed while the queue is running. The queue thread continues
+    /// the PTS timeline of already produced frames with the new interval.
+    output_framerate: Mutex<Framerate>,
 
-    /// - When new input is connected and sends the first frame we want to wait
-    /// buffer_duration before sending first frame of that input.
-    /// - When pipeline is started we want to start with a frame that was receive
-    /// `buffer_duration` time ago
-    buffer_duration: Duration,
+    sync_policy: SyncPolicy,
+    offline: bool,
+    /// Notified when the queue thread takes frames from input queues. Used to
+    /// block decoders in offline mode.
+    frames_consumed: Condvar,
+    /// Wall clock time when the last frame of each input was enqueued.
+    last_frame_times: Mutex<HashMap<InputId, Instant>>,
 
     /// Base time that is used to synchronize PTS value of received frame to
     /// the same clock. When enqueueing the frame we are modifying it's PTS
@@ -54,52 +120,98 @@ pub struct Queue {
 }
 
 impl Queue {
-    pub fn new(output_framerate: Framerate) -> Self {
+    pub fn new(output_framerate: Framerate, opts: Options) -> Self {
         Queue {
-            internal_queue: Mutex::new(InternalQueue::new()),
+            internal_queue: Mutex::new(InternalQueue::new(opts.clone())),
             check_queue_channel: unbounded(),
-            output_framerate,
-            buffer_duration: DEFAULT_BUFFER_DURATION,
+            output_framerate: Mutex::new(output_framerate),
+            sync_policy: opts.sync_policy,
+            offline: opts.offline,
+            frames_consumed: Condvar::new(),
+            last_frame_times: Mutex::new(HashMap::new()),
             clock_start: Instant::now(),
         }
     }
 
-    pub fn add_input(&self, input_id: InputId) {
-        self.internal_queue.lock().unwrap().add_input(input_id);
+    pub fn add_input(&self, input_id: InputId, opts: InputOptions) {
+        self.internal_queue
+            .lock()
+            .unwrap()
+            .add_input(input_id, opts);
     }
 
     pub fn remove_input(&self, input_id: &InputId) {
         self.internal_queue.lock().unwrap().remove_input(input_id);
+        self.last_frame_times.lock().unwrap().remove(input_id);
+        self.frames_consumed.notify_all();
+    }
+
+    /// Time elapsed since the last frame of the input was enqueued, `None` if it
+    /// didn't deliver any frame yet.
+    pub fn time_since_last_frame(&self, input_id: &InputId) -> Option<Duration> {
+        self.last_frame_times
+            .lock()
+            .unwrap()
+            .get(input_id)
+            .map(Instant::elapsed)
     }
 
     pub fn start(self: &Arc<Self>, sender: Sender<FrameSet<InputId>>) {
         let queue = self.clone();
-        let tick_duration = self.output_framerate.get_interval_duration();
 
         QueueThread::new(
             queue,
             sender,
             queue_thread::Options {
-                tick_duration,
-                buffer_duration: self.buffer_duration,
-                output_framerate: self.output_framerate,
+                sync_policy: self.sync_policy,
+                offline: self.offline,
                 clock_start: self.clock_start,
             },
         )
         .spawn();
     }
 
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    pub fn output_framerate(&self) -> Framerate {
+        *self.output_framerate.lock().unwrap()
+    }
+
+    /// Changes the framerate of produced frame sets. Takes effect from the next
+    /// frame set, PTS of frames that were already produced are not affected.
+    pub fn set_output_framerate(&self, framerate: Framerate) {
+        *self.output_framerate.lock().unwrap() = framerate;
+        self.check_queue_channel.0.send(()).unwrap();
+    }
+
+    /// Instant that corresponds to PTS=0 of frames produced by the queue.
+    pub fn clock_start(&self) -> Instant {
+        self.clock_start
+    }
+
     pub fn enqueue_frame(&self, input_id: InputId, frame: Frame) -> Result<(), QueueError> {
-        let is_first_frame_for_input = !self
-            .internal_queue
+        self.last_frame_times
             .lock()
             .unwrap()
-            .did_receive_frame(&input_id);
+            .insert(input_id.clone(), Instant::now());
+        if self.offline {
+            return self.enqueue_offline_frame(input_id, frame);
+        }
+
+        let (is_first_frame_for_input, buffer_duration) = {
+            let internal_queue = self.internal_queue.lock().unwrap();
+            (
+                !internal_queue.did_receive_frame(&input_id),
+                internal_queue.buffer_duration(&input_id),
+            )
+        };
         if is_first_frame_for_input {
-            // Sleep here ensures that we will buffer `self.buffer_duration` on each input.
+            // Sleep here ensures that we will buffer `buffer_duration` on each input.
             // It also makes calculation easier because PTS of frames will be already offset
             // by a correct value.
-            thread::sleep(self.buffer_duration);
+            thread::sleep(buffer_duration);
         }
 
         let mut internal_queue = self.internal_queue.lock().unwrap();
@@ -109,9 +221,7 @@ impl Queue {
         // We don't know when pipeline is started, so we can't resolve real_next_pts,
         // but we can remove frames based on estimated PTS. This only works if queue
         // is able to push frames in real time and is never behind more than one frame.
-        let framerate_tick = Duration::from_secs_f64(
-            self.output_framerate.den as f64 / self.output_framerate.num as f64,
-        );
+        let framerate_tick = self.output_framerate().get_interval_duration();
         let estimated_pts = self.clock_start.elapsed() - framerate_tick;
         if let Err(err) = internal_queue.drop_old_frames_by_input_id(&input_id, estimated_pts) {
             error!(
@@ -126,6 +236,44 @@ impl Queue {
         Ok(())
     }
 
+    /// Blocks until the queue thread consumes frames if the input is too far ahead
+    /// of the output. PTS are not related to the wall clock, so frames are not
+    /// dropped based on time.
+    fn enqueue_offline_frame(&self, input_id: InputId, frame: Frame) -> Result<(), QueueError> {
+        let mut internal_queue = self.internal_queue.lock().unwrap();
+        internal_queue.drop_offline_frames(&input_id)?;
+        let mut internal_queue = self
+            .frames_consumed
+            .wait_while(internal_queue, |internal_queue| {
+                internal_queue.input_queue_len(&input_id) >= MAX_OFFLINE_INPUT_QUEUE_LEN
+            })
+            .unwrap();
+        internal_queue.enqueue_offline_frame(input_id, frame)?;
+
+        self.check_queue_channel.0.send(()).unwrap();
+        Ok(())
+    }
+
+    /// Called when the input will not produce any more frames (e.g. end of file).
+    /// Queue does not wait for that input anymore.
+    pub fn mark_input_ended(&self, input_id: &InputId) {
+        self.internal_queue
+            .lock()
+            .unwrap()
+            .mark_input_ended(input_id);
+        self.check_queue_channel.0.send(()).unwrap();
+    }
+
+    /// Re-timestamps frames of the input, so frames captured at the same sender
+    /// wall clock time have the same PTS on all inputs synced to sender clock.
+    /// `pts_zero_time` is the sender wall clock time of the input frame with PTS=0.
+    pub fn sync_input_to_sender_clock(&self, input_id: &InputId, pts_zero_time: SystemTime) {
+        self.internal_queue
+            .lock()
+            .unwrap()
+            .sync_to_sender_clock(input_id, pts_zero_time);
+    }
+
     pub fn subscribe_input_listener(&self, input_id: InputId, callback: Box<dyn FnOnce() + Send>) {
         self.internal_queue
             .lock()
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use compositor_common::{
+    frame::{FrameData, Nv12Data, PixelFormat, YuvData},
+    Frame,
+};
+
+/// Strategy used to pick a frame of an input for each output frame when input
+/// and output framerates differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FramerateConversion {
+    /// Use the input frame with PTS closest to the output frame PTS. Cheap, but
+    /// input frames can be shown for an uneven number of output frames when
+    /// timestamps jitter around the midpoint between output frames.
+    #[default]
+    Nearest,
+    /// Mix two input frames surrounding the output frame PTS, weighted by their
+    /// distance from it. Smooths motion at the cost of ghosting and CPU time.
+    Blend,
+    /// Use the latest input frame with PTS not later than the output frame PTS.
+    /// Frames are dropped or duplicated with an even cadence (e.g. 3:2 pulldown
+    /// for 24 fps input and 60 fps output).
+    DropDuplicate,
+}
+
+/// Index of the frame that should be at the front of the input queue when
+/// rendering an output frame with `pts`. Frames before it are not needed anymore.
+pub(super) fn first_needed_frame(
+    conversion: FramerateConversion,
+    frames: &[Frame],
+    pts: Duration,
+) -> Option<usize> {
+    match conversion {
+        FramerateConversion::Nearest => frames
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, frame)| frame.pts.as_nanos().abs_diff(pts.as_nanos()))
+            .map(|(index, _)| index),
+        FramerateConversion::Blend | FramerateConversion::DropDuplicate => {
+            match frames.iter().rposition(|frame| frame.pts <= pts) {
+                Some(index) => Some(index),
+                None if frames.is_empty() => None,
+                None => Some(0),
+            }
+        }
+    }
+}
+
+/// Produces a frame for output `pts` from the input queue. Expects frames that
+/// are not needed anymore to be already dropped.
+pub(super) fn convert(
+    conversion: FramerateConversion,
+    frames: &[Frame],
+    pts: Duration,
+) -> Option<Frame> {
+    let first = frames.first()?;
+    if conversion != FramerateConversion::Blend {
+        return Some(first.clone());
+    }
+
+    let Some(second) = frames.get(1) else {
+        return Some(first.clone());
+    };
+    let can_blend = first.pts <= pts
+        && pts < second.pts
+        && first.resolution == second.resolution
+        && first.format == second.format;
+    if !can_blend {
+        return Some(first.clone());
+    }
+
+    let weight = (pts - first.pts).as_secs_f64() / (second.pts - first.pts).as_secs_f64();
+    Some(blend(first, second, weight as f32).unwrap_or_else(|| first.clone()))
+}
+
+/// Mixes two frames with the same resolution and format. `weight` is a weight
+/// of the second frame. Returns `None` if data of the frames has different
+/// layouts (e.g. the decoder switched from NV12 to planar YUV).
+fn blend(first: &Frame, second: &Frame, weight: f32) -> Option<Frame> {
+    let pixel_format = first.format.pixel_format;
+    let blend_plane = |a: &Bytes, b: &Bytes| match pixel_format {
+        PixelFormat::Yuv420p => blend_u8_plane(a, b, weight),
+        PixelFormat::Yuv420p10le => blend_u16le_plane(a, b, weight),
+    };
+
+    let data = match (&first.data, &second.data) {
+        (FrameData::PlanarYuv(a), FrameData::PlanarYuv(b)) => FrameData::PlanarYuv(YuvData {
+            y_plane: blend_plane(&a.y_plane, &b.y_plane),
+            u_plane: blend_plane(&a.u_plane, &b.u_plane),
+            v_plane: blend_plane(&a.v_plane, &b.v_plane),
+        }),
+        (FrameData::Nv12(a), FrameData::Nv12(b)) => FrameData::Nv12(Nv12Data {
+            y_plane: blend_u8_plane(&a.y_plane, &b.y_plane, weight),
+            uv_plane: blend_u8_plane(&a.uv_plane, &b.uv_plane, weight),
+        }),
+        (FrameData::Rgba(a), FrameData::Rgba(b)) => FrameData::Rgba(blend_u8_plane(a, b, weight)),
+        _ => return None,
+    };
+    Some(Frame {
+        data,
+        resolution: first.resolution,
+        format: first.format,
+        pts: first.pts,
+    })
+}
+
+fn blend_u8_plane(a: &[u8], b: &[u8], weight: f32) -> Bytes {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| lerp(*a as f32, *b as f32, weight) as u8)
+        .collect()
+}
+
+fn blend_u16le_plane(a: &[u8], b: &[u8], weight: f32) -> Bytes {
+    a.chunks_exact(2)
+        .zip(b.chunks_exact(2))
+        .flat_map(|(a, b)| {
+            let a = u16::from_le_bytes([a[0], a[1]]);
+            let b = u16::from_le_bytes([b[0], b[1]]);
+            (lerp(a as f32, b as f32, weight) as u16).to_le_bytes()
+        })
+        .collect()
+}
+
+fn lerp(a: f32, b: f32, weight: f32) -> f32 {
+    (a + (b - a) * weight).round()
+}
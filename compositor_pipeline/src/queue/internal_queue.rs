@@ -2,11 +2,15 @@ use compositor_common::scene::InputId;
 use compositor_common::Frame;
 use compositor_render::FrameSet;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 use std::time::Instant;
+use std::time::SystemTime;
 
-use super::QueueError;
+use log::{debug, info};
+
+use super::framerate_conversion::{self, FramerateConversion};
+use super::{InputOptions, Options, QueueError};
 
 pub struct InternalQueue {
     /// frames are PTS ordered. PTS include timestamps offsets
@@ -15,24 +19,78 @@ pub struct InternalQueue {
     /// offsets that normalize input pts to zero relative to the
     /// Queue:clock_start value.
     timestamp_offsets: HashMap<InputId, Duration>,
+    inputs_options: HashMap<InputId, InputOptions>,
+    /// Defaults for values not specified in `inputs_options`.
+    opts: Options,
+    /// Sender wall clock time and the PTS it is mapped to. Established by the first
+    /// input synced to sender clock, so its frames are not re-timestamped.
+    sender_clock_origin: Option<(SystemTime, Duration)>,
+    /// Inputs that will not produce any more frames.
+    ended_inputs: HashSet<InputId>,
+    /// PTS of the next frame set in offline mode. Frames older than needed for
+    /// it can be dropped when new frames are enqueued.
+    next_offline_pts: Duration,
 }
 
 impl InternalQueue {
-    pub fn new() -> Self {
+    pub fn new(opts: Options) -> Self {
         InternalQueue {
             inputs_queues: HashMap::new(),
             inputs_listeners: HashMap::new(),
             timestamp_offsets: HashMap::new(),
+            inputs_options: HashMap::new(),
+            opts,
+            sender_clock_origin: None,
+            ended_inputs: HashSet::new(),
+            next_offline_pts: Duration::ZERO,
         }
     }
 
-    pub fn add_input(&mut self, input_id: InputId) {
-        self.inputs_queues.insert(input_id, Vec::new());
+    pub fn add_input(&mut self, input_id: InputId, opts: InputOptions) {
+        self.inputs_queues.insert(input_id.clone(), Vec::new());
+        self.inputs_options.insert(input_id, opts);
     }
 
     pub fn remove_input(&mut self, input_id: &InputId) {
         self.inputs_queues.remove(input_id);
         self.timestamp_offsets.remove(input_id);
+        self.inputs_options.remove(input_id);
+        self.ended_inputs.remove(input_id);
+    }
+
+    pub fn mark_input_ended(&mut self, input_id: &InputId) {
+        if self.inputs_queues.contains_key(input_id) {
+            self.ended_inputs.insert(input_id.clone());
+        }
+    }
+
+    pub fn input_queue_len(&self, input_id: &InputId) -> usize {
+        self.inputs_queues.get(input_id).map_or(0, Vec::len)
+    }
+
+    fn framerate_conversion(&self, input_id: &InputId) -> FramerateConversion {
+        self.inputs_options
+            .get(input_id)
+            .map(|opts| opts.framerate_conversion)
+            .unwrap_or_default()
+    }
+
+    /// - When new input is connected and sends the first frame we want to wait
+    ///   buffer_duration before sending first frame of that input.
+    /// - When pipeline is started we want to start with a frame that was receive
+    ///   `buffer_duration` time ago
+    pub fn buffer_duration(&self, input_id: &InputId) -> Duration {
+        self.inputs_options
+            .get(input_id)
+            .and_then(|opts| opts.buffer_duration)
+            .unwrap_or(self.opts.buffer_duration)
+    }
+
+    fn max_lateness(&self, input_id: &InputId) -> Option<Duration> {
+        self.inputs_options
+            .get(input_id)
+            .and_then(|opts| opts.max_lateness)
+            .or(self.opts.max_input_lateness)
     }
 
     pub fn did_receive_frame(&self, input_id: &InputId) -> bool {
@@ -45,34 +103,125 @@ impl InternalQueue {
         mut frame: Frame,
         clock_start: Instant,
     ) -> Result<(), QueueError> {
+        let max_lateness = self.max_lateness(&input_id);
         let Some(input_queue) = self.inputs_queues.get_mut(&input_id) else {
             return Err(QueueError::UnknownInputId(input_id));
         };
 
         let offset = *self
             .timestamp_offsets
-            .entry(input_id)
+            .entry(input_id.clone())
             .or_insert_with(|| clock_start.elapsed().saturating_sub(frame.pts));
 
         // Modify frame pts to be at the time frame where PTS=0 represent clock_start
         frame.pts += offset;
 
+        let lateness = clock_start.elapsed().saturating_sub(frame.pts);
+        if max_lateness.is_some_and(|max_lateness| lateness > max_lateness) {
+            debug!("Dropping frame on input {input_id}: arrived {lateness:?} late.");
+            return Ok(());
+        }
+
+        input_queue.push(frame);
+        Ok(())
+    }
+
+    /// Frames produced by decoders already start at PTS=0, so in offline mode
+    /// they are enqueued without any offset.
+    pub fn enqueue_offline_frame(
+        &mut self,
+        input_id: InputId,
+        frame: Frame,
+    ) -> Result<(), QueueError> {
+        let Some(input_queue) = self.inputs_queues.get_mut(&input_id) else {
+            return Err(QueueError::UnknownInputId(input_id));
+        };
         input_queue.push(frame);
+        self.timestamp_offsets.insert(input_id, Duration::ZERO);
         Ok(())
     }
 
-    /// Gets frames closest to buffer pts.
+    pub fn drop_offline_frames(&mut self, input_id: &InputId) -> Result<(), QueueError> {
+        self.drop_old_frames_by_input_id(input_id, self.next_offline_pts)
+    }
+
+    pub fn set_next_offline_pts(&mut self, next_buffer_pts: Duration) {
+        self.next_offline_pts = next_buffer_pts;
+    }
+
+    /// In offline mode the queue waits for every input that did not end, even
+    /// if it did not send any frame yet. At least one input is required.
+    pub fn check_all_inputs_ready_offline(&self, next_buffer_pts: Duration) -> bool {
+        !self.inputs_queues.is_empty()
+            && self.inputs_queues.iter().all(|(input_id, input_queue)| {
+                self.ended_inputs.contains(input_id)
+                    || input_queue
+                        .last()
+                        .is_some_and(|last_frame| last_frame.pts >= next_buffer_pts)
+            })
+    }
+
+    /// All inputs ended and the last frames of all of them are older than
+    /// next_buffer_pts.
+    pub fn check_all_inputs_finished(&self, next_buffer_pts: Duration) -> bool {
+        !self.inputs_queues.is_empty()
+            && self.inputs_queues.iter().all(|(input_id, input_queue)| {
+                self.ended_inputs.contains(input_id)
+                    && input_queue.iter().all(|frame| frame.pts < next_buffer_pts)
+            })
+    }
+
+    pub fn sync_to_sender_clock(&mut self, input_id: &InputId, pts_zero_time: SystemTime) {
+        let should_sync = self
+            .inputs_options
+            .get(input_id)
+            .is_some_and(|opts| opts.sync_with_sender_clock);
+        let (Some(input_queue), Some(offset)) = (
+            self.inputs_queues.get_mut(input_id),
+            self.timestamp_offsets.get_mut(input_id),
+        ) else {
+            return;
+        };
+        if !should_sync {
+            return;
+        }
+
+        let (origin_time, origin_pts) = *self
+            .sender_clock_origin
+            .get_or_insert((pts_zero_time, *offset));
+        let new_offset = match pts_zero_time.duration_since(origin_time) {
+            Ok(after_origin) => origin_pts + after_origin,
+            Err(err) => origin_pts.saturating_sub(err.duration()),
+        };
+
+        for frame in input_queue.iter_mut() {
+            frame.pts = frame.pts - *offset + new_offset;
+        }
+        info!(
+            "Input {input_id} synchronized to sender clock, PTS offset changed from {:?} to {:?}.",
+            *offset, new_offset
+        );
+        *offset = new_offset;
+    }
+
+    /// Gets frames for buffer pts, selected (or blended) according to the
+    /// framerate conversion of each input.
     pub fn get_frames_batch(&mut self, buffer_pts: Duration) -> FrameSet<InputId> {
-        for (_, input_queue) in self.inputs_queues.iter_mut() {
-            Self::drop_old_input_frames(input_queue, buffer_pts);
+        for (input_id, input_queue) in self.inputs_queues.iter_mut() {
+            let conversion = self
+                .inputs_options
+                .get(input_id)
+                .map(|opts| opts.framerate_conversion)
+                .unwrap_or_default();
+            Self::drop_old_input_frames(input_queue, conversion, buffer_pts);
         }
 
         let mut frames_batch = FrameSet::new(buffer_pts);
         for (input_id, input_queue) in &self.inputs_queues {
-            if let Some(nearest_frame) = input_queue.first() {
-                frames_batch
-                    .frames
-                    .insert(input_id.clone(), nearest_frame.clone());
+            let conversion = self.framerate_conversion(input_id);
+            if let Some(frame) = framerate_conversion::convert(conversion, input_queue, buffer_pts)
+            {
+                frames_batch.frames.insert(input_id.clone(), frame);
             }
         }
 
@@ -98,20 +247,40 @@ impl InternalQueue {
             })
     }
 
+    /// Like [`Self::check_all_inputs_ready`], but inputs that are late for more than
+    /// their max lateness (buffer duration if not set) at `elapsed` time, or that
+    /// did not receive any frame yet, are not waited for.
+    pub fn check_all_inputs_ready_or_late(
+        &self,
+        next_buffer_pts: Duration,
+        elapsed: Duration,
+    ) -> bool {
+        self.inputs_queues.iter().all(|(input_id, input_queue)| {
+            let is_ready = input_queue
+                .last()
+                .is_some_and(|last_frame| last_frame.pts >= next_buffer_pts);
+            let max_wait = self
+                .max_lateness(input_id)
+                .unwrap_or_else(|| self.buffer_duration(input_id));
+            is_ready || !self.did_receive_frame(input_id) || next_buffer_pts + max_wait < elapsed
+        })
+    }
+
     /// Drops frames that won't be used anymore by the VideoCompositor from a single input.
     ///
-    /// Finds frame that is closest to the next_buffer_pts and removes everything older.
-    /// Frames in queue have monotonically increasing pts, so we can just drop all the frames
-    /// before the "closest" one.
-    fn drop_old_input_frames(input_queue: &mut Vec<Frame>, next_buffer_pts: Duration) {
-        let next_output_buffer_nanos = next_buffer_pts.as_nanos();
-        let closest_diff_frame_index = input_queue
-            .iter()
-            .enumerate()
-            .min_by_key(|(_index, frame)| frame.pts.as_nanos().abs_diff(next_output_buffer_nanos))
-            .map(|(index, _frame)| index);
-
-        if let Some(index) = closest_diff_frame_index {
+    /// Finds the first frame needed to produce the next_buffer_pts frame (the closest one
+    /// or the last one before it, depending on the framerate conversion) and removes
+    /// everything older. Frames in queue have monotonically increasing pts, so we can just
+    /// drop all the frames before that one.
+    fn drop_old_input_frames(
+        input_queue: &mut Vec<Frame>,
+        conversion: FramerateConversion,
+        next_buffer_pts: Duration,
+    ) {
+        let first_needed_index =
+            framerate_conversion::first_needed_frame(conversion, input_queue, next_buffer_pts);
+
+        if let Some(index) = first_needed_index {
             input_queue.drain(0..index);
         }
     }
@@ -121,12 +290,13 @@ impl InternalQueue {
         input_id: &InputId,
         next_buffer_pts: Duration,
     ) -> Result<(), QueueError> {
+        let conversion = self.framerate_conversion(input_id);
         let input_queue = self
             .inputs_queues
             .get_mut(input_id)
             .ok_or_else(|| QueueError::UnknownInputId(input_id.clone()))?;
 
-        Self::drop_old_input_frames(input_queue, next_buffer_pts);
+        Self::drop_old_input_frames(input_queue, conversion, next_buffer_pts);
         Ok(())
     }
 
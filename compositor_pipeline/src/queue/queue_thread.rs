@@ -8,20 +8,25 @@ use std::{
 use compositor_common::{scene::InputId, Framerate};
 use compositor_render::FrameSet;
 use crossbeam_channel::{tick, Sender};
+use log::info;
 
-use super::Queue;
+use super::{Queue, SyncPolicy};
 
 pub struct Options {
-    pub buffer_duration: Duration,
-    pub tick_duration: Duration,
+    pub sync_policy: SyncPolicy,
+    /// Produce batches as soon as all inputs are ready instead of following
+    /// the wall clock. See [`super::Options::offline`].
+    pub offline: bool,
     pub clock_start: Instant,
-    pub output_framerate: Framerate,
 }
 
 pub struct QueueThread {
     queue: Arc<Queue>,
     sender: Sender<FrameSet<InputId>>,
     opts: Options,
+    /// Framerate used to calculate PTS of the next batches. Counter and offset
+    /// are reset when it changes, so new PTS continue from the last batch.
+    output_framerate: Framerate,
     sent_batches_counter: u32,
     output_frame_offset: Duration,
 }
@@ -29,10 +34,12 @@ pub struct QueueThread {
 impl QueueThread {
     pub fn new(queue: Arc<Queue>, sender: Sender<FrameSet<InputId>>, opts: Options) -> Self {
         let output_frame_offset = opts.clock_start.elapsed();
+        let output_framerate = queue.output_framerate();
         Self {
             queue,
             sender,
             opts,
+            output_framerate,
             output_frame_offset,
             sent_batches_counter: 0,
         }
@@ -47,6 +54,10 @@ impl QueueThread {
     }
 
     fn run(&mut self) {
+        if self.opts.offline {
+            return self.run_offline();
+        }
+
         // This is just in case QueueThread is not spawned after the creation
         self.output_frame_offset = self.opts.clock_start.elapsed();
         self.start_ticker();
@@ -58,11 +69,18 @@ impl QueueThread {
     }
 
     fn on_queue_event(&mut self) {
+        self.update_output_framerate();
         let mut internal_queue = self.queue.internal_queue.lock().unwrap();
         let next_buffer_pts = self.get_next_output_buffer_pts();
 
-        let ready_to_push = internal_queue.check_all_inputs_ready(next_buffer_pts)
-            || self.should_push_pts(next_buffer_pts);
+        let ready_to_push = match self.opts.sync_policy {
+            SyncPolicy::RenderAvailable => {
+                internal_queue.check_all_inputs_ready(next_buffer_pts)
+                    || self.should_push_pts(next_buffer_pts)
+            }
+            SyncPolicy::WaitForAllInputs => internal_queue
+                .check_all_inputs_ready_or_late(next_buffer_pts, self.opts.clock_start.elapsed()),
+        };
         if !ready_to_push {
             return;
         }
@@ -75,22 +93,78 @@ impl QueueThread {
         self.sent_batches_counter += 1;
     }
 
+    /// Batches start at PTS=0 and are pushed as soon as all inputs have frames
+    /// for them. Returns when all inputs ended, which drops the sender and
+    /// finishes the render loop.
+    fn run_offline(&mut self) {
+        self.output_frame_offset = Duration::ZERO;
+
+        loop {
+            self.queue.check_queue_channel.1.recv().unwrap();
+            self.update_output_framerate();
+
+            loop {
+                let next_buffer_pts = self.get_next_output_buffer_pts();
+                let mut internal_queue = self.queue.internal_queue.lock().unwrap();
+                if internal_queue.check_all_inputs_finished(next_buffer_pts) {
+                    info!("All inputs ended. Finishing offline processing.");
+                    return;
+                }
+                if !internal_queue.check_all_inputs_ready_offline(next_buffer_pts) {
+                    break;
+                }
+
+                let frames_batch = internal_queue.get_frames_batch(next_buffer_pts);
+                for input_id in frames_batch.frames.keys() {
+                    internal_queue.call_input_listeners(input_id)
+                }
+                self.sent_batches_counter += 1;
+                internal_queue.set_next_offline_pts(self.get_next_output_buffer_pts());
+                drop(internal_queue);
+                self.queue.frames_consumed.notify_all();
+
+                // Render channel is bounded in offline mode, so this blocks until
+                // the renderer catches up. Queue lock is released to not block
+                // the decoders in the meantime.
+                if self.sender.send(frames_batch).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
     fn get_next_output_buffer_pts(&self) -> Duration {
-        Duration::from_secs_f64(
-            self.sent_batches_counter as f64 * self.opts.output_framerate.den as f64
-                / self.opts.output_framerate.num as f64,
-        ) + self.output_frame_offset
+        self.output_framerate
+            .frame_pts(self.sent_batches_counter as u64)
+            + self.output_frame_offset
+    }
+
+    fn update_output_framerate(&mut self) {
+        let output_framerate = self.queue.output_framerate();
+        if output_framerate == self.output_framerate {
+            return;
+        }
+        self.output_frame_offset = self.get_next_output_buffer_pts();
+        self.sent_batches_counter = 0;
+        self.output_framerate = output_framerate;
     }
 
     fn start_ticker(&self) {
-        let check_queue_sender = self.queue.check_queue_channel.0.clone();
-        let tick_duration = self.opts.tick_duration;
+        let queue = self.queue.clone();
         thread::spawn(move || {
-            let ticker = tick(tick_duration);
+            let check_queue_sender = queue.check_queue_channel.0.clone();
+            let mut tick_duration = queue.output_framerate().get_interval_duration();
+            let mut ticker = tick(tick_duration);
             check_queue_sender.send(()).unwrap();
             loop {
                 ticker.recv().unwrap();
                 check_queue_sender.send(()).unwrap();
+
+                let new_tick_duration = queue.output_framerate().get_interval_duration();
+                if new_tick_duration != tick_duration {
+                    tick_duration = new_tick_duration;
+                    ticker = tick(tick_duration);
+                }
             }
         });
     }
@@ -0,0 +1,47 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) texture_id: i32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.position = vec4(input.position, 1.0);
+    output.tex_coords = input.tex_coords;
+
+    return output;
+}
+
+struct CommonShaderParameters {
+    time: f32,
+    texture_count: u32,
+    output_resolution: vec2<u32>,
+    frame_index: u32,
+    random_seed: u32,
+}
+
+struct InputShaderParameters {
+    resolution: vec2<u32>,
+    pts_offset: f32,
+}
+
+@group(0) @binding(0) var textures: binding_array<texture_2d<f32>, 16>;
+@group(0) @binding(1) var<uniform> inputs_params: array<InputShaderParameters, 16>;
+@group(2) @binding(0) var sampler_: sampler;
+
+var<push_constant> common_params: CommonShaderParameters;
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    if (common_params.texture_count == 0u) {
+        return vec4(0.0, 0.0, 0.0, 0.0);
+    }
+    return textureSample(textures[0], sampler_, input.tex_coords);
+}
@@ -0,0 +1,279 @@
+//! Frames per second of the renderer for representative scenes. Run with:
+//!
+//! ```console
+//! cargo bench -p compositor_render --features bench
+//! ```
+
+use std::{sync::Arc, time::Duration};
+
+use compositor_common::{
+    frame::{FrameData, FrameFormat, YuvData},
+    renderer_spec::{FallbackStrategy, RendererId, RendererSpec, ShaderSpec},
+    scene::{
+        builtin_transformations::{BuiltinSpec, TiledLayoutSpec},
+        constraints::NodeConstraints,
+        text_spec::{Style, TextDimensions, TextSpec, Weight, Wrap},
+        InputId, NodeId, NodeParams, NodeSpec, OutputId, OutputSpec, Resolution, SceneSpec,
+    },
+    util::{
+        align::{HorizontalAlign, VerticalAlign},
+        colors::RGBAColor,
+    },
+    Frame, Framerate,
+};
+use compositor_render::{
+    renderer::RendererOptions, FrameSet, GpuOptions, Renderer, WebRendererOptions,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const FRAMERATE: Framerate = Framerate { num: 30, den: 1 };
+const OUTPUT_ID: &str = "output";
+const SHADER_ID: &str = "passthrough";
+
+const RESOLUTION_720P: Resolution = Resolution {
+    width: 1280,
+    height: 720,
+};
+const RESOLUTION_1080P: Resolution = Resolution {
+    width: 1920,
+    height: 1080,
+};
+const RESOLUTION_4K: Resolution = Resolution {
+    width: 3840,
+    height: 2160,
+};
+
+/// Input uploaded and converted to RGBA, then converted back to YUV and
+/// downloaded, without any other processing.
+fn yuv_upload_download(c: &mut Criterion) {
+    let mut group = c.benchmark_group("yuv_upload_download");
+    group.throughput(Throughput::Elements(1));
+    for (name, resolution) in [
+        ("720p", RESOLUTION_720P),
+        ("1080p", RESOLUTION_1080P),
+        ("4k", RESOLUTION_4K),
+    ] {
+        let scene = shader_chain_scene(1, resolution);
+        let renderer = create_renderer(vec![passthrough_shader()], scene);
+        let inputs = inputs(1, resolution);
+        group.bench_function(name, |b| render_frames(b, &renderer, &inputs));
+    }
+    group.finish();
+}
+
+/// 1080p inputs placed on a 1080p output by the tiled layout.
+fn tiled_layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tiled_layout");
+    group.throughput(Throughput::Elements(1));
+    for inputs_count in [1, 4, 16, 64] {
+        let scene = tiled_layout_scene(inputs_count);
+        let renderer = create_renderer(vec![], scene);
+        let inputs = inputs(inputs_count, RESOLUTION_1080P);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(inputs_count),
+            &inputs,
+            |b, inputs| render_frames(b, &renderer, inputs),
+        );
+    }
+    group.finish();
+}
+
+/// Shader nodes applied one after another on a 1080p input.
+fn shader_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("shader_chain");
+    group.throughput(Throughput::Elements(1));
+    for length in [1, 4, 16] {
+        let scene = shader_chain_scene(length, RESOLUTION_1080P);
+        let renderer = create_renderer(vec![passthrough_shader()], scene);
+        let inputs = inputs(1, RESOLUTION_1080P);
+        group.bench_with_input(BenchmarkId::from_parameter(length), &inputs, |b, inputs| {
+            render_frames(b, &renderer, inputs)
+        });
+    }
+    group.finish();
+}
+
+/// Text is laid out once, so this mostly measures drawing the glyphs.
+fn text(c: &mut Criterion) {
+    let mut group = c.benchmark_group("text");
+    group.throughput(Throughput::Elements(1));
+    let renderer = create_renderer(vec![], text_scene(RESOLUTION_1080P));
+    group.bench_function("1080p", |b| render_frames(b, &renderer, &[]));
+    group.finish();
+}
+
+fn render_frames(b: &mut criterion::Bencher, renderer: &Renderer, inputs: &[(InputId, Frame)]) {
+    let frame_duration = Duration::from_secs_f64(FRAMERATE.den as f64 / FRAMERATE.num as f64);
+    let mut pts = Duration::ZERO;
+    b.iter(|| {
+        let mut frame_set = FrameSet::new(pts);
+        for (input_id, frame) in inputs {
+            frame_set.frames.insert(
+                input_id.clone(),
+                Frame {
+                    pts,
+                    ..frame.clone()
+                },
+            );
+        }
+        pts += frame_duration;
+        renderer.render(frame_set).expect("render")
+    });
+}
+
+fn create_renderer(renderers: Vec<RendererSpec>, scene: SceneSpec) -> Renderer {
+    let (mut renderer, _event_loop) = Renderer::new(RendererOptions {
+        web_renderer: WebRendererOptions {
+            init: false,
+            disable_gpu: false,
+        },
+        gpu: GpuOptions::default(),
+        framerate: FRAMERATE,
+        stream_fallback_timeout: Duration::from_secs(1),
+        shader_random_seed: Some(0),
+    })
+    .expect("create renderer");
+
+    for spec in renderers {
+        renderer.register_renderer(spec).expect("register renderer");
+    }
+    renderer
+        .update_scene(Arc::new(scene))
+        .expect("update scene");
+    renderer
+}
+
+fn passthrough_shader() -> RendererSpec {
+    RendererSpec::Shader(ShaderSpec {
+        shader_id: RendererId(SHADER_ID.into()),
+        source: include_str!("./passthrough.wgsl").into(),
+        fallback_strategy: FallbackStrategy::FallbackIfAllInputsMissing,
+        constraints: NodeConstraints::empty(),
+    })
+}
+
+/// Gray frames, content does not affect the rendering time.
+fn inputs(count: usize, resolution: Resolution) -> Vec<(InputId, Frame)> {
+    let pixels = resolution.width * resolution.height;
+    let data = FrameData::PlanarYuv(YuvData {
+        y_plane: vec![128; pixels].into(),
+        u_plane: vec![128; pixels / 4].into(),
+        v_plane: vec![128; pixels / 4].into(),
+    });
+    (0..count)
+        .map(|index| {
+            let frame = Frame {
+                data: data.clone(),
+                resolution,
+                format: FrameFormat::default(),
+                pts: Duration::ZERO,
+            };
+            (input_id(index), frame)
+        })
+        .collect()
+}
+
+fn shader_chain_scene(length: usize, resolution: Resolution) -> SceneSpec {
+    let nodes: Vec<_> = (0..length)
+        .map(|index| {
+            let input_pad = match index {
+                0 => input_id(0).0,
+                _ => node_id(&format!("shader_{}", index - 1)),
+            };
+            NodeSpec {
+                node_id: node_id(&format!("shader_{index}")),
+                input_pads: vec![input_pad],
+                fallback_id: None,
+                input_fallback_timeout: None,
+                params: NodeParams::Shader {
+                    shader_id: RendererId(SHADER_ID.into()),
+                    shader_params: None,
+                    storage_params: None,
+                    image_ids: Vec::new(),
+                    resolution,
+                    sampler: Default::default(),
+                    geometry: Default::default(),
+                },
+            }
+        })
+        .collect();
+    let root_id = nodes.last().unwrap().node_id.clone();
+    scene(nodes, root_id)
+}
+
+fn tiled_layout_scene(inputs_count: usize) -> SceneSpec {
+    let root_id = node_id("layout");
+    let layout = NodeSpec {
+        node_id: root_id.clone(),
+        input_pads: (0..inputs_count).map(|index| input_id(index).0).collect(),
+        fallback_id: None,
+        input_fallback_timeout: None,
+        params: NodeParams::Builtin(BuiltinSpec::TiledLayout(TiledLayoutSpec {
+            background_color_rgba: RGBAColor(0, 0, 0, 255),
+            tile_aspect_ratio: (16, 9),
+            resolution: RESOLUTION_1080P,
+            margin: 0,
+            padding: 0,
+            horizontal_alignment: HorizontalAlign::Center,
+            vertical_alignment: VerticalAlign::Center,
+            mipmaps: false,
+        })),
+    };
+    scene(vec![layout], root_id)
+}
+
+fn text_scene(resolution: Resolution) -> SceneSpec {
+    let root_id = node_id("text");
+    let text = NodeSpec {
+        node_id: root_id.clone(),
+        input_pads: vec![],
+        fallback_id: None,
+        input_fallback_timeout: None,
+        params: NodeParams::Text(TextSpec {
+            content: "The quick brown fox jumps over the lazy dog. "
+                .repeat(40)
+                .into(),
+            font_size: 48.0,
+            line_height: None,
+            color_rgba: RGBAColor(255, 255, 255, 255),
+            font_family: String::from("Verdana"),
+            style: Style::Normal,
+            align: HorizontalAlign::Left,
+            weight: Weight::Normal,
+            wrap: Wrap::Word,
+            background_color_rgba: RGBAColor(0, 0, 0, 255),
+            dimensions: TextDimensions::Fixed {
+                width: resolution.width as u32,
+                height: resolution.height as u32,
+            },
+        }),
+    };
+    scene(vec![text], root_id)
+}
+
+fn scene(nodes: Vec<NodeSpec>, root_id: NodeId) -> SceneSpec {
+    SceneSpec {
+        nodes,
+        outputs: vec![OutputSpec {
+            output_id: OutputId(node_id(OUTPUT_ID)),
+            input_pad: root_id,
+        }],
+    }
+}
+
+fn input_id(index: usize) -> InputId {
+    InputId(node_id(&format!("input_{index}")))
+}
+
+fn node_id(id: &str) -> NodeId {
+    NodeId(id.into())
+}
+
+criterion_group!(
+    benches,
+    yuv_upload_download,
+    tiled_layout,
+    shader_chain,
+    text
+);
+criterion_main!(benches);
@@ -1,12 +1,14 @@
 use std::{path::Path, process::Stdio, sync::Arc, time::Duration};
 
 use compositor_common::{
-    frame::YuvData,
+    frame::{FrameData, FrameFormat, YuvData},
     renderer_spec::{FallbackStrategy, RendererId, RendererSpec, ShaderSpec},
     scene::{constraints::NodeConstraints, NodeId, NodeSpec, OutputSpec, Resolution, SceneSpec},
     Frame, Framerate,
 };
-use compositor_render::{renderer::RendererOptions, FrameSet, Renderer, WebRendererOptions};
+use compositor_render::{
+    renderer::RendererOptions, FrameSet, GpuOptions, Renderer, WebRendererOptions,
+};
 
 const FRAMERATE: Framerate = Framerate { num: 30, den: 1 };
 
@@ -68,9 +70,10 @@ fn get_image(path: impl AsRef<Path>) -> Frame {
     std::fs::remove_file("input.yuv").expect("rm input.yuv");
 
     Frame {
-        data: yuv_data,
+        data: FrameData::PlanarYuv(yuv_data),
         pts: Duration::from_secs(1),
         resolution,
+        format: FrameFormat::default(),
     }
 }
 
@@ -87,8 +90,10 @@ fn main() {
             init: false,
             ..Default::default()
         },
+        gpu: GpuOptions::default(),
         framerate: FRAMERATE,
         stream_fallback_timeout: Duration::from_secs(1),
+        shader_random_seed: None,
     })
     .expect("create renderer");
     let shader_key = RendererId("silly shader".into());
@@ -114,9 +119,14 @@ fn main() {
                 params: compositor_common::scene::NodeParams::Shader {
                     shader_id: shader_key,
                     shader_params: None,
+                    storage_params: None,
+                    image_ids: Vec::new(),
                     resolution,
+                    sampler: Default::default(),
+                    geometry: Default::default(),
                 },
                 fallback_id: None,
+                input_fallback_timeout: None,
             }],
             outputs: vec![OutputSpec {
                 input_pad: shader_id,
@@ -129,10 +139,13 @@ fn main() {
     frame_set.frames.insert(input_id.into(), frame);
     let output = renderer.render(frame_set).expect("render");
     let output = output.frames.get(&output_id.into()).expect("extract frame");
+    let FrameData::PlanarYuv(yuv_data) = &output.data else {
+        panic!("output frames are always planar YUV");
+    };
     let mut output_data = Vec::with_capacity(resolution.width * resolution.height * 3 / 2);
-    output_data.extend_from_slice(&output.data.y_plane);
-    output_data.extend_from_slice(&output.data.u_plane);
-    output_data.extend_from_slice(&output.data.v_plane);
+    output_data.extend_from_slice(&yuv_data.y_plane);
+    output_data.extend_from_slice(&yuv_data.u_plane);
+    output_data.extend_from_slice(&yuv_data.v_plane);
     std::fs::write("output.yuv", output_data).expect("write");
 
     ffmpeg_yuv_to_jpeg("output.yuv", "output.jpg", resolution);
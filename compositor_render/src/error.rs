@@ -1,20 +1,25 @@
+use std::{io, path::PathBuf, time::Duration};
+
 use compositor_common::{
     error::UnsatisfiedConstraintsError,
     renderer_spec::RendererId,
-    scene::{transition::TransitionValidationError, NodeId},
+    scene::{transition::TransitionValidationError, InputId, NodeId, OutputId, Resolution},
     SceneSpecValidationError,
 };
 
 use crate::{
     registry,
     transformations::{
-        builtin::error::InitBuiltinError, image_renderer::ImageError,
-        web_renderer::chromium_context::WebRendererContextError,
+        builtin::error::InitBuiltinError,
+        captions::CaptionsError,
+        image_renderer::{ImageError, SvgError},
+        mesh::MeshError,
+        web_renderer::{bundle::LocalBundleError, chromium_context::WebRendererContextError},
     },
     wgpu::{shader::CreateShaderError, validation::ParametersValidationError, CreateWgpuCtxError},
 };
 
-pub use crate::registry::RegisterError;
+pub use crate::registry::{RegisterError, UpdateError};
 pub use crate::wgpu::WgpuError;
 
 #[derive(Debug, thiserror::Error)]
@@ -39,6 +44,54 @@ pub enum RegisterRendererError {
 
     #[error("Failed to register image \"{1}\".")]
     Image(#[source] ImageError, RendererId),
+
+    #[error("Failed to register mesh \"{1}\".")]
+    Mesh(#[source] MeshError, RendererId),
+
+    #[error("Failed to register web renderer \"{1}\".")]
+    WebRenderer(#[source] LocalBundleError, RendererId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateShaderError {
+    #[error(transparent)]
+    RendererRegistry(#[from] registry::UpdateError),
+
+    #[error("Failed to update shader \"{1}\".")]
+    Shader(#[source] CreateShaderError, RendererId),
+
+    #[error("Failed to update shader \"{1}\". Parameters of the \"{2}\" node are not valid for the new shader.")]
+    ParametersValidationError(#[source] ParametersValidationError, RendererId, NodeId),
+
+    #[error("Failed to update shader \"{1}\". Constraints for node \"{2}\" are not satisfied.")]
+    ConstraintsValidationError(#[source] UnsatisfiedConstraintsError, RendererId, NodeId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PreloadImageError {
+    #[error("Failed to preload image. The \"{0}\" image does not exist.")]
+    NotFound(RendererId),
+
+    #[error("Failed to preload image \"{1}\".")]
+    SvgRasterization(#[source] SvgError, RendererId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RegisterWatermarkError {
+    #[error("Failed to register watermark. The \"{0}\" image does not exist.")]
+    ImageNotFound(RendererId),
+
+    #[error("Failed to register watermark with image \"{1}\".")]
+    SvgRasterization(#[source] SvgError, RendererId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SetOutputSlateError {
+    #[error("Failed to set slate. The \"{0}\" image does not exist.")]
+    ImageNotFound(RendererId),
+
+    #[error("Failed to set slate with image \"{1}\".")]
+    SvgRasterization(#[source] SvgError, RendererId),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -54,12 +107,59 @@ pub enum UnregisterRendererError {
     #[error("Failed to unregister \"{0}\" shader. It is still used in scene definition by \"{1}\" node.")]
     ShaderStillInUse(RendererId, NodeId),
 
+    #[error(
+        "Failed to unregister \"{0}\" mesh. It is still used in scene definition by \"{1}\" node."
+    )]
+    MeshStillInUse(RendererId, NodeId),
+
     #[error(
         "Failed to unregister \"{0}\" web renderer instance. It is still used in scene definition by \"{1}\" node."
     )]
     WebRendererInstanceStillInUse(RendererId, NodeId),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum RendererInfoError {
+    #[error("Failed to query renderer. The \"{renderer_id}\" {item_type} does not exist.")]
+    NotFound {
+        item_type: &'static str,
+        renderer_id: RendererId,
+    },
+
+    #[error("Parameters of the \"{1}\" shader use types that can't be provided in requests.")]
+    UnsupportedShaderParams(#[source] ParametersValidationError, RendererId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebRendererEventError {
+    #[error("Failed to send event. The \"{0}\" web renderer instance does not exist.")]
+    NotFound(RendererId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    #[error("Failed to take a snapshot. Node \"{0}\" is not a part of the current scene.")]
+    NodeNotFound(NodeId),
+
+    #[error("Failed to take a snapshot. Output \"{0}\" is not a part of the current scene.")]
+    OutputNotFound(OutputId),
+
+    #[error("Failed to take a snapshot. Nothing was rendered yet.")]
+    NoFrame,
+
+    #[error("Failed to download the frame from the GPU.")]
+    Download(#[from] wgpu::BufferAsyncError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FreezeFrameError {
+    #[error("Failed to grab a frame for image \"{1}\".")]
+    Snapshot(#[source] SnapshotError, RendererId),
+
+    #[error(transparent)]
+    RendererRegistry(#[from] registry::RegisterError),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum RenderSceneError {
     #[error(transparent)]
@@ -85,6 +185,15 @@ pub enum UpdateSceneError {
 
     #[error("Constraints for node \"{1}\" are not satisfied.")]
     ConstraintsValidationError(#[source] UnsatisfiedConstraintsError, NodeId),
+
+    #[error("Resolution of node \"{0}\" can't have zero width or height.")]
+    EmptyResolution(NodeId),
+
+    #[error("Resolution {}x{} of node \"{0}\" exceeds the max texture size supported by the GPU ({2}x{2}).", .1.width, .1.height)]
+    ResolutionExceedsGpuLimits(NodeId, Resolution, u32),
+
+    #[error("Output \"{0}\" is a key output, it is rendered from its fill output and can't be used in the scene.")]
+    KeyOutputInScene(OutputId),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -101,6 +210,70 @@ pub enum CreateNodeError {
     #[error("Image \"{0}\" does not exist. You have to register it first before using it in the scene definition.")]
     ImageNotFound(RendererId),
 
+    #[error("Failed to render image \"{1}\".")]
+    SvgRasterization(#[source] SvgError, RendererId),
+
+    #[error("Mesh \"{0}\" does not exist. You have to register it first before using it in the scene definition.")]
+    MeshNotFound(RendererId),
+
     #[error(transparent)]
     TransitionValidation(#[from] TransitionValidationError),
+
+    #[error("Failed to load captions.")]
+    Captions(#[source] CaptionsError),
+
+    #[error("Input stream \"{0}\" was not registered with a replay buffer.")]
+    ReplayBufferNotFound(InputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayBufferError {
+    #[error("Input stream \"{0}\" was not registered with a replay buffer.")]
+    NotFound(InputId),
+
+    #[error("Replay buffer of input stream \"{0}\" does not contain any frames yet.")]
+    Empty(InputId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotTestError {
+    #[error(transparent)]
+    InitRenderer(#[from] InitRendererEngineError),
+
+    #[error(transparent)]
+    RegisterRenderer(#[from] RegisterRendererError),
+
+    #[error(transparent)]
+    UpdateScene(#[from] UpdateSceneError),
+
+    #[error(transparent)]
+    RenderScene(#[from] RenderSceneError),
+
+    #[error("Web renderer \"{0}\" can't be used in snapshot tests.")]
+    WebRendererNotSupported(RendererId),
+
+    #[error("Output \"{0}\" was not rendered for pts {1:?}.")]
+    OutputNotRendered(OutputId, Duration),
+
+    #[error("Golden image {0:?} does not exist. Generate snapshots first.")]
+    GoldenNotFound(PathBuf),
+
+    #[error("Golden image {path:?} has resolution {expected:?}, but {actual:?} was rendered.")]
+    ResolutionMismatch {
+        path: PathBuf,
+        expected: Resolution,
+        actual: Resolution,
+    },
+
+    #[error("Rendered frame differs from golden image {path:?} on {mismatched_pixels} pixels.")]
+    Mismatch {
+        path: PathBuf,
+        mismatched_pixels: usize,
+    },
+
+    #[error("Failed to read or write image {1:?}.")]
+    ImageError(#[source] image::ImageError, PathBuf),
+
+    #[error("Failed to create directory for {1:?}.")]
+    IoError(#[source] io::Error, PathBuf),
 }
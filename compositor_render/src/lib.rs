@@ -1,5 +1,6 @@
 pub mod error;
 pub mod renderer;
+pub mod testing;
 
 pub(crate) mod registry;
 pub(crate) mod transformations;
@@ -13,9 +14,18 @@ mod validation;
 
 pub use event_loop::EventLoop;
 pub use frame_set::FrameSet;
+pub use renderer::renderers::RendererInfo;
+pub use transformations::image_renderer::ImageResidency;
+pub use validation::SceneSpecExt;
+pub use wgpu::adapter::{
+    available_adapters, AdapterInfo, AdapterSelector, GpuBackend, GpuOptions, GpuPowerPreference,
+};
+pub use wgpu::capabilities::GpuCapabilities;
+pub use wgpu::texture::TexturePoolUsage;
 
 pub use transformations::web_renderer::{
-    WebRendererOptions, EMBED_SOURCE_FRAMES_MESSAGE, UNEMBED_SOURCE_FRAMES_MESSAGE,
+    WebRendererEvent, WebRendererOptions, EMBED_SOURCE_FRAMES_MESSAGE, POST_MESSAGE_MESSAGE,
+    UNEMBED_SOURCE_FRAMES_MESSAGE,
 };
 
 pub type Renderer = sync_renderer::SyncRenderer;
@@ -18,19 +18,40 @@ pub struct UnregisterError {
     renderer_id: RendererId,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, thiserror::Error)]
+#[error("Failed to update a {item_type}. The \"{renderer_id}\" {item_type} does not exist.")]
+pub struct UpdateError {
+    item_type: &'static str,
+    renderer_id: RendererId,
+}
+
+impl UnregisterError {
+    pub fn renderer_id(&self) -> &RendererId {
+        &self.renderer_id
+    }
+}
+
+impl UpdateError {
+    pub fn renderer_id(&self) -> &RendererId {
+        &self.renderer_id
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RegistryType {
     Shader,
     WebRenderer,
     Image,
+    Mesh,
 }
 
 impl RegistryType {
-    fn registry_item_name(&self) -> &'static str {
+    pub fn registry_item_name(&self) -> &'static str {
         match self {
             RegistryType::Shader => "shader",
             RegistryType::WebRenderer => "web renderer instance",
             RegistryType::Image => "image",
+            RegistryType::Mesh => "mesh",
         }
     }
 }
@@ -56,6 +77,10 @@ impl<T: Clone> RendererRegistry<T> {
         self.registry.get(key)
     }
 
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&RendererId, &T)> {
+        self.registry.iter()
+    }
+
     pub(crate) fn register(&mut self, id: RendererId, renderer: T) -> Result<(), RegisterError> {
         if self.registry.contains_key(&id) {
             return Err(RegisterError::KeyTaken {
@@ -69,6 +94,17 @@ impl<T: Clone> RendererRegistry<T> {
         Ok(())
     }
 
+    /// Replaces already registered entry, returns the previous one.
+    pub(crate) fn update(&mut self, id: &RendererId, renderer: T) -> Result<T, UpdateError> {
+        match self.registry.get_mut(id) {
+            Some(current) => Ok(std::mem::replace(current, renderer)),
+            None => Err(UpdateError {
+                item_type: self.registry_type.registry_item_name(),
+                renderer_id: id.clone(),
+            }),
+        }
+    }
+
     pub(crate) fn unregister(&mut self, id: &RendererId) -> Result<(), UnregisterError> {
         match self.registry.remove(id) {
             Some(_) => Ok(()),
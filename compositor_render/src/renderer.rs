@@ -1,38 +1,68 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
 use compositor_common::{
-    scene::{InputId, OutputId, SceneSpec},
+    frame::FrameFormat,
+    renderer_spec::RendererId,
+    scene::{
+        shader::ShaderParam, InputId, NodeParams, OutputId, OutputSlate, SceneSpec, Watermark,
+    },
     Framerate,
 };
 
-use crate::wgpu::{WgpuCtx, WgpuErrorScope};
+use crate::wgpu::{adapter::GpuOptions, WgpuCtx, WgpuErrorScope};
 use crate::{
-    error::{InitRendererEngineError, RenderSceneError, UpdateSceneError},
+    error::{
+        InitRendererEngineError, RegisterWatermarkError, RenderSceneError, SetOutputSlateError,
+        UpdateSceneError, UpdateShaderError,
+    },
     transformations::{
-        text_renderer::TextRendererCtx, web_renderer::chromium_context::ChromiumContext,
+        shader::Shader, text_renderer::TextRendererCtx,
+        web_renderer::chromium_context::ChromiumContext,
     },
+    validation::SceneSpecExt,
+    wgpu::validation::ParametersValidationError,
     FrameSet, WebRendererOptions,
 };
 
 use self::{
+    input_fallback::InputFallbacks,
+    keying::OutputKeys,
     node::NodeSpecExt,
     render_loop::{populate_inputs, read_outputs, run_transforms},
     renderers::Renderers,
+    replay::ReplayBuffers,
+    scaling::OutputScalers,
     scene::Scene,
+    slate::OutputSlates,
+    test_pattern::InputTestPatterns,
+    watermark::OutputWatermarks,
 };
 
+mod input_fallback;
+mod keying;
 pub mod node;
 mod render_loop;
 pub mod renderers;
+mod replay;
+mod scaling;
 pub mod scene;
+mod slate;
+mod snapshot;
+mod test_pattern;
+mod watermark;
 
 pub(crate) use render_loop::NodeRenderPass;
+pub use snapshot::{Snapshot, SnapshotTarget};
 
 pub struct RendererOptions {
     pub web_renderer: WebRendererOptions,
+    pub gpu: GpuOptions,
     pub framerate: Framerate,
     pub stream_fallback_timeout: Duration,
+    /// Seed passed to shaders in `random_seed`. Random for every shader if not set.
+    pub shader_random_seed: Option<u32>,
 }
 
 pub struct Renderer {
@@ -42,10 +72,22 @@ pub struct Renderer {
 
     pub scene: Scene,
     pub scene_spec: Arc<SceneSpec>,
+    /// Pixel format and color space of registered outputs. Outputs that are
+    /// missing here use the default format.
+    output_formats: HashMap<OutputId, FrameFormat>,
+    pub(crate) input_fallbacks: InputFallbacks,
+    pub(crate) input_test_patterns: InputTestPatterns,
+    pub(crate) output_watermarks: OutputWatermarks,
+    pub(crate) output_scalers: OutputScalers,
+    pub(crate) output_keys: OutputKeys,
+    pub(crate) output_slates: OutputSlates,
+    pub(crate) replay_buffers: ReplayBuffers,
 
     pub(crate) renderers: Renderers,
 
     stream_fallback_timeout: Duration,
+    /// Number of frames rendered so far, passed to shaders in common params.
+    frame_index: u32,
 }
 
 pub struct RenderCtx<'a> {
@@ -55,6 +97,7 @@ pub struct RenderCtx<'a> {
     pub chromium: &'a Arc<ChromiumContext>,
 
     pub(crate) renderers: &'a Renderers,
+    pub(crate) replay_buffers: &'a ReplayBuffers,
 
     pub(crate) stream_fallback_timeout: Duration,
 }
@@ -66,7 +109,7 @@ pub struct RegisterCtx {
 
 impl Renderer {
     pub fn new(opts: RendererOptions) -> Result<Self, InitRendererEngineError> {
-        let wgpu_ctx = Arc::new(WgpuCtx::new()?);
+        let wgpu_ctx = Arc::new(WgpuCtx::new(&opts.gpu, opts.shader_random_seed)?);
 
         Ok(Self {
             wgpu_ctx: wgpu_ctx.clone(),
@@ -78,8 +121,17 @@ impl Renderer {
                 nodes: vec![],
                 outputs: vec![],
             }),
+            output_formats: HashMap::new(),
+            input_fallbacks: InputFallbacks::default(),
+            input_test_patterns: InputTestPatterns::default(),
+            output_watermarks: OutputWatermarks::default(),
+            output_scalers: OutputScalers::default(),
+            output_keys: OutputKeys::default(),
+            output_slates: OutputSlates::default(),
+            replay_buffers: ReplayBuffers::default(),
 
             stream_fallback_timeout: opts.stream_fallback_timeout,
+            frame_index: 0,
         })
     }
 
@@ -94,21 +146,45 @@ impl Renderer {
         &mut self,
         mut inputs: FrameSet<InputId>,
     ) -> Result<FrameSet<OutputId>, RenderSceneError> {
+        self.replay_buffers.push_frames(&inputs);
         let ctx = &mut RenderCtx {
             wgpu_ctx: &self.wgpu_ctx,
             chromium: &self.chromium_context,
             text_renderer_ctx: &self.text_renderer_ctx,
             renderers: &self.renderers,
+            replay_buffers: &self.replay_buffers,
             stream_fallback_timeout: self.stream_fallback_timeout,
         };
 
         let scope = WgpuErrorScope::push(&ctx.wgpu_ctx.device);
 
-        populate_inputs(ctx, &mut self.scene, &mut inputs).unwrap();
-        run_transforms(ctx, &mut self.scene, inputs.pts).unwrap();
-        let frames = read_outputs(ctx, &mut self.scene, inputs.pts).unwrap();
+        let inputs_missing = inputs.frames.values().all(|frame| {
+            Duration::saturating_sub(inputs.pts, ctx.stream_fallback_timeout) > frame.pts
+        });
+        populate_inputs(
+            ctx,
+            &mut self.scene,
+            &mut inputs,
+            &self.input_test_patterns,
+            &mut self.input_fallbacks,
+        )
+        .unwrap();
+        run_transforms(ctx, &mut self.scene, inputs.pts, self.frame_index).unwrap();
+        let frames = read_outputs(
+            ctx,
+            &mut self.scene,
+            &mut self.output_watermarks,
+            &mut self.output_scalers,
+            &mut self.output_keys,
+            &mut self.output_slates,
+            inputs_missing,
+            inputs.pts,
+        )
+        .unwrap();
 
         scope.pop(&ctx.wgpu_ctx.device)?;
+        self.wgpu_ctx.texture_pool.trim();
+        self.frame_index = self.frame_index.wrapping_add(1);
 
         Ok(FrameSet {
             frames,
@@ -124,15 +200,134 @@ impl Renderer {
                 text_renderer_ctx: &self.text_renderer_ctx,
                 chromium: &self.chromium_context,
                 renderers: &self.renderers,
+                replay_buffers: &self.replay_buffers,
                 stream_fallback_timeout: self.stream_fallback_timeout,
             },
             &scene_spec,
+            &self.output_formats,
+            &self.output_scalers,
+            &self.output_keys,
         )?;
         self.scene_spec = scene_spec;
         Ok(())
     }
 
+    /// Runs the same checks as `update_scene` and creates nodes of the scene, but
+    /// does not replace the current scene.
+    pub fn validate_scene(&self, scene_spec: &SceneSpec) -> Result<(), UpdateSceneError> {
+        self.validate_constraints(scene_spec)?;
+        Scene::empty().update(
+            &RenderCtx {
+                wgpu_ctx: &self.wgpu_ctx,
+                text_renderer_ctx: &self.text_renderer_ctx,
+                chromium: &self.chromium_context,
+                renderers: &self.renderers,
+                replay_buffers: &self.replay_buffers,
+                stream_fallback_timeout: self.stream_fallback_timeout,
+            },
+            scene_spec,
+            &self.output_formats,
+            &self.output_scalers,
+            &self.output_keys,
+        )
+    }
+
+    /// Format needs to be registered before the output is used in a scene.
+    pub(crate) fn register_output_format(&mut self, output_id: OutputId, format: FrameFormat) {
+        self.output_formats.insert(output_id, format);
+    }
+
+    pub(crate) fn unregister_output_format(&mut self, output_id: &OutputId) {
+        self.output_formats.remove(output_id);
+    }
+
+    pub(crate) fn register_output_watermark(
+        &mut self,
+        output_id: OutputId,
+        watermark: Watermark,
+    ) -> Result<(), RegisterWatermarkError> {
+        self.output_watermarks
+            .register(&self.wgpu_ctx, &self.renderers, output_id, watermark)
+    }
+
+    pub(crate) fn set_output_slate(
+        &mut self,
+        output_id: OutputId,
+        slate: OutputSlate,
+    ) -> Result<(), SetOutputSlateError> {
+        self.output_slates
+            .set(&self.wgpu_ctx, &self.renderers, output_id, slate)
+    }
+
+    /// Replace registered shader. Nodes from the current scene that are using it
+    /// are switched to the new shader without being recreated.
+    pub(crate) fn update_shader(
+        &mut self,
+        shader_id: &RendererId,
+        shader: Arc<Shader>,
+    ) -> Result<(), UpdateShaderError> {
+        // Nodes keep params buffers written for the old shader, so params provided
+        // by field name have to map to the same layout.
+        let same_params_layout = match self.renderers.shaders.get_ref(shader_id) {
+            Some(old_shader) => {
+                old_shader.params_type().ok() == shader.params_type().ok()
+                    && old_shader.storage_params_type().ok() == shader.storage_params_type().ok()
+            }
+            None => true,
+        };
+        for node_spec in &self.scene_spec.nodes {
+            let NodeParams::Shader {
+                shader_id: node_shader_id,
+                shader_params,
+                storage_params,
+                image_ids,
+                ..
+            } = &node_spec.params
+            else {
+                continue;
+            };
+            if node_shader_id != shader_id {
+                continue;
+            }
+
+            let has_object_fields = shader_params
+                .iter()
+                .chain(storage_params)
+                .any(ShaderParam::has_object_fields);
+            let result = match has_object_fields && !same_params_layout {
+                true => Err(ParametersValidationError::ObjectParamsLayoutChanged),
+                false => shader.validate_params(
+                    shader_params.as_ref(),
+                    storage_params.as_ref(),
+                    image_ids.len(),
+                ),
+            };
+            result.map_err(|err| {
+                UpdateShaderError::ParametersValidationError(
+                    err,
+                    shader_id.clone(),
+                    node_spec.node_id.clone(),
+                )
+            })?;
+            shader
+                .constraints()
+                .check(&self.scene_spec, &node_spec.node_id)
+                .map_err(|err| {
+                    UpdateShaderError::ConstraintsValidationError(
+                        err,
+                        shader_id.clone(),
+                        node_spec.node_id.clone(),
+                    )
+                })?;
+        }
+
+        let old_shader = self.renderers.shaders.update(shader_id, shader.clone())?;
+        self.scene.nodes.replace_shader(&old_shader, &shader);
+        Ok(())
+    }
+
     fn validate_constraints(&self, scene_spec: &SceneSpec) -> Result<(), UpdateSceneError> {
+        scene_spec.validate_resolutions(self.wgpu_ctx.capabilities.max_texture_dimension_2d)?;
         for node_spec in &scene_spec.nodes {
             node_spec
                 .constraints(&self.renderers)?
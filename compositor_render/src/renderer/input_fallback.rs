@@ -0,0 +1,96 @@
+use std::{collections::HashMap, time::Duration};
+
+use compositor_common::{
+    error::ErrorStack,
+    scene::{InputFallback, InputId, Resolution},
+};
+use log::error;
+
+use crate::{
+    renderer::RenderCtx, transformations::image_renderer::ImageNode, wgpu::texture::NodeTexture,
+};
+
+/// Used by color fallback if no frame was received from the input yet.
+const DEFAULT_FALLBACK_RESOLUTION: Resolution = Resolution {
+    width: 1920,
+    height: 1080,
+};
+
+#[derive(Default)]
+pub(crate) struct InputFallbacks {
+    fallbacks: HashMap<InputId, InputFallback>,
+    /// Created when the fallback image is shown and dropped when the input is back,
+    /// so the image is drawn again over the input frame next time.
+    image_nodes: HashMap<InputId, ImageNode>,
+}
+
+impl InputFallbacks {
+    pub fn register(&mut self, input_id: InputId, fallback: InputFallback) {
+        self.image_nodes.remove(&input_id);
+        self.fallbacks.insert(input_id, fallback);
+    }
+
+    pub fn unregister(&mut self, input_id: &InputId) {
+        self.image_nodes.remove(input_id);
+        self.fallbacks.remove(input_id);
+    }
+
+    pub fn on_input_frame(&mut self, input_id: &InputId) {
+        self.image_nodes.remove(input_id);
+    }
+
+    /// Renders fallback content of the missing input to its node texture. Returns
+    /// false if there is no fallback to show and the node should be cleared.
+    /// `fallback_timeout` is the time after which the input was considered missing.
+    pub fn render(
+        &mut self,
+        ctx: &mut RenderCtx,
+        input_id: &InputId,
+        target: &mut NodeTexture,
+        pts: Duration,
+        fallback_timeout: Duration,
+    ) -> bool {
+        let Some(fallback) = self.fallbacks.get(input_id) else {
+            return false;
+        };
+        match fallback {
+            InputFallback::FreezeLastFrame { duration } => match target.frame_pts() {
+                Some(last_pts) => pts <= last_pts + fallback_timeout + *duration,
+                None => false,
+            },
+            InputFallback::Color(color) => {
+                let resolution = target
+                    .state()
+                    .map(|state| state.resolution())
+                    .unwrap_or(DEFAULT_FALLBACK_RESOLUTION);
+                target.clear_frame_pts();
+                let state = target.ensure_size(ctx.wgpu_ctx, resolution);
+                ctx.wgpu_ctx
+                    .utils
+                    .fill_rgba_with_color(ctx.wgpu_ctx, state.rgba_texture(), *color);
+                true
+            }
+            InputFallback::Image(image_id) => {
+                if !self.image_nodes.contains_key(input_id) {
+                    let Some(image) = ctx.renderers.images.get(image_id) else {
+                        error!("Fallback image \"{image_id}\" of input \"{input_id}\" is not registered.");
+                        return false;
+                    };
+                    match ImageNode::new(ctx.wgpu_ctx, image, None) {
+                        Ok(node) => self.image_nodes.insert(input_id.clone(), node),
+                        Err(err) => {
+                            error!(
+                                "Failed to render fallback image \"{image_id}\" of input \"{input_id}\": {}",
+                                ErrorStack::new(&err).into_string()
+                            );
+                            return false;
+                        }
+                    };
+                }
+                target.clear_frame_pts();
+                self.image_nodes[input_id].render(ctx, target, pts);
+                true
+            }
+        }
+    }
+}
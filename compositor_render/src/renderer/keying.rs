@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use compositor_common::{
+    frame::FrameFormat,
+    scene::{NodeId, OutputId},
+    util::colors::RGBColor,
+};
+
+use crate::{
+    renderer::{slate::OutputFrame, RenderCtx},
+    wgpu::texture::OutputTexture,
+};
+
+struct OutputKey {
+    fill_output_id: OutputId,
+    format: FrameFormat,
+    /// Alpha of the fill frame in the Y plane, U and V planes are neutral.
+    /// `None` while the fill output is not used in the scene.
+    target: Option<OutputTexture>,
+}
+
+/// Outputs that receive the alpha matte (key) of the frame sent to another
+/// output (fill), so the graphics can be keyed downstream, e.g. by a hardware
+/// switcher. The key is rendered from the same frame as the fill, after scaling,
+/// watermark and slate, so both outputs stay in sync. Key outputs are not part
+/// of the scene, they follow their fill output across scene updates.
+#[derive(Default)]
+pub(crate) struct OutputKeys(HashMap<OutputId, OutputKey>);
+
+impl OutputKeys {
+    pub fn register(&mut self, output_id: OutputId, fill_output_id: OutputId, format: FrameFormat) {
+        self.0.insert(
+            output_id,
+            OutputKey {
+                fill_output_id,
+                format,
+                target: None,
+            },
+        );
+    }
+
+    pub fn unregister(&mut self, output_id: &OutputId) {
+        self.0.remove(output_id);
+    }
+
+    pub fn is_key(&self, output_id: &OutputId) -> bool {
+        self.0.contains_key(output_id)
+    }
+
+    /// Key outputs rendered from the frame of the fill output.
+    pub fn keys_of<'a>(
+        &'a self,
+        fill_output_id: &'a OutputId,
+    ) -> impl Iterator<Item = &'a OutputId> + 'a {
+        self.0
+            .iter()
+            .filter(move |(_, key)| &key.fill_output_id == fill_output_id)
+            .map(|(output_id, _)| output_id)
+    }
+
+    /// Allocates textures of keys with the resolution of their fill outputs in
+    /// the current scene. Called before the frame is read from outputs.
+    pub fn update_targets(
+        &mut self,
+        ctx: &RenderCtx,
+        scene_outputs: &HashMap<OutputId, (NodeId, OutputTexture)>,
+    ) {
+        for key in self.0.values_mut() {
+            let Some((_, fill_texture)) = scene_outputs.get(&key.fill_output_id) else {
+                key.target = None;
+                continue;
+            };
+            let resolution = fill_texture.resolution();
+            let is_up_to_date = key
+                .target
+                .as_ref()
+                .is_some_and(|target| target.resolution() == resolution);
+            if !is_up_to_date {
+                key.target = Some(OutputTexture::new(ctx.wgpu_ctx, resolution, key.format));
+            }
+        }
+    }
+
+    /// Renders the alpha of the fill frame to all keys of the fill output. Frames
+    /// filled with a color are opaque.
+    pub fn render<'a>(
+        &'a self,
+        ctx: &RenderCtx,
+        fill_output_id: &OutputId,
+        fill: &OutputFrame,
+    ) -> Vec<(&'a OutputId, &'a OutputTexture)> {
+        let targets: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(_, key)| &key.fill_output_id == fill_output_id)
+            .filter_map(|(output_id, key)| Some((output_id, key.target.as_ref()?)))
+            .collect();
+        for (_, target) in &targets {
+            match fill {
+                OutputFrame::Texture(texture) => ctx.wgpu_ctx.format.convert_rgba_alpha_to_yuv(
+                    ctx.wgpu_ctx,
+                    (texture.rgba_texture(), texture.bind_group()),
+                    target.yuv_textures(),
+                ),
+                OutputFrame::Color(_) => target
+                    .yuv_textures()
+                    .fill_with_color(ctx.wgpu_ctx, RGBColor::WHITE),
+            }
+        }
+        targets
+    }
+}
@@ -11,8 +11,8 @@ use crate::transformations::shader::node::ShaderNode;
 
 use crate::transformations::transition::TransitionNode;
 use crate::transformations::{
-    builtin::BuiltinNode, image_renderer::ImageNode, text_renderer::TextRendererNode,
-    web_renderer::node::WebRendererNode,
+    builtin::BuiltinNode, captions::CaptionsNode, image_renderer::ImageNode, replay::ReplayNode,
+    text_renderer::TextRendererNode, timecode::TimecodeNode, web_renderer::node::WebRendererNode,
 };
 use crate::wgpu::texture::NodeTexture;
 
@@ -23,6 +23,9 @@ pub enum RenderNode {
     Shader(ShaderNode),
     Web(WebRendererNode),
     Text(TextRendererNode),
+    Captions(CaptionsNode),
+    Timecode(TimecodeNode),
+    Replay(ReplayNode),
     Image(ImageNode),
     Builtin(BuiltinNode),
     Transition(TransitionNode),
@@ -45,9 +48,22 @@ impl RenderNode {
             NodeParams::Shader {
                 shader_id,
                 shader_params,
+                storage_params,
+                image_ids,
                 resolution,
+                sampler,
+                geometry,
             } => {
-                let node = ShaderNode::new(ctx, shader_id, shader_params, resolution)?;
+                let node = ShaderNode::new(
+                    ctx,
+                    shader_id,
+                    shader_params,
+                    storage_params,
+                    image_ids,
+                    resolution,
+                    sampler,
+                )?
+                .with_geometry(ctx, geometry)?;
                 Ok(Self::Shader(node))
             }
             NodeParams::Builtin(transformation) => {
@@ -59,13 +75,25 @@ impl RenderNode {
                 let renderer = TextRendererNode::new(ctx, text_spec.clone());
                 Ok(Self::Text(renderer))
             }
-            NodeParams::Image { image_id } => {
+            NodeParams::Captions(captions_spec) => {
+                let node = CaptionsNode::new(captions_spec).map_err(CreateNodeError::Captions)?;
+                Ok(Self::Captions(node))
+            }
+            NodeParams::Timecode(timecode_spec) => {
+                Ok(Self::Timecode(TimecodeNode::new(timecode_spec)))
+            }
+            NodeParams::Replay(replay_spec) => Ok(Self::Replay(ReplayNode::new(ctx, replay_spec)?)),
+            NodeParams::Image {
+                image_id,
+                resolution,
+            } => {
                 let image = ctx
                     .renderers
                     .images
                     .get(image_id)
                     .ok_or_else(|| CreateNodeError::ImageNotFound(image_id.clone()))?;
-                let node = ImageNode::new(image);
+                let node = ImageNode::new(ctx.wgpu_ctx, image, *resolution)
+                    .map_err(|err| CreateNodeError::SvgRasterization(err, image_id.clone()))?;
                 Ok(Self::Image(node))
             }
             NodeParams::Transition(transition_spec) => {
@@ -81,6 +109,7 @@ impl RenderNode {
         sources: &[(&NodeId, &NodeTexture)],
         target: &mut NodeTexture,
         pts: Duration,
+        frame_index: u32,
     ) {
         if self.should_fallback(sources) {
             target.clear();
@@ -88,16 +117,21 @@ impl RenderNode {
         }
 
         match self {
-            RenderNode::Shader(ref shader) => {
-                shader.render(sources, target, pts);
+            RenderNode::Shader(shader) => {
+                shader.render(sources, target, pts, frame_index);
+            }
+            RenderNode::Builtin(builtin_node) => {
+                builtin_node.render(sources, target, pts, frame_index)
             }
-            RenderNode::Builtin(builtin_node) => builtin_node.render(sources, target, pts),
             RenderNode::Web(renderer) => renderer.render(ctx, sources, target),
             RenderNode::Text(ref renderer) => {
                 renderer.render(ctx, target);
             }
+            RenderNode::Captions(node) => node.render(ctx, target, pts),
+            RenderNode::Timecode(node) => node.render(ctx, target, pts),
+            RenderNode::Replay(node) => node.render(ctx, target, pts),
             RenderNode::Image(ref node) => node.render(ctx, target, pts),
-            RenderNode::Transition(node) => node.render(sources, target, pts),
+            RenderNode::Transition(node) => node.render(sources, target, pts, frame_index),
             RenderNode::InputStream => {
                 // Nothing to do, textures on input nodes should be populated
                 // at the start of render loop
@@ -105,14 +139,67 @@ impl RenderNode {
         }
     }
 
+    /// Nodes that only record GPU commands and don't need `RenderCtx`, so nodes
+    /// that don't depend on each other can be encoded on separate threads.
+    pub fn is_gpu_only(&self) -> bool {
+        matches!(
+            self,
+            RenderNode::Shader(_) | RenderNode::Builtin(_) | RenderNode::Transition(_)
+        )
+    }
+
+    /// Same as `render` for `is_gpu_only` nodes, but the render is returned as
+    /// a job that can run on another thread. `None` if there is nothing to render
+    /// or the node is not `is_gpu_only`.
+    pub(crate) fn gpu_render_job<'a>(
+        &'a mut self,
+        sources: Vec<(&'a NodeId, &'a NodeTexture)>,
+        target: &'a mut NodeTexture,
+    ) -> Option<GpuRenderJob<'a>> {
+        if self.should_fallback(&sources) {
+            target.clear();
+            return None;
+        }
+        let node = match self {
+            RenderNode::Shader(node) => GpuRenderNode::Shader(node),
+            RenderNode::Builtin(node) => GpuRenderNode::Builtin(node),
+            RenderNode::Transition(node) => GpuRenderNode::Transition(node),
+            _ => return None,
+        };
+        Some(GpuRenderJob {
+            node,
+            sources,
+            target,
+        })
+    }
+
+    /// Output of static nodes depends only on their inputs, so it doesn't have
+    /// to be rendered again until one of the inputs changes.
+    pub fn is_static(&self) -> bool {
+        match self {
+            RenderNode::Text(_) => true,
+            RenderNode::Image(node) => !node.is_animated(),
+            RenderNode::Builtin(node) => node.is_static(),
+            RenderNode::Shader(_)
+            | RenderNode::Web(_)
+            | RenderNode::Captions(_)
+            | RenderNode::Timecode(_)
+            | RenderNode::Replay(_)
+            | RenderNode::Transition(_)
+            | RenderNode::InputStream => false,
+        }
+    }
+
     // TODO: remove this function (should be handled dynamically on output)
     pub fn resolution(&self) -> Option<Resolution> {
         match self {
             RenderNode::Shader(node) => Some(node.resolution()),
             RenderNode::Web(node) => Some(node.resolution()),
             RenderNode::Text(node) => Some(node.resolution()),
+            RenderNode::Captions(node) => Some(node.resolution()),
+            RenderNode::Timecode(node) => Some(node.resolution()),
             RenderNode::Image(node) => Some(node.resolution()),
-            RenderNode::InputStream => None,
+            RenderNode::Replay(_) | RenderNode::InputStream => None,
             RenderNode::Builtin(node) => node.resolution_from_spec(),
             RenderNode::Transition(node) => node.resolution(),
         }
@@ -140,6 +227,9 @@ impl RenderNode {
             RenderNode::Shader(shader_node) => shader_node.fallback_strategy(),
             RenderNode::Web(web_renderer_node) => web_renderer_node.fallback_strategy(),
             RenderNode::Text(_) => FallbackStrategy::NeverFallback,
+            RenderNode::Captions(_) => FallbackStrategy::NeverFallback,
+            RenderNode::Timecode(_) => FallbackStrategy::NeverFallback,
+            RenderNode::Replay(_) => FallbackStrategy::NeverFallback,
             RenderNode::Image(_) => FallbackStrategy::NeverFallback,
             RenderNode::Builtin(builtin_node) => builtin_node.fallback_strategy(),
             RenderNode::InputStream => FallbackStrategy::NeverFallback,
@@ -148,28 +238,65 @@ impl RenderNode {
     }
 }
 
+enum GpuRenderNode<'a> {
+    Shader(&'a mut ShaderNode),
+    Builtin(&'a mut BuiltinNode),
+    Transition(&'a mut TransitionNode),
+}
+
+/// Render of a node created with `RenderNode::gpu_render_job`. Commands are
+/// only recorded, they are submitted with `WgpuCtx::submit_pending`.
+pub(crate) struct GpuRenderJob<'a> {
+    node: GpuRenderNode<'a>,
+    sources: Vec<(&'a NodeId, &'a NodeTexture)>,
+    target: &'a mut NodeTexture,
+}
+
+impl GpuRenderJob<'_> {
+    pub fn run(self, pts: Duration, frame_index: u32) {
+        let GpuRenderJob {
+            node,
+            sources,
+            target,
+        } = self;
+        match node {
+            GpuRenderNode::Shader(node) => node.render(&sources, target, pts, frame_index),
+            GpuRenderNode::Builtin(node) => node.render(&sources, target, pts, frame_index),
+            GpuRenderNode::Transition(node) => node.render(&sources, target, pts, frame_index),
+        }
+    }
+}
+
 pub struct Node {
     pub node_id: NodeId,
     pub output: NodeTexture,
     pub inputs: Vec<NodeId>,
     pub fallback: Option<NodeId>,
+    /// Overrides `stream_fallback_timeout` for input streams used by this node.
+    pub input_fallback_timeout: Option<Duration>,
     pub renderer: RenderNode,
+    /// Incremented every time the output is rendered. Nodes that use this node
+    /// as an input compare it to detect changes.
+    pub output_version: u64,
+    /// Nodes used as inputs (after fallbacks) and their versions from the last
+    /// render of a static node. `None` if the node has to be rendered again.
+    pub rendered_inputs: Option<Vec<(NodeId, u64)>>,
 }
 
 impl Node {
     pub fn new(ctx: &RenderCtx, spec: &NodeSpec) -> Result<Self, CreateNodeError> {
         let node = RenderNode::new(ctx, spec)?;
-        let mut output = NodeTexture::new();
-        if let Some(resolution) = node.resolution() {
-            output.ensure_size(ctx.wgpu_ctx, resolution);
-        }
-
+        // Texture is allocated on the first render, after nodes of the previous scene
+        // are dropped, so it can be reused from the texture pool.
         Ok(Self {
             node_id: spec.node_id.clone(),
             renderer: node,
             inputs: spec.input_pads.clone(),
             fallback: spec.fallback_id.clone(),
-            output,
+            input_fallback_timeout: spec.input_fallback_timeout,
+            output: NodeTexture::new(),
+            output_version: 0,
+            rendered_inputs: None,
         })
     }
 
@@ -181,7 +308,10 @@ impl Node {
             renderer: RenderNode::InputStream,
             inputs: vec![],
             fallback: None,
+            input_fallback_timeout: None,
             output,
+            output_version: 0,
+            rendered_inputs: None,
         }
     }
 }
@@ -220,6 +350,9 @@ impl NodeSpecExt for NodeSpec {
                     )
                 }),
             NodeParams::Text(_) => Ok(NodeParams::text_constraints()),
+            NodeParams::Captions(_) => Ok(NodeParams::captions_constraints()),
+            NodeParams::Timecode(_) => Ok(NodeParams::timecode_constraints()),
+            NodeParams::Replay(_) => Ok(NodeParams::replay_constraints()),
             NodeParams::Image { .. } => Ok(NodeParams::image_constraints()),
             NodeParams::Builtin(transformation) => Ok(transformation.constraints()),
             NodeParams::Transition(spec) => Ok(spec.end.constraints()),
@@ -1,9 +1,12 @@
 use std::{
     collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    thread,
     time::Duration,
 };
 
 use compositor_common::{
+    frame::FrameData,
     scene::{InputId, NodeId, OutputId},
     util::colors::RGBColor,
     Frame,
@@ -12,28 +15,42 @@ use log::error;
 
 use crate::{
     renderer::{
-        node::Node,
+        input_fallback::InputFallbacks,
+        keying::OutputKeys,
+        node::{GpuRenderJob, Node, RenderNode},
+        scaling::OutputScalers,
         scene::{InternalSceneError, Scene, SceneNodesSet},
+        slate::{OutputFrame, OutputSlates},
+        test_pattern::InputTestPatterns,
+        watermark::OutputWatermarks,
         RenderCtx,
     },
+    wgpu::texture::{NodeTexture, NodeTextureState},
     FrameSet,
 };
 
 pub(super) fn populate_inputs(
-    ctx: &RenderCtx,
+    ctx: &mut RenderCtx,
     scene: &mut Scene,
     frame_set: &mut FrameSet<InputId>,
+    test_patterns: &InputTestPatterns,
+    fallbacks: &mut InputFallbacks,
 ) -> Result<(), InternalSceneError> {
+    let mut frames_pts = HashMap::with_capacity(scene.inputs.len());
     for (input_id, input_textures) in &mut scene.inputs {
         let Some(frame) = frame_set.frames.remove(input_id) else {
             input_textures.clear();
             continue;
         };
-        if Duration::saturating_sub(frame_set.pts, ctx.stream_fallback_timeout) > frame.pts {
+        let fallback_timeout =
+            input_fallback_timeout(ctx, &scene.input_fallback_timeouts, input_id);
+        if Duration::saturating_sub(frame_set.pts, fallback_timeout) > frame.pts {
             input_textures.clear();
             continue;
         }
 
+        frames_pts.insert(input_id.clone(), frame.pts);
+        fallbacks.on_input_frame(input_id);
         input_textures.upload(ctx.wgpu_ctx, frame);
     }
 
@@ -45,64 +62,111 @@ pub(super) fn populate_inputs(
             let node_texture = node
                 .output
                 .ensure_size(ctx.wgpu_ctx, input_textures.resolution());
-            ctx.wgpu_ctx.format.convert_yuv_to_rgba(
-                ctx.wgpu_ctx,
-                (input_textures.yuv_textures(), input_textures.bind_group()),
-                node_texture.rgba_texture(),
-            );
-        } else {
+            input_textures.convert_to_rgba(ctx.wgpu_ctx, node_texture.rgba_texture());
+            node.output.generate_mipmaps(ctx.wgpu_ctx);
+            if let Some(pts) = frames_pts.get(input_id) {
+                node.output.set_frame_pts(*pts);
+            }
+        } else if !test_patterns.render(ctx, input_id, &mut node.output, frame_set.pts)
+            && !fallbacks.render(
+                ctx,
+                input_id,
+                &mut node.output,
+                frame_set.pts,
+                input_fallback_timeout(ctx, &scene.input_fallback_timeouts, input_id),
+            )
+        {
             node.output.clear()
         }
     }
     Ok(())
 }
 
-pub(super) fn read_outputs(
+fn input_fallback_timeout(
     ctx: &RenderCtx,
+    timeouts: &HashMap<InputId, Duration>,
+    input_id: &InputId,
+) -> Duration {
+    timeouts
+        .get(input_id)
+        .copied()
+        .unwrap_or(ctx.stream_fallback_timeout)
+}
+
+/// `inputs_missing` is true if none of the inputs delivered a frame, outputs
+/// with a slate set to show in that case send the slate instead of the scene.
+/// Key outputs receive the alpha of the frame sent to their fill output.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn read_outputs(
+    ctx: &mut RenderCtx,
     scene: &mut Scene,
+    watermarks: &mut OutputWatermarks,
+    scalers: &mut OutputScalers,
+    keys: &mut OutputKeys,
+    slates: &mut OutputSlates,
+    inputs_missing: bool,
     pts: Duration,
 ) -> Result<HashMap<OutputId, Frame>, InternalSceneError> {
+    keys.update_targets(ctx, &scene.outputs);
+    let keys = &*keys;
     let mut pending_downloads = Vec::with_capacity(scene.outputs.len());
     for (output_id, (node_id, output_texture)) in &scene.outputs {
-        let node = scene.nodes.node_or_fallback(node_id)?;
-        match node.output.state() {
-            Some(node) => {
-                ctx.wgpu_ctx.format.convert_rgba_to_yuv(
-                    ctx.wgpu_ctx,
-                    ((node.rgba_texture()), (node.bind_group())),
-                    output_texture.yuv_textures(),
-                );
-            }
+        let resolution = output_texture.resolution();
+        let frame = match slates.render(ctx, output_id, inputs_missing, resolution, pts) {
+            Some(slate) => slate,
             None => {
-                let (y, u, v) = RGBColor::BLACK.to_yuv();
-                ctx.wgpu_ctx.utils.fill_r8_with_value(
-                    ctx.wgpu_ctx,
-                    output_texture.yuv_textures().plane(0),
-                    y,
+                let node = scene.nodes.node_or_fallback(node_id)?;
+                let node_texture = match node.output.state() {
+                    Some(texture) => scalers
+                        .render(ctx, output_id, texture.rgba_texture())
+                        .or(Some(texture)),
+                    None => None,
+                };
+                let watermarked = watermarks.render(
+                    ctx,
+                    output_id,
+                    node_texture.map(NodeTextureState::rgba_texture),
+                    resolution,
+                    pts,
                 );
-                ctx.wgpu_ctx.utils.fill_r8_with_value(
-                    ctx.wgpu_ctx,
-                    output_texture.yuv_textures().plane(1),
-                    u,
-                );
-                ctx.wgpu_ctx.utils.fill_r8_with_value(
+                match watermarked.or(node_texture) {
+                    Some(texture) => OutputFrame::Texture(texture),
+                    None => OutputFrame::Color(RGBColor::BLACK),
+                }
+            }
+        };
+        for (key_output_id, key_texture) in keys.render(ctx, output_id, &frame) {
+            pending_downloads.push((
+                key_output_id,
+                key_texture.start_download(ctx.wgpu_ctx),
+                key_texture.resolution(),
+                key_texture.format(),
+            ));
+        }
+        match frame {
+            OutputFrame::Texture(texture) => {
+                ctx.wgpu_ctx.format.convert_rgba_to_yuv(
                     ctx.wgpu_ctx,
-                    output_texture.yuv_textures().plane(2),
-                    v,
+                    ((texture.rgba_texture()), (texture.bind_group())),
+                    output_texture.yuv_textures(),
                 );
             }
+            OutputFrame::Color(color) => output_texture
+                .yuv_textures()
+                .fill_with_color(ctx.wgpu_ctx, color),
         };
         let yuv_pending = output_texture.start_download(ctx.wgpu_ctx);
         pending_downloads.push((
             output_id,
             yuv_pending,
             output_texture.resolution().to_owned(),
+            output_texture.format(),
         ));
     }
     ctx.wgpu_ctx.device.poll(wgpu::MaintainBase::Wait);
 
     let mut result = HashMap::new();
-    for (output_id, yuv_pending, resolution) in pending_downloads {
+    for (output_id, yuv_pending, resolution, format) in pending_downloads {
         let yuv_data = match yuv_pending.wait() {
             Ok(data) => data,
             Err(err) => {
@@ -113,8 +177,9 @@ pub(super) fn read_outputs(
         result.insert(
             output_id.clone(),
             Frame {
-                data: yuv_data,
+                data: FrameData::PlanarYuv(yuv_data),
                 resolution,
+                format,
                 pts,
             },
         );
@@ -126,58 +191,209 @@ pub(super) fn run_transforms(
     ctx: &mut RenderCtx,
     scene: &mut Scene,
     pts: Duration,
+    frame_index: u32,
 ) -> Result<(), InternalSceneError> {
+    let output_node_ids: Vec<_> = scene
+        .outputs
+        .values()
+        .map(|(node_id, _)| node_id.clone())
+        .collect();
     let mut already_rendered = HashSet::new();
-    for (node_id, _) in scene.outputs.values() {
-        render_node(ctx, &mut scene.nodes, pts, node_id, &mut already_rendered)?;
+    // Nodes of a wave don't depend on each other, so GPU only nodes of the
+    // wave are encoded on multiple threads.
+    for wave in scene.nodes.render_waves(&output_node_ids)? {
+        let (gpu_node_ids, other_node_ids): (Vec<_>, Vec<_>) =
+            wave.into_iter().partition(|node_id| {
+                scene
+                    .nodes
+                    .node(node_id)
+                    .is_ok_and(|node| node.renderer.is_gpu_only())
+            });
+        for node_id in &other_node_ids {
+            render_node(
+                ctx,
+                &mut scene.nodes,
+                &scene.input_fallback_timeouts,
+                pts,
+                frame_index,
+                node_id,
+                &mut already_rendered,
+            )?;
+        }
+
+        // Fallbacks of the other nodes could have already rendered some of them.
+        let gpu_node_ids: Vec<_> = gpu_node_ids
+            .into_iter()
+            .filter(|node_id| !already_rendered.contains(node_id))
+            .collect();
+        render_gpu_nodes(
+            ctx,
+            &mut scene.nodes,
+            &scene.input_fallback_timeouts,
+            pts,
+            frame_index,
+            &gpu_node_ids,
+        )?;
+        for node_id in gpu_node_ids {
+            let node = scene.nodes.node(&node_id)?;
+            let fallback_id = match node.output.is_empty() {
+                true => node.fallback.clone(),
+                false => None,
+            };
+            already_rendered.insert(node_id);
+            if let Some(fallback_id) = fallback_id {
+                render_node(
+                    ctx,
+                    &mut scene.nodes,
+                    &scene.input_fallback_timeouts,
+                    pts,
+                    frame_index,
+                    &fallback_id,
+                    &mut already_rendered,
+                )?;
+            }
+        }
     }
+    // Nodes only record their commands, all of them are submitted together.
+    ctx.wgpu_ctx.submit_pending();
     Ok(())
 }
 
-pub(super) fn render_node(
-    ctx: &mut RenderCtx,
+/// Renders `is_gpu_only` nodes that don't depend on each other. Jobs are split
+/// between threads, each thread encodes its own command buffers.
+fn render_gpu_nodes(
+    ctx: &RenderCtx,
     nodes: &mut SceneNodesSet,
+    input_fallback_timeouts: &HashMap<InputId, Duration>,
     pts: Duration,
-    node_id: &NodeId,
-    already_rendered: &mut HashSet<NodeId>,
+    frame_index: u32,
+    node_ids: &[NodeId],
 ) -> Result<(), InternalSceneError> {
-    if already_rendered.contains(node_id) {
+    let missing = NodeTexture::new();
+    let mut passes = nodes.node_render_passes(node_ids)?;
+    let mut jobs: Vec<GpuRenderJob> = passes
+        .iter_mut()
+        .filter_map(|pass| {
+            render_sources(
+                pass,
+                ctx.stream_fallback_timeout,
+                input_fallback_timeouts,
+                pts,
+                &missing,
+            )
+        })
+        .filter_map(|(renderer, sources, target)| renderer.gpu_render_job(sources, target))
+        .collect();
+
+    let threads = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(jobs.len());
+    if threads <= 1 {
+        jobs.into_iter().for_each(|job| job.run(pts, frame_index));
         return Ok(());
     }
-    // Make sure all input are rendered
-    {
-        let input_ids: Vec<_> = nodes.node(node_id)?.inputs.to_vec();
-        for input_id in input_ids {
-            render_node(ctx, nodes, pts, &input_id, already_rendered)?;
+    let jobs_per_thread = jobs.len().div_ceil(threads);
+    thread::scope(|scope| {
+        while jobs.len() > jobs_per_thread {
+            let thread_jobs = jobs.split_off(jobs.len() - jobs_per_thread);
+            scope.spawn(move || {
+                thread_jobs
+                    .into_iter()
+                    .for_each(|job| job.run(pts, frame_index))
+            });
         }
-    }
-    // Try to render node
-    //
-    // - If node texture is empty after the render and fallback_id was
-    // defined return that Some(fallback_id) from this block.
-    // - If node texture is not empty return None, even if fallback_id
-    // was defined
-    let fallback_id = {
-        let NodeRenderPass { node, inputs } = nodes.node_render_pass(node_id)?;
-        let input_textures: Vec<_> = inputs
-            .iter()
-            .map(|(node_id, node)| (node_id, &node.output))
-            .collect();
-        node.renderer
-            .render(ctx, &input_textures, &mut node.output, pts);
+        // The rest is encoded on the current thread.
+        jobs.drain(..).for_each(|job| job.run(pts, frame_index));
+    });
+    Ok(())
+}
 
-        match node.output.is_empty() {
-            true => node.fallback.clone(),
-            false => None,
+pub(super) fn render_node(
+    ctx: &mut RenderCtx,
+    nodes: &mut SceneNodesSet,
+    input_fallback_timeouts: &HashMap<InputId, Duration>,
+    pts: Duration,
+    frame_index: u32,
+    node_id: &NodeId,
+    already_rendered: &mut HashSet<NodeId>,
+) -> Result<(), InternalSceneError> {
+    nodes.render_with_inputs(node_id, already_rendered, &mut |nodes, node_id| {
+        let missing = NodeTexture::new();
+        let mut pass = nodes.node_render_pass(node_id)?;
+        let sources = render_sources(
+            &mut pass,
+            ctx.stream_fallback_timeout,
+            input_fallback_timeouts,
+            pts,
+            &missing,
+        );
+        if let Some((renderer, sources, target)) = sources {
+            renderer.render(ctx, &sources, target, pts, frame_index);
         }
-    };
+        Ok(())
+    })
+}
 
-    // Try to render a fallback
-    if let Some(fallback_id) = fallback_id {
-        render_node(ctx, nodes, pts, &fallback_id, already_rendered)?;
+/// Renderer, inputs and the target of the node, `None` if the node doesn't have
+/// to be rendered. Inputs with stale frames are replaced with `missing`.
+fn render_sources<'a>(
+    NodeRenderPass { node, inputs }: &'a mut NodeRenderPass,
+    stream_fallback_timeout: Duration,
+    input_fallback_timeouts: &HashMap<InputId, Duration>,
+    pts: Duration,
+    missing: &'a NodeTexture,
+) -> Option<(
+    &'a mut RenderNode,
+    Vec<(&'a NodeId, &'a NodeTexture)>,
+    &'a mut NodeTexture,
+)> {
+    let input_versions: Vec<_> = inputs
+        .iter()
+        .map(|(_, input)| (input.node_id.clone(), input.output_version))
+        .collect();
+    // Static nodes keep the texture from the previous frame if their
+    // inputs didn't change, e.g. layouts of images and text.
+    let is_up_to_date =
+        node.renderer.is_static() && node.rendered_inputs.as_ref() == Some(&input_versions);
+    if is_up_to_date {
+        return None;
     }
+    let usage_timeout = node
+        .input_fallback_timeout
+        .unwrap_or(stream_fallback_timeout);
+    let sources = inputs
+        .iter()
+        .map(|(node_id, input)| {
+            match is_stale_input(input, usage_timeout, input_fallback_timeouts, pts) {
+                true => (node_id, missing),
+                false => (node_id, &input.output),
+            }
+        })
+        .collect();
+    node.output_version = node.output_version.wrapping_add(1);
+    node.rendered_inputs = node.renderer.is_static().then_some(input_versions);
+    Some((&mut node.renderer, sources, &mut node.output))
+}
 
-    Ok(())
+/// Input frames are kept for the longest timeout of all nodes that use the input.
+/// Nodes with a shorter timeout treat the frame as missing once it's older than
+/// their own timeout. Frames older than the longest timeout are shown by the input
+/// fallback (e.g. frozen), so they are not stale for any node.
+fn is_stale_input(
+    input: &Node,
+    usage_timeout: Duration,
+    input_fallback_timeouts: &HashMap<InputId, Duration>,
+    pts: Duration,
+) -> bool {
+    let (RenderNode::InputStream, Some(frame_pts)) = (&input.renderer, input.output.frame_pts())
+    else {
+        return false;
+    };
+    let Some(kept_for) = input_fallback_timeouts.get(&input.node_id.clone().into()) else {
+        return false;
+    };
+    let frame_age = pts.saturating_sub(frame_pts);
+    frame_age > usage_timeout && frame_age <= *kept_for
 }
 
 pub(crate) struct NodeRenderPass<'a> {
@@ -1,10 +1,17 @@
 use std::sync::Arc;
 
+use compositor_common::{
+    renderer_spec::{FallbackStrategy, RendererId},
+    scene::{constraints::NodeConstraints, shader::ShaderParamType, NodeParams},
+};
+
 use crate::{
+    error::RendererInfoError,
     registry::{RegistryType, RendererRegistry},
     transformations::{
         builtin::{error::InitBuiltinError, transformations::BuiltinTransformations},
         image_renderer::Image,
+        mesh::Mesh,
         shader::Shader,
         web_renderer::WebRenderer,
     },
@@ -12,10 +19,27 @@ use crate::{
 
 use super::WgpuCtx;
 
+/// Constraints of nodes that use a registered renderer, e.g. so scene builders
+/// can present only valid options.
+#[derive(Debug, Clone)]
+pub struct RendererInfo {
+    pub constraints: NodeConstraints,
+    pub fallback_strategy: FallbackStrategy,
+    /// Structure of params expected by the shader, `None` for other renderers
+    /// and for shaders without the user-defined binding.
+    pub shader_params: Option<ShaderParamType>,
+    /// Same as `shader_params`, but for the user-defined storage buffer.
+    pub storage_params: Option<ShaderParamType>,
+    /// Number of images that can be bound to the shader, 0 for other renderers and
+    /// for shaders without the images binding.
+    pub max_images: usize,
+}
+
 pub(crate) struct Renderers {
     pub(crate) shaders: RendererRegistry<Arc<Shader>>,
     pub(crate) web_renderers: RendererRegistry<Arc<WebRenderer>>,
     pub(crate) images: RendererRegistry<Image>,
+    pub(crate) meshes: RendererRegistry<Mesh>,
     pub(crate) builtin: BuiltinTransformations,
 }
 
@@ -25,7 +49,67 @@ impl Renderers {
             shaders: RendererRegistry::new(RegistryType::Shader),
             web_renderers: RendererRegistry::new(RegistryType::WebRenderer),
             images: RendererRegistry::new(RegistryType::Image),
+            meshes: RendererRegistry::new(RegistryType::Mesh),
             builtin: BuiltinTransformations::new(&wgpu_ctx)?,
         })
     }
+
+    pub fn info(
+        &self,
+        renderer_id: &RendererId,
+        registry_type: RegistryType,
+    ) -> Result<RendererInfo, RendererInfoError> {
+        let not_found = || RendererInfoError::NotFound {
+            item_type: registry_type.registry_item_name(),
+            renderer_id: renderer_id.clone(),
+        };
+        match registry_type {
+            RegistryType::Shader => {
+                let shader = self.shaders.get_ref(renderer_id).ok_or_else(not_found)?;
+                let unsupported_params =
+                    |err| RendererInfoError::UnsupportedShaderParams(err, renderer_id.clone());
+                Ok(RendererInfo {
+                    constraints: shader.constraints().clone(),
+                    fallback_strategy: shader.fallback_strategy(),
+                    shader_params: shader.params_type().map_err(unsupported_params)?,
+                    storage_params: shader.storage_params_type().map_err(unsupported_params)?,
+                    max_images: shader.max_images(),
+                })
+            }
+            RegistryType::WebRenderer => {
+                let web_renderer = self
+                    .web_renderers
+                    .get_ref(renderer_id)
+                    .ok_or_else(not_found)?;
+                Ok(RendererInfo {
+                    constraints: web_renderer.constraints().clone(),
+                    fallback_strategy: web_renderer.fallback_strategy(),
+                    shader_params: None,
+                    storage_params: None,
+                    max_images: 0,
+                })
+            }
+            RegistryType::Image => {
+                self.images.get_ref(renderer_id).ok_or_else(not_found)?;
+                Ok(RendererInfo {
+                    constraints: NodeParams::image_constraints().clone(),
+                    fallback_strategy: FallbackStrategy::NeverFallback,
+                    shader_params: None,
+                    storage_params: None,
+                    max_images: 0,
+                })
+            }
+            // meshes are drawn by shader nodes, so they don't constrain nodes on their own
+            RegistryType::Mesh => {
+                self.meshes.get_ref(renderer_id).ok_or_else(not_found)?;
+                Ok(RendererInfo {
+                    constraints: NodeConstraints(Vec::new()),
+                    fallback_strategy: FallbackStrategy::NeverFallback,
+                    shader_params: None,
+                    storage_params: None,
+                    max_images: 0,
+                })
+            }
+        }
+    }
 }
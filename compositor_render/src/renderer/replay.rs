@@ -0,0 +1,230 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use compositor_common::{scene::InputId, Frame};
+
+use crate::{error::ReplayBufferError, FrameSet};
+
+/// Last frames of inputs, kept in memory so they can be played back by replay
+/// nodes. Frames share their data with the frames passed to the renderer, so
+/// only frames older than the current one take additional memory.
+#[derive(Default)]
+pub(crate) struct ReplayBuffers {
+    buffers: HashMap<InputId, ReplayBuffer>,
+    /// PTS of the last rendered frame set.
+    last_pts: Duration,
+}
+
+struct ReplayBuffer {
+    duration: Duration,
+    frames: VecDeque<Frame>,
+    playback: Option<Playback>,
+}
+
+/// Range of recorded frames played back by replay nodes of the input.
+struct Playback {
+    /// PTS of the first and the last played back frame.
+    from: Duration,
+    to: Duration,
+    speed: f64,
+    /// Output PTS at which the playback started.
+    started_at: Duration,
+}
+
+impl Playback {
+    /// PTS of the recorded frame shown at the output PTS, `None` after the
+    /// playback ended.
+    fn source_pts(&self, pts: Duration) -> Option<Duration> {
+        let elapsed = pts.saturating_sub(self.started_at).mul_f64(self.speed);
+        let source_pts = self.from + elapsed;
+        (source_pts <= self.to).then_some(source_pts)
+    }
+
+    /// Output PTS at which the playback ends.
+    fn end(&self) -> Duration {
+        self.started_at + (self.to - self.from).div_f64(self.speed)
+    }
+}
+
+impl ReplayBuffers {
+    pub fn register(&mut self, input_id: InputId, duration: Duration) {
+        self.buffers.insert(
+            input_id,
+            ReplayBuffer {
+                duration,
+                frames: VecDeque::new(),
+                playback: None,
+            },
+        );
+    }
+
+    pub fn unregister(&mut self, input_id: &InputId) {
+        self.buffers.remove(input_id);
+    }
+
+    pub fn contains(&self, input_id: &InputId) -> bool {
+        self.buffers.contains_key(input_id)
+    }
+
+    /// Records frames of inputs with a replay buffer. Called before the frame
+    /// set is rendered.
+    pub fn push_frames(&mut self, frame_set: &FrameSet<InputId>) {
+        self.last_pts = frame_set.pts;
+        for (input_id, buffer) in &mut self.buffers {
+            if let Some(frame) = frame_set.frames.get(input_id) {
+                // The same frame is passed again if the input is slower than the output.
+                let is_new = buffer
+                    .frames
+                    .back()
+                    .map_or(true, |last| last.pts < frame.pts);
+                if is_new {
+                    buffer.frames.push_back(frame.clone());
+                }
+            }
+            if buffer
+                .playback
+                .as_ref()
+                .is_some_and(|playback| frame_set.pts > playback.end())
+            {
+                buffer.playback = None;
+            }
+
+            let Some(last) = buffer.frames.back() else {
+                continue;
+            };
+            // Frames that are still going to be played back are kept even if they
+            // are older than the buffer duration.
+            let keep_from = match &buffer.playback {
+                Some(playback) => last.pts.saturating_sub(buffer.duration).min(playback.from),
+                None => last.pts.saturating_sub(buffer.duration),
+            };
+            while buffer
+                .frames
+                .front()
+                .is_some_and(|frame| frame.pts < keep_from)
+            {
+                buffer.frames.pop_front();
+            }
+        }
+    }
+
+    /// Plays back frames starting `offset` before the last recorded frame, at
+    /// `speed` relative to the real time. Playback ends after `duration` of
+    /// recorded frames or at the frame that was the last one when it started.
+    pub fn start(
+        &mut self,
+        input_id: &InputId,
+        offset: Duration,
+        duration: Option<Duration>,
+        speed: f64,
+    ) -> Result<(), ReplayBufferError> {
+        let buffer = self
+            .buffers
+            .get_mut(input_id)
+            .ok_or_else(|| ReplayBufferError::NotFound(input_id.clone()))?;
+        let (Some(first), Some(last)) = (buffer.frames.front(), buffer.frames.back()) else {
+            return Err(ReplayBufferError::Empty(input_id.clone()));
+        };
+        let from = last.pts.saturating_sub(offset).max(first.pts);
+        let to = match duration {
+            Some(duration) => (from + duration).min(last.pts),
+            None => last.pts,
+        };
+        buffer.playback = Some(Playback {
+            from,
+            to,
+            speed,
+            started_at: self.last_pts,
+        });
+        Ok(())
+    }
+
+    pub fn stop(&mut self, input_id: &InputId) -> Result<(), ReplayBufferError> {
+        let buffer = self
+            .buffers
+            .get_mut(input_id)
+            .ok_or_else(|| ReplayBufferError::NotFound(input_id.clone()))?;
+        buffer.playback = None;
+        Ok(())
+    }
+
+    /// Recorded frame that should be shown at the output PTS, `None` if no replay
+    /// of the input is playing.
+    pub fn frame(&self, input_id: &InputId, pts: Duration) -> Option<&Frame> {
+        let buffer = self.buffers.get(input_id)?;
+        let source_pts = buffer.playback.as_ref()?.source_pts(pts)?;
+        let index = buffer
+            .frames
+            .partition_point(|frame| frame.pts <= source_pts);
+        buffer.frames.get(index.checked_sub(1)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use compositor_common::{
+        frame::{FrameData, FrameFormat, YuvData},
+        scene::{NodeId, Resolution},
+    };
+
+    use super::*;
+
+    fn frame_set(input_id: &InputId, pts_ms: u64) -> FrameSet<InputId> {
+        let pts = Duration::from_millis(pts_ms);
+        let frame = Frame {
+            data: FrameData::PlanarYuv(YuvData {
+                y_plane: Bytes::new(),
+                u_plane: Bytes::new(),
+                v_plane: Bytes::new(),
+            }),
+            resolution: Resolution {
+                width: 2,
+                height: 2,
+            },
+            format: FrameFormat::default(),
+            pts,
+        };
+        FrameSet {
+            frames: HashMap::from([(input_id.clone(), frame)]),
+            pts,
+        }
+    }
+
+    fn frame_pts_ms(buffers: &ReplayBuffers, input_id: &InputId, pts_ms: u64) -> Option<u64> {
+        buffers
+            .frame(input_id, Duration::from_millis(pts_ms))
+            .map(|frame| frame.pts.as_millis() as u64)
+    }
+
+    #[test]
+    fn slow_motion_replay() {
+        let input_id = InputId(NodeId("input".into()));
+        let mut buffers = ReplayBuffers::default();
+        buffers.register(input_id.clone(), Duration::from_millis(1000));
+        for pts_ms in (0..=2000).step_by(100) {
+            buffers.push_frames(&frame_set(&input_id, pts_ms));
+        }
+        // older frames are dropped
+        assert_eq!(buffers.buffers[&input_id].frames.len(), 11);
+
+        buffers
+            .start(&input_id, Duration::from_millis(500), None, 0.5)
+            .unwrap();
+        assert_eq!(frame_pts_ms(&buffers, &input_id, 2000), Some(1500));
+        assert_eq!(frame_pts_ms(&buffers, &input_id, 2200), Some(1600));
+        assert_eq!(frame_pts_ms(&buffers, &input_id, 2250), Some(1600));
+
+        // frames needed by the playback are kept
+        for pts_ms in (2100..=2900).step_by(100) {
+            buffers.push_frames(&frame_set(&input_id, pts_ms));
+        }
+        assert_eq!(frame_pts_ms(&buffers, &input_id, 2900), Some(1900));
+
+        // playback ends at the last frame recorded before it started
+        assert_eq!(frame_pts_ms(&buffers, &input_id, 3000), Some(2000));
+        assert_eq!(frame_pts_ms(&buffers, &input_id, 3100), None);
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use compositor_common::scene::{OutputId, Resolution};
+
+use crate::{
+    renderer::RenderCtx,
+    wgpu::texture::{NodeTexture, NodeTextureState, RGBATexture},
+};
+
+struct OutputScaler {
+    resolution: Resolution,
+    /// Scaled output frame, converted to YUV instead of the node texture.
+    target: NodeTexture,
+}
+
+/// Outputs that receive the composed frame scaled to their own resolution, e.g.
+/// a low resolution proxy rendered from the same scene as the full resolution
+/// output. Registered together with outputs, so they are not affected by scene
+/// updates.
+#[derive(Default)]
+pub(crate) struct OutputScalers(HashMap<OutputId, OutputScaler>);
+
+impl OutputScalers {
+    pub fn register(&mut self, output_id: OutputId, resolution: Resolution) {
+        self.0.insert(
+            output_id,
+            OutputScaler {
+                resolution,
+                target: NodeTexture::new(),
+            },
+        );
+    }
+
+    pub fn unregister(&mut self, output_id: &OutputId) {
+        self.0.remove(output_id);
+    }
+
+    /// Resolution of frames sent to the output, `None` if the output uses the
+    /// resolution of its node.
+    pub fn resolution(&self, output_id: &OutputId) -> Option<Resolution> {
+        self.0.get(output_id).map(|scaler| scaler.resolution)
+    }
+
+    /// Last scaled frame of the output.
+    pub fn frame(&self, output_id: &OutputId) -> Option<&NodeTextureState> {
+        self.0.get(output_id)?.target.state()
+    }
+
+    /// Returns `base` scaled to the resolution of the output, or `None` if the
+    /// output is not scaled.
+    pub fn render(
+        &mut self,
+        ctx: &RenderCtx,
+        output_id: &OutputId,
+        base: &RGBATexture,
+    ) -> Option<&NodeTextureState> {
+        let scaler = self.0.get_mut(output_id)?;
+        let target = scaler.target.ensure_size(ctx.wgpu_ctx, scaler.resolution);
+        ctx.wgpu_ctx
+            .utils
+            .render_layers(ctx.wgpu_ctx, &[base], target.rgba_texture());
+        scaler.target.state()
+    }
+}
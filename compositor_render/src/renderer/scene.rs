@@ -1,18 +1,32 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
-use compositor_common::scene::{InputId, NodeId, OutputId, SceneSpec};
+use compositor_common::frame::FrameFormat;
+use compositor_common::scene::{InputId, NodeId, NodeParams, OutputId, SceneSpec};
 use log::error;
 
+use crate::transformations::shader::Shader;
 use crate::wgpu::texture::{InputTexture, OutputTexture};
 use crate::{error::UpdateSceneError, wgpu::WgpuErrorScope};
 
 use super::NodeRenderPass;
-use super::{node::Node, RenderCtx};
+use super::{
+    keying::OutputKeys,
+    node::{Node, RenderNode},
+    scaling::OutputScalers,
+    RenderCtx,
+};
 
 pub struct Scene {
     pub nodes: SceneNodesSet,
     pub outputs: HashMap<OutputId, (NodeId, OutputTexture)>,
     pub inputs: HashMap<InputId, InputTexture>,
+    /// The longest fallback timeout of all nodes that use the input. Frames are
+    /// kept until then, nodes with shorter timeouts treat them as missing earlier.
+    pub input_fallback_timeouts: HashMap<InputId, Duration>,
 }
 
 impl Scene {
@@ -21,10 +35,18 @@ impl Scene {
             nodes: SceneNodesSet::new(),
             outputs: HashMap::new(),
             inputs: HashMap::new(),
+            input_fallback_timeouts: HashMap::new(),
         }
     }
 
-    pub fn update(&mut self, ctx: &RenderCtx, spec: &SceneSpec) -> Result<(), UpdateSceneError> {
+    pub fn update(
+        &mut self,
+        ctx: &RenderCtx,
+        spec: &SceneSpec,
+        output_formats: &HashMap<OutputId, FrameFormat>,
+        output_scalers: &OutputScalers,
+        output_keys: &OutputKeys,
+    ) -> Result<(), UpdateSceneError> {
         // TODO: If we want nodes to be stateful we could try reusing nodes instead
         //       of recreating them on every scene update
         let scope = WgpuErrorScope::push(&ctx.wgpu_ctx.device);
@@ -35,6 +57,9 @@ impl Scene {
             .outputs
             .iter()
             .map(|output| {
+                if output_keys.is_key(&output.output_id) {
+                    return Err(UpdateSceneError::KeyOutputInScene(output.output_id.clone()));
+                }
                 Self::ensure_node(ctx, &output.input_pad, spec, &mut inputs, &mut new_nodes)?;
                 let node = new_nodes
                     .get(&output.input_pad)
@@ -42,17 +67,28 @@ impl Scene {
                 let resolution = node.renderer.resolution().ok_or_else(|| {
                     UpdateSceneError::UnknownResolutionOnOutput(node.node_id.clone())
                 })?;
-                let output_texture = OutputTexture::new(ctx.wgpu_ctx, resolution);
+                let format = output_formats
+                    .get(&output.output_id)
+                    .copied()
+                    .unwrap_or_default();
+                let resolution = output_scalers
+                    .resolution(&output.output_id)
+                    .unwrap_or(resolution);
+                let output_texture = OutputTexture::new(ctx.wgpu_ctx, resolution, format);
                 Ok((
                     output.output_id.clone(),
                     (node.node_id.clone(), output_texture),
                 ))
             })
             .collect::<Result<_, UpdateSceneError>>()?;
+        Self::enable_input_mipmaps(spec, &mut new_nodes);
+        let input_fallback_timeouts =
+            Self::input_fallback_timeouts(spec, &inputs, ctx.stream_fallback_timeout);
 
         scope.pop(&ctx.wgpu_ctx.device)?;
 
         self.inputs = inputs;
+        self.input_fallback_timeouts = input_fallback_timeouts;
         self.outputs = outputs;
         self.nodes = SceneNodesSet { nodes: new_nodes };
 
@@ -95,6 +131,51 @@ impl Scene {
         inputs.insert(node_id.clone().into(), InputTexture::new());
         Ok(())
     }
+
+    /// Mipmaps are only generated for input streams that are used directly
+    /// by a transformation that requested them.
+    fn enable_input_mipmaps(spec: &SceneSpec, nodes: &mut HashMap<NodeId, Node>) {
+        let nodes_with_mipmaps = spec.nodes.iter().filter(|node| match &node.params {
+            NodeParams::Builtin(builtin) => builtin.uses_mipmaps(),
+            NodeParams::Transition(transition) => {
+                transition.start.uses_mipmaps() || transition.end.uses_mipmaps()
+            }
+            _ => false,
+        });
+        for node_spec in nodes_with_mipmaps {
+            for input_id in &node_spec.input_pads {
+                if let Some(node) = nodes.get_mut(input_id) {
+                    if let RenderNode::InputStream = node.renderer {
+                        node.output.set_mipmaps(true);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Inputs used only by outputs or nodes without an override use `default_timeout`.
+    fn input_fallback_timeouts(
+        spec: &SceneSpec,
+        inputs: &HashMap<InputId, InputTexture>,
+        default_timeout: Duration,
+    ) -> HashMap<InputId, Duration> {
+        let mut timeouts: HashMap<InputId, Duration> = inputs
+            .keys()
+            .map(|input_id| (input_id.clone(), default_timeout))
+            .collect();
+        let overrides = spec.nodes.iter().filter_map(|node| {
+            node.input_fallback_timeout
+                .map(|timeout| (&node.input_pads, timeout))
+        });
+        for (input_pads, timeout) in overrides {
+            for input_pad in input_pads {
+                if let Some(max_timeout) = timeouts.get_mut(&input_pad.clone().into()) {
+                    *max_timeout = Duration::max(*max_timeout, timeout);
+                }
+            }
+        }
+        timeouts
+    }
 }
 
 #[derive(Default)]
@@ -121,6 +202,14 @@ impl SceneNodesSet {
             .ok_or_else(|| InternalSceneError::MissingNode(node_id.clone()))
     }
 
+    pub(crate) fn replace_shader(&mut self, old: &Arc<Shader>, new: &Arc<Shader>) {
+        for node in self.nodes.values_mut() {
+            if let RenderNode::Shader(shader_node) = &mut node.renderer {
+                shader_node.replace_shader(old, new);
+            }
+        }
+    }
+
     pub fn node_or_fallback<'a>(
         &'a self,
         node_id: &NodeId,
@@ -134,16 +223,29 @@ impl SceneNodesSet {
         &'a mut self,
         node_id: &NodeId,
     ) -> Result<NodeRenderPass<'a>, InternalSceneError> {
-        let input_ids: Vec<NodeId> = self.node(node_id)?.inputs.to_vec();
+        let mut passes = self.node_render_passes(std::slice::from_ref(node_id))?;
+        Ok(passes.remove(0))
+    }
 
+    /// Borrow all nodes that are needed to render nodes node_ids. Nodes can't be
+    /// inputs of each other.
+    pub(crate) fn node_render_passes<'a>(
+        &'a mut self,
+        node_ids: &[NodeId],
+    ) -> Result<Vec<NodeRenderPass<'a>>, InternalSceneError> {
         // Borrow all the references, Fallback technically can be applied on every
         // level, so the easiest approach is to just borrow everything
         let mut nodes_mut: HashMap<&NodeId, &mut Node> = self.nodes.iter_mut().collect();
 
-        // Extract mutable borrow for the node we will render.
-        let node = nodes_mut
-            .remove(&node_id)
-            .ok_or_else(|| InternalSceneError::MissingNode(node_id.clone()))?;
+        // Extract mutable borrows for the nodes we will render.
+        let rendered_nodes = node_ids
+            .iter()
+            .map(|node_id| {
+                nodes_mut
+                    .remove(node_id)
+                    .ok_or_else(|| InternalSceneError::MissingNode(node_id.clone()))
+            })
+            .collect::<Result<Vec<_>, InternalSceneError>>()?;
 
         // Convert mutable borrows on rest of the nodes into immutable.
         // One input might be used multiple times, so we might need to
@@ -153,17 +255,92 @@ impl SceneNodesSet {
             .map(|(id, node)| (id, &*node))
             .collect();
 
-        // Get immutable borrows for inputs. For each input if node texture
-        // is empty go through the fallback chain
-        let inputs: Vec<(NodeId, &Node)> = input_ids
+        rendered_nodes
             .into_iter()
-            .map(|input_id| {
-                let node = Self::find_fallback_node(&nodes, &input_id)?;
-                // input_id and node.node_id are different if fallback is triggered
-                Ok((input_id, node))
+            .map(|node| {
+                // Get immutable borrows for inputs. For each input if node texture
+                // is empty go through the fallback chain
+                let inputs: Vec<(NodeId, &Node)> = node
+                    .inputs
+                    .iter()
+                    .map(|input_id| {
+                        let node = Self::find_fallback_node(&nodes, input_id)?;
+                        // input_id and node.node_id are different if fallback is triggered
+                        Ok((input_id.clone(), node))
+                    })
+                    .collect::<Result<Vec<_>, InternalSceneError>>()?;
+                Ok(NodeRenderPass { node, inputs })
             })
-            .collect::<Result<Vec<_>, InternalSceneError>>()?;
-        Ok(NodeRenderPass { node, inputs })
+            .collect()
+    }
+
+    /// Calls `render` for node_id after its inputs and, if the node is empty
+    /// afterwards, for its fallback. Nodes from `already_rendered` are skipped, so
+    /// a node used by multiple nodes or outputs is rendered once per frame.
+    pub(crate) fn render_with_inputs<F>(
+        &mut self,
+        node_id: &NodeId,
+        already_rendered: &mut HashSet<NodeId>,
+        render: &mut F,
+    ) -> Result<(), InternalSceneError>
+    where
+        F: FnMut(&mut Self, &NodeId) -> Result<(), InternalSceneError>,
+    {
+        if already_rendered.contains(node_id) {
+            return Ok(());
+        }
+        let input_ids = self.node(node_id)?.inputs.to_vec();
+        for input_id in input_ids {
+            self.render_with_inputs(&input_id, already_rendered, render)?;
+        }
+
+        render(self, node_id)?;
+        // Node can be an input of multiple nodes or outputs, its texture is
+        // reused by all of them for the rest of the frame.
+        already_rendered.insert(node_id.clone());
+
+        let node = self.node(node_id)?;
+        match (node.output.is_empty(), node.fallback.clone()) {
+            (true, Some(fallback_id)) => {
+                self.render_with_inputs(&fallback_id, already_rendered, render)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Nodes needed to render node_ids grouped by their depth in the scene, nodes
+    /// of a wave only use nodes of the previous waves as inputs. Fallbacks are
+    /// not included, they are rendered only if the node they replace is empty.
+    pub(crate) fn render_waves(
+        &self,
+        node_ids: &[NodeId],
+    ) -> Result<Vec<Vec<NodeId>>, InternalSceneError> {
+        let mut depths = HashMap::new();
+        for node_id in node_ids {
+            self.node_depth(node_id, &mut depths)?;
+        }
+        let wave_count = depths.values().max().map_or(0, |depth| depth + 1);
+        let mut waves = vec![Vec::new(); wave_count];
+        for (node_id, depth) in depths {
+            waves[depth].push(node_id);
+        }
+        Ok(waves)
+    }
+
+    fn node_depth(
+        &self,
+        node_id: &NodeId,
+        depths: &mut HashMap<NodeId, usize>,
+    ) -> Result<usize, InternalSceneError> {
+        if let Some(depth) = depths.get(node_id) {
+            return Ok(*depth);
+        }
+        let mut depth = 0;
+        for input_id in &self.node(node_id)?.inputs {
+            depth = depth.max(self.node_depth(input_id, depths)? + 1);
+        }
+        depths.insert(node_id.clone(), depth);
+        Ok(depth)
     }
 
     fn find_fallback_node<'a>(
@@ -188,3 +365,101 @@ pub enum InternalSceneError {
     #[error("Missing node \"{0}\"")]
     MissingNode(NodeId),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_id(id: &str) -> NodeId {
+        NodeId(id.into())
+    }
+
+    /// Nodes without renderers, "c" uses "a", "d" uses "c" and "b", "f" uses "c".
+    /// Node "a" is empty and falls back to "e".
+    fn nodes() -> SceneNodesSet {
+        let node = |id: &str, inputs: &[&str], fallback: Option<&str>| {
+            let mut node = Node::new_input(&node_id(id));
+            node.inputs = inputs.iter().map(|id| node_id(id)).collect();
+            node.fallback = fallback.map(node_id);
+            (node_id(id), node)
+        };
+        SceneNodesSet {
+            nodes: HashMap::from([
+                node("a", &[], Some("e")),
+                node("b", &[], None),
+                node("c", &["a"], None),
+                node("d", &["c", "b"], None),
+                node("e", &[], None),
+                node("f", &["c"], None),
+            ]),
+        }
+    }
+
+    #[test]
+    fn render_waves_group_nodes_by_depth() {
+        let waves = nodes().render_waves(&[node_id("d"), node_id("c")]).unwrap();
+        let waves: Vec<Vec<_>> = waves
+            .into_iter()
+            .map(|mut wave| {
+                wave.sort_by(|a, b| a.0.cmp(&b.0));
+                wave
+            })
+            .collect();
+
+        assert_eq!(
+            waves,
+            vec![
+                vec![node_id("a"), node_id("b")],
+                vec![node_id("c")],
+                vec![node_id("d")],
+            ]
+        );
+    }
+
+    #[test]
+    fn node_render_passes_resolve_fallbacks() {
+        let mut nodes = nodes();
+        let passes = nodes
+            .node_render_passes(&[node_id("c"), node_id("b")])
+            .unwrap();
+
+        let inputs: Vec<Vec<_>> = passes
+            .iter()
+            .map(|pass| {
+                pass.inputs
+                    .iter()
+                    .map(|(input_id, node)| (input_id.clone(), node.node_id.clone()))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(inputs, vec![vec![(node_id("a"), node_id("e"))], vec![]]);
+        assert!(nodes.node_render_passes(&[node_id("x")]).is_err());
+    }
+
+    #[test]
+    fn render_with_inputs_renders_shared_nodes_once() {
+        let mut nodes = nodes();
+        let mut already_rendered = HashSet::new();
+        let mut rendered = Vec::new();
+        for id in ["d", "f"] {
+            nodes
+                .render_with_inputs(&node_id(id), &mut already_rendered, &mut |_, node_id| {
+                    rendered.push(node_id.clone());
+                    Ok(())
+                })
+                .unwrap();
+        }
+
+        assert_eq!(
+            rendered,
+            vec![
+                node_id("a"),
+                node_id("e"),
+                node_id("c"),
+                node_id("b"),
+                node_id("d"),
+                node_id("f"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use std::{collections::HashMap, time::Duration};
+
+use compositor_common::{
+    scene::{
+        slate::{SlateContent, SlateMode},
+        OutputId, OutputSlate, Resolution,
+    },
+    util::colors::RGBColor,
+};
+
+use crate::{
+    error::SetOutputSlateError,
+    renderer::{renderers::Renderers, RenderCtx},
+    transformations::image_renderer::ImageNode,
+    wgpu::{
+        texture::{NodeTexture, NodeTextureState},
+        utils::WatermarkRect,
+        WgpuCtx,
+    },
+};
+
+enum OutputFrames {
+    Color(RGBColor),
+    Image {
+        image: ImageNode,
+        image_texture: NodeTexture,
+        /// Image fitted to the output resolution.
+        target: NodeTexture,
+    },
+}
+
+struct ActiveSlate {
+    mode: SlateMode,
+    frames: OutputFrames,
+}
+
+/// Frame sent to the output, either the rendered scene or its slate.
+pub(super) enum OutputFrame<'a> {
+    Color(RGBColor),
+    Texture(&'a NodeTextureState),
+}
+
+/// Slates are set per output independently of the scene, so they survive
+/// scene updates.
+#[derive(Default)]
+pub(crate) struct OutputSlates(HashMap<OutputId, ActiveSlate>);
+
+impl OutputSlates {
+    pub fn set(
+        &mut self,
+        ctx: &WgpuCtx,
+        renderers: &Renderers,
+        output_id: OutputId,
+        slate: OutputSlate,
+    ) -> Result<(), SetOutputSlateError> {
+        let frames = match slate.content {
+            SlateContent::Color(color) => OutputFrames::Color(color),
+            SlateContent::Image(image_id) => {
+                let image = renderers
+                    .images
+                    .get(&image_id)
+                    .ok_or_else(|| SetOutputSlateError::ImageNotFound(image_id.clone()))?;
+                let image = ImageNode::new(ctx, image, None)
+                    .map_err(|err| SetOutputSlateError::SvgRasterization(err, image_id))?;
+                OutputFrames::Image {
+                    image,
+                    image_texture: NodeTexture::new(),
+                    target: NodeTexture::new(),
+                }
+            }
+        };
+        self.0.insert(
+            output_id,
+            ActiveSlate {
+                mode: slate.mode,
+                frames,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn unregister(&mut self, output_id: &OutputId) {
+        self.0.remove(output_id);
+    }
+
+    /// Returns the slate frame if it should replace the scene on the output.
+    /// `inputs_missing` is true if none of the inputs delivered a frame.
+    pub fn render(
+        &mut self,
+        ctx: &mut RenderCtx,
+        output_id: &OutputId,
+        inputs_missing: bool,
+        resolution: Resolution,
+        pts: Duration,
+    ) -> Option<OutputFrame<'_>> {
+        let slate = self.0.get_mut(output_id)?;
+        match slate.mode {
+            SlateMode::On => (),
+            SlateMode::WhenInputsMissing if inputs_missing => (),
+            SlateMode::WhenInputsMissing => return None,
+        }
+
+        let (image, image_texture, target) = match &mut slate.frames {
+            OutputFrames::Color(color) => return Some(OutputFrame::Color(*color)),
+            OutputFrames::Image {
+                image,
+                image_texture,
+                target,
+            } => (image, image_texture, target),
+        };
+        image.render(ctx, image_texture, pts);
+        let Some(image) = image_texture.state() else {
+            return Some(OutputFrame::Color(RGBColor::BLACK));
+        };
+
+        let rect = fit_rect(image.resolution(), resolution);
+        let target_state = target.ensure_size(ctx.wgpu_ctx, resolution);
+        ctx.wgpu_ctx.utils.render_watermark(
+            ctx.wgpu_ctx,
+            None,
+            image.rgba_texture(),
+            target_state.rgba_texture(),
+            rect,
+            1.0,
+        );
+        target.state().map(OutputFrame::Texture)
+    }
+}
+
+/// Largest rect with the aspect ratio of the image, centered in the output.
+fn fit_rect(image: Resolution, output: Resolution) -> WatermarkRect {
+    let scale = f32::min(
+        output.width as f32 / image.width as f32,
+        output.height as f32 / image.height as f32,
+    );
+    let width = image.width as f32 * scale;
+    let height = image.height as f32 * scale;
+    WatermarkRect {
+        x: (output.width as f32 - width) / 2.0,
+        y: (output.height as f32 - height) / 2.0,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,116 @@
+use bytes::Bytes;
+use compositor_common::{
+    renderer_spec::RendererId,
+    scene::{NodeId, OutputId, Resolution},
+};
+
+use crate::{
+    error::{FreezeFrameError, SnapshotError},
+    transformations::image_renderer::Image,
+    wgpu::texture::{NodeTextureState, RGBATexture},
+};
+
+use super::Renderer;
+
+#[derive(Debug, Clone)]
+pub enum SnapshotTarget {
+    Node(NodeId),
+    /// Frame sent to the output, including its watermark and scaling.
+    Output(OutputId),
+}
+
+/// Still frame in the RGBA8 format, rows are not padded.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub resolution: Resolution,
+    pub data: Bytes,
+}
+
+impl Renderer {
+    /// Downloads the last frame rendered by the node or the output. Nothing is
+    /// rendered here, so the snapshot is not newer than the last rendered frame.
+    /// If `resolution` is specified, the frame is scaled on the GPU before download.
+    pub(crate) fn snapshot(
+        &self,
+        target: &SnapshotTarget,
+        resolution: Option<Resolution>,
+    ) -> Result<Snapshot, SnapshotError> {
+        let texture = self.snapshot_texture(target)?;
+        match resolution {
+            Some(resolution) if resolution != texture.resolution() => {
+                let scaled = RGBATexture::new(&self.wgpu_ctx, resolution);
+                self.wgpu_ctx.utils.render_layers(
+                    &self.wgpu_ctx,
+                    &[texture.rgba_texture()],
+                    &scaled,
+                );
+                Ok(Snapshot {
+                    resolution,
+                    data: scaled.download(&self.wgpu_ctx)?,
+                })
+            }
+            _ => Ok(Snapshot {
+                resolution: texture.resolution(),
+                data: texture.rgba_texture().download(&self.wgpu_ctx)?,
+            }),
+        }
+    }
+
+    /// Registers the last frame rendered by the node or the output as a static
+    /// image. Frame is copied on the GPU, so the image does not change when the
+    /// node is removed or its source disconnects.
+    pub(crate) fn freeze_frame(
+        &mut self,
+        image_id: RendererId,
+        target: &SnapshotTarget,
+    ) -> Result<(), FreezeFrameError> {
+        let texture = self
+            .snapshot_texture(target)
+            .map_err(|err| FreezeFrameError::Snapshot(err, image_id.clone()))?;
+        let still = RGBATexture::new(&self.wgpu_ctx, texture.resolution());
+        self.wgpu_ctx
+            .utils
+            .render_layers(&self.wgpu_ctx, &[texture.rgba_texture()], &still);
+        self.renderers
+            .images
+            .register(image_id, Image::from_texture(still))?;
+        Ok(())
+    }
+
+    fn snapshot_texture(
+        &self,
+        target: &SnapshotTarget,
+    ) -> Result<&NodeTextureState, SnapshotError> {
+        let texture = match target {
+            SnapshotTarget::Node(node_id) => self
+                .scene
+                .nodes
+                .node_or_fallback(node_id)
+                .map_err(|_| SnapshotError::NodeNotFound(node_id.clone()))?
+                .output
+                .state(),
+            SnapshotTarget::Output(output_id) => {
+                let (node_id, _) = self
+                    .scene
+                    .outputs
+                    .get(output_id)
+                    .ok_or_else(|| SnapshotError::OutputNotFound(output_id.clone()))?;
+                let frame = self
+                    .output_watermarks
+                    .frame(output_id)
+                    .or_else(|| self.output_scalers.frame(output_id));
+                match frame {
+                    Some(frame) => Some(frame),
+                    None => self
+                        .scene
+                        .nodes
+                        .node_or_fallback(node_id)
+                        .map_err(|_| SnapshotError::OutputNotFound(output_id.clone()))?
+                        .output
+                        .state(),
+                }
+            }
+        };
+        texture.ok_or(SnapshotError::NoFrame)
+    }
+}
@@ -0,0 +1,66 @@
+use std::{collections::HashMap, time::Duration};
+
+use compositor_common::{
+    scene::{InputId, TestPatternSpec},
+    Framerate,
+};
+
+use crate::{
+    renderer::RenderCtx,
+    wgpu::{texture::NodeTexture, utils::PatternTimecode},
+};
+
+/// Inputs that are generated by the renderer instead of being received from
+/// the queue.
+#[derive(Default)]
+pub(crate) struct InputTestPatterns(HashMap<InputId, TestPatternSpec>);
+
+impl InputTestPatterns {
+    pub fn register(&mut self, input_id: InputId, spec: TestPatternSpec) {
+        self.0.insert(input_id, spec);
+    }
+
+    pub fn unregister(&mut self, input_id: &InputId) {
+        self.0.remove(input_id);
+    }
+
+    /// Renders the frame of the test pattern to its node texture. Returns false
+    /// if the input is not a test pattern.
+    pub fn render(
+        &self,
+        ctx: &mut RenderCtx,
+        input_id: &InputId,
+        target: &mut NodeTexture,
+        pts: Duration,
+    ) -> bool {
+        let Some(spec) = self.0.get(input_id) else {
+            return false;
+        };
+        let timecode = spec.timecode.then(|| pattern_timecode(spec.framerate, pts));
+        let state = target.ensure_size(ctx.wgpu_ctx, spec.resolution);
+        ctx.wgpu_ctx.utils.render_test_pattern(
+            ctx.wgpu_ctx,
+            state.rgba_texture(),
+            spec.pattern,
+            timecode,
+        );
+        target.generate_mipmaps(ctx.wgpu_ctx);
+        target.set_frame_pts(pts);
+        true
+    }
+}
+
+/// Non-drop-frame timecode, fractional framerates are counted with the
+/// nominal (rounded up) number of frames per second.
+fn pattern_timecode(framerate: Framerate, pts: Duration) -> PatternTimecode {
+    let frame_index =
+        pts.as_nanos() * framerate.num as u128 / framerate.den as u128 / 1_000_000_000;
+    let frames_per_second = ((framerate.num + framerate.den - 1) / framerate.den) as u128;
+    let seconds = frame_index / frames_per_second;
+    PatternTimecode {
+        hours: ((seconds / 3600) % 100) as u32,
+        minutes: ((seconds / 60) % 60) as u32,
+        seconds: (seconds % 60) as u32,
+        frames: (frame_index % frames_per_second) as u32,
+    }
+}
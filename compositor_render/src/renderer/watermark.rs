@@ -0,0 +1,113 @@
+use std::{collections::HashMap, time::Duration};
+
+use compositor_common::scene::{watermark::WatermarkAnchor, OutputId, Resolution, Watermark};
+
+use crate::{
+    error::RegisterWatermarkError,
+    renderer::{renderers::Renderers, RenderCtx},
+    transformations::image_renderer::ImageNode,
+    wgpu::{
+        texture::{NodeTexture, NodeTextureState, RGBATexture},
+        utils::WatermarkRect,
+        WgpuCtx,
+    },
+};
+
+struct OutputWatermark {
+    spec: Watermark,
+    image: ImageNode,
+    image_texture: NodeTexture,
+    /// Output frame with the watermark, converted to YUV instead of the node texture.
+    target: NodeTexture,
+}
+
+/// Watermarks are registered together with outputs, so they are not affected
+/// by scene updates.
+#[derive(Default)]
+pub(crate) struct OutputWatermarks(HashMap<OutputId, OutputWatermark>);
+
+impl OutputWatermarks {
+    pub fn register(
+        &mut self,
+        ctx: &WgpuCtx,
+        renderers: &Renderers,
+        output_id: OutputId,
+        spec: Watermark,
+    ) -> Result<(), RegisterWatermarkError> {
+        let image = renderers
+            .images
+            .get(&spec.image_id)
+            .ok_or_else(|| RegisterWatermarkError::ImageNotFound(spec.image_id.clone()))?;
+        let image = ImageNode::new(ctx, image, None)
+            .map_err(|err| RegisterWatermarkError::SvgRasterization(err, spec.image_id.clone()))?;
+        self.0.insert(
+            output_id,
+            OutputWatermark {
+                spec,
+                image,
+                image_texture: NodeTexture::new(),
+                target: NodeTexture::new(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn unregister(&mut self, output_id: &OutputId) {
+        self.0.remove(output_id);
+    }
+
+    /// Last frame of the output with the watermark.
+    pub fn frame(&self, output_id: &OutputId) -> Option<&NodeTextureState> {
+        self.0.get(output_id)?.target.state()
+    }
+
+    /// Returns the frame of the output with the watermark drawn over `base`, or
+    /// `None` if the output has no watermark.
+    pub fn render(
+        &mut self,
+        ctx: &mut RenderCtx,
+        output_id: &OutputId,
+        base: Option<&RGBATexture>,
+        resolution: Resolution,
+        pts: Duration,
+    ) -> Option<&NodeTextureState> {
+        let watermark = self.0.get_mut(output_id)?;
+        watermark
+            .image
+            .render(ctx, &mut watermark.image_texture, pts);
+        let image = watermark.image_texture.state()?;
+
+        let rect = watermark_rect(&watermark.spec, image.resolution(), resolution);
+        let target = watermark.target.ensure_size(ctx.wgpu_ctx, resolution);
+        ctx.wgpu_ctx.utils.render_watermark(
+            ctx.wgpu_ctx,
+            base,
+            image.rgba_texture(),
+            target.rgba_texture(),
+            rect,
+            watermark.spec.opacity,
+        );
+        watermark.target.state()
+    }
+}
+
+fn watermark_rect(spec: &Watermark, image: Resolution, output: Resolution) -> WatermarkRect {
+    let width = image.width as f32;
+    let height = image.height as f32;
+    let (offset_x, offset_y) = (spec.offset_x as f32, spec.offset_y as f32);
+    let (x, y) = match spec.anchor {
+        WatermarkAnchor::TopLeft => (offset_x, offset_y),
+        WatermarkAnchor::TopRight => (output.width as f32 - width - offset_x, offset_y),
+        WatermarkAnchor::BottomLeft => (offset_x, output.height as f32 - height - offset_y),
+        WatermarkAnchor::BottomRight => (
+            output.width as f32 - width - offset_x,
+            output.height as f32 - height - offset_y,
+        ),
+    };
+    WatermarkRect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
@@ -1,21 +1,36 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
 
 use compositor_common::{
-    renderer_spec::{RendererId, RendererSpec},
-    scene::{InputId, OutputId, SceneSpec},
+    frame::FrameFormat,
+    renderer_spec::{RendererId, RendererSpec, ShaderSpec},
+    scene::{
+        InputFallback, InputId, OutputId, OutputSlate, Resolution, SceneSpec, TestPatternSpec,
+        Watermark,
+    },
 };
 
 use crate::{
     error::{
-        InitRendererEngineError, RegisterRendererError, RenderSceneError, UnregisterRendererError,
-        UpdateSceneError,
+        FreezeFrameError, InitRendererEngineError, PreloadImageError, RegisterRendererError,
+        RegisterWatermarkError, RenderSceneError, RendererInfoError, ReplayBufferError,
+        SetOutputSlateError, SnapshotError, UnregisterRendererError, UpdateSceneError,
+        UpdateShaderError, WebRendererEventError,
     },
     event_loop::EventLoop,
     registry::RegistryType,
-    renderer::{Renderer, RendererOptions},
-    transformations::{image_renderer::Image, shader::Shader, web_renderer::WebRenderer},
+    renderer::{renderers::RendererInfo, Renderer, RendererOptions, Snapshot, SnapshotTarget},
+    transformations::{
+        image_renderer::{Image, ImageResidency},
+        mesh::Mesh,
+        shader::Shader,
+        web_renderer::WebRenderer,
+    },
     validation::SceneSpecExt,
-    FrameSet,
+    wgpu::texture::TexturePoolUsage,
+    FrameSet, GpuCapabilities, WebRendererEvent,
 };
 
 #[derive(Clone)]
@@ -30,8 +45,8 @@ impl SyncRenderer {
     }
 
     pub fn register_renderer(&self, spec: RendererSpec) -> Result<(), RegisterRendererError> {
-        let ctx = self.0.lock().unwrap().register_ctx();
-        let mut guard = self.0.lock().unwrap();
+        let ctx = self.lock().register_ctx();
+        let mut guard = self.lock();
         match spec {
             RendererSpec::Shader(spec) => {
                 let shader_id = spec.shader_id.clone();
@@ -46,7 +61,9 @@ impl SyncRenderer {
             }
             RendererSpec::WebRenderer(params) => {
                 let instance_id = params.instance_id.clone();
-                let web = Arc::new(WebRenderer::new(&ctx, params));
+                let web = WebRenderer::new(&ctx, params)
+                    .map_err(|err| RegisterRendererError::WebRenderer(err, instance_id.clone()))?;
+                let web = Arc::new(web);
 
                 Ok(guard.renderers.web_renderers.register(instance_id, web)?)
             }
@@ -57,15 +74,72 @@ impl SyncRenderer {
 
                 Ok(guard.renderers.images.register(image_id, asset)?)
             }
+            RendererSpec::Mesh(spec) => {
+                let mesh_id = spec.mesh_id.clone();
+                let mesh = Mesh::new(&ctx.wgpu_ctx, spec)
+                    .map_err(|err| RegisterRendererError::Mesh(err, mesh_id.clone()))?;
+
+                Ok(guard.renderers.meshes.register(mesh_id, mesh)?)
+            }
         }
     }
 
+    /// Recompile a registered shader and swap it in place. Nodes using the shader keep
+    /// their state and start rendering with the new version on the next frame.
+    pub fn update_shader(&self, spec: ShaderSpec) -> Result<(), UpdateShaderError> {
+        let ctx = self.lock().register_ctx();
+        let shader_id = spec.shader_id.clone();
+        let shader = Shader::new(&ctx.wgpu_ctx, spec)
+            .map_err(|err| UpdateShaderError::Shader(err, shader_id.clone()))?;
+
+        self.lock().update_shader(&shader_id, Arc::new(shader))
+    }
+
+    /// Uploads the image to the GPU ahead of its first use and keeps it there until
+    /// it is unregistered. `None` stands for the default resolution of the image.
+    pub fn preload_image(
+        &self,
+        image_id: &RendererId,
+        resolutions: &[Option<Resolution>],
+    ) -> Result<(), PreloadImageError> {
+        let (ctx, image) = {
+            let guard = self.lock();
+            let image = guard
+                .renderers
+                .images
+                .get(image_id)
+                .ok_or_else(|| PreloadImageError::NotFound(image_id.clone()))?;
+            (guard.register_ctx(), image)
+        };
+        image
+            .preload(&ctx.wgpu_ctx, resolutions)
+            .map_err(|err| PreloadImageError::SvgRasterization(err, image_id.clone()))
+    }
+
+    pub fn images_residency(&self) -> Vec<(RendererId, ImageResidency)> {
+        let guard = self.lock();
+        guard
+            .renderers
+            .images
+            .iter()
+            .map(|(id, image)| (id.clone(), image.residency()))
+            .collect()
+    }
+
+    pub fn renderer_info(
+        &self,
+        renderer_id: &RendererId,
+        registry_type: RegistryType,
+    ) -> Result<RendererInfo, RendererInfoError> {
+        self.lock().renderers.info(renderer_id, registry_type)
+    }
+
     pub fn unregister_renderer(
         &self,
         renderer_id: &RendererId,
         registry_type: RegistryType,
     ) -> Result<(), UnregisterRendererError> {
-        let mut guard = self.0.lock().unwrap();
+        let mut guard = self.lock();
         guard
             .scene_spec
             .validate_can_unregister(renderer_id, registry_type)?;
@@ -73,19 +147,213 @@ impl SyncRenderer {
             RegistryType::Shader => guard.renderers.shaders.unregister(renderer_id)?,
             RegistryType::WebRenderer => guard.renderers.web_renderers.unregister(renderer_id)?,
             RegistryType::Image => guard.renderers.images.unregister(renderer_id)?,
+            RegistryType::Mesh => guard.renderers.meshes.unregister(renderer_id)?,
         }
         Ok(())
     }
 
+    pub fn dispatch_web_renderer_event(
+        &self,
+        instance_id: &RendererId,
+        event: WebRendererEvent,
+    ) -> Result<(), WebRendererEventError> {
+        self.web_renderer(instance_id)?.dispatch_event(event);
+        Ok(())
+    }
+
+    pub fn execute_web_renderer_javascript(
+        &self,
+        instance_id: &RendererId,
+        code: String,
+    ) -> Result<(), WebRendererEventError> {
+        self.web_renderer(instance_id)?.execute_javascript(code);
+        Ok(())
+    }
+
+    /// Messages posted by the page with `post_message` since the last call.
+    pub fn take_web_renderer_messages(
+        &self,
+        instance_id: &RendererId,
+    ) -> Result<Vec<String>, WebRendererEventError> {
+        Ok(self.web_renderer(instance_id)?.take_messages())
+    }
+
+    fn web_renderer(
+        &self,
+        instance_id: &RendererId,
+    ) -> Result<Arc<WebRenderer>, WebRendererEventError> {
+        self.lock()
+            .renderers
+            .web_renderers
+            .get(instance_id)
+            .ok_or_else(|| WebRendererEventError::NotFound(instance_id.clone()))
+    }
+
+    pub fn snapshot(
+        &self,
+        target: &SnapshotTarget,
+        resolution: Option<Resolution>,
+    ) -> Result<Snapshot, SnapshotError> {
+        self.lock().snapshot(target, resolution)
+    }
+
+    pub fn freeze_frame(
+        &self,
+        image_id: RendererId,
+        target: &SnapshotTarget,
+    ) -> Result<(), FreezeFrameError> {
+        self.lock().freeze_frame(image_id, target)
+    }
+
     pub fn render(&self, input: FrameSet<InputId>) -> Result<FrameSet<OutputId>, RenderSceneError> {
-        self.0.lock().unwrap().render(input)
+        self.lock().render(input)
     }
 
     pub fn update_scene(&mut self, scene_specs: Arc<SceneSpec>) -> Result<(), UpdateSceneError> {
-        self.0.lock().unwrap().update_scene(scene_specs)
+        self.lock().update_scene(scene_specs)
+    }
+
+    pub fn validate_scene(&self, scene_spec: &SceneSpec) -> Result<(), UpdateSceneError> {
+        self.lock().validate_scene(scene_spec)
+    }
+
+    /// Set pixel format and color space of frames rendered for the output. If
+    /// `scaled_resolution` is set, the frame composed for the output is scaled
+    /// to that resolution on the GPU.
+    pub fn register_output(
+        &self,
+        output_id: OutputId,
+        format: FrameFormat,
+        scaled_resolution: Option<Resolution>,
+    ) {
+        let mut guard = self.lock();
+        if let Some(resolution) = scaled_resolution {
+            guard.output_scalers.register(output_id.clone(), resolution);
+        }
+        guard.register_output_format(output_id, format)
+    }
+
+    /// Output receives the alpha matte (key) of frames sent to `fill_output_id`
+    /// instead of a frame of the scene. The key has the resolution of the fill.
+    pub fn register_output_key(
+        &self,
+        output_id: OutputId,
+        fill_output_id: OutputId,
+        format: FrameFormat,
+    ) {
+        let mut guard = self.lock();
+        guard
+            .output_keys
+            .register(output_id.clone(), fill_output_id, format);
+        guard.register_output_format(output_id, format)
+    }
+
+    pub fn is_key_output(&self, output_id: &OutputId) -> bool {
+        self.lock().output_keys.is_key(output_id)
+    }
+
+    /// Key outputs registered with `fill_output_id` as their fill.
+    pub fn key_outputs_of(&self, fill_output_id: &OutputId) -> Vec<OutputId> {
+        self.lock()
+            .output_keys
+            .keys_of(fill_output_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Content shown in place of the input when its frames are missing.
+    pub fn register_input_fallback(&self, input_id: InputId, fallback: InputFallback) {
+        self.lock().input_fallbacks.register(input_id, fallback)
+    }
+
+    pub fn unregister_input_fallback(&self, input_id: &InputId) {
+        self.lock().input_fallbacks.unregister(input_id)
+    }
+
+    /// Input with frames generated by the renderer, it doesn't need frames from
+    /// the queue.
+    pub fn register_test_pattern(&self, input_id: InputId, spec: TestPatternSpec) {
+        self.lock().input_test_patterns.register(input_id, spec)
+    }
+
+    pub fn unregister_test_pattern(&self, input_id: &InputId) {
+        self.lock().input_test_patterns.unregister(input_id)
+    }
+
+    /// Keeps frames of the input from the last `duration` for replay nodes.
+    pub fn register_replay_buffer(&self, input_id: InputId, duration: Duration) {
+        self.lock().replay_buffers.register(input_id, duration)
+    }
+
+    pub fn unregister_replay_buffer(&self, input_id: &InputId) {
+        self.lock().replay_buffers.unregister(input_id)
+    }
+
+    pub fn start_replay(
+        &self,
+        input_id: &InputId,
+        offset: Duration,
+        duration: Option<Duration>,
+        speed: f64,
+    ) -> Result<(), ReplayBufferError> {
+        self.lock()
+            .replay_buffers
+            .start(input_id, offset, duration, speed)
+    }
+
+    pub fn stop_replay(&self, input_id: &InputId) -> Result<(), ReplayBufferError> {
+        self.lock().replay_buffers.stop(input_id)
+    }
+
+    /// Image drawn over every frame of the output, regardless of the scene.
+    pub fn register_output_watermark(
+        &self,
+        output_id: OutputId,
+        watermark: Watermark,
+    ) -> Result<(), RegisterWatermarkError> {
+        self.lock().register_output_watermark(output_id, watermark)
+    }
+
+    /// Scene rendered for the output is replaced with the slate, according to
+    /// its mode.
+    pub fn set_output_slate(
+        &self,
+        output_id: OutputId,
+        slate: OutputSlate,
+    ) -> Result<(), SetOutputSlateError> {
+        self.lock().set_output_slate(output_id, slate)
+    }
+
+    pub fn remove_output_slate(&self, output_id: &OutputId) {
+        self.lock().output_slates.unregister(output_id)
+    }
+
+    pub fn unregister_output(&self, output_id: &OutputId) {
+        let mut guard = self.lock();
+        guard.unregister_output_format(output_id);
+        guard.output_watermarks.unregister(output_id);
+        guard.output_scalers.unregister(output_id);
+        guard.output_keys.unregister(output_id);
+        guard.output_slates.unregister(output_id);
     }
 
     pub fn scene_spec(&self) -> Arc<SceneSpec> {
-        self.0.lock().unwrap().scene_spec.clone()
+        self.lock().scene_spec.clone()
+    }
+
+    /// Size of node textures allocated by the renderer, including textures kept
+    /// in the pool for reuse by future scenes.
+    pub fn node_textures_usage(&self) -> TexturePoolUsage {
+        self.lock().wgpu_ctx.texture_pool.usage()
+    }
+
+    pub fn capabilities(&self) -> GpuCapabilities {
+        self.lock().wgpu_ctx.capabilities.clone()
+    }
+
+    /// Lock is poisoned if rendering panicked. Renderer is used anyway, so the
+    /// pipeline can recover if the panic was caused by a single frame.
+    fn lock(&self) -> MutexGuard<Renderer> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
     }
 }
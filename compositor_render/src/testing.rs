@@ -0,0 +1,487 @@
+//! Renders scenes without a pipeline and compares the outputs with golden PNG
+//! images, so custom shaders and layouts can be covered by regression tests.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use compositor_common::{
+    frame::{ColorSpace, FrameData, PixelFormat, YuvData},
+    renderer_spec::RendererSpec,
+    scene::{InputId, OutputId, Resolution, SceneSpec, TestPatternSpec},
+    Frame, Framerate,
+};
+
+use crate::{
+    error::SnapshotTestError, renderer::RendererOptions, FrameSet, GpuOptions, Renderer,
+    WebRendererOptions,
+};
+
+/// Renderer of every test starts from the same state, so results depend only
+/// on the test case.
+const FRAMERATE: Framerate = Framerate { num: 30, den: 1 };
+const SHADER_RANDOM_SEED: u32 = 0;
+
+pub struct SnapshotTest {
+    /// Prefix of golden files, can contain `/` to group them in directories.
+    pub name: String,
+    /// Web renderers are not supported.
+    pub renderers: Vec<RendererSpec>,
+    pub scene: Arc<SceneSpec>,
+    /// The same frames are rendered on every timestamp, their `pts` is ignored.
+    pub inputs: HashMap<InputId, Frame>,
+    /// Inputs generated by the renderer, they don't need frames in `inputs`.
+    pub test_patterns: HashMap<InputId, TestPatternSpec>,
+    /// Frames are rendered in this order, `frame_index` of shaders is the
+    /// position on this list.
+    pub timestamps: Vec<Duration>,
+    pub tolerance: Tolerance,
+}
+
+/// Allowed difference between rendered and golden images. GPUs and drivers
+/// may round colors differently, so exact comparison is not always portable.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tolerance {
+    /// Pixels where no channel differs by more than this are equal.
+    pub max_channel_diff: u8,
+    /// Fraction (0.0 - 1.0) of pixels that can differ by more than `max_channel_diff`.
+    pub max_mismatched_pixels: f64,
+}
+
+/// Output frame converted to RGBA.
+#[derive(Debug, Clone)]
+pub struct RenderedSnapshot {
+    pub output_id: OutputId,
+    pub pts: Duration,
+    pub resolution: Resolution,
+    pub data: Vec<u8>,
+}
+
+impl SnapshotTest {
+    /// Renders all outputs of the scene on every timestamp.
+    pub fn render(self) -> Result<Vec<RenderedSnapshot>, SnapshotTestError> {
+        let renderer = create_renderer(self.renderers, self.test_patterns, self.scene.clone())?;
+
+        let mut snapshots = Vec::new();
+        for pts in self.timestamps.iter().copied() {
+            let mut frame_set = FrameSet::new(pts);
+            for (input_id, frame) in self.inputs.iter() {
+                frame_set.frames.insert(
+                    input_id.clone(),
+                    Frame {
+                        pts,
+                        ..frame.clone()
+                    },
+                );
+            }
+
+            let outputs = renderer.render(frame_set)?;
+            for output in self.scene.outputs.iter() {
+                let frame = outputs.frames.get(&output.output_id).ok_or_else(|| {
+                    SnapshotTestError::OutputNotRendered(output.output_id.clone(), pts)
+                })?;
+                snapshots.push(RenderedSnapshot {
+                    output_id: output.output_id.clone(),
+                    pts,
+                    resolution: frame.resolution,
+                    data: frame_to_rgba(frame),
+                });
+            }
+        }
+        Ok(snapshots)
+    }
+
+    /// Compares rendered outputs with golden images from `snapshots_dir`.
+    pub fn check(self, snapshots_dir: impl AsRef<Path>) -> Result<(), SnapshotTestError> {
+        let (name, tolerance) = (self.name.clone(), self.tolerance);
+        for snapshot in self.render()? {
+            snapshot.check(snapshots_dir.as_ref(), &name, tolerance)?;
+        }
+        Ok(())
+    }
+
+    /// Renders the outputs and writes them to `snapshots_dir` as new golden images.
+    pub fn update(self, snapshots_dir: impl AsRef<Path>) -> Result<(), SnapshotTestError> {
+        let name = self.name.clone();
+        for snapshot in self.render()? {
+            snapshot.save(snapshots_dir.as_ref(), &name)?;
+        }
+        Ok(())
+    }
+}
+
+impl RenderedSnapshot {
+    /// Golden image of the snapshot: `<snapshots_dir>/<test_name>_<pts in ms>_<output_id>.png`
+    pub fn path(&self, snapshots_dir: &Path, test_name: &str) -> PathBuf {
+        snapshots_dir.join(format!(
+            "{}_{}_{}.png",
+            test_name,
+            self.pts.as_millis(),
+            self.output_id
+        ))
+    }
+
+    /// Compares the snapshot with its golden image from `snapshots_dir`.
+    pub fn check(
+        &self,
+        snapshots_dir: &Path,
+        test_name: &str,
+        tolerance: Tolerance,
+    ) -> Result<(), SnapshotTestError> {
+        let path = self.path(snapshots_dir, test_name);
+        if !path.exists() {
+            return Err(SnapshotTestError::GoldenNotFound(path));
+        }
+        let golden = image::open(&path)
+            .map_err(|err| SnapshotTestError::ImageError(err, path.clone()))?
+            .to_rgba8();
+
+        let golden_resolution = Resolution {
+            width: golden.width() as usize,
+            height: golden.height() as usize,
+        };
+        if golden_resolution != self.resolution {
+            return Err(SnapshotTestError::ResolutionMismatch {
+                path,
+                expected: golden_resolution,
+                actual: self.resolution,
+            });
+        }
+
+        let mismatched_pixels = mismatched_pixels(&golden, &self.data, tolerance.max_channel_diff);
+        if !tolerance.accepts(mismatched_pixels, self.resolution) {
+            return Err(SnapshotTestError::Mismatch {
+                path,
+                mismatched_pixels,
+            });
+        }
+        Ok(())
+    }
+
+    /// Writes the snapshot to `snapshots_dir` as a new golden image.
+    pub fn save(&self, snapshots_dir: &Path, test_name: &str) -> Result<(), SnapshotTestError> {
+        let path = self.path(snapshots_dir, test_name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| SnapshotTestError::IoError(err, path.clone()))?;
+        }
+        image::save_buffer(
+            &path,
+            &self.data,
+            self.resolution.width as u32,
+            self.resolution.height as u32,
+            image::ColorType::Rgba8,
+        )
+        .map_err(|err| SnapshotTestError::ImageError(err, path.clone()))
+    }
+}
+
+fn create_renderer(
+    renderers: Vec<RendererSpec>,
+    test_patterns: HashMap<InputId, TestPatternSpec>,
+    scene: Arc<SceneSpec>,
+) -> Result<Renderer, SnapshotTestError> {
+    let (mut renderer, _event_loop) = Renderer::new(RendererOptions {
+        web_renderer: WebRendererOptions {
+            init: false,
+            disable_gpu: false,
+        },
+        gpu: GpuOptions::default(),
+        framerate: FRAMERATE,
+        stream_fallback_timeout: Duration::from_secs(3),
+        shader_random_seed: Some(SHADER_RANDOM_SEED),
+    })?;
+
+    for spec in renderers {
+        if let RendererSpec::WebRenderer(spec) = spec {
+            return Err(SnapshotTestError::WebRendererNotSupported(spec.instance_id));
+        }
+        renderer.register_renderer(spec)?;
+    }
+    for (input_id, spec) in test_patterns {
+        renderer.register_test_pattern(input_id, spec);
+    }
+    renderer.update_scene(scene)?;
+    Ok(renderer)
+}
+
+impl Tolerance {
+    fn accepts(&self, mismatched_pixels: usize, resolution: Resolution) -> bool {
+        let pixels = resolution.width * resolution.height;
+        mismatched_pixels as f64 <= self.max_mismatched_pixels * pixels as f64
+    }
+}
+
+/// Number of pixels of two RGBA images where any channel differs by more than
+/// `max_channel_diff`.
+pub fn mismatched_pixels(expected: &[u8], actual: &[u8], max_channel_diff: u8) -> usize {
+    let size_diff = expected.len().abs_diff(actual.len()) / 4;
+    let mismatched = expected
+        .chunks_exact(4)
+        .zip(actual.chunks_exact(4))
+        .filter(|(expected, actual)| {
+            expected
+                .iter()
+                .zip(actual.iter())
+                .any(|(expected, actual)| expected.abs_diff(*actual) > max_channel_diff)
+        })
+        .count();
+    mismatched + size_diff
+}
+
+/// Converts a YUV 4:2:0 frame to RGBA the same way input frames are converted
+/// by the renderer (`yuv_to_rgba.wgsl`), respecting the pixel format and the
+/// color space of the frame. Frames with RGBA data are returned as is.
+pub fn frame_to_rgba(frame: &Frame) -> Vec<u8> {
+    let (y_plane, u_plane, v_plane) = match &frame.data {
+        FrameData::PlanarYuv(YuvData {
+            y_plane,
+            u_plane,
+            v_plane,
+        }) => (y_plane.clone(), u_plane.clone(), v_plane.clone()),
+        FrameData::Nv12(data) => (
+            data.y_plane.clone(),
+            data.uv_plane.iter().step_by(2).copied().collect(),
+            data.uv_plane.iter().skip(1).step_by(2).copied().collect(),
+        ),
+        FrameData::Rgba(data) => return data.to_vec(),
+    };
+
+    let (y_plane, u_plane, v_plane, max_sample_value) = match frame.format.pixel_format {
+        PixelFormat::Yuv420p => (
+            samples_8bit(&y_plane),
+            samples_8bit(&u_plane),
+            samples_8bit(&v_plane),
+            255.0,
+        ),
+        PixelFormat::Yuv420p10le => (
+            samples_10bit(&y_plane),
+            samples_10bit(&u_plane),
+            samples_10bit(&v_plane),
+            1023.0,
+        ),
+    };
+
+    let mut rgba_data = Vec::with_capacity(y_plane.len() * 4);
+    for (i, y_plane) in y_plane.chunks(frame.resolution.width).enumerate() {
+        for (j, y) in y_plane.iter().enumerate() {
+            let u = u_plane[(i / 2) * (frame.resolution.width / 2) + (j / 2)];
+            let v = v_plane[(i / 2) * (frame.resolution.width / 2) + (j / 2)];
+            let rgb = match frame.format.color_space {
+                ColorSpace::Bt601 => bt601_to_rgb(*y, u, v, max_sample_value),
+                ColorSpace::Bt709 => {
+                    let [y, u, v] = from_limited_range(*y, u, v, max_sample_value);
+                    bt709_to_rgb(y, u, v)
+                }
+                ColorSpace::Bt2020Pq => {
+                    let [y, u, v] = from_limited_range(*y, u, v, max_sample_value);
+                    bt2020_pq_to_rgb(y, u, v)
+                }
+            };
+            rgba_data.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+        }
+    }
+
+    rgba_data
+}
+
+fn samples_8bit(plane: &[u8]) -> Vec<f32> {
+    plane.iter().map(|sample| *sample as f32).collect()
+}
+
+fn samples_10bit(plane: &[u8]) -> Vec<f32> {
+    plane
+        .chunks_exact(2)
+        .map(|sample| u16::from_le_bytes([sample[0], sample[1]]) as f32)
+        .collect()
+}
+
+/// Full range, samples are scaled to 8-bit values, so 8-bit frames are converted
+/// without rounding errors.
+fn bt601_to_rgb(y: f32, u: f32, v: f32, max_sample_value: f32) -> [u8; 3] {
+    let scale = 255.0 / max_sample_value;
+    let (y, u, v) = (y * scale, u * scale, v * scale);
+
+    let r = (y + 1.40200 * (v - 128.0)).clamp(0.0, 255.0);
+    let g = (y - 0.34414 * (u - 128.0) - 0.71414 * (v - 128.0)).clamp(0.0, 255.0);
+    let b = (y + 1.77200 * (u - 128.0)).clamp(0.0, 255.0);
+    [r as u8, g as u8, b as u8]
+}
+
+/// Returns Y' in 0.0-1.0 range and Cb, Cr in -0.5-0.5 range, e.g. 16-235 for Y
+/// and 16-240 for UV in 8-bit, 64-940 and 64-960 in 10-bit.
+fn from_limited_range(y: f32, u: f32, v: f32, max_sample_value: f32) -> [f32; 3] {
+    let step = (max_sample_value + 1.0) / 256.0;
+    [
+        (y - 16.0 * step) / (219.0 * step),
+        (u - 128.0 * step) / (224.0 * step),
+        (v - 128.0 * step) / (224.0 * step),
+    ]
+}
+
+fn bt709_to_rgb(y: f32, cb: f32, cr: f32) -> [u8; 3] {
+    unorm([
+        y + 1.5748 * cr,
+        y - 0.18732 * cb - 0.46812 * cr,
+        y + 1.8556 * cb,
+    ])
+}
+
+/// HDR frames are tone-mapped to SDR, SDR white is placed at 203 nits.
+fn bt2020_pq_to_rgb(y: f32, cb: f32, cr: f32) -> [u8; 3] {
+    let rgb_2020 = [
+        y + 1.4746 * cr,
+        y - 0.16455 * cb - 0.57135 * cr,
+        y + 1.8814 * cb,
+    ]
+    .map(|value| pq_eotf(value) * (10000.0 / 203.0));
+    let dot = |row: [f32; 3]| {
+        let value: f32 = row.iter().zip(rgb_2020).map(|(a, b)| a * b).sum();
+        value.max(0.0)
+    };
+    let linear_709 = [
+        dot([1.6605, -0.5876, -0.0728]),
+        dot([-0.1246, 1.1329, -0.0083]),
+        dot([-0.0182, -0.1006, 1.1187]),
+    ];
+    unorm(tone_map(linear_709).map(linear_to_srgb))
+}
+
+/// SMPTE ST 2084, returns linear light where 1.0 is 10000 nits.
+fn pq_eotf(value: f32) -> f32 {
+    let m1 = 0.1593017578125;
+    let m2 = 78.84375;
+    let c1 = 0.8359375;
+    let c2 = 18.8515625;
+    let c3 = 18.6875;
+
+    let p = value.clamp(0.0, 1.0).powf(1.0 / m2);
+    ((p - c1).max(0.0) / (c2 - c3 * p)).powf(1.0 / m1)
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    let value = value.clamp(0.0, 1.0);
+    match value <= 0.0031308 {
+        true => value * 12.92,
+        false => 1.055 * value.powf(1.0 / 2.4) - 0.055,
+    }
+}
+
+/// Compresses highlights above the knee, applied to the max channel to preserve
+/// hue.
+fn tone_map(linear: [f32; 3]) -> [f32; 3] {
+    let knee = 0.8;
+    let peak = linear[0].max(linear[1]).max(linear[2]);
+    if peak <= knee {
+        return linear;
+    }
+    let excess = peak - knee;
+    let mapped_peak = knee + (1.0 - knee) * excess / (excess + 1.0 - knee);
+    linear.map(|value| value * (mapped_peak / peak))
+}
+
+/// Same rounding as writes to a `Rgba8Unorm` texture.
+fn unorm(rgb: [f32; 3]) -> [u8; 3] {
+    rgb.map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use compositor_common::frame::FrameFormat;
+
+    use super::*;
+
+    #[test]
+    fn mismatched_pixels_respects_channel_diff() {
+        let expected = [10, 10, 10, 255, 0, 0, 0, 255];
+        let actual = [12, 10, 10, 255, 0, 0, 9, 255];
+
+        assert_eq!(mismatched_pixels(&expected, &actual, 0), 2);
+        assert_eq!(mismatched_pixels(&expected, &actual, 2), 1);
+        assert_eq!(mismatched_pixels(&expected, &actual, 9), 0);
+        assert_eq!(mismatched_pixels(&expected, &actual[..4], 2), 1);
+    }
+
+    #[test]
+    fn tolerance_accepts_fraction_of_pixels() {
+        let resolution = Resolution {
+            width: 10,
+            height: 10,
+        };
+        let tolerance = Tolerance {
+            max_channel_diff: 0,
+            max_mismatched_pixels: 0.05,
+        };
+
+        assert!(tolerance.accepts(5, resolution));
+        assert!(!tolerance.accepts(6, resolution));
+        assert!(Tolerance::default().accepts(0, resolution));
+        assert!(!Tolerance::default().accepts(1, resolution));
+    }
+
+    /// 2x2 frame with the same sample in every pixel.
+    fn frame(format: FrameFormat, [y, u, v]: [u16; 3]) -> Frame {
+        let plane = |sample: u16, samples: usize| -> bytes::Bytes {
+            match format.pixel_format {
+                PixelFormat::Yuv420p => vec![sample as u8; samples].into(),
+                PixelFormat::Yuv420p10le => sample.to_le_bytes().repeat(samples).into(),
+            }
+        };
+        Frame {
+            data: FrameData::PlanarYuv(YuvData {
+                y_plane: plane(y, 4),
+                u_plane: plane(u, 1),
+                v_plane: plane(v, 1),
+            }),
+            resolution: Resolution {
+                width: 2,
+                height: 2,
+            },
+            format,
+            pts: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn frame_to_rgba_respects_frame_format() {
+        let format = |pixel_format, color_space| FrameFormat {
+            pixel_format,
+            color_space,
+        };
+        let pixel = |frame: Frame| frame_to_rgba(&frame)[..4].to_vec();
+
+        let full_range = format(PixelFormat::Yuv420p, ColorSpace::Bt601);
+        assert_eq!(
+            pixel(frame(full_range, [255, 128, 128])),
+            [255, 255, 255, 255]
+        );
+        assert_eq!(pixel(frame(full_range, [76, 85, 255])), [254, 0, 0, 255]);
+
+        let limited_range = format(PixelFormat::Yuv420p, ColorSpace::Bt709);
+        assert_eq!(
+            pixel(frame(limited_range, [235, 128, 128])),
+            [255, 255, 255, 255]
+        );
+        assert_eq!(pixel(frame(limited_range, [16, 128, 128])), [0, 0, 0, 255]);
+
+        let limited_range_10bit = format(PixelFormat::Yuv420p10le, ColorSpace::Bt709);
+        assert_eq!(
+            pixel(frame(limited_range_10bit, [940, 512, 512])),
+            [255, 255, 255, 255]
+        );
+        assert_eq!(
+            pixel(frame(limited_range_10bit, [64, 512, 512])),
+            [0, 0, 0, 255]
+        );
+
+        // Gray below the tone-mapping knee, computed with the same formulas in f64.
+        let hdr = format(PixelFormat::Yuv420p10le, ColorSpace::Bt2020Pq);
+        let gray = pixel(frame(hdr, [400, 512, 512]));
+        assert!(gray[..3].iter().all(|channel| channel.abs_diff(102) <= 1));
+        assert_eq!(pixel(frame(hdr, [64, 512, 512])), [0, 0, 0, 255]);
+    }
+}
@@ -1,6 +1,10 @@
 pub mod builtin;
+pub mod captions;
 pub mod image_renderer;
+pub mod mesh;
+pub mod replay;
 pub mod shader;
 pub mod text_renderer;
+pub mod timecode;
 pub mod transition;
 pub mod web_renderer;
@@ -1,5 +1,7 @@
 use compositor_common::{
-    scene::builtin_transformations::{BuiltinSpec, FixedPositionLayoutSpec},
+    scene::builtin_transformations::{
+        BuiltinSpec, FixedPositionLayoutSpec, PerspectiveSpec, Transform2dSpec,
+    },
     util::InterpolationState,
 };
 
@@ -12,6 +14,7 @@ pub mod transformations;
 pub mod utils;
 
 pub use node::BuiltinNode;
+pub(crate) use node::BuiltinSpecExt;
 
 #[derive(Debug)]
 pub enum BuiltinState {
@@ -25,6 +28,8 @@ pub enum BuiltinState {
 #[derive(Debug)]
 pub enum BuiltinTransition {
     FixedPositionLayout(FixedPositionLayoutSpec, FixedPositionLayoutSpec),
+    Transform2d(Transform2dSpec, Transform2dSpec),
+    Perspective(PerspectiveSpec, PerspectiveSpec),
 }
 
 impl BuiltinTransition {
@@ -33,6 +38,8 @@ impl BuiltinTransition {
             BuiltinTransition::FixedPositionLayout(_, end) => {
                 BuiltinSpec::FixedPositionLayout(end.clone())
             }
+            BuiltinTransition::Transform2d(_, end) => BuiltinSpec::Transform2d(end.clone()),
+            BuiltinTransition::Perspective(_, end) => BuiltinSpec::Perspective(end.clone()),
         }
     }
 }
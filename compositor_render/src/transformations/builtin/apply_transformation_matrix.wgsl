@@ -14,11 +14,19 @@ struct CommonShaderParameters {
     time: f32,
     texture_count: u32,
     output_resolution: vec2<u32>,
+    frame_index: u32,
+    random_seed: u32,
+}
+
+struct InputShaderParameters {
+    resolution: vec2<u32>,
+    pts_offset: f32,
 }
 
 var<push_constant> common_params: CommonShaderParameters;
 
 @group(0) @binding(0) var textures: binding_array<texture_2d<f32>, 16>;
+@group(0) @binding(1) var<uniform> inputs_params: array<InputShaderParameters, 16>;
 @group(1) @binding(0) var<uniform> transformation_matrices: array<mat4x4<f32>, 16>;
 @group(2) @binding(0) var sampler_: sampler;
 
@@ -46,7 +54,8 @@ fn vs_main(input: VertexInput) -> VertexOutput {
 
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let sample = textureSample(textures[input.texture_id], sampler_, input.tex_coords);
+    // Defined by the scaling quality variant of the shader.
+    let sample = sample_input(input.texture_id, input.tex_coords);
     
     if common_params.texture_count == 0u {
         return vec4<f32>(0.0, 0.0, 0.0, 0.0);
@@ -0,0 +1,120 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) texture_id: i32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct CommonShaderParameters {
+    time: f32,
+    texture_count: u32,
+    output_resolution: vec2<u32>,
+    frame_index: u32,
+    random_seed: u32,
+}
+
+struct InputShaderParameters {
+    resolution: vec2<u32>,
+    pts_offset: f32,
+}
+
+struct BorderParams {
+    color: vec4<f32>,
+    shadow_color: vec4<f32>,
+    // all values in pixels
+    shadow_offset: vec2<f32>,
+    // space between the border and the edge of the output
+    shadow_margins: vec2<f32>,
+    width: f32,
+    // outer radius of the border
+    corner_radius: f32,
+    shadow_blur_radius: f32,
+}
+
+var<push_constant> common_params: CommonShaderParameters;
+
+@group(0) @binding(0) var textures: binding_array<texture_2d<f32>, 16>;
+@group(0) @binding(1) var<uniform> inputs_params: array<InputShaderParameters, 16>;
+@group(1) @binding(0) var<uniform> border_params: BorderParams;
+@group(2) @binding(0) var sampler_: sampler;
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.position = vec4(input.position, 1.0);
+    output.tex_coords = input.tex_coords;
+
+    return output;
+}
+
+// Signed distance from the rounded rectangle, negative inside of it.
+fn rounded_rect_distance(position: vec2<f32>, top_left: vec2<f32>, size: vec2<f32>, radius: f32) -> f32 {
+    let half_size = size / 2.0;
+    let radius_ = min(radius, min(half_size.x, half_size.y));
+    let q = abs(position - top_left - half_size) - half_size + radius_;
+    return length(max(q, vec2(0.0))) + min(max(q.x, q.y), 0.0) - radius_;
+}
+
+// Blends colors with straight (not premultiplied) alpha.
+fn over(src: vec4<f32>, dst: vec4<f32>) -> vec4<f32> {
+    let alpha = src.a + dst.a * (1.0 - src.a);
+    if alpha <= 0.0 {
+        return vec4(0.0);
+    }
+    let color = (src.rgb * src.a + dst.rgb * dst.a * (1.0 - src.a)) / alpha;
+    return vec4(color, alpha);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let output_size = vec2<f32>(common_params.output_resolution);
+    let input_size = vec2<f32>(textureDimensions(textures[0]));
+    let position = input.tex_coords * output_size;
+
+    let width = border_params.width;
+    let border_top_left = border_params.shadow_margins;
+    let border_size = input_size + 2.0 * width;
+    let content_top_left = border_top_left + width;
+    let outer_radius = border_params.corner_radius;
+    let inner_radius = max(outer_radius - width, 0.0);
+
+    let content_distance = rounded_rect_distance(position, content_top_left, input_size, inner_radius);
+    let border_distance = rounded_rect_distance(position, border_top_left, border_size, outer_radius);
+
+    let border_coverage = clamp(0.5 - border_distance, 0.0, 1.0);
+    let content_coverage = clamp(0.5 - content_distance, 0.0, 1.0);
+    var color = vec4(0.0);
+
+    if border_params.shadow_color.a > 0.0 {
+        let blur = border_params.shadow_blur_radius;
+        let shadow_distance = rounded_rect_distance(
+            position - border_params.shadow_offset,
+            border_top_left,
+            border_size,
+            outer_radius
+        );
+        let coverage = 1.0 - smoothstep(-blur - 0.5, blur + 0.5, shadow_distance);
+        // Shadow is visible only outside of the border, it doesn't show through
+        // transparent parts of the border or the input.
+        let alpha = border_params.shadow_color.a * coverage * (1.0 - border_coverage);
+        color = vec4(border_params.shadow_color.rgb, alpha);
+    }
+
+    if width > 0.0 {
+        // border is drawn only between the outer and the inner edge
+        let ring_coverage = clamp(border_coverage - content_coverage, 0.0, 1.0);
+        let border_color = border_params.color;
+        color = over(vec4(border_color.rgb, border_color.a * ring_coverage), color);
+    }
+
+    let tex_coords = (position - content_top_left) / input_size;
+    let content = textureSampleLevel(textures[0], sampler_, tex_coords, 0.0);
+    color = over(vec4(content.rgb, content.a * content_coverage), color);
+
+    return color;
+}
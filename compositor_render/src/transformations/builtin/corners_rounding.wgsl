@@ -14,6 +14,13 @@ struct CommonShaderParameters {
     time: f32,
     texture_count: u32,
     output_resolution: vec2<u32>,
+    frame_index: u32,
+    random_seed: u32,
+}
+
+struct InputShaderParameters {
+    resolution: vec2<u32>,
+    pts_offset: f32,
 }
 
 struct CornersRoudningParams {
@@ -23,6 +30,7 @@ struct CornersRoudningParams {
 var<push_constant> common_params: CommonShaderParameters;
 
 @group(0) @binding(0) var textures: binding_array<texture_2d<f32>, 16>;
+@group(0) @binding(1) var<uniform> inputs_params: array<InputShaderParameters, 16>;
 @group(1) @binding(0) var<uniform> corners_rounding_params: CornersRoudningParams;
 @group(2) @binding(0) var sampler_: sampler;
 
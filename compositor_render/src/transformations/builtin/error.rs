@@ -12,4 +12,16 @@ pub enum InitBuiltinError {
 
     #[error("Failed to initialize corners_rounding transformation.")]
     CornersRounding(#[source] CreateShaderError),
+
+    #[error("Failed to initialize privacy_mask transformation.")]
+    PrivacyMask(#[source] CreateShaderError),
+
+    #[error("Failed to initialize stylize transformation.")]
+    Stylize(#[source] CreateShaderError),
+
+    #[error("Failed to initialize scope transformation.")]
+    Scope(#[source] CreateShaderError),
+
+    #[error("Failed to initialize border transformation.")]
+    Border(#[source] CreateShaderError),
 }
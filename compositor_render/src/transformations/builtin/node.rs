@@ -3,7 +3,7 @@ use std::{sync::Arc, time::Duration};
 use compositor_common::{
     renderer_spec::FallbackStrategy,
     scene::{
-        builtin_transformations::{BuiltinSpec, FitToResolutionSpec},
+        builtin_transformations::{BuiltinSpec, FitToResolutionSpec, StylizeEffect},
         NodeId, Resolution,
     },
 };
@@ -11,7 +11,12 @@ use compositor_common::{
 use crate::{
     renderer::RenderCtx,
     utils::rgba_to_wgpu_color,
-    wgpu::{shader::WgpuShader, texture::NodeTexture},
+    wgpu::{
+        common_pipeline::MAX_TEXTURE_COUNT,
+        shader::{InputsBindGroup, WgpuShader},
+        texture::NodeTexture,
+        WgpuCtx,
+    },
 };
 
 use super::{params::RenderParams, shader_params::ParamsBuffer, BuiltinState};
@@ -19,7 +24,23 @@ use super::{params::RenderParams, shader_params::ParamsBuffer, BuiltinState};
 pub struct BuiltinNode {
     state: BuiltinState,
     gpu_shader: Arc<WgpuShader>,
+    /// Shader can sample at most `MAX_TEXTURE_COUNT` textures, layouts with more
+    /// inputs are rendered in multiple passes drawn on top of each other.
+    passes: Vec<BuiltinPass>,
+}
+
+struct BuiltinPass {
     params_buffer: ParamsBuffer,
+    inputs_bind_group: InputsBindGroup,
+}
+
+impl BuiltinPass {
+    fn new(params_buffer_content: bytes::Bytes, wgpu_ctx: &WgpuCtx) -> Self {
+        Self {
+            params_buffer: ParamsBuffer::new(params_buffer_content, wgpu_ctx),
+            inputs_bind_group: InputsBindGroup::default(),
+        }
+    }
 }
 
 impl BuiltinNode {
@@ -34,12 +55,12 @@ impl BuiltinNode {
 
         let params_buffer_content =
             RenderParams::new(&state, &input_resolutions).shader_buffer_content();
-        let params_buffer = ParamsBuffer::new(params_buffer_content, &gpu_shader.wgpu_ctx);
+        let pass = BuiltinPass::new(params_buffer_content, &gpu_shader.wgpu_ctx);
 
         Self {
             state,
             gpu_shader,
-            params_buffer,
+            passes: vec![pass],
         }
     }
 
@@ -51,11 +72,23 @@ impl BuiltinNode {
         &mut self.state
     }
 
+    /// Output depends only on the inputs, it's not animated.
+    pub fn is_static(&self) -> bool {
+        match &self.state {
+            BuiltinState::Static(BuiltinSpec::Stylize {
+                effect: StylizeEffect::FilmGrain { .. },
+            }) => false,
+            BuiltinState::Static(_) => true,
+            BuiltinState::Interpolated { .. } => false,
+        }
+    }
+
     pub fn render(
         &mut self,
         sources: &[(&NodeId, &NodeTexture)],
         target: &mut NodeTexture,
         pts: Duration,
+        frame_index: u32,
     ) {
         let input_resolutions: Vec<Option<Resolution>> = sources
             .iter()
@@ -63,21 +96,49 @@ impl BuiltinNode {
             .collect();
 
         let output_resolution = self.output_resolution(&input_resolutions);
+        let clear_color = self.clear_color().unwrap_or(wgpu::Color::TRANSPARENT);
+        let wgpu_ctx = &self.gpu_shader.wgpu_ctx;
 
-        let params_buffer_content =
-            RenderParams::new(&self.state, &input_resolutions).shader_buffer_content();
-
-        self.params_buffer
-            .update(params_buffer_content, &self.gpu_shader.wgpu_ctx);
-
-        let target = target.ensure_size(&self.gpu_shader.wgpu_ctx, output_resolution);
-        self.gpu_shader.render(
-            self.params_buffer.bind_group(),
-            sources,
-            target,
-            pts,
-            self.clear_color(),
-        );
+        let render_params = RenderParams::new(&self.state, &input_resolutions);
+        let batches: Vec<_> = match sources.len() > MAX_TEXTURE_COUNT as usize {
+            false => vec![(sources, render_params)],
+            true => sources
+                .chunks(MAX_TEXTURE_COUNT as usize)
+                .enumerate()
+                .map(|(index, batch)| {
+                    let start = index * MAX_TEXTURE_COUNT as usize;
+                    (batch, render_params.for_inputs(start..start + batch.len()))
+                })
+                .collect(),
+        };
+
+        let target = target.ensure_size(wgpu_ctx, output_resolution);
+        for (index, (batch, params)) in batches.into_iter().enumerate() {
+            let params_buffer_content = params.shader_buffer_content();
+            match self.passes.get_mut(index) {
+                Some(pass) => pass.params_buffer.update(params_buffer_content, wgpu_ctx),
+                None => self
+                    .passes
+                    .push(BuiltinPass::new(params_buffer_content, wgpu_ctx)),
+            }
+            let pass = &mut self.passes[index];
+
+            let load = match index {
+                0 => wgpu::LoadOp::Clear(clear_color),
+                _ => wgpu::LoadOp::Load,
+            };
+            self.gpu_shader.render(
+                pass.params_buffer.bind_group(),
+                &mut pass.inputs_bind_group,
+                batch,
+                target,
+                pts,
+                frame_index,
+                None,
+                None,
+                load,
+            );
+        }
     }
 
     pub fn clear_color(&self) -> Option<wgpu::Color> {
@@ -117,7 +178,7 @@ impl BuiltinNode {
     }
 }
 
-trait BuiltinSpecExt {
+pub(crate) trait BuiltinSpecExt {
     fn clear_color(&self) -> Option<wgpu::Color>;
     fn output_resolution(&self, input_resolutions: &[Option<Resolution>]) -> Resolution;
     fn resolution(&self) -> Option<Resolution>;
@@ -133,6 +194,12 @@ impl BuiltinSpecExt for BuiltinSpec {
             BuiltinSpec::TiledLayout(spec) => Some(rgba_to_wgpu_color(&spec.background_color_rgba)),
             BuiltinSpec::CornersRounding { .. } => Some(wgpu::Color::TRANSPARENT),
             BuiltinSpec::MirrorImage { .. } => None,
+            BuiltinSpec::PrivacyMask(_) => None,
+            BuiltinSpec::Stylize { .. } => None,
+            BuiltinSpec::Scope { .. } => None,
+            BuiltinSpec::Transform2d(spec) => Some(rgba_to_wgpu_color(&spec.background_color_rgba)),
+            BuiltinSpec::Perspective(spec) => Some(rgba_to_wgpu_color(&spec.background_color_rgba)),
+            BuiltinSpec::Border(_) => None,
             BuiltinSpec::FitToResolution(FitToResolutionSpec {
                 background_color_rgba,
                 ..
@@ -159,9 +226,21 @@ impl BuiltinSpecExt for BuiltinSpec {
             BuiltinSpec::TiledLayout(spec) => spec.resolution,
             BuiltinSpec::MirrorImage { .. } => first_input_resolution(input_resolutions),
             BuiltinSpec::CornersRounding { .. } => first_input_resolution(input_resolutions),
+            BuiltinSpec::PrivacyMask(_) => first_input_resolution(input_resolutions),
+            BuiltinSpec::Stylize { .. } => first_input_resolution(input_resolutions),
+            BuiltinSpec::Scope { resolution, .. } => *resolution,
+            BuiltinSpec::Transform2d(spec) => spec
+                .resolution
+                .unwrap_or_else(|| first_input_resolution(input_resolutions)),
+            BuiltinSpec::Perspective(spec) => spec
+                .resolution
+                .unwrap_or_else(|| first_input_resolution(input_resolutions)),
+            BuiltinSpec::Border(spec) => {
+                spec.output_resolution(first_input_resolution(input_resolutions))
+            }
             BuiltinSpec::FitToResolution(FitToResolutionSpec { resolution, .. }) => *resolution,
-            BuiltinSpec::FillToResolution { resolution } => *resolution,
-            BuiltinSpec::StretchToResolution { resolution } => *resolution,
+            BuiltinSpec::FillToResolution { resolution, .. } => *resolution,
+            BuiltinSpec::StretchToResolution { resolution, .. } => *resolution,
         }
     }
 
@@ -171,11 +250,17 @@ impl BuiltinSpecExt for BuiltinSpec {
             BuiltinSpec::TiledLayout(spec) => Some(spec.resolution),
             BuiltinSpec::MirrorImage { .. } => None,
             BuiltinSpec::CornersRounding { .. } => None,
+            BuiltinSpec::PrivacyMask(_) => None,
+            BuiltinSpec::Stylize { .. } => None,
+            BuiltinSpec::Scope { resolution, .. } => Some(*resolution),
+            BuiltinSpec::Transform2d(spec) => spec.resolution,
+            BuiltinSpec::Perspective(spec) => spec.resolution,
+            BuiltinSpec::Border(_) => None,
             BuiltinSpec::FitToResolution(FitToResolutionSpec { resolution, .. }) => {
                 Some(*resolution)
             }
-            BuiltinSpec::FillToResolution { resolution } => Some(*resolution),
-            BuiltinSpec::StretchToResolution { resolution } => Some(*resolution),
+            BuiltinSpec::FillToResolution { resolution, .. } => Some(*resolution),
+            BuiltinSpec::StretchToResolution { resolution, .. } => Some(*resolution),
         }
     }
 
@@ -187,7 +272,13 @@ impl BuiltinSpecExt for BuiltinSpec {
             | BuiltinSpec::FixedPositionLayout { .. }
             | BuiltinSpec::TiledLayout { .. }
             | BuiltinSpec::MirrorImage { .. }
-            | BuiltinSpec::CornersRounding { .. } => FallbackStrategy::FallbackIfAllInputsMissing,
+            | BuiltinSpec::CornersRounding { .. }
+            | BuiltinSpec::PrivacyMask(_)
+            | BuiltinSpec::Stylize { .. }
+            | BuiltinSpec::Scope { .. }
+            | BuiltinSpec::Transform2d(_)
+            | BuiltinSpec::Perspective(_)
+            | BuiltinSpec::Border(_) => FallbackStrategy::FallbackIfAllInputsMissing,
         }
     }
 }
@@ -1,27 +1,43 @@
+use std::ops::Range;
+
 use compositor_common::{
     scene::{
-        builtin_transformations::{BuiltinSpec, FitToResolutionSpec, MirrorMode},
+        builtin_transformations::{
+            BuiltinSpec, FitToResolutionSpec, MirrorMode, ScopeMode, StylizeEffect,
+        },
         Resolution,
     },
     util::{ContinuousValue, InterpolationState},
 };
 
 use self::{
+    border::BorderParams,
     box_layout_params::BoxLayoutParams,
     corners_rounding::CornersRoundingParams,
     fixed_position_layout::new_fixed_position_layout_params,
     mirror_image::MirrorModeExt,
+    perspective::PerspectiveParams,
+    privacy_mask::PrivacyMaskParams,
+    scope::ScopeModeExt,
+    stylize::StylizeEffectExt,
     tiled_layout::new_tiled_layout_params,
+    transform_2d::Transform2dParams,
     transform_to_resolution::{new_fit_to_resolution_params, FillParams},
 };
 
 use super::{box_layout::BoxLayout, BuiltinState, BuiltinTransition};
 
+mod border;
 mod box_layout_params;
 mod corners_rounding;
 mod fixed_position_layout;
 mod mirror_image;
+mod perspective;
+mod privacy_mask;
+mod scope;
+mod stylize;
 mod tiled_layout;
+mod transform_2d;
 mod transform_to_resolution;
 
 #[derive(Debug, Clone)]
@@ -30,6 +46,12 @@ pub(super) enum RenderParams {
     Fill(FillParams),
     MirrorMode(MirrorMode),
     CornersRounding(CornersRoundingParams),
+    PrivacyMask(PrivacyMaskParams),
+    Stylize(StylizeEffect),
+    Scope(ScopeMode),
+    Transform2d(Transform2dParams),
+    Perspective(PerspectiveParams),
+    Border(BorderParams),
     None,
 }
 
@@ -60,6 +82,16 @@ impl RenderParams {
                 ),
                 state,
             ),
+            BuiltinTransition::Transform2d(start, end) => Self::interpolate(
+                &Self::new_from_spec(&BuiltinSpec::Transform2d(start.clone()), input_resolutions),
+                &Self::new_from_spec(&BuiltinSpec::Transform2d(end.clone()), input_resolutions),
+                state,
+            ),
+            BuiltinTransition::Perspective(start, end) => Self::interpolate(
+                &Self::new_from_spec(&BuiltinSpec::Perspective(start.clone()), input_resolutions),
+                &Self::new_from_spec(&BuiltinSpec::Perspective(end.clone()), input_resolutions),
+                state,
+            ),
         }
     }
 
@@ -75,11 +107,27 @@ impl RenderParams {
             BuiltinSpec::CornersRounding { border_radius } => RenderParams::CornersRounding(
                 CornersRoundingParams::new(*border_radius, input_resolutions),
             ),
+            BuiltinSpec::PrivacyMask(spec) => {
+                RenderParams::PrivacyMask(PrivacyMaskParams::new(spec, input_resolutions))
+            }
+            BuiltinSpec::Stylize { effect } => RenderParams::Stylize(*effect),
+            BuiltinSpec::Scope { mode, .. } => RenderParams::Scope(*mode),
+            BuiltinSpec::Transform2d(spec) => {
+                RenderParams::Transform2d(Transform2dParams::new(spec, input_resolutions))
+            }
+            BuiltinSpec::Perspective(spec) => {
+                RenderParams::Perspective(PerspectiveParams::new(spec, input_resolutions))
+            }
+            BuiltinSpec::Border(spec) => {
+                RenderParams::Border(BorderParams::new(spec, input_resolutions))
+            }
             BuiltinSpec::FitToResolution(FitToResolutionSpec {
                 background_color_rgba: _,
                 horizontal_alignment,
                 vertical_alignment,
                 resolution,
+                mipmaps: _,
+                scaling_quality: _,
             }) => match input_resolutions.get(0).unwrap_or(&None) {
                 Some(input_resolution) => RenderParams::BoxLayout(new_fit_to_resolution_params(
                     *input_resolution,
@@ -92,7 +140,7 @@ impl RenderParams {
                     output_resolution: *resolution,
                 }),
             },
-            BuiltinSpec::FillToResolution { resolution } => {
+            BuiltinSpec::FillToResolution { resolution, .. } => {
                 match input_resolutions.get(0).unwrap_or(&None) {
                     Some(input_resolution) => {
                         RenderParams::Fill(FillParams::new(*input_resolution, *resolution))
@@ -104,6 +152,22 @@ impl RenderParams {
         }
     }
 
+    /// Params of inputs in `range`, used when inputs are rendered in multiple passes.
+    /// Only layouts can have more inputs than a shader can sample at once.
+    pub fn for_inputs(&self, range: Range<usize>) -> Self {
+        match self {
+            RenderParams::BoxLayout(params) => RenderParams::BoxLayout(BoxLayoutParams {
+                boxes: params
+                    .boxes
+                    .get(range)
+                    .map(<[BoxLayout]>::to_vec)
+                    .unwrap_or_default(),
+                output_resolution: params.output_resolution,
+            }),
+            params => params.clone(),
+        }
+    }
+
     /// Returned bytes have to match shader memory layout to work properly.
     /// Should produce buffer with the same size for the same inputs count
     /// https://www.w3.org/TR/WGSL/#memory-layouts
@@ -115,6 +179,12 @@ impl RenderParams {
                 corners_rounding_params.shader_buffer_content()
             }
             RenderParams::Fill(fill_params) => fill_params.shader_buffer_content(),
+            RenderParams::PrivacyMask(params) => params.shader_buffer_content(),
+            RenderParams::Stylize(effect) => effect.shader_buffer_content(),
+            RenderParams::Scope(mode) => mode.shader_buffer_content(),
+            RenderParams::Transform2d(params) => params.shader_buffer_content(),
+            RenderParams::Perspective(params) => params.shader_buffer_content(),
+            RenderParams::Border(params) => params.shader_buffer_content(),
             RenderParams::None => bytes::Bytes::new(),
         }
     }
@@ -126,6 +196,12 @@ impl ContinuousValue for RenderParams {
             (RenderParams::BoxLayout(start), RenderParams::BoxLayout(end)) => {
                 RenderParams::BoxLayout(BoxLayoutParams::interpolate(start, end, state))
             }
+            (RenderParams::Transform2d(start), RenderParams::Transform2d(end)) => {
+                RenderParams::Transform2d(Transform2dParams::interpolate(start, end, state))
+            }
+            (RenderParams::Perspective(start), RenderParams::Perspective(end)) => {
+                RenderParams::Perspective(PerspectiveParams::interpolate(start, end, state))
+            }
             (start, _) => start.clone(),
         }
     }
@@ -0,0 +1,67 @@
+use bytes::{BufMut, BytesMut};
+use compositor_common::{
+    scene::{builtin_transformations::BorderSpec, Resolution},
+    util::{colors::RGBAColor, coord::Coord},
+};
+
+#[derive(Debug, Clone)]
+pub struct BorderParams {
+    color: RGBAColor,
+    shadow_color: RGBAColor,
+    // pixels
+    shadow_offset: (f32, f32),
+    shadow_margins: (f32, f32),
+    width: f32,
+    corner_radius: f32,
+    shadow_blur_radius: f32,
+}
+
+impl BorderParams {
+    pub fn new(spec: &BorderSpec, input_resolutions: &[Option<Resolution>]) -> Self {
+        let corner_radius = match (spec.corner_radius, input_resolutions.first()) {
+            (Coord::Pixel(pixels), _) => pixels as f32,
+            (Coord::Percent(percent), Some(Some(input_resolution))) => {
+                let input_size = input_resolution.width.min(input_resolution.height) as f32;
+                (input_size + 2.0 * spec.width as f32) * percent as f32 / 100.0
+            }
+            (Coord::Percent(_), _) => 0.0,
+        };
+        let (margin_x, margin_y) = spec.shadow_margins();
+        let shadow = spec.shadow;
+
+        Self {
+            color: spec.color,
+            shadow_color: shadow.map(|s| s.color).unwrap_or(RGBAColor(0, 0, 0, 0)),
+            shadow_offset: shadow
+                .map(|s| (s.offset_x as f32, s.offset_y as f32))
+                .unwrap_or_default(),
+            shadow_margins: (margin_x as f32, margin_y as f32),
+            width: spec.width as f32,
+            corner_radius: corner_radius.max(0.0),
+            shadow_blur_radius: shadow.map(|s| s.blur_radius as f32).unwrap_or_default(),
+        }
+    }
+
+    pub fn shader_buffer_content(&self) -> bytes::Bytes {
+        let mut buffer = BytesMut::new();
+        put_color(&mut buffer, self.color);
+        put_color(&mut buffer, self.shadow_color);
+        buffer.put_f32_le(self.shadow_offset.0);
+        buffer.put_f32_le(self.shadow_offset.1);
+        buffer.put_f32_le(self.shadow_margins.0);
+        buffer.put_f32_le(self.shadow_margins.1);
+        buffer.put_f32_le(self.width);
+        buffer.put_f32_le(self.corner_radius);
+        buffer.put_f32_le(self.shadow_blur_radius);
+        // padding, struct size is a multiple of 16 bytes
+        buffer.put_u32_le(0);
+        buffer.freeze()
+    }
+}
+
+fn put_color(buffer: &mut BytesMut, color: RGBAColor) {
+    let RGBAColor(r, g, b, a) = color;
+    for channel in [r, g, b, a] {
+        buffer.put_f32_le(channel as f32 / 255.0);
+    }
+}
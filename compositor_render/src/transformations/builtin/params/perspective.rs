@@ -0,0 +1,151 @@
+use compositor_common::{
+    scene::{builtin_transformations::PerspectiveSpec, Resolution},
+    util::{ContinuousValue, InterpolationState},
+};
+use nalgebra_glm::{rotate_x, rotate_y, rotate_z, scale, translate, vec3, Mat4};
+
+use crate::transformations::builtin::utils::mat4_to_bytes;
+
+/// Points closer to the camera than this (in pixels) are clipped.
+const NEAR_PLANE_DISTANCE: f32 = 1.0;
+
+/// Values of the spec resolved to pixels, so they can be interpolated.
+#[derive(Debug, Clone)]
+pub struct PerspectiveParams {
+    input_resolution: Option<Resolution>,
+    output_resolution: Resolution,
+    rotation_degrees: (f32, f32, f32),
+    distance: f32,
+    scale: f32,
+    // pixels in [0, output_resolution] coords, z is pointing toward the viewer
+    translation: (f32, f32, f32),
+    anchor: (f32, f32),
+}
+
+impl PerspectiveParams {
+    pub fn new(spec: &PerspectiveSpec, input_resolutions: &[Option<Resolution>]) -> Self {
+        let input_resolution = input_resolutions.first().copied().flatten();
+        let output_resolution = spec.resolution.or(input_resolution).unwrap_or(Resolution {
+            width: 1,
+            height: 1,
+        });
+        Self {
+            input_resolution,
+            output_resolution,
+            rotation_degrees: (
+                spec.rotation_x.0 as f32,
+                spec.rotation_y.0 as f32,
+                spec.rotation_z.0 as f32,
+            ),
+            distance: spec
+                .distance
+                .unwrap_or(output_resolution.width as f32)
+                .max(NEAR_PLANE_DISTANCE),
+            scale: spec.scale,
+            translation: (
+                spec.translate_x.pixels(output_resolution.width as u32) as f32,
+                spec.translate_y.pixels(output_resolution.height as u32) as f32,
+                spec.translate_z,
+            ),
+            anchor: spec.anchor,
+        }
+    }
+
+    pub fn shader_buffer_content(&self) -> bytes::Bytes {
+        mat4_to_bytes(&self.transformation_matrix())
+    }
+
+    /// Returns matrix that transforms input plane vertices (located in corners
+    /// of clip space) to the final position. Input is transformed in
+    /// ([-output_width / 2, output_width / 2], [-output_height / 2, output_height / 2])
+    /// coordinate system and then projected from the camera placed at
+    /// (0, 0, distance). The input plane is not flat anymore after rotation, so
+    /// the matrix produces `w` other than 1 and depth in the [0, 1) range
+    /// for points in front of the near plane.
+    fn transformation_matrix(&self) -> Mat4 {
+        let Some(input_resolution) = self.input_resolution else {
+            // Degenerated plane, nothing is rendered.
+            return Mat4::zeros();
+        };
+        let (input_width, input_height) = (
+            input_resolution.width as f32,
+            input_resolution.height as f32,
+        );
+        let (output_width, output_height) = (
+            self.output_resolution.width as f32,
+            self.output_resolution.height as f32,
+        );
+
+        let distance = self.distance;
+        // Infinite far plane, depth of the point at distance `d` from the camera is
+        // (1 - near / d) after perspective division.
+        #[rustfmt::skip]
+        let projection = Mat4::new(
+            2.0 / output_width, 0.0, 0.0, 0.0,
+            0.0, 2.0 / output_height, 0.0, 0.0,
+            0.0, 0.0, -1.0 / distance, (distance - NEAR_PLANE_DISTANCE) / distance,
+            0.0, 0.0, -1.0 / distance, 1.0,
+        );
+
+        let anchor_x = self.anchor.0 * input_width;
+        let anchor_y = self.anchor.1 * input_height;
+        let mut transformation_matrix = translate(
+            &projection,
+            &vec3(
+                -(output_width / 2.0) + anchor_x + self.translation.0,
+                (output_height / 2.0) - anchor_y - self.translation.1,
+                self.translation.2,
+            ),
+        );
+
+        let (rotation_x, rotation_y, rotation_z) = self.rotation_degrees;
+        transformation_matrix = rotate_z(&transformation_matrix, rotation_z.to_radians());
+        transformation_matrix = rotate_y(&transformation_matrix, rotation_y.to_radians());
+        transformation_matrix = rotate_x(&transformation_matrix, rotation_x.to_radians());
+        transformation_matrix = scale(
+            &transformation_matrix,
+            &vec3(self.scale, self.scale, self.scale),
+        );
+
+        // anchor relative to the middle of the input
+        transformation_matrix = translate(
+            &transformation_matrix,
+            &vec3(
+                (input_width / 2.0) - anchor_x,
+                anchor_y - (input_height / 2.0),
+                0.0,
+            ),
+        );
+        scale(
+            &transformation_matrix,
+            &vec3(input_width / 2.0, input_height / 2.0, 1.0),
+        )
+    }
+}
+
+impl ContinuousValue for PerspectiveParams {
+    fn interpolate(start: &Self, end: &Self, state: InterpolationState) -> Self {
+        let interpolate = |start: f32, end: f32| f32::interpolate(&start, &end, state);
+        Self {
+            input_resolution: start.input_resolution,
+            output_resolution: start.output_resolution,
+            rotation_degrees: (
+                interpolate(start.rotation_degrees.0, end.rotation_degrees.0),
+                interpolate(start.rotation_degrees.1, end.rotation_degrees.1),
+                interpolate(start.rotation_degrees.2, end.rotation_degrees.2),
+            ),
+            // spring interpolation can overshoot
+            distance: interpolate(start.distance, end.distance).max(NEAR_PLANE_DISTANCE),
+            scale: interpolate(start.scale, end.scale),
+            translation: (
+                interpolate(start.translation.0, end.translation.0),
+                interpolate(start.translation.1, end.translation.1),
+                interpolate(start.translation.2, end.translation.2),
+            ),
+            anchor: (
+                interpolate(start.anchor.0, end.anchor.0),
+                interpolate(start.anchor.1, end.anchor.1),
+            ),
+        }
+    }
+}
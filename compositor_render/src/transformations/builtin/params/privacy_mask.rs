@@ -0,0 +1,65 @@
+use bytes::{BufMut, BytesMut};
+use compositor_common::scene::{
+    builtin_transformations::{PrivacyMaskEffect, PrivacyMaskSpec, PRIVACY_MASK_MAX_REGIONS_COUNT},
+    Resolution,
+};
+
+#[derive(Debug, Clone)]
+pub struct PrivacyMaskParams {
+    effect: PrivacyMaskEffect,
+    // x, y, width, height in pixels
+    regions: Vec<[f32; 4]>,
+}
+
+impl PrivacyMaskParams {
+    pub fn new(spec: &PrivacyMaskSpec, input_resolutions: &[Option<Resolution>]) -> Self {
+        let Some(Some(input_resolution)) = input_resolutions.first() else {
+            return Self {
+                effect: spec.effect,
+                regions: vec![],
+            };
+        };
+        let width = input_resolution.width as u32;
+        let height = input_resolution.height as u32;
+
+        let regions = spec
+            .regions
+            .iter()
+            .take(PRIVACY_MASK_MAX_REGIONS_COUNT as usize)
+            .map(|region| {
+                [
+                    region.left.pixels(width) as f32,
+                    region.top.pixels(height) as f32,
+                    region.width.pixels(width) as f32,
+                    region.height.pixels(height) as f32,
+                ]
+            })
+            .collect();
+
+        Self {
+            effect: spec.effect,
+            regions,
+        }
+    }
+
+    pub fn shader_buffer_content(&self) -> bytes::Bytes {
+        let (effect, effect_size) = match self.effect {
+            PrivacyMaskEffect::Blur { radius } => (0_u32, radius),
+            PrivacyMaskEffect::Pixelate { block_size } => (1_u32, block_size),
+        };
+
+        let mut buffer = BytesMut::new();
+        buffer.put_u32_le(effect);
+        buffer.put_f32_le(effect_size as f32);
+        buffer.put_u32_le(self.regions.len() as u32);
+        // padding, regions array is aligned to 16 bytes
+        buffer.put_u32_le(0);
+
+        for index in 0..PRIVACY_MASK_MAX_REGIONS_COUNT as usize {
+            let region = self.regions.get(index).copied().unwrap_or_default();
+            region.iter().for_each(|value| buffer.put_f32_le(*value));
+        }
+
+        buffer.freeze()
+    }
+}
@@ -0,0 +1,15 @@
+use compositor_common::scene::builtin_transformations::ScopeMode;
+
+pub trait ScopeModeExt {
+    fn shader_buffer_content(&self) -> bytes::Bytes;
+}
+
+impl ScopeModeExt for ScopeMode {
+    fn shader_buffer_content(&self) -> bytes::Bytes {
+        match self {
+            ScopeMode::LumaWaveform => bytes::Bytes::copy_from_slice(&0_u32.to_le_bytes()),
+            ScopeMode::RgbParade => bytes::Bytes::copy_from_slice(&1_u32.to_le_bytes()),
+            ScopeMode::Vectorscope => bytes::Bytes::copy_from_slice(&2_u32.to_le_bytes()),
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use bytes::{BufMut, BytesMut};
+use compositor_common::scene::builtin_transformations::StylizeEffect;
+
+pub trait StylizeEffectExt {
+    fn shader_buffer_content(&self) -> bytes::Bytes;
+}
+
+impl StylizeEffectExt for StylizeEffect {
+    fn shader_buffer_content(&self) -> bytes::Bytes {
+        let (effect, value) = match *self {
+            StylizeEffect::EdgeDetect => (0_u32, 0.0),
+            StylizeEffect::Posterize { levels } => (1_u32, levels as f32),
+            StylizeEffect::Vignette { strength } => (2_u32, strength),
+            StylizeEffect::FilmGrain { strength } => (3_u32, strength),
+            StylizeEffect::ChromaticAberration { offset } => (4_u32, offset as f32),
+        };
+
+        let mut buffer = BytesMut::new();
+        buffer.put_u32_le(effect);
+        buffer.put_f32_le(value);
+        buffer.freeze()
+    }
+}
@@ -0,0 +1,124 @@
+use compositor_common::{
+    scene::{builtin_transformations::Transform2dSpec, Resolution},
+    util::{ContinuousValue, InterpolationState},
+};
+use nalgebra_glm::{rotate_z, scale, translate, vec3, Mat4};
+
+use crate::transformations::builtin::utils::mat4_to_bytes;
+
+/// Values of the spec resolved to pixels, so they can be interpolated.
+#[derive(Debug, Clone)]
+pub struct Transform2dParams {
+    input_resolution: Option<Resolution>,
+    output_resolution: Resolution,
+    rotation_degrees: f32,
+    scale: (f32, f32),
+    // pixels in [0, output_resolution] coords
+    translation: (f32, f32),
+    anchor: (f32, f32),
+}
+
+impl Transform2dParams {
+    pub fn new(spec: &Transform2dSpec, input_resolutions: &[Option<Resolution>]) -> Self {
+        let input_resolution = input_resolutions.first().copied().flatten();
+        let output_resolution = spec.resolution.or(input_resolution).unwrap_or(Resolution {
+            width: 1,
+            height: 1,
+        });
+        Self {
+            input_resolution,
+            output_resolution,
+            rotation_degrees: spec.rotation.0 as f32,
+            scale: (spec.scale_x, spec.scale_y),
+            translation: (
+                spec.translate_x.pixels(output_resolution.width as u32) as f32,
+                spec.translate_y.pixels(output_resolution.height as u32) as f32,
+            ),
+            anchor: spec.anchor,
+        }
+    }
+
+    pub fn shader_buffer_content(&self) -> bytes::Bytes {
+        mat4_to_bytes(&self.transformation_matrix())
+    }
+
+    /// Returns matrix that transforms input plane vertices (located in corners
+    /// of clip space) to the final position. Transformations are applied in
+    /// ([-output_width / 2, output_width / 2], [-output_height / 2, output_height / 2])
+    /// coordinate system, with the anchor point moved to the origin.
+    fn transformation_matrix(&self) -> Mat4 {
+        let Some(input_resolution) = self.input_resolution else {
+            // Degenerated plane, nothing is rendered.
+            return Mat4::zeros();
+        };
+        let (input_width, input_height) = (
+            input_resolution.width as f32,
+            input_resolution.height as f32,
+        );
+        let (output_width, output_height) = (
+            self.output_resolution.width as f32,
+            self.output_resolution.height as f32,
+        );
+
+        let mut transformation_matrix = Mat4::identity();
+        transformation_matrix = scale(
+            &transformation_matrix,
+            &vec3(2.0 / output_width, 2.0 / output_height, 1.0),
+        );
+
+        let anchor_x = self.anchor.0 * input_width;
+        let anchor_y = self.anchor.1 * input_height;
+        transformation_matrix = translate(
+            &transformation_matrix,
+            &vec3(
+                -(output_width / 2.0) + anchor_x + self.translation.0,
+                (output_height / 2.0) - anchor_y - self.translation.1,
+                0.0,
+            ),
+        );
+
+        transformation_matrix =
+            rotate_z(&transformation_matrix, self.rotation_degrees.to_radians());
+        transformation_matrix = scale(
+            &transformation_matrix,
+            &vec3(self.scale.0, self.scale.1, 1.0),
+        );
+
+        // anchor relative to the middle of the input
+        transformation_matrix = translate(
+            &transformation_matrix,
+            &vec3(
+                (input_width / 2.0) - anchor_x,
+                anchor_y - (input_height / 2.0),
+                0.0,
+            ),
+        );
+        scale(
+            &transformation_matrix,
+            &vec3(input_width / 2.0, input_height / 2.0, 1.0),
+        )
+    }
+}
+
+impl ContinuousValue for Transform2dParams {
+    fn interpolate(start: &Self, end: &Self, state: InterpolationState) -> Self {
+        let interpolate_pair = |start: (f32, f32), end: (f32, f32)| {
+            (
+                f32::interpolate(&start.0, &end.0, state),
+                f32::interpolate(&start.1, &end.1, state),
+            )
+        };
+        Self {
+            input_resolution: start.input_resolution,
+            output_resolution: start.output_resolution,
+            rotation_degrees: f32::interpolate(
+                &start.rotation_degrees,
+                &end.rotation_degrees,
+                state,
+            ),
+            scale: interpolate_pair(start.scale, end.scale),
+            translation: interpolate_pair(start.translation, end.translation),
+            anchor: interpolate_pair(start.anchor, end.anchor),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) texture_id: i32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct CommonShaderParameters {
+    time: f32,
+    texture_count: u32,
+    output_resolution: vec2<u32>,
+    frame_index: u32,
+    random_seed: u32,
+}
+
+struct InputShaderParameters {
+    resolution: vec2<u32>,
+    pts_offset: f32,
+}
+
+struct PrivacyMaskParams {
+    // 0 - blur, 1 - pixelate
+    effect: u32,
+    // blur radius or block size in pixels
+    effect_size: f32,
+    regions_count: u32,
+    // x, y, width, height in pixels
+    regions: array<vec4<f32>, 16>,
+}
+
+var<push_constant> common_params: CommonShaderParameters;
+
+@group(0) @binding(0) var textures: binding_array<texture_2d<f32>, 16>;
+@group(0) @binding(1) var<uniform> inputs_params: array<InputShaderParameters, 16>;
+@group(1) @binding(0) var<uniform> privacy_mask_params: PrivacyMaskParams;
+@group(2) @binding(0) var sampler_: sampler;
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.position = vec4(input.position, 1.0);
+    output.tex_coords = input.tex_coords;
+
+    return output;
+}
+
+fn is_in_region(position: vec2<f32>) -> bool {
+    let regions_count = min(privacy_mask_params.regions_count, 16u);
+    for (var i = 0u; i < regions_count; i++) {
+        let region = privacy_mask_params.regions[i];
+        if (all(position >= region.xy) && all(position < region.xy + region.zw)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+fn pixelate(position: vec2<f32>, input_resolution: vec2<f32>) -> vec4<f32> {
+    let block_size = max(privacy_mask_params.effect_size, 1.0);
+    let block_center = (floor(position / block_size) + 0.5) * block_size;
+    return textureSampleLevel(textures[0], sampler_, block_center / input_resolution, 0.0);
+}
+
+// Box blur with a constant amount of samples (9x9) spread over the radius,
+// so the cost does not depend on the blur radius.
+fn blur(tex_coords: vec2<f32>, input_resolution: vec2<f32>) -> vec4<f32> {
+    let samples_per_side = 4;
+    let step = privacy_mask_params.effect_size / f32(samples_per_side) / input_resolution;
+
+    var color = vec4(0.0);
+    for (var x = -samples_per_side; x <= samples_per_side; x++) {
+        for (var y = -samples_per_side; y <= samples_per_side; y++) {
+            let offset = vec2(f32(x), f32(y)) * step;
+            color += textureSampleLevel(textures[0], sampler_, tex_coords + offset, 0.0);
+        }
+    }
+
+    let samples_count = f32((2 * samples_per_side + 1) * (2 * samples_per_side + 1));
+    return color / samples_count;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let input_resolution = vec2<f32>(textureDimensions(textures[0]));
+    let position = input.tex_coords * input_resolution;
+
+    if (!is_in_region(position)) {
+        return textureSampleLevel(textures[0], sampler_, input.tex_coords, 0.0);
+    }
+
+    if (privacy_mask_params.effect == 1u) {
+        return pixelate(position, input_resolution);
+    }
+    return blur(input.tex_coords, input_resolution);
+}
@@ -0,0 +1,13 @@
+const KERNEL_RADIUS: f32 = 2.0;
+
+// Catmull-Rom spline.
+fn kernel(x: f32) -> f32 {
+    let t = abs(x);
+    if t < 1.0 {
+        return (1.5 * t - 2.5) * t * t + 1.0;
+    }
+    if t < 2.0 {
+        return ((-0.5 * t + 2.5) * t - 4.0) * t + 2.0;
+    }
+    return 0.0;
+}
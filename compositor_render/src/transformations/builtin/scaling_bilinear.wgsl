@@ -0,0 +1,3 @@
+fn sample_input(texture_id: i32, tex_coords: vec2<f32>) -> vec4<f32> {
+    return textureSample(textures[texture_id], sampler_, tex_coords);
+}
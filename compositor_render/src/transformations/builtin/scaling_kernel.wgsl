@@ -0,0 +1,33 @@
+// Separable resampling with `kernel` defined by the variant of the shader.
+
+// Max number of texels read along each axis. When downscaling by a larger factor
+// the kernel is narrower than the output pixel and some texels are skipped.
+const MAX_TAPS: f32 = 32.0;
+
+fn sample_input(texture_id: i32, tex_coords: vec2<f32>) -> vec4<f32> {
+    let dimensions = vec2<i32>(textureDimensions(textures[texture_id]));
+    let position = tex_coords * vec2<f32>(dimensions);
+    // Input texels per output pixel. Kernel is stretched by this factor when
+    // downscaling, so all texels covered by the output pixel contribute.
+    let footprint = abs(dpdx(position)) + abs(dpdy(position));
+    let scale = clamp(footprint, vec2(1.0), vec2(MAX_TAPS / (2.0 * KERNEL_RADIUS)));
+
+    let center = position - 0.5;
+    let support = KERNEL_RADIUS * scale;
+    let first = vec2<i32>(floor(center - support)) + 1;
+    let last = vec2<i32>(floor(center + support));
+
+    var color = vec4<f32>(0.0);
+    var weights = 0.0;
+    for (var y = first.y; y <= last.y; y++) {
+        let weight_y = kernel((f32(y) - center.y) / scale.y);
+        for (var x = first.x; x <= last.x; x++) {
+            let weight = kernel((f32(x) - center.x) / scale.x) * weight_y;
+            let texel = clamp(vec2(x, y), vec2(0), dimensions - 1);
+            color += textureLoad(textures[texture_id], texel, 0) * weight;
+            weights += weight;
+        }
+    }
+    // Negative lobes can overshoot around hard edges.
+    return clamp(color / weights, vec4(0.0), vec4(1.0));
+}
@@ -0,0 +1,17 @@
+const KERNEL_RADIUS: f32 = 3.0;
+const PI: f32 = 3.141592653589793;
+
+fn sinc(x: f32) -> f32 {
+    if abs(x) < 0.00001 {
+        return 1.0;
+    }
+    let px = PI * x;
+    return sin(px) / px;
+}
+
+fn kernel(x: f32) -> f32 {
+    if abs(x) >= KERNEL_RADIUS {
+        return 0.0;
+    }
+    return sinc(x) * sinc(x / KERNEL_RADIUS);
+}
@@ -0,0 +1,5 @@
+fn sample_input(texture_id: i32, tex_coords: vec2<f32>) -> vec4<f32> {
+    let dimensions = vec2<i32>(textureDimensions(textures[texture_id]));
+    let texel = vec2<i32>(floor(tex_coords * vec2<f32>(dimensions)));
+    return textureLoad(textures[texture_id], clamp(texel, vec2(0), dimensions - 1), 0);
+}
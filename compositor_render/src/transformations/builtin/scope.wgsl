@@ -0,0 +1,162 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) texture_id: i32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct CommonShaderParameters {
+    time: f32,
+    texture_count: u32,
+    output_resolution: vec2<u32>,
+    frame_index: u32,
+    random_seed: u32,
+}
+
+struct InputShaderParameters {
+    resolution: vec2<u32>,
+    pts_offset: f32,
+}
+struct ScopeParams {
+    // 0 - luma waveform, 1 - RGB parade, 2 - vectorscope
+    mode: u32,
+}
+
+var<push_constant> common_params: CommonShaderParameters;
+
+@group(0) @binding(0) var textures: binding_array<texture_2d<f32>, 16>;
+@group(0) @binding(1) var<uniform> inputs_params: array<InputShaderParameters, 16>;
+@group(1) @binding(0) var<uniform> scope_params: ScopeParams;
+@group(2) @binding(0) var sampler_: sampler;
+
+// Amount of input rows sampled for every column of a waveform.
+const WAVEFORM_SAMPLES: i32 = 128;
+// Input is sampled on a grid of VECTORSCOPE_SAMPLES x VECTORSCOPE_SAMPLES points.
+const VECTORSCOPE_SAMPLES: i32 = 32;
+const GRATICULE_COLOR: vec3<f32> = vec3<f32>(0.25, 0.25, 0.25);
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.position = vec4(input.position, 1.0);
+    output.tex_coords = input.tex_coords;
+
+    return output;
+}
+
+fn sample(tex_coords: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(textures[0], sampler_, tex_coords, 0.0);
+}
+
+// BT.709 luma and chroma
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+fn chroma(color: vec3<f32>) -> vec2<f32> {
+    let y = luma(color);
+    return vec2((color.b - y) / 1.8556, (color.r - y) / 1.5748);
+}
+
+// Returns 0 - luma, 1 - red, 2 - green, 3 - blue component.
+fn component(color: vec3<f32>, channel: u32) -> f32 {
+    switch channel {
+        case 1u: {
+            return color.r;
+        }
+        case 2u: {
+            return color.g;
+        }
+        case 3u: {
+            return color.b;
+        }
+        default: {
+            return luma(color);
+        }
+    }
+}
+
+// Fraction of the samples from input column `column` with component value
+// close to `level`.
+fn waveform(column: f32, level: f32, channel: u32) -> f32 {
+    let bin_size = 2.0 / f32(common_params.output_resolution.y);
+    var hits = 0;
+    for (var i = 0; i < WAVEFORM_SAMPLES; i++) {
+        let row = (f32(i) + 0.5) / f32(WAVEFORM_SAMPLES);
+        let value = component(sample(vec2(column, row)).rgb, channel);
+        if (abs(value - level) < bin_size) {
+            hits++;
+        }
+    }
+    return f32(hits) / f32(WAVEFORM_SAMPLES);
+}
+
+fn waveform_graticule(level: f32) -> bool {
+    let line_width = 1.0 / f32(common_params.output_resolution.y);
+    let distance_to_line = abs(fract(level * 4.0 + 0.5) - 0.5) / 4.0;
+    return distance_to_line < line_width;
+}
+
+fn render_waveform(tex_coords: vec2<f32>, channel: u32, trace_color: vec3<f32>) -> vec4<f32> {
+    let level = 1.0 - tex_coords.y;
+    let intensity = clamp(waveform(tex_coords.x, level, channel) * 20.0, 0.0, 1.0);
+    var background = vec3(0.0);
+    if (waveform_graticule(level)) {
+        background = GRATICULE_COLOR;
+    }
+    return vec4(mix(background, trace_color, intensity), 1.0);
+}
+
+fn render_rgb_parade(tex_coords: vec2<f32>) -> vec4<f32> {
+    let section = min(u32(tex_coords.x * 3.0), 2u);
+    let column = fract(tex_coords.x * 3.0);
+    var trace_color = vec3(0.0);
+    trace_color[section] = 1.0;
+    return render_waveform(vec2(column, tex_coords.y), section + 1u, trace_color);
+}
+
+// Chroma plane is scaled so the Cb/Cr range [-0.5, 0.5] fills the output.
+fn render_vectorscope(tex_coords: vec2<f32>) -> vec4<f32> {
+    let position = vec2(tex_coords.x - 0.5, 0.5 - tex_coords.y);
+    let radius = 1.5 / f32(min(common_params.output_resolution.x, common_params.output_resolution.y));
+
+    var hits = 0;
+    for (var x = 0; x < VECTORSCOPE_SAMPLES; x++) {
+        for (var y = 0; y < VECTORSCOPE_SAMPLES; y++) {
+            let input_coords = (vec2(f32(x), f32(y)) + 0.5) / f32(VECTORSCOPE_SAMPLES);
+            if (distance(chroma(sample(input_coords).rgb), position) < radius) {
+                hits++;
+            }
+        }
+    }
+    let samples_count = f32(VECTORSCOPE_SAMPLES * VECTORSCOPE_SAMPLES);
+    let intensity = clamp(f32(hits) / samples_count * 64.0, 0.0, 1.0);
+
+    var background = vec3(0.0);
+    let is_circle = abs(length(position) - 0.5) < radius;
+    let is_axis = min(abs(position.x), abs(position.y)) < radius * 0.5;
+    if (is_circle || is_axis) {
+        background = GRATICULE_COLOR;
+    }
+    return vec4(mix(background, vec3(1.0), intensity), 1.0);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    switch scope_params.mode {
+        case 1u: {
+            return render_rgb_parade(input.tex_coords);
+        }
+        case 2u: {
+            return render_vectorscope(input.tex_coords);
+        }
+        default: {
+            return render_waveform(input.tex_coords, 0u, vec3(0.4, 1.0, 0.4));
+        }
+    }
+}
@@ -0,0 +1,141 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) texture_id: i32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct CommonShaderParameters {
+    time: f32,
+    texture_count: u32,
+    output_resolution: vec2<u32>,
+    frame_index: u32,
+    random_seed: u32,
+}
+
+struct InputShaderParameters {
+    resolution: vec2<u32>,
+    pts_offset: f32,
+}
+
+struct StylizeParams {
+    // 0 - edge detect, 1 - posterize, 2 - vignette, 3 - film grain,
+    // 4 - chromatic aberration
+    effect: u32,
+    // levels, strength or offset in pixels depending on the effect
+    value: f32,
+}
+
+var<push_constant> common_params: CommonShaderParameters;
+
+@group(0) @binding(0) var textures: binding_array<texture_2d<f32>, 16>;
+@group(0) @binding(1) var<uniform> inputs_params: array<InputShaderParameters, 16>;
+@group(1) @binding(0) var<uniform> stylize_params: StylizeParams;
+@group(2) @binding(0) var sampler_: sampler;
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.position = vec4(input.position, 1.0);
+    output.tex_coords = input.tex_coords;
+
+    return output;
+}
+
+fn sample(tex_coords: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(textures[0], sampler_, tex_coords, 0.0);
+}
+
+fn luma(color: vec4<f32>) -> f32 {
+    return dot(color.rgb, vec3(0.299, 0.587, 0.114));
+}
+
+// Sobel operator on luma
+fn edge_detect(tex_coords: vec2<f32>, pixel: vec2<f32>) -> vec4<f32> {
+    let top_left = luma(sample(tex_coords + vec2(-1.0, -1.0) * pixel));
+    let top = luma(sample(tex_coords + vec2(0.0, -1.0) * pixel));
+    let top_right = luma(sample(tex_coords + vec2(1.0, -1.0) * pixel));
+    let left = luma(sample(tex_coords + vec2(-1.0, 0.0) * pixel));
+    let right = luma(sample(tex_coords + vec2(1.0, 0.0) * pixel));
+    let bottom_left = luma(sample(tex_coords + vec2(-1.0, 1.0) * pixel));
+    let bottom = luma(sample(tex_coords + vec2(0.0, 1.0) * pixel));
+    let bottom_right = luma(sample(tex_coords + vec2(1.0, 1.0) * pixel));
+
+    let horizontal = (top_right + 2.0 * right + bottom_right) - (top_left + 2.0 * left + bottom_left);
+    let vertical = (bottom_left + 2.0 * bottom + bottom_right) - (top_left + 2.0 * top + top_right);
+    let magnitude = clamp(length(vec2(horizontal, vertical)), 0.0, 1.0);
+
+    return vec4(vec3(magnitude), sample(tex_coords).a);
+}
+
+fn posterize(color: vec4<f32>) -> vec4<f32> {
+    let steps = max(stylize_params.value - 1.0, 1.0);
+    return vec4(round(color.rgb * steps) / steps, color.a);
+}
+
+fn vignette(color: vec4<f32>, tex_coords: vec2<f32>) -> vec4<f32> {
+    // 0 in the center, 1 in the corners
+    let distance_from_center = length(tex_coords - 0.5) / length(vec2(0.5));
+    let darkening = stylize_params.value * smoothstep(0.4, 1.0, distance_from_center);
+    return vec4(color.rgb * (1.0 - darkening), color.a);
+}
+
+fn hash(value: u32) -> u32 {
+    var state = value;
+    state ^= state >> 16u;
+    state *= 0x7feb352du;
+    state ^= state >> 15u;
+    state *= 0x846ca68bu;
+    state ^= state >> 16u;
+    return state;
+}
+
+// Noise is different for every frame and every instance of the shader.
+fn film_grain(color: vec4<f32>, position: vec2<f32>) -> vec4<f32> {
+    let pixel = vec2<u32>(position);
+    let seed = hash(common_params.random_seed ^ hash(common_params.frame_index));
+    let noise = f32(hash(seed ^ hash(pixel.x ^ hash(pixel.y)))) / 4294967295.0 - 0.5;
+    return vec4(clamp(color.rgb + noise * stylize_params.value, vec3(0.0), vec3(1.0)), color.a);
+}
+
+// Offset grows with the distance from the center and reaches `value` pixels
+// at the edges of the image.
+fn chromatic_aberration(tex_coords: vec2<f32>, pixel: vec2<f32>) -> vec4<f32> {
+    let offset = (tex_coords - 0.5) * 2.0 * stylize_params.value * pixel;
+    let center = sample(tex_coords);
+    let red = sample(tex_coords + offset).r;
+    let blue = sample(tex_coords - offset).b;
+    return vec4(red, center.g, blue, center.a);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let input_resolution = vec2<f32>(textureDimensions(textures[0]));
+    let pixel = 1.0 / input_resolution;
+
+    switch stylize_params.effect {
+        case 0u: {
+            return edge_detect(input.tex_coords, pixel);
+        }
+        case 1u: {
+            return posterize(sample(input.tex_coords));
+        }
+        case 2u: {
+            return vignette(sample(input.tex_coords), input.tex_coords);
+        }
+        case 3u: {
+            return film_grain(sample(input.tex_coords), input.tex_coords * input_resolution);
+        }
+        case 4u: {
+            return chromatic_aberration(input.tex_coords, pixel);
+        }
+        default: {
+            return sample(input.tex_coords);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use compositor_common::scene::builtin_transformations::BuiltinSpec;
+use compositor_common::scene::builtin_transformations::{BuiltinSpec, ScalingQuality};
 
 use crate::wgpu::{
     shader::{CreateShaderError, WgpuShader},
@@ -13,6 +13,10 @@ pub struct BuiltinTransformations {
     apply_matrix: ApplyTransformationMatrix,
     mirror_image: MirrorImage,
     corners_rounding: CornersRounding,
+    privacy_mask: PrivacyMask,
+    stylize: Stylize,
+    scope: Scope,
+    border: Border,
 }
 
 impl BuiltinTransformations {
@@ -23,35 +27,76 @@ impl BuiltinTransformations {
             mirror_image: MirrorImage::new(wgpu_ctx).map_err(InitBuiltinError::MirrorImage)?,
             corners_rounding: CornersRounding::new(wgpu_ctx)
                 .map_err(InitBuiltinError::CornersRounding)?,
+            privacy_mask: PrivacyMask::new(wgpu_ctx).map_err(InitBuiltinError::PrivacyMask)?,
+            stylize: Stylize::new(wgpu_ctx).map_err(InitBuiltinError::Stylize)?,
+            scope: Scope::new(wgpu_ctx).map_err(InitBuiltinError::Scope)?,
+            border: Border::new(wgpu_ctx).map_err(InitBuiltinError::Border)?,
         })
     }
 
     pub fn gpu_shader(&self, state: &BuiltinState) -> Arc<WgpuShader> {
         match state {
             BuiltinState::Interpolated { transition, .. } => match transition {
-                BuiltinTransition::FixedPositionLayout(_, _) => self.apply_matrix.0.clone(),
+                BuiltinTransition::FixedPositionLayout(_, _)
+                | BuiltinTransition::Transform2d(_, _)
+                | BuiltinTransition::Perspective(_, _) => {
+                    self.apply_matrix.shader(ScalingQuality::Bilinear)
+                }
             },
             BuiltinState::Static(spec) => match spec {
                 BuiltinSpec::FitToResolution(_)
                 | BuiltinSpec::FillToResolution { .. }
                 | BuiltinSpec::StretchToResolution { .. }
                 | BuiltinSpec::FixedPositionLayout { .. }
-                | BuiltinSpec::TiledLayout { .. } => self.apply_matrix.0.clone(),
+                | BuiltinSpec::TiledLayout { .. }
+                | BuiltinSpec::Transform2d(_)
+                | BuiltinSpec::Perspective(_) => self.apply_matrix.shader(spec.scaling_quality()),
                 BuiltinSpec::MirrorImage { .. } => self.mirror_image.0.clone(),
                 BuiltinSpec::CornersRounding { .. } => self.corners_rounding.0.clone(),
+                BuiltinSpec::PrivacyMask(_) => self.privacy_mask.0.clone(),
+                BuiltinSpec::Stylize { .. } => self.stylize.0.clone(),
+                BuiltinSpec::Scope { .. } => self.scope.0.clone(),
+                BuiltinSpec::Border(_) => self.border.0.clone(),
             },
         }
     }
 }
 
-pub struct ApplyTransformationMatrix(Arc<WgpuShader>);
+/// Variants of the shader differ only in the function that samples inputs.
+/// Matrices can flip or rotate inputs in 3D, so back faces are not culled.
+pub struct ApplyTransformationMatrix {
+    nearest: Arc<WgpuShader>,
+    bilinear: Arc<WgpuShader>,
+    bicubic: Arc<WgpuShader>,
+    lanczos: Arc<WgpuShader>,
+}
 
 impl ApplyTransformationMatrix {
     fn new(wgpu_ctx: &Arc<WgpuCtx>) -> Result<Self, CreateShaderError> {
-        Ok(Self(Arc::new(WgpuShader::new(
-            wgpu_ctx,
-            include_str!("./apply_transformation_matrix.wgsl").into(),
-        )?)))
+        let variant = |sampling: &[&str]| -> Result<_, CreateShaderError> {
+            let mut source = include_str!("./apply_transformation_matrix.wgsl").to_string();
+            for part in sampling {
+                source.push('\n');
+                source.push_str(part);
+            }
+            Ok(Arc::new(WgpuShader::new_double_sided(wgpu_ctx, source)?))
+        };
+        let kernel = include_str!("./scaling_kernel.wgsl");
+        Ok(Self {
+            nearest: variant(&[include_str!("./scaling_nearest.wgsl")])?,
+            bilinear: variant(&[include_str!("./scaling_bilinear.wgsl")])?,
+            bicubic: variant(&[kernel, include_str!("./scaling_bicubic.wgsl")])?,
+            lanczos: variant(&[kernel, include_str!("./scaling_lanczos.wgsl")])?,
+        })
+    }
+
+    fn shader(&self, quality: ScalingQuality) -> Arc<WgpuShader> {
+        match quality {
+            ScalingQuality::Nearest => self.nearest.clone(),
+            ScalingQuality::Bilinear => self.bilinear.clone(),
+            ScalingQuality::Bicubic => self.bicubic.clone(),
+            ScalingQuality::Lanczos => self.lanczos.clone(),
+        }
     }
 }
 
@@ -76,3 +121,47 @@ impl CornersRounding {
         )?)))
     }
 }
+
+pub struct PrivacyMask(Arc<WgpuShader>);
+
+impl PrivacyMask {
+    fn new(wgpu_ctx: &Arc<WgpuCtx>) -> Result<Self, CreateShaderError> {
+        Ok(Self(Arc::new(WgpuShader::new(
+            wgpu_ctx,
+            include_str!("./privacy_mask.wgsl").into(),
+        )?)))
+    }
+}
+
+pub struct Stylize(Arc<WgpuShader>);
+
+impl Stylize {
+    fn new(wgpu_ctx: &Arc<WgpuCtx>) -> Result<Self, CreateShaderError> {
+        Ok(Self(Arc::new(WgpuShader::new(
+            wgpu_ctx,
+            include_str!("./stylize.wgsl").into(),
+        )?)))
+    }
+}
+
+pub struct Scope(Arc<WgpuShader>);
+
+impl Scope {
+    fn new(wgpu_ctx: &Arc<WgpuCtx>) -> Result<Self, CreateShaderError> {
+        Ok(Self(Arc::new(WgpuShader::new(
+            wgpu_ctx,
+            include_str!("./scope.wgsl").into(),
+        )?)))
+    }
+}
+
+pub struct Border(Arc<WgpuShader>);
+
+impl Border {
+    fn new(wgpu_ctx: &Arc<WgpuCtx>) -> Result<Self, CreateShaderError> {
+        Ok(Self(Arc::new(WgpuShader::new(
+            wgpu_ctx,
+            include_str!("./border.wgsl").into(),
+        )?)))
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    fs,
+    sync::{Arc, Mutex, Weak},
+    thread,
+    time::Duration,
+};
+
+use compositor_common::{
+    scene::{
+        captions::{CaptionsPosition, CaptionsSource, CaptionsSpec},
+        Resolution,
+    },
+    util::colors::RGBAColor,
+};
+use glyphon::{
+    cosmic_text::{Align, Wrap},
+    Attrs, AttrsOwned, Buffer, Color, Family, Metrics, Shaping, TextArea, TextBounds,
+};
+use log::{debug, warn};
+
+use crate::{renderer::RenderCtx, wgpu::texture::NodeTexture};
+
+use self::parser::Cue;
+
+use super::text_renderer::draw_text;
+
+mod parser;
+
+pub use parser::ParseCaptionsError;
+
+pub struct CaptionsNode {
+    cues: Cues,
+    resolution: Resolution,
+    attributes: AttrsOwned,
+    metrics: Metrics,
+    align: Align,
+    position: CaptionsPosition,
+    safe_area_margin: u32,
+    offset: Duration,
+    /// Text drawn on the node texture, `None` before the first render.
+    displayed_text: Option<String>,
+}
+
+enum Cues {
+    Static(Vec<Cue>),
+    /// Replaced by the download thread on every refresh.
+    Live(Arc<Mutex<Vec<Cue>>>),
+}
+
+impl CaptionsNode {
+    pub fn new(spec: &CaptionsSpec) -> Result<Self, CaptionsError> {
+        let cues = match &spec.source {
+            CaptionsSource::File { path } => {
+                Cues::Static(parser::parse(&fs::read_to_string(path)?)?)
+            }
+            CaptionsSource::Url {
+                url,
+                refresh_interval,
+            } => Cues::Live(spawn_download_thread(url.clone(), *refresh_interval)),
+        };
+
+        let RGBAColor(r, g, b, a) = spec.color_rgba;
+        let attributes = Attrs::new()
+            .color(Color::rgba(r, g, b, a))
+            .family(Family::Name(&spec.font_family))
+            .weight((&spec.weight).into());
+
+        Ok(Self {
+            cues,
+            resolution: spec.resolution,
+            attributes: AttrsOwned::new(attributes),
+            metrics: Metrics::new(spec.font_size, spec.line_height.unwrap_or(spec.font_size)),
+            align: spec.align.into(),
+            position: spec.position,
+            safe_area_margin: spec.safe_area_margin,
+            offset: spec.offset,
+            displayed_text: None,
+        })
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    pub fn render(&mut self, ctx: &RenderCtx, target: &mut NodeTexture, pts: Duration) {
+        let text = match pts.checked_sub(self.offset) {
+            Some(pts) => match &self.cues {
+                Cues::Static(cues) => parser::active_text(cues, pts),
+                Cues::Live(cues) => parser::active_text(&cues.lock().unwrap(), pts),
+            },
+            None => String::new(),
+        };
+        if self.displayed_text.as_ref() == Some(&text) {
+            return;
+        }
+
+        let margin = self.safe_area_margin as f32;
+        let width = (self.resolution.width as f32 - 2.0 * margin).max(0.0);
+        let height = (self.resolution.height as f32 - 2.0 * margin).max(0.0);
+
+        let buffer = {
+            let font_system = &mut ctx.text_renderer_ctx.font_system();
+            let mut buffer = Buffer::new(font_system, self.metrics);
+            buffer.set_size(font_system, width, height);
+            buffer.set_wrap(font_system, Wrap::Word);
+            buffer.set_text(
+                font_system,
+                &text,
+                self.attributes.as_attrs(),
+                Shaping::Advanced,
+            );
+            for line in &mut buffer.lines {
+                line.set_align(Some(self.align));
+            }
+            buffer.shape_until_scroll(font_system);
+            buffer
+        };
+
+        let text_height = buffer.layout_runs().count() as f32 * self.metrics.line_height;
+        let top = match self.position {
+            CaptionsPosition::Top => margin,
+            CaptionsPosition::Bottom => margin + (height - text_height).max(0.0),
+        };
+        let text_area = TextArea {
+            buffer: &buffer,
+            left: margin,
+            top,
+            scale: 1.0,
+            bounds: TextBounds {
+                left: margin as i32,
+                top: margin as i32,
+                right: (margin + width) as i32,
+                bottom: (margin + height) as i32,
+            },
+            default_color: Color::rgb(255, 255, 255),
+        };
+        draw_text(
+            ctx,
+            target,
+            self.resolution,
+            text_area,
+            wgpu::Color::TRANSPARENT,
+        );
+        self.displayed_text = Some(text);
+    }
+}
+
+/// Downloads the document every `refresh_interval` until the node is dropped.
+/// Cues are empty until the first download succeeds, so a slow server does not
+/// block the scene update.
+fn spawn_download_thread(url: String, refresh_interval: Duration) -> Arc<Mutex<Vec<Cue>>> {
+    let cues = Arc::new(Mutex::new(Vec::new()));
+    let weak_cues: Weak<Mutex<Vec<Cue>>> = Arc::downgrade(&cues);
+
+    thread::spawn(move || loop {
+        let downloaded = download_cues(&url);
+        let Some(cues) = weak_cues.upgrade() else {
+            debug!("Captions node dropped, stopping downloads from {url}.");
+            return;
+        };
+        match downloaded {
+            Ok(downloaded) => *cues.lock().unwrap() = downloaded,
+            Err(err) => warn!("Failed to refresh captions from {url}: {err}"),
+        }
+        drop(cues);
+        thread::sleep(refresh_interval);
+    });
+
+    cues
+}
+
+fn download_cues(url: &str) -> Result<Vec<Cue>, CaptionsError> {
+    let response = reqwest::blocking::get(url)?.error_for_status()?;
+    Ok(parser::parse(&response.text()?)?)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CaptionsError {
+    #[error("Failed to read captions file.")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to download captions.")]
+    Download(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Parse(#[from] ParseCaptionsError),
+}
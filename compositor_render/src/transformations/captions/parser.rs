@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+/// Text displayed between `start` and `end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cue {
+    pub start: Duration,
+    pub end: Duration,
+    pub text: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParseCaptionsError {
+    #[error("Invalid cue timestamp \"{0}\" in line {1}.")]
+    InvalidTimestamp(String, usize),
+}
+
+/// Parses SRT and WebVTT documents. Both formats describe cues as a
+/// `<start> --> <end>` line followed by lines of text, so they are handled
+/// by the same parser. Blocks without timings (WebVTT header, `NOTE`, `STYLE`
+/// and `REGION` blocks) are skipped. Cues are sorted by start time.
+pub fn parse(content: &str) -> Result<Vec<Cue>, ParseCaptionsError> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut cues = Vec::new();
+    let mut lines = content.lines().enumerate().peekable();
+
+    while let Some((index, line)) = lines.next() {
+        let Some((start, end)) = line.split_once("-->") else {
+            continue;
+        };
+        let start = parse_timestamp(start.trim(), index + 1)?;
+        // WebVTT cue settings can follow the end timestamp.
+        let end = end.split_whitespace().next().unwrap_or_default();
+        let end = parse_timestamp(end, index + 1)?;
+
+        let mut text_lines = Vec::new();
+        while let Some((_, line)) = lines.next_if(|(_, line)| !line.trim().is_empty()) {
+            text_lines.push(strip_markup(line.trim()));
+        }
+        cues.push(Cue {
+            start,
+            end,
+            text: text_lines.join("\n"),
+        });
+    }
+
+    cues.sort_by_key(|cue| cue.start);
+    Ok(cues)
+}
+
+/// Text of cues displayed at `pts`, one cue per line.
+pub fn active_text(cues: &[Cue], pts: Duration) -> String {
+    cues.iter()
+        .take_while(|cue| cue.start <= pts)
+        .filter(|cue| pts < cue.end)
+        .map(|cue| cue.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Accepts `hh:mm:ss,mmm` (SRT), `hh:mm:ss.mmm` and `mm:ss.mmm` (WebVTT).
+fn parse_timestamp(timestamp: &str, line: usize) -> Result<Duration, ParseCaptionsError> {
+    let invalid = || ParseCaptionsError::InvalidTimestamp(timestamp.to_string(), line);
+
+    let (time, millis) = timestamp.split_once([',', '.']).ok_or_else(invalid)?;
+    let millis: u64 = match millis.len() {
+        3 => millis.parse().map_err(|_| invalid())?,
+        _ => return Err(invalid()),
+    };
+
+    let parts = time
+        .split(':')
+        .map(|part| part.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let seconds = match parts[..] {
+        [hours, minutes, seconds] => hours * 3600 + minutes * 60 + seconds,
+        [minutes, seconds] => minutes * 60 + seconds,
+        _ => return Err(invalid()),
+    };
+
+    Ok(Duration::from_millis(seconds * 1000 + millis))
+}
+
+/// Removes formatting tags (`<b>`, `<i>`, `<c.class>`, `<v Speaker>`, ...)
+/// and decodes basic character references, styling comes from the node.
+fn strip_markup(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for char in line.chars() {
+        match char {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(char),
+            _ => {}
+        }
+    }
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(start_ms: u64, end_ms: u64, text: &str) -> Cue {
+        Cue {
+            start: Duration::from_millis(start_ms),
+            end: Duration::from_millis(end_ms),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_srt() {
+        let content = "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\n<i>world</i>\r\n\r\n2\r\n01:00:00,000 --> 01:00:01,000\r\nBye\r\n";
+
+        assert_eq!(
+            parse(content).unwrap(),
+            vec![
+                cue(1000, 2500, "Hello\nworld"),
+                cue(3_600_000, 3_601_000, "Bye")
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_webvtt() {
+        let content = "WEBVTT\n\nNOTE comment\n\nintro\n00:05.000 --> 00:06.000 align:start line:0\n<v Speaker>Tom &amp; Jerry\n\n00:01.000 --> 00:02.000\nFirst\n";
+
+        assert_eq!(
+            parse(content).unwrap(),
+            vec![cue(1000, 2000, "First"), cue(5000, 6000, "Tom & Jerry")]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_timestamp() {
+        let content = "1\n00:00:01 --> 00:00:02,000\nHello\n";
+
+        assert!(matches!(
+            parse(content),
+            Err(ParseCaptionsError::InvalidTimestamp(timestamp, 2)) if timestamp == "00:00:01"
+        ));
+    }
+
+    #[test]
+    fn overlapping_cues_are_joined() {
+        let cues = vec![cue(0, 2000, "First"), cue(1000, 3000, "Second")];
+
+        assert_eq!(active_text(&cues, Duration::from_millis(500)), "First");
+        assert_eq!(
+            active_text(&cues, Duration::from_millis(1500)),
+            "First\nSecond"
+        );
+        assert_eq!(active_text(&cues, Duration::from_millis(2000)), "Second");
+        assert_eq!(active_text(&cues, Duration::from_millis(3000)), "");
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fs, io,
     str::{from_utf8, Utf8Error},
     sync::{Arc, Mutex},
@@ -8,10 +9,13 @@ use std::{
 use bytes::{Bytes, BytesMut};
 
 use compositor_common::{
-    renderer_spec::{ImageSpec, ImageSrc, ImageType},
+    renderer_spec::{AnimationOptions, ImageSpec, ImageSrc, ImageType, NineSliceInsets},
     scene::Resolution,
 };
-use image::{codecs::gif::GifDecoder, AnimationDecoder, ImageFormat};
+use image::{
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    AnimationDecoder, ImageFormat,
+};
 use resvg::{
     tiny_skia,
     usvg::{self, TreeParsing},
@@ -35,34 +39,106 @@ pub enum Image {
 impl Image {
     pub fn new(ctx: &RegisterCtx, spec: ImageSpec) -> Result<Self, ImageError> {
         let file = Self::download_file(&spec.src)?;
+        let nine_slice = spec.nine_slice;
         let renderer = match spec.image_type {
-            ImageType::Png => {
-                let asset = BitmapAsset::new(&ctx.wgpu_ctx, file, ImageFormat::Png)?;
-                Image::Bitmap(Arc::new(asset))
+            ImageType::Png { animation } => {
+                Self::new_animated_or_bitmap(ctx, file, ImageFormat::Png, animation, nine_slice)?
             }
             ImageType::Jpeg => {
-                let asset = BitmapAsset::new(&ctx.wgpu_ctx, file, ImageFormat::Jpeg)?;
+                let asset = BitmapAsset::new(&ctx.wgpu_ctx, file, ImageFormat::Jpeg, nine_slice)?;
                 Image::Bitmap(Arc::new(asset))
             }
             ImageType::Svg { resolution } => {
-                let asset = SvgAsset::new(&ctx.wgpu_ctx, file, resolution)?;
+                if nine_slice.is_some() {
+                    return Err(ImageError::NineSliceNotSupported);
+                }
+                let asset = SvgAsset::new(file, resolution)?;
                 Image::Svg(Arc::new(asset))
             }
-            ImageType::Gif => {
-                let asset = AnimatedAsset::new(&ctx.wgpu_ctx, file.clone(), ImageFormat::Gif);
-                match asset {
-                    Ok(asset) => Image::Animated(Arc::new(asset)),
-                    Err(AnimatedError::SingleFrame) => {
-                        let asset = BitmapAsset::new(&ctx.wgpu_ctx, file, ImageFormat::Gif)?;
-                        Image::Bitmap(Arc::new(asset))
-                    }
-                    Err(err) => return Err(ImageError::from(err)),
-                }
+            ImageType::Gif { animation } => {
+                Self::new_animated_or_bitmap(ctx, file, ImageFormat::Gif, animation, nine_slice)?
+            }
+            ImageType::Webp { animation } => {
+                Self::new_animated_or_bitmap(ctx, file, ImageFormat::WebP, animation, nine_slice)?
             }
         };
         Ok(renderer)
     }
 
+    /// Static image with the content of the texture, e.g. a frame grabbed from
+    /// the scene.
+    pub(crate) fn from_texture(texture: RGBATexture) -> Self {
+        Image::Bitmap(Arc::new(BitmapAsset {
+            texture,
+            nine_slice: None,
+        }))
+    }
+
+    fn new_animated_or_bitmap(
+        ctx: &RegisterCtx,
+        file: Bytes,
+        format: ImageFormat,
+        options: AnimationOptions,
+        nine_slice: Option<NineSliceInsets>,
+    ) -> Result<Self, ImageError> {
+        let asset = AnimatedAsset::new(&ctx.wgpu_ctx, file.clone(), format, options, nine_slice);
+        match asset {
+            Ok(asset) => Ok(Image::Animated(Arc::new(asset))),
+            Err(AnimatedError::SingleFrame) => {
+                let asset = BitmapAsset::new(&ctx.wgpu_ctx, file, format, nine_slice)?;
+                Ok(Image::Bitmap(Arc::new(asset)))
+            }
+            Err(err) => Err(ImageError::from(err)),
+        }
+    }
+
+    /// Uploads the image to the GPU ahead of its first use and keeps it there until the
+    /// image is unregistered. Bitmaps and animations are uploaded on registration, so this
+    /// only rasterizes SVGs (`None` stands for the default resolution).
+    pub fn preload(
+        &self,
+        ctx: &WgpuCtx,
+        resolutions: &[Option<Resolution>],
+    ) -> Result<(), SvgError> {
+        if let Image::Svg(asset) = self {
+            for resolution in resolutions {
+                asset.pin(ctx, *resolution)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Texture bound to shader nodes that use the image. Animated images are bound
+    /// with their first frame and SVGs are rasterized in the default resolution.
+    pub(crate) fn bind(&self, ctx: &WgpuCtx) -> Result<BoundImage, SvgError> {
+        let bound = match self {
+            Image::Bitmap(asset) => BoundImage::Bitmap(asset.clone()),
+            Image::Animated(asset) => BoundImage::Animated(asset.clone()),
+            Image::Svg(asset) => BoundImage::Svg(asset.texture(ctx, None)?),
+        };
+        Ok(bound)
+    }
+
+    pub fn residency(&self) -> ImageResidency {
+        match self {
+            Image::Bitmap(asset) => ImageResidency {
+                resident_resolutions: vec![asset.resolution()],
+                pinned_resolutions: vec![asset.resolution()],
+                gpu_memory_bytes: texture_size_bytes(&asset.texture),
+            },
+            Image::Animated(asset) => ImageResidency {
+                resident_resolutions: vec![asset.resolution()],
+                pinned_resolutions: vec![asset.resolution()],
+                gpu_memory_bytes: asset
+                    .frames
+                    .iter()
+                    .map(|frame| texture_size_bytes(&frame.texture))
+                    .sum(),
+            },
+            Image::Svg(asset) => asset.residency(),
+        }
+    }
+
     fn download_file(src: &ImageSrc) -> Result<bytes::Bytes, ImageError> {
         match src {
             ImageSrc::Url { url } => {
@@ -78,25 +154,61 @@ impl Image {
     }
 }
 
+/// Keeps the texture of an image alive while it is bound to a shader node.
+pub(crate) enum BoundImage {
+    Bitmap(Arc<BitmapAsset>),
+    Animated(Arc<AnimatedAsset>),
+    Svg(Arc<RGBATexture>),
+}
+
+impl BoundImage {
+    pub fn view(&self) -> &wgpu::TextureView {
+        let texture = match self {
+            BoundImage::Bitmap(asset) => &asset.texture,
+            BoundImage::Animated(asset) => &asset.frames[0].texture,
+            BoundImage::Svg(texture) => texture,
+        };
+        &texture.texture().sampled_view
+    }
+}
+
+/// Textures of an image that are currently stored on the GPU.
+#[derive(Debug, Clone)]
+pub struct ImageResidency {
+    pub resident_resolutions: Vec<Resolution>,
+    /// Resolutions that stay on the GPU until the image is unregistered.
+    pub pinned_resolutions: Vec<Resolution>,
+    pub gpu_memory_bytes: usize,
+}
+
 pub enum ImageNode {
     Bitmap {
         asset: Arc<BitmapAsset>,
         state: Mutex<BitmapNodeState>,
+        draw_mode: DrawMode,
     },
     Animated {
         asset: Arc<AnimatedAsset>,
         state: Mutex<AnimatedNodeState>,
+        draw_mode: DrawMode,
     },
     Svg {
-        asset: Arc<SvgAsset>,
+        texture: Arc<RGBATexture>,
         state: Mutex<SvgNodeState>,
     },
 }
 
 impl ImageNode {
-    pub fn new(image: Image) -> Self {
-        match image {
+    /// `resolution` is used to rasterize SVG images and as the output size of nine-slice
+    /// images. Other images are always rendered in their original resolution.
+    pub fn new(
+        ctx: &WgpuCtx,
+        image: Image,
+        resolution: Option<Resolution>,
+    ) -> Result<Self, SvgError> {
+        let node = match image {
             Image::Bitmap(asset) => Self::Bitmap {
+                draw_mode: DrawMode::new(asset.nine_slice, resolution),
                 asset,
                 state: BitmapNodeState {
                     was_rendered: false,
@@ -104,32 +216,50 @@ impl ImageNode {
                 .into(),
             },
             Image::Animated(asset) => Self::Animated {
+                draw_mode: DrawMode::new(asset.nine_slice, resolution),
                 asset,
                 state: AnimatedNodeState { first_pts: None }.into(),
             },
             Image::Svg(asset) => Self::Svg {
-                asset,
+                texture: asset.texture(ctx, resolution)?,
                 state: SvgNodeState {
                     was_rendered: false,
                 }
                 .into(),
             },
-        }
+        };
+        Ok(node)
+    }
+
+    pub fn is_animated(&self) -> bool {
+        matches!(self, ImageNode::Animated { .. })
     }
 
     pub fn render(&self, ctx: &mut RenderCtx, target: &mut NodeTexture, pts: Duration) {
         match self {
-            ImageNode::Bitmap { asset, state } => asset.render(ctx.wgpu_ctx, target, state),
-            ImageNode::Animated { asset, state } => asset.render(ctx.wgpu_ctx, target, state, pts),
-            ImageNode::Svg { asset, state } => asset.render(ctx.wgpu_ctx, target, state),
+            ImageNode::Bitmap {
+                asset,
+                state,
+                draw_mode,
+            } => asset.render(ctx.wgpu_ctx, target, state, *draw_mode),
+            ImageNode::Animated {
+                asset,
+                state,
+                draw_mode,
+            } => asset.render(ctx.wgpu_ctx, target, state, *draw_mode, pts),
+            ImageNode::Svg { texture, state } => render_svg(ctx.wgpu_ctx, texture, target, state),
         }
     }
 
     pub fn resolution(&self) -> Resolution {
         match self {
-            ImageNode::Bitmap { asset, .. } => asset.resolution(),
-            ImageNode::Animated { asset, .. } => asset.resolution(),
-            ImageNode::Svg { asset, .. } => asset.resolution(),
+            ImageNode::Bitmap {
+                asset, draw_mode, ..
+            } => draw_mode.resolution().unwrap_or_else(|| asset.resolution()),
+            ImageNode::Animated {
+                asset, draw_mode, ..
+            } => draw_mode.resolution().unwrap_or_else(|| asset.resolution()),
+            ImageNode::Svg { texture, .. } => texture_resolution(texture),
         }
     }
 }
@@ -140,31 +270,48 @@ pub struct BitmapNodeState {
 
 pub struct BitmapAsset {
     texture: RGBATexture,
+    nine_slice: Option<NineSliceInsets>,
 }
 
 impl BitmapAsset {
-    fn new(ctx: &WgpuCtx, data: Bytes, format: ImageFormat) -> Result<Self, image::ImageError> {
+    fn new(
+        ctx: &WgpuCtx,
+        data: Bytes,
+        format: ImageFormat,
+        nine_slice: Option<NineSliceInsets>,
+    ) -> Result<Self, ImageError> {
         let img = image::load_from_memory_with_format(&data, format)?;
-        let texture = RGBATexture::new(
-            ctx,
-            Resolution {
-                width: img.width() as usize,
-                height: img.height() as usize,
-            },
-        );
+        let resolution = Resolution {
+            width: img.width() as usize,
+            height: img.height() as usize,
+        };
+        if let Some(insets) = nine_slice {
+            validate_nine_slice(insets, resolution)?;
+        }
+
+        let texture = RGBATexture::new(ctx, resolution);
         texture.upload(ctx, &img.to_rgba8());
         ctx.queue.submit([]);
 
-        Ok(Self { texture })
+        Ok(Self {
+            texture,
+            nine_slice,
+        })
     }
 
-    fn render(&self, ctx: &WgpuCtx, target: &mut NodeTexture, state: &Mutex<BitmapNodeState>) {
+    fn render(
+        &self,
+        ctx: &WgpuCtx,
+        target: &mut NodeTexture,
+        state: &Mutex<BitmapNodeState>,
+        draw_mode: DrawMode,
+    ) {
         let mut state = state.lock().unwrap();
         if state.was_rendered {
             return;
         }
 
-        copy_texture_to_node_texture(ctx, &self.texture, target);
+        draw_texture(ctx, &self.texture, target, draw_mode);
         state.was_rendered = true;
     }
 
@@ -182,41 +329,104 @@ pub struct SvgNodeState {
 }
 
 pub struct SvgAsset {
-    texture: RGBATexture,
+    data: Bytes,
+    /// Resolution used by nodes that do not specify their own. Either resolution
+    /// provided on registration or the intrinsic size of the SVG.
+    default_resolution: Resolution,
+    /// Texture rasterized for the most recently requested resolution. Nodes are recreated
+    /// on every scene update, so this avoids rasterizing again if resolution didn't change.
+    last_texture: Mutex<Option<Arc<RGBATexture>>>,
+    /// Textures rasterized ahead of time with [`Image::preload`].
+    pinned_textures: Mutex<HashMap<Resolution, Arc<RGBATexture>>>,
 }
 
 impl SvgAsset {
-    fn new(
-        ctx: &WgpuCtx,
-        data: Bytes,
-        maybe_resolution: Option<Resolution>,
-    ) -> Result<Self, SvgError> {
-        let text_svg = from_utf8(&data)?;
-        let tree = usvg::Tree::from_str(text_svg, &Default::default())?;
-        let tree = resvg::Tree::from_usvg(&tree);
-        let resolution = maybe_resolution.unwrap_or_else(|| Resolution {
+    fn new(data: Bytes, maybe_resolution: Option<Resolution>) -> Result<Self, SvgError> {
+        let tree = parse_svg(&data)?;
+        let default_resolution = maybe_resolution.unwrap_or_else(|| Resolution {
             width: tree.size.width() as usize,
             height: tree.size.height() as usize,
         });
 
+        Ok(Self {
+            data,
+            default_resolution,
+            last_texture: Mutex::new(None),
+            pinned_textures: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns SVG rasterized at the `resolution` (or default resolution if not specified).
+    fn texture(
+        &self,
+        ctx: &WgpuCtx,
+        resolution: Option<Resolution>,
+    ) -> Result<Arc<RGBATexture>, SvgError> {
+        let resolution = resolution.unwrap_or(self.default_resolution);
+        if let Some(texture) = self.pinned_textures.lock().unwrap().get(&resolution) {
+            return Ok(texture.clone());
+        }
+        let mut last_texture = self.last_texture.lock().unwrap();
+        if let Some(texture) = last_texture.as_ref() {
+            if texture_resolution(texture) == resolution {
+                return Ok(texture.clone());
+            }
+        }
+
+        let texture = Arc::new(self.rasterize(ctx, resolution)?);
+        *last_texture = Some(texture.clone());
+        Ok(texture)
+    }
+
+    fn pin(&self, ctx: &WgpuCtx, resolution: Option<Resolution>) -> Result<(), SvgError> {
+        let resolution = resolution.unwrap_or(self.default_resolution);
+        if self
+            .pinned_textures
+            .lock()
+            .unwrap()
+            .contains_key(&resolution)
+        {
+            return Ok(());
+        }
+        let texture = self.texture(ctx, Some(resolution))?;
+        self.pinned_textures
+            .lock()
+            .unwrap()
+            .insert(resolution, texture);
+        Ok(())
+    }
+
+    fn residency(&self) -> ImageResidency {
+        let pinned_textures = self.pinned_textures.lock().unwrap();
+        let mut textures: Vec<_> = pinned_textures.values().cloned().collect();
+        if let Some(last_texture) = self.last_texture.lock().unwrap().as_ref() {
+            if !textures.iter().any(|t| Arc::ptr_eq(t, last_texture)) {
+                textures.push(last_texture.clone());
+            }
+        }
+        ImageResidency {
+            resident_resolutions: textures.iter().map(|t| texture_resolution(t)).collect(),
+            pinned_resolutions: pinned_textures.keys().copied().collect(),
+            gpu_memory_bytes: textures.iter().map(|t| texture_size_bytes(t)).sum(),
+        }
+    }
+
+    fn rasterize(&self, ctx: &WgpuCtx, resolution: Resolution) -> Result<RGBATexture, SvgError> {
+        let tree = resvg::Tree::from_usvg(&parse_svg(&self.data)?);
+
         let mut buffer = BytesMut::zeroed(resolution.width * resolution.height * 4);
         let mut pixmap = tiny_skia::PixmapMut::from_bytes(
             &mut buffer,
             resolution.width as u32,
             resolution.height as u32,
         )
-        .unwrap();
-
-        let transform = match maybe_resolution {
-            Some(_) => {
-                let scale_multiplier = f32::min(
-                    resolution.width as f32 / tree.size.width(),
-                    resolution.height as f32 / tree.size.height(),
-                );
-                tiny_skia::Transform::from_scale(scale_multiplier, scale_multiplier)
-            }
-            None => tiny_skia::Transform::default(),
-        };
+        .ok_or(SvgError::InvalidResolution(resolution))?;
+
+        let scale_multiplier = f32::min(
+            resolution.width as f32 / tree.size.width(),
+            resolution.height as f32 / tree.size.height(),
+        );
+        let transform = tiny_skia::Transform::from_scale(scale_multiplier, scale_multiplier);
 
         tree.render(transform, &mut pixmap);
 
@@ -224,25 +434,40 @@ impl SvgAsset {
         texture.upload(ctx, pixmap.data_mut());
         ctx.queue.submit([]);
 
-        Ok(Self { texture })
+        Ok(texture)
     }
+}
 
-    fn render(&self, ctx: &WgpuCtx, target: &mut NodeTexture, state: &Mutex<SvgNodeState>) {
-        let mut state = state.lock().unwrap();
-        if state.was_rendered {
-            return;
-        }
+fn parse_svg(data: &[u8]) -> Result<usvg::Tree, SvgError> {
+    let text_svg = from_utf8(data)?;
+    Ok(usvg::Tree::from_str(text_svg, &Default::default())?)
+}
 
-        copy_texture_to_node_texture(ctx, &self.texture, target);
-        state.was_rendered = true;
+fn render_svg(
+    ctx: &WgpuCtx,
+    texture: &RGBATexture,
+    target: &mut NodeTexture,
+    state: &Mutex<SvgNodeState>,
+) {
+    let mut state = state.lock().unwrap();
+    if state.was_rendered {
+        return;
     }
 
-    fn resolution(&self) -> Resolution {
-        let size = self.texture.size();
-        Resolution {
-            width: size.width as usize,
-            height: size.height as usize,
-        }
+    copy_texture_to_node_texture(ctx, texture, target);
+    state.was_rendered = true;
+}
+
+fn texture_size_bytes(texture: &RGBATexture) -> usize {
+    let size = texture.size();
+    size.width as usize * size.height as usize * 4
+}
+
+fn texture_resolution(texture: &RGBATexture) -> Resolution {
+    let size = texture.size();
+    Resolution {
+        width: size.width as usize,
+        height: size.height as usize,
     }
 }
 
@@ -253,18 +478,46 @@ pub struct AnimatedNodeState {
 pub struct AnimatedAsset {
     frames: Vec<AnimationFrame>,
     animation_duration: Duration,
+    options: AnimationOptions,
+    nine_slice: Option<NineSliceInsets>,
 }
 
 struct AnimationFrame {
     texture: RGBATexture,
+    /// Time since the start of the animation when this frame should be displayed.
     pts: Duration,
 }
 
+/// Browsers play GIF frames with a delay of 10ms or lower using 100ms delay instead.
+/// A lot of GIFs in the wild rely on that behavior.
+const GIF_MIN_FRAME_DELAY: Duration = Duration::from_millis(10);
+const GIF_DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
 impl AnimatedAsset {
-    fn new(ctx: &WgpuCtx, data: Bytes, format: ImageFormat) -> Result<Self, AnimatedError> {
+    fn new(
+        ctx: &WgpuCtx,
+        data: Bytes,
+        format: ImageFormat,
+        options: AnimationOptions,
+        nine_slice: Option<NineSliceInsets>,
+    ) -> Result<Self, AnimatedError> {
         let decoded_frames = match format {
             ImageFormat::Gif => GifDecoder::new(&data[..])?.into_frames(),
-            _ => todo!(),
+            ImageFormat::Png => {
+                let decoder = PngDecoder::new(&data[..])?;
+                if !decoder.is_apng() {
+                    return Err(AnimatedError::SingleFrame);
+                }
+                decoder.apng().into_frames()
+            }
+            ImageFormat::WebP => {
+                let decoder = WebPDecoder::new(&data[..])?;
+                if !decoder.has_animation() {
+                    return Err(AnimatedError::SingleFrame);
+                }
+                decoder.into_frames()
+            }
+            _ => return Err(AnimatedError::UnsupportedFormat(format)),
         };
 
         let mut animation_duration: Duration = Duration::ZERO;
@@ -281,12 +534,15 @@ impl AnimatedAsset {
             );
             texture.upload(ctx, buffer);
 
-            let delay: Duration = frame.delay().into();
-            animation_duration += delay;
+            let mut delay: Duration = frame.delay().into();
+            if format == ImageFormat::Gif && delay <= GIF_MIN_FRAME_DELAY {
+                delay = GIF_DEFAULT_FRAME_DELAY;
+            }
             frames.push(AnimationFrame {
                 texture,
                 pts: animation_duration,
             });
+            animation_duration += delay;
 
             if frames.len() > 1000 {
                 return Err(AnimatedError::TooMuchFrames);
@@ -314,9 +570,19 @@ impl AnimatedAsset {
             animation_duration = Duration::from_nanos(1)
         }
 
+        if let Some(insets) = nine_slice {
+            let resolution = Resolution {
+                width: first_frame_size.width as usize,
+                height: first_frame_size.height as usize,
+            };
+            validate_nine_slice(insets, resolution)?;
+        }
+
         Ok(Self {
             frames,
             animation_duration,
+            options,
+            nine_slice,
         })
     }
 
@@ -325,6 +591,7 @@ impl AnimatedAsset {
         ctx: &WgpuCtx,
         target: &mut NodeTexture,
         state: &Mutex<AnimatedNodeState>,
+        draw_mode: DrawMode,
         pts: Duration,
     ) {
         let mut state = state.lock().unwrap();
@@ -336,16 +603,32 @@ impl AnimatedAsset {
             }
         };
 
-        let animation_pts = Duration::from_nanos(
-            ((pts.as_nanos() - first_pts.as_nanos()) % self.animation_duration.as_nanos()) as u64,
-        );
+        let elapsed = pts.saturating_sub(first_pts).as_nanos();
+        let animation_duration = self.animation_duration.as_nanos();
+
+        let is_finished = self
+            .options
+            .loop_count
+            .is_some_and(|loop_count| elapsed / animation_duration >= loop_count as u128);
+        if is_finished {
+            match self.options.hold_last_frame {
+                true => {
+                    let last_frame = self.frames.last().unwrap();
+                    draw_texture(ctx, &last_frame.texture, target, draw_mode)
+                }
+                false => target.clear(),
+            }
+            return;
+        }
 
-        let closest_frame = self
+        let animation_pts = Duration::from_nanos((elapsed % animation_duration) as u64);
+        let current_frame = self
             .frames
             .iter()
-            .min_by_key(|frame| u128::abs_diff(frame.pts.as_nanos(), animation_pts.as_nanos()))
-            .unwrap();
-        copy_texture_to_node_texture(ctx, &closest_frame.texture, target)
+            .rev()
+            .find(|frame| frame.pts <= animation_pts)
+            .unwrap_or_else(|| self.frames.first().unwrap());
+        draw_texture(ctx, &current_frame.texture, target, draw_mode)
     }
 
     fn resolution(&self) -> Resolution {
@@ -357,6 +640,65 @@ impl AnimatedAsset {
     }
 }
 
+/// Defines how an image texture is drawn into the node output.
+#[derive(Debug, Clone, Copy)]
+pub enum DrawMode {
+    /// Copy texture as is, node has the same resolution as the image.
+    Copy,
+    /// Stretch the center part of the texture to `resolution`, keeping the size of the
+    /// borders defined by `insets`.
+    NineSlice {
+        insets: NineSliceInsets,
+        resolution: Option<Resolution>,
+    },
+}
+
+impl DrawMode {
+    fn new(nine_slice: Option<NineSliceInsets>, resolution: Option<Resolution>) -> Self {
+        match nine_slice {
+            Some(insets) => DrawMode::NineSlice { insets, resolution },
+            None => DrawMode::Copy,
+        }
+    }
+
+    fn resolution(&self) -> Option<Resolution> {
+        match self {
+            DrawMode::Copy => None,
+            DrawMode::NineSlice { resolution, .. } => *resolution,
+        }
+    }
+}
+
+fn draw_texture(ctx: &WgpuCtx, source: &RGBATexture, target: &mut NodeTexture, mode: DrawMode) {
+    match mode {
+        DrawMode::NineSlice {
+            insets,
+            resolution: Some(resolution),
+        } => {
+            let target = target.ensure_size(ctx, resolution);
+            ctx.utils
+                .render_nine_slice(ctx, source, target.rgba_texture(), insets);
+        }
+        // Without node resolution nine-slice image has the original size.
+        DrawMode::NineSlice {
+            resolution: None, ..
+        }
+        | DrawMode::Copy => copy_texture_to_node_texture(ctx, source, target),
+    }
+}
+
+fn validate_nine_slice(
+    insets: NineSliceInsets,
+    resolution: Resolution,
+) -> Result<(), NineSliceError> {
+    let fits_horizontally = (insets.left + insets.right) as usize <= resolution.width;
+    let fits_vertically = (insets.top + insets.bottom) as usize <= resolution.height;
+    if !fits_horizontally || !fits_vertically {
+        return Err(NineSliceError::InsetsExceedImageSize(resolution));
+    }
+    Ok(())
+}
+
 fn copy_texture_to_node_texture(ctx: &WgpuCtx, source: &RGBATexture, target: &mut NodeTexture) {
     let mut encoder = ctx
         .device
@@ -389,7 +731,7 @@ fn copy_texture_to_node_texture(ctx: &WgpuCtx, source: &RGBATexture, target: &mu
         size,
     );
 
-    ctx.queue.submit(Some(encoder.finish()));
+    ctx.submit(encoder.finish());
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -408,6 +750,18 @@ pub enum ImageError {
 
     #[error(transparent)]
     ParsingAnimatedFailed(#[from] AnimatedError),
+
+    #[error(transparent)]
+    InvalidNineSlice(#[from] NineSliceError),
+
+    #[error("Nine-slice rendering is not supported for SVG images.")]
+    NineSliceNotSupported,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum NineSliceError {
+    #[error("Nine-slice insets do not fit inside the image of size {}x{}.", .0.width, .0.height)]
+    InsetsExceedImageSize(Resolution),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -417,6 +771,9 @@ pub enum SvgError {
 
     #[error("Failed to parse the SVG image: {0}")]
     ParsingSvgFailed(#[from] usvg::Error),
+
+    #[error("Failed to rasterize the SVG image, resolution {}x{} is not supported.", .0.width, .0.height)]
+    InvalidResolution(Resolution),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -437,6 +794,12 @@ pub enum AnimatedError {
     #[error("Failed to read animated image, variable resolution is not supported.")]
     UnsupportedVariableResolution,
 
+    #[error("Animated images in {0:?} format are not supported.")]
+    UnsupportedFormat(ImageFormat),
+
     #[error("Failed to parse image: {0}")]
     FailedToParse(#[from] image::ImageError),
+
+    #[error(transparent)]
+    InvalidNineSlice(#[from] NineSliceError),
 }
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use compositor_common::renderer_spec::MeshSpec;
+
+use crate::wgpu::{
+    common_pipeline::{surface::MeshSurface, Vertex},
+    WgpuCtx,
+};
+
+/// Max number of vertices and indices of a registered mesh.
+pub const MAX_MESH_VERTEX_COUNT: usize = 1 << 20;
+
+/// Geometry uploaded to the GPU once and shared by all shader nodes drawing it.
+#[derive(Clone)]
+pub struct Mesh {
+    surface: Arc<MeshSurface>,
+}
+
+impl Mesh {
+    pub fn new(wgpu_ctx: &WgpuCtx, spec: MeshSpec) -> Result<Self, MeshError> {
+        let vertex_count = spec.vertices.len();
+        if vertex_count == 0 {
+            return Err(MeshError::NoVertices);
+        }
+        if vertex_count > MAX_MESH_VERTEX_COUNT {
+            return Err(MeshError::TooManyVertices("vertices", vertex_count));
+        }
+        match &spec.indices {
+            Some(indices) => {
+                if indices.len() > MAX_MESH_VERTEX_COUNT {
+                    return Err(MeshError::TooManyVertices("indices", indices.len()));
+                }
+                if indices.is_empty() || indices.len() % 3 != 0 {
+                    return Err(MeshError::IncompleteTriangles("indices", indices.len()));
+                }
+                if let Some(&index) = indices.iter().find(|&&i| i as usize >= vertex_count) {
+                    return Err(MeshError::IndexOutOfRange {
+                        index,
+                        vertex_count,
+                    });
+                }
+            }
+            None => {
+                if vertex_count % 3 != 0 {
+                    return Err(MeshError::IncompleteTriangles("vertices", vertex_count));
+                }
+            }
+        }
+
+        let vertices: Vec<_> = spec
+            .vertices
+            .iter()
+            .map(|vertex| Vertex {
+                position: vertex.position,
+                texture_coords: vertex.texture_coords,
+                input_id: vertex.input_id,
+                data: vertex.data,
+            })
+            .collect();
+        let surface = MeshSurface::new(&wgpu_ctx.device, &vertices, spec.indices.as_deref());
+
+        Ok(Self {
+            surface: Arc::new(surface),
+        })
+    }
+
+    pub(crate) fn surface(&self) -> Arc<MeshSurface> {
+        self.surface.clone()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MeshError {
+    #[error("Mesh has to contain at least one triangle.")]
+    NoVertices,
+
+    #[error("Mesh has {1} {0}, but at most {MAX_MESH_VERTEX_COUNT} are supported.")]
+    TooManyVertices(&'static str, usize),
+
+    #[error("Mesh is drawn as a list of triangles, number of {0} has to be a positive multiple of 3, but it is {1}.")]
+    IncompleteTriangles(&'static str, usize),
+
+    #[error("Index {index} is out of range for a mesh with {vertex_count} vertices.")]
+    IndexOutOfRange { index: u32, vertex_count: usize },
+}
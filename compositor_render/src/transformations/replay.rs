@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use compositor_common::scene::{replay::ReplaySpec, InputId};
+
+use crate::{
+    error::CreateNodeError,
+    renderer::RenderCtx,
+    wgpu::texture::{InputTexture, NodeTexture},
+};
+
+pub struct ReplayNode {
+    input_id: InputId,
+    texture: InputTexture,
+    /// PTS of the recorded frame uploaded to `texture`.
+    uploaded_pts: Option<Duration>,
+}
+
+impl ReplayNode {
+    pub fn new(ctx: &RenderCtx, spec: &ReplaySpec) -> Result<Self, CreateNodeError> {
+        if !ctx.replay_buffers.contains(&spec.input_id) {
+            return Err(CreateNodeError::ReplayBufferNotFound(spec.input_id.clone()));
+        }
+        Ok(Self {
+            input_id: spec.input_id.clone(),
+            texture: InputTexture::new(),
+            uploaded_pts: None,
+        })
+    }
+
+    pub fn render(&mut self, ctx: &RenderCtx, target: &mut NodeTexture, pts: Duration) {
+        let Some(frame) = ctx.replay_buffers.frame(&self.input_id, pts) else {
+            self.uploaded_pts = None;
+            target.clear();
+            return;
+        };
+        // Slow motion shows the same recorded frame for several output frames.
+        if self.uploaded_pts == Some(frame.pts) && !target.is_empty() {
+            return;
+        }
+
+        let frame_pts = frame.pts;
+        self.texture.upload(ctx.wgpu_ctx, frame.clone());
+        let Some(state) = self.texture.state() else {
+            return;
+        };
+        let node_texture = target.ensure_size(ctx.wgpu_ctx, state.resolution());
+        state.convert_to_rgba(ctx.wgpu_ctx, node_texture.rgba_texture());
+        target.generate_mipmaps(ctx.wgpu_ctx);
+        target.set_frame_pts(frame_pts);
+        self.uploaded_pts = Some(frame_pts);
+    }
+}
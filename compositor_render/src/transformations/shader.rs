@@ -2,11 +2,15 @@ use std::sync::Arc;
 
 use compositor_common::{
     renderer_spec::{FallbackStrategy, ShaderSpec},
-    scene::constraints::NodeConstraints,
+    scene::{
+        constraints::NodeConstraints,
+        shader::{ShaderParam, ShaderParamType},
+    },
 };
 
 use crate::wgpu::{
     shader::{CreateShaderError, WgpuShader},
+    validation::ParametersValidationError,
     WgpuCtx,
 };
 
@@ -37,4 +41,40 @@ impl Shader {
     pub fn constraints(&self) -> &NodeConstraints {
         &self.constraints
     }
+
+    pub fn fallback_strategy(&self) -> FallbackStrategy {
+        self.fallback_strategy
+    }
+
+    /// Checks that params of a shader node can be used with this shader. Params
+    /// provided by field name are validated after mapping them to the layout of
+    /// the user-defined bindings.
+    pub fn validate_params(
+        &self,
+        shader_params: Option<&ShaderParam>,
+        storage_params: Option<&ShaderParam>,
+        images_count: usize,
+    ) -> Result<(), ParametersValidationError> {
+        if let Some(params) = shader_params {
+            self.wgpu_shader.prepare_params(params)?;
+        }
+        if let Some(params) = storage_params {
+            self.wgpu_shader.prepare_storage_params(params)?;
+        }
+        self.wgpu_shader.validate_images_count(images_count)
+    }
+
+    pub fn params_type(&self) -> Result<Option<ShaderParamType>, ParametersValidationError> {
+        self.wgpu_shader.params_type()
+    }
+
+    pub fn max_images(&self) -> usize {
+        self.wgpu_shader.max_images()
+    }
+
+    pub fn storage_params_type(
+        &self,
+    ) -> Result<Option<ShaderParamType>, ParametersValidationError> {
+        self.wgpu_shader.storage_params_type()
+    }
 }
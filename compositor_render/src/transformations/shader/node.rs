@@ -2,21 +2,37 @@ use std::{sync::Arc, time::Duration};
 
 use compositor_common::{
     renderer_spec::{FallbackStrategy, RendererId},
-    scene::{shader::ShaderParam, NodeId, Resolution},
+    scene::{
+        shader::{SamplerSpec, ShaderGeometry, ShaderParam},
+        NodeId, Resolution,
+    },
 };
 use wgpu::util::DeviceExt;
 
 use crate::{
     error::CreateNodeError,
     renderer::RenderCtx,
-    wgpu::{texture::NodeTexture, WgpuCtx},
+    transformations::image_renderer::BoundImage,
+    wgpu::{
+        common_pipeline::surface::MeshSurface, shader::InputsBindGroup, texture::NodeTexture,
+        WgpuCtx,
+    },
 };
 
 use super::Shader;
 
 pub struct ShaderNode {
     params_bind_group: wgpu::BindGroup,
-    _custom_params_buffer: wgpu::Buffer,
+    inputs_bind_group: InputsBindGroup,
+    custom_params: ParamsBuffer,
+    storage_params: Option<ParamsBuffer>,
+    /// Registered images bound to the shader, kept alive while the node exists.
+    images: Vec<BoundImage>,
+    start_pts: Option<Duration>,
+    /// Only set if node uses non-default sampler settings.
+    sampler: Option<(Arc<wgpu::Sampler>, wgpu::BindGroup)>,
+    /// Drawn instead of the planes of input textures.
+    mesh: Option<Arc<MeshSurface>>,
     shader: Arc<Shader>,
     resolution: Resolution,
 }
@@ -26,86 +42,246 @@ impl ShaderNode {
         ctx: &RenderCtx,
         shader_id: &RendererId,
         shader_params: &Option<ShaderParam>,
+        storage_params: &Option<ShaderParam>,
+        image_ids: &[RendererId],
         resolution: &Resolution,
+        sampler_spec: &SamplerSpec,
     ) -> Result<Self, CreateNodeError> {
         let shader = ctx
             .renderers
             .shaders
             .get(shader_id)
             .ok_or_else(|| CreateNodeError::ShaderNotFound(shader_id.clone()))?;
+        let validation_error =
+            |err| CreateNodeError::ShaderNodeParametersValidationError(err, shader_id.clone());
 
-        if let Some(params) = shader_params {
-            shader.wgpu_shader.validate_params(params).map_err(|err| {
-                CreateNodeError::ShaderNodeParametersValidationError(err, shader_id.clone())
-            })?
-        }
+        let shader_params = shader_params
+            .as_ref()
+            .map(|params| shader.wgpu_shader.prepare_params(params))
+            .transpose()
+            .map_err(validation_error)?;
+        let storage_params = storage_params
+            .as_ref()
+            .map(|params| shader.wgpu_shader.prepare_storage_params(params))
+            .transpose()
+            .map_err(validation_error)?;
+        shader
+            .wgpu_shader
+            .validate_images_count(image_ids.len())
+            .map_err(validation_error)?;
+
+        let images = image_ids
+            .iter()
+            .map(|image_id| {
+                let image = ctx
+                    .renderers
+                    .images
+                    .get(image_id)
+                    .ok_or_else(|| CreateNodeError::ImageNotFound(image_id.clone()))?;
+                image
+                    .bind(ctx.wgpu_ctx)
+                    .map_err(|err| CreateNodeError::SvgRasterization(err, image_id.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let custom_params = ParamsBuffer::new_uniform(ctx.wgpu_ctx, shader_params);
+        let storage_params = storage_params.map(|params| {
+            ParamsBuffer::new_storage(ctx.wgpu_ctx, params, shader.wgpu_shader.min_storage_size())
+        });
+        let params_bind_group =
+            Self::new_params_bind_group(&shader, &custom_params, storage_params.as_ref(), &images);
 
-        let custom_params_buffer = Self::new_params_buffer(ctx.wgpu_ctx, shader_params);
-        let params_bind_group = Self::new_params_bind_group(ctx.wgpu_ctx, &custom_params_buffer);
+        let sampler = if *sampler_spec != SamplerSpec::default() {
+            let sampler = ctx
+                .wgpu_ctx
+                .samplers
+                .get_or_create(&ctx.wgpu_ctx.device, sampler_spec);
+            let bind_group = shader.wgpu_shader.new_sampler_bind_group(&sampler);
+            Some((sampler, bind_group))
+        } else {
+            None
+        };
 
         Ok(Self {
             params_bind_group,
-            _custom_params_buffer: custom_params_buffer,
+            inputs_bind_group: InputsBindGroup::default(),
+            custom_params,
+            storage_params,
+            images,
+            start_pts: None,
+            sampler,
+            mesh: None,
             shader,
             resolution: *resolution,
         })
     }
 
-    fn new_params_buffer(ctx: &WgpuCtx, shader_params: &Option<ShaderParam>) -> wgpu::Buffer {
-        match shader_params {
-            Some(params) => {
-                let params = params.to_bytes();
-                ctx.device
-                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                        label: Some("shader node custom params buffer"),
-                        usage: wgpu::BufferUsages::UNIFORM,
-                        contents: &params,
-                    })
+    pub fn with_geometry(
+        mut self,
+        ctx: &RenderCtx,
+        geometry: &ShaderGeometry,
+    ) -> Result<Self, CreateNodeError> {
+        self.mesh = match geometry {
+            ShaderGeometry::InputPlanes => None,
+            ShaderGeometry::Mesh(mesh_id) => {
+                let mesh = ctx
+                    .renderers
+                    .meshes
+                    .get_ref(mesh_id)
+                    .ok_or_else(|| CreateNodeError::MeshNotFound(mesh_id.clone()))?;
+                Some(mesh.surface())
             }
-            None => ctx
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("shader node empty custom params buffer"),
-                    contents: &[0],
-                    usage: wgpu::BufferUsages::UNIFORM,
-                }),
-        }
+            ShaderGeometry::Procedural { vertex_count } => Some(Arc::new(MeshSurface::zeroed(
+                &ctx.wgpu_ctx.device,
+                *vertex_count,
+            ))),
+        };
+        Ok(self)
     }
 
-    fn new_params_bind_group(ctx: &WgpuCtx, buffer: &wgpu::Buffer) -> wgpu::BindGroup {
-        ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("shader node params bind group"),
-            layout: &ctx.shader_parameters_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: buffer.as_entire_binding(),
-            }],
-        })
+    fn new_params_bind_group(
+        shader: &Shader,
+        custom_params: &ParamsBuffer,
+        storage_params: Option<&ParamsBuffer>,
+        images: &[BoundImage],
+    ) -> wgpu::BindGroup {
+        let image_views: Vec<_> = images.iter().map(BoundImage::view).collect();
+        shader.wgpu_shader.new_params_bind_group(
+            &custom_params.buffer,
+            storage_params.map(|params| &params.buffer),
+            &image_views,
+        )
     }
 
     pub fn resolution(&self) -> Resolution {
         self.resolution
     }
 
+    /// Switch node to the `new` shader if it is currently using `old` one. Parameters
+    /// buffer is reused, so `new` shader has to accept the same parameters.
+    pub(crate) fn replace_shader(&mut self, old: &Arc<Shader>, new: &Arc<Shader>) {
+        if Arc::ptr_eq(&self.shader, old) {
+            self.shader = new.clone();
+            // bind groups have to match layouts of the new pipeline
+            self.params_bind_group = Self::new_params_bind_group(
+                new,
+                &self.custom_params,
+                self.storage_params.as_ref(),
+                &self.images,
+            );
+            if let Some((sampler, bind_group)) = &mut self.sampler {
+                *bind_group = new.wgpu_shader.new_sampler_bind_group(sampler);
+            }
+        }
+    }
+
     pub fn fallback_strategy(&self) -> FallbackStrategy {
-        self.shader.fallback_strategy
+        self.shader.fallback_strategy()
     }
 
     pub fn render(
-        &self,
+        &mut self,
         sources: &[(&NodeId, &NodeTexture)],
         target: &mut NodeTexture,
         pts: Duration,
+        frame_index: u32,
     ) {
+        let start_pts = *self.start_pts.get_or_insert(pts);
+        let ctx = &self.shader.wgpu_shader.wgpu_ctx;
+        let time = pts.saturating_sub(start_pts);
+        self.custom_params.update(ctx, time);
+        if let Some(storage_params) = &self.storage_params {
+            storage_params.update(ctx, time);
+        }
+
         let target = target.ensure_size(&self.shader.wgpu_shader.wgpu_ctx, self.resolution);
         self.shader.wgpu_shader.render(
             &self.params_bind_group,
+            &mut self.inputs_bind_group,
             sources,
             target,
             pts,
-            self.shader.clear_color,
+            frame_index,
+            self.sampler.as_ref().map(|(_, bind_group)| bind_group),
+            self.mesh.as_deref(),
+            wgpu::LoadOp::Clear(self.shader.clear_color.unwrap_or(wgpu::Color::TRANSPARENT)),
+        )
+    }
+}
+
+/// Buffer with params of the node. Params with keyframes are written again on
+/// every frame.
+struct ParamsBuffer {
+    buffer: wgpu::Buffer,
+    /// Only set if params contain keyframes and need to be updated on every frame.
+    animated_params: Option<ShaderParam>,
+}
+
+impl ParamsBuffer {
+    fn new_uniform(ctx: &WgpuCtx, params: Option<ShaderParam>) -> Self {
+        match params {
+            Some(params) => Self::new(
+                ctx,
+                params,
+                "shader node custom params buffer",
+                wgpu::BufferUsages::UNIFORM,
+                0,
+            ),
+            None => Self {
+                buffer: ctx
+                    .device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("shader node empty custom params buffer"),
+                        contents: &[0],
+                        usage: wgpu::BufferUsages::UNIFORM,
+                    }),
+                animated_params: None,
+            },
+        }
+    }
+
+    /// Buffer is padded with zeros to `min_size`, e.g. when params contain an
+    /// empty runtime-sized array.
+    fn new_storage(ctx: &WgpuCtx, params: ShaderParam, min_size: u64) -> Self {
+        Self::new(
+            ctx,
+            params,
+            "shader node storage params buffer",
+            wgpu::BufferUsages::STORAGE,
+            min_size,
         )
     }
+
+    fn new(
+        ctx: &WgpuCtx,
+        params: ShaderParam,
+        label: &str,
+        usage: wgpu::BufferUsages,
+        min_size: u64,
+    ) -> Self {
+        let mut contents = params.to_bytes().to_vec();
+        if (contents.len() as u64) < min_size {
+            contents.resize(min_size as usize, 0);
+        }
+        let buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                usage: usage | wgpu::BufferUsages::COPY_DST,
+                contents: &contents,
+            });
+        Self {
+            buffer,
+            animated_params: Some(params).filter(ShaderParam::has_keyframes),
+        }
+    }
+
+    fn update(&self, ctx: &WgpuCtx, time: Duration) {
+        if let Some(params) = &self.animated_params {
+            ctx.queue
+                .write_buffer(&self.buffer, 0, &params.value_at(time).to_bytes());
+        }
+    }
 }
 
 pub trait ShaderParamExt {
@@ -121,6 +297,9 @@ impl ShaderParamExt for ShaderParam {
                 ShaderParam::I32(v) => bytes.extend_from_slice(&v.to_le_bytes()),
                 ShaderParam::List(v) => v.iter().for_each(|v| extend_bytes(v, bytes)),
                 ShaderParam::Struct(v) => v.iter().for_each(|v| extend_bytes(&v.value, bytes)),
+                ShaderParam::Keyframes(_) => extend_bytes(&s.value_at(Duration::ZERO), bytes),
+                // Replaced with a struct when the node is created.
+                ShaderParam::Object(_) => {}
             }
         }
 
@@ -1,6 +1,6 @@
 use std::{
     cmp::max,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, MutexGuard},
 };
 
 use compositor_common::scene::{
@@ -57,6 +57,10 @@ impl TextRendererCtx {
             swash_cache: Mutex::new(SwashCache::new()),
         }
     }
+
+    pub(crate) fn font_system(&self) -> MutexGuard<FontSystem> {
+        self.font_system.lock().unwrap()
+    }
 }
 
 impl Default for TextRendererCtx {
@@ -101,78 +105,26 @@ impl TextRendererNode {
         }
 
         info!("Text render");
-        let text_renderer = renderer_ctx.text_renderer_ctx;
-        let font_system = &mut text_renderer.font_system.lock().unwrap();
-        let cache = &mut text_renderer.swash_cache.lock().unwrap();
-
-        let swapchain_format = TextureFormat::Rgba8Unorm;
-        let mut atlas = TextAtlas::new(
-            &renderer_ctx.wgpu_ctx.device,
-            &renderer_ctx.wgpu_ctx.queue,
-            swapchain_format,
-        );
-        let mut text_renderer = glyphon::TextRenderer::new(
-            &mut atlas,
-            &renderer_ctx.wgpu_ctx.device,
-            MultisampleState::default(),
-            None,
+        let text_area = TextArea {
+            buffer: &self.buffer,
+            left: 0.0,
+            top: 0.0,
+            scale: 1.0,
+            bounds: TextBounds {
+                left: 0,
+                top: 0,
+                right: self.resolution.width as i32,
+                bottom: self.resolution.height as i32,
+            },
+            default_color: Color::rgb(255, 255, 255),
+        };
+        draw_text(
+            renderer_ctx,
+            target,
+            self.resolution,
+            text_area,
+            self.background_color,
         );
-
-        text_renderer
-            .prepare(
-                &renderer_ctx.wgpu_ctx.device,
-                &renderer_ctx.wgpu_ctx.queue,
-                font_system,
-                &mut atlas,
-                glyphon::Resolution {
-                    width: self.resolution.width as u32,
-                    height: self.resolution.height as u32,
-                },
-                [TextArea {
-                    buffer: &self.buffer,
-                    left: 0 as f32,
-                    top: 0 as f32,
-                    scale: 1.0,
-                    bounds: TextBounds {
-                        left: 0,
-                        top: 0,
-                        right: self.resolution.width as i32,
-                        bottom: self.resolution.height as i32,
-                    },
-                    default_color: Color::rgb(255, 255, 255),
-                }],
-                cache,
-            )
-            .unwrap();
-
-        let mut encoder =
-            renderer_ctx
-                .wgpu_ctx
-                .device
-                .create_command_encoder(&CommandEncoderDescriptor {
-                    label: Some("Text renderer encoder"),
-                });
-
-        let target_state = target.ensure_size(renderer_ctx.wgpu_ctx, self.resolution);
-        let view = &target_state.rgba_texture().texture().view;
-        {
-            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(self.background_color),
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: None,
-            });
-
-            text_renderer.render(&atlas, &mut pass).unwrap();
-        }
-
-        renderer_ctx.wgpu_ctx.queue.submit(Some(encoder.finish()));
         *was_rendered = true;
     }
 
@@ -263,3 +215,73 @@ impl TextRendererNode {
         Resolution { width, height }
     }
 }
+
+/// Clears `target` with `background_color` and draws the laid out text on it.
+pub(crate) fn draw_text(
+    renderer_ctx: &RenderCtx,
+    target: &mut NodeTexture,
+    resolution: Resolution,
+    text_area: TextArea,
+    background_color: wgpu::Color,
+) {
+    let text_renderer = renderer_ctx.text_renderer_ctx;
+    let font_system = &mut text_renderer.font_system.lock().unwrap();
+    let cache = &mut text_renderer.swash_cache.lock().unwrap();
+
+    let swapchain_format = TextureFormat::Rgba8Unorm;
+    let mut atlas = TextAtlas::new(
+        &renderer_ctx.wgpu_ctx.device,
+        &renderer_ctx.wgpu_ctx.queue,
+        swapchain_format,
+    );
+    let mut text_renderer = glyphon::TextRenderer::new(
+        &mut atlas,
+        &renderer_ctx.wgpu_ctx.device,
+        MultisampleState::default(),
+        None,
+    );
+
+    text_renderer
+        .prepare(
+            &renderer_ctx.wgpu_ctx.device,
+            &renderer_ctx.wgpu_ctx.queue,
+            font_system,
+            &mut atlas,
+            glyphon::Resolution {
+                width: resolution.width as u32,
+                height: resolution.height as u32,
+            },
+            [text_area],
+            cache,
+        )
+        .unwrap();
+
+    let mut encoder =
+        renderer_ctx
+            .wgpu_ctx
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Text renderer encoder"),
+            });
+
+    let target_state = target.ensure_size(renderer_ctx.wgpu_ctx, resolution);
+    let view = &target_state.rgba_texture().texture().view;
+    {
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(background_color),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        text_renderer.render(&atlas, &mut pass).unwrap();
+    }
+
+    renderer_ctx.wgpu_ctx.submit(encoder.finish());
+}
@@ -0,0 +1,139 @@
+use std::time::{Duration, SystemTime};
+
+use compositor_common::{
+    scene::{
+        timecode::{TimecodeSource, TimecodeSpec},
+        Resolution,
+    },
+    util::colors::RGBAColor,
+};
+use glyphon::{
+    cosmic_text::Align, Attrs, AttrsOwned, Buffer, Color, Family, Metrics, Shaping, TextArea,
+    TextBounds,
+};
+
+use crate::{renderer::RenderCtx, utils::rgba_to_wgpu_color, wgpu::texture::NodeTexture};
+
+use super::text_renderer::draw_text;
+
+pub struct TimecodeNode {
+    source: TimecodeSource,
+    resolution: Resolution,
+    attributes: AttrsOwned,
+    metrics: Metrics,
+    align: Align,
+    background_color: wgpu::Color,
+    /// Text drawn on the node texture, `None` before the first render.
+    displayed_text: Option<String>,
+}
+
+impl TimecodeNode {
+    pub fn new(spec: &TimecodeSpec) -> Self {
+        let RGBAColor(r, g, b, a) = spec.color_rgba;
+        let attributes = Attrs::new()
+            .color(Color::rgba(r, g, b, a))
+            .family(Family::Name(&spec.font_family));
+
+        Self {
+            source: spec.source,
+            resolution: spec.resolution,
+            attributes: AttrsOwned::new(attributes),
+            metrics: Metrics::new(spec.font_size, spec.font_size),
+            align: spec.align.into(),
+            background_color: rgba_to_wgpu_color(&spec.background_color_rgba),
+            displayed_text: None,
+        }
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    pub fn render(&mut self, ctx: &RenderCtx, target: &mut NodeTexture, pts: Duration) {
+        let text = match self.source {
+            TimecodeSource::Pts => format_timecode(pts),
+            TimecodeSource::WallClock => {
+                let since_epoch = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default();
+                format_timecode(Duration::from_millis(
+                    (since_epoch.as_millis() % (24 * 3600 * 1000)) as u64,
+                ))
+            }
+        };
+        if self.displayed_text.as_ref() == Some(&text) {
+            return;
+        }
+
+        let width = self.resolution.width as f32;
+        let height = self.resolution.height as f32;
+        let buffer = {
+            let font_system = &mut ctx.text_renderer_ctx.font_system();
+            let mut buffer = Buffer::new(font_system, self.metrics);
+            buffer.set_size(font_system, width, height);
+            buffer.set_text(
+                font_system,
+                &text,
+                self.attributes.as_attrs(),
+                Shaping::Advanced,
+            );
+            for line in &mut buffer.lines {
+                line.set_align(Some(self.align));
+            }
+            buffer.shape_until_scroll(font_system);
+            buffer
+        };
+
+        let text_area = TextArea {
+            buffer: &buffer,
+            left: 0.0,
+            top: ((height - self.metrics.line_height) / 2.0).max(0.0),
+            scale: 1.0,
+            bounds: TextBounds {
+                left: 0,
+                top: 0,
+                right: self.resolution.width as i32,
+                bottom: self.resolution.height as i32,
+            },
+            default_color: Color::rgb(255, 255, 255),
+        };
+        draw_text(
+            ctx,
+            target,
+            self.resolution,
+            text_area,
+            self.background_color,
+        );
+        self.displayed_text = Some(text);
+    }
+}
+
+/// `HH:MM:SS.mmm`, hours are not wrapped after 24.
+fn format_timecode(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timecode_format() {
+        assert_eq!(format_timecode(Duration::ZERO), "00:00:00.000");
+        assert_eq!(
+            format_timecode(Duration::from_millis(3_723_045)),
+            "01:02:03.045"
+        );
+        assert_eq!(
+            format_timecode(Duration::from_secs(100 * 3600)),
+            "100:00:00.000"
+        );
+    }
+}
@@ -55,6 +55,7 @@ impl TransitionNode {
         sources: &[(&NodeId, &NodeTexture)],
         target: &mut NodeTexture,
         pts: Duration,
+        frame_index: u32,
     ) {
         let interpolation_state = self.next_interpolation_state(pts);
         match self.builtin_node.state_mut() {
@@ -64,7 +65,7 @@ impl TransitionNode {
             } => *state = interpolation_state,
             BuiltinState::Static(_) => error!("Static builtin inside transition node."),
         }
-        self.builtin_node.render(sources, target, pts)
+        self.builtin_node.render(sources, target, pts, frame_index)
     }
 
     fn next_interpolation_state(&mut self, pts: Duration) -> InterpolationState {
@@ -91,6 +92,8 @@ impl From<TransitionType> for BuiltinTransition {
             TransitionType::FixedPositionLayout(start, end) => {
                 Self::FixedPositionLayout(start, end)
             }
+            TransitionType::Transform2d(start, end) => Self::Transform2d(start, end),
+            TransitionType::Perspective(start, end) => Self::Perspective(start, end),
         }
     }
 }
@@ -1,28 +1,36 @@
 use std::env;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::Mutex;
 
 use crate::renderer::{RegisterCtx, RenderCtx};
 use crate::wgpu::format::bgra_to_rgba::BGRAToRGBAConverter;
-use crate::wgpu::texture::{BGRATexture, NodeTexture};
+use crate::wgpu::texture::{BGRATexture, NodeTexture, RGBATexture};
 
+use compositor_chromium::cef;
 use compositor_common::{
-    renderer_spec::{FallbackStrategy, WebRendererSpec},
+    renderer_spec::{FallbackStrategy, WebCompositingMode, WebRendererSpec, WebRendererSrc},
     scene::{constraints::NodeConstraints, NodeId, Resolution},
 };
 use log::{error, info};
 
 use self::browser::{BrowserController, EmbedFrameError};
+use self::bundle::{LocalBundle, LocalBundleError};
+use self::chromium_context::BrowserOptions;
+use self::source_download::SourceDownload;
 
 pub mod browser;
+pub mod bundle;
 pub mod chromium_context;
 mod chromium_sender;
 mod chromium_sender_thread;
 pub(crate) mod node;
 mod shared_memory;
+mod source_download;
 
 pub const EMBED_SOURCE_FRAMES_MESSAGE: &str = "EMBED_SOURCE_FRAMES";
 pub const UNEMBED_SOURCE_FRAMES_MESSAGE: &str = "UNEMBED_SOURCE_FRAMES";
+/// Sent from the renderer process when the page calls `post_message(string)`.
+pub const POST_MESSAGE_MESSAGE: &str = "POST_MESSAGE";
 
 pub struct WebRendererOptions {
     pub init: bool,
@@ -38,70 +46,183 @@ impl Default for WebRendererOptions {
     }
 }
 
+/// Input event forwarded to the page. Coordinates are in pixels, relative to the
+/// top-left corner of the page rendered at the web renderer resolution.
+#[derive(Debug, Clone, Copy)]
+pub enum WebRendererEvent {
+    MouseMove {
+        x: i32,
+        y: i32,
+    },
+    MouseDown {
+        x: i32,
+        y: i32,
+        button: cef::MouseButton,
+        click_count: i32,
+    },
+    MouseUp {
+        x: i32,
+        y: i32,
+        button: cef::MouseButton,
+        click_count: i32,
+    },
+    MouseWheel {
+        x: i32,
+        y: i32,
+        delta_x: i32,
+        delta_y: i32,
+    },
+    /// `key_code` is a Windows virtual key code (e.g. 0x0D for Enter).
+    KeyDown {
+        key_code: i32,
+        modifiers: cef::EventModifiers,
+    },
+    KeyUp {
+        key_code: i32,
+        modifiers: cef::EventModifiers,
+    },
+    /// Character typed into the focused element.
+    Char {
+        character: char,
+        modifiers: cef::EventModifiers,
+    },
+}
+
 pub struct WebRenderer {
     params: WebRendererSpec,
     controller: Mutex<BrowserController>,
+    _bundle: Option<LocalBundle>,
 
     bgra_texture: BGRATexture,
     _bgra_bind_group_layout: wgpu::BindGroupLayout,
     bgra_bind_group: wgpu::BindGroup,
     bgra_to_rgba: BGRAToRGBAConverter,
+    /// Page rendered separately from the sources, used when they are not embedded.
+    page_texture: Option<RGBATexture>,
 }
 
 impl WebRenderer {
-    pub fn new(ctx: &RegisterCtx, params: WebRendererSpec) -> Self {
-        info!("Starting web renderer for {}", &params.url);
+    pub fn new(ctx: &RegisterCtx, params: WebRendererSpec) -> Result<Self, LocalBundleError> {
+        let (url, bundle) = match &params.src {
+            WebRendererSrc::Url { url } => (url.clone(), None),
+            WebRendererSrc::LocalPath { path } => {
+                let bundle = LocalBundle::new(path)?;
+                (bundle.url().to_string(), Some(bundle))
+            }
+        };
+        info!("Starting web renderer for {url}");
 
         let bgra_texture = BGRATexture::new(&ctx.wgpu_ctx, params.resolution);
         let bgra_bind_group_layout = BGRATexture::new_bind_group_layout(&ctx.wgpu_ctx.device);
         let bgra_bind_group = bgra_texture.new_bind_group(&ctx.wgpu_ctx, &bgra_bind_group_layout);
         let bgra_to_rgba = BGRAToRGBAConverter::new(&ctx.wgpu_ctx.device, &bgra_bind_group_layout);
-
+        let page_texture = match params.compositing_mode {
+            WebCompositingMode::Embed => None,
+            WebCompositingMode::Overlay | WebCompositingMode::Underlay => {
+                Some(RGBATexture::new(&ctx.wgpu_ctx, params.resolution))
+            }
+        };
+
+        let browser_options = BrowserOptions {
+            url,
+            transparent_background: params.transparent_background,
+            mute_audio: params.mute_audio,
+        };
         let controller = Mutex::new(BrowserController::new(
             ctx,
-            params.url.clone(),
+            browser_options,
             params.resolution,
         ));
 
-        Self {
+        Ok(Self {
             params,
             controller,
+            _bundle: bundle,
             bgra_texture,
             _bgra_bind_group_layout: bgra_bind_group_layout,
             bgra_bind_group,
             bgra_to_rgba,
-        }
+            page_texture,
+        })
     }
 
+    /// Returns which `dirty` sources were copied to be embedded in the page,
+    /// frames of the others are skipped.
     pub fn render(
         &self,
         ctx: &RenderCtx,
         node_id: &NodeId,
         sources: &[(&NodeId, &NodeTexture)],
-        buffers: &[Arc<wgpu::Buffer>],
+        dirty: &[bool],
+        downloads: &mut [Option<SourceDownload>],
         target: &mut NodeTexture,
-    ) -> Result<(), RenderWebsiteError> {
+    ) -> Result<Vec<bool>, RenderWebsiteError> {
         let mut controller = self.controller.lock().unwrap();
-        controller.send_sources(ctx, node_id.clone(), sources, buffers)?;
-
+        let Some(page_texture) = &self.page_texture else {
+            let sent = controller.send_sources(ctx, node_id.clone(), sources, dirty, downloads)?;
+
+            if let Some(frame) = controller.retrieve_frame() {
+                let target = target.ensure_size(ctx.wgpu_ctx, self.params.resolution);
+
+                self.bgra_texture.upload(ctx.wgpu_ctx, &frame);
+                self.bgra_to_rgba.convert(
+                    ctx.wgpu_ctx,
+                    (&self.bgra_texture, &self.bgra_bind_group),
+                    target.rgba_texture(),
+                );
+            }
+            return Ok(sent);
+        };
+
+        // Page keeps its previous content until a new frame is painted
         if let Some(frame) = controller.retrieve_frame() {
-            let target = target.ensure_size(ctx.wgpu_ctx, self.params.resolution);
-
             self.bgra_texture.upload(ctx.wgpu_ctx, &frame);
             self.bgra_to_rgba.convert(
                 ctx.wgpu_ctx,
                 (&self.bgra_texture, &self.bgra_bind_group),
-                target.rgba_texture(),
+                page_texture,
             );
         }
 
-        Ok(())
+        let mut layers: Vec<_> = sources
+            .iter()
+            .filter_map(|(_, texture)| texture.state())
+            .map(|state| state.rgba_texture())
+            .collect();
+        match self.params.compositing_mode {
+            WebCompositingMode::Underlay => layers.insert(0, page_texture),
+            WebCompositingMode::Embed | WebCompositingMode::Overlay => layers.push(page_texture),
+        }
+
+        let target = target.ensure_size(ctx.wgpu_ctx, self.params.resolution);
+        ctx.wgpu_ctx
+            .utils
+            .render_layers(ctx.wgpu_ctx, &layers, target.rgba_texture());
+        Ok(vec![false; sources.len()])
+    }
+
+    /// Sources are sent to the page only in [`WebCompositingMode::Embed`] mode.
+    pub fn embeds_sources(&self) -> bool {
+        self.params.compositing_mode == WebCompositingMode::Embed
     }
 
     pub fn resolution(&self) -> Resolution {
         self.params.resolution
     }
 
+    pub fn dispatch_event(&self, event: WebRendererEvent) {
+        self.controller.lock().unwrap().dispatch_event(event)
+    }
+
+    pub fn execute_javascript(&self, code: String) {
+        self.controller.lock().unwrap().execute_javascript(code)
+    }
+
+    /// Messages posted by the page since the last call, oldest first.
+    pub fn take_messages(&self) -> Vec<String> {
+        self.controller.lock().unwrap().take_messages()
+    }
+
     pub fn shared_memory_root_path(renderer_id: &str) -> PathBuf {
         env::temp_dir()
             .join("video_compositor")
@@ -1,33 +1,45 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
 
 use bytes::Bytes;
 use compositor_chromium::cef;
 use compositor_common::scene::{NodeId, Resolution};
-use crossbeam_channel::bounded;
-use log::error;
+use log::{error, warn};
 use shared_memory::ShmemError;
 
 use crate::{
     renderer::{RegisterCtx, RenderCtx},
     wgpu::texture::NodeTexture,
+    POST_MESSAGE_MESSAGE,
+};
+
+use super::{
+    chromium_context::BrowserOptions, chromium_sender::ChromiumSender,
+    source_download::SourceDownload, WebRendererEvent,
 };
 
-use super::chromium_sender::ChromiumSender;
+/// Messages that were not retrieved are dropped (oldest first) above that limit.
+const MAX_PENDING_MESSAGES: usize = 1000;
 
 pub(super) struct BrowserController {
     chromium_sender: ChromiumSender,
     frame_data: Arc<Mutex<Bytes>>,
+    messages: Arc<Mutex<VecDeque<String>>>,
 }
 
 impl BrowserController {
-    pub fn new(ctx: &RegisterCtx, url: String, resolution: Resolution) -> Self {
+    pub fn new(ctx: &RegisterCtx, options: BrowserOptions, resolution: Resolution) -> Self {
         let frame_data = Arc::new(Mutex::new(Bytes::new()));
-        let client = BrowserClient::new(frame_data.clone(), resolution);
-        let chromium_sender = ChromiumSender::new(ctx, url, client);
+        let messages = Arc::new(Mutex::new(VecDeque::new()));
+        let client = BrowserClient::new(frame_data.clone(), messages.clone(), resolution);
+        let chromium_sender = ChromiumSender::new(ctx, options, client);
 
         Self {
             chromium_sender,
             frame_data,
+            messages,
         }
     }
 
@@ -39,96 +51,88 @@ impl BrowserController {
         Some(frame_data.clone())
     }
 
+    pub fn dispatch_event(&mut self, event: WebRendererEvent) {
+        self.chromium_sender.dispatch_event(event)
+    }
+
+    pub fn execute_javascript(&mut self, code: String) {
+        self.chromium_sender.execute_javascript(code)
+    }
+
+    pub fn take_messages(&mut self) -> Vec<String> {
+        self.messages.lock().unwrap().drain(..).collect()
+    }
+
+    /// Frames of `dirty` sources are copied to their downloads and embedded in
+    /// the page during one of the following calls, once the copy finished. The
+    /// remaining sources keep content embedded previously. Returns which dirty
+    /// sources were copied, frames of the others are skipped because their
+    /// previous downloads are still in progress.
     pub fn send_sources(
         &mut self,
         ctx: &RenderCtx,
         node_id: NodeId,
         sources: &[(&NodeId, &NodeTexture)],
-        buffers: &[Arc<wgpu::Buffer>],
-    ) -> Result<(), EmbedFrameError> {
+        dirty: &[bool],
+        downloads: &mut [Option<SourceDownload>],
+    ) -> Result<Vec<bool>, EmbedFrameError> {
+        // Finishes mapping of downloads copied during the previous renders
+        ctx.wgpu_ctx.device.poll(wgpu::Maintain::Poll);
+
         self.chromium_sender
             .ensure_shared_memory(node_id.clone(), sources);
-        self.copy_sources_to_buffers(ctx, sources, buffers)?;
-
-        let mut pending_downloads = Vec::new();
-        for (source_idx, ((_, texture), buffer)) in sources.iter().zip(buffers).enumerate() {
-            let Some(texture_state) = texture.state() else {
+        let mut resolutions = vec![None; sources.len()];
+        for (source_idx, download) in downloads.iter_mut().enumerate() {
+            let Some(download) = download else {
                 continue;
             };
-            let size = texture_state.rgba_texture().size();
-            pending_downloads.push(self.copy_buffer_to_shmem(
+            let Some(frame) = download.take_mapped()? else {
+                continue;
+            };
+            self.chromium_sender.update_shared_memory(
                 node_id.clone(),
                 source_idx,
-                size,
-                buffer.clone(),
-            ));
+                frame.buffer.clone(),
+                frame.size,
+            );
+            download.unmap(frame);
+            resolutions[source_idx] = Some(download.resolution());
         }
+        self.chromium_sender.embed_sources(node_id, resolutions);
 
-        ctx.wgpu_ctx.device.poll(wgpu::Maintain::Wait);
-
-        for pending in pending_downloads {
-            pending()?;
-        }
-
-        self.chromium_sender.embed_sources(node_id, sources);
-        Ok(())
-    }
-
-    fn copy_sources_to_buffers(
-        &self,
-        ctx: &RenderCtx,
-        sources: &[(&NodeId, &NodeTexture)],
-        buffers: &[Arc<wgpu::Buffer>],
-    ) -> Result<(), EmbedFrameError> {
         let mut encoder = ctx
             .wgpu_ctx
             .device
             .create_command_encoder(&Default::default());
-
-        for ((_, texture), buffer) in sources.iter().zip(buffers) {
-            let Some(texture_state) = texture.state() else {
-                continue;
-            };
-            texture_state
-                .rgba_texture()
-                .copy_to_buffer(&mut encoder, buffer);
-        }
-        ctx.wgpu_ctx.queue.submit(Some(encoder.finish()));
-
-        Ok(())
-    }
-
-    fn copy_buffer_to_shmem(
-        &self,
-        node_id: NodeId,
-        source_idx: usize,
-        size: wgpu::Extent3d,
-        source: Arc<wgpu::Buffer>,
-    ) -> impl FnOnce() -> Result<(), EmbedFrameError> + '_ {
-        let (s, r) = bounded(1);
-        source
-            .slice(..)
-            .map_async(wgpu::MapMode::Read, move |result| {
-                if let Err(err) = s.send(result) {
-                    error!("channel send error: {err}")
-                }
-            });
-
-        move || {
-            r.recv().unwrap()?;
-
-            self.chromium_sender
-                .update_shared_memory(node_id, source_idx, source.clone(), size);
-            source.unmap();
-
-            Ok(())
-        }
+        let copied: Vec<_> = sources
+            .iter()
+            .zip(downloads.iter_mut())
+            .zip(dirty)
+            .map(
+                |(((_, texture), download), dirty)| match (texture.state(), download) {
+                    (Some(texture_state), Some(download)) if *dirty => {
+                        download.copy(&mut encoder, texture_state.rgba_texture())
+                    }
+                    _ => false,
+                },
+            )
+            .collect();
+        ctx.wgpu_ctx.submit(encoder.finish());
+
+        downloads
+            .iter()
+            .zip(&copied)
+            .filter(|(_, copied)| **copied)
+            .filter_map(|(download, _)| download.as_ref())
+            .for_each(SourceDownload::map);
+        Ok(copied)
     }
 }
 
 #[derive(Clone)]
 pub(super) struct BrowserClient {
     frame_data: Arc<Mutex<Bytes>>,
+    messages: Arc<Mutex<VecDeque<String>>>,
     resolution: Resolution,
 }
 
@@ -138,12 +142,41 @@ impl cef::Client for BrowserClient {
     fn render_handler(&self) -> Option<Self::RenderHandlerType> {
         Some(RenderHandler::new(self.frame_data.clone(), self.resolution))
     }
+
+    fn on_process_message_received(
+        &mut self,
+        _browser: &cef::Browser,
+        _frame: &cef::Frame,
+        _source_process: cef::ProcessId,
+        message: &cef::ProcessMessage,
+    ) -> bool {
+        if message.name() != POST_MESSAGE_MESSAGE {
+            return false;
+        }
+        let Some(data) = message.read_string(0) else {
+            error!("Failed to read message posted by the page");
+            return false;
+        };
+
+        let mut messages = self.messages.lock().unwrap();
+        if messages.len() >= MAX_PENDING_MESSAGES {
+            warn!("Too many messages posted by the page, dropping the oldest one");
+            messages.pop_front();
+        }
+        messages.push_back(data);
+        true
+    }
 }
 
 impl BrowserClient {
-    pub fn new(frame_data: Arc<Mutex<Bytes>>, resolution: Resolution) -> Self {
+    pub fn new(
+        frame_data: Arc<Mutex<Bytes>>,
+        messages: Arc<Mutex<VecDeque<String>>>,
+        resolution: Resolution,
+    ) -> Self {
         Self {
             frame_data,
+            messages,
             resolution,
         }
     }
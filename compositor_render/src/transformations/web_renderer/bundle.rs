@@ -0,0 +1,241 @@
+use std::{
+    env, fs, io,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+use log::warn;
+
+use crate::utils::random_string;
+
+const ENTRYPOINT: &str = "index.html";
+
+/// Page loaded from the local filesystem. Files of the bundle are served over
+/// HTTP on the loopback interface, so the page can fetch its assets (e.g. ES
+/// modules) without giving `file://` pages access to other local files. Zip
+/// archives are extracted to a temporary directory that is removed when the
+/// bundle is dropped.
+pub(super) struct LocalBundle {
+    url: String,
+    server: Arc<tiny_http::Server>,
+    server_thread: Option<JoinHandle<()>>,
+    _extracted_dir: Option<ExtractedDir>,
+}
+
+impl LocalBundle {
+    pub fn new(path: &str) -> Result<Self, LocalBundleError> {
+        let path = fs::canonicalize(path)
+            .map_err(|err| LocalBundleError::NotFound(path.to_string(), err))?;
+
+        if path.is_dir() {
+            let entrypoint = entrypoint(&path)?;
+            return Self::serve(path, &entrypoint, None);
+        }
+
+        if path.extension().map_or(false, |ext| ext == "zip") {
+            let mut archive = zip::ZipArchive::new(fs::File::open(&path)?)?;
+            // Created before extracting, so partially extracted files are removed on error
+            let extracted_dir = ExtractedDir(
+                env::temp_dir()
+                    .join("video_compositor")
+                    .join(format!("bundle_{}", random_string(30))),
+            );
+            archive.extract(&extracted_dir.0)?;
+            let root = fs::canonicalize(&extracted_dir.0)?;
+            let entrypoint = archive_entrypoint(&root)?;
+            return Self::serve(root, &entrypoint, Some(extracted_dir));
+        }
+
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self::serve(root, &path, None)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Serves files from canonical `root` under a random URL prefix, `entrypoint`
+    /// is a file inside `root` loaded by the page.
+    fn serve(
+        root: PathBuf,
+        entrypoint: &Path,
+        extracted_dir: Option<ExtractedDir>,
+    ) -> Result<Self, LocalBundleError> {
+        let server =
+            Arc::new(tiny_http::Server::http("127.0.0.1:0").map_err(LocalBundleError::Server)?);
+        let port = server.server_addr().to_ip().map_or(0, |addr| addr.port());
+        let prefix = format!("/{}/", random_string(30));
+        let entrypoint = entrypoint.strip_prefix(&root).unwrap_or(entrypoint);
+        let url = format!("http://127.0.0.1:{port}{prefix}{}", encode_path(entrypoint));
+
+        let server_thread = thread::Builder::new()
+            .name("web bundle server".to_string())
+            .spawn({
+                let server = server.clone();
+                move || serve_files(&server, &root, &prefix)
+            })?;
+
+        Ok(Self {
+            url,
+            server,
+            server_thread: Some(server_thread),
+            _extracted_dir: extracted_dir,
+        })
+    }
+}
+
+impl Drop for LocalBundle {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(server_thread) = self.server_thread.take() {
+            if server_thread.join().is_err() {
+                warn!("Web bundle server thread panicked");
+            }
+        }
+    }
+}
+
+/// Directory with an extracted zip archive, removed when dropped.
+struct ExtractedDir(PathBuf);
+
+impl Drop for ExtractedDir {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.0) {
+            warn!("Failed to remove extracted bundle {:?}: {err}", self.0);
+        }
+    }
+}
+
+/// Runs until the server is unblocked.
+fn serve_files(server: &tiny_http::Server, root: &Path, prefix: &str) {
+    for request in server.incoming_requests() {
+        let file = bundle_file(root, prefix, request.url())
+            .and_then(|path| Some((fs::File::open(&path).ok()?, content_type(&path))));
+        let response = match file {
+            Some((file, content_type)) => tiny_http::Response::from_file(file)
+                .with_header(
+                    tiny_http::Header::from_bytes(&b"Content-Type"[..], content_type).unwrap(),
+                )
+                .boxed(),
+            None => tiny_http::Response::empty(404).boxed(),
+        };
+        if let Err(err) = request.respond(response) {
+            warn!("Failed to send web bundle file: {err}");
+        }
+    }
+}
+
+/// Resolves a requested URL to a file inside `root`. Returns `None` for URLs
+/// without the bundle prefix and for paths leading outside of `root`.
+fn bundle_file(root: &Path, prefix: &str, url: &str) -> Option<PathBuf> {
+    let path = url.split(['?', '#']).next()?.strip_prefix(prefix)?;
+    let path = PathBuf::from(decode_path(path)?);
+    let is_relative = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_relative {
+        return None;
+    }
+
+    let path = fs::canonicalize(root.join(path)).ok()?;
+    match path.starts_with(root) && path.is_file() {
+        true => Some(path),
+        false => None,
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    match extension.to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" => "application/json",
+        "wasm" => "application/wasm",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        _ => "application/octet-stream",
+    }
+}
+
+fn entrypoint(dir: &Path) -> Result<PathBuf, LocalBundleError> {
+    let entrypoint = dir.join(ENTRYPOINT);
+    if !entrypoint.is_file() {
+        return Err(LocalBundleError::MissingEntrypoint(dir.to_path_buf()));
+    }
+    Ok(entrypoint)
+}
+
+/// Archives often wrap all files in a single top-level directory, in that case
+/// the entrypoint is looked up inside of it.
+fn archive_entrypoint(dir: &Path) -> Result<PathBuf, LocalBundleError> {
+    if dir.join(ENTRYPOINT).is_file() {
+        return Ok(dir.join(ENTRYPOINT));
+    }
+
+    let entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    match entries.as_slice() {
+        [entry] if entry.path().is_dir() => entrypoint(&entry.path()),
+        _ => Err(LocalBundleError::MissingEntrypoint(dir.to_path_buf())),
+    }
+}
+
+/// Percent-encodes everything except unreserved characters and path separators.
+fn encode_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let mut url = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    url
+}
+
+fn decode_path(path: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'%' => {
+                let hex = [iter.next()?, iter.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LocalBundleError {
+    #[error("Path \"{0}\" does not exist.")]
+    NotFound(String, #[source] io::Error),
+
+    #[error("Bundle directory {0:?} does not contain \"index.html\".")]
+    MissingEntrypoint(PathBuf),
+
+    #[error("Failed to read bundle: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to extract zip archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Failed to start server for bundle files: {0}")]
+    Server(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
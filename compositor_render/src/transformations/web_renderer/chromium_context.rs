@@ -4,12 +4,21 @@ use crate::utils::random_string;
 use compositor_chromium::cef;
 use compositor_common::Framerate;
 use crossbeam_channel::RecvError;
-use log::info;
+use log::{error, info};
 
 use crate::WebRendererOptions;
 
 use super::browser::BrowserClient;
 
+/// Settings of a browser started for a web renderer instance.
+#[derive(Debug, Clone)]
+pub(super) struct BrowserOptions {
+    pub url: String,
+    /// Background color is fully transparent instead of white.
+    pub transparent_background: bool,
+    pub mute_audio: bool,
+}
+
 pub struct ChromiumContext {
     instance_id: String,
     context: Option<Arc<cef::Context>>,
@@ -56,7 +65,7 @@ impl ChromiumContext {
 
     pub(super) fn start_browser(
         &self,
-        url: &str,
+        options: &BrowserOptions,
         state: BrowserClient,
     ) -> Result<cef::Browser, WebRendererContextError> {
         let context = self
@@ -68,13 +77,22 @@ impl ChromiumContext {
             windowless_rendering_enabled: true,
         };
         let settings = cef::BrowserSettings {
-            windowless_frame_rate: (self.framerate.num as i32) / (self.framerate.den as i32),
-            background_color: 0,
+            windowless_frame_rate: (self.framerate.num as f64 / self.framerate.den as f64).round()
+                as i32,
+            background_color: match options.transparent_background {
+                true => 0x00000000,
+                false => 0xFFFFFFFF,
+            },
         };
 
         let (tx, rx) = crossbeam_channel::bounded(1);
         let task = cef::Task::new(move || {
-            let result = context.start_browser(state, window_info, settings, url);
+            let result = context.start_browser(state, window_info, settings, &options.url);
+            if let (Ok(browser), true) = (&result, options.mute_audio) {
+                if let Err(err) = browser.set_audio_muted(true) {
+                    error!("Failed to mute audio of {}: {err}", options.url);
+                }
+            }
             tx.send(result).unwrap();
         });
 
@@ -6,7 +6,10 @@ use crossbeam_channel::{Receiver, Sender};
 
 use crate::wgpu::texture::NodeTexture;
 
-use super::{browser::BrowserClient, chromium_sender_thread::ChromiumSenderThread};
+use super::{
+    browser::BrowserClient, chromium_context::BrowserOptions,
+    chromium_sender_thread::ChromiumSenderThread, WebRendererEvent,
+};
 
 pub(super) struct ChromiumSender {
     message_sender: Sender<ChromiumSenderMessage>,
@@ -15,13 +18,13 @@ pub(super) struct ChromiumSender {
 }
 
 impl ChromiumSender {
-    pub fn new(ctx: &RegisterCtx, url: String, browser_client: BrowserClient) -> Self {
+    pub fn new(ctx: &RegisterCtx, options: BrowserOptions, browser_client: BrowserClient) -> Self {
         let (message_sender, message_receiver) = crossbeam_channel::unbounded();
         let (unmap_signal_sender, unmap_signal_receiver) = crossbeam_channel::bounded(0);
 
         ChromiumSenderThread::new(
             ctx,
-            url,
+            options,
             browser_client,
             message_receiver,
             unmap_signal_sender,
@@ -34,11 +37,9 @@ impl ChromiumSender {
         }
     }
 
-    pub fn embed_sources(&self, node_id: NodeId, sources: &[(&NodeId, &NodeTexture)]) {
-        let resolutions = sources
-            .iter()
-            .map(|(_, texture)| texture.resolution())
-            .collect();
+    /// Sources without resolution were not written to the shared memory, so the
+    /// page keeps showing their previous frame.
+    pub fn embed_sources(&self, node_id: NodeId, resolutions: Vec<Option<Resolution>>) {
         self.message_sender
             .send(ChromiumSenderMessage::EmbedSources {
                 node_id,
@@ -60,6 +61,18 @@ impl ChromiumSender {
             .unwrap();
     }
 
+    pub fn dispatch_event(&self, event: WebRendererEvent) {
+        self.message_sender
+            .send(ChromiumSenderMessage::DispatchEvent(event))
+            .unwrap();
+    }
+
+    pub fn execute_javascript(&self, code: String) {
+        self.message_sender
+            .send(ChromiumSenderMessage::ExecuteJavaScript(code))
+            .unwrap();
+    }
+
     pub fn update_shared_memory(
         &self,
         node_id: NodeId,
@@ -93,6 +106,8 @@ pub(super) enum ChromiumSenderMessage {
         resolutions: Vec<Option<Resolution>>,
     },
     UpdateSharedMemory(UpdateSharedMemoryInfo),
+    DispatchEvent(WebRendererEvent),
+    ExecuteJavaScript(String),
 }
 
 pub(super) struct UpdateSharedMemoryInfo {
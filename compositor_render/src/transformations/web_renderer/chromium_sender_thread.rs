@@ -18,14 +18,17 @@ use crate::transformations::web_renderer::chromium_sender::{
     ChromiumSenderMessage, UpdateSharedMemoryInfo,
 };
 use crate::transformations::web_renderer::shared_memory::{SharedMemory, SharedMemoryError};
-use crate::transformations::web_renderer::WebRenderer;
+use crate::transformations::web_renderer::{WebRenderer, WebRendererEvent};
 use crate::{wgpu::texture::utils::pad_to_256, EMBED_SOURCE_FRAMES_MESSAGE};
 
-use super::{browser::BrowserClient, chromium_context::ChromiumContext};
+use super::{
+    browser::BrowserClient,
+    chromium_context::{BrowserOptions, ChromiumContext},
+};
 
 pub(super) struct ChromiumSenderThread {
     chromium_ctx: Arc<ChromiumContext>,
-    url: String,
+    options: BrowserOptions,
     browser_client: BrowserClient,
 
     message_receiver: Receiver<ChromiumSenderMessage>,
@@ -35,14 +38,14 @@ pub(super) struct ChromiumSenderThread {
 impl ChromiumSenderThread {
     pub fn new(
         ctx: &RegisterCtx,
-        url: String,
+        options: BrowserOptions,
         browser_client: BrowserClient,
         message_receiver: Receiver<ChromiumSenderMessage>,
         unmap_signal_sender: Sender<()>,
     ) -> Self {
         Self {
             chromium_ctx: ctx.chromium.clone(),
-            url,
+            options,
             browser_client,
             message_receiver,
             unmap_signal_sender,
@@ -56,9 +59,9 @@ impl ChromiumSenderThread {
     fn run(&mut self) {
         let Ok(browser) = self
             .chromium_ctx
-            .start_browser(&self.url, self.browser_client.clone())
+            .start_browser(&self.options, self.browser_client.clone())
         else {
-            error!("Couldn't start browser for {}", self.url);
+            error!("Couldn't start browser for {}", self.options.url);
             return;
         };
 
@@ -76,6 +79,12 @@ impl ChromiumSenderThread {
                 ChromiumSenderMessage::UpdateSharedMemory(info) => {
                     self.handle_shmem_update(&mut state, info)
                 }
+                ChromiumSenderMessage::DispatchEvent(event) => {
+                    self.handle_dispatch_event(&state, event)
+                }
+                ChromiumSenderMessage::ExecuteJavaScript(code) => {
+                    self.handle_execute_javascript(&state, &code)
+                }
             };
 
             if let Err(err) = result {
@@ -168,16 +177,110 @@ impl ChromiumSenderThread {
         // Writes buffer data to shared memory
         {
             let range = info.buffer.slice(..).get_mapped_range();
-            let chunks = range.chunks((4 * pad_to_256(info.size.width)) as usize);
-            for (i, chunk) in chunks.enumerate() {
-                let bytes_len = (4 * info.size.width) as usize;
-                shared_memory.write(&chunk[..bytes_len], i * bytes_len)?;
+            let bytes_len = (4 * info.size.width) as usize;
+            let padded_bytes_len = (4 * pad_to_256(info.size.width)) as usize;
+            if bytes_len == padded_bytes_len {
+                // Rows are not padded (e.g. 1280 or 1920 pixels wide), so the
+                // frame is written at once
+                shared_memory.write(&range, 0)?;
+            } else {
+                for (i, chunk) in range.chunks(padded_bytes_len).enumerate() {
+                    shared_memory.write(&chunk[..bytes_len], i * bytes_len)?;
+                }
             }
         }
 
         self.unmap_signal_sender.send(()).unwrap();
         Ok(())
     }
+
+    fn handle_dispatch_event(
+        &self,
+        state: &ThreadState,
+        event: WebRendererEvent,
+    ) -> Result<(), ChromiumSenderThreadError> {
+        let browser = &state.browser;
+        let mouse_event = |x, y| cef::MouseEvent {
+            x,
+            y,
+            modifiers: cef::EventModifiers::default(),
+        };
+        let key_event = |event_type, key_code, character, modifiers| cef::KeyEvent {
+            event_type,
+            key_code,
+            character,
+            modifiers,
+        };
+
+        match event {
+            WebRendererEvent::MouseMove { x, y } => {
+                browser.send_mouse_move_event(mouse_event(x, y), false)?
+            }
+            WebRendererEvent::MouseDown {
+                x,
+                y,
+                button,
+                click_count,
+            } => browser.send_mouse_click_event(mouse_event(x, y), button, false, click_count)?,
+            WebRendererEvent::MouseUp {
+                x,
+                y,
+                button,
+                click_count,
+            } => browser.send_mouse_click_event(mouse_event(x, y), button, true, click_count)?,
+            WebRendererEvent::MouseWheel {
+                x,
+                y,
+                delta_x,
+                delta_y,
+            } => browser.send_mouse_wheel_event(mouse_event(x, y), delta_x, delta_y)?,
+            WebRendererEvent::KeyDown {
+                key_code,
+                modifiers,
+            } => browser.send_key_event(key_event(
+                cef::KeyEventType::RawKeyDown,
+                key_code,
+                0,
+                modifiers,
+            ))?,
+            WebRendererEvent::KeyUp {
+                key_code,
+                modifiers,
+            } => browser.send_key_event(key_event(
+                cef::KeyEventType::KeyUp,
+                key_code,
+                0,
+                modifiers,
+            ))?,
+            WebRendererEvent::Char {
+                character,
+                modifiers,
+            } => {
+                // Characters outside of BMP are sent as a surrogate pair.
+                let mut buffer = [0; 2];
+                for code_unit in character.encode_utf16(&mut buffer) {
+                    browser.send_key_event(key_event(
+                        cef::KeyEventType::Char,
+                        0,
+                        *code_unit,
+                        modifiers,
+                    ))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_execute_javascript(
+        &self,
+        state: &ThreadState,
+        code: &str,
+    ) -> Result<(), ChromiumSenderThreadError> {
+        let frame = state.browser.main_frame()?;
+        frame.execute_javascript(code)?;
+        Ok(())
+    }
 }
 
 struct ThreadState {
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use compositor_common::{
     error::ErrorStack,
@@ -9,18 +9,17 @@ use log::error;
 
 use crate::{
     renderer::RenderCtx,
-    wgpu::{
-        texture::{utils::pad_to_256, NodeTexture, RGBATexture},
-        WgpuCtx,
-    },
+    wgpu::{texture::NodeTexture, WgpuCtx},
 };
 
-use super::WebRenderer;
+use super::{source_download::SourceDownload, WebRenderer};
 
 pub struct WebRendererNode {
     renderer: Arc<WebRenderer>,
     node_id: NodeId,
-    buffers: Vec<Arc<wgpu::Buffer>>,
+    downloads: Vec<Option<SourceDownload>>,
+    /// Input frames that were sent to the page during the previous renders.
+    embedded_frames: Vec<Option<(NodeId, Duration)>>,
 }
 
 impl WebRendererNode {
@@ -28,7 +27,8 @@ impl WebRendererNode {
         Self {
             renderer,
             node_id: node_id.clone(),
-            buffers: Vec::new(),
+            downloads: Vec::new(),
+            embedded_frames: Vec::new(),
         }
     }
 
@@ -38,16 +38,45 @@ impl WebRendererNode {
         sources: &[(&NodeId, &NodeTexture)],
         target: &mut NodeTexture,
     ) {
-        self.ensure_buffers(ctx.wgpu_ctx, sources);
+        if self.renderer.embeds_sources() {
+            self.ensure_downloads(ctx.wgpu_ctx, sources);
+        }
+
+        // Sources without PTS are not input frames, so there is no way to tell
+        // whether they changed and they are always embedded.
+        let frames: Vec<_> = sources
+            .iter()
+            .map(|(id, texture)| texture.frame_pts().map(|pts| ((*id).clone(), pts)))
+            .collect();
+        let dirty: Vec<_> = frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| frame.is_none() || self.embedded_frames.get(i) != Some(frame))
+            .collect();
 
-        if let Err(err) = self
-            .renderer
-            .render(ctx, &self.node_id, sources, &self.buffers, target)
-        {
-            error!(
-                "Failed to run web render: {}",
-                ErrorStack::new(&err).into_string()
-            );
+        match self.renderer.render(
+            ctx,
+            &self.node_id,
+            sources,
+            &dirty,
+            &mut self.downloads,
+            target,
+        ) {
+            // Skipped frames are copied again during the next render
+            Ok(sent) => {
+                self.embedded_frames = frames
+                    .into_iter()
+                    .zip(dirty.iter().zip(sent))
+                    .map(|(frame, (dirty, sent))| frame.filter(|_| !dirty || sent))
+                    .collect()
+            }
+            Err(err) => {
+                self.embedded_frames.clear();
+                error!(
+                    "Failed to run web render: {}",
+                    ErrorStack::new(&err).into_string()
+                );
+            }
         }
     }
 
@@ -59,33 +88,22 @@ impl WebRendererNode {
         self.renderer.fallback_strategy()
     }
 
-    fn ensure_buffers(&mut self, wgpu_ctx: &WgpuCtx, sources: &[(&NodeId, &NodeTexture)]) {
-        self.buffers.resize_with(sources.len(), || {
-            let buffer = wgpu_ctx.device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("Temporary texture buffer"),
-                size: 0,
-                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-                mapped_at_creation: false,
-            });
-
-            Arc::new(buffer)
-        });
+    fn ensure_downloads(&mut self, wgpu_ctx: &WgpuCtx, sources: &[(&NodeId, &NodeTexture)]) {
+        self.downloads.resize_with(sources.len(), || None);
 
-        for ((_, texture), buffer) in sources.iter().zip(&mut self.buffers) {
+        for ((_, texture), download) in sources.iter().zip(&mut self.downloads) {
             let Some(texture_state) = texture.state() else {
+                *download = None;
                 continue;
             };
 
             let texture = texture_state.rgba_texture();
-            Self::ensure_buffer_size(wgpu_ctx, buffer, texture);
-        }
-    }
-
-    fn ensure_buffer_size(ctx: &WgpuCtx, buffer: &mut Arc<wgpu::Buffer>, texture: &RGBATexture) {
-        let texture_size = texture.size();
-        let texture_size = (4 * pad_to_256(texture_size.width) * texture_size.height) as u64;
-        if buffer.size() != texture_size {
-            *buffer = Arc::new(texture.new_download_buffer(ctx));
+            if !download
+                .as_ref()
+                .is_some_and(|download| download.fits(texture))
+            {
+                *download = Some(SourceDownload::new(wgpu_ctx, texture));
+            }
         }
     }
 }
@@ -0,0 +1,142 @@
+use std::sync::{Arc, Mutex};
+
+use compositor_common::scene::Resolution;
+
+use crate::wgpu::{texture::RGBATexture, WgpuCtx};
+
+/// Downloads frames of a source embedded in the page through a pair of staging
+/// buffers that are reused across renders. A frame copied during one render is
+/// mapped in the background and written to the shared memory during the next
+/// one, so the render thread never waits for the GPU to finish the copy.
+pub struct SourceDownload {
+    buffers: [DownloadBuffer; 2],
+    size: wgpu::Extent3d,
+    /// Buffer the most recent frame was copied to.
+    latest: usize,
+}
+
+struct DownloadBuffer {
+    buffer: Arc<wgpu::Buffer>,
+    state: Arc<Mutex<DownloadState>>,
+}
+
+enum DownloadState {
+    /// Buffer is unmapped and can be used for a new copy.
+    Idle,
+    /// Copy was submitted, buffer is not mapped yet.
+    Pending,
+    /// Copy finished, mapped buffer waits to be written to the shared memory.
+    Mapped,
+    Failed(wgpu::BufferAsyncError),
+}
+
+/// Mapped buffer with a downloaded frame, rows are padded to 256 bytes. Has to
+/// be released with [`SourceDownload::unmap`].
+pub(super) struct MappedFrame {
+    pub buffer: Arc<wgpu::Buffer>,
+    pub size: wgpu::Extent3d,
+    index: usize,
+}
+
+impl SourceDownload {
+    pub fn new(ctx: &WgpuCtx, texture: &RGBATexture) -> Self {
+        let buffer = || DownloadBuffer {
+            buffer: Arc::new(texture.new_download_buffer(ctx)),
+            state: Arc::new(Mutex::new(DownloadState::Idle)),
+        };
+        Self {
+            buffers: [buffer(), buffer()],
+            size: texture.size(),
+            latest: 0,
+        }
+    }
+
+    /// Buffers hold frames of the texture size, so a new download has to be
+    /// created when the source is resized.
+    pub fn fits(&self, texture: &RGBATexture) -> bool {
+        self.size == texture.size()
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        Resolution {
+            width: self.size.width as usize,
+            height: self.size.height as usize,
+        }
+    }
+
+    /// Most recent frame whose copy finished since the previous call. Older
+    /// frames that were not written are dropped.
+    pub fn take_mapped(&mut self) -> Result<Option<MappedFrame>, wgpu::BufferAsyncError> {
+        let mut mapped = None;
+        for index in [self.latest, 1 - self.latest] {
+            let buffer = &self.buffers[index];
+            let mut state = buffer.state.lock().unwrap();
+            match &*state {
+                DownloadState::Mapped if mapped.is_none() => {
+                    mapped = Some(MappedFrame {
+                        buffer: buffer.buffer.clone(),
+                        size: self.size,
+                        index,
+                    });
+                }
+                DownloadState::Mapped => {
+                    buffer.buffer.unmap();
+                    *state = DownloadState::Idle;
+                }
+                DownloadState::Failed(_) => {
+                    let DownloadState::Failed(err) =
+                        std::mem::replace(&mut *state, DownloadState::Idle)
+                    else {
+                        unreachable!();
+                    };
+                    return Err(err);
+                }
+                DownloadState::Idle | DownloadState::Pending => (),
+            }
+        }
+        Ok(mapped)
+    }
+
+    pub fn unmap(&mut self, frame: MappedFrame) {
+        let buffer = &self.buffers[frame.index];
+        buffer.buffer.unmap();
+        *buffer.state.lock().unwrap() = DownloadState::Idle;
+    }
+
+    /// Records a copy of the texture to a buffer that is not in use. Returns
+    /// `false` if both buffers still wait for the GPU, in which case the frame is
+    /// skipped. [`SourceDownload::map`] has to be called after the encoder is
+    /// submitted.
+    pub fn copy(&mut self, encoder: &mut wgpu::CommandEncoder, texture: &RGBATexture) -> bool {
+        let is_idle =
+            |buffer: &DownloadBuffer| matches!(*buffer.state.lock().unwrap(), DownloadState::Idle);
+        let Some(index) = [1 - self.latest, self.latest]
+            .into_iter()
+            .find(|index| is_idle(&self.buffers[*index]))
+        else {
+            return false;
+        };
+
+        let buffer = &self.buffers[index];
+        texture.copy_to_buffer(encoder, &buffer.buffer);
+        *buffer.state.lock().unwrap() = DownloadState::Pending;
+        self.latest = index;
+        true
+    }
+
+    /// Maps the buffer of the last copy in the background, mapping finishes
+    /// during one of the following device polls.
+    pub fn map(&self) {
+        let buffer = &self.buffers[self.latest];
+        let state = buffer.state.clone();
+        buffer
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *state.lock().unwrap() = match result {
+                    Ok(()) => DownloadState::Mapped,
+                    Err(err) => DownloadState::Failed(err),
+                };
+            });
+    }
+}
@@ -1,9 +1,18 @@
 use compositor_common::{
     renderer_spec::RendererId,
-    scene::{NodeParams, SceneSpec},
+    scene::{
+        shader::ShaderGeometry,
+        text_spec::{TextDimensions, TextSpec},
+        transition::TransitionSpec,
+        NodeParams, Resolution, SceneSpec,
+    },
 };
 
-use crate::{error::UnregisterRendererError, registry::RegistryType};
+use crate::{
+    error::{UnregisterRendererError, UpdateSceneError},
+    registry::RegistryType,
+    transformations::builtin::BuiltinSpecExt,
+};
 
 pub trait SceneSpecExt {
     fn validate_can_unregister(
@@ -11,6 +20,10 @@ pub trait SceneSpecExt {
         renderer_id: &RendererId,
         registry_type: RegistryType,
     ) -> Result<(), UnregisterRendererError>;
+
+    /// Checks that textures of nodes with resolution known from the spec can be
+    /// created on the GPU. Transitions are checked for both start and end state.
+    fn validate_resolutions(&self, max_texture_dimension: u32) -> Result<(), UpdateSceneError>;
 }
 
 impl SceneSpecExt for SceneSpec {
@@ -47,6 +60,7 @@ impl SceneSpecExt for SceneSpec {
             RegistryType::Image => {
                 let node = self.nodes.iter().find(|node| match &node.params {
                     NodeParams::Image { image_id, .. } => image_id == renderer_id,
+                    NodeParams::Shader { image_ids, .. } => image_ids.contains(renderer_id),
                     _ => false,
                 });
                 if let Some(node) = node {
@@ -56,7 +70,132 @@ impl SceneSpecExt for SceneSpec {
                     ));
                 }
             }
+            RegistryType::Mesh => {
+                let node = self.nodes.iter().find(|node| match &node.params {
+                    NodeParams::Shader {
+                        geometry: ShaderGeometry::Mesh(mesh_id),
+                        ..
+                    } => mesh_id == renderer_id,
+                    _ => false,
+                });
+                if let Some(node) = node {
+                    return Err(UnregisterRendererError::MeshStillInUse(
+                        renderer_id.clone(),
+                        node.node_id.clone(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_resolutions(&self, max_texture_dimension: u32) -> Result<(), UpdateSceneError> {
+        for node in self.nodes.iter() {
+            for resolution in declared_resolutions(&node.params) {
+                if resolution.width == 0 || resolution.height == 0 {
+                    return Err(UpdateSceneError::EmptyResolution(node.node_id.clone()));
+                }
+                let max = max_texture_dimension as usize;
+                if resolution.width > max || resolution.height > max {
+                    return Err(UpdateSceneError::ResolutionExceedsGpuLimits(
+                        node.node_id.clone(),
+                        resolution,
+                        max_texture_dimension,
+                    ));
+                }
+            }
         }
         Ok(())
     }
 }
+
+/// Resolutions the node can render at, regardless of its inputs. Text nodes are
+/// checked against their max size.
+fn declared_resolutions(params: &NodeParams) -> Vec<Resolution> {
+    match params {
+        NodeParams::Shader { resolution, .. } => vec![*resolution],
+        NodeParams::Image { resolution, .. } => resolution.iter().copied().collect(),
+        NodeParams::Builtin(spec) => spec.resolution().into_iter().collect(),
+        NodeParams::Transition(TransitionSpec { start, end, .. }) => start
+            .resolution()
+            .into_iter()
+            .chain(end.resolution())
+            .collect(),
+        NodeParams::Text(TextSpec { dimensions, .. }) => {
+            let (width, height) = match *dimensions {
+                TextDimensions::Fitted {
+                    max_width,
+                    max_height,
+                } => (max_width, max_height),
+                TextDimensions::FittedColumn { width, max_height } => (width, max_height),
+                TextDimensions::Fixed { width, height } => (width, height),
+            };
+            vec![Resolution {
+                width: width as usize,
+                height: height as usize,
+            }]
+        }
+        NodeParams::Captions(spec) => vec![spec.resolution],
+        NodeParams::Timecode(spec) => vec![spec.resolution],
+        // Resolution of replayed frames is not known before they are recorded.
+        NodeParams::Replay(_) => vec![],
+        // Resolution is a part of the registered web renderer, not the node.
+        NodeParams::WebRenderer { .. } => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use compositor_common::scene::{NodeId, NodeSpec, OutputSpec};
+
+    use super::*;
+
+    fn scene_with_shader(resolution: Resolution) -> SceneSpec {
+        let node_id = NodeId(Arc::from("shader"));
+        SceneSpec {
+            nodes: vec![NodeSpec {
+                node_id: node_id.clone(),
+                input_pads: vec![],
+                fallback_id: None,
+                input_fallback_timeout: None,
+                params: NodeParams::Shader {
+                    shader_id: RendererId(Arc::from("shader")),
+                    shader_params: None,
+                    storage_params: None,
+                    image_ids: Vec::new(),
+                    resolution,
+                    sampler: Default::default(),
+                    geometry: Default::default(),
+                },
+            }],
+            outputs: vec![OutputSpec {
+                output_id: NodeId(Arc::from("output")).into(),
+                input_pad: node_id,
+            }],
+        }
+    }
+
+    #[test]
+    fn validate_resolutions() {
+        let scene = scene_with_shader(Resolution {
+            width: 8192,
+            height: 1080,
+        });
+        assert!(scene.validate_resolutions(8192).is_ok());
+        assert!(matches!(
+            scene.validate_resolutions(4096),
+            Err(UpdateSceneError::ResolutionExceedsGpuLimits(_, _, 4096))
+        ));
+
+        let scene = scene_with_shader(Resolution {
+            width: 0,
+            height: 1080,
+        });
+        assert!(matches!(
+            scene.validate_resolutions(8192),
+            Err(UpdateSceneError::EmptyResolution(_))
+        ));
+    }
+}
@@ -1,5 +1,9 @@
 use log::error;
 
+use self::adapter::AdapterSelector;
+
+pub(crate) mod adapter;
+pub(crate) mod capabilities;
 pub(crate) mod common_pipeline;
 mod ctx;
 pub(crate) mod format;
@@ -37,6 +41,9 @@ pub enum CreateWgpuCtxError {
     #[error("Failed to get a wgpu adapter.")]
     NoAdapter,
 
+    #[error("No GPU adapter matches {0:?}.")]
+    AdapterNotFound(AdapterSelector),
+
     #[error("Failed to get a wgpu device.")]
     NoDevice(#[from] wgpu::RequestDeviceError),
 
@@ -0,0 +1,120 @@
+use super::CreateWgpuCtxError;
+
+/// Selects the GPU used by the renderer. By default, wgpu picks a high performance
+/// adapter from any backend.
+#[derive(Debug, Clone, Default)]
+pub struct GpuOptions {
+    /// Adapters of all backends are considered if not set.
+    pub backend: Option<GpuBackend>,
+    pub power_preference: GpuPowerPreference,
+    pub adapter: Option<AdapterSelector>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuBackend {
+    Vulkan,
+    Metal,
+    Dx12,
+    Gl,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum GpuPowerPreference {
+    #[default]
+    HighPerformance,
+    LowPower,
+}
+
+#[derive(Debug, Clone)]
+pub enum AdapterSelector {
+    /// First adapter whose name contains this string (case insensitive).
+    Name(String),
+    /// Position on the list returned by [`available_adapters`].
+    Index(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct AdapterInfo {
+    pub name: String,
+    pub backend: String,
+    pub device_type: String,
+    pub vendor: u32,
+    pub device: u32,
+}
+
+impl GpuBackend {
+    fn backends(&self) -> wgpu::Backends {
+        match self {
+            GpuBackend::Vulkan => wgpu::Backends::VULKAN,
+            GpuBackend::Metal => wgpu::Backends::METAL,
+            GpuBackend::Dx12 => wgpu::Backends::DX12,
+            GpuBackend::Gl => wgpu::Backends::GL,
+        }
+    }
+}
+
+impl From<GpuPowerPreference> for wgpu::PowerPreference {
+    fn from(preference: GpuPowerPreference) -> Self {
+        match preference {
+            GpuPowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+            GpuPowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+        }
+    }
+}
+
+/// Adapters of all backends, in the order used by [`AdapterSelector::Index`].
+pub fn available_adapters() -> Vec<AdapterInfo> {
+    let instance = new_instance(wgpu::Backends::all());
+    instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .map(|adapter| {
+            let info = adapter.get_info();
+            AdapterInfo {
+                name: info.name,
+                backend: format!("{:?}", info.backend),
+                device_type: format!("{:?}", info.device_type),
+                vendor: info.vendor,
+                device: info.device,
+            }
+        })
+        .collect()
+}
+
+fn new_instance(backends: wgpu::Backends) -> wgpu::Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    })
+}
+
+pub(super) fn select_adapter(opts: &GpuOptions) -> Result<wgpu::Adapter, CreateWgpuCtxError> {
+    let backends = opts
+        .backend
+        .map_or(wgpu::Backends::all(), |backend| backend.backends());
+
+    let Some(selector) = &opts.adapter else {
+        let instance = new_instance(backends);
+        return pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptionsBase {
+            power_preference: opts.power_preference.into(),
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))
+        .ok_or(CreateWgpuCtxError::NoAdapter);
+    };
+
+    // Enumerate all backends, so indexes match the list from `available_adapters`.
+    let instance = new_instance(wgpu::Backends::all());
+    let mut adapters = instance.enumerate_adapters(wgpu::Backends::all());
+    let has_backend =
+        |adapter: &wgpu::Adapter| backends.contains(adapter.get_info().backend.into());
+    let adapter = match selector {
+        AdapterSelector::Index(index) => adapters.nth(*index).filter(has_backend),
+        AdapterSelector::Name(name) => {
+            let name = name.to_lowercase();
+            adapters.find(|adapter| {
+                has_backend(adapter) && adapter.get_info().name.to_lowercase().contains(&name)
+            })
+        }
+    };
+    adapter.ok_or_else(|| CreateWgpuCtxError::AdapterNotFound(selector.clone()))
+}
@@ -0,0 +1,52 @@
+/// Limits and optional features negotiated with the GPU adapter when the wgpu
+/// context was created. Shaders are validated against them, so a shader that
+/// works on one host can be rejected on another.
+#[derive(Debug, Clone)]
+pub struct GpuCapabilities {
+    pub adapter_name: String,
+    pub backend: String,
+    /// Max size (in bytes) of all push constants used by a shader.
+    pub max_push_constant_size: u32,
+    /// Max number of textures in a shader, including `binding_array` elements.
+    pub max_binding_array_size: u32,
+    /// Max size (in bytes) of a uniform buffer.
+    pub max_uniform_buffer_binding_size: u32,
+    pub max_texture_dimension_2d: u32,
+    /// Adapter supports `f16`. The WGSL frontend does not parse `f16` yet, so
+    /// shaders can't use it even if this is true.
+    pub shader_f16: bool,
+    pub shader_f64: bool,
+}
+
+impl GpuCapabilities {
+    /// Features that are requested only if the adapter supports them.
+    pub(super) const OPTIONAL_FEATURES: wgpu::Features =
+        wgpu::Features::SHADER_F16.union(wgpu::Features::SHADER_F64);
+
+    pub(super) fn new(adapter_info: &wgpu::AdapterInfo, device: &wgpu::Device) -> Self {
+        let limits = device.limits();
+        let features = device.features();
+        Self {
+            adapter_name: adapter_info.name.clone(),
+            backend: format!("{:?}", adapter_info.backend),
+            max_push_constant_size: limits.max_push_constant_size,
+            max_binding_array_size: limits.max_sampled_textures_per_shader_stage,
+            max_uniform_buffer_binding_size: limits.max_uniform_buffer_binding_size,
+            max_texture_dimension_2d: limits.max_texture_dimension_2d,
+            shader_f16: features.contains(wgpu::Features::SHADER_F16),
+            shader_f64: features.contains(wgpu::Features::SHADER_F64),
+        }
+    }
+
+    /// Shader capabilities enabled on the device. Required features are always
+    /// present, otherwise device creation would fail.
+    pub(crate) fn naga_capabilities(&self) -> naga::valid::Capabilities {
+        let mut capabilities = naga::valid::Capabilities::PUSH_CONSTANT
+            | naga::valid::Capabilities::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING
+            | naga::valid::Capabilities::UNIFORM_BUFFER_AND_STORAGE_TEXTURE_ARRAY_NON_UNIFORM_INDEXING;
+        if self.shader_f64 {
+            capabilities |= naga::valid::Capabilities::FLOAT64;
+        }
+        capabilities
+    }
+}
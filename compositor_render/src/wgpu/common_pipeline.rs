@@ -1,3 +1,9 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use compositor_common::scene::shader::{SamplerAddressMode, SamplerFilter, SamplerSpec};
 use wgpu::{util::DeviceExt, BindGroup, BindGroupLayout, Buffer};
 
 pub mod surface;
@@ -20,13 +26,20 @@ pub struct Vertex {
     pub position: [f32; 3],
     pub texture_coords: [f32; 2],
     pub input_id: i32,
+    /// Values passed from meshes, zero for the planes of input textures.
+    pub data: [f32; 4],
 }
 
 impl Vertex {
     pub const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
         array_stride: std::mem::size_of::<Vertex>() as u64,
         step_mode: wgpu::VertexStepMode::Vertex,
-        attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Sint32],
+        attributes: &wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x2,
+            2 => Sint32,
+            3 => Float32x4
+        ],
     };
 
     const fn empty() -> Self {
@@ -34,6 +47,7 @@ impl Vertex {
             position: [0.0, 0.0, 0.0],
             texture_coords: [0.0, 0.0],
             input_id: 0,
+            data: [0.0; 4],
         }
     }
 }
@@ -47,16 +61,7 @@ pub struct Sampler {
 
 impl Sampler {
     pub fn new(device: &wgpu::Device) -> Self {
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("sampler"),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            min_filter: wgpu::FilterMode::Linear,
-            mag_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        });
+        let sampler = device.create_sampler(&sampler_descriptor(&SamplerSpec::default()));
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("sampler bind group layout"),
@@ -83,6 +88,55 @@ impl Sampler {
             bind_group_layout,
         }
     }
+
+    /// Bind group compatible with this sampler's layout, but using a different sampler.
+    pub fn new_bind_group(&self, device: &wgpu::Device, sampler: &wgpu::Sampler) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("custom sampler bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            }],
+        })
+    }
+}
+
+fn sampler_descriptor(spec: &SamplerSpec) -> wgpu::SamplerDescriptor<'static> {
+    let address_mode = match spec.address_mode {
+        SamplerAddressMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        SamplerAddressMode::Repeat => wgpu::AddressMode::Repeat,
+        SamplerAddressMode::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+    };
+    let filter = match spec.filter {
+        SamplerFilter::Linear => wgpu::FilterMode::Linear,
+        SamplerFilter::Nearest => wgpu::FilterMode::Nearest,
+    };
+    wgpu::SamplerDescriptor {
+        label: Some("sampler"),
+        address_mode_u: address_mode,
+        address_mode_w: address_mode,
+        address_mode_v: address_mode,
+        min_filter: filter,
+        mag_filter: filter,
+        mipmap_filter: filter,
+        anisotropy_clamp: spec.anisotropy,
+        ..Default::default()
+    }
+}
+
+/// Samplers shared between nodes that use the same sampler settings.
+#[derive(Debug, Default)]
+pub struct SamplerCache(Mutex<HashMap<SamplerSpec, Arc<wgpu::Sampler>>>);
+
+impl SamplerCache {
+    pub fn get_or_create(&self, device: &wgpu::Device, spec: &SamplerSpec) -> Arc<wgpu::Sampler> {
+        let mut samplers = self.0.lock().unwrap();
+        samplers
+            .entry(*spec)
+            .or_insert_with(|| Arc::new(device.create_sampler(&sampler_descriptor(spec))))
+            .clone()
+    }
 }
 
 // TODO: This should be done with push-constants, not with a buffer
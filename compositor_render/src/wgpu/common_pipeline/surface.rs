@@ -11,21 +11,25 @@ const SINGLE_PLANE_VERTICES: [Vertex; 4] = [
         position: [1.0, -1.0, 0.0],
         texture_coords: [1.0, 1.0],
         input_id: -1,
+        data: [0.0; 4],
     },
     Vertex {
         position: [1.0, 1.0, 0.0],
         texture_coords: [1.0, 0.0],
         input_id: -1,
+        data: [0.0; 4],
     },
     Vertex {
         position: [-1.0, 1.0, 0.0],
         texture_coords: [0.0, 0.0],
         input_id: -1,
+        data: [0.0; 4],
     },
     Vertex {
         position: [-1.0, -1.0, 0.0],
         texture_coords: [0.0, 1.0],
         input_id: -1,
+        data: [0.0; 4],
     },
 ];
 
@@ -49,24 +53,28 @@ impl<const N: usize> Vertices<N> {
                 position: [1.0, -1.0, 0.0],
                 texture_coords: [1.0, 1.0],
                 input_id: input_id as i32,
+                data: [0.0; 4],
             };
 
             vertices[input_id * 4 + 1] = Vertex {
                 position: [1.0, 1.0, 0.0],
                 texture_coords: [1.0, 0.0],
                 input_id: input_id as i32,
+                data: [0.0; 4],
             };
 
             vertices[input_id * 4 + 2] = Vertex {
                 position: [-1.0, 1.0, 0.0],
                 texture_coords: [0.0, 0.0],
                 input_id: input_id as i32,
+                data: [0.0; 4],
             };
 
             vertices[input_id * 4 + 3] = Vertex {
                 position: [-1.0, -1.0, 0.0],
                 texture_coords: [0.0, 1.0],
                 input_id: input_id as i32,
+                data: [0.0; 4],
             };
 
             input_id += 1;
@@ -226,3 +234,68 @@ impl SingleSurface {
         render_pass.draw_indexed(0..SINGLE_PLANE_INDICES.len() as u32, 0, 0..1);
     }
 }
+
+/// Vertices drawn instead of the planes of input textures, e.g. uploaded from a
+/// registered mesh.
+#[derive(Debug)]
+pub struct MeshSurface {
+    vertices: Buffer,
+    /// `None` if every 3 consecutive vertices form a triangle.
+    indices: Option<Buffer>,
+    /// Number of indices, or vertices if there is no index buffer.
+    len: u32,
+}
+
+impl MeshSurface {
+    pub fn new(device: &wgpu::Device, vertices: &[Vertex], indices: Option<&[u32]>) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mesh vertex buffer"),
+            usage: wgpu::BufferUsages::VERTEX,
+            contents: bytemuck::cast_slice(vertices),
+        });
+
+        let index_buffer = indices.map(|indices| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("mesh index buffer"),
+                usage: wgpu::BufferUsages::INDEX,
+                contents: bytemuck::cast_slice(indices),
+            })
+        });
+
+        Self {
+            vertices: vertex_buffer,
+            indices: index_buffer,
+            len: indices.map_or(vertices.len(), <[u32]>::len) as u32,
+        }
+    }
+
+    /// Vertices with all attributes set to zero, positions are computed by the
+    /// vertex shader.
+    pub fn zeroed(device: &wgpu::Device, vertex_count: u32) -> Self {
+        // buffers are zero-initialized
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("procedural vertex buffer"),
+            size: vertex_count as u64 * std::mem::size_of::<Vertex>() as u64,
+            usage: wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            vertices: vertex_buffer,
+            indices: None,
+            len: vertex_count,
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+
+        match &self.indices {
+            Some(indices) => {
+                render_pass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..self.len, 0, 0..1);
+            }
+            None => render_pass.draw(0..self.len, 0..1),
+        }
+    }
+}
@@ -1,8 +1,16 @@
+use std::sync::Mutex;
+
 use log::error;
 
 use super::{
-    format::TextureFormat, shader::WgpuShader, utils::TextureUtils, CreateWgpuCtxError,
-    WgpuErrorScope,
+    adapter::{select_adapter, GpuOptions},
+    capabilities::GpuCapabilities,
+    common_pipeline::SamplerCache,
+    format::TextureFormat,
+    shader::WgpuShader,
+    texture::TexturePool,
+    utils::TextureUtils,
+    CreateWgpuCtxError, WgpuErrorScope,
 };
 
 #[derive(Debug)]
@@ -16,38 +24,49 @@ pub struct WgpuCtx {
     pub utils: TextureUtils,
 
     pub shader_parameters_bind_group_layout: wgpu::BindGroupLayout,
+    pub samplers: SamplerCache,
+    pub texture_pool: TexturePool,
+
+    pub capabilities: GpuCapabilities,
+    pub shader_random_seed: Option<u32>,
+
+    /// Command buffers recorded by nodes, but not submitted yet. Submitting all of
+    /// them at once is much cheaper than a submit per node.
+    pending_commands: Mutex<Vec<wgpu::CommandBuffer>>,
 }
 
 impl WgpuCtx {
-    pub fn new() -> Result<Self, CreateWgpuCtxError> {
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            ..Default::default()
-        });
-
-        let adapter =
-            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptionsBase {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: None,
-            }))
-            .ok_or(CreateWgpuCtxError::NoAdapter)?;
+    pub fn new(
+        opts: &GpuOptions,
+        shader_random_seed: Option<u32>,
+    ) -> Result<Self, CreateWgpuCtxError> {
+        let adapter = select_adapter(opts)?;
 
+        // Request the highest limits the adapter supports for values that user
+        // shaders can depend on.
+        let adapter_limits = adapter.limits();
         let (device, queue) = pollster::block_on(adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: Some("Video Compositor's GPU :^)"),
                 limits: wgpu::Limits {
-                    max_push_constant_size: 128,
+                    max_push_constant_size: adapter_limits.max_push_constant_size.max(128),
+                    max_sampled_textures_per_shader_stage: adapter_limits
+                        .max_sampled_textures_per_shader_stage
+                        .max(wgpu::Limits::default().max_sampled_textures_per_shader_stage),
                     ..Default::default()
                 },
-                features: wgpu::Features::TEXTURE_BINDING_ARRAY
+                features: (adapter.features() & GpuCapabilities::OPTIONAL_FEATURES)
+                    | wgpu::Features::TEXTURE_BINDING_ARRAY
                     | wgpu::Features::PUSH_CONSTANTS
+                    | wgpu::Features::TEXTURE_FORMAT_16BIT_NORM
                     | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING
                     | wgpu::Features::UNIFORM_BUFFER_AND_STORAGE_TEXTURE_ARRAY_NON_UNIFORM_INDEXING,
             },
             None,
         ))?;
 
+        let capabilities = GpuCapabilities::new(&adapter.get_info(), &device);
+
         let shader_header =
             naga::front::wgsl::parse_str(include_str!("./shader/shader_header.wgsl"))
                 .expect("failed to parse the shader header file");
@@ -73,6 +92,35 @@ impl WgpuCtx {
             format,
             utils,
             shader_parameters_bind_group_layout,
+            samplers: SamplerCache::default(),
+            texture_pool: TexturePool::default(),
+            capabilities,
+            shader_random_seed,
+            pending_commands: Mutex::new(Vec::new()),
         })
     }
+
+    /// Commands are submitted with the next `submit` or `submit_pending` call.
+    ///
+    /// Writes done with `queue.write_*` are executed before any of the pending
+    /// commands, so buffers and textures used by the commands can't be written
+    /// again until they are submitted. Nodes encoded on separate threads enqueue
+    /// their commands in any order, so they can't depend on each other.
+    pub fn enqueue(&self, commands: wgpu::CommandBuffer) {
+        self.pending_commands.lock().unwrap().push(commands);
+    }
+
+    /// Submits pending commands followed by `commands`.
+    pub fn submit(&self, commands: wgpu::CommandBuffer) {
+        let mut pending = self.pending_commands.lock().unwrap();
+        pending.push(commands);
+        self.queue.submit(pending.drain(..));
+    }
+
+    pub fn submit_pending(&self) {
+        let mut pending = self.pending_commands.lock().unwrap();
+        if !pending.is_empty() {
+            self.queue.submit(pending.drain(..));
+        }
+    }
 }
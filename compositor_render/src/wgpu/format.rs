@@ -1,7 +1,9 @@
+use compositor_common::frame::{ColorSpace, FrameFormat};
+
 use self::{rgba_to_yuv::RGBAToYUVConverter, yuv_to_rgba::YUVToRGBAConverter};
 
 use super::{
-    texture::{RGBATexture, YUVTextures},
+    texture::{NV12Texture, RGBATexture, YUVTextures},
     WgpuCtx,
 };
 
@@ -9,26 +11,66 @@ pub mod bgra_to_rgba;
 mod rgba_to_yuv;
 mod yuv_to_rgba;
 
+/// Push constants shared by YUV<->RGBA conversion shaders.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct ConversionParams {
+    sample_scale: f32,
+    max_sample_value: f32,
+    color_space: u32,
+}
+
+impl ConversionParams {
+    const SIZE: u32 = std::mem::size_of::<Self>() as u32;
+
+    fn new(format: FrameFormat) -> Self {
+        let max_sample_value = format.pixel_format.max_sample_value() as f32;
+        // Samples are stored in 8-bit or 16-bit normalized textures
+        let max_stored_value = match format.pixel_format.bytes_per_sample() {
+            1 => u8::MAX as f32,
+            _ => u16::MAX as f32,
+        };
+        Self {
+            sample_scale: max_stored_value / max_sample_value,
+            max_sample_value,
+            color_space: match format.color_space {
+                ColorSpace::Bt601 => 0,
+                ColorSpace::Bt2020Pq => 1,
+                ColorSpace::Bt709 => 2,
+            },
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TextureFormat {
     yuv_to_rgba: YUVToRGBAConverter,
+    nv12_to_rgba: YUVToRGBAConverter,
     rgba_to_yuv: RGBAToYUVConverter,
+    rgba_alpha_to_yuv: RGBAToYUVConverter,
 
     yuv_layout: wgpu::BindGroupLayout,
+    nv12_layout: wgpu::BindGroupLayout,
     rgba_layout: wgpu::BindGroupLayout,
 }
 
 impl TextureFormat {
     pub fn new(device: &wgpu::Device) -> Self {
         let yuv_layout = YUVTextures::new_bind_group_layout(device);
+        let nv12_layout = NV12Texture::new_bind_group_layout(device);
         let rgba_layout = RGBATexture::new_bind_group_layout(device);
-        let yuv_to_rgba = YUVToRGBAConverter::new(device, &yuv_layout);
-        let rgba_to_yuv = RGBAToYUVConverter::new(device, &rgba_layout);
+        let yuv_to_rgba = YUVToRGBAConverter::new(device, &yuv_layout, "fs_main");
+        let nv12_to_rgba = YUVToRGBAConverter::new(device, &nv12_layout, "fs_main_nv12");
+        let rgba_to_yuv = RGBAToYUVConverter::new(device, &rgba_layout, "fs_main");
+        let rgba_alpha_to_yuv = RGBAToYUVConverter::new(device, &rgba_layout, "fs_alpha");
         Self {
             yuv_to_rgba,
+            nv12_to_rgba,
             rgba_to_yuv,
+            rgba_alpha_to_yuv,
 
             yuv_layout,
+            nv12_layout,
             rgba_layout,
         }
     }
@@ -37,6 +79,10 @@ impl TextureFormat {
         &self.yuv_layout
     }
 
+    pub fn nv12_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.nv12_layout
+    }
+
     pub fn rgba_layout(&self) -> &wgpu::BindGroupLayout {
         &self.rgba_layout
     }
@@ -50,12 +96,32 @@ impl TextureFormat {
         self.rgba_to_yuv.convert(ctx, src, dst);
     }
 
+    /// Writes alpha of the texture as luma with neutral chroma, so the frame can
+    /// be encoded as a key (alpha matte).
+    pub fn convert_rgba_alpha_to_yuv(
+        &self,
+        ctx: &WgpuCtx,
+        src: (&RGBATexture, &wgpu::BindGroup),
+        dst: &YUVTextures,
+    ) {
+        self.rgba_alpha_to_yuv.convert(ctx, src, dst);
+    }
+
     pub fn convert_yuv_to_rgba(
         &self,
         ctx: &WgpuCtx,
         src: (&YUVTextures, &wgpu::BindGroup),
         dst: &RGBATexture,
     ) {
-        self.yuv_to_rgba.convert(ctx, src, dst)
+        self.yuv_to_rgba.convert(ctx, (src.0.format(), src.1), dst)
+    }
+
+    pub fn convert_nv12_to_rgba(
+        &self,
+        ctx: &WgpuCtx,
+        src: (&NV12Texture, &wgpu::BindGroup),
+        dst: &RGBATexture,
+    ) {
+        self.nv12_to_rgba.convert(ctx, (src.0.format(), src.1), dst)
     }
 }
@@ -84,6 +84,6 @@ impl BGRAToRGBAConverter {
             self.surface.draw(&mut render_pass);
         }
 
-        ctx.queue.submit(Some(encoder.finish()));
+        ctx.submit(encoder.finish());
     }
 }
@@ -1,13 +1,19 @@
+use compositor_common::frame::PixelFormat;
+use wgpu::ShaderStages;
+
 use crate::wgpu::{
     common_pipeline::{surface::SingleSurface, Sampler, U32Uniform, Vertex, PRIMITIVE_STATE},
     texture::{RGBATexture, YUVTextures},
 };
 
-use super::WgpuCtx;
+use super::{ConversionParams, WgpuCtx};
 
 #[derive(Debug)]
 pub struct RGBAToYUVConverter {
+    /// Renders to 8-bit planes
     pipeline: wgpu::RenderPipeline,
+    /// Renders to 16-bit planes used for 10-bit formats
+    pipeline_16bit: wgpu::RenderPipeline,
     plane_selector: U32Uniform,
     sampler: Sampler,
     surface: SingleSurface,
@@ -17,6 +23,7 @@ impl RGBAToYUVConverter {
     pub fn new(
         device: &wgpu::Device,
         single_texture_bind_group_layout: &wgpu::BindGroupLayout,
+        fragment_entry_point: &str,
     ) -> Self {
         let plane_selector = U32Uniform::new(device);
         let sampler = Sampler::new(device);
@@ -29,42 +36,48 @@ impl RGBAToYUVConverter {
                 &sampler.bind_group_layout,
                 &plane_selector.bind_group_layout,
             ],
-            push_constant_ranges: &[],
+            push_constant_ranges: &[wgpu::PushConstantRange {
+                stages: ShaderStages::FRAGMENT,
+                range: 0..ConversionParams::SIZE,
+            }],
         });
 
         let shader_module = device.create_shader_module(wgpu::include_wgsl!("rgba_to_yuv.wgsl"));
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("RGBA to YUV color converter pipeline"),
-            layout: Some(&pipeline_layout),
-            primitive: PRIMITIVE_STATE,
+        let create_pipeline = |format: wgpu::TextureFormat| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("RGBA to YUV color converter pipeline"),
+                layout: Some(&pipeline_layout),
+                primitive: PRIMITIVE_STATE,
 
-            vertex: wgpu::VertexState {
-                module: &shader_module,
-                entry_point: "vs_main",
-                buffers: &[Vertex::LAYOUT],
-            },
+                vertex: wgpu::VertexState {
+                    module: &shader_module,
+                    entry_point: "vs_main",
+                    buffers: &[Vertex::LAYOUT],
+                },
 
-            fragment: Some(wgpu::FragmentState {
-                module: &shader_module,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::R8Unorm,
-                    write_mask: wgpu::ColorWrites::all(),
-                    blend: None,
-                })],
-            }),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader_module,
+                    entry_point: fragment_entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        write_mask: wgpu::ColorWrites::all(),
+                        blend: None,
+                    })],
+                }),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            })
+        };
 
         Self {
-            pipeline,
+            pipeline: create_pipeline(wgpu::TextureFormat::R8Unorm),
+            pipeline_16bit: create_pipeline(wgpu::TextureFormat::R16Unorm),
             sampler,
             surface,
             plane_selector,
@@ -72,6 +85,11 @@ impl RGBAToYUVConverter {
     }
 
     pub fn convert(&self, ctx: &WgpuCtx, src: (&RGBATexture, &wgpu::BindGroup), dst: &YUVTextures) {
+        let pipeline = match dst.format().pixel_format {
+            PixelFormat::Yuv420p => &self.pipeline,
+            PixelFormat::Yuv420p10le => &self.pipeline_16bit,
+        };
+        let params = ConversionParams::new(dst.format());
         for plane in [0, 1, 2] {
             ctx.queue.write_buffer(
                 &self.plane_selector.buffer,
@@ -116,14 +134,19 @@ impl RGBAToYUVConverter {
                     depth_stencil_attachment: None,
                 });
 
-                render_pass.set_pipeline(&self.pipeline);
+                render_pass.set_pipeline(pipeline);
                 render_pass.set_bind_group(0, src.1, &[]);
                 render_pass.set_bind_group(1, &self.sampler.bind_group, &[]);
                 render_pass.set_bind_group(2, &self.plane_selector.bind_group, &[]);
+                render_pass.set_push_constants(
+                    ShaderStages::FRAGMENT,
+                    0,
+                    bytemuck::bytes_of(&params),
+                );
                 self.surface.draw(&mut render_pass);
             }
 
-            ctx.queue.submit(Some(encoder.finish()));
+            ctx.submit(encoder.finish());
         }
     }
 }
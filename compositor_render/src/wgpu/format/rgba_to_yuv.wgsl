@@ -22,9 +22,79 @@ fn vs_main(input: VertexInput) -> VertexOutput {
 @group(1) @binding(0) var sampler_: sampler;
 @group(2) @binding(0) var<uniform> plane_selector: u32;
 
+struct ConversionParams {
+    // Multiplier that maps values written to the texture to the 0.0-1.0 range
+    // of the sample values (10-bit samples are stored in 16-bit textures).
+    sample_scale: f32,
+    max_sample_value: f32,
+    // 0 - full range BT.601, 1 - BT.2020 PQ (limited range), 2 - BT.709 (limited range)
+    color_space: u32,
+}
+
+var<push_constant> params: ConversionParams;
+
+fn srgb_to_linear(value: vec3<f32>) -> vec3<f32> {
+    return select(pow((value + 0.055) / 1.055, vec3(2.4)), value / 12.92, value <= vec3(0.04045));
+}
+
+// SMPTE ST 2084, expects linear light where 1.0 is 10000 nits
+fn pq_oetf(linear: vec3<f32>) -> vec3<f32> {
+    let m1 = 0.1593017578125;
+    let m2 = 78.84375;
+    let c1 = 0.8359375;
+    let c2 = 18.8515625;
+    let c3 = 18.6875;
+
+    let y = pow(clamp(linear, vec3(0.0), vec3(1.0)), vec3(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3(m2));
+}
+
+// Expects Y' in 0.0-1.0 range and Cb, Cr in -0.5-0.5 range, returns sample
+// for the plane selected by `plane_selector`.
+fn to_limited_range(luma: f32, cb: f32, cr: f32) -> f32 {
+    // e.g. 16-235 for Y and 16-240 for UV in 8-bit, 64-940 and 64-960 in 10-bit
+    let step = (params.max_sample_value + 1.0) / 256.0;
+    var sample: f32;
+    if(plane_selector == 0u) {
+        sample = 16.0 * step + 219.0 * step * luma;
+    } else if(plane_selector == 1u) {
+        sample = 128.0 * step + 224.0 * step * cb;
+    } else {
+        sample = 128.0 * step + 224.0 * step * cr;
+    }
+    return clamp(sample / params.max_sample_value, 0.0, 1.0);
+}
+
+// Same conversion as `rgb_to_yuv_samples` in compositor_common.
+fn rgb_to_bt709(color: vec3<f32>) -> f32 {
+    let luma = dot(vec3(0.2126, 0.7152, 0.0722), color);
+    return to_limited_range(luma, (color.b - luma) / 1.8556, (color.r - luma) / 1.5748);
+}
+
+// Same conversion as `rgb_to_yuv_samples` in compositor_common.
+fn rgb_to_bt2020_pq(color: vec3<f32>) -> f32 {
+    let linear_709 = srgb_to_linear(color);
+    // SDR white (1.0) is mapped to 203 nits
+    let linear_2020 = vec3(
+        dot(vec3(0.6274, 0.3293, 0.0433), linear_709),
+        dot(vec3(0.0691, 0.9195, 0.0114), linear_709),
+        dot(vec3(0.0164, 0.0880, 0.8956), linear_709),
+    ) * (203.0 / 10000.0);
+    let rgb_2020 = pq_oetf(linear_2020);
+
+    let luma = dot(vec3(0.2627, 0.6780, 0.0593), rgb_2020);
+    return to_limited_range(luma, (rgb_2020.b - luma) / 1.8814, (rgb_2020.r - luma) / 1.4746);
+}
+
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) f32 {
     let color = textureSample(texture, sampler_, input.tex_coords);
+    if params.color_space == 1u {
+        return rgb_to_bt2020_pq(color.rgb) / params.sample_scale;
+    }
+    if params.color_space == 2u {
+        return rgb_to_bt709(color.rgb) / params.sample_scale;
+    }
     var conversion_weights: vec4<f32>;
     var conversion_bias: f32;
 
@@ -44,5 +114,19 @@ fn fs_main(input: VertexOutput) -> @location(0) f32 {
         conversion_weights = vec4<f32>();
     }
 
-    return clamp(dot(color, conversion_weights) + conversion_bias, 0.0, 1.0);
+    return clamp(dot(color, conversion_weights) + conversion_bias, 0.0, 1.0) / params.sample_scale;
+}
+
+// Alpha as luma with neutral chroma, used by key outputs. Opaque pixels are
+// white (full luma), transparent ones are black.
+@fragment
+fn fs_alpha(input: VertexOutput) -> @location(0) f32 {
+    let alpha = textureSample(texture, sampler_, input.tex_coords).a;
+    if params.color_space != 0u {
+        return to_limited_range(alpha, 0.0, 0.0) / params.sample_scale;
+    }
+    if(plane_selector == 0u) {
+        return alpha / params.sample_scale;
+    }
+    return (128.0 / 255.0) / params.sample_scale;
 }
@@ -1,10 +1,15 @@
+use compositor_common::frame::FrameFormat;
+use wgpu::ShaderStages;
+
 use crate::wgpu::{
     common_pipeline::{surface::SingleSurface, Sampler, Vertex, PRIMITIVE_STATE},
-    texture::{RGBATexture, YUVTextures},
+    texture::RGBATexture,
 };
 
-use super::WgpuCtx;
+use super::{ConversionParams, WgpuCtx};
 
+/// Converts planar YUV or NV12 textures, depending on the fragment shader entry
+/// point (`fs_main` or `fs_main_nv12`) and the bind group layout.
 #[derive(Debug)]
 pub struct YUVToRGBAConverter {
     pipeline: wgpu::RenderPipeline,
@@ -16,6 +21,7 @@ impl YUVToRGBAConverter {
     pub fn new(
         device: &wgpu::Device,
         yuv_textures_bind_group_layout: &wgpu::BindGroupLayout,
+        fragment_entry_point: &str,
     ) -> Self {
         let shader_module = device.create_shader_module(wgpu::include_wgsl!("yuv_to_rgba.wgsl"));
         let sampler = Sampler::new(device);
@@ -24,7 +30,10 @@ impl YUVToRGBAConverter {
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("YUV to RGBA color converter render pipeline layout"),
             bind_group_layouts: &[yuv_textures_bind_group_layout, &sampler.bind_group_layout],
-            push_constant_ranges: &[],
+            push_constant_ranges: &[wgpu::PushConstantRange {
+                stages: ShaderStages::FRAGMENT,
+                range: 0..ConversionParams::SIZE,
+            }],
         });
 
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -40,7 +49,7 @@ impl YUVToRGBAConverter {
 
             fragment: Some(wgpu::FragmentState {
                 module: &shader_module,
-                entry_point: "fs_main",
+                entry_point: fragment_entry_point,
                 targets: &[Some(wgpu::ColorTargetState {
                     format: wgpu::TextureFormat::Rgba8Unorm,
                     write_mask: wgpu::ColorWrites::all(),
@@ -64,7 +73,7 @@ impl YUVToRGBAConverter {
         }
     }
 
-    pub fn convert(&self, ctx: &WgpuCtx, src: (&YUVTextures, &wgpu::BindGroup), dst: &RGBATexture) {
+    pub fn convert(&self, ctx: &WgpuCtx, src: (FrameFormat, &wgpu::BindGroup), dst: &RGBATexture) {
         let mut encoder = ctx
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -88,10 +97,15 @@ impl YUVToRGBAConverter {
             render_pass.set_pipeline(&self.pipeline);
             render_pass.set_bind_group(0, src.1, &[]);
             render_pass.set_bind_group(1, &self.sampler.bind_group, &[]);
+            render_pass.set_push_constants(
+                ShaderStages::FRAGMENT,
+                0,
+                bytemuck::bytes_of(&ConversionParams::new(src.0)),
+            );
 
             self.surface.draw(&mut render_pass);
         }
 
-        ctx.queue.submit(Some(encoder.finish()));
+        ctx.submit(encoder.finish());
     }
 }
@@ -19,16 +19,106 @@ fn vs_main(input: VertexInput) -> VertexOutput {
 }
 
 @group(0) @binding(0) var y_texture: texture_2d<f32>;
+// For NV12 frames it holds interleaved U and V samples (in the red and green channels).
 @group(0) @binding(1) var u_texture: texture_2d<f32>;
 @group(0) @binding(2) var v_texture: texture_2d<f32>;
 
 @group(1) @binding(0) var sampler_: sampler;
 
-@fragment
-fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let y = textureSample(y_texture, sampler_, input.tex_coords).x;
-    let u = textureSample(u_texture, sampler_, input.tex_coords).x;
-    let v = textureSample(v_texture, sampler_, input.tex_coords).x;
+struct ConversionParams {
+    // Multiplier that maps values read from the texture to the 0.0-1.0 range
+    // of the sample values (10-bit samples are stored in 16-bit textures).
+    sample_scale: f32,
+    max_sample_value: f32,
+    // 0 - full range BT.601, 1 - BT.2020 PQ (limited range), 2 - BT.709 (limited range)
+    color_space: u32,
+}
+
+var<push_constant> params: ConversionParams;
+
+// SMPTE ST 2084, returns linear light where 1.0 is 10000 nits
+fn pq_eotf(value: vec3<f32>) -> vec3<f32> {
+    let m1 = 0.1593017578125;
+    let m2 = 78.84375;
+    let c1 = 0.8359375;
+    let c2 = 18.8515625;
+    let c3 = 18.6875;
+
+    let p = pow(clamp(value, vec3(0.0), vec3(1.0)), vec3(1.0 / m2));
+    return pow(max(p - c1, vec3(0.0)) / (c2 - c3 * p), vec3(1.0 / m1));
+}
+
+fn linear_to_srgb(value: vec3<f32>) -> vec3<f32> {
+    let value = clamp(value, vec3(0.0), vec3(1.0));
+    return select(1.055 * pow(value, vec3(1.0 / 2.4)) - 0.055, value * 12.92, value <= vec3(0.0031308));
+}
+
+// Keeps values below the knee unchanged and smoothly compresses highlights
+// above it into the remaining range. Applied to the max channel to preserve hue.
+fn tone_map(linear: vec3<f32>) -> vec3<f32> {
+    let knee = 0.8;
+    let peak = max(max(linear.r, linear.g), linear.b);
+    if peak <= knee {
+        return linear;
+    }
+    let excess = peak - knee;
+    let mapped_peak = knee + (1.0 - knee) * excess / (excess + 1.0 - knee);
+    return linear * (mapped_peak / peak);
+}
+
+// Returns Y' in 0.0-1.0 range and Cb, Cr in -0.5-0.5 range.
+fn from_limited_range(y: f32, u: f32, v: f32) -> vec3<f32> {
+    // e.g. 16-235 for Y and 16-240 for UV in 8-bit, 64-940 and 64-960 in 10-bit
+    let step = (params.max_sample_value + 1.0) / 256.0;
+    return vec3(
+        (y * params.max_sample_value - 16.0 * step) / (219.0 * step),
+        (u * params.max_sample_value - 128.0 * step) / (224.0 * step),
+        (v * params.max_sample_value - 128.0 * step) / (224.0 * step),
+    );
+}
+
+fn bt709_to_rgb(y: f32, u: f32, v: f32) -> vec3<f32> {
+    let ycbcr = from_limited_range(y, u, v);
+    let luma = ycbcr.x;
+    let cb = ycbcr.y;
+    let cr = ycbcr.z;
+
+    let rgb = vec3(
+        luma + 1.5748 * cr,
+        luma - 0.18732 * cb - 0.46812 * cr,
+        luma + 1.8556 * cb,
+    );
+    return clamp(rgb, vec3(0.0), vec3(1.0));
+}
+
+fn bt2020_pq_to_rgb(y: f32, u: f32, v: f32) -> vec3<f32> {
+    let ycbcr = from_limited_range(y, u, v);
+    let luma = ycbcr.x;
+    let cb = ycbcr.y;
+    let cr = ycbcr.z;
+
+    let rgb_2020 = vec3(
+        luma + 1.4746 * cr,
+        luma - 0.16455 * cb - 0.57135 * cr,
+        luma + 1.8814 * cb,
+    );
+    // SDR white (1.0) is mapped to 203 nits
+    let linear_2020 = pq_eotf(rgb_2020) * (10000.0 / 203.0);
+    let linear_709 = vec3(
+        dot(vec3(1.6605, -0.5876, -0.0728), linear_2020),
+        dot(vec3(-0.1246, 1.1329, -0.0083), linear_2020),
+        dot(vec3(-0.0182, -0.1006, 1.1187), linear_2020),
+    );
+    return linear_to_srgb(tone_map(max(linear_709, vec3(0.0))));
+}
+
+fn yuv_to_rgba(y: f32, u: f32, v: f32) -> vec4<f32> {
+    if params.color_space == 1u {
+        return vec4(bt2020_pq_to_rgb(y, u, v), 1.0);
+    }
+    if params.color_space == 2u {
+        return vec4(bt709_to_rgb(y, u, v), 1.0);
+    }
 
     let r = y + 1.40200 * (v - 128.0 / 255.0);
     let g = y - 0.34414 * (u - 128.0 / 255.0) - 0.71414 * (v - 128.0 / 255.0);
@@ -36,3 +126,18 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
 
     return vec4(clamp(r, 0.0, 1.0), clamp(g, 0.0, 1.0), clamp(b, 0.0, 1.0), 1.0);
 }
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let y = textureSample(y_texture, sampler_, input.tex_coords).x * params.sample_scale;
+    let u = textureSample(u_texture, sampler_, input.tex_coords).x * params.sample_scale;
+    let v = textureSample(v_texture, sampler_, input.tex_coords).x * params.sample_scale;
+    return yuv_to_rgba(y, u, v);
+}
+
+@fragment
+fn fs_main_nv12(input: VertexOutput) -> @location(0) vec4<f32> {
+    let y = textureSample(y_texture, sampler_, input.tex_coords).x * params.sample_scale;
+    let uv = textureSample(u_texture, sampler_, input.tex_coords).xy * params.sample_scale;
+    return yuv_to_rgba(y, uv.x, uv.y);
+}
@@ -1,13 +1,21 @@
-use std::{sync::Arc, time::Duration};
+use std::{iter, num::NonZeroU32, sync::Arc, time::Duration};
 
-use compositor_common::scene::{shader::ShaderParam, NodeId};
+use compositor_common::scene::{
+    shader::{ShaderParam, ShaderParamType},
+    NodeId,
+};
 
-use self::{common_params::CommonShaderParameters, pipeline::Pipeline};
+use self::{
+    common_params::{CommonShaderParameters, InputShaderParameters, InputsShaderParameters},
+    pipeline::Pipeline,
+};
 
 use super::{
+    common_pipeline::surface::MeshSurface,
     texture::{NodeTexture, NodeTextureState, Texture},
     validation::{
-        validate_contains_header, validate_params, ParametersValidationError, ShaderValidationError,
+        params_type, resolve_object_params, validate_capabilities, validate_contains_header,
+        validate_params, ParametersValidationError, ShaderValidationError,
     },
     WgpuCtx, WgpuError, WgpuErrorScope,
 };
@@ -22,6 +30,9 @@ pub const FRAGMENT_ENTRYPOINT_NAME: &str = "fs_main";
 
 pub const USER_DEFINED_BUFFER_GROUP: u32 = 1;
 pub const USER_DEFINED_BUFFER_BINDING: u32 = 0;
+pub const USER_DEFINED_STORAGE_BINDING: u32 = 1;
+pub const USER_DEFINED_IMAGES_BINDING: u32 = 2;
+pub const USER_DEFINED_IMAGES_AMOUNT: u32 = 8;
 
 #[derive(Debug, thiserror::Error)]
 pub enum CreateShaderError {
@@ -43,30 +54,109 @@ pub enum CreateShaderError {
 /// var<push_constant> common_params: CommonShaderParameters;
 ///
 /// @group(0) @binding(0) var textures: binding_array<texture_2d<f32>, 16>;
+/// @group(0) @binding(1) var<uniform> inputs_params: array<InputShaderParameters, 16>;
 /// @group(1) @binding(0) var<uniform> shaders_custom_buffer: CustomStruct;
+/// @group(1) @binding(1) var<storage, read> shaders_custom_storage: CustomStorage;
+/// @group(1) @binding(2) var images: binding_array<texture_2d<f32>, 8>;
 /// @group(2) @binding(0) var sampler_: sampler;
 /// ```
+///
+/// Vertex shader receives `VertexInput` declared in the header. It can declare
+/// only the leading fields of it, and builtin arguments like `@builtin(vertex_index)`.
+///
+/// Bindings in group 1 are optional. Shaders that declare only the uniform buffer
+/// use the params layout shared with builtins.
 #[derive(Debug)]
 pub struct WgpuShader {
     pub wgpu_ctx: Arc<WgpuCtx>,
     pipeline: Pipeline,
     empty_texture: Texture,
+    /// Random value passed to the shader in common params. Generated once, so it
+    /// stays the same for every frame rendered with this shader.
+    random_seed: u32,
     shader: naga::Module,
+    user_bindings: UserBindings,
+    /// Layout of the params bind group, `None` if the shader uses the shared layout.
+    params_bgl: Option<wgpu::BindGroupLayout>,
+    /// Bound if the shader declares a storage buffer, but the node has no storage params.
+    empty_storage_buffer: Option<wgpu::Buffer>,
+}
+
+/// Optional bindings in group 1 declared by the shader, in addition to the uniform
+/// buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UserBindings {
+    pub storage: bool,
+    pub images: bool,
+}
+
+/// Input textures and their params bound to the shader, owned by the node that
+/// renders with it. The bind group is reused between frames and recreated only when
+/// input textures change, e.g. when an input changes resolution or becomes empty.
+#[derive(Debug, Default)]
+pub struct InputsBindGroup {
+    params_buffer: Option<wgpu::Buffer>,
+    bind_group: Option<CachedBindGroup>,
+}
+
+#[derive(Debug)]
+struct CachedBindGroup {
+    layout_id: wgpu::Id<wgpu::BindGroupLayout>,
+    texture_view_ids: Vec<wgpu::Id<wgpu::TextureView>>,
+    bind_group: wgpu::BindGroup,
 }
 
 impl WgpuShader {
     pub fn new(wgpu_ctx: &Arc<WgpuCtx>, shader_src: String) -> Result<Self, CreateShaderError> {
+        Self::new_with_cull_mode(wgpu_ctx, shader_src, Some(wgpu::Face::Back))
+    }
+
+    /// Planes facing away from the viewer are rendered too (mirrored) instead of
+    /// being culled, e.g. inputs flipped with a negative scale or rotated in 3D.
+    pub fn new_double_sided(
+        wgpu_ctx: &Arc<WgpuCtx>,
+        shader_src: String,
+    ) -> Result<Self, CreateShaderError> {
+        Self::new_with_cull_mode(wgpu_ctx, shader_src, None)
+    }
+
+    fn new_with_cull_mode(
+        wgpu_ctx: &Arc<WgpuCtx>,
+        shader_src: String,
+        cull_mode: Option<wgpu::Face>,
+    ) -> Result<Self, CreateShaderError> {
         let scope = WgpuErrorScope::push(&wgpu_ctx.device);
 
         let shader =
             naga::front::wgsl::parse_str(&shader_src).map_err(CreateShaderError::ParseError)?;
 
         validate_contains_header(&wgpu_ctx.shader_header, &shader)?;
+        validate_capabilities(&shader, &wgpu_ctx.capabilities)?;
+
+        let storage_type = user_binding_type(&shader, USER_DEFINED_STORAGE_BINDING);
+        let user_bindings = UserBindings {
+            storage: storage_type.is_some(),
+            images: user_binding_type(&shader, USER_DEFINED_IMAGES_BINDING).is_some(),
+        };
+        let params_bgl = (user_bindings != UserBindings::default())
+            .then(|| Self::new_user_bind_group_layout(&wgpu_ctx.device, user_bindings));
+        let empty_storage_buffer = storage_type.map(|ty| {
+            wgpu_ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("shader empty storage buffer"),
+                // runtime-sized arrays are counted as a single element
+                size: shader.types[ty].inner.size(&shader.constants) as u64,
+                usage: wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            })
+        });
 
         let pipeline = Pipeline::new(
             &wgpu_ctx.device,
             wgpu::ShaderSource::Naga(std::borrow::Cow::Owned(shader.clone())),
-            &wgpu_ctx.shader_parameters_bind_group_layout,
+            params_bgl
+                .as_ref()
+                .unwrap_or(&wgpu_ctx.shader_parameters_bind_group_layout),
+            cull_mode,
         );
 
         let empty_texture = Texture::new(
@@ -87,94 +177,316 @@ impl WgpuShader {
             wgpu_ctx: wgpu_ctx.clone(),
             pipeline,
             empty_texture,
+            random_seed: wgpu_ctx.shader_random_seed.unwrap_or_else(rand::random),
             shader,
+            user_bindings,
+            params_bgl,
+            empty_storage_buffer,
         })
     }
 
     pub fn new_parameters_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("shader parameters bind group layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
+        Self::new_user_bind_group_layout(device, UserBindings::default())
+    }
+
+    fn new_user_bind_group_layout(
+        device: &wgpu::Device,
+        user_bindings: UserBindings,
+    ) -> wgpu::BindGroupLayout {
+        let mut entries = vec![wgpu::BindGroupLayoutEntry {
+            binding: USER_DEFINED_BUFFER_BINDING,
+            count: None,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+        }];
+        if user_bindings.storage {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: USER_DEFINED_STORAGE_BINDING,
                 count: None,
                 visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                 ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
                     has_dynamic_offset: false,
                     min_binding_size: None,
                 },
-            }],
+            });
+        }
+        if user_bindings.images {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: USER_DEFINED_IMAGES_BINDING,
+                count: NonZeroU32::new(USER_DEFINED_IMAGES_AMOUNT),
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+            });
+        }
+
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("shader parameters bind group layout"),
+            entries: &entries,
         })
     }
 
+    /// Draws `mesh` if specified, otherwise a plane for every source.
     pub fn render(
         &self,
         params: &wgpu::BindGroup,
+        inputs: &mut InputsBindGroup,
         sources: &[(&NodeId, &NodeTexture)],
         target: &NodeTextureState,
         pts: Duration,
-        clear_color: Option<wgpu::Color>,
+        frame_index: u32,
+        sampler: Option<&wgpu::BindGroup>,
+        mesh: Option<&MeshSurface>,
+        load: wgpu::LoadOp<wgpu::Color>,
     ) {
-        let ctx = &self.wgpu_ctx;
-
         // TODO: sources need to be ordered
 
-        // TODO: most things that happen in this method should not be done every frame
+        let input_textures_bg = self.inputs_bind_group(inputs, sources, pts);
 
-        let textures = sources
-            .iter()
-            .map(|(_, node_texture)| node_texture.state())
-            .collect::<Vec<_>>();
-        let mut texture_views = textures
-            .iter()
-            .map(|node_texture| match node_texture {
-                Some(node_texture) => &node_texture.rgba_texture().texture().view,
-                None => &self.empty_texture.view,
-            })
-            .collect::<Vec<_>>();
-
-        texture_views.extend(
-            (textures.len()..INPUT_TEXTURES_AMOUNT as usize).map(|_| &self.empty_texture.view),
+        let common_shader_params = CommonShaderParameters::new(
+            pts,
+            sources.len() as u32,
+            target.resolution(),
+            frame_index,
+            self.random_seed,
         );
 
-        let input_textures_bg = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &self.pipeline.textures_bgl,
-            label: None,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureViewArray(&texture_views),
-            }],
-        });
-
-        let common_shader_params =
-            CommonShaderParameters::new(pts, sources.len() as u32, target.resolution());
-
         self.pipeline.render(
-            &input_textures_bg,
+            input_textures_bg,
             params,
             target.rgba_texture().texture(),
-            ctx,
+            &self.wgpu_ctx,
             common_shader_params,
-            clear_color,
+            sampler,
+            mesh,
+            load,
         );
     }
 
-    pub fn validate_params(&self, params: &ShaderParam) -> Result<(), ParametersValidationError> {
-        let ty = self
-            .shader
-            .global_variables
-            .iter()
-            .find(|(_, global)| match global.binding.as_ref() {
-                Some(binding) => {
-                    (binding.group, binding.binding)
-                        == (USER_DEFINED_BUFFER_GROUP, USER_DEFINED_BUFFER_BINDING)
+    fn inputs_bind_group<'a>(
+        &self,
+        inputs: &'a mut InputsBindGroup,
+        sources: &[(&NodeId, &NodeTexture)],
+        pts: Duration,
+    ) -> &'a wgpu::BindGroup {
+        let ctx = &self.wgpu_ctx;
+
+        let params_buffer = inputs.params_buffer.get_or_insert_with(|| {
+            ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("shader inputs params buffer"),
+                size: std::mem::size_of::<InputsShaderParameters>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        });
+        let inputs_params = InputShaderParameters::new_array(sources, pts);
+        ctx.queue
+            .write_buffer(params_buffer, 0, bytemuck::cast_slice(&inputs_params));
+
+        let texture_views = move || {
+            sources
+                .iter()
+                .map(move |(_, node_texture)| match node_texture.state() {
+                    Some(node_texture) => &node_texture.rgba_texture().texture().sampled_view,
+                    None => &self.empty_texture.view,
+                })
+                .chain(iter::repeat(&self.empty_texture.view))
+                .take(INPUT_TEXTURES_AMOUNT as usize)
+        };
+        let layout_id = self.pipeline.textures_bgl.global_id();
+
+        let cached = match inputs.bind_group.take() {
+            Some(cached)
+                if cached.layout_id == layout_id
+                    && cached
+                        .texture_view_ids
+                        .iter()
+                        .copied()
+                        .eq(texture_views().map(wgpu::TextureView::global_id)) =>
+            {
+                cached
+            }
+            _ => {
+                let texture_views: Vec<_> = texture_views().collect();
+                let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &self.pipeline.textures_bgl,
+                    label: None,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureViewArray(&texture_views),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: params_buffer.as_entire_binding(),
+                        },
+                    ],
+                });
+                CachedBindGroup {
+                    layout_id,
+                    texture_view_ids: texture_views.iter().map(|view| view.global_id()).collect(),
+                    bind_group,
                 }
+            }
+        };
+        &inputs.bind_group.insert(cached).bind_group
+    }
+
+    /// Bind group that can be passed to `render` to replace the default sampler.
+    pub fn new_sampler_bind_group(&self, sampler: &wgpu::Sampler) -> wgpu::BindGroup {
+        self.pipeline
+            .new_sampler_bind_group(&self.wgpu_ctx.device, sampler)
+    }
 
-                None => false,
+    /// Bind group with params of a node (group 1). Images that are not provided are
+    /// bound as an empty texture.
+    pub fn new_params_bind_group(
+        &self,
+        params: &wgpu::Buffer,
+        storage: Option<&wgpu::Buffer>,
+        images: &[&wgpu::TextureView],
+    ) -> wgpu::BindGroup {
+        let image_views: Vec<_> = images
+            .iter()
+            .copied()
+            .chain(iter::repeat(&self.empty_texture.view))
+            .take(USER_DEFINED_IMAGES_AMOUNT as usize)
+            .collect();
+
+        let mut entries = vec![wgpu::BindGroupEntry {
+            binding: USER_DEFINED_BUFFER_BINDING,
+            resource: params.as_entire_binding(),
+        }];
+        if let Some(empty_storage_buffer) = &self.empty_storage_buffer {
+            entries.push(wgpu::BindGroupEntry {
+                binding: USER_DEFINED_STORAGE_BINDING,
+                resource: storage.unwrap_or(empty_storage_buffer).as_entire_binding(),
+            });
+        }
+        if self.user_bindings.images {
+            entries.push(wgpu::BindGroupEntry {
+                binding: USER_DEFINED_IMAGES_BINDING,
+                resource: wgpu::BindingResource::TextureViewArray(&image_views),
+            });
+        }
+
+        self.wgpu_ctx
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("shader node params bind group"),
+                layout: self
+                    .params_bgl
+                    .as_ref()
+                    .unwrap_or(&self.wgpu_ctx.shader_parameters_bind_group_layout),
+                entries: &entries,
             })
-            .map(|(_, handle)| handle.ty)
-            .ok_or(ParametersValidationError::NoBindingInShader)?;
+    }
+
+    /// Size of the user-defined storage buffer with runtime-sized arrays counted as
+    /// a single element, 0 if the shader does not declare it.
+    pub fn min_storage_size(&self) -> u64 {
+        self.empty_storage_buffer
+            .as_ref()
+            .map_or(0, wgpu::Buffer::size)
+    }
+
+    /// Number of images that can be bound to the shader, 0 if the shader does not
+    /// declare the images binding.
+    pub fn max_images(&self) -> usize {
+        match self.user_bindings.images {
+            true => USER_DEFINED_IMAGES_AMOUNT as usize,
+            false => 0,
+        }
+    }
+
+    pub fn validate_images_count(&self, count: usize) -> Result<(), ParametersValidationError> {
+        if count > 0 && !self.user_bindings.images {
+            return Err(ParametersValidationError::NoImagesBindingInShader);
+        }
+        if count > USER_DEFINED_IMAGES_AMOUNT as usize {
+            return Err(ParametersValidationError::TooManyImages(count));
+        }
+        Ok(())
+    }
+
+    /// Params in the layout of the user-defined uniform buffer. Params provided by
+    /// field name are mapped to that layout before validation.
+    pub fn prepare_params(
+        &self,
+        params: &ShaderParam,
+    ) -> Result<ShaderParam, ParametersValidationError> {
+        self.prepare_binding_params(
+            params,
+            USER_DEFINED_BUFFER_BINDING,
+            ParametersValidationError::NoBindingInShader,
+        )
+    }
+
+    /// Same as [`Self::prepare_params`], but for the user-defined storage buffer.
+    pub fn prepare_storage_params(
+        &self,
+        params: &ShaderParam,
+    ) -> Result<ShaderParam, ParametersValidationError> {
+        self.prepare_binding_params(
+            params,
+            USER_DEFINED_STORAGE_BINDING,
+            ParametersValidationError::NoStorageBindingInShader,
+        )
+    }
+
+    fn prepare_binding_params(
+        &self,
+        params: &ShaderParam,
+        binding: u32,
+        missing_binding_error: ParametersValidationError,
+    ) -> Result<ShaderParam, ParametersValidationError> {
+        let ty = user_binding_type(&self.shader, binding).ok_or(missing_binding_error)?;
+
+        let params = match params.has_object_fields() {
+            true => resolve_object_params(params, &params_type(ty, &self.shader)?)?,
+            false => params.clone(),
+        };
+        validate_params(&params, ty, &self.shader)?;
+        Ok(params)
+    }
+
+    /// Structure of params expected by the shader, `None` if the shader does not
+    /// declare the user-defined binding.
+    pub fn params_type(&self) -> Result<Option<ShaderParamType>, ParametersValidationError> {
+        user_binding_type(&self.shader, USER_DEFINED_BUFFER_BINDING)
+            .map(|ty| params_type(ty, &self.shader))
+            .transpose()
+    }
 
-        validate_params(params, ty, &self.shader)
+    /// Same as [`Self::params_type`], but for the user-defined storage buffer.
+    pub fn storage_params_type(
+        &self,
+    ) -> Result<Option<ShaderParamType>, ParametersValidationError> {
+        user_binding_type(&self.shader, USER_DEFINED_STORAGE_BINDING)
+            .map(|ty| params_type(ty, &self.shader))
+            .transpose()
     }
 }
+
+fn user_binding_type(shader: &naga::Module, binding: u32) -> Option<naga::Handle<naga::Type>> {
+    shader
+        .global_variables
+        .iter()
+        .find(|(_, global)| match global.binding.as_ref() {
+            Some(global_binding) => {
+                (global_binding.group, global_binding.binding)
+                    == (USER_DEFINED_BUFFER_GROUP, binding)
+            }
+
+            None => false,
+        })
+        .map(|(_, handle)| handle.ty)
+}
@@ -1,6 +1,10 @@
 use std::time::Duration;
 
-use compositor_common::scene::Resolution;
+use compositor_common::scene::{NodeId, Resolution};
+
+use crate::wgpu::texture::NodeTexture;
+
+use super::INPUT_TEXTURES_AMOUNT;
 
 #[repr(C)]
 #[derive(Debug, bytemuck::Pod, bytemuck::Zeroable, Clone, Copy)]
@@ -8,10 +12,18 @@ pub struct CommonShaderParameters {
     time: f32,
     pub texture_count: u32,
     output_resolution: [u32; 2],
+    frame_index: u32,
+    random_seed: u32,
 }
 
 impl CommonShaderParameters {
-    pub fn new(time: Duration, texture_count: u32, output_resolution: Resolution) -> Self {
+    pub fn new(
+        time: Duration,
+        texture_count: u32,
+        output_resolution: Resolution,
+        frame_index: u32,
+        random_seed: u32,
+    ) -> Self {
         Self {
             time: time.as_secs_f32(),
             texture_count,
@@ -19,6 +31,8 @@ impl CommonShaderParameters {
                 output_resolution.width as u32,
                 output_resolution.height as u32,
             ],
+            frame_index,
+            random_seed,
         }
     }
 
@@ -34,3 +48,41 @@ impl CommonShaderParameters {
         bytemuck::bytes_of(self)
     }
 }
+
+/// Per-input parameters passed to shaders in a uniform array. Layout matches
+/// `InputShaderParameters` from the shader header (array stride of 16 bytes).
+#[repr(C)]
+#[derive(Debug, bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Default)]
+pub struct InputShaderParameters {
+    resolution: [u32; 2],
+    pts_offset: f32,
+    _padding: u32,
+}
+
+pub type InputsShaderParameters = [InputShaderParameters; INPUT_TEXTURES_AMOUNT as usize];
+
+impl InputShaderParameters {
+    /// Empty inputs have zero resolution. PTS offset is the difference between
+    /// the rendered pts and pts of the input frame, it is only known for inputs
+    /// that are input streams, 0 otherwise.
+    pub fn new_array(sources: &[(&NodeId, &NodeTexture)], pts: Duration) -> InputsShaderParameters {
+        let mut params = InputsShaderParameters::default();
+        for (param, (_, node_texture)) in params.iter_mut().zip(sources) {
+            let resolution = node_texture.resolution().unwrap_or(Resolution {
+                width: 0,
+                height: 0,
+            });
+            let pts_offset = node_texture
+                .frame_pts()
+                .map(|frame_pts| pts.as_secs_f32() - frame_pts.as_secs_f32())
+                .unwrap_or(0.0);
+
+            *param = InputShaderParameters {
+                resolution: [resolution.width as u32, resolution.height as u32],
+                pts_offset,
+                _padding: 0,
+            };
+        }
+        params
+    }
+}
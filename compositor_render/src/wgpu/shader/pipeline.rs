@@ -3,7 +3,10 @@ use std::num::NonZeroU32;
 use wgpu::ShaderStages;
 
 use crate::wgpu::{
-    common_pipeline::{surface::Surfaces, Sampler, Vertex},
+    common_pipeline::{
+        surface::{MeshSurface, Surfaces},
+        Sampler, Vertex, PRIMITIVE_STATE,
+    },
     texture::Texture,
     WgpuCtx,
 };
@@ -25,21 +28,34 @@ impl Pipeline {
         device: &wgpu::Device,
         shader_source: wgpu::ShaderSource,
         uniforms_bgl: &wgpu::BindGroupLayout,
+        cull_mode: Option<wgpu::Face>,
     ) -> Self {
         let sampler = Sampler::new(device);
 
         let textures_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("shader transformation textures bgl"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                count: NonZeroU32::new(INPUT_TEXTURES_AMOUNT),
-                visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Texture {
-                    multisampled: false,
-                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    view_dimension: wgpu::TextureViewDimension::D2,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    count: NonZeroU32::new(INPUT_TEXTURES_AMOUNT),
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
                 },
-            }],
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    count: None,
+                    visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                },
+            ],
         });
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -62,13 +78,8 @@ impl Pipeline {
             label: Some("shader transformation pipeline :^)"),
             depth_stencil: None,
             primitive: wgpu::PrimitiveState {
-                conservative: false,
-                cull_mode: Some(wgpu::Face::Back),
-                front_face: wgpu::FrontFace::Ccw,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                unclipped_depth: false,
+                cull_mode,
+                ..PRIMITIVE_STATE
             },
             vertex: wgpu::VertexState {
                 buffers: &[Vertex::LAYOUT],
@@ -101,6 +112,14 @@ impl Pipeline {
         }
     }
 
+    pub fn new_sampler_bind_group(
+        &self,
+        device: &wgpu::Device,
+        sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        self.sampler.new_bind_group(device, sampler)
+    }
+
     pub fn render(
         &self,
         inputs: &wgpu::BindGroup,
@@ -108,18 +127,16 @@ impl Pipeline {
         target: &Texture,
         ctx: &WgpuCtx,
         common_parameters: CommonShaderParameters,
-        clear_color: Option<wgpu::Color>,
+        sampler: Option<&wgpu::BindGroup>,
+        mesh: Option<&MeshSurface>,
+        load: wgpu::LoadOp<wgpu::Color>,
     ) {
         let mut encoder = ctx.device.create_command_encoder(&Default::default());
-        let clear_color = clear_color.unwrap_or(wgpu::Color::TRANSPARENT);
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(clear_color),
-                        store: true,
-                    },
+                    ops: wgpu::Operations { load, store: true },
                     view: &target.view,
                     resolve_target: None,
                 })],
@@ -137,15 +154,16 @@ impl Pipeline {
 
             render_pass.set_bind_group(0, inputs, &[]);
             render_pass.set_bind_group(USER_DEFINED_BUFFER_GROUP, uniforms, &[]);
-            render_pass.set_bind_group(2, &self.sampler.bind_group, &[]);
-
-            self.surfaces
-                .draw(&mut render_pass, common_parameters.texture_count);
+            render_pass.set_bind_group(2, sampler.unwrap_or(&self.sampler.bind_group), &[]);
+
+            match mesh {
+                Some(mesh) => mesh.draw(&mut render_pass),
+                None => self
+                    .surfaces
+                    .draw(&mut render_pass, common_parameters.texture_count),
+            }
         }
 
-        // TODO: this should not submit, it should return the command buffer.
-        //       the buffer should then be put in an array with other command
-        //       buffers and submitted as a whole
-        ctx.queue.submit(Some(encoder.finish()));
+        ctx.enqueue(encoder.finish());
     }
 }
@@ -2,15 +2,25 @@ struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
     @location(2) texture_id: i32,
+    // values from a mesh registered by the user, zero for other geometry
+    @location(3) data: vec4<f32>,
 }
 
 struct CommonShaderParameters {
     time: f32,
     texture_count: u32,
     output_resolution: vec2<u32>,
+    frame_index: u32,
+    random_seed: u32,
+}
+
+struct InputShaderParameters {
+    resolution: vec2<u32>,
+    pts_offset: f32,
 }
 
 @group(0) @binding(0) var textures: binding_array<texture_2d<f32>, 16>;
+@group(0) @binding(1) var<uniform> inputs_params: array<InputShaderParameters, 16>;
 @group(2) @binding(0) var sampler_: sampler;
 
 var<push_constant> common_params: CommonShaderParameters;
@@ -1,12 +1,17 @@
-use std::{io::Write, mem};
+use std::{io::Write, mem, time::Duration};
 
 use bytes::{BufMut, Bytes, BytesMut};
-use compositor_common::{scene::Resolution, Frame};
+use compositor_common::{
+    frame::{FrameData, FrameFormat},
+    scene::Resolution,
+    Frame,
+};
 use crossbeam_channel::bounded;
 use log::error;
 use wgpu::{Buffer, BufferAsyncError, MapMode};
 
 use self::{
+    pool::PooledTexture,
     utils::{pad_to_256, texture_size_to_resolution},
     yuv::YUVPendingDownload,
 };
@@ -15,32 +20,110 @@ use super::WgpuCtx;
 
 mod base;
 mod bgra;
+mod nv12;
+mod pool;
 mod rgba;
 pub mod utils;
 mod yuv;
 
 pub type BGRATexture = bgra::BGRATexture;
+pub type NV12Texture = nv12::NV12Texture;
 pub type RGBATexture = rgba::RGBATexture;
 pub type YUVTextures = yuv::YUVTextures;
 
 pub type Texture = base::Texture;
 
-pub struct InputTextureState {
-    textures: YUVTextures,
-    bind_group: wgpu::BindGroup,
+pub use pool::{TexturePool, TexturePoolUsage};
+
+/// Input frame uploaded in its own pixel format, so it can be converted to RGBA
+/// on the GPU.
+pub enum InputTextureState {
+    PlanarYuv {
+        textures: YUVTextures,
+        bind_group: wgpu::BindGroup,
+    },
+    Nv12 {
+        texture: NV12Texture,
+        bind_group: wgpu::BindGroup,
+    },
+    Rgba(RGBATexture),
 }
 
 impl InputTextureState {
-    pub fn yuv_textures(&self) -> &YUVTextures {
-        &self.textures
+    fn new(ctx: &WgpuCtx, frame: &Frame) -> Self {
+        match frame.data {
+            FrameData::PlanarYuv(_) => {
+                let textures = YUVTextures::new(ctx, frame.resolution, frame.format);
+                let bind_group = textures.new_bind_group(ctx, ctx.format.yuv_layout());
+                Self::PlanarYuv {
+                    textures,
+                    bind_group,
+                }
+            }
+            FrameData::Nv12(_) => {
+                let texture = NV12Texture::new(ctx, frame.resolution, frame.format);
+                let bind_group = texture.new_bind_group(ctx, ctx.format.nv12_layout());
+                Self::Nv12 {
+                    texture,
+                    bind_group,
+                }
+            }
+            FrameData::Rgba(_) => Self::Rgba(RGBATexture::new(ctx, frame.resolution)),
+        }
     }
 
-    pub fn bind_group(&self) -> &wgpu::BindGroup {
-        &self.bind_group
+    /// Whether the frame can be uploaded without recreating textures.
+    fn is_compatible(&self, frame: &Frame) -> bool {
+        match (self, &frame.data) {
+            (Self::PlanarYuv { textures, .. }, FrameData::PlanarYuv(_)) => {
+                textures.resolution == frame.resolution && textures.format == frame.format
+            }
+            (Self::Nv12 { texture, .. }, FrameData::Nv12(_)) => {
+                texture.resolution == frame.resolution && texture.format == frame.format
+            }
+            (Self::Rgba(texture), FrameData::Rgba(_)) => {
+                texture_size_to_resolution(&texture.size()) == frame.resolution
+            }
+            _ => false,
+        }
+    }
+
+    fn upload(&self, ctx: &WgpuCtx, data: &FrameData) {
+        match (self, data) {
+            (Self::PlanarYuv { textures, .. }, FrameData::PlanarYuv(data)) => {
+                textures.upload(ctx, data)
+            }
+            (Self::Nv12 { texture, .. }, FrameData::Nv12(data)) => texture.upload(ctx, data),
+            (Self::Rgba(texture), FrameData::Rgba(data)) => texture.upload(ctx, data),
+            _ => error!("Frame data does not match the format of the input texture."),
+        }
+    }
+
+    /// Writes the frame as RGBA to `dst`, which needs to have the same resolution.
+    pub fn convert_to_rgba(&self, ctx: &WgpuCtx, dst: &RGBATexture) {
+        match self {
+            Self::PlanarYuv {
+                textures,
+                bind_group,
+            } => ctx
+                .format
+                .convert_yuv_to_rgba(ctx, (textures, bind_group), dst),
+            Self::Nv12 {
+                texture,
+                bind_group,
+            } => ctx
+                .format
+                .convert_nv12_to_rgba(ctx, (texture, bind_group), dst),
+            Self::Rgba(texture) => texture.copy_to_texture(ctx, dst),
+        }
     }
 
     pub fn resolution(&self) -> Resolution {
-        self.textures.resolution
+        match self {
+            Self::PlanarYuv { textures, .. } => textures.resolution,
+            Self::Nv12 { texture, .. } => texture.resolution,
+            Self::Rgba(texture) => texture_size_to_resolution(&texture.size()),
+        }
     }
 }
 
@@ -56,33 +139,20 @@ impl InputTexture {
     }
 
     pub fn upload(&mut self, ctx: &WgpuCtx, frame: Frame) {
-        let state = self.ensure_size(ctx, frame.resolution);
-        state.textures.upload(ctx, &frame.data)
+        let state = self.ensure_format(ctx, &frame);
+        state.upload(ctx, &frame.data)
     }
 
-    fn ensure_size<'a>(
-        &'a mut self,
-        ctx: &WgpuCtx,
-        new_resolution: Resolution,
-    ) -> &'a InputTextureState {
-        fn new_state(ctx: &WgpuCtx, new_resolution: Resolution) -> InputTextureState {
-            let textures = YUVTextures::new(ctx, new_resolution);
-            let bind_group = textures.new_bind_group(ctx, ctx.format.yuv_layout());
-            InputTextureState {
-                textures,
-                bind_group,
-            }
-        }
-
+    fn ensure_format<'a>(&'a mut self, ctx: &WgpuCtx, frame: &Frame) -> &'a InputTextureState {
         self.0 = match self.0.replace(OptionalState::None) {
             OptionalState::Some(state) | OptionalState::NoneWithOldState(state) => {
-                if state.textures.resolution == new_resolution {
+                if state.is_compatible(frame) {
                     OptionalState::Some(state)
                 } else {
-                    OptionalState::Some(new_state(ctx, new_resolution))
+                    OptionalState::Some(InputTextureState::new(ctx, frame))
                 }
             }
-            OptionalState::None => OptionalState::Some(new_state(ctx, new_resolution)),
+            OptionalState::None => OptionalState::Some(InputTextureState::new(ctx, frame)),
         };
         self.state().unwrap()
     }
@@ -99,18 +169,20 @@ impl Default for InputTexture {
 }
 
 pub struct NodeTextureState {
-    texture: RGBATexture,
+    texture: PooledTexture,
     bind_group: wgpu::BindGroup,
+    mipmaps: bool,
 }
 
 impl NodeTextureState {
-    fn new(ctx: &WgpuCtx, resolution: Resolution) -> Self {
-        let texture = RGBATexture::new(ctx, resolution);
+    fn new(ctx: &WgpuCtx, resolution: Resolution, mipmaps: bool) -> Self {
+        let texture = ctx.texture_pool.get(ctx, resolution, mipmaps);
         let bind_group = texture.new_bind_group(ctx, ctx.format.rgba_layout());
 
         Self {
             texture,
             bind_group,
+            mipmaps,
         }
     }
 
@@ -127,15 +199,55 @@ impl NodeTextureState {
     }
 }
 
-pub struct NodeTexture(OptionalState<NodeTextureState>);
+pub struct NodeTexture {
+    state: OptionalState<NodeTextureState>,
+    /// PTS of the input frame that was uploaded to this texture. Only set on
+    /// textures of input stream nodes.
+    frame_pts: Option<Duration>,
+    /// Allocate a full mip chain, so nodes that downscale this texture can
+    /// use trilinear filtering.
+    mipmaps: bool,
+}
 
 impl NodeTexture {
     pub fn new() -> Self {
-        Self(OptionalState::new())
+        Self {
+            state: OptionalState::new(),
+            frame_pts: None,
+            mipmaps: false,
+        }
+    }
+
+    pub fn set_mipmaps(&mut self, mipmaps: bool) {
+        self.mipmaps = mipmaps
+    }
+
+    /// Populates mip levels from the first level. Noop if texture was allocated
+    /// without mipmaps.
+    pub fn generate_mipmaps(&self, ctx: &WgpuCtx) {
+        if let Some(state) = self.state.state() {
+            if state.mipmaps {
+                ctx.utils.generate_mipmaps(ctx, &state.texture);
+            }
+        }
     }
 
     pub fn clear(&mut self) {
-        self.0.clear()
+        self.state.clear();
+        self.frame_pts = None;
+    }
+
+    pub fn set_frame_pts(&mut self, pts: Duration) {
+        self.frame_pts = Some(pts)
+    }
+
+    pub fn frame_pts(&self) -> Option<Duration> {
+        self.frame_pts
+    }
+
+    /// Content of the texture no longer comes from an input frame.
+    pub fn clear_frame_pts(&mut self) {
+        self.frame_pts = None
     }
 
     pub fn ensure_size<'a>(
@@ -143,33 +255,35 @@ impl NodeTexture {
         ctx: &WgpuCtx,
         new_resolution: Resolution,
     ) -> &'a NodeTextureState {
-        self.0 = match self.0.replace(OptionalState::None) {
+        self.state = match self.state.replace(OptionalState::None) {
             OptionalState::NoneWithOldState(state) | OptionalState::Some(state) => {
-                if texture_size_to_resolution(&state.texture.size()) == new_resolution {
+                if texture_size_to_resolution(&state.texture.size()) == new_resolution
+                    && state.mipmaps == self.mipmaps
+                {
                     OptionalState::Some(state)
                 } else {
-                    let new_inner = NodeTextureState::new(ctx, new_resolution);
+                    let new_inner = NodeTextureState::new(ctx, new_resolution, self.mipmaps);
                     OptionalState::Some(new_inner)
                 }
             }
             OptionalState::None => {
-                let new_inner = NodeTextureState::new(ctx, new_resolution);
+                let new_inner = NodeTextureState::new(ctx, new_resolution, self.mipmaps);
                 OptionalState::Some(new_inner)
             }
         };
-        self.0.state().unwrap()
+        self.state.state().unwrap()
     }
 
     pub fn state(&self) -> Option<&NodeTextureState> {
-        self.0.state()
+        self.state.state()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.0.state().is_none()
+        self.state.state().is_none()
     }
 
     pub fn resolution(&self) -> Option<Resolution> {
-        self.0.state().map(NodeTextureState::resolution)
+        self.state.state().map(NodeTextureState::resolution)
     }
 }
 
@@ -186,8 +300,8 @@ pub struct OutputTexture {
 }
 
 impl OutputTexture {
-    pub fn new(ctx: &WgpuCtx, resolution: Resolution) -> Self {
-        let textures = YUVTextures::new(ctx, resolution);
+    pub fn new(ctx: &WgpuCtx, resolution: Resolution, format: FrameFormat) -> Self {
+        let textures = YUVTextures::new(ctx, resolution, format);
         let buffers = textures.new_download_buffers(ctx);
 
         Self {
@@ -205,6 +319,10 @@ impl OutputTexture {
         self.resolution
     }
 
+    pub fn format(&self) -> FrameFormat {
+        self.textures.format
+    }
+
     pub fn start_download<'a>(
         &'a self,
         ctx: &WgpuCtx,
@@ -227,7 +345,11 @@ impl OutputTexture {
         size: wgpu::Extent3d,
         source: &'a Buffer,
     ) -> impl FnOnce() -> Result<Bytes, BufferAsyncError> + 'a {
-        let buffer = BytesMut::with_capacity((size.width * size.height) as usize);
+        let bytes_per_sample = self.textures.format.pixel_format.bytes_per_sample();
+        let row_size = size.width as usize * bytes_per_sample;
+        // Must match the layout used in `Texture::copy_to_buffer`
+        let padded_row_size = pad_to_256(size.width) as usize * bytes_per_sample;
+        let buffer = BytesMut::with_capacity(row_size * size.height as usize);
         let (s, r) = bounded(1);
         source.slice(..).map_async(MapMode::Read, move |result| {
             if let Err(err) = s.send(result) {
@@ -240,9 +362,8 @@ impl OutputTexture {
             let mut buffer = buffer.writer();
             {
                 let range = source.slice(..).get_mapped_range();
-                let chunks = range.chunks(pad_to_256(size.width) as usize);
-                for chunk in chunks {
-                    buffer.write_all(&chunk[..size.width as usize]).unwrap();
+                for chunk in range.chunks(padded_row_size) {
+                    buffer.write_all(&chunk[..row_size]).unwrap();
                 }
             };
             source.unmap();
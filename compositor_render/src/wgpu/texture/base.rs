@@ -5,7 +5,10 @@ use super::utils::pad_to_256;
 #[derive(Debug)]
 pub struct Texture {
     pub texture: wgpu::Texture,
+    /// View of the first mip level, use it as a render target.
     pub view: wgpu::TextureView,
+    /// View of all mip levels, use it to sample the texture.
+    pub sampled_view: wgpu::TextureView,
 }
 
 impl Texture {
@@ -20,11 +23,22 @@ impl Texture {
         size: wgpu::Extent3d,
         format: wgpu::TextureFormat,
         usage: wgpu::TextureUsages,
+    ) -> Self {
+        Self::new_with_mip_levels(ctx, label, size, format, usage, 1)
+    }
+
+    pub fn new_with_mip_levels(
+        ctx: &WgpuCtx,
+        label: Option<&str>,
+        size: wgpu::Extent3d,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+        mip_level_count: u32,
     ) -> Self {
         let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
             label,
             size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
@@ -32,9 +46,29 @@ impl Texture {
             view_formats: &[format],
         });
 
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            mip_level_count: Some(1),
+            ..Default::default()
+        });
+        let sampled_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            view,
+            sampled_view,
+        }
+    }
 
-        Self { texture, view }
+    pub fn mip_level_count(&self) -> u32 {
+        self.texture.mip_level_count()
+    }
+
+    pub fn mip_level_view(&self, mip_level: u32) -> wgpu::TextureView {
+        self.texture.create_view(&wgpu::TextureViewDescriptor {
+            base_mip_level: mip_level,
+            mip_level_count: Some(1),
+            ..Default::default()
+        })
     }
 
     pub fn size(&self) -> wgpu::Extent3d {
@@ -0,0 +1,102 @@
+use compositor_common::{
+    frame::{FrameFormat, Nv12Data},
+    scene::Resolution,
+};
+
+use crate::wgpu::WgpuCtx;
+
+use super::base::Texture;
+
+/// Y plane and a single plane with interleaved U and V samples. Bind group has
+/// the same layout as the first two entries of [`super::YUVTextures`], so both
+/// can share a conversion shader.
+pub struct NV12Texture {
+    pub(super) planes: [Texture; 2],
+    pub(super) resolution: Resolution,
+    pub(super) format: FrameFormat,
+}
+
+impl NV12Texture {
+    pub fn new(ctx: &WgpuCtx, resolution: Resolution, format: FrameFormat) -> Self {
+        Self {
+            planes: [
+                Self::new_plane(
+                    ctx,
+                    resolution.width,
+                    resolution.height,
+                    wgpu::TextureFormat::R8Unorm,
+                ),
+                Self::new_plane(
+                    ctx,
+                    resolution.width / 2,
+                    resolution.height / 2,
+                    wgpu::TextureFormat::Rg8Unorm,
+                ),
+            ],
+            resolution,
+            format,
+        }
+    }
+
+    pub fn format(&self) -> FrameFormat {
+        self.format
+    }
+
+    fn new_plane(
+        ctx: &WgpuCtx,
+        width: usize,
+        height: usize,
+        format: wgpu::TextureFormat,
+    ) -> Texture {
+        Texture::new(
+            ctx,
+            None,
+            wgpu::Extent3d {
+                width: width as u32,
+                height: height as u32,
+                depth_or_array_layers: 1,
+            },
+            format,
+            wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+        )
+    }
+
+    pub fn new_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        let create_entry = |binding: u32| wgpu::BindGroupLayoutEntry {
+            binding,
+            ty: Texture::DEFAULT_BINDING_TYPE,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            count: None,
+        };
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("nv12 textures bind group layout"),
+            entries: &[create_entry(0), create_entry(1)],
+        })
+    }
+
+    pub(super) fn new_bind_group(
+        &self,
+        ctx: &WgpuCtx,
+        layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::BindGroup {
+        ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("nv12 textures bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.planes[0].view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&self.planes[1].view),
+                },
+            ],
+        })
+    }
+
+    pub fn upload(&self, ctx: &WgpuCtx, data: &Nv12Data) {
+        self.planes[0].upload_data(&ctx.queue, &data.y_plane, 1);
+        self.planes[1].upload_data(&ctx.queue, &data.uv_plane, 2);
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::Deref,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use compositor_common::scene::Resolution;
+
+use crate::wgpu::WgpuCtx;
+
+use super::RGBATexture;
+
+/// Idle textures that were not reused for this long are released.
+const MAX_IDLE_TIME: Duration = Duration::from_secs(10);
+/// Released textures are not kept if idle textures would exceed this size.
+const MAX_IDLE_BYTES: usize = 512 * 1024 * 1024;
+
+/// Node textures that are no longer used by any node. Scene update recreates all
+/// nodes, so without the pool every update would allocate all textures again.
+#[derive(Default)]
+pub struct TexturePool(Arc<Mutex<PoolState>>);
+
+#[derive(Default)]
+struct PoolState {
+    idle: HashMap<PoolKey, Vec<IdleTexture>>,
+    /// Size of all textures allocated by the pool, both idle and in use.
+    allocated_bytes: usize,
+    idle_bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PoolKey {
+    resolution: Resolution,
+    mipmaps: bool,
+}
+
+struct IdleTexture {
+    texture: RGBATexture,
+    released_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TexturePoolUsage {
+    /// Size of all node textures, including idle ones.
+    pub allocated_bytes: usize,
+    pub idle_bytes: usize,
+    pub idle_textures: usize,
+}
+
+impl TexturePool {
+    pub fn get(&self, ctx: &WgpuCtx, resolution: Resolution, mipmaps: bool) -> PooledTexture {
+        let key = PoolKey {
+            resolution,
+            mipmaps,
+        };
+        let mut state = self.0.lock().unwrap();
+        let idle = state.idle.get_mut(&key).and_then(Vec::pop);
+        let texture = match idle {
+            Some(IdleTexture { texture, .. }) => {
+                state.idle_bytes -= texture_size_bytes(&texture);
+                texture
+            }
+            None => {
+                let texture = match mipmaps {
+                    true => RGBATexture::new_with_mipmaps(ctx, resolution),
+                    false => RGBATexture::new(ctx, resolution),
+                };
+                state.allocated_bytes += texture_size_bytes(&texture);
+                texture
+            }
+        };
+
+        PooledTexture {
+            texture: Some(texture),
+            key,
+            pool: Arc::downgrade(&self.0),
+        }
+    }
+
+    /// Releases textures that were not reused for `MAX_IDLE_TIME`.
+    pub fn trim(&self) {
+        let mut state = self.0.lock().unwrap();
+        let PoolState {
+            idle,
+            allocated_bytes,
+            idle_bytes,
+        } = &mut *state;
+
+        let now = Instant::now();
+        for textures in idle.values_mut() {
+            textures.retain(|idle| {
+                if now.duration_since(idle.released_at) < MAX_IDLE_TIME {
+                    return true;
+                }
+                let size = texture_size_bytes(&idle.texture);
+                *allocated_bytes -= size;
+                *idle_bytes -= size;
+                false
+            });
+        }
+        idle.retain(|_, textures| !textures.is_empty());
+    }
+
+    pub fn usage(&self) -> TexturePoolUsage {
+        let state = self.0.lock().unwrap();
+        TexturePoolUsage {
+            allocated_bytes: state.allocated_bytes,
+            idle_bytes: state.idle_bytes,
+            idle_textures: state.idle.values().map(Vec::len).sum(),
+        }
+    }
+}
+
+impl PoolState {
+    fn release(&mut self, key: PoolKey, texture: RGBATexture) {
+        let size = texture_size_bytes(&texture);
+        if self.idle_bytes + size > MAX_IDLE_BYTES {
+            self.allocated_bytes -= size;
+            return;
+        }
+        self.idle_bytes += size;
+        self.idle.entry(key).or_default().push(IdleTexture {
+            texture,
+            released_at: Instant::now(),
+        });
+    }
+}
+
+impl fmt::Debug for TexturePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TexturePool").field(&self.usage()).finish()
+    }
+}
+
+/// Texture taken from the pool, it goes back to the pool when dropped.
+pub struct PooledTexture {
+    /// Only `None` while the texture is dropped.
+    texture: Option<RGBATexture>,
+    key: PoolKey,
+    pool: Weak<Mutex<PoolState>>,
+}
+
+impl Deref for PooledTexture {
+    type Target = RGBATexture;
+
+    fn deref(&self) -> &RGBATexture {
+        self.texture.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledTexture {
+    fn drop(&mut self) {
+        let (Some(texture), Some(pool)) = (self.texture.take(), self.pool.upgrade()) else {
+            return;
+        };
+        pool.lock().unwrap().release(self.key, texture);
+    }
+}
+
+fn texture_size_bytes(texture: &RGBATexture) -> usize {
+    let size = texture.size();
+    (0..texture.texture().mip_level_count())
+        .map(|level| {
+            let width = usize::max(size.width as usize >> level, 1);
+            let height = usize::max(size.height as usize >> level, 1);
+            width * height * 4
+        })
+        .sum()
+}
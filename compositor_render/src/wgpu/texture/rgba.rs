@@ -1,14 +1,28 @@
+use bytes::{Bytes, BytesMut};
 use compositor_common::scene::Resolution;
+use crossbeam_channel::bounded;
 
 use crate::wgpu::WgpuCtx;
 
-use super::base::Texture;
+use super::{base::Texture, utils::pad_to_256};
 
 pub struct RGBATexture(Texture);
 
 impl RGBATexture {
     pub fn new(ctx: &WgpuCtx, resolution: Resolution) -> Self {
-        Self(Texture::new(
+        Self::new_with_mip_levels(ctx, resolution, 1)
+    }
+
+    /// Texture with a full mip chain, mip levels above 0 need to be populated
+    /// with [`crate::wgpu::utils::TextureUtils::generate_mipmaps`].
+    pub fn new_with_mipmaps(ctx: &WgpuCtx, resolution: Resolution) -> Self {
+        let max_dimension = usize::max(resolution.width, resolution.height).max(1) as u32;
+        let mip_level_count = u32::BITS - max_dimension.leading_zeros();
+        Self::new_with_mip_levels(ctx, resolution, mip_level_count)
+    }
+
+    fn new_with_mip_levels(ctx: &WgpuCtx, resolution: Resolution, mip_level_count: u32) -> Self {
+        Self(Texture::new_with_mip_levels(
             ctx,
             None,
             wgpu::Extent3d {
@@ -21,6 +35,7 @@ impl RGBATexture {
                 | wgpu::TextureUsages::COPY_DST
                 | wgpu::TextureUsages::COPY_SRC
                 | wgpu::TextureUsages::TEXTURE_BINDING,
+            mip_level_count,
         ))
     }
 
@@ -36,7 +51,7 @@ impl RGBATexture {
         })
     }
 
-    pub(super) fn new_bind_group(
+    pub(crate) fn new_bind_group(
         &self,
         ctx: &WgpuCtx,
         layout: &wgpu::BindGroupLayout,
@@ -55,6 +70,21 @@ impl RGBATexture {
         self.0.upload_data(&ctx.queue, data, 4);
     }
 
+    /// Copies the first mip level, `dst` needs to have the same size.
+    pub fn copy_to_texture(&self, ctx: &WgpuCtx, dst: &RGBATexture) {
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("RGBA texture copy command encoder"),
+            });
+        encoder.copy_texture_to_texture(
+            self.0.texture.as_image_copy(),
+            dst.0.texture.as_image_copy(),
+            self.size(),
+        );
+        ctx.submit(encoder.finish());
+    }
+
     pub fn new_download_buffer(&self, ctx: &WgpuCtx) -> wgpu::Buffer {
         self.0.new_download_buffer(ctx)
     }
@@ -63,6 +93,43 @@ impl RGBATexture {
         self.0.copy_to_buffer(encoder, buffer);
     }
 
+    /// Blocks until content of the texture is copied to the CPU. Returned data has
+    /// no row padding.
+    pub fn download(&self, ctx: &WgpuCtx) -> Result<Bytes, wgpu::BufferAsyncError> {
+        let buffer = self.new_download_buffer(ctx);
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("RGBA texture download command encoder"),
+            });
+        self.copy_to_buffer(&mut encoder, &buffer);
+        ctx.submit(encoder.finish());
+
+        let (sender, receiver) = bounded(1);
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                // Receiver is alive until the device is polled
+                sender.send(result).unwrap();
+            });
+        ctx.device.poll(wgpu::MaintainBase::Wait);
+        receiver.recv().unwrap()?;
+
+        let size = self.size();
+        let row_size = size.width as usize * 4;
+        // Must match the layout used in `Texture::copy_to_buffer`
+        let padded_row_size = pad_to_256(size.width) as usize * 4;
+        let mut data = BytesMut::with_capacity(row_size * size.height as usize);
+        {
+            let range = buffer.slice(..).get_mapped_range();
+            for chunk in range.chunks(padded_row_size) {
+                data.extend_from_slice(&chunk[..row_size]);
+            }
+        }
+        buffer.unmap();
+        Ok(data.freeze())
+    }
+
     pub fn size(&self) -> wgpu::Extent3d {
         self.0.size()
     }
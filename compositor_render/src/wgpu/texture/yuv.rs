@@ -1,7 +1,11 @@
 use std::marker::PhantomData;
 
 use bytes::Bytes;
-use compositor_common::{frame::YuvData, scene::Resolution};
+use compositor_common::{
+    frame::{FrameFormat, PixelFormat, YuvData},
+    scene::Resolution,
+    util::colors::RGBColor,
+};
 use wgpu::Buffer;
 
 use crate::wgpu::WgpuCtx;
@@ -46,17 +50,34 @@ where
 pub struct YUVTextures {
     pub(super) planes: [Texture; 3],
     pub(super) resolution: Resolution,
+    pub(super) format: FrameFormat,
 }
 
 impl YUVTextures {
-    pub fn new(ctx: &WgpuCtx, resolution: Resolution) -> Self {
+    pub fn new(ctx: &WgpuCtx, resolution: Resolution, format: FrameFormat) -> Self {
+        let plane_format = match format.pixel_format {
+            PixelFormat::Yuv420p => wgpu::TextureFormat::R8Unorm,
+            // 10-bit samples are stored as is, shaders need to rescale them
+            PixelFormat::Yuv420p10le => wgpu::TextureFormat::R16Unorm,
+        };
         Self {
             planes: [
-                Self::new_plane(ctx, resolution.width, resolution.height),
-                Self::new_plane(ctx, resolution.width / 2, resolution.height / 2),
-                Self::new_plane(ctx, resolution.width / 2, resolution.height / 2),
+                Self::new_plane(ctx, resolution.width, resolution.height, plane_format),
+                Self::new_plane(
+                    ctx,
+                    resolution.width / 2,
+                    resolution.height / 2,
+                    plane_format,
+                ),
+                Self::new_plane(
+                    ctx,
+                    resolution.width / 2,
+                    resolution.height / 2,
+                    plane_format,
+                ),
             ],
             resolution,
+            format,
         }
     }
 
@@ -64,7 +85,16 @@ impl YUVTextures {
         &self.planes[i]
     }
 
-    fn new_plane(ctx: &WgpuCtx, width: usize, height: usize) -> Texture {
+    pub fn format(&self) -> FrameFormat {
+        self.format
+    }
+
+    fn new_plane(
+        ctx: &WgpuCtx,
+        width: usize,
+        height: usize,
+        format: wgpu::TextureFormat,
+    ) -> Texture {
         Texture::new(
             ctx,
             None,
@@ -73,7 +103,7 @@ impl YUVTextures {
                 height: height as u32,
                 depth_or_array_layers: 1,
             },
-            wgpu::TextureFormat::R8Unorm,
+            format,
             wgpu::TextureUsages::RENDER_ATTACHMENT
                 | wgpu::TextureUsages::COPY_DST
                 | wgpu::TextureUsages::COPY_SRC
@@ -138,12 +168,25 @@ impl YUVTextures {
             self.planes[plane].copy_to_buffer(&mut encoder, &buffers[plane]);
         }
 
-        ctx.queue.submit(Some(encoder.finish()));
+        ctx.submit(encoder.finish());
     }
 
     pub fn upload(&self, ctx: &WgpuCtx, data: &YuvData) {
-        self.planes[0].upload_data(&ctx.queue, &data.y_plane, 1);
-        self.planes[1].upload_data(&ctx.queue, &data.u_plane, 1);
-        self.planes[2].upload_data(&ctx.queue, &data.v_plane, 1);
+        let bytes_per_sample = self.format.pixel_format.bytes_per_sample() as u32;
+        self.planes[0].upload_data(&ctx.queue, &data.y_plane, bytes_per_sample);
+        self.planes[1].upload_data(&ctx.queue, &data.u_plane, bytes_per_sample);
+        self.planes[2].upload_data(&ctx.queue, &data.v_plane, bytes_per_sample);
+    }
+
+    pub fn fill_with_color(&self, ctx: &WgpuCtx, color: RGBColor) {
+        let max_stored_value = match self.format.pixel_format.bytes_per_sample() {
+            1 => u8::MAX as f32,
+            _ => u16::MAX as f32,
+        };
+        let samples = self.format.rgb_to_yuv_samples(color);
+        for (plane, sample) in self.planes.iter().zip(samples) {
+            ctx.utils
+                .fill_r8_with_value(ctx, plane, sample as f32 / max_stored_value);
+        }
     }
 }
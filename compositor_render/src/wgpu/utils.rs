@@ -1,22 +1,118 @@
-use self::r8_fill_with_color::R8FillWithValue;
+use compositor_common::{
+    renderer_spec::NineSliceInsets, scene::test_pattern::TestPattern, util::colors::RGBAColor,
+};
 
-use super::{texture::Texture, WgpuCtx};
+use self::{
+    layers::Layers, mipmap::MipmapGenerator, nine_slice::NineSlice,
+    r8_fill_with_color::R8FillWithValue, test_pattern::TestPatternGenerator, watermark::Watermark,
+};
 
+use super::{
+    texture::{RGBATexture, Texture},
+    WgpuCtx,
+};
+
+mod layers;
+mod mipmap;
+mod nine_slice;
 mod r8_fill_with_color;
+mod test_pattern;
+mod watermark;
+
+pub use test_pattern::PatternTimecode;
+pub use watermark::WatermarkRect;
 
 #[derive(Debug)]
 pub struct TextureUtils {
     pub r8_fill_with_value: R8FillWithValue,
+    pub nine_slice: NineSlice,
+    pub mipmap: MipmapGenerator,
+    pub layers: Layers,
+    pub watermark: Watermark,
+    pub test_pattern: TestPatternGenerator,
 }
 
 impl TextureUtils {
     pub fn new(device: &wgpu::Device) -> Self {
         Self {
             r8_fill_with_value: R8FillWithValue::new(device),
+            nine_slice: NineSlice::new(device),
+            mipmap: MipmapGenerator::new(device),
+            layers: Layers::new(device),
+            watermark: Watermark::new(device),
+            test_pattern: TestPatternGenerator::new(device),
         }
     }
 
     pub fn fill_r8_with_value(&self, ctx: &WgpuCtx, dst: &Texture, value: f32) {
         self.r8_fill_with_value.fill(ctx, dst, value)
     }
+
+    pub fn render_nine_slice(
+        &self,
+        ctx: &WgpuCtx,
+        src: &RGBATexture,
+        dst: &RGBATexture,
+        insets: NineSliceInsets,
+    ) {
+        self.nine_slice.render(ctx, src, dst, insets)
+    }
+
+    pub fn fill_rgba_with_color(&self, ctx: &WgpuCtx, dst: &RGBATexture, color: RGBAColor) {
+        let RGBAColor(r, g, b, a) = color;
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Fill with color command encoder"),
+            });
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Fill with color render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &dst.texture().view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: r as f64 / 255.0,
+                        g: g as f64 / 255.0,
+                        b: b as f64 / 255.0,
+                        a: a as f64 / 255.0,
+                    }),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        ctx.submit(encoder.finish());
+    }
+
+    pub fn render_layers(&self, ctx: &WgpuCtx, layers: &[&RGBATexture], dst: &RGBATexture) {
+        self.layers.render(ctx, layers, dst)
+    }
+
+    pub fn render_watermark(
+        &self,
+        ctx: &WgpuCtx,
+        base: Option<&RGBATexture>,
+        watermark: &RGBATexture,
+        dst: &RGBATexture,
+        rect: WatermarkRect,
+        opacity: f32,
+    ) {
+        self.watermark
+            .render(ctx, base, watermark, dst, rect, opacity)
+    }
+
+    pub fn render_test_pattern(
+        &self,
+        ctx: &WgpuCtx,
+        dst: &RGBATexture,
+        pattern: TestPattern,
+        timecode: Option<PatternTimecode>,
+    ) {
+        self.test_pattern.render(ctx, dst, pattern, timecode)
+    }
+
+    pub fn generate_mipmaps(&self, ctx: &WgpuCtx, texture: &RGBATexture) {
+        self.mipmap.generate(ctx, texture)
+    }
 }
@@ -0,0 +1,101 @@
+use crate::wgpu::{
+    common_pipeline::{surface::SingleSurface, Sampler, Vertex, PRIMITIVE_STATE},
+    texture::RGBATexture,
+    WgpuCtx,
+};
+
+/// Draws textures stretched to the target size on top of each other (the first
+/// one at the bottom), blending them based on the alpha channel.
+#[derive(Debug)]
+pub struct Layers {
+    pipeline: wgpu::RenderPipeline,
+    sampler: Sampler,
+    surface: SingleSurface,
+    rgba_layout: wgpu::BindGroupLayout,
+}
+
+impl Layers {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader_module = device.create_shader_module(wgpu::include_wgsl!("layers.wgsl"));
+        let sampler = Sampler::new(device);
+        let surface = SingleSurface::new(device);
+        let rgba_layout = RGBATexture::new_bind_group_layout(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layers render pipeline layout"),
+            bind_group_layouts: &[&rgba_layout, &sampler.bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Layers render pipeline"),
+            layout: Some(&pipeline_layout),
+            primitive: PRIMITIVE_STATE,
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[Vertex::LAYOUT],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    write_mask: wgpu::ColorWrites::all(),
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                })],
+            }),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            sampler,
+            surface,
+            rgba_layout,
+        }
+    }
+
+    pub fn render(&self, ctx: &WgpuCtx, layers: &[&RGBATexture], dst: &RGBATexture) {
+        let bind_groups: Vec<_> = layers
+            .iter()
+            .map(|layer| layer.new_bind_group(ctx, &self.rgba_layout))
+            .collect();
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Layers command encoder"),
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Layers render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                    view: &dst.texture().view,
+                    resolve_target: None,
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(1, &self.sampler.bind_group, &[]);
+            for bind_group in &bind_groups {
+                render_pass.set_bind_group(0, bind_group, &[]);
+                self.surface.draw(&mut render_pass);
+            }
+        }
+
+        ctx.submit(encoder.finish());
+    }
+}
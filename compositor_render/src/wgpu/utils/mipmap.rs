@@ -0,0 +1,107 @@
+use crate::wgpu::{
+    common_pipeline::{surface::SingleSurface, Sampler, Vertex, PRIMITIVE_STATE},
+    texture::RGBATexture,
+    WgpuCtx,
+};
+
+/// Populates mip levels of a texture by downscaling each level from the previous one.
+#[derive(Debug)]
+pub struct MipmapGenerator {
+    pipeline: wgpu::RenderPipeline,
+    sampler: Sampler,
+    surface: SingleSurface,
+    rgba_layout: wgpu::BindGroupLayout,
+}
+
+impl MipmapGenerator {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader_module = device.create_shader_module(wgpu::include_wgsl!("mipmap.wgsl"));
+        let sampler = Sampler::new(device);
+        let surface = SingleSurface::new(device);
+        let rgba_layout = RGBATexture::new_bind_group_layout(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mipmap render pipeline layout"),
+            bind_group_layouts: &[&rgba_layout, &sampler.bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mipmap render pipeline"),
+            layout: Some(&pipeline_layout),
+            primitive: PRIMITIVE_STATE,
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[Vertex::LAYOUT],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    write_mask: wgpu::ColorWrites::all(),
+                    blend: None,
+                })],
+            }),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            sampler,
+            surface,
+            rgba_layout,
+        }
+    }
+
+    pub fn generate(&self, ctx: &WgpuCtx, texture: &RGBATexture) {
+        let texture = texture.texture();
+        let views: Vec<_> = (0..texture.mip_level_count())
+            .map(|level| texture.mip_level_view(level))
+            .collect();
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Mipmap command encoder"),
+            });
+
+        for (src, dst) in views.iter().zip(views.iter().skip(1)) {
+            let src_bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Mipmap source bind group"),
+                layout: &self.rgba_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(src),
+                }],
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Mipmap render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                    view: dst,
+                    resolve_target: None,
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &src_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.sampler.bind_group, &[]);
+            self.surface.draw(&mut render_pass);
+        }
+
+        ctx.submit(encoder.finish());
+    }
+}
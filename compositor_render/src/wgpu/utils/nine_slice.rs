@@ -0,0 +1,129 @@
+use compositor_common::renderer_spec::NineSliceInsets;
+use wgpu::ShaderStages;
+
+use crate::wgpu::{
+    common_pipeline::{surface::SingleSurface, Sampler, Vertex, PRIMITIVE_STATE},
+    texture::RGBATexture,
+    WgpuCtx,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct NineSliceParams {
+    insets: [f32; 4],
+    source_resolution: [f32; 2],
+    target_resolution: [f32; 2],
+}
+
+/// Draws a texture stretched to the target size, where corners and edges defined
+/// by insets keep their original size and only the center part is scaled.
+#[derive(Debug)]
+pub struct NineSlice {
+    pipeline: wgpu::RenderPipeline,
+    sampler: Sampler,
+    surface: SingleSurface,
+    rgba_layout: wgpu::BindGroupLayout,
+}
+
+impl NineSlice {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader_module = device.create_shader_module(wgpu::include_wgsl!("nine_slice.wgsl"));
+        let sampler = Sampler::new(device);
+        let surface = SingleSurface::new(device);
+        let rgba_layout = RGBATexture::new_bind_group_layout(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Nine-slice render pipeline layout"),
+            bind_group_layouts: &[&rgba_layout, &sampler.bind_group_layout],
+            push_constant_ranges: &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::FRAGMENT,
+                range: 0..std::mem::size_of::<NineSliceParams>() as u32,
+            }],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Nine-slice render pipeline"),
+            layout: Some(&pipeline_layout),
+            primitive: PRIMITIVE_STATE,
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[Vertex::LAYOUT],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    write_mask: wgpu::ColorWrites::all(),
+                    blend: None,
+                })],
+            }),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            sampler,
+            surface,
+            rgba_layout,
+        }
+    }
+
+    pub fn render(
+        &self,
+        ctx: &WgpuCtx,
+        src: &RGBATexture,
+        dst: &RGBATexture,
+        insets: NineSliceInsets,
+    ) {
+        let src_size = src.size();
+        let dst_size = dst.size();
+        let params = NineSliceParams {
+            insets: [
+                insets.top as f32,
+                insets.right as f32,
+                insets.bottom as f32,
+                insets.left as f32,
+            ],
+            source_resolution: [src_size.width as f32, src_size.height as f32],
+            target_resolution: [dst_size.width as f32, dst_size.height as f32],
+        };
+        let src_bind_group = src.new_bind_group(ctx, &self.rgba_layout);
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Nine-slice command encoder"),
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Nine-slice render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                    view: &dst.texture().view,
+                    resolve_target: None,
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &src_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.sampler.bind_group, &[]);
+            render_pass.set_push_constants(ShaderStages::FRAGMENT, 0, bytemuck::bytes_of(&params));
+            self.surface.draw(&mut render_pass);
+        }
+
+        ctx.submit(encoder.finish());
+    }
+}
@@ -0,0 +1,61 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct NineSliceParams {
+    // top, right, bottom, left (in source texture pixels)
+    insets: vec4<f32>,
+    source_resolution: vec2<f32>,
+    target_resolution: vec2<f32>,
+}
+
+@group(0) @binding(0) var texture: texture_2d<f32>;
+@group(1) @binding(0) var sampler_: sampler;
+
+var<push_constant> params: NineSliceParams;
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.position = vec4(input.position, 1.0);
+    output.tex_coords = input.tex_coords;
+
+    return output;
+}
+
+// Maps position in the target texture to position in the source texture along one axis.
+// Borders keep their size (or are scaled down if they don't fit into the target) and
+// the center is stretched to fill the remaining space.
+fn source_position(target_pos: f32, start: f32, end: f32, source_size: f32, target_size: f32) -> f32 {
+    let border_scale = min(1.0, target_size / max(start + end, 1.0));
+    let target_start = start * border_scale;
+    let target_end = end * border_scale;
+
+    if target_pos < target_start {
+        return target_pos / border_scale;
+    }
+    if target_pos > target_size - target_end {
+        return source_size - (target_size - target_pos) / border_scale;
+    }
+
+    let center_progress = (target_pos - target_start) / max(target_size - target_start - target_end, 0.0001);
+    return start + center_progress * (source_size - start - end);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let target_pos = input.tex_coords * params.target_resolution;
+    let source_pos = vec2<f32>(
+        source_position(target_pos.x, params.insets.w, params.insets.y, params.source_resolution.x, params.target_resolution.x),
+        source_position(target_pos.y, params.insets.x, params.insets.z, params.source_resolution.y, params.target_resolution.y),
+    );
+
+    return textureSample(texture, sampler_, source_pos / params.source_resolution);
+}
@@ -6,9 +6,11 @@ use crate::wgpu::{
     WgpuCtx,
 };
 
+/// Fills single channel textures with a value. Supports R8Unorm and R16Unorm targets.
 #[derive(Debug)]
 pub struct R8FillWithValue {
     pipeline: wgpu::RenderPipeline,
+    pipeline_16bit: wgpu::RenderPipeline,
     surface: SingleSurface,
 }
 
@@ -26,35 +28,38 @@ impl R8FillWithValue {
             }],
         });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Fill with value pipeline"),
-            layout: Some(&pipeline_layout),
-            primitive: PRIMITIVE_STATE,
-            vertex: wgpu::VertexState {
-                module: &shader_module,
-                entry_point: "vs_main",
-                buffers: &[Vertex::LAYOUT],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader_module,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::R8Unorm,
-                    write_mask: wgpu::ColorWrites::all(),
-                    blend: None,
-                })],
-            }),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
+        let create_pipeline = |format: wgpu::TextureFormat| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Fill with value pipeline"),
+                layout: Some(&pipeline_layout),
+                primitive: PRIMITIVE_STATE,
+                vertex: wgpu::VertexState {
+                    module: &shader_module,
+                    entry_point: "vs_main",
+                    buffers: &[Vertex::LAYOUT],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader_module,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        write_mask: wgpu::ColorWrites::all(),
+                        blend: None,
+                    })],
+                }),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            })
+        };
 
         Self {
-            pipeline,
+            pipeline: create_pipeline(wgpu::TextureFormat::R8Unorm),
+            pipeline_16bit: create_pipeline(wgpu::TextureFormat::R16Unorm),
             surface: surfaces,
         }
     }
@@ -80,11 +85,14 @@ impl R8FillWithValue {
                 depth_stencil_attachment: None,
             });
 
-            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_pipeline(match dst.texture.format() {
+                wgpu::TextureFormat::R16Unorm => &self.pipeline_16bit,
+                _ => &self.pipeline,
+            });
             render_pass.set_push_constants(ShaderStages::FRAGMENT, 0, bytemuck::bytes_of(&value));
             self.surface.draw(&mut render_pass);
         }
 
-        ctx.queue.submit(Some(encoder.finish()));
+        ctx.submit(encoder.finish());
     }
 }
@@ -0,0 +1,137 @@
+use compositor_common::scene::test_pattern::TestPattern;
+use wgpu::ShaderStages;
+
+use crate::wgpu::{
+    common_pipeline::{surface::SingleSurface, Vertex, PRIMITIVE_STATE},
+    texture::RGBATexture,
+    WgpuCtx,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct TestPatternParams {
+    color: [f32; 4],
+    resolution: [f32; 2],
+    pattern: u32,
+    show_timecode: u32,
+    timecode: [u32; 4],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PatternTimecode {
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub frames: u32,
+}
+
+/// Generates frames of test pattern inputs.
+#[derive(Debug)]
+pub struct TestPatternGenerator {
+    pipeline: wgpu::RenderPipeline,
+    surface: SingleSurface,
+}
+
+impl TestPatternGenerator {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader_module = device.create_shader_module(wgpu::include_wgsl!("test_pattern.wgsl"));
+        let surface = SingleSurface::new(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Test pattern render pipeline layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::FRAGMENT,
+                range: 0..std::mem::size_of::<TestPatternParams>() as u32,
+            }],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Test pattern render pipeline"),
+            layout: Some(&pipeline_layout),
+            primitive: PRIMITIVE_STATE,
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[Vertex::LAYOUT],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    write_mask: wgpu::ColorWrites::all(),
+                    blend: None,
+                })],
+            }),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self { pipeline, surface }
+    }
+
+    pub fn render(
+        &self,
+        ctx: &WgpuCtx,
+        dst: &RGBATexture,
+        pattern: TestPattern,
+        timecode: Option<PatternTimecode>,
+    ) {
+        let (pattern, color) = match pattern {
+            TestPattern::SmpteBars => (0, [0.0; 4]),
+            TestPattern::Checkerboard => (1, [0.0; 4]),
+            TestPattern::Color(color) => (
+                2,
+                [
+                    color.0 as f32 / 255.0,
+                    color.1 as f32 / 255.0,
+                    color.2 as f32 / 255.0,
+                    1.0,
+                ],
+            ),
+        };
+        let dst_size = dst.size();
+        let params = TestPatternParams {
+            color,
+            resolution: [dst_size.width as f32, dst_size.height as f32],
+            pattern,
+            show_timecode: timecode.is_some() as u32,
+            timecode: timecode
+                .map(|t| [t.hours, t.minutes, t.seconds, t.frames])
+                .unwrap_or_default(),
+        };
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Test pattern command encoder"),
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Test pattern render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                    view: &dst.texture().view,
+                    resolve_target: None,
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_push_constants(ShaderStages::FRAGMENT, 0, bytemuck::bytes_of(&params));
+            self.surface.draw(&mut render_pass);
+        }
+
+        ctx.submit(encoder.finish());
+    }
+}
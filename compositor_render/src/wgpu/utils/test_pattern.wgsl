@@ -0,0 +1,184 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct TestPatternParams {
+    color: vec4<f32>,
+    resolution: vec2<f32>,
+    // 0 - SMPTE bars, 1 - checkerboard, 2 - solid color
+    pattern: u32,
+    show_timecode: u32,
+    // hours, minutes, seconds, frames
+    timecode: vec4<u32>,
+}
+
+var<push_constant> params: TestPatternParams;
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.position = vec4(input.position, 1.0);
+    output.tex_coords = input.tex_coords;
+
+    return output;
+}
+
+fn rgb8(r: f32, g: f32, b: f32) -> vec4<f32> {
+    return vec4(r / 255.0, g / 255.0, b / 255.0, 1.0);
+}
+
+// 75% bars with 7.5% setup.
+fn smpte_bars(uv: vec2<f32>) -> vec4<f32> {
+    let bar = u32(clamp(uv.x * 7.0, 0.0, 6.0));
+    if uv.y < 2.0 / 3.0 {
+        switch bar {
+            case 0u: { return rgb8(192.0, 192.0, 192.0); }
+            case 1u: { return rgb8(192.0, 192.0, 0.0); }
+            case 2u: { return rgb8(0.0, 192.0, 192.0); }
+            case 3u: { return rgb8(0.0, 192.0, 0.0); }
+            case 4u: { return rgb8(192.0, 0.0, 192.0); }
+            case 5u: { return rgb8(192.0, 0.0, 0.0); }
+            default: { return rgb8(0.0, 0.0, 192.0); }
+        }
+    }
+
+    let black = rgb8(19.0, 19.0, 19.0);
+    if uv.y < 0.75 {
+        // reverse blue bars
+        switch bar {
+            case 0u: { return rgb8(0.0, 0.0, 192.0); }
+            case 2u: { return rgb8(192.0, 0.0, 192.0); }
+            case 4u: { return rgb8(0.0, 192.0, 192.0); }
+            case 6u: { return rgb8(192.0, 192.0, 192.0); }
+            default: { return black; }
+        }
+    }
+
+    // -I, white, +Q and black are 5/4 of the top bar wide
+    let section = u32(clamp(uv.x * 28.0 / 5.0, 0.0, 4.0));
+    switch section {
+        case 0u: { return rgb8(0.0, 33.0, 76.0); }
+        case 1u: { return rgb8(255.0, 255.0, 255.0); }
+        case 2u: { return rgb8(50.0, 0.0, 106.0); }
+        case 3u: { return black; }
+        default: {}
+    }
+
+    // PLUGE (-4%, 0%, +4%) below the fifth top bar
+    let pluge = u32(clamp((uv.x - 5.0 / 7.0) * 21.0, 0.0, 3.0));
+    switch pluge {
+        case 0u: { return rgb8(9.0, 9.0, 9.0); }
+        case 2u: { return rgb8(29.0, 29.0, 29.0); }
+        default: { return black; }
+    }
+}
+
+fn checkerboard(position: vec2<f32>) -> vec4<f32> {
+    let size = max(params.resolution.y / 16.0, 1.0);
+    let cell = vec2<u32>(position / size);
+    if (cell.x + cell.y) % 2u == 0u {
+        return vec4(1.0, 1.0, 1.0, 1.0);
+    }
+    return vec4(0.0, 0.0, 0.0, 1.0);
+}
+
+// Bits of lit segments, from a (top) to g (middle).
+fn digit_segments(digit: u32) -> u32 {
+    switch digit {
+        case 0u: { return 0x3Fu; }
+        case 1u: { return 0x06u; }
+        case 2u: { return 0x5Bu; }
+        case 3u: { return 0x4Fu; }
+        case 4u: { return 0x66u; }
+        case 5u: { return 0x6Du; }
+        case 6u: { return 0x7Du; }
+        case 7u: { return 0x07u; }
+        case 8u: { return 0x7Fu; }
+        default: { return 0x6Fu; }
+    }
+}
+
+fn in_rect(p: vec2<f32>, top_left: vec2<f32>, bottom_right: vec2<f32>) -> bool {
+    return all(p >= top_left) && all(p <= bottom_right);
+}
+
+// `p` is a position in the glyph, in the [0, 1] x [0, 2] range.
+fn is_digit_lit(digit: u32, p: vec2<f32>) -> bool {
+    let t = 0.2;
+    let segments = digit_segments(digit);
+    var lit = false;
+    lit = lit || ((segments & 0x01u) != 0u && in_rect(p, vec2(0.0, 0.0), vec2(1.0, t)));
+    lit = lit || ((segments & 0x02u) != 0u && in_rect(p, vec2(1.0 - t, 0.0), vec2(1.0, 1.0)));
+    lit = lit || ((segments & 0x04u) != 0u && in_rect(p, vec2(1.0 - t, 1.0), vec2(1.0, 2.0)));
+    lit = lit || ((segments & 0x08u) != 0u && in_rect(p, vec2(0.0, 2.0 - t), vec2(1.0, 2.0)));
+    lit = lit || ((segments & 0x10u) != 0u && in_rect(p, vec2(0.0, 1.0), vec2(t, 2.0)));
+    lit = lit || ((segments & 0x20u) != 0u && in_rect(p, vec2(0.0, 0.0), vec2(t, 1.0)));
+    lit = lit || ((segments & 0x40u) != 0u && in_rect(p, vec2(0.0, 1.0 - t / 2.0), vec2(1.0, 1.0 + t / 2.0)));
+    return lit;
+}
+
+fn is_colon_lit(p: vec2<f32>) -> bool {
+    let half_size = vec2(0.15, 0.15);
+    return in_rect(p, vec2(0.5, 0.6) - half_size, vec2(0.5, 0.6) + half_size)
+        || in_rect(p, vec2(0.5, 1.4) - half_size, vec2(0.5, 1.4) + half_size);
+}
+
+// `HH:MM:SS:FF` on a black box in the middle of the frame. Returns transparent
+// color outside of the box.
+fn timecode(position: vec2<f32>) -> vec4<f32> {
+    let height = min(params.resolution.y / 12.0, params.resolution.x / 8.0);
+    let cell_width = 0.75 * height;
+    let padding = 0.25 * height;
+    let text_size = vec2(11.0 * cell_width - padding, height);
+    let local = position - (params.resolution - text_size) / 2.0;
+    if any(local < vec2(-padding)) || any(local > text_size + padding) {
+        return vec4(0.0);
+    }
+
+    let black = vec4(0.0, 0.0, 0.0, 1.0);
+    if any(local < vec2(0.0)) || any(local >= text_size) {
+        return black;
+    }
+    let index = u32(local.x / cell_width);
+    let p = vec2(local.x - f32(index) * cell_width, local.y) / (0.5 * height);
+    if p.x > 1.0 {
+        return black;
+    }
+
+    var lit = false;
+    if index % 3u == 2u {
+        lit = is_colon_lit(p);
+    } else {
+        let value = params.timecode[min(index / 3u, 3u)];
+        let digit = select(value % 10u, (value / 10u) % 10u, index % 3u == 0u);
+        lit = is_digit_lit(digit, p);
+    }
+    return select(black, vec4(1.0, 1.0, 1.0, 1.0), lit);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let position = input.tex_coords * params.resolution;
+
+    var color: vec4<f32>;
+    switch params.pattern {
+        case 0u: { color = smpte_bars(input.tex_coords); }
+        case 1u: { color = checkerboard(position); }
+        default: { color = params.color; }
+    }
+
+    if params.show_timecode != 0u {
+        let timecode_color = timecode(position);
+        if timecode_color.a > 0.0 {
+            color = timecode_color;
+        }
+    }
+    return color;
+}
@@ -0,0 +1,142 @@
+use wgpu::ShaderStages;
+
+use crate::wgpu::{
+    common_pipeline::{surface::SingleSurface, Sampler, Vertex, PRIMITIVE_STATE},
+    texture::RGBATexture,
+    WgpuCtx,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct WatermarkParams {
+    rect: [f32; 4],
+    target_resolution: [f32; 2],
+    opacity: f32,
+    has_base: u32,
+}
+
+/// Position of the watermark on the target texture in pixels.
+#[derive(Debug, Clone, Copy)]
+pub struct WatermarkRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Draws a base texture stretched to the target size with a watermark texture
+/// blended on top of it at the given position.
+#[derive(Debug)]
+pub struct Watermark {
+    pipeline: wgpu::RenderPipeline,
+    sampler: Sampler,
+    surface: SingleSurface,
+    rgba_layout: wgpu::BindGroupLayout,
+}
+
+impl Watermark {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader_module = device.create_shader_module(wgpu::include_wgsl!("watermark.wgsl"));
+        let sampler = Sampler::new(device);
+        let surface = SingleSurface::new(device);
+        let rgba_layout = RGBATexture::new_bind_group_layout(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Watermark render pipeline layout"),
+            bind_group_layouts: &[&rgba_layout, &rgba_layout, &sampler.bind_group_layout],
+            push_constant_ranges: &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::FRAGMENT,
+                range: 0..std::mem::size_of::<WatermarkParams>() as u32,
+            }],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Watermark render pipeline"),
+            layout: Some(&pipeline_layout),
+            primitive: PRIMITIVE_STATE,
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[Vertex::LAYOUT],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    write_mask: wgpu::ColorWrites::all(),
+                    blend: None,
+                })],
+            }),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            sampler,
+            surface,
+            rgba_layout,
+        }
+    }
+
+    /// If `base` is `None`, the watermark is drawn over black.
+    pub fn render(
+        &self,
+        ctx: &WgpuCtx,
+        base: Option<&RGBATexture>,
+        watermark: &RGBATexture,
+        dst: &RGBATexture,
+        rect: WatermarkRect,
+        opacity: f32,
+    ) {
+        let dst_size = dst.size();
+        let params = WatermarkParams {
+            rect: [rect.x, rect.y, rect.width, rect.height],
+            target_resolution: [dst_size.width as f32, dst_size.height as f32],
+            opacity,
+            has_base: base.is_some() as u32,
+        };
+        let watermark_bind_group = watermark.new_bind_group(ctx, &self.rgba_layout);
+        // Shader ignores the base texture if there is none, but the slot can't be empty.
+        let base_bind_group = match base {
+            Some(base) => base.new_bind_group(ctx, &self.rgba_layout),
+            None => watermark.new_bind_group(ctx, &self.rgba_layout),
+        };
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Watermark command encoder"),
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Watermark render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                    view: &dst.texture().view,
+                    resolve_target: None,
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &base_bind_group, &[]);
+            render_pass.set_bind_group(1, &watermark_bind_group, &[]);
+            render_pass.set_bind_group(2, &self.sampler.bind_group, &[]);
+            render_pass.set_push_constants(ShaderStages::FRAGMENT, 0, bytemuck::bytes_of(&params));
+            self.surface.draw(&mut render_pass);
+        }
+
+        ctx.submit(encoder.finish());
+    }
+}
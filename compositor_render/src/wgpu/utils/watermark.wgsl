@@ -0,0 +1,52 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct WatermarkParams {
+    // x, y, width, height (in target texture pixels)
+    rect: vec4<f32>,
+    target_resolution: vec2<f32>,
+    opacity: f32,
+    // 0 if there is no base texture and the watermark is drawn over black
+    has_base: u32,
+}
+
+@group(0) @binding(0) var base_texture: texture_2d<f32>;
+@group(1) @binding(0) var watermark_texture: texture_2d<f32>;
+@group(2) @binding(0) var sampler_: sampler;
+
+var<push_constant> params: WatermarkParams;
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.position = vec4(input.position, 1.0);
+    output.tex_coords = input.tex_coords;
+
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let base = select(
+        vec4<f32>(0.0, 0.0, 0.0, 1.0),
+        textureSample(base_texture, sampler_, input.tex_coords),
+        params.has_base != 0u
+    );
+
+    let target_pos = input.tex_coords * params.target_resolution;
+    let watermark_coords = (target_pos - params.rect.xy) / params.rect.zw;
+    let watermark = textureSample(watermark_texture, sampler_, watermark_coords);
+
+    let is_inside = all(watermark_coords >= vec2<f32>(0.0)) && all(watermark_coords <= vec2<f32>(1.0));
+    let alpha = select(0.0, watermark.a * params.opacity, is_inside);
+
+    return vec4(mix(base.rgb, watermark.rgb, alpha), base.a + alpha * (1.0 - base.a));
+}
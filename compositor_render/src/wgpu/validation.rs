@@ -1,10 +1,23 @@
-use compositor_common::scene::shader::ShaderParam;
-use naga::{ArraySize, ConstantInner, Handle, Module, ScalarKind, ShaderStage, Type, VectorSize};
+use std::collections::HashMap;
+
+use compositor_common::scene::shader::{
+    ShaderParam, ShaderParamStructField, ShaderParamStructFieldType, ShaderParamType,
+    ShaderParamValue,
+};
+use naga::{
+    valid::{Capabilities, ValidationFlags, Validator},
+    AddressSpace, ArraySize, ConstantInner, Handle, Module, ScalarKind, ShaderStage, Type,
+    TypeInner, VectorSize,
+};
 
 use self::error::BindingExt;
 
+use super::capabilities::GpuCapabilities;
 use super::shader::VERTEX_ENTRYPOINT_NAME;
-use super::shader::{USER_DEFINED_BUFFER_BINDING, USER_DEFINED_BUFFER_GROUP};
+use super::shader::{
+    USER_DEFINED_BUFFER_BINDING, USER_DEFINED_BUFFER_GROUP, USER_DEFINED_IMAGES_AMOUNT,
+    USER_DEFINED_IMAGES_BINDING, USER_DEFINED_STORAGE_BINDING,
+};
 
 use error::ShaderGlobalVariableExt;
 
@@ -24,6 +37,77 @@ pub fn validate_contains_header(
     Ok(())
 }
 
+/// Checks that the shader does not use features or exceed limits that were not
+/// negotiated with the GPU.
+pub fn validate_capabilities(
+    shader: &naga::Module,
+    capabilities: &GpuCapabilities,
+) -> Result<(), ShaderValidationError> {
+    let mut push_constants_size = 0;
+    let mut textures_count = 0;
+    for (_, global) in shader.global_variables.iter() {
+        let name = || global.name.unwrap_with("<unknown>");
+        let ty = &shader.types[global.ty].inner;
+        match global.space {
+            AddressSpace::PushConstant => push_constants_size += ty.size(&shader.constants),
+            AddressSpace::Uniform => {
+                let size = ty.size(&shader.constants);
+                if size > capabilities.max_uniform_buffer_binding_size {
+                    return Err(ShaderValidationError::UniformBufferTooLarge {
+                        name: name(),
+                        size,
+                        max: capabilities.max_uniform_buffer_binding_size,
+                    });
+                }
+            }
+            AddressSpace::Handle => {
+                if let TypeInner::BindingArray { size, .. } = ty {
+                    let size = eval_array_size(*size, shader)
+                        .map_err(|err| ShaderValidationError::BindingArrayBadSize(err, name()))?;
+                    if size > capabilities.max_binding_array_size as u64 {
+                        return Err(ShaderValidationError::BindingArrayTooLarge {
+                            name: name(),
+                            size,
+                            max: capabilities.max_binding_array_size,
+                        });
+                    }
+                    textures_count += size;
+                }
+            }
+            _ => {}
+        }
+    }
+    // e.g. input textures and user-defined images together
+    if textures_count > capabilities.max_binding_array_size as u64 {
+        return Err(ShaderValidationError::TooManyTextures {
+            count: textures_count,
+            max: capabilities.max_binding_array_size,
+        });
+    }
+    if push_constants_size > capabilities.max_push_constant_size {
+        return Err(ShaderValidationError::PushConstantsTooLarge {
+            size: push_constants_size,
+            max: capabilities.max_push_constant_size,
+        });
+    }
+
+    let flags = ValidationFlags::all();
+    if let Err(err) = Validator::new(flags, capabilities.naga_capabilities()).validate(shader) {
+        // Other errors are reported by wgpu when the shader is compiled.
+        let is_valid_with_all_features = Validator::new(flags, Capabilities::all())
+            .validate(shader)
+            .is_ok();
+        if is_valid_with_all_features {
+            return Err(ShaderValidationError::UnsupportedFeature(
+                err,
+                capabilities.adapter_name.clone(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 fn validate_globals(
     header: &naga::Module,
     shader: &naga::Module,
@@ -47,24 +131,63 @@ fn validate_globals(
         )?;
     }
 
-    // validate user-defined buffer is a uniform
-    shader
-        .global_variables
-        .iter()
-        .find(|(_, global)| {
-            global.binding.is_some()
-                && global.binding.as_ref().unwrap().group == USER_DEFINED_BUFFER_GROUP
-                && global.binding.as_ref().unwrap().binding == USER_DEFINED_BUFFER_BINDING
-                && global.space == naga::AddressSpace::Uniform
-        })
-        .map_or(Ok(()), |(_, global)| match global.space {
-            naga::AddressSpace::Uniform => Ok(()),
-            _ => Err(ShaderValidationError::UserBindingNotUniform),
-        })?;
+    for (_, global) in shader.global_variables.iter() {
+        match &global.binding {
+            Some(binding) if binding.group == USER_DEFINED_BUFFER_GROUP => {
+                validate_user_binding(global, binding.binding, shader)?
+            }
+            _ => {}
+        }
+    }
 
     Ok(())
 }
 
+/// User-defined bindings are optional, but if declared they have to match the
+/// layout of the params bind group.
+fn validate_user_binding(
+    global: &naga::GlobalVariable,
+    binding: u32,
+    shader: &naga::Module,
+) -> Result<(), ShaderValidationError> {
+    match binding {
+        USER_DEFINED_BUFFER_BINDING => match global.space {
+            AddressSpace::Uniform => Ok(()),
+            _ => Err(ShaderValidationError::UserBindingNotUniform),
+        },
+
+        USER_DEFINED_STORAGE_BINDING => match global.space {
+            AddressSpace::Storage { access } if access == naga::StorageAccess::LOAD => Ok(()),
+            _ => Err(ShaderValidationError::UserStorageBindingNotReadOnly),
+        },
+
+        USER_DEFINED_IMAGES_BINDING => {
+            let TypeInner::BindingArray { base, size } = &shader.types[global.ty].inner else {
+                return Err(ShaderValidationError::UserImagesBindingBadType);
+            };
+            let is_sampled_texture = matches!(
+                shader.types[*base].inner,
+                TypeInner::Image {
+                    dim: naga::ImageDimension::D2,
+                    arrayed: false,
+                    class: naga::ImageClass::Sampled {
+                        kind: ScalarKind::Float,
+                        multi: false,
+                    },
+                }
+            );
+            let has_expected_size = eval_array_size(*size, shader)
+                .is_ok_and(|size| size == USER_DEFINED_IMAGES_AMOUNT as u64);
+            match is_sampled_texture && has_expected_size {
+                true => Ok(()),
+                false => Err(ShaderValidationError::UserImagesBindingBadType),
+            }
+        }
+
+        binding => Err(ShaderValidationError::UnknownUserBinding(binding)),
+    }
+}
+
 fn validate_vertex_input(
     header: &naga::Module,
     shader: &naga::Module,
@@ -77,13 +200,22 @@ fn validate_vertex_input(
         })
         .ok_or(ShaderValidationError::VertexShaderNotFound)?;
 
-    if vertex.function.arguments.len() != 1 {
-        return Err(ShaderValidationError::VertexShaderBadArgumentAmount(
-            vertex.function.arguments.len(),
-        ));
-    }
-
-    let vertex_input = vertex.function.arguments[0].ty;
+    // builtins (e.g. `vertex_index`) don't come from the vertex buffer
+    let inputs: Vec<_> = vertex
+        .function
+        .arguments
+        .iter()
+        .filter(|argument| !matches!(argument.binding, Some(naga::Binding::BuiltIn(_))))
+        .collect();
+    let vertex_input = match inputs.as_slice() {
+        [] => return Ok(()),
+        [input] => input.ty,
+        _ => {
+            return Err(ShaderValidationError::VertexShaderBadArgumentAmount(
+                inputs.len(),
+            ))
+        }
+    };
     let vertex_input_type = &shader.types[vertex_input];
 
     let (header_vertex_input, _) = header
@@ -96,12 +228,51 @@ fn validate_vertex_input(
             )
         })?;
 
-    validate_type_equivalent(header_vertex_input, header, vertex_input, shader)
+    validate_struct_prefix(header_vertex_input, header, vertex_input, shader)
         .map_err(ShaderValidationError::VertexShaderBadInput)?;
 
     Ok(())
 }
 
+/// Same as [`validate_type_equivalent`], but a provided struct can omit trailing
+/// fields of the expected one, e.g. vertex attributes that the shader doesn't use.
+fn validate_struct_prefix(
+    expected: Handle<Type>,
+    expected_module: &Module,
+    provided: Handle<Type>,
+    provided_module: &Module,
+) -> Result<(), TypeEquivalenceError> {
+    let expected_type = &expected_module.types[expected];
+    let (
+        TypeInner::Struct {
+            members: expected_members,
+            ..
+        },
+        TypeInner::Struct {
+            members: provided_members,
+            ..
+        },
+    ) = (&expected_type.inner, &provided_module.types[provided].inner)
+    else {
+        return validate_type_equivalent(expected, expected_module, provided, provided_module);
+    };
+
+    if provided_members.len() > expected_members.len() {
+        return Err(TypeEquivalenceError::StructFieldNumberMismatch {
+            struct_name: expected_type.name.unwrap_with("<unnamed>"),
+            expected_field_number: expected_members.len(),
+            actual_field_number: provided_members.len(),
+        });
+    }
+    validate_struct_members_equivalent(
+        expected_type,
+        &expected_members[..provided_members.len()],
+        expected_module,
+        provided_members,
+        provided_module,
+    )
+}
+
 fn validate_type_equivalent(
     expected: Handle<Type>,
     expected_module: &Module,
@@ -238,51 +409,13 @@ fn validate_type_equivalent(
                 });
             }
 
-            for (expected_member, provided_member) in
-                expected_members.iter().zip(provided_members.iter())
-            {
-                if expected_member.name != provided_member.name {
-                    return Err(TypeEquivalenceError::StructFieldNameMismatch {
-                        struct_name: expected_type.name.unwrap_with("<unnamed>"),
-                        expected_field_name: expected_member.name.unwrap_with("<unnamed>"),
-                        actual_field_name: provided_member.name.unwrap_with("<unnamed>"),
-                    });
-                }
-
-                // skipped checking if m1.offset == m2.offset
-                // if all fields have the same types, how can the offsets be different?
-
-                validate_type_equivalent(
-                    expected_member.ty,
-                    expected_module,
-                    provided_member.ty,
-                    provided_module,
-                )
-                .map_err(|err| {
-                    TypeEquivalenceError::StructFieldStructureMismatch {
-                        struct_name: expected_type.name.unwrap_with("<unnamed>"),
-                        field_name: expected_member.name.unwrap_with("<unnamed>"),
-                        error: Box::new(err),
-                    }
-                })?;
-
-                if expected_member.binding != provided_member.binding {
-                    return Err(TypeEquivalenceError::StructFieldBindingMismatch {
-                        struct_name: expected_type.name.unwrap_with("<unnamed>"),
-                        field_name: expected_member.name.unwrap_with("<unnamed>"),
-                        expected_binding: expected_member
-                            .binding
-                            .as_ref()
-                            .map(BindingExt::to_string)
-                            .unwrap_with("<no binding>"),
-                        actual_binding: provided_member
-                            .binding
-                            .as_ref()
-                            .map(BindingExt::to_string)
-                            .unwrap_with("<no binding>"),
-                    });
-                }
-            }
+            validate_struct_members_equivalent(
+                expected_type,
+                expected_members,
+                expected_module,
+                provided_members,
+                provided_module,
+            )?;
         }
 
         naga::TypeInner::Pointer { .. } => {
@@ -293,6 +426,58 @@ fn validate_type_equivalent(
     Ok(())
 }
 
+/// Compares fields of structs with the same number of fields.
+fn validate_struct_members_equivalent(
+    expected_type: &Type,
+    expected_members: &[naga::StructMember],
+    expected_module: &Module,
+    provided_members: &[naga::StructMember],
+    provided_module: &Module,
+) -> Result<(), TypeEquivalenceError> {
+    for (expected_member, provided_member) in expected_members.iter().zip(provided_members.iter()) {
+        if expected_member.name != provided_member.name {
+            return Err(TypeEquivalenceError::StructFieldNameMismatch {
+                struct_name: expected_type.name.unwrap_with("<unnamed>"),
+                expected_field_name: expected_member.name.unwrap_with("<unnamed>"),
+                actual_field_name: provided_member.name.unwrap_with("<unnamed>"),
+            });
+        }
+
+        // skipped checking if m1.offset == m2.offset
+        // if all fields have the same types, how can the offsets be different?
+
+        validate_type_equivalent(
+            expected_member.ty,
+            expected_module,
+            provided_member.ty,
+            provided_module,
+        )
+        .map_err(|err| TypeEquivalenceError::StructFieldStructureMismatch {
+            struct_name: expected_type.name.unwrap_with("<unnamed>"),
+            field_name: expected_member.name.unwrap_with("<unnamed>"),
+            error: Box::new(err),
+        })?;
+
+        if expected_member.binding != provided_member.binding {
+            return Err(TypeEquivalenceError::StructFieldBindingMismatch {
+                struct_name: expected_type.name.unwrap_with("<unnamed>"),
+                field_name: expected_member.name.unwrap_with("<unnamed>"),
+                expected_binding: expected_member
+                    .binding
+                    .as_ref()
+                    .map(BindingExt::to_string)
+                    .unwrap_with("<no binding>"),
+                actual_binding: provided_member
+                    .binding
+                    .as_ref()
+                    .map(BindingExt::to_string)
+                    .unwrap_with("<no binding>"),
+            });
+        }
+    }
+    Ok(())
+}
+
 fn eval_array_size(size: ArraySize, module: &naga::Module) -> Result<u64, ConstArraySizeEvalError> {
     match size {
         ArraySize::Constant(c) => {
@@ -390,6 +575,279 @@ pub fn validate_params(
     }
 }
 
+/// Structure of params accepted by [`validate_params`] for the type. Fails for the
+/// same types that can't be provided as params.
+pub fn params_type(
+    ty: Handle<Type>,
+    module: &naga::Module,
+) -> Result<ShaderParamType, ParametersValidationError> {
+    let ty = &module.types[ty];
+
+    match &ty.inner {
+        naga::TypeInner::Scalar { kind, width } => scalar_params_type(*kind, *width),
+
+        naga::TypeInner::Vector { size, kind, width } => Ok(ShaderParamType::Vector {
+            element: Box::new(scalar_params_type(*kind, *width)?),
+            size: *size as usize,
+        }),
+
+        naga::TypeInner::Matrix { columns, rows, .. } => Ok(ShaderParamType::Matrix {
+            columns: *columns as usize,
+            rows: *rows as usize,
+        }),
+
+        naga::TypeInner::Array { base, size, .. } => Ok(ShaderParamType::Array {
+            element: Box::new(params_type(*base, module)?),
+            max_length: match size {
+                ArraySize::Dynamic => None,
+                size => Some(eval_array_size(*size, module)? as usize),
+            },
+        }),
+
+        naga::TypeInner::Struct { members, .. } => {
+            let struct_name = ty.name.as_deref().unwrap_or("<unnamed>");
+            let fields = members
+                .iter()
+                .map(|member| {
+                    let field_name = member.name.unwrap_with("<unnamed>");
+                    match params_type(member.ty, module) {
+                        Ok(field_type) => Ok(ShaderParamStructFieldType {
+                            field_name,
+                            field_type,
+                        }),
+                        Err(err) => Err(ParametersValidationError::WrongFieldType {
+                            struct_name: struct_name.to_string(),
+                            struct_field: field_name,
+                            error: Box::new(err),
+                        }),
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(ShaderParamType::Struct(fields))
+        }
+
+        naga::TypeInner::Pointer { .. }
+        | naga::TypeInner::ValuePointer { .. }
+        | naga::TypeInner::Atomic { .. }
+        | naga::TypeInner::Image { .. }
+        | naga::TypeInner::Sampler { .. }
+        | naga::TypeInner::AccelerationStructure
+        | naga::TypeInner::RayQuery
+        | naga::TypeInner::BindingArray { .. } => Err(ParametersValidationError::ForbiddenType(
+            ty.inner.type_name(),
+        )),
+    }
+}
+
+/// Replaces [`ShaderParam::Object`] values with params in the layout of `ty`.
+/// Fields that are not specified are filled with zeros. Other params are returned
+/// unchanged, mismatches are reported by [`validate_params`].
+pub fn resolve_object_params(
+    params: &ShaderParam,
+    ty: &ShaderParamType,
+) -> Result<ShaderParam, ParametersValidationError> {
+    match (params, ty) {
+        (ShaderParam::Object(fields), _) => resolve_object(fields, ty),
+
+        (
+            ShaderParam::List(list),
+            ShaderParamType::Vector { element, .. } | ShaderParamType::Array { element, .. },
+        ) => {
+            let list = list
+                .iter()
+                .enumerate()
+                .map(|(idx, param)| {
+                    resolve_object_params(param, element).map_err(|err| {
+                        ParametersValidationError::WrongArrayElementType {
+                            idx,
+                            error: Box::new(err),
+                        }
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(ShaderParam::List(list))
+        }
+
+        (ShaderParam::Struct(fields), ShaderParamType::Struct(field_types)) => {
+            let fields = fields
+                .iter()
+                .map(|field| {
+                    let field_type = field_types
+                        .iter()
+                        .find(|field_type| field_type.field_name == field.field_name);
+                    let value = match field_type {
+                        Some(field_type) => {
+                            resolve_object_params(&field.value, &field_type.field_type)?
+                        }
+                        None => field.value.clone(),
+                    };
+                    Ok(ShaderParamStructField {
+                        field_name: field.field_name.clone(),
+                        value,
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(ShaderParam::Struct(fields))
+        }
+
+        _ => Ok(params.clone()),
+    }
+}
+
+fn resolve_object(
+    fields: &HashMap<String, ShaderParamValue>,
+    ty: &ShaderParamType,
+) -> Result<ShaderParam, ParametersValidationError> {
+    let ShaderParamType::Struct(field_types) = ty else {
+        return Err(ParametersValidationError::WrongType {
+            expected: ty.to_string(),
+            actual: "object".to_string(),
+        });
+    };
+
+    let is_known = |name: &String| {
+        field_types
+            .iter()
+            .any(|field_type| &field_type.field_name == name)
+    };
+    if let Some(field) = fields.keys().find(|name| !is_known(name)) {
+        return Err(ParametersValidationError::UnknownField {
+            field: field.clone(),
+            available: field_types
+                .iter()
+                .map(|field_type| format!("\"{}\"", field_type.field_name))
+                .collect::<Vec<_>>()
+                .join(", "),
+        });
+    }
+
+    let fields =
+        field_types
+            .iter()
+            .map(|field_type| {
+                let value =
+                    match fields.get(&field_type.field_name) {
+                        Some(value) => resolve_object_value(value, &field_type.field_type)
+                            .map_err(|err| ParametersValidationError::WrongObjectFieldValue {
+                                field: field_type.field_name.clone(),
+                                error: Box::new(err),
+                            })?,
+                        None => field_type.field_type.zeroed(),
+                    };
+                Ok(ShaderParamStructField {
+                    field_name: field_type.field_name.clone(),
+                    value,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+    Ok(ShaderParam::Struct(fields))
+}
+
+fn resolve_object_value(
+    value: &ShaderParamValue,
+    ty: &ShaderParamType,
+) -> Result<ShaderParam, ParametersValidationError> {
+    match (value, ty) {
+        (ShaderParamValue::Typed(params), _) => resolve_object_params(params, ty),
+
+        (ShaderParamValue::Object(fields), _) => resolve_object(fields, ty),
+
+        (ShaderParamValue::Number(number), ShaderParamType::F32) => {
+            Ok(ShaderParam::F32(*number as f32))
+        }
+
+        (ShaderParamValue::Number(number), ShaderParamType::U32) => {
+            if number.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(number) {
+                Ok(ShaderParam::U32(*number as u32))
+            } else {
+                Err(ParametersValidationError::InvalidNumber {
+                    value: *number,
+                    expected: "u32",
+                })
+            }
+        }
+
+        (ShaderParamValue::Number(number), ShaderParamType::I32) => {
+            if number.fract() == 0.0 && (i32::MIN as f64..=i32::MAX as f64).contains(number) {
+                Ok(ShaderParam::I32(*number as i32))
+            } else {
+                Err(ParametersValidationError::InvalidNumber {
+                    value: *number,
+                    expected: "i32",
+                })
+            }
+        }
+
+        (ShaderParamValue::List(list), ShaderParamType::Vector { element, .. }) => {
+            resolve_list(list, element, |idx, err| {
+                ParametersValidationError::WrongVectorElementType {
+                    idx,
+                    error: Box::new(err),
+                }
+            })
+        }
+
+        (ShaderParamValue::List(list), ShaderParamType::Array { element, .. }) => {
+            resolve_list(list, element, |idx, err| {
+                ParametersValidationError::WrongArrayElementType {
+                    idx,
+                    error: Box::new(err),
+                }
+            })
+        }
+
+        (ShaderParamValue::List(list), ShaderParamType::Matrix { columns, .. }) => {
+            let row = ShaderParamType::Vector {
+                element: Box::new(ShaderParamType::F32),
+                size: *columns,
+            };
+            resolve_list(list, &row, |idx, err| {
+                ParametersValidationError::WrongMatrixRowType {
+                    idx,
+                    error: Box::new(err),
+                }
+            })
+        }
+
+        (ShaderParamValue::Number(_), _) => Err(ParametersValidationError::WrongType {
+            expected: ty.to_string(),
+            actual: "number".to_string(),
+        }),
+
+        (ShaderParamValue::List(_), _) => Err(ParametersValidationError::WrongType {
+            expected: ty.to_string(),
+            actual: "list".to_string(),
+        }),
+    }
+}
+
+fn resolve_list(
+    list: &[ShaderParamValue],
+    element: &ShaderParamType,
+    wrap_err: fn(usize, ParametersValidationError) -> ParametersValidationError,
+) -> Result<ShaderParam, ParametersValidationError> {
+    let list = list
+        .iter()
+        .enumerate()
+        .map(|(idx, value)| resolve_object_value(value, element).map_err(|err| wrap_err(idx, err)))
+        .collect::<Result<_, _>>()?;
+    Ok(ShaderParam::List(list))
+}
+
+fn scalar_params_type(
+    kind: ScalarKind,
+    width: u8,
+) -> Result<ShaderParamType, ParametersValidationError> {
+    match (kind, width) {
+        (ScalarKind::Float, 4) => Ok(ShaderParamType::F32),
+        (ScalarKind::Uint, 4) => Ok(ShaderParamType::U32),
+        (ScalarKind::Sint, 4) => Ok(ShaderParamType::I32),
+        _ => Err(ParametersValidationError::UnsupportedScalarKind(
+            kind, width,
+        )),
+    }
+}
+
 fn validate_struct(
     params: &ShaderParam,
     struct_name_in_shader: &str,
@@ -452,15 +910,21 @@ fn validate_array(
     module: &naga::Module,
 ) -> Result<(), ParametersValidationError> {
     // ignoring the `stride`, it probably doesn't matter if the types are correct
-    let evaluated_size = eval_array_size(size, module)?;
+    // runtime-sized arrays (only allowed in storage buffers) accept any length
+    let evaluated_size = match size {
+        ArraySize::Dynamic => None,
+        size => Some(eval_array_size(size, module)?),
+    };
 
     match params {
         ShaderParam::List(list) => {
-            if list.len() > evaluated_size as usize {
-                return Err(ParametersValidationError::ListTooLong {
-                    expected: evaluated_size as usize,
-                    actual: list.len(),
-                });
+            if let Some(evaluated_size) = evaluated_size {
+                if list.len() > evaluated_size as usize {
+                    return Err(ParametersValidationError::ListTooLong {
+                        expected: evaluated_size as usize,
+                        actual: list.len(),
+                    });
+                }
             }
 
             for (idx, param) in list.iter().enumerate() {
@@ -565,7 +1029,7 @@ fn validate_scalar(
 ) -> Result<(), ParametersValidationError> {
     match (kind, width) {
         (ScalarKind::Float, 4) => match params {
-            ShaderParam::F32(_) => Ok(()),
+            ShaderParam::F32(_) | ShaderParam::Keyframes(_) => Ok(()),
             _ => Err(ParametersValidationError::WrongType {
                 actual: params.to_string(),
                 expected: naga::TypeInner::Scalar { kind, width }.to_string(module),
@@ -715,7 +1179,7 @@ trait ShaderParamExt {
 impl ShaderParamExt for ShaderParam {
     fn to_string(&self) -> String {
         match self {
-            ShaderParam::F32(_) => "f32".to_string(),
+            ShaderParam::F32(_) | ShaderParam::Keyframes(_) => "f32".to_string(),
             ShaderParam::U32(_) => "u32".to_string(),
             ShaderParam::I32(_) => "i32".to_string(),
             ShaderParam::List(list) => {
@@ -734,6 +1198,11 @@ impl ShaderParamExt for ShaderParam {
                     .join(", ");
                 format!("struct {{ {} }}", fields)
             }
+            ShaderParam::Object(fields) => {
+                let mut fields = fields.keys().cloned().collect::<Vec<_>>();
+                fields.sort();
+                format!("object {{ {} }}", fields.join(", "))
+            }
         }
     }
 }
@@ -1,4 +1,7 @@
-use super::{USER_DEFINED_BUFFER_BINDING, USER_DEFINED_BUFFER_GROUP, VERTEX_ENTRYPOINT_NAME};
+use super::{
+    USER_DEFINED_BUFFER_BINDING, USER_DEFINED_BUFFER_GROUP, USER_DEFINED_IMAGES_AMOUNT,
+    USER_DEFINED_IMAGES_BINDING, USER_DEFINED_STORAGE_BINDING, VERTEX_ENTRYPOINT_NAME,
+};
 
 const HEADER_DOCS_URL: &str =
     "https://github.com/membraneframework/video_compositor/wiki/Shader#header";
@@ -14,7 +17,7 @@ pub enum ShaderValidationError {
     #[error("Could not find a vertex shader entrypoint. Expected \"fn {VERTEX_ENTRYPOINT_NAME}(input: VertexInput)\".")]
     VertexShaderNotFound,
 
-    #[error("Wrong vertex shader argument amount: found {0}, expected 1.")]
+    #[error("Wrong vertex shader argument amount: found {0} arguments other than builtins, expected at most 1.")]
     VertexShaderBadArgumentAmount(usize),
 
     #[error(
@@ -27,6 +30,40 @@ pub enum ShaderValidationError {
 
     #[error("User defined binding (group {USER_DEFINED_BUFFER_GROUP}, binding {USER_DEFINED_BUFFER_BINDING}) is not a uniform buffer. Is it defined as var<uniform>?")]
     UserBindingNotUniform,
+
+    #[error("User defined storage binding (group {USER_DEFINED_BUFFER_GROUP}, binding {USER_DEFINED_STORAGE_BINDING}) is not a read-only storage buffer. Is it defined as var<storage, read>?")]
+    UserStorageBindingNotReadOnly,
+
+    #[error("User defined images binding (group {USER_DEFINED_BUFFER_GROUP}, binding {USER_DEFINED_IMAGES_BINDING}) has a wrong type. Expected binding_array<texture_2d<f32>, {USER_DEFINED_IMAGES_AMOUNT}>.")]
+    UserImagesBindingBadType,
+
+    #[error("Binding {0} in group {USER_DEFINED_BUFFER_GROUP} is not supported. Only bindings {USER_DEFINED_BUFFER_BINDING} (uniform buffer), {USER_DEFINED_STORAGE_BINDING} (storage buffer) and {USER_DEFINED_IMAGES_BINDING} (images) can be declared.")]
+    UnknownUserBinding(u32),
+
+    #[error(
+        "Push constants in the shader take {size} bytes, but the GPU supports at most {max} bytes."
+    )]
+    PushConstantsTooLarge { size: u32, max: u32 },
+
+    #[error(
+        "Uniform buffer \"{name}\" takes {size} bytes, but the GPU supports at most {max} bytes."
+    )]
+    UniformBufferTooLarge { name: String, size: u32, max: u32 },
+
+    #[error("Binding array \"{name}\" has {size} elements, but the GPU supports at most {max} textures per shader.")]
+    BindingArrayTooLarge { name: String, size: u64, max: u32 },
+
+    #[error("Binding arrays in the shader have {count} textures in total, but the GPU supports at most {max} textures per shader.")]
+    TooManyTextures { count: u64, max: u32 },
+
+    #[error("Binding array \"{1}\" has an invalid size.")]
+    BindingArrayBadSize(#[source] ConstArraySizeEvalError, String),
+
+    #[error("Shader uses a feature that is not supported by the GPU \"{1}\".")]
+    UnsupportedFeature(
+        #[source] naga::WithSpan<naga::valid::ValidationError>,
+        String,
+    ),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -94,6 +131,15 @@ pub enum ParametersValidationError {
     #[error("No user-defined binding was found in the shader, even though parameters were provided in the request. Add \"@group(1) @binding(0) var<uniform> example_params: ExampleType;\" in your shader code.")]
     NoBindingInShader,
 
+    #[error("No user-defined storage binding was found in the shader, even though storage parameters were provided in the request. Add \"@group(1) @binding(1) var<storage, read> example_data: array<ExampleType>;\" in your shader code.")]
+    NoStorageBindingInShader,
+
+    #[error("No user-defined images binding was found in the shader, even though images were provided in the request. Add \"@group(1) @binding(2) var images: binding_array<texture_2d<f32>, {USER_DEFINED_IMAGES_AMOUNT}>;\" in your shader code.")]
+    NoImagesBindingInShader,
+
+    #[error("Too many images bound to the shader (expected at most {USER_DEFINED_IMAGES_AMOUNT}, actual: {0}).")]
+    TooManyImages(usize),
+
     #[error("A type used in the shader cannot be provided at node registration: {0}.")]
     ForbiddenType(&'static str),
 
@@ -152,6 +198,22 @@ pub enum ParametersValidationError {
         #[source]
         error: Box<ParametersValidationError>,
     },
+
+    #[error("Field \"{field}\" does not exist in the struct (available fields: {available}).")]
+    UnknownField { field: String, available: String },
+
+    #[error("Error while mapping field \"{field}\" provided by name.")]
+    WrongObjectFieldValue {
+        field: String,
+        #[source]
+        error: Box<ParametersValidationError>,
+    },
+
+    #[error("Number {value} is not a valid {expected} value.")]
+    InvalidNumber { value: f64, expected: &'static str },
+
+    #[error("Parameters provided by field name would have a different layout in the new shader. Update the scene to recreate the node.")]
+    ObjectParamsLayoutChanged,
 }
 
 pub(crate) trait ShaderGlobalVariableExt {
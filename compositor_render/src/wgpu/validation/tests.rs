@@ -98,10 +98,48 @@ mod header_checking {
             Err(ShaderValidationError::VertexShaderBadInput(_))
         ));
     }
+
+    #[test]
+    fn vertex_input_prefix_and_builtins() {
+        let expected = r#"
+            struct VertexInput {
+                @location(0) position: vec3<f32>,
+                @location(1) tex_coords: vec2<f32>,
+            }
+            "#;
+
+        let prefix = r#"
+            struct VertexInput {
+                @location(0) position: vec3<f32>,
+            }
+    
+            @vertex
+            fn vs_main(in: VertexInput, @builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+                return vec4(0);
+            }
+            "#;
+
+        let only_builtins = r#"
+            @vertex
+            fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+                return vec4(0);
+            }
+            "#;
+
+        let expected = naga::front::wgsl::parse_str(expected).unwrap();
+        let prefix = naga::front::wgsl::parse_str(prefix).unwrap();
+        let only_builtins = naga::front::wgsl::parse_str(only_builtins).unwrap();
+
+        assert!(validate_contains_header(&expected, &prefix).is_ok());
+        assert!(validate_contains_header(&expected, &only_builtins).is_ok());
+    }
 }
 
 mod params_validation {
-    use compositor_common::scene::shader::ShaderParamStructField;
+    use compositor_common::scene::{
+        shader::{ShaderParamKeyframe, ShaderParamStructField},
+        transition::Interpolation,
+    };
 
     use super::super::*;
 
@@ -203,4 +241,244 @@ mod params_validation {
             Err(ParametersValidationError::WrongFieldName { .. })
         ))
     }
+
+    #[test]
+    fn keyframes() {
+        let (module, ty) = parse_and_get_type(
+            r#"
+                    struct MyType {
+                        float: f32,
+                        int: i32,
+                    }
+                "#,
+            "MyType",
+        );
+
+        let keyframes = ShaderParam::Keyframes(vec![ShaderParamKeyframe {
+            time: std::time::Duration::ZERO,
+            value: 1.0,
+            interpolation: Interpolation::Linear,
+        }]);
+
+        let params = ShaderParam::Struct(vec![
+            ShaderParamStructField {
+                field_name: "float".into(),
+                value: keyframes.clone(),
+            },
+            ShaderParamStructField {
+                field_name: "int".into(),
+                value: ShaderParam::I32(5),
+            },
+        ]);
+        validate_params(&params, ty, &module).unwrap();
+
+        let params = ShaderParam::Struct(vec![
+            ShaderParamStructField {
+                field_name: "float".into(),
+                value: ShaderParam::F32(1.0),
+            },
+            ShaderParamStructField {
+                field_name: "int".into(),
+                value: keyframes,
+            },
+        ]);
+        assert!(matches!(
+            validate_params(&params, ty, &module),
+            Err(ParametersValidationError::WrongFieldType { .. })
+        ))
+    }
+
+    #[test]
+    fn derived_type() {
+        let (module, ty) = parse_and_get_type(
+            r#"
+                    struct Color {
+                        rgba: vec4<f32>,
+                    }
+
+                    struct MyType {
+                        count: u32,
+                        colors: array<Color, 4>,
+                        transform: mat2x3<f32>,
+                    }
+                "#,
+            "MyType",
+        );
+
+        let field = |name: &str, field_type| ShaderParamStructFieldType {
+            field_name: name.to_string(),
+            field_type,
+        };
+        assert_eq!(
+            params_type(ty, &module).unwrap(),
+            ShaderParamType::Struct(vec![
+                field("count", ShaderParamType::U32),
+                field(
+                    "colors",
+                    ShaderParamType::Array {
+                        element: Box::new(ShaderParamType::Struct(vec![field(
+                            "rgba",
+                            ShaderParamType::Vector {
+                                element: Box::new(ShaderParamType::F32),
+                                size: 4,
+                            },
+                        )])),
+                        max_length: Some(4),
+                    },
+                ),
+                field(
+                    "transform",
+                    ShaderParamType::Matrix {
+                        columns: 2,
+                        rows: 3,
+                    },
+                ),
+            ])
+        );
+
+        let (module, ty) = parse_and_get_type(
+            r#"
+                    struct MyType {
+                        flag: bool,
+                    }
+                "#,
+            "MyType",
+        );
+        assert!(matches!(
+            params_type(ty, &module),
+            Err(ParametersValidationError::WrongFieldType { .. })
+        ))
+    }
+
+    #[test]
+    fn object_params() {
+        let (module, ty) = parse_and_get_type(
+            r#"
+                    struct Light {
+                        position: vec2<f32>,
+                        intensity: f32,
+                    }
+
+                    struct MyType {
+                        count: u32,
+                        lights: array<Light, 2>,
+                        offset: i32,
+                    }
+                "#,
+            "MyType",
+        );
+        let params_type = params_type(ty, &module).unwrap();
+
+        let light = ShaderParamValue::Object(HashMap::from([(
+            "position".to_string(),
+            ShaderParamValue::List(vec![
+                ShaderParamValue::Number(0.5),
+                ShaderParamValue::Number(1.0),
+            ]),
+        )]));
+        let params = ShaderParam::Object(HashMap::from([
+            ("lights".to_string(), ShaderParamValue::List(vec![light])),
+            ("count".to_string(), ShaderParamValue::Number(3.0)),
+        ]));
+        // missing fields are filled with zeros, struct fields follow the shader order
+        let resolved = resolve_object_params(&params, &params_type).unwrap();
+        assert!(validate_params(&resolved, ty, &module).is_ok());
+        let ShaderParam::Struct(fields) = &resolved else {
+            panic!("expected struct, got {resolved:?}");
+        };
+        let names: Vec<_> = fields
+            .iter()
+            .map(|field| field.field_name.as_str())
+            .collect();
+        assert_eq!(names, ["count", "lights", "offset"]);
+        assert!(matches!(fields[0].value, ShaderParam::U32(3)));
+        assert!(matches!(fields[2].value, ShaderParam::I32(0)));
+
+        let unknown_field = ShaderParam::Object(HashMap::from([(
+            "color".to_string(),
+            ShaderParamValue::Number(1.0),
+        )]));
+        assert!(matches!(
+            resolve_object_params(&unknown_field, &params_type),
+            Err(ParametersValidationError::UnknownField { field, .. }) if field == "color"
+        ));
+
+        let negative_count = ShaderParam::Object(HashMap::from([(
+            "count".to_string(),
+            ShaderParamValue::Number(-1.0),
+        )]));
+        let Err(ParametersValidationError::WrongObjectFieldValue { field, error }) =
+            resolve_object_params(&negative_count, &params_type)
+        else {
+            panic!("expected error naming the field");
+        };
+        assert_eq!(field, "count");
+        assert!(matches!(
+            *error,
+            ParametersValidationError::InvalidNumber {
+                expected: "u32",
+                ..
+            }
+        ));
+    }
+}
+
+mod capabilities_checking {
+    use super::super::*;
+
+    fn capabilities() -> GpuCapabilities {
+        GpuCapabilities {
+            adapter_name: "test".to_string(),
+            backend: "Vulkan".to_string(),
+            max_push_constant_size: 16,
+            max_binding_array_size: 16,
+            max_uniform_buffer_binding_size: 16384,
+            max_texture_dimension_2d: 8192,
+            shader_f16: false,
+            shader_f64: false,
+        }
+    }
+
+    #[test]
+    fn push_constants_too_large() {
+        let shader = r#"
+            struct Params {
+                a: vec4<f32>,
+                b: vec4<f32>,
+            }
+            var<push_constant> params: Params;
+            "#;
+        let shader = naga::front::wgsl::parse_str(shader).unwrap();
+
+        assert!(matches!(
+            validate_capabilities(&shader, &capabilities()),
+            Err(ShaderValidationError::PushConstantsTooLarge { size: 32, max: 16 })
+        ));
+    }
+
+    #[test]
+    fn binding_array_too_large() {
+        let shader = r#"
+            @group(0) @binding(0) var textures: binding_array<texture_2d<f32>, 32>;
+            "#;
+        let shader = naga::front::wgsl::parse_str(shader).unwrap();
+
+        assert!(matches!(
+            validate_capabilities(&shader, &capabilities()),
+            Err(ShaderValidationError::BindingArrayTooLarge { size: 32, .. })
+        ));
+    }
+
+    #[test]
+    fn f64_not_supported() {
+        let shader = r#"
+            @group(1) @binding(0) var<uniform> value: f64;
+            "#;
+        let shader = naga::front::wgsl::parse_str(shader).unwrap();
+
+        assert!(matches!(
+            validate_capabilities(&shader, &capabilities()),
+            Err(ShaderValidationError::UnsupportedFeature(_, _))
+        ));
+    }
 }
@@ -1,11 +1,15 @@
 use anyhow::{anyhow, Result};
 
-use log::error;
+use compositor_common::scene::Resolution;
+use log::{error, info};
 use reqwest::{blocking::Response, StatusCode};
 use std::{
     fs::File,
-    io::{self, Write},
+    io::{self, Read, Write},
     path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+    sync::mpsc,
+    thread,
     time::Duration,
 };
 
@@ -105,3 +109,147 @@ pub fn ensure_downloaded(url: &str, destination: &Path) -> Result<()> {
     }
     download(url, destination)
 }
+
+/// Examples started with `--headless-check` verify decoded output frames instead of
+/// opening a player, so they can be used as smoke tests.
+#[allow(dead_code)]
+pub fn is_headless_check() -> bool {
+    std::env::args().any(|arg| arg == "--headless-check")
+}
+
+/// Output frame converted to RGB24.
+#[allow(dead_code)]
+pub struct RgbFrame {
+    pub resolution: Resolution,
+    pub data: Vec<u8>,
+}
+
+#[allow(dead_code)]
+impl RgbFrame {
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        let offset = (y * self.resolution.width + x) * 3;
+        [
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+        ]
+    }
+
+    /// Colors are compared with a tolerance, because the output is encoded with a lossy codec.
+    pub fn expect_color(&self, x: usize, y: usize, expected: [u8; 3]) -> Result<()> {
+        let actual = self.pixel(x, y);
+        let is_close = actual
+            .iter()
+            .zip(expected.iter())
+            .all(|(a, e)| a.abs_diff(*e) <= 40);
+        match is_close {
+            true => Ok(()),
+            false => Err(anyhow!(
+                "Expected color {expected:?} at ({x}, {y}), found {actual:?}."
+            )),
+        }
+    }
+
+    pub fn expect_not_black(&self) -> Result<()> {
+        match self.data.iter().any(|value| *value > 40) {
+            true => Ok(()),
+            false => Err(anyhow!("Output frame is black.")),
+        }
+    }
+}
+
+/// Opens the output stream in ffplay. With `--headless-check`, receives `frame_count`
+/// frames instead, runs `check` on the last one and exits the process with the result.
+#[allow(dead_code)]
+pub fn watch_output<F>(
+    port: u16,
+    resolution: Resolution,
+    frame_count: usize,
+    check: F,
+) -> Result<()>
+where
+    F: FnOnce(&RgbFrame) -> Result<()> + Send + 'static,
+{
+    let output_sdp = write_example_sdp_file("127.0.0.1", port)?;
+    if !is_headless_check() {
+        Command::new("ffplay")
+            .args(["-protocol_whitelist", "file,rtp,udp", &output_sdp])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        return Ok(());
+    }
+
+    let mut decoder = Command::new("ffmpeg");
+    decoder.args(["-protocol_whitelist", "file,rtp,udp", "-i", &output_sdp]);
+    spawn_headless_check(decoder, resolution, Some(frame_count), check);
+    Ok(())
+}
+
+/// Decodes all frames of a video file, runs `check` on the last one and exits
+/// the process with the result.
+#[allow(dead_code)]
+pub fn check_output_file<F>(path: &Path, resolution: Resolution, check: F)
+where
+    F: FnOnce(&RgbFrame) -> Result<()> + Send + 'static,
+{
+    let mut decoder = Command::new("ffmpeg");
+    decoder.arg("-i").arg(path);
+    spawn_headless_check(decoder, resolution, None, check);
+}
+
+const HEADLESS_CHECK_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn spawn_headless_check<F>(
+    mut decoder: Command,
+    resolution: Resolution,
+    frame_count: Option<usize>,
+    check: F,
+) where
+    F: FnOnce(&RgbFrame) -> Result<()> + Send + 'static,
+{
+    if let Some(frame_count) = frame_count {
+        decoder.args(["-frames:v", &frame_count.to_string()]);
+    }
+    decoder
+        .args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let result = read_last_frame(decoder, resolution).and_then(|frame| check(&frame));
+        sender.send(result).unwrap();
+    });
+    thread::spawn(move || {
+        let result = receiver
+            .recv_timeout(HEADLESS_CHECK_TIMEOUT)
+            .unwrap_or_else(|_| Err(anyhow!("Timed out waiting for output frames.")));
+        match result {
+            Ok(()) => {
+                info!("[example] Headless check passed.");
+                process::exit(0);
+            }
+            Err(err) => {
+                error!("[example] Headless check failed: {err}");
+                process::exit(1);
+            }
+        }
+    });
+}
+
+fn read_last_frame(mut decoder: Command, resolution: Resolution) -> Result<RgbFrame> {
+    let mut child = decoder.spawn()?;
+    let mut stdout = child.stdout.take().ok_or_else(|| anyhow!("no stdout"))?;
+
+    let frame_size = resolution.width * resolution.height * 3;
+    let mut frame = vec![0; frame_size];
+    let mut last_frame = None;
+    while stdout.read_exact(&mut frame).is_ok() {
+        last_frame = Some(frame.clone());
+    }
+    child.wait()?;
+
+    let data = last_frame.ok_or_else(|| anyhow!("No frames were decoded."))?;
+    Ok(RgbFrame { resolution, data })
+}
@@ -0,0 +1,168 @@
+use anyhow::{anyhow, Result};
+use compositor_common::scene::Resolution;
+use log::{error, info};
+use serde_json::json;
+use std::{path::PathBuf, process::Command, thread, time::Duration};
+use video_compositor::http;
+
+#[path = "./common/common.rs"]
+mod common;
+
+const VIDEO_RESOLUTION: Resolution = Resolution {
+    width: 1280,
+    height: 720,
+};
+
+const FRAMERATE: u32 = 30;
+const DURATION_SECS: u32 = 5;
+
+const INPUT_FILE_PATH: &str = "/tmp/example_file_to_file_input.mp4";
+const OUTPUT_FILE_PATH: &str = "/tmp/example_file_to_file_output.mp4";
+
+fn main() {
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "info"),
+    );
+    ffmpeg_next::format::network::init();
+
+    thread::spawn(|| {
+        if let Err(err) = start_example_client_code() {
+            error!("{err}");
+            std::process::exit(1);
+        }
+    });
+
+    http::Server::new(8001).run();
+}
+
+/// Streams a file into the compositor and records the output into another file.
+/// The compositor works in real time, so rendering takes as long as the input file.
+fn start_example_client_code() -> Result<()> {
+    thread::sleep(Duration::from_secs(2));
+
+    info!("[example] Generate input file.");
+    let status = Command::new("ffmpeg")
+        .args([
+            "-y",
+            "-f",
+            "lavfi",
+            "-i",
+            &format!(
+                "testsrc=s={}x{}:r={}:d={}",
+                VIDEO_RESOLUTION.width, VIDEO_RESOLUTION.height, FRAMERATE, DURATION_SECS
+            ),
+            "-pix_fmt",
+            "yuv420p",
+            "-c:v",
+            "libx264",
+            INPUT_FILE_PATH,
+        ])
+        .status()?;
+    if !status.success() {
+        return Err(anyhow!("Failed to generate the input file."));
+    }
+
+    info!("[example] Sending init request.");
+    common::post(&json!({
+        "type": "init",
+        "web_renderer": {
+            "init": false
+        },
+        "framerate": FRAMERATE,
+    }))?;
+
+    info!("[example] Start recording the output.");
+    let output_sdp = common::write_example_sdp_file("127.0.0.1", 8002)?;
+    let mut recorder = Command::new("ffmpeg")
+        .args([
+            "-y",
+            "-protocol_whitelist",
+            "file,rtp,udp",
+            "-i",
+            &output_sdp,
+            "-t",
+            &DURATION_SECS.to_string(),
+            "-c:v",
+            "libx264",
+            "-preset",
+            "ultrafast",
+            OUTPUT_FILE_PATH,
+        ])
+        .spawn()?;
+
+    info!("[example] Send register output request.");
+    common::post(&json!({
+        "type": "register",
+        "entity_type": "output_stream",
+        "output_id": "output_1",
+        "port": 8002,
+        "ip": "127.0.0.1",
+        "resolution": {
+            "width": VIDEO_RESOLUTION.width,
+            "height": VIDEO_RESOLUTION.height,
+        },
+        "encoder_settings": {
+            "preset": "ultrafast"
+        }
+    }))?;
+
+    info!("[example] Send register input request.");
+    common::post(&json!({
+        "type": "register",
+        "entity_type": "input_stream",
+        "input_id": "input_1",
+        "port": 8004
+    }))?;
+
+    info!("[example] Update scene");
+    common::post(&json!({
+        "type": "update_scene",
+        "nodes": [
+            {
+                "node_id": "mirrored",
+                "type": "builtin:mirror_image",
+                "input_pads": ["input_1"],
+            }
+        ],
+        "outputs": [
+            {
+                "output_id": "output_1",
+                "input_pad": "mirrored"
+            }
+        ]
+    }))?;
+
+    info!("[example] Start pipeline");
+    common::post(&json!({
+        "type": "start",
+    }))?;
+
+    info!("[example] Start input stream");
+    Command::new("ffmpeg")
+        .args([
+            "-re",
+            "-i",
+            INPUT_FILE_PATH,
+            "-c:v",
+            "libx264",
+            "-f",
+            "rtp",
+            "rtp://127.0.0.1:8004?rtcpport=8004",
+        ])
+        .spawn()?;
+
+    recorder.wait()?;
+    info!("[example] Output saved to {OUTPUT_FILE_PATH}.");
+
+    if common::is_headless_check() {
+        common::check_output_file(
+            &PathBuf::from(OUTPUT_FILE_PATH),
+            VIDEO_RESOLUTION,
+            |frame| frame.expect_not_black(),
+        );
+    } else {
+        std::process::exit(0);
+    }
+
+    Ok(())
+}
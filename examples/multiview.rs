@@ -0,0 +1,138 @@
+use anyhow::Result;
+use compositor_common::scene::Resolution;
+use log::{error, info};
+use serde_json::json;
+use std::{process::Command, thread, time::Duration};
+use video_compositor::http;
+
+#[path = "./common/common.rs"]
+mod common;
+
+const VIDEO_RESOLUTION: Resolution = Resolution {
+    width: 1920,
+    height: 1080,
+};
+
+const FRAMERATE: u32 = 30;
+
+/// Input ports and colors of the solid color test sources.
+const INPUTS: [(u16, &str, [u8; 3]); 4] = [
+    (8004, "red", [255, 0, 0]),
+    (8006, "lime", [0, 255, 0]),
+    (8008, "blue", [0, 0, 255]),
+    (8010, "yellow", [255, 255, 0]),
+];
+
+fn main() {
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "info"),
+    );
+    ffmpeg_next::format::network::init();
+
+    thread::spawn(|| {
+        if let Err(err) = start_example_client_code() {
+            error!("{err}")
+        }
+    });
+
+    http::Server::new(8001).run();
+}
+
+fn start_example_client_code() -> Result<()> {
+    thread::sleep(Duration::from_secs(2));
+
+    info!("[example] Sending init request.");
+    common::post(&json!({
+        "type": "init",
+        "web_renderer": {
+            "init": false
+        },
+        "framerate": FRAMERATE,
+    }))?;
+
+    info!("[example] Start listening on output port.");
+    common::watch_output(8002, VIDEO_RESOLUTION, FRAMERATE as usize * 5, |frame| {
+        // 2x2 grid, each tile has a quarter of the output
+        let (width, height) = (VIDEO_RESOLUTION.width, VIDEO_RESOLUTION.height);
+        frame.expect_color(width / 4, height / 4, INPUTS[0].2)?;
+        frame.expect_color(width * 3 / 4, height / 4, INPUTS[1].2)?;
+        frame.expect_color(width / 4, height * 3 / 4, INPUTS[2].2)?;
+        frame.expect_color(width * 3 / 4, height * 3 / 4, INPUTS[3].2)
+    })?;
+
+    info!("[example] Send register output request.");
+    common::post(&json!({
+        "type": "register",
+        "entity_type": "output_stream",
+        "output_id": "output_1",
+        "port": 8002,
+        "ip": "127.0.0.1",
+        "resolution": {
+            "width": VIDEO_RESOLUTION.width,
+            "height": VIDEO_RESOLUTION.height,
+        },
+        "encoder_settings": {
+            "preset": "ultrafast"
+        }
+    }))?;
+
+    info!("[example] Send register input requests.");
+    for (index, (port, _, _)) in INPUTS.iter().enumerate() {
+        common::post(&json!({
+            "type": "register",
+            "entity_type": "input_stream",
+            "input_id": format!("input_{}", index + 1),
+            "port": port
+        }))?;
+    }
+
+    info!("[example] Update scene");
+    common::post(&json!({
+        "type": "update_scene",
+        "nodes": [
+            {
+                "node_id": "layout",
+                "type": "builtin:tiled_layout",
+                "resolution": { "width": VIDEO_RESOLUTION.width, "height": VIDEO_RESOLUTION.height },
+                "input_pads": ["input_1", "input_2", "input_3", "input_4"],
+            }
+        ],
+        "outputs": [
+            {
+                "output_id": "output_1",
+                "input_pad": "layout"
+            }
+        ]
+    }))?;
+
+    info!("[example] Start pipeline");
+    common::post(&json!({
+        "type": "start",
+    }))?;
+
+    info!("[example] Start input streams");
+    for (port, color, _) in INPUTS {
+        let ffmpeg_source = format!(
+            "color=c={}:s={}x{}:r=30,format=yuv420p",
+            color,
+            VIDEO_RESOLUTION.width / 2,
+            VIDEO_RESOLUTION.height / 2
+        );
+        Command::new("ffmpeg")
+            .args([
+                "-re",
+                "-f",
+                "lavfi",
+                "-i",
+                &ffmpeg_source,
+                "-c:v",
+                "libx264",
+                "-f",
+                "rtp",
+                &format!("rtp://127.0.0.1:{port}?rtcpport={port}"),
+            ])
+            .spawn()?;
+    }
+
+    Ok(())
+}
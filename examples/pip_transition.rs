@@ -0,0 +1,159 @@
+use anyhow::Result;
+use compositor_common::scene::Resolution;
+use log::{error, info};
+use serde_json::json;
+use std::{process::Command, thread, time::Duration};
+use video_compositor::http;
+
+#[path = "./common/common.rs"]
+mod common;
+
+const VIDEO_RESOLUTION: Resolution = Resolution {
+    width: 1920,
+    height: 1080,
+};
+
+const PIP_RESOLUTION: Resolution = Resolution {
+    width: 480,
+    height: 270,
+};
+
+const FRAMERATE: u32 = 30;
+
+fn main() {
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "info"),
+    );
+    ffmpeg_next::format::network::init();
+
+    thread::spawn(|| {
+        if let Err(err) = start_example_client_code() {
+            error!("{err}")
+        }
+    });
+
+    http::Server::new(8001).run();
+}
+
+fn start_example_client_code() -> Result<()> {
+    thread::sleep(Duration::from_secs(2));
+
+    info!("[example] Sending init request.");
+    common::post(&json!({
+        "type": "init",
+        "web_renderer": {
+            "init": false
+        },
+        "framerate": FRAMERATE,
+    }))?;
+
+    info!("[example] Start listening on output port.");
+    // Transition takes 2 seconds, check a frame after it finished.
+    common::watch_output(8002, VIDEO_RESOLUTION, FRAMERATE as usize * 5, |frame| {
+        let (width, height) = (VIDEO_RESOLUTION.width, VIDEO_RESOLUTION.height);
+        frame.expect_color(width / 2, height / 2, [255, 0, 0])?;
+        frame.expect_color(
+            width - PIP_RESOLUTION.width / 2,
+            height - PIP_RESOLUTION.height / 2,
+            [0, 0, 255],
+        )
+    })?;
+
+    info!("[example] Send register output request.");
+    common::post(&json!({
+        "type": "register",
+        "entity_type": "output_stream",
+        "output_id": "output_1",
+        "port": 8002,
+        "ip": "127.0.0.1",
+        "resolution": {
+            "width": VIDEO_RESOLUTION.width,
+            "height": VIDEO_RESOLUTION.height,
+        },
+        "encoder_settings": {
+            "preset": "ultrafast"
+        }
+    }))?;
+
+    info!("[example] Send register input requests.");
+    common::post(&json!({
+        "type": "register",
+        "entity_type": "input_stream",
+        "input_id": "main",
+        "port": 8004
+    }))?;
+    common::post(&json!({
+        "type": "register",
+        "entity_type": "input_stream",
+        "input_id": "pip",
+        "port": 8006
+    }))?;
+
+    info!("[example] Update scene");
+    common::post(&json!({
+        "type": "update_scene",
+        "nodes": [
+            {
+                "type": "transition",
+                "node_id": "layout",
+                "start": {
+                    "type": "builtin:fixed_position_layout",
+                    "texture_layouts": [
+                        { "left": "0px", "top": "0px" },
+                        { "left": "0px", "top": "0px" },
+                    ],
+                    "resolution": { "width": VIDEO_RESOLUTION.width, "height": VIDEO_RESOLUTION.height },
+                },
+                "end": {
+                    "type": "builtin:fixed_position_layout",
+                    "texture_layouts": [
+                        { "left": "0px", "top": "0px" },
+                        { "right": "0px", "bottom": "0px" },
+                    ],
+                    "resolution": { "width": VIDEO_RESOLUTION.width, "height": VIDEO_RESOLUTION.height },
+                },
+                "interpolation": "linear",
+                "transition_duration_ms": 2_000,
+                "input_pads": ["main", "pip"],
+            }
+        ],
+        "outputs": [
+            {
+                "output_id": "output_1",
+                "input_pad": "layout"
+            }
+        ]
+    }))?;
+
+    info!("[example] Start pipeline");
+    common::post(&json!({
+        "type": "start",
+    }))?;
+
+    info!("[example] Start input streams");
+    for (port, color, resolution) in [
+        (8004, "red", VIDEO_RESOLUTION),
+        (8006, "blue", PIP_RESOLUTION),
+    ] {
+        let ffmpeg_source = format!(
+            "color=c={}:s={}x{}:r=30,format=yuv420p",
+            color, resolution.width, resolution.height
+        );
+        Command::new("ffmpeg")
+            .args([
+                "-re",
+                "-f",
+                "lavfi",
+                "-i",
+                &ffmpeg_source,
+                "-c:v",
+                "libx264",
+                "-f",
+                "rtp",
+                &format!("rtp://127.0.0.1:{port}?rtcpport={port}"),
+            ])
+            .spawn()?;
+    }
+
+    Ok(())
+}
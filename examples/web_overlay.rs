@@ -0,0 +1,157 @@
+use anyhow::Result;
+use compositor_chromium::cef::bundle_for_development;
+use compositor_common::scene::Resolution;
+use log::{error, info};
+use serde_json::json;
+use std::{env, process::Command, thread, time::Duration};
+use video_compositor::http;
+
+#[path = "./common/common.rs"]
+mod common;
+
+const HTML_FILE_PATH: &str = "examples/web_overlay.html";
+
+const VIDEO_RESOLUTION: Resolution = Resolution {
+    width: 1920,
+    height: 1080,
+};
+
+const FRAMERATE: u32 = 30;
+
+fn main() {
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "info"),
+    );
+    ffmpeg_next::format::network::init();
+    let target_path = &std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("..");
+
+    if let Err(err) = bundle_for_development(target_path) {
+        panic!(
+            "Build process helper first. For release profile use: cargo build -r --bin process_helper. {:?}",
+            err
+        );
+    }
+    thread::spawn(|| {
+        if let Err(err) = start_example_client_code() {
+            error!("{err}")
+        }
+    });
+
+    http::Server::new(8001).run();
+}
+
+fn start_example_client_code() -> Result<()> {
+    thread::sleep(Duration::from_secs(2));
+
+    info!("[example] Sending init request.");
+    common::post(&json!({
+        "type": "init",
+        "framerate": FRAMERATE,
+    }))?;
+
+    info!("[example] Start listening on output port.");
+    common::watch_output(8002, VIDEO_RESOLUTION, FRAMERATE as usize * 5, |frame| {
+        let (width, height) = (VIDEO_RESOLUTION.width, VIDEO_RESOLUTION.height);
+        // input stream is visible above the lower third rendered by the website
+        frame.expect_color(width / 2, height / 4, [0, 255, 0])?;
+        frame.expect_color(width - 100, height - 100, [255, 0, 255])
+    })?;
+
+    let file_path = env::current_dir()?
+        .join(HTML_FILE_PATH)
+        .display()
+        .to_string();
+
+    info!("[example] Send register output request.");
+    common::post(&json!({
+        "type": "register",
+        "entity_type": "output_stream",
+        "output_id": "output_1",
+        "port": 8002,
+        "ip": "127.0.0.1",
+        "resolution": {
+            "width": VIDEO_RESOLUTION.width,
+            "height": VIDEO_RESOLUTION.height,
+        },
+        "encoder_settings": {
+            "preset": "ultrafast"
+        }
+    }))?;
+
+    info!("[example] Send register input request.");
+    common::post(&json!({
+        "type": "register",
+        "entity_type": "input_stream",
+        "input_id": "input_1",
+        "port": 8004
+    }))?;
+
+    info!("[example] Register web renderer transform");
+    common::post(&json!({
+        "type": "register",
+        "entity_type": "web_renderer",
+        "instance_id": "overlay",
+        "url": format!("file://{file_path}"),
+        "resolution": { "width": VIDEO_RESOLUTION.width, "height": VIDEO_RESOLUTION.height },
+    }))?;
+
+    info!("[example] Update scene");
+    common::post(&json!({
+        "type": "update_scene",
+        "nodes": [
+            {
+                "node_id": "overlay",
+                "type": "web_renderer",
+                "instance_id": "overlay",
+                "input_pads": [],
+            },
+            {
+                "node_id": "layout",
+                "type": "builtin:fixed_position_layout",
+                "texture_layouts": [
+                    { "left": "0px", "top": "0px" },
+                    { "left": "0px", "top": "0px" },
+                ],
+                "resolution": { "width": VIDEO_RESOLUTION.width, "height": VIDEO_RESOLUTION.height },
+                "input_pads": ["input_1", "overlay"],
+            }
+        ],
+        "outputs": [
+            {
+                "output_id": "output_1",
+                "input_pad": "layout"
+            }
+        ]
+    }))?;
+
+    info!("[example] Start pipeline");
+    common::post(&json!({
+        "type": "start",
+    }))?;
+
+    info!("[example] Start input stream");
+    let ffmpeg_source = format!(
+        "color=c=lime:s={}x{}:r=30,format=yuv420p",
+        VIDEO_RESOLUTION.width, VIDEO_RESOLUTION.height
+    );
+    Command::new("ffmpeg")
+        .args([
+            "-re",
+            "-f",
+            "lavfi",
+            "-i",
+            &ffmpeg_source,
+            "-c:v",
+            "libx264",
+            "-f",
+            "rtp",
+            "rtp://127.0.0.1:8004?rtcpport=8004",
+        ])
+        .spawn()?;
+
+    Ok(())
+}
@@ -1,36 +1,270 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    env, fmt, fs,
+    io::Cursor,
+    net::{IpAddr, SocketAddr},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, PoisonError},
+    time::Duration,
+};
 
-use compositor_pipeline::pipeline::{self};
-use compositor_render::{EventLoop, RegistryType};
+use base64::Engine;
+use bytes::Bytes;
+use compositor_common::{
+    scene::{
+        shader::ShaderParamType,
+        slate::{SlateContent, SlateMode},
+        OutputSlate, SceneSpec,
+    },
+    util::colors::RGBColor,
+};
+use compositor_pipeline::{pipeline, queue};
+use compositor_render::{
+    error::UpdateSceneError,
+    renderer::{Snapshot, SnapshotTarget},
+    EventLoop, RegistryType, SceneSpecExt,
+};
 use crossbeam_channel::{bounded, Receiver};
 
+use log::error;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tiny_http::StatusCode;
 
 use crate::{
+    camera_receiver,
     error::ApiError,
-    rtp_receiver::{self, RtpReceiver},
-    rtp_sender::{self, RtpSender},
+    file_receiver::{self, PlaybackCommand},
+    file_writer,
+    input::{self, Input},
+    logger, mpegts_sender,
+    multiviewer::{self, MultiviewerOptions},
+    output::{self, Output},
+    preview::Previews,
+    rtp_proxy::AllowedSource,
+    rtp_receiver, rtp_sender, rtsp_receiver,
+    scripting::{self, ScriptEvent, ScriptState, Scripts},
     types::{
-        self, InitOptions, InputId, OutputId, RegisterInputRequest, RegisterOutputRequest,
-        RegisterRequest, RendererId, Scene,
+        self, InitOptions, InputId, NodeId, OutputId, RegisterCameraRequest,
+        RegisterInputFileRequest, RegisterInputRequest, RegisterOutputFileRequest,
+        RegisterOutputMpegTsRequest, RegisterOutputRequest, RegisterRequest,
+        RegisterRtspInputRequest, RegisterTestPatternRequest, RendererId, Scene,
     },
 };
 
-pub type Pipeline = compositor_pipeline::Pipeline<RtpReceiver, RtpSender>;
+pub type Pipeline = compositor_pipeline::Pipeline<Input, Output>;
 
-#[derive(Serialize, Deserialize)]
+/// Same as ffmpeg default for UDP outputs.
+const DEFAULT_MULTICAST_TTL: u8 = 16;
+/// How often loaded scripts are checked for inputs that went live or were lost.
+const SCRIPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Request {
     Init(InitOptions),
     Register(RegisterRequest),
     Unregister(UnregisterRequest),
     UpdateScene(types::Scene),
+    /// Validates the scene the same way as `update_scene` (including constraints
+    /// of shaders and resolutions of output nodes), but does not apply it.
+    ValidateScene(types::Scene),
+    UpdateShader(types::ShaderSpec),
     Query(QueryRequest),
     Start,
+    /// Resumes sending rendered frames to an output stopped with "stop_output". The
+    /// first frame is a keyframe.
+    StartOutput {
+        output_id: OutputId,
+    },
+    /// Stops sending rendered frames to the output without unregistering it, e.g.
+    /// while its destination is temporarily down. Registration options are kept,
+    /// so the output can be resumed with "start_output".
+    StopOutput {
+        output_id: OutputId,
+        /// What the output sends while it is stopped. Defaults to nothing.
+        frames: Option<types::StoppedOutputFrames>,
+    },
+    /// Replaces the scene rendered for the output with a slate, e.g. a "be right
+    /// back" image. Frames keep being encoded while the slate is shown, so receivers
+    /// of the stream stay connected. The output has to be used in the scene.
+    UpdateOutputSlate {
+        output_id: OutputId,
+        mode: types::SlateMode,
+        /// Defaults to a black color.
+        content: Option<types::SlateContent>,
+    },
+    /// Forces the next frame of the output to be encoded as a keyframe (IDR), e.g.
+    /// when a new receiver joins the stream.
+    GenerateKeyframe {
+        output_id: OutputId,
+    },
+    /// Embeds text as closed captions in the stream of the output. The output has
+    /// to be registered with `captions.source` set to "api". Text is wrapped into
+    /// rows of 32 characters, each row scrolls in from the bottom of the screen.
+    SendCaptions {
+        output_id: OutputId,
+        text: String,
+    },
+    /// Signals an ad break to downstream systems with a SCTE-35 `splice_insert`
+    /// command, written with the next frame of the output. The output has to be
+    /// registered with `scte35` enabled.
+    InsertScte35 {
+        output_id: OutputId,
+        event_id: u32,
+        splice_type: types::Scte35SpliceType,
+        /// Time from the next frame to the splice point, the splice time in the command
+        /// is based on the output PTS. Ad insertion systems usually expect at least
+        /// 4 seconds. Defaults to 0.
+        preroll_ms: Option<f64>,
+        /// Expected duration of the break, only for "splice_out".
+        break_duration_ms: Option<f64>,
+        /// Return to the network feed when the break ends without waiting for
+        /// "splice_in". Requires `break_duration_ms`. Defaults to false.
+        auto_return: Option<bool>,
+    },
+    /// Reopens the encoder of the output with new settings without interrupting
+    /// the stream. Settings that are not specified are reset to their defaults.
+    UpdateOutput {
+        output_id: OutputId,
+        encoder_settings: types::EncoderSettings,
+    },
+    KeepAlive,
+    /// Changes framerate of all outputs without restarting them. Accepts
+    /// fractional values, e.g. "30000/1001".
+    UpdateFramerate {
+        framerate: types::Framerate,
+    },
+    /// Controls playback of a file input. Fields that are not set are left unchanged.
+    UpdateInputPlayback {
+        input_id: InputId,
+        /// Continue from the keyframe at or before that time (relative to the start
+        /// of the file).
+        seek_ms: Option<f64>,
+        /// Paused input keeps showing its last frame. In offline mode rendering waits
+        /// until the input is resumed.
+        paused: Option<bool>,
+        #[serde(rename = "loop")]
+        loop_playback: Option<bool>,
+        playback_rate: Option<f64>,
+    },
+    /// Plays back frames from the replay buffer of the input in all "replay" nodes
+    /// of that input. The input has to be registered with `replay_buffer_ms`.
+    /// Starting a replay again restarts it from the new position.
+    StartReplay {
+        input_id: InputId,
+        /// Replay starts this far back from the latest buffered frame.
+        offset_ms: f64,
+        /// Duration of buffered frames to play back. Defaults to everything up to
+        /// the latest frame at the time of the request.
+        duration_ms: Option<f64>,
+        /// Playback speed relative to real time, e.g. 0.5 for slow motion. Has to be
+        /// between 0.1 and 10. Defaults to 1.
+        speed: Option<f64>,
+    },
+    /// Stops the replay of the input, "replay" nodes show their fallbacks again.
+    StopReplay {
+        input_id: InputId,
+    },
+    /// Uploads the image to the GPU ahead of its first use and keeps it there until
+    /// it is unregistered. SVGs are rasterized at each of `resolutions` (or at their
+    /// default resolution if not specified), other images are uploaded on register.
+    PreloadImage {
+        image_id: RendererId,
+        resolutions: Option<Vec<types::Resolution>>,
+    },
+    /// Grabs the last frame of an input or a node and registers it as an image
+    /// with `image_id` (freeze frame). The image stays the same after the source
+    /// changes or disconnects, until it is unregistered. Exactly one of `input_id`
+    /// and `node_id` has to be specified, the input or node has to be a part of
+    /// the current scene.
+    FreezeFrame {
+        image_id: RendererId,
+        input_id: Option<InputId>,
+        node_id: Option<NodeId>,
+    },
+    /// Forwards mouse and keyboard events to the page rendered by the web renderer
+    /// instance, e.g. to drive interactive overlays remotely.
+    SendWebRendererEvent {
+        instance_id: RendererId,
+        event: types::WebRendererEvent,
+    },
+    /// Executes JavaScript code in the page rendered by the web renderer instance,
+    /// e.g. to update displayed data without reloading the page. The request does
+    /// not wait for the code to finish.
+    ExecuteJavascript {
+        instance_id: RendererId,
+        code: String,
+    },
+    /// Renders all registered inputs in a grid, labeled with their ids, to the
+    /// output. The grid is updated when inputs are registered or unregistered. The
+    /// output has to be registered first, while the multiviewer is enabled it
+    /// replaces the output of the scene with the same id. Shows up to 16 inputs.
+    EnableMultiviewer {
+        output_id: OutputId,
+        resolution: types::Resolution,
+        /// Chosen to make the grid close to square if not specified.
+        columns: Option<u32>,
+    },
+    DisableMultiviewer,
+    /// Changes log levels without restarting the compositor. `level` has the same
+    /// syntax as the `RUST_LOG` environment variable, e.g. "info,compositor_pipeline=debug".
+    UpdateLoggerLevel {
+        level: String,
+    },
+    /// Loads a Rhai script that updates the scene in reaction to events (inputs
+    /// going live or lost, periodic ticks, "trigger_script" requests). Replaces the
+    /// script with the same id. See `scripting::Scripts` for the script interface.
+    LoadScript {
+        script_id: String,
+        source: String,
+        /// Interval of tick events, defaults to 1 second.
+        tick_interval_ms: Option<f64>,
+    },
+    UnloadScript {
+        script_id: String,
+    },
+    /// Sends a trigger event with the name and payload to all loaded scripts.
+    TriggerScript {
+        name: String,
+        payload: Option<serde_json::Value>,
+    },
+    /// Applies scenes of the entries one after another, following timestamps of
+    /// rendered frames, e.g. for pre-programmed shows. Replaces the running timeline.
+    /// A scene sent with "update_scene" is shown until the next entry starts.
+    /// Inputs and outputs used by any entry can't be unregistered until the
+    /// timeline is stopped.
+    StartTimeline {
+        entries: Vec<types::TimelineEntry>,
+        /// Start again from the first entry after the last one ends. Otherwise the
+        /// scene of the last entry is kept. Defaults to false.
+        #[serde(rename = "loop")]
+        loop_playback: Option<bool>,
+    },
+    /// Stops the timeline, the scene of the current entry is kept.
+    StopTimeline,
+    /// Fields that are not set are left unchanged.
+    UpdateTimelinePlayback {
+        paused: Option<bool>,
+        /// Position relative to the start of the timeline.
+        seek_ms: Option<f64>,
+    },
+    /// Applies register, unregister and update_scene requests. The whole batch is
+    /// validated against the inputs, outputs and renderers it would leave
+    /// registered before anything is applied. Register requests are applied
+    /// first, then update_scene requests and unregister requests last, each in the
+    /// order of the batch, so a failed batch never has to register removed
+    /// entities again. If a register or update_scene request fails, requests that
+    /// were already applied are reverted and the error is returned. An entity
+    /// can't be unregistered and registered again in the same batch. Frames are
+    /// not rendered while the scene updates and unregisters are applied, so the
+    /// scene is never observed half-updated.
+    Batch {
+        requests: Vec<Request>,
+    },
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "entity_type", rename_all = "snake_case")]
 pub enum UnregisterRequest {
     InputStream { input_id: InputId },
@@ -38,37 +272,364 @@ pub enum UnregisterRequest {
     Shader { shader_id: RendererId },
     WebRenderer { instance_id: RendererId },
     Image { image_id: RendererId },
+    Mesh { mesh_id: RendererId },
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "query", rename_all = "snake_case")]
 pub enum QueryRequest {
-    WaitForNextFrame { input_id: InputId },
+    WaitForNextFrame {
+        input_id: InputId,
+    },
     Scene,
     Inputs,
     Outputs,
+    Capabilities,
+    Images,
+    Stats,
+    /// GPU memory used by node textures and registered images.
+    GpuMemory,
+    /// Current log levels in the `RUST_LOG` syntax.
+    LoggerLevel,
+    /// Events emitted by the pipeline since the previous query, oldest first. Up
+    /// to 100 most recent events are kept, e.g. to poll for render failures.
+    Events,
+    /// GPU adapters available on this machine, indexes can be used in the
+    /// `gpu.adapter_index` init option.
+    GpuAdapters,
+    /// SDP of an RTP input, describing where and how the stream should be sent.
+    /// If `path` is specified, SDP is also written to that file. `path` is
+    /// relative to the directory set in `MEMBRANE_VIDEO_COMPOSITOR_SDP_DIR`.
+    InputSdp {
+        input_id: InputId,
+        path: Option<Arc<str>>,
+    },
+    /// SDP of an RTP output that can be passed to a receiver (e.g. `ffplay`).
+    /// If `path` is specified, SDP is also written to that file. `path` is
+    /// relative to the directory set in `MEMBRANE_VIDEO_COMPOSITOR_SDP_DIR`.
+    OutputSdp {
+        output_id: OutputId,
+        path: Option<Arc<str>>,
+    },
+    /// Current entry and position of the running timeline.
+    Timeline,
+    /// Messages posted by the page of the web renderer instance with
+    /// `post_message(string)` since the previous query, oldest first.
+    WebRendererMessages {
+        instance_id: RendererId,
+    },
+    /// Last frame rendered by a node or sent to an output, encoded as a base64
+    /// image. Exactly one of `output_id` and `node_id` has to be specified.
+    Snapshot {
+        output_id: Option<OutputId>,
+        node_id: Option<NodeId>,
+        /// Defaults to "png".
+        format: Option<SnapshotFormat>,
+    },
+    /// Constraints of nodes using a registered renderer and, for shaders, the
+    /// structure expected in `shader_params`. Exactly one of `shader_id`,
+    /// `instance_id` and `image_id` has to be specified.
+    Renderer {
+        shader_id: Option<RendererId>,
+        instance_id: Option<RendererId>,
+        image_id: Option<RendererId>,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotFormat {
+    Png,
+    Jpeg,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum Response {
     Ok {},
     Scene(Scene),
     Inputs { inputs: Vec<InputInfo> },
     Outputs { outputs: Vec<OutputInfo> },
+    Capabilities(CapabilitiesInfo),
+    Images { images: Vec<ImageInfo> },
+    Stats(StatsInfo),
+    Sdp { sdp: String },
+    WebRendererMessages { messages: Vec<String> },
+    Snapshot(SnapshotInfo),
+    GpuMemory(GpuMemoryInfo),
+    GpuAdapters { adapters: Vec<GpuAdapterInfo> },
+    LoggerLevel { level: Option<String> },
+    Events { events: Vec<EventInfo> },
+    Timeline { timeline: Option<TimelineInfo> },
+    Renderer(RendererInfo),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct InputInfo {
     pub id: InputId,
-    pub port: u16,
+    pub port: Option<u16>,
+    /// RTSP URL without credentials.
+    pub url: Option<Arc<str>>,
+    pub device: Option<Arc<str>>,
+    pub path: Option<PathBuf>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct OutputInfo {
     pub id: OutputId,
-    pub port: u16,
-    pub ip: Arc<str>,
+    pub port: Option<u16>,
+    pub ip: Option<Arc<str>>,
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ImageInfo {
+    pub id: RendererId,
+    /// Resolutions of textures currently stored on the GPU.
+    pub resident_resolutions: Vec<types::Resolution>,
+    /// Resolutions that stay on the GPU until the image is unregistered.
+    pub pinned_resolutions: Vec<types::Resolution>,
+    pub gpu_memory_bytes: usize,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SnapshotInfo {
+    pub resolution: types::Resolution,
+    pub format: SnapshotFormat,
+    /// Base64 encoded image.
+    pub data: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct GpuMemoryInfo {
+    /// Textures of scene nodes, including unused ones kept for reuse by later
+    /// scene updates.
+    pub node_textures_bytes: usize,
+    /// Unused node textures, released after a few seconds if not reused.
+    pub pooled_textures_bytes: usize,
+    pub pooled_textures_count: usize,
+    pub images_bytes: usize,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct GpuAdapterInfo {
+    pub index: usize,
+    pub name: String,
+    pub backend: String,
+    pub device_type: String,
+    pub vendor: u32,
+    pub device: u32,
+    /// Adapter is used by the renderer.
+    pub selected: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventInfo {
+    /// Rendering failed `consecutive_failures` times in a row, the render failure
+    /// policy is applied until it recovers. `error` is the last failure.
+    RenderFailing {
+        consecutive_failures: u32,
+        error: String,
+    },
+    /// Frame was rendered successfully after `failed_frames` consecutive failures.
+    RenderRecovered { failed_frames: u32 },
+    /// Renderer panicked while rendering a frame. The frame is handled as a failed
+    /// one, rendering continues with the next frame.
+    RenderPanicked { message: String },
+    /// Rendering stopped because of the "stop" render failure policy.
+    RenderingStopped,
+    /// Scene of the timeline entry was applied.
+    TimelineEntryStarted { entry_index: usize },
+    /// Scene of the timeline entry could not be applied (e.g. because a web
+    /// renderer failed), the previous scene is kept until the next entry.
+    TimelineEntryFailed { entry_index: usize, error: String },
+}
+
+impl From<pipeline::PipelineEvent> for EventInfo {
+    fn from(event: pipeline::PipelineEvent) -> Self {
+        match event {
+            pipeline::PipelineEvent::RenderFailing {
+                consecutive_failures,
+                error,
+            } => Self::RenderFailing {
+                consecutive_failures,
+                error,
+            },
+            pipeline::PipelineEvent::RenderRecovered { failed_frames } => {
+                Self::RenderRecovered { failed_frames }
+            }
+            pipeline::PipelineEvent::RenderPanicked { message } => Self::RenderPanicked { message },
+            pipeline::PipelineEvent::RenderingStopped => Self::RenderingStopped,
+            pipeline::PipelineEvent::TimelineEntryStarted { entry_index } => {
+                Self::TimelineEntryStarted { entry_index }
+            }
+            pipeline::PipelineEvent::TimelineEntryFailed { entry_index, error } => {
+                Self::TimelineEntryFailed { entry_index, error }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct TimelineInfo {
+    pub entry_index: usize,
+    /// Position in the current pass of the timeline.
+    pub position_ms: f64,
+    pub paused: bool,
+    /// Timeline is not looped and the last entry already ended.
+    pub finished: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct StatsInfo {
+    pub rendered_frames: u64,
+    /// Frames dropped because rendering was too slow, see `backpressure` init option.
+    pub dropped_frames: u64,
+    /// Latency added by the compositor, calculated from the most recent frames.
+    pub latency: LatencyInfo,
+}
+
+/// Stages are not measured until the first frame passes them. Queue and total
+/// latency are not measured in offline mode.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct LatencyInfo {
+    /// From the frame reaching the queue until rendering starts. Includes input
+    /// buffering and waiting for other inputs.
+    pub queue: Option<LatencyPercentilesInfo>,
+    pub render: Option<LatencyPercentilesInfo>,
+    /// From the rendered frame reaching the encoder until its packet is produced.
+    pub encode: Option<LatencyPercentilesInfo>,
+    /// From the frame reaching the queue until its packet is produced.
+    pub total: Option<LatencyPercentilesInfo>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct LatencyPercentilesInfo {
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    /// Number of frames the percentiles are calculated from.
+    pub samples: usize,
+}
+
+impl From<pipeline::LatencyPercentiles> for LatencyPercentilesInfo {
+    fn from(percentiles: pipeline::LatencyPercentiles) -> Self {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        Self {
+            p50_ms: ms(percentiles.p50),
+            p90_ms: ms(percentiles.p90),
+            p99_ms: ms(percentiles.p99),
+            max_ms: ms(percentiles.max),
+            samples: percentiles.samples,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct RendererInfo {
+    /// Constraints in the same format as in the register request, including the
+    /// defaults applied when they were not specified.
+    pub constraints: types::NodeConstraints,
+    pub fallback_strategy: types::FallbackStrategy,
+    /// Derived from the uniform struct declared by the shader, `None` if the
+    /// shader doesn't use `shader_params` or the renderer is not a shader.
+    pub shader_params: Option<ShaderParamTypeInfo>,
+    /// Structure expected in `storage_params`, `None` if the shader doesn't
+    /// declare the storage buffer.
+    pub storage_params: Option<ShaderParamTypeInfo>,
+    /// Max number of images in `image_ids`.
+    pub max_images: usize,
+}
+
+impl From<compositor_render::RendererInfo> for RendererInfo {
+    fn from(info: compositor_render::RendererInfo) -> Self {
+        Self {
+            constraints: info.constraints.into(),
+            fallback_strategy: info.fallback_strategy.into(),
+            shader_params: info.shader_params.map(Into::into),
+            storage_params: info.storage_params.map(Into::into),
+            max_images: info.max_images,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShaderParamTypeInfo {
+    /// Accepts `f32` or `keyframes` params.
+    F32,
+    U32,
+    I32,
+    Vector {
+        element: Box<ShaderParamTypeInfo>,
+        size: usize,
+    },
+    Matrix {
+        columns: usize,
+        rows: usize,
+    },
+    /// Params list can be shorter than `max_length`. Runtime-sized arrays in
+    /// storage buffers have no `max_length`.
+    Array {
+        element: Box<ShaderParamTypeInfo>,
+        max_length: Option<usize>,
+    },
+    Struct {
+        fields: Vec<ShaderParamFieldTypeInfo>,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ShaderParamFieldTypeInfo {
+    pub field_name: String,
+    pub field_type: ShaderParamTypeInfo,
+}
+
+impl From<ShaderParamType> for ShaderParamTypeInfo {
+    fn from(ty: ShaderParamType) -> Self {
+        match ty {
+            ShaderParamType::F32 => Self::F32,
+            ShaderParamType::U32 => Self::U32,
+            ShaderParamType::I32 => Self::I32,
+            ShaderParamType::Vector { element, size } => Self::Vector {
+                element: Box::new((*element).into()),
+                size,
+            },
+            ShaderParamType::Matrix { columns, rows } => Self::Matrix { columns, rows },
+            ShaderParamType::Array {
+                element,
+                max_length,
+            } => Self::Array {
+                element: Box::new((*element).into()),
+                max_length,
+            },
+            ShaderParamType::Struct(fields) => Self::Struct {
+                fields: fields
+                    .into_iter()
+                    .map(|field| ShaderParamFieldTypeInfo {
+                        field_name: field.field_name,
+                        field_type: field.field_type.into(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// Limits and features negotiated with the GPU. Shaders that exceed them are
+/// rejected on register.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct CapabilitiesInfo {
+    pub adapter_name: String,
+    pub backend: String,
+    pub max_push_constant_size: u32,
+    pub max_binding_array_size: u32,
+    pub max_uniform_buffer_binding_size: u32,
+    pub max_texture_dimension_2d: u32,
+    pub shader_f16: bool,
+    pub shader_f64: bool,
 }
 
 pub enum ResponseHandler {
@@ -79,12 +640,290 @@ pub enum ResponseHandler {
 
 pub struct Api {
     pipeline: Pipeline,
+    keep_alive_timeout: Option<Duration>,
+    /// Requests that registered currently existing entities, batches are
+    /// validated against this registry before they are applied.
+    registered: HashMap<RegisteredEntity, RegisterRequest>,
+    previews: Previews,
+    /// Last scene sent in the update_scene request, without multiviewer nodes.
+    scene: Arc<SceneSpec>,
+    multiviewer: Option<MultiviewerOptions>,
+    scripts: Scripts,
+    /// Live inputs from the last `run_scripts` call, used to detect changes.
+    live_inputs: HashSet<compositor_common::scene::InputId>,
+}
+
+/// Entity created by a register request and removed by an unregister request.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum RegisteredEntity {
+    Input(compositor_common::scene::InputId),
+    Output(compositor_common::scene::OutputId),
+    Renderer(compositor_common::renderer_spec::RendererId, RegistryType),
+}
+
+impl fmt::Display for RegisteredEntity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisteredEntity::Input(input_id) => write!(f, "input stream \"{input_id}\""),
+            RegisteredEntity::Output(output_id) => write!(f, "output stream \"{output_id}\""),
+            RegisteredEntity::Renderer(renderer_id, registry_type) => write!(
+                f,
+                "{} \"{renderer_id}\"",
+                registry_type.registry_item_name()
+            ),
+        }
+    }
+}
+
+/// Batch validated against the registry it would produce, before anything is
+/// applied.
+struct BatchPlan {
+    /// Applied first, before rendering is paused, in the order of the batch.
+    /// Registered entities are not rendered until a scene uses them.
+    registers: Vec<RegisterRequest>,
+    /// Applied in order after all registers succeeded, while rendering is paused.
+    scenes: Vec<Arc<SceneSpec>>,
+    /// Applied after all scenes, in the order of the batch.
+    unregisters: Vec<RegisteredEntity>,
+    /// Scene after the whole batch is applied.
+    final_scene: Arc<SceneSpec>,
+}
+
+/// Reverts a step applied as a part of a batch.
+enum BatchRollback {
+    Unregister(RegisteredEntity),
+    RestoreScene(Arc<SceneSpec>),
+}
+
+impl BatchPlan {
+    /// Follows the batch on a copy of the registry. Fails if an entity is
+    /// registered twice, unregistered while it doesn't exist, or if a scene
+    /// uses inputs, outputs or renderers that are not registered at its point of
+    /// the batch.
+    fn new(
+        registered: &HashMap<RegisteredEntity, RegisterRequest>,
+        scene: &Arc<SceneSpec>,
+        requests: Vec<Request>,
+    ) -> Result<Self, ApiError> {
+        let mut entities: HashSet<RegisteredEntity> = registered.keys().cloned().collect();
+        // Key output -> fill output
+        let mut key_outputs: HashMap<_, _> = registered.values().filter_map(output_key).collect();
+        let mut plan = BatchPlan {
+            registers: Vec::new(),
+            scenes: Vec::new(),
+            unregisters: Vec::new(),
+            final_scene: scene.clone(),
+        };
+        for request in requests {
+            match request {
+                Request::Register(request) => {
+                    let entity = RegisteredEntity::from(&request);
+                    if plan.unregisters.contains(&entity) {
+                        return Err(invalid_batch(
+                            format!("{entity} is unregistered earlier in the batch, it can't be registered again in the same batch."),
+                            &entity,
+                        ));
+                    }
+                    if !entities.insert(entity.clone()) {
+                        return Err(invalid_batch(
+                            format!("{entity} is already registered."),
+                            &entity,
+                        ));
+                    }
+                    key_outputs.extend(output_key(&request));
+                    plan.registers.push(request);
+                }
+                Request::Unregister(request) => {
+                    let entity = RegisteredEntity::from(request);
+                    if !entities.remove(&entity) {
+                        return Err(invalid_batch(
+                            format!("{entity} is not registered."),
+                            &entity,
+                        ));
+                    }
+                    if let RegisteredEntity::Output(output_id) = &entity {
+                        key_outputs.remove(output_id);
+                        if let Some(key_output_id) = key_outputs
+                            .iter()
+                            .find_map(|(key, fill)| (fill == output_id).then_some(key))
+                        {
+                            return Err(compositor_pipeline::error::UnregisterOutputError::KeyStillRegistered(
+                                output_id.clone(),
+                                key_output_id.clone(),
+                            )
+                            .into());
+                        }
+                    }
+                    plan.unregisters.push(entity);
+                }
+                Request::UpdateScene(scene) => {
+                    let scene = Arc::new(SceneSpec::try_from(scene)?);
+                    plan.validate_scene(&scene, &entities)?;
+                    plan.final_scene = scene.clone();
+                    plan.scenes.push(scene);
+                }
+                _ => {
+                    return Err(ApiError::new(
+                        "INVALID_BATCH_REQUEST",
+                        "Batch can only contain register, unregister and update_scene requests."
+                            .to_string(),
+                        StatusCode(400),
+                    ))
+                }
+            }
+        }
+        // Without update_scene requests in the batch, the current scene has to
+        // stay valid after the unregisters.
+        plan.validate_scene(&plan.final_scene, &entities)?;
+        Ok(plan)
+    }
+
+    /// Scene can only use entities registered at its point of the batch, entities
+    /// unregistered earlier are still registered when it is applied.
+    fn validate_scene(
+        &self,
+        scene: &SceneSpec,
+        entities: &HashSet<RegisteredEntity>,
+    ) -> Result<(), ApiError> {
+        let inputs = entities
+            .iter()
+            .filter_map(|entity| match entity {
+                RegisteredEntity::Input(input_id) => Some(&input_id.0),
+                _ => None,
+            })
+            .collect();
+        let outputs = entities
+            .iter()
+            .filter_map(|entity| match entity {
+                RegisteredEntity::Output(output_id) => Some(&output_id.0),
+                _ => None,
+            })
+            .collect();
+        scene
+            .validate(&inputs, &outputs)
+            .map_err(UpdateSceneError::InvalidSpec)?;
+        for entity in &self.unregisters {
+            if let RegisteredEntity::Renderer(renderer_id, registry_type) = entity {
+                scene.validate_can_unregister(renderer_id, *registry_type)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid_batch(message: String, entity: &RegisteredEntity) -> ApiError {
+    let mut err = ApiError::new("INVALID_BATCH_REQUEST", message, StatusCode(400));
+    err.entity_ids.push(match entity {
+        RegisteredEntity::Input(input_id) => input_id.to_string(),
+        RegisteredEntity::Output(output_id) => output_id.to_string(),
+        RegisteredEntity::Renderer(renderer_id, _) => renderer_id.to_string(),
+    });
+    err
+}
+
+/// Key output and its fill output, if the request registers a key output.
+fn output_key(
+    request: &RegisterRequest,
+) -> Option<(
+    compositor_common::scene::OutputId,
+    compositor_common::scene::OutputId,
+)> {
+    let (output_id, key_of) = match request {
+        RegisterRequest::OutputStream(RegisterOutputRequest {
+            output_id, options, ..
+        })
+        | RegisterRequest::OutputFile(RegisterOutputFileRequest {
+            output_id, options, ..
+        })
+        | RegisterRequest::OutputMpegTs(RegisterOutputMpegTsRequest {
+            output_id, options, ..
+        }) => (output_id, options.key_of.as_ref()?),
+        _ => return None,
+    };
+    Some((output_id.clone().into(), key_of.clone().into()))
 }
 
 impl Api {
-    pub fn new(opts: pipeline::Options) -> Result<(Api, EventLoop), ApiError> {
-        let (pipeline, event_loop) = Pipeline::new(opts)?;
-        Ok((Api { pipeline }, event_loop))
+    pub fn new(opts: InitOptions) -> Result<(Api, EventLoop), ApiError> {
+        let keep_alive_timeout = opts
+            .keep_alive_timeout_ms
+            .map(|timeout_ms| Duration::from_millis(timeout_ms as u64));
+        let (pipeline, event_loop) = Pipeline::new(opts.try_into()?)?;
+        Ok((
+            Api {
+                pipeline,
+                keep_alive_timeout,
+                registered: HashMap::new(),
+                previews: Previews::default(),
+                scene: Arc::new(SceneSpec {
+                    nodes: vec![],
+                    outputs: vec![],
+                }),
+                multiviewer: None,
+                scripts: Scripts::default(),
+                live_inputs: HashSet::new(),
+            },
+            event_loop,
+        ))
+    }
+
+    pub fn keep_alive_timeout(&self) -> Option<Duration> {
+        self.keep_alive_timeout
+    }
+
+    /// Latest JPEG preview of the output, see `preview` option of output registration.
+    pub fn preview(&self, output_id: &str) -> Result<Bytes, ApiError> {
+        let output_id =
+            compositor_common::scene::OutputId(compositor_common::scene::NodeId(output_id.into()));
+        self.previews.get(&output_id).ok_or_else(|| {
+            ApiError::new(
+                "PREVIEW_NOT_AVAILABLE",
+                format!("Preview of output \"{output_id}\" is not available. Output does not exist, has no preview enabled or nothing was rendered yet."),
+                tiny_http::StatusCode(404),
+            )
+        })
+    }
+
+    /// Removes the scene and all registered inputs and outputs. Used when the
+    /// controller stopped sending requests, so the composition does not keep
+    /// running without anyone managing it. Registered renderers are kept.
+    pub fn teardown(&mut self) {
+        let empty_scene = SceneSpec {
+            nodes: vec![],
+            outputs: vec![],
+        };
+        self.multiviewer = None;
+        self.scripts.clear();
+        // Fails only if no timeline is running.
+        let _ = self.pipeline.stop_timeline();
+        if let Err(err) = self.update_scene(Arc::new(empty_scene)) {
+            error!("Failed to clear the scene: {}", err.message);
+        }
+
+        let output_ids: Vec<_> = self
+            .pipeline
+            .with_outputs(|iter| iter.map(|(id, _)| id.clone()).collect());
+        for output_id in output_ids {
+            if let Err(err) = self.unregister(&RegisteredEntity::Output(output_id.clone())) {
+                error!(
+                    "Failed to unregister output \"{output_id}\": {}",
+                    err.message
+                );
+            }
+        }
+
+        let input_ids: Vec<_> = self
+            .pipeline
+            .inputs()
+            .map(|(id, _)| id)
+            .chain(self.pipeline.test_patterns())
+            .cloned()
+            .collect();
+        for input_id in input_ids {
+            if let Err(err) = self.unregister(&RegisteredEntity::Input(input_id.clone())) {
+                error!("Failed to unregister input \"{input_id}\": {}", err.message);
+            }
+        }
     }
 
     pub fn handle_request(&mut self, request: Request) -> Result<ResponseHandler, ApiError> {
@@ -106,12 +945,518 @@ impl Api {
                 self.pipeline.start();
                 Ok(ResponseHandler::Ok)
             }
-            Request::UpdateScene(scene_spec) => {
+            Request::StartOutput { output_id } => {
+                self.pipeline.start_output(&output_id.into())?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::StopOutput { output_id, frames } => {
+                let frames = frames.map(TryInto::try_into).transpose()?;
+                self.pipeline
+                    .stop_output(&output_id.into(), frames.unwrap_or_default())?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::UpdateOutputSlate {
+                output_id,
+                mode,
+                content,
+            } => {
+                let content = match content {
+                    Some(content) => content.try_into()?,
+                    None => SlateContent::Color(RGBColor::BLACK),
+                };
+                let slate =
+                    Option::<SlateMode>::from(mode).map(|mode| OutputSlate { content, mode });
                 self.pipeline
-                    .update_scene(Arc::new(scene_spec.try_into()?))?;
+                    .update_output_slate(&output_id.into(), slate)?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::GenerateKeyframe { output_id } => {
+                self.pipeline.request_keyframe(&output_id.into())?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::SendCaptions { output_id, text } => {
+                self.pipeline.send_captions(&output_id.into(), &text)?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::InsertScte35 {
+                output_id,
+                event_id,
+                splice_type,
+                preroll_ms,
+                break_duration_ms,
+                auto_return,
+            } => {
+                let splice = scte35_splice(
+                    event_id,
+                    splice_type,
+                    preroll_ms,
+                    break_duration_ms,
+                    auto_return,
+                )?;
+                self.pipeline.insert_scte35(&output_id.into(), splice)?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::UpdateOutput {
+                output_id,
+                encoder_settings,
+            } => {
+                self.pipeline
+                    .update_output(&output_id.into(), encoder_settings.try_into()?)?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::UpdateScene(scene_spec) => {
+                self.update_scene(Arc::new(scene_spec.try_into()?))?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::ValidateScene(scene_spec) => {
+                let scene_spec: SceneSpec = scene_spec.try_into()?;
+                self.pipeline.validate_scene(&scene_spec)?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::UpdateShader(spec) => {
+                self.pipeline.update_shader(spec.try_into()?)?;
                 Ok(ResponseHandler::Ok)
             }
             Request::Query(query) => self.handle_query(query),
+            Request::KeepAlive => Ok(ResponseHandler::Ok),
+            Request::UpdateLoggerLevel { level } => {
+                logger::update_level(&level).map_err(|err| {
+                    ApiError::new(
+                        "INVALID_LOGGER_LEVEL",
+                        format!("Failed to update logger level \"{level}\": {err}"),
+                        tiny_http::StatusCode(400),
+                    )
+                })?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::UpdateFramerate { framerate } => {
+                self.pipeline.update_framerate(framerate.try_into()?);
+                Ok(ResponseHandler::Ok)
+            }
+            Request::UpdateInputPlayback {
+                input_id,
+                seek_ms,
+                paused,
+                loop_playback,
+                playback_rate,
+            } => {
+                self.update_input_playback(
+                    input_id,
+                    seek_ms,
+                    paused,
+                    loop_playback,
+                    playback_rate,
+                )?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::StartReplay {
+                input_id,
+                offset_ms,
+                duration_ms,
+                speed,
+            } => {
+                let speed = speed.unwrap_or(1.0);
+                if !(0.1..=10.0).contains(&speed) {
+                    return Err(ApiError::new(
+                        "INVALID_REPLAY_SPEED",
+                        format!("Replay speed has to be between 0.1 and 10, received {speed}."),
+                        StatusCode(400),
+                    ));
+                }
+                self.pipeline.start_replay(
+                    &input_id.into(),
+                    Duration::from_millis(offset_ms.max(0.0) as u64),
+                    duration_ms.map(|ms| Duration::from_millis(ms.max(0.0) as u64)),
+                    speed,
+                )?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::StopReplay { input_id } => {
+                self.pipeline.stop_replay(&input_id.into())?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::PreloadImage {
+                image_id,
+                resolutions,
+            } => {
+                let resolutions = match resolutions {
+                    Some(resolutions) => resolutions
+                        .into_iter()
+                        .map(|resolution| Some(resolution.into()))
+                        .collect(),
+                    None => vec![None],
+                };
+                self.pipeline
+                    .preload_image(&image_id.into(), &resolutions)?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::FreezeFrame {
+                image_id,
+                input_id,
+                node_id,
+            } => {
+                let node_id = match (input_id, node_id) {
+                    (Some(input_id), None) => compositor_common::scene::InputId::from(input_id).0,
+                    (None, Some(node_id)) => node_id.into(),
+                    _ => {
+                        return Err(ApiError::new(
+                            "INVALID_FREEZE_FRAME_SOURCE",
+                            "Freeze frame requires exactly one of \"input_id\" and \"node_id\"."
+                                .to_string(),
+                            StatusCode(400),
+                        ))
+                    }
+                };
+                self.pipeline
+                    .freeze_frame(image_id.into(), &SnapshotTarget::Node(node_id))?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::SendWebRendererEvent { instance_id, event } => {
+                let instance_id = instance_id.into();
+                let events: Vec<compositor_render::WebRendererEvent> = event.into();
+                for event in events {
+                    self.pipeline
+                        .dispatch_web_renderer_event(&instance_id, event)?;
+                }
+                Ok(ResponseHandler::Ok)
+            }
+            Request::ExecuteJavascript { instance_id, code } => {
+                self.pipeline
+                    .execute_web_renderer_javascript(&instance_id.into(), code)?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::EnableMultiviewer {
+                output_id,
+                resolution,
+                columns,
+            } => {
+                let opts = MultiviewerOptions {
+                    output_id: output_id.into(),
+                    resolution: resolution.into(),
+                    columns: columns.map(|columns| columns as usize),
+                };
+                let previous = self.multiviewer.replace(opts);
+                if let Err(err) = self.update_scene(self.scene.clone()) {
+                    self.multiviewer = previous;
+                    return Err(err);
+                }
+                Ok(ResponseHandler::Ok)
+            }
+            Request::DisableMultiviewer => {
+                self.multiviewer = None;
+                self.update_scene(self.scene.clone())?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::StartTimeline {
+                entries,
+                loop_playback,
+            } => {
+                self.start_timeline(entries, loop_playback.unwrap_or(false))?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::StopTimeline => {
+                self.pipeline.stop_timeline()?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::UpdateTimelinePlayback { paused, seek_ms } => {
+                let seek = seek_ms
+                    .map(|seek_ms| Duration::try_from_secs_f64(seek_ms / 1000.0))
+                    .transpose()
+                    .map_err(|err| {
+                        ApiError::new(
+                            "INVALID_TIMELINE_SEEK",
+                            format!("Invalid \"seek_ms\" value: {err}"),
+                            StatusCode(400),
+                        )
+                    })?;
+                self.pipeline.update_timeline_playback(paused, seek)?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::LoadScript {
+                script_id,
+                source,
+                tick_interval_ms,
+            } => {
+                self.load_script(script_id, &source, tick_interval_ms)?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::UnloadScript { script_id } => {
+                self.scripts.unload(&script_id)?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::TriggerScript { name, payload } => {
+                let event = ScriptEvent::Trigger {
+                    name,
+                    payload: payload.unwrap_or_default(),
+                };
+                let mut result = Ok(());
+                for script_id in self.scripts.script_ids() {
+                    let script_result = self.dispatch_script_event(&script_id, &event);
+                    if result.is_ok() {
+                        result = script_result;
+                    }
+                }
+                result?;
+                Ok(ResponseHandler::Ok)
+            }
+            Request::Batch { requests } => {
+                self.handle_batch(requests)?;
+                Ok(ResponseHandler::Ok)
+            }
+        }
+    }
+
+    /// Time until scripts have to be polled with `run_scripts`, `None` if no
+    /// script is loaded.
+    pub fn time_to_next_script_run(&self) -> Option<Duration> {
+        self.scripts
+            .time_to_next_tick()
+            .map(|tick| tick.min(SCRIPT_POLL_INTERVAL))
+    }
+
+    /// Sends events about inputs that went live or were lost since the last call
+    /// and tick events that are due to loaded scripts.
+    pub fn run_scripts(&mut self) {
+        if self.scripts.is_empty() {
+            return;
+        }
+        let live_inputs: HashSet<_> = self.pipeline.live_inputs().cloned().collect();
+        let events: Vec<_> = live_inputs
+            .difference(&self.live_inputs)
+            .map(|input_id| ScriptEvent::InputLive {
+                input_id: input_id.to_string(),
+            })
+            .chain(self.live_inputs.difference(&live_inputs).map(|input_id| {
+                ScriptEvent::InputLost {
+                    input_id: input_id.to_string(),
+                }
+            }))
+            .collect();
+        self.live_inputs = live_inputs;
+
+        let due_ticks = self.scripts.take_due_ticks();
+        for script_id in self.scripts.script_ids() {
+            let tick = due_ticks.contains(&script_id).then_some(ScriptEvent::Tick);
+            for event in events.iter().chain(tick.iter()) {
+                if let Err(err) = self.dispatch_script_event(&script_id, event) {
+                    error!("{}", err.message);
+                }
+            }
+        }
+    }
+
+    fn load_script(
+        &mut self,
+        script_id: String,
+        source: &str,
+        tick_interval_ms: Option<f64>,
+    ) -> Result<(), ApiError> {
+        let tick_interval = match tick_interval_ms {
+            Some(tick_interval_ms) if tick_interval_ms <= 0.0 => {
+                return Err(ApiError::new(
+                    "INVALID_SCRIPT_TICK_INTERVAL",
+                    format!(
+                        "Tick interval has to be a positive number, received {tick_interval_ms}."
+                    ),
+                    StatusCode(400),
+                ));
+            }
+            Some(tick_interval_ms) => Duration::try_from_secs_f64(tick_interval_ms / 1000.0)
+                .map_err(|err| {
+                    ApiError::new(
+                        "INVALID_SCRIPT_TICK_INTERVAL",
+                        format!("Invalid \"tick_interval_ms\" value: {err}"),
+                        StatusCode(400),
+                    )
+                })?,
+            None => scripting::DEFAULT_TICK_INTERVAL,
+        };
+        if self.scripts.is_empty() {
+            // Inputs that are already live are reported in the state, not as events.
+            self.live_inputs = self.pipeline.live_inputs().cloned().collect();
+        }
+        let replaced = self
+            .scripts
+            .load(script_id.clone(), source, tick_interval)?;
+        if let Err(err) = self.dispatch_script_event(&script_id, &ScriptEvent::Load) {
+            self.scripts.restore(script_id, replaced);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Applies the scene returned by the script, if any.
+    fn dispatch_script_event(
+        &mut self,
+        script_id: &str,
+        event: &ScriptEvent,
+    ) -> Result<(), ApiError> {
+        let state = self.script_state();
+        let Some(scene) = self.scripts.handle_event(script_id, event, &state)? else {
+            return Ok(());
+        };
+        let scene: SceneSpec = scene.try_into().map_err(|err| {
+            ApiError::new(
+                "SCRIPT_FAILED",
+                format!("Script \"{script_id}\" returned an invalid scene: {err}"),
+                StatusCode(400),
+            )
+        })?;
+        self.update_scene(Arc::new(scene))
+    }
+
+    fn script_state(&self) -> ScriptState {
+        let mut inputs: Vec<_> = self
+            .pipeline
+            .inputs()
+            .map(|(id, _)| id)
+            .chain(self.pipeline.test_patterns())
+            .map(ToString::to_string)
+            .collect();
+        inputs.sort();
+        let mut live_inputs: Vec<_> = self.live_inputs.iter().map(ToString::to_string).collect();
+        live_inputs.sort();
+        let mut outputs: Vec<_> = self
+            .pipeline
+            .with_outputs(|iter| iter.map(|(id, _)| id.to_string()).collect());
+        outputs.sort();
+        ScriptState {
+            inputs,
+            live_inputs,
+            outputs,
+            scene: self.scene.as_ref().clone().into(),
+        }
+    }
+
+    /// Applies the scene with the multiviewer added, if it's enabled.
+    fn update_scene(&mut self, scene: Arc<SceneSpec>) -> Result<(), ApiError> {
+        self.apply_scene(&scene, None)?;
+        self.scene = scene;
+        Ok(())
+    }
+
+    /// `removed_input` is left out of the multiviewer, so it can be unregistered.
+    fn apply_scene(
+        &mut self,
+        scene: &Arc<SceneSpec>,
+        removed_input: Option<&compositor_common::scene::InputId>,
+    ) -> Result<(), ApiError> {
+        let Some(multiviewer) = &self.multiviewer else {
+            return Ok(self.pipeline.update_scene(scene.clone())?);
+        };
+        let inputs = self.multiviewer_inputs(removed_input);
+        let scene = multiviewer::with_multiviewer(scene, multiviewer, &inputs);
+        Ok(self.pipeline.update_scene(Arc::new(scene))?)
+    }
+
+    /// Inputs shown in the multiviewer, sorted by id.
+    fn multiviewer_inputs(
+        &self,
+        removed_input: Option<&compositor_common::scene::InputId>,
+    ) -> Vec<compositor_common::scene::InputId> {
+        let mut inputs: Vec<_> = self
+            .pipeline
+            .inputs()
+            .map(|(id, _)| id)
+            .chain(self.pipeline.test_patterns())
+            .filter(|id| Some(*id) != removed_input)
+            .cloned()
+            .collect();
+        inputs.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+        inputs
+    }
+
+    /// Scenes of the entries are shown with the multiviewer, if it's enabled.
+    fn start_timeline(
+        &mut self,
+        entries: Vec<types::TimelineEntry>,
+        loop_playback: bool,
+    ) -> Result<(), ApiError> {
+        let inputs = self.multiviewer_inputs(None);
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                let mut entry: pipeline::TimelineEntry = entry.try_into()?;
+                if let Some(multiviewer) = &self.multiviewer {
+                    entry.scene = Arc::new(multiviewer::with_multiviewer(
+                        &entry.scene,
+                        multiviewer,
+                        &inputs,
+                    ));
+                }
+                Ok(entry)
+            })
+            .collect::<Result<_, ApiError>>()?;
+        self.pipeline.start_timeline(pipeline::TimelineSpec {
+            entries,
+            loop_playback,
+        })?;
+        Ok(())
+    }
+
+    fn handle_batch(&mut self, requests: Vec<Request>) -> Result<(), ApiError> {
+        let plan = BatchPlan::new(&self.registered, &self.scene, requests)?;
+        for entity in &plan.unregisters {
+            match entity {
+                RegisteredEntity::Input(input_id) => self
+                    .pipeline
+                    .validate_input_not_in_use(input_id, &plan.final_scene)?,
+                RegisteredEntity::Output(output_id) => self
+                    .pipeline
+                    .validate_output_not_in_use(output_id, &plan.final_scene)?,
+                RegisteredEntity::Renderer(_, _) => (),
+            }
+        }
+
+        let mut rollbacks = Vec::with_capacity(plan.registers.len() + plan.scenes.len());
+        // Registering can take seconds (e.g. probing an input or starting a web
+        // renderer), so outputs keep receiving frames in the meantime.
+        for register_request in plan.registers {
+            let entity = RegisteredEntity::from(&register_request);
+            if let Err(err) = self.handle_register_request(register_request) {
+                self.rollback_batch(rollbacks);
+                return Err(err);
+            }
+            rollbacks.push(BatchRollback::Unregister(entity));
+        }
+
+        let render_lock = self.pipeline.render_lock();
+        let _guard = render_lock.lock().unwrap_or_else(PoisonError::into_inner);
+        for scene_spec in plan.scenes {
+            let previous_scene_spec = self.scene.clone();
+            if let Err(err) = self.update_scene(scene_spec) {
+                self.rollback_batch(rollbacks);
+                return Err(err);
+            }
+            rollbacks.push(BatchRollback::RestoreScene(previous_scene_spec));
+        }
+        for (index, entity) in plan.unregisters.iter().enumerate() {
+            if let Err(err) = self.unregister(entity) {
+                // Removed entities are not registered again, so the batch can only
+                // be reverted if nothing was unregistered yet.
+                match index {
+                    0 => self.rollback_batch(rollbacks),
+                    _ => error!(
+                        "Failed to unregister {entity}, the batch is applied only partially: {}",
+                        err.message
+                    ),
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reverts applied steps in the reverse order.
+    fn rollback_batch(&mut self, rollbacks: Vec<BatchRollback>) {
+        for rollback in rollbacks.into_iter().rev() {
+            let result = match rollback {
+                BatchRollback::Unregister(entity) => self.unregister(&entity),
+                BatchRollback::RestoreScene(scene_spec) => self.update_scene(scene_spec),
+            };
+            if let Err(err) = result {
+                error!("Failed to revert batch request: {}", err.message);
+            }
         }
     }
 
@@ -141,7 +1486,10 @@ impl Api {
                     .inputs()
                     .map(|(id, node)| InputInfo {
                         id: id.clone().into(),
-                        port: node.port,
+                        port: node.port(),
+                        url: node.url().cloned(),
+                        device: node.device().map(Into::into),
+                        path: node.path().map(Into::into),
                     })
                     .collect();
                 Ok(ResponseHandler::Response(Response::Inputs { inputs }))
@@ -150,25 +1498,269 @@ impl Api {
                 let outputs = self.pipeline.with_outputs(|iter| {
                     iter.map(|(id, output)| OutputInfo {
                         id: id.clone().into(),
-                        port: output.port,
-                        ip: output.ip.clone(),
+                        port: output.port(),
+                        ip: output.ip().cloned(),
+                        path: output.path().map(Into::into),
                     })
                     .collect()
                 });
                 Ok(ResponseHandler::Response(Response::Outputs { outputs }))
             }
+            QueryRequest::Images => {
+                let images = self
+                    .pipeline
+                    .renderer()
+                    .images_residency()
+                    .into_iter()
+                    .map(|(id, residency)| ImageInfo {
+                        id: id.into(),
+                        resident_resolutions: residency
+                            .resident_resolutions
+                            .into_iter()
+                            .map(Into::into)
+                            .collect(),
+                        pinned_resolutions: residency
+                            .pinned_resolutions
+                            .into_iter()
+                            .map(Into::into)
+                            .collect(),
+                        gpu_memory_bytes: residency.gpu_memory_bytes,
+                    })
+                    .collect();
+                Ok(ResponseHandler::Response(Response::Images { images }))
+            }
+            QueryRequest::GpuMemory => {
+                let renderer = self.pipeline.renderer();
+                let node_textures = renderer.node_textures_usage();
+                let images_bytes = renderer
+                    .images_residency()
+                    .into_iter()
+                    .map(|(_, residency)| residency.gpu_memory_bytes)
+                    .sum();
+                Ok(ResponseHandler::Response(Response::GpuMemory(
+                    GpuMemoryInfo {
+                        node_textures_bytes: node_textures.allocated_bytes,
+                        pooled_textures_bytes: node_textures.idle_bytes,
+                        pooled_textures_count: node_textures.idle_textures,
+                        images_bytes,
+                    },
+                )))
+            }
+            QueryRequest::GpuAdapters => {
+                let capabilities = self.pipeline.renderer().capabilities();
+                let adapters = compositor_render::available_adapters()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, adapter)| GpuAdapterInfo {
+                        index,
+                        selected: adapter.name == capabilities.adapter_name
+                            && adapter.backend == capabilities.backend,
+                        name: adapter.name,
+                        backend: adapter.backend,
+                        device_type: adapter.device_type,
+                        vendor: adapter.vendor,
+                        device: adapter.device,
+                    })
+                    .collect();
+                Ok(ResponseHandler::Response(Response::GpuAdapters {
+                    adapters,
+                }))
+            }
+            QueryRequest::Events => Ok(ResponseHandler::Response(Response::Events {
+                events: self
+                    .pipeline
+                    .take_events()
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            })),
+            QueryRequest::Timeline => Ok(ResponseHandler::Response(Response::Timeline {
+                timeline: self.pipeline.timeline_status().map(|status| TimelineInfo {
+                    entry_index: status.entry_index,
+                    position_ms: status.position.as_secs_f64() * 1000.0,
+                    paused: status.paused,
+                    finished: status.finished,
+                }),
+            })),
+            QueryRequest::LoggerLevel => Ok(ResponseHandler::Response(Response::LoggerLevel {
+                level: logger::current_level(),
+            })),
+            QueryRequest::Stats => {
+                let stats = self.pipeline.render_stats();
+                let latency = self.pipeline.latency_stats();
+                Ok(ResponseHandler::Response(Response::Stats(StatsInfo {
+                    rendered_frames: stats.rendered_frames(),
+                    dropped_frames: stats.dropped_frames(),
+                    latency: LatencyInfo {
+                        queue: latency.queue().map(Into::into),
+                        render: latency.render().map(Into::into),
+                        encode: latency.encode().map(Into::into),
+                        total: latency.total().map(Into::into),
+                    },
+                })))
+            }
+            QueryRequest::InputSdp { input_id, path } => {
+                let input_id: compositor_common::scene::InputId = input_id.into();
+                let Some((_, input)) = self.pipeline.inputs().find(|(id, _)| **id == input_id)
+                else {
+                    return Err(ApiError::new(
+                        "INPUT_NOT_FOUND",
+                        format!(
+                            "Failed to generate SDP of input \"{input_id}\". Input does not exist."
+                        ),
+                        tiny_http::StatusCode(404),
+                    ));
+                };
+                let Some(sdp) = input.sdp() else {
+                    return Err(ApiError::new(
+                        "SDP_NOT_AVAILABLE",
+                        format!("Failed to generate SDP of input \"{input_id}\". SDP is available only for RTP inputs."),
+                        tiny_http::StatusCode(400),
+                    ));
+                };
+                write_sdp_file(path, &sdp)?;
+                Ok(ResponseHandler::Response(Response::Sdp { sdp }))
+            }
+            QueryRequest::OutputSdp { output_id, path } => {
+                let output_id: compositor_common::scene::OutputId = output_id.into();
+                let sdp = self.pipeline.with_outputs(|mut iter| {
+                    iter.find(|(id, _)| **id == output_id)
+                        .map(|(_, output)| output.sdp())
+                });
+                let sdp = match sdp {
+                    Some(Some(sdp)) => sdp,
+                    Some(None) => {
+                        return Err(ApiError::new(
+                            "SDP_NOT_AVAILABLE",
+                            format!("Failed to generate SDP of output \"{output_id}\". SDP is available only for RTP outputs."),
+                            tiny_http::StatusCode(400),
+                        ))
+                    }
+                    None => {
+                        return Err(ApiError::new(
+                            "OUTPUT_NOT_FOUND",
+                            format!("Failed to generate SDP of output \"{output_id}\". Output does not exist."),
+                            tiny_http::StatusCode(404),
+                        ))
+                    }
+                };
+                write_sdp_file(path, &sdp)?;
+                Ok(ResponseHandler::Response(Response::Sdp { sdp }))
+            }
+            QueryRequest::WebRendererMessages { instance_id } => {
+                let messages = self
+                    .pipeline
+                    .take_web_renderer_messages(&instance_id.into())?;
+                Ok(ResponseHandler::Response(Response::WebRendererMessages {
+                    messages,
+                }))
+            }
+            QueryRequest::Snapshot {
+                output_id,
+                node_id,
+                format,
+            } => {
+                let target =
+                    match (output_id, node_id) {
+                        (Some(output_id), None) => SnapshotTarget::Output(output_id.into()),
+                        (None, Some(node_id)) => SnapshotTarget::Node(node_id.into()),
+                        _ => return Err(ApiError::new(
+                            "INVALID_SNAPSHOT_TARGET",
+                            "Snapshot query requires exactly one of \"output_id\" and \"node_id\"."
+                                .to_string(),
+                            tiny_http::StatusCode(400),
+                        )),
+                    };
+                let format = format.unwrap_or(SnapshotFormat::Png);
+                let snapshot = self.pipeline.renderer().snapshot(&target, None)?;
+                let resolution = snapshot.resolution;
+                let image = encode_snapshot(snapshot, format)?;
+                Ok(ResponseHandler::Response(Response::Snapshot(
+                    SnapshotInfo {
+                        resolution: resolution.into(),
+                        format,
+                        data: base64::engine::general_purpose::STANDARD.encode(image),
+                    },
+                )))
+            }
+            QueryRequest::Capabilities => {
+                let capabilities = self.pipeline.renderer().capabilities();
+                Ok(ResponseHandler::Response(Response::Capabilities(
+                    CapabilitiesInfo {
+                        adapter_name: capabilities.adapter_name,
+                        backend: capabilities.backend,
+                        max_push_constant_size: capabilities.max_push_constant_size,
+                        max_binding_array_size: capabilities.max_binding_array_size,
+                        max_uniform_buffer_binding_size: capabilities
+                            .max_uniform_buffer_binding_size,
+                        max_texture_dimension_2d: capabilities.max_texture_dimension_2d,
+                        shader_f16: capabilities.shader_f16,
+                        shader_f64: capabilities.shader_f64,
+                    },
+                )))
+            }
+            QueryRequest::Renderer {
+                shader_id,
+                instance_id,
+                image_id,
+            } => {
+                let (renderer_id, registry_type) = match (shader_id, instance_id, image_id) {
+                    (Some(shader_id), None, None) => (shader_id, RegistryType::Shader),
+                    (None, Some(instance_id), None) => (instance_id, RegistryType::WebRenderer),
+                    (None, None, Some(image_id)) => (image_id, RegistryType::Image),
+                    _ => return Err(ApiError::new(
+                        "INVALID_RENDERER_QUERY",
+                        "Renderer query requires exactly one of \"shader_id\", \"instance_id\" and \"image_id\"."
+                            .to_string(),
+                        tiny_http::StatusCode(400),
+                    )),
+                };
+                let info = self
+                    .pipeline
+                    .renderer()
+                    .renderer_info(&renderer_id.into(), registry_type)?;
+                Ok(ResponseHandler::Response(Response::Renderer(info.into())))
+            }
         }
     }
 
     fn handle_register_request(&mut self, request: RegisterRequest) -> Result<(), ApiError> {
+        let entity = RegisteredEntity::from(&request);
+        self.register(request.clone())?;
+        if let RegisteredEntity::Input(input_id) = &entity {
+            if self.multiviewer.is_some() {
+                if let Err(err) = self.apply_scene(&self.scene.clone(), None) {
+                    error!(
+                        "Failed to add input \"{input_id}\" to the multiviewer: {}",
+                        err.message
+                    );
+                }
+            }
+        }
+        self.registered.insert(entity, request);
+        Ok(())
+    }
+
+    fn register(&mut self, request: RegisterRequest) -> Result<(), ApiError> {
         match request {
             RegisterRequest::InputStream(input_stream) => self.register_input(input_stream),
+            RegisterRequest::RtspInput(rtsp_input) => self.register_rtsp_input(rtsp_input),
+            RegisterRequest::Camera(camera) => self.register_camera(camera),
+            RegisterRequest::InputFile(input_file) => self.register_input_file(input_file),
+            RegisterRequest::TestPattern(test_pattern) => self.register_test_pattern(test_pattern),
             RegisterRequest::OutputStream(output_stream) => self.register_output(output_stream),
+            RegisterRequest::OutputFile(output_file) => self.register_output_file(output_file),
+            RegisterRequest::OutputMpegTs(output_mpeg_ts) => {
+                self.register_output_mpeg_ts(output_mpeg_ts)
+            }
             RegisterRequest::Shader(spec) => {
                 let spec = spec.try_into()?;
                 Ok(self.pipeline.register_renderer(spec)?)
             }
-            RegisterRequest::WebRenderer(spec) => {
+            RegisterRequest::WebRenderer(mut spec) => {
+                if let Some(path) = spec.path {
+                    spec.path = Some(resolve_web_bundle_path(&path)?);
+                }
                 let spec = spec.try_into()?;
                 Ok(self.pipeline.register_renderer(spec)?)
             }
@@ -176,40 +1768,161 @@ impl Api {
                 let spec = spec.try_into()?;
                 Ok(self.pipeline.register_renderer(spec)?)
             }
+            RegisterRequest::Mesh(spec) => Ok(self.pipeline.register_renderer(spec.into())?),
         }
     }
 
     fn handle_unregister_request(&mut self, request: UnregisterRequest) -> Result<(), ApiError> {
-        match request {
-            UnregisterRequest::InputStream { input_id } => {
-                Ok(self.pipeline.unregister_input(&input_id.into())?)
+        self.unregister(&request.into())
+    }
+
+    fn unregister(&mut self, entity: &RegisteredEntity) -> Result<(), ApiError> {
+        match entity {
+            RegisteredEntity::Input(input_id) => {
+                if self.multiviewer.is_some() {
+                    self.apply_scene(&self.scene.clone(), Some(input_id))?;
+                }
+                if let Err(err) = self.pipeline.unregister_input(input_id) {
+                    if self.multiviewer.is_some() {
+                        // Input is still registered, so it's added back
+                        self.apply_scene(&self.scene.clone(), None)?;
+                    }
+                    return Err(err.into());
+                }
             }
-            UnregisterRequest::OutputStream { output_id } => {
-                Ok(self.pipeline.unregister_output(&output_id.into())?)
+            RegisteredEntity::Output(output_id) => {
+                self.pipeline.unregister_output(output_id)?;
+                self.previews.stop(output_id);
             }
-            UnregisterRequest::Shader { shader_id } => Ok(self
+            RegisteredEntity::Renderer(renderer_id, registry_type) => self
                 .pipeline
-                .unregister_renderer(&shader_id.into(), RegistryType::Shader)?),
-            UnregisterRequest::WebRenderer { instance_id } => Ok(self
-                .pipeline
-                .unregister_renderer(&instance_id.into(), RegistryType::WebRenderer)?),
-            UnregisterRequest::Image { image_id } => Ok(self
-                .pipeline
-                .unregister_renderer(&image_id.into(), RegistryType::Image)?),
+                .unregister_renderer(renderer_id, *registry_type)?,
         }
+        self.registered.remove(entity);
+        Ok(())
     }
 
     fn register_output(&mut self, request: RegisterOutputRequest) -> Result<(), ApiError> {
         let RegisterOutputRequest {
             output_id,
             port,
-            resolution,
-            encoder_settings,
             ip,
+            options,
+            fec,
+            multicast,
+            srtp_key,
         } = request;
+        let multicast = multicast_options(&output_id, &ip, multicast)?;
+        if let Some(srtp_key) = &srtp_key {
+            validate_srtp_key(srtp_key)?;
+        }
+
+        self.ensure_destination_unused(&output_id, port, &ip)?;
+
+        let fec_overhead_percentage = fec.map(|fec| fec.overhead_percentage);
+        if let Some(overhead_percentage) = fec_overhead_percentage {
+            if !(3..=100).contains(&overhead_percentage) {
+                return Err(ApiError::new(
+                    "INVALID_FEC_OVERHEAD",
+                    format!("Failed to register output stream \"{output_id}\". FEC overhead has to be between 3 and 100 percent, received {overhead_percentage}."),
+                    tiny_http::StatusCode(400),
+                ));
+            }
+        }
+
+        self.register_pipeline_output(
+            output_id,
+            options,
+            false,
+            output::Options::Rtp(rtp_sender::Options {
+                port,
+                ip,
+                fec_overhead_percentage,
+                multicast,
+                srtp_key,
+            }),
+        )
+    }
+
+    fn register_output_mpeg_ts(
+        &mut self,
+        request: RegisterOutputMpegTsRequest,
+    ) -> Result<(), ApiError> {
+        let RegisterOutputMpegTsRequest {
+            output_id,
+            port,
+            ip,
+            transport,
+            options,
+            multicast,
+            pmt_pid,
+            video_pid,
+            service_name,
+            pcr_interval_ms,
+            scte35,
+        } = request;
+        let multicast = multicast_options(&output_id, &ip, multicast)?;
+        let pmt_pid = pmt_pid.unwrap_or(mpegts_sender::DEFAULT_PMT_PID);
+        let video_pid = video_pid.unwrap_or(mpegts_sender::DEFAULT_VIDEO_PID);
+        // Ranges accepted by the ffmpeg MPEG-TS muxer, PIDs below 32 are reserved.
+        if !(0x20..=0x1FFA).contains(&pmt_pid) || !(0x20..=0x1FF0).contains(&video_pid) {
+            return Err(ApiError::new(
+                "INVALID_MPEG_TS_PID",
+                format!("Failed to register output \"{output_id}\". PMT PID has to be between 32 and 8186 and video PID between 32 and 8176, received {pmt_pid} and {video_pid}."),
+                StatusCode(400),
+            ));
+        }
+        let scte35 = scte35.unwrap_or(false);
+        let stream_pids = video_pid..=video_pid + scte35 as u16;
+        if stream_pids.contains(&pmt_pid) {
+            return Err(ApiError::new(
+                "INVALID_MPEG_TS_PID",
+                format!("Failed to register output \"{output_id}\". PID {pmt_pid} of the PMT is already used by a stream of the output."),
+                StatusCode(400),
+            ));
+        }
+        let pcr_interval = pcr_interval_ms
+            .map(Duration::from_millis)
+            .unwrap_or(mpegts_sender::DEFAULT_PCR_INTERVAL);
+        if !(1..=100).contains(&pcr_interval.as_millis()) {
+            return Err(ApiError::new(
+                "INVALID_PCR_INTERVAL",
+                format!("Failed to register output \"{output_id}\". PCR interval has to be between 1 and 100 ms, received {} ms.", pcr_interval.as_millis()),
+                StatusCode(400),
+            ));
+        }
+        self.ensure_destination_unused(&output_id, port, &ip)?;
+
+        self.register_pipeline_output(
+            output_id,
+            options,
+            scte35,
+            output::Options::MpegTs(mpegts_sender::Options {
+                port,
+                ip,
+                transport: transport
+                    .map(Into::into)
+                    .unwrap_or(mpegts_sender::Transport::Udp),
+                multicast,
+                pmt_pid,
+                video_pid,
+                service_name: service_name
+                    .unwrap_or_else(|| mpegts_sender::DEFAULT_SERVICE_NAME.into()),
+                pcr_interval,
+                scte35,
+            }),
+        )
+    }
 
+    /// Two outputs can't send packets to the same address.
+    fn ensure_destination_unused(
+        &self,
+        output_id: &OutputId,
+        port: u16,
+        ip: &Arc<str>,
+    ) -> Result<(), ApiError> {
         self.pipeline.with_outputs(|mut iter| {
-            if let Some((node_id, _)) = iter.find(|(_, output)| output.port == port && output.ip == ip) {
+            if let Some((node_id, _)) = iter.find(|(_, output)| output.port() == Some(port) && output.ip() == Some(ip)) {
                 return Err(ApiError::new(
                     "PORT_AND_IP_ALREADY_IN_USE",
                     format!("Failed to register output stream \"{output_id}\". Combination of port {port} and IP {ip} is already used by node \"{node_id}\""),
@@ -217,24 +1930,128 @@ impl Api {
                 ));
             };
             Ok(())
+        })
+    }
+
+    fn register_output_file(&mut self, request: RegisterOutputFileRequest) -> Result<(), ApiError> {
+        let RegisterOutputFileRequest {
+            output_id,
+            path,
+            options,
+            scte35,
+        } = request;
+        let path = PathBuf::from(path.as_ref());
+        let scte35 = scte35.unwrap_or(false);
+        if scte35 && !file_writer::supports_scte35(&path) {
+            return Err(ApiError::new(
+                "SCTE35_NOT_SUPPORTED",
+                format!("Failed to register output file \"{output_id}\". SCTE-35 markers can only be written to MPEG-TS files (\".ts\" extension)."),
+                StatusCode(400),
+            ));
+        }
+
+        self.pipeline.with_outputs(|mut iter| {
+            if let Some((node_id, _)) = iter.find(|(_, output)| output.path() == Some(&path)) {
+                return Err(ApiError::new(
+                    "PATH_ALREADY_IN_USE",
+                    format!("Failed to register output file \"{output_id}\". Path {path:?} is already used by node \"{node_id}\""),
+                    tiny_http::StatusCode(400)
+                ));
+            };
+            Ok(())
         })?;
 
+        self.register_pipeline_output(
+            output_id,
+            options,
+            scte35,
+            output::Options::File(file_writer::Options {
+                path: path.into(),
+                scte35,
+            }),
+        )
+    }
+
+    /// Registers the output with options shared by all output types and starts
+    /// its preview.
+    fn register_pipeline_output(
+        &mut self,
+        output_id: OutputId,
+        options: types::OutputOptions,
+        scte35: bool,
+        receiver_options: output::Options,
+    ) -> Result<(), ApiError> {
+        let types::OutputOptions {
+            resolution,
+            scale_to_resolution,
+            key_of,
+            encoder_settings,
+            format,
+            watermark,
+            preview,
+            captions,
+            timecode_sei,
+        } = options;
+        let preview = preview.map(TryInto::try_into).transpose()?;
         self.pipeline.register_output(
-            output_id.into(),
+            output_id.clone().into(),
             pipeline::OutputOptions {
-                resolution: resolution.into(),
-                encoder_settings: encoder_settings.into(),
-                receiver_options: rtp_sender::Options { port, ip },
+                resolution: resolution.clone().into(),
+                scale_to_resolution: scale_to_resolution.unwrap_or(false),
+                key_of: key_of.map(Into::into),
+                encoder_settings: encoder_settings.try_into()?,
+                format: format.map(Into::into).unwrap_or_default(),
+                watermark: watermark.map(TryInto::try_into).transpose()?,
+                captions: captions.map(Into::into),
+                timecode_sei: timecode_sei.unwrap_or(false),
+                scte35,
+                receiver_options,
             },
         )?;
 
+        if let Some(preview) = preview {
+            self.previews.start(
+                self.pipeline.renderer().clone(),
+                output_id.into(),
+                resolution.into(),
+                preview,
+            );
+        }
+
         Ok(())
     }
 
     fn register_input(&mut self, request: RegisterInputRequest) -> Result<(), ApiError> {
-        let RegisterInputRequest { input_id: id, port } = request;
+        let RegisterInputRequest {
+            input_id: id,
+            port,
+            format,
+            framerate_conversion,
+            buffer_duration_ms,
+            max_lateness_ms,
+            sync_with_sender_clock,
+            fec,
+            allowed_sources,
+            srtp_key,
+            fallback,
+            preprocess,
+            recording,
+            replay_buffer_ms,
+        } = request;
+        let allowed_sources = allowed_sources
+            .unwrap_or_default()
+            .iter()
+            .map(|source| parse_allowed_source(source))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(srtp_key) = &srtp_key {
+            validate_srtp_key(srtp_key)?;
+        }
 
-        if let Some((node_id, _)) = self.pipeline.inputs().find(|(_, input)| input.port == port) {
+        if let Some((node_id, _)) = self
+            .pipeline
+            .inputs()
+            .find(|(_, input)| input.port() == Some(port))
+        {
             return Err(ApiError::new(
                 "PORT_ALREADY_IN_USE",
                 format!("Failed to register input stream \"{id}\". Port {port} is already used by node \"{node_id}\""),
@@ -244,12 +2061,712 @@ impl Api {
 
         self.pipeline.register_input(
             id.clone().into(),
-            rtp_receiver::Options {
+            input::Options::Rtp(rtp_receiver::Options {
                 port,
                 input_id: id.into(),
+                fec: fec.unwrap_or(false),
+                allowed_sources,
+                srtp_key,
+            }),
+            types::decoder_options(format, preprocess, recording)?,
+            queue::InputOptions {
+                framerate_conversion: framerate_conversion.map(Into::into).unwrap_or_default(),
+                buffer_duration: buffer_duration_ms.map(|ms| Duration::from_millis(ms as u64)),
+                max_lateness: max_lateness_ms.map(|ms| Duration::from_millis(ms as u64)),
+                sync_with_sender_clock: sync_with_sender_clock.unwrap_or(false),
             },
+            fallback.map(TryInto::try_into).transpose()?,
+            replay_buffer_ms.map(replay_buffer_duration).transpose()?,
         )?;
 
         Ok(())
     }
+
+    fn register_rtsp_input(&mut self, request: RegisterRtspInputRequest) -> Result<(), ApiError> {
+        let RegisterRtspInputRequest {
+            input_id: id,
+            url,
+            transport,
+            format,
+            framerate_conversion,
+            buffer_duration_ms,
+            max_lateness_ms,
+            sync_with_sender_clock,
+            fallback,
+            preprocess,
+            recording,
+            replay_buffer_ms,
+        } = request;
+
+        if !url.starts_with("rtsp://") {
+            return Err(ApiError::new(
+                "INVALID_RTSP_URL",
+                format!(
+                    "Failed to register RTSP input \"{id}\". URL has to start with \"rtsp://\"."
+                ),
+                tiny_http::StatusCode(400),
+            ));
+        }
+
+        self.pipeline.register_input(
+            id.clone().into(),
+            input::Options::Rtsp(rtsp_receiver::Options {
+                input_id: id.into(),
+                url,
+                transport: transport.map(Into::into),
+            }),
+            types::decoder_options(format, preprocess, recording)?,
+            queue::InputOptions {
+                framerate_conversion: framerate_conversion.map(Into::into).unwrap_or_default(),
+                buffer_duration: buffer_duration_ms.map(|ms| Duration::from_millis(ms as u64)),
+                max_lateness: max_lateness_ms.map(|ms| Duration::from_millis(ms as u64)),
+                sync_with_sender_clock: sync_with_sender_clock.unwrap_or(false),
+            },
+            fallback.map(TryInto::try_into).transpose()?,
+            replay_buffer_ms.map(replay_buffer_duration).transpose()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn register_camera(&mut self, request: RegisterCameraRequest) -> Result<(), ApiError> {
+        let RegisterCameraRequest {
+            input_id: id,
+            device,
+            resolution,
+            framerate,
+            format,
+            framerate_conversion,
+            buffer_duration_ms,
+            max_lateness_ms,
+            fallback,
+            preprocess,
+            recording,
+            replay_buffer_ms,
+        } = request;
+
+        if let Some((node_id, _)) = self
+            .pipeline
+            .inputs()
+            .find(|(_, input)| input.device() == Some(device.as_ref()))
+        {
+            return Err(ApiError::new(
+                "DEVICE_ALREADY_IN_USE",
+                format!("Failed to register camera \"{id}\". Device {device} is already used by node \"{node_id}\""),
+                tiny_http::StatusCode(400)
+            ));
+        }
+
+        self.pipeline.register_input(
+            id.clone().into(),
+            input::Options::Camera(camera_receiver::Options {
+                input_id: id.into(),
+                device: device.to_string(),
+                resolution: resolution.map(Into::into),
+                framerate: framerate.map(TryInto::try_into).transpose()?,
+            }),
+            types::decoder_options(format, preprocess, recording)?,
+            queue::InputOptions {
+                framerate_conversion: framerate_conversion.map(Into::into).unwrap_or_default(),
+                buffer_duration: buffer_duration_ms.map(|ms| Duration::from_millis(ms as u64)),
+                max_lateness: max_lateness_ms.map(|ms| Duration::from_millis(ms as u64)),
+                sync_with_sender_clock: false,
+            },
+            fallback.map(TryInto::try_into).transpose()?,
+            replay_buffer_ms.map(replay_buffer_duration).transpose()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn register_test_pattern(
+        &mut self,
+        request: RegisterTestPatternRequest,
+    ) -> Result<(), ApiError> {
+        let input_id = request.input_id.clone().into();
+        self.pipeline
+            .register_test_pattern(input_id, request.try_into()?)?;
+        Ok(())
+    }
+
+    fn register_input_file(&mut self, request: RegisterInputFileRequest) -> Result<(), ApiError> {
+        let RegisterInputFileRequest {
+            input_id: id,
+            path,
+            loop_playback,
+            playback_rate,
+            format,
+            framerate_conversion,
+            fallback,
+            preprocess,
+            recording,
+            replay_buffer_ms,
+        } = request;
+        let playback_rate = playback_rate.unwrap_or(1.0);
+        validate_playback_rate(playback_rate)?;
+
+        self.pipeline.register_input(
+            id.clone().into(),
+            input::Options::File(file_receiver::Options {
+                input_id: id.into(),
+                path: PathBuf::from(path.as_ref()),
+                realtime: !self.pipeline.queue().is_offline(),
+                loop_playback: loop_playback.unwrap_or(false),
+                playback_rate,
+            }),
+            types::decoder_options(format, preprocess, recording)?,
+            queue::InputOptions {
+                framerate_conversion: framerate_conversion.map(Into::into).unwrap_or_default(),
+                ..Default::default()
+            },
+            fallback.map(TryInto::try_into).transpose()?,
+            replay_buffer_ms.map(replay_buffer_duration).transpose()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn update_input_playback(
+        &self,
+        input_id: InputId,
+        seek_ms: Option<f64>,
+        paused: Option<bool>,
+        loop_playback: Option<bool>,
+        playback_rate: Option<f64>,
+    ) -> Result<(), ApiError> {
+        let input_id: compositor_common::scene::InputId = input_id.into();
+        let Some((_, input)) = self.pipeline.inputs().find(|(id, _)| **id == input_id) else {
+            return Err(ApiError::new(
+                "INPUT_NOT_FOUND",
+                format!("Failed to update playback of input \"{input_id}\". Input does not exist."),
+                tiny_http::StatusCode(404),
+            ));
+        };
+        let Input::File(receiver) = input else {
+            return Err(ApiError::new(
+                "NOT_A_FILE_INPUT",
+                format!("Failed to update playback of input \"{input_id}\". Playback can be controlled only for file inputs."),
+                tiny_http::StatusCode(400),
+            ));
+        };
+        if let Some(playback_rate) = playback_rate {
+            validate_playback_rate(playback_rate)?;
+        }
+
+        if let Some(seek_ms) = seek_ms {
+            let timestamp = Duration::from_millis(seek_ms.max(0.0) as u64);
+            receiver.control_playback(PlaybackCommand::Seek(timestamp));
+        }
+        if let Some(playback_rate) = playback_rate {
+            receiver.control_playback(PlaybackCommand::SetPlaybackRate(playback_rate));
+        }
+        if let Some(loop_playback) = loop_playback {
+            receiver.control_playback(PlaybackCommand::SetLoop(loop_playback));
+        }
+        match paused {
+            Some(true) => receiver.control_playback(PlaybackCommand::Pause),
+            Some(false) => receiver.control_playback(PlaybackCommand::Resume),
+            None => (),
+        }
+        Ok(())
+    }
+}
+
+impl From<&RegisterRequest> for RegisteredEntity {
+    fn from(request: &RegisterRequest) -> Self {
+        match request {
+            RegisterRequest::InputStream(RegisterInputRequest { input_id, .. })
+            | RegisterRequest::RtspInput(RegisterRtspInputRequest { input_id, .. })
+            | RegisterRequest::Camera(RegisterCameraRequest { input_id, .. })
+            | RegisterRequest::InputFile(RegisterInputFileRequest { input_id, .. })
+            | RegisterRequest::TestPattern(RegisterTestPatternRequest { input_id, .. }) => {
+                Self::Input(input_id.clone().into())
+            }
+            RegisterRequest::OutputStream(RegisterOutputRequest { output_id, .. })
+            | RegisterRequest::OutputFile(RegisterOutputFileRequest { output_id, .. })
+            | RegisterRequest::OutputMpegTs(RegisterOutputMpegTsRequest { output_id, .. }) => {
+                Self::Output(output_id.clone().into())
+            }
+            RegisterRequest::Shader(spec) => {
+                Self::Renderer(spec.shader_id.clone().into(), RegistryType::Shader)
+            }
+            RegisterRequest::WebRenderer(spec) => {
+                Self::Renderer(spec.instance_id.clone().into(), RegistryType::WebRenderer)
+            }
+            RegisterRequest::Image(spec) => {
+                Self::Renderer(spec.image_id().clone().into(), RegistryType::Image)
+            }
+            RegisterRequest::Mesh(spec) => {
+                Self::Renderer(spec.mesh_id.clone().into(), RegistryType::Mesh)
+            }
+        }
+    }
+}
+
+impl From<UnregisterRequest> for RegisteredEntity {
+    fn from(request: UnregisterRequest) -> Self {
+        match request {
+            UnregisterRequest::InputStream { input_id } => Self::Input(input_id.into()),
+            UnregisterRequest::OutputStream { output_id } => Self::Output(output_id.into()),
+            UnregisterRequest::Shader { shader_id } => {
+                Self::Renderer(shader_id.into(), RegistryType::Shader)
+            }
+            UnregisterRequest::WebRenderer { instance_id } => {
+                Self::Renderer(instance_id.into(), RegistryType::WebRenderer)
+            }
+            UnregisterRequest::Image { image_id } => {
+                Self::Renderer(image_id.into(), RegistryType::Image)
+            }
+            UnregisterRequest::Mesh { mesh_id } => {
+                Self::Renderer(mesh_id.into(), RegistryType::Mesh)
+            }
+        }
+    }
+}
+
+/// Multicast options are only allowed (and always set) for multicast group addresses.
+fn multicast_options(
+    output_id: &OutputId,
+    ip: &str,
+    options: Option<types::MulticastOptions>,
+) -> Result<Option<rtp_sender::MulticastOptions>, ApiError> {
+    let is_multicast = ip
+        .parse::<IpAddr>()
+        .is_ok_and(|address| address.is_multicast());
+    let options = match (is_multicast, options) {
+        (true, options) => options.unwrap_or(types::MulticastOptions {
+            ttl: None,
+            interface: None,
+        }),
+        (false, None) => return Ok(None),
+        (false, Some(_)) => {
+            return Err(ApiError::new(
+                "INVALID_MULTICAST_OPTIONS",
+                format!("Failed to register output stream \"{output_id}\". Multicast options can only be used with multicast group addresses, received IP {ip}."),
+                tiny_http::StatusCode(400),
+            ))
+        }
+    };
+
+    let ttl = options.ttl.unwrap_or(DEFAULT_MULTICAST_TTL);
+    if ttl == 0 {
+        return Err(ApiError::new(
+            "INVALID_MULTICAST_OPTIONS",
+            format!("Failed to register output stream \"{output_id}\". Multicast TTL has to be between 1 and 255."),
+            tiny_http::StatusCode(400),
+        ));
+    }
+    if let Some(interface) = &options.interface {
+        if interface.parse::<IpAddr>().is_err() {
+            return Err(ApiError::new(
+                "INVALID_MULTICAST_OPTIONS",
+                format!("Failed to register output stream \"{output_id}\". Multicast interface has to be an IP address of a local interface, received {interface}."),
+                tiny_http::StatusCode(400),
+            ));
+        }
+    }
+    Ok(Some(rtp_sender::MulticastOptions {
+        ttl,
+        interface: options.interface,
+    }))
+}
+
+fn parse_allowed_source(source: &str) -> Result<AllowedSource, ApiError> {
+    if let Ok(address) = source.parse::<SocketAddr>() {
+        return Ok(AllowedSource::Address(address));
+    }
+    if let Ok(ip) = source.parse::<IpAddr>() {
+        return Ok(AllowedSource::Ip(ip));
+    }
+    Err(ApiError::new(
+        "INVALID_ALLOWED_SOURCE",
+        format!("Allowed source has to be an IP address with an optional port, e.g. \"192.168.1.10\" or \"192.168.1.10:5004\", received \"{source}\"."),
+        tiny_http::StatusCode(400),
+    ))
+}
+
+/// 30 bytes of key and salt are encoded as 40 base64 characters without padding.
+fn validate_srtp_key(srtp_key: &str) -> Result<(), ApiError> {
+    let is_base64 = srtp_key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/');
+    if srtp_key.len() == 40 && is_base64 {
+        return Ok(());
+    }
+    Err(ApiError::new(
+        "INVALID_SRTP_KEY",
+        "SRTP key has to be 30 bytes (16 bytes of master key and 14 bytes of salt) encoded in base64.".to_string(),
+        tiny_http::StatusCode(400),
+    ))
+}
+
+/// Directory SDP files are written to. Writing SDP files is disabled if not set.
+pub const SDP_DIR_ENV: &str = "MEMBRANE_VIDEO_COMPOSITOR_SDP_DIR";
+
+fn write_sdp_file(path: Option<Arc<str>>, sdp: &str) -> Result<(), ApiError> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let dir = env::var(SDP_DIR_ENV).map_err(|_| {
+        ApiError::new(
+            "SDP_WRITE_DISABLED",
+            format!("Writing SDP files is disabled. Set {SDP_DIR_ENV} to the directory they should be written to."),
+            tiny_http::StatusCode(400),
+        )
+    })?;
+    let full_path = sdp_file_path(Path::new(&dir), Path::new(path.as_ref()))?;
+    fs::write(&full_path, sdp).map_err(|err| {
+        ApiError::new(
+            "SDP_WRITE_FAILED",
+            format!("Failed to write SDP to {full_path:?}: {err}"),
+            tiny_http::StatusCode(500),
+        )
+    })
+}
+
+/// Resolves `path` inside `dir`. Absolute paths, ".." components and symlinks
+/// leading outside of `dir` are rejected, so API clients can't write files
+/// anywhere else.
+fn sdp_file_path(dir: &Path, path: &Path) -> Result<PathBuf, ApiError> {
+    let invalid_path = || {
+        ApiError::new(
+            "INVALID_SDP_PATH",
+            format!("SDP path {path:?} has to be a file path relative to {SDP_DIR_ENV}, without \"..\" components."),
+            tiny_http::StatusCode(400),
+        )
+    };
+    let is_relative = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_relative || path.file_name().is_none() {
+        return Err(invalid_path());
+    }
+
+    let dir = fs::canonicalize(dir).map_err(|err| {
+        ApiError::new(
+            "SDP_WRITE_FAILED",
+            format!("Failed to open SDP directory {dir:?}: {err}"),
+            tiny_http::StatusCode(500),
+        )
+    })?;
+    let full_path = dir.join(path);
+    let parent = full_path
+        .parent()
+        .and_then(|parent| fs::canonicalize(parent).ok())
+        .ok_or_else(invalid_path)?;
+    let is_symlink = fs::symlink_metadata(&full_path).is_ok_and(|meta| meta.is_symlink());
+    if !parent.starts_with(&dir) || is_symlink {
+        return Err(invalid_path());
+    }
+    Ok(full_path)
+}
+
+/// Directory local web renderer bundles are loaded from. Loading local bundles
+/// is disabled if not set.
+pub const WEB_BUNDLES_DIR_ENV: &str = "MEMBRANE_VIDEO_COMPOSITOR_WEB_BUNDLES_DIR";
+
+fn resolve_web_bundle_path(path: &str) -> Result<String, ApiError> {
+    let dir = env::var(WEB_BUNDLES_DIR_ENV).map_err(|_| {
+        ApiError::new(
+            "WEB_BUNDLES_DISABLED",
+            format!("Loading local web renderer bundles is disabled. Set {WEB_BUNDLES_DIR_ENV} to the directory they should be loaded from."),
+            tiny_http::StatusCode(400),
+        )
+    })?;
+    let full_path = web_bundle_path(Path::new(&dir), path)?;
+    Ok(full_path.to_string_lossy().into_owned())
+}
+
+/// Resolves `path` of a web renderer bundle inside `dir`. Absolute paths, ".."
+/// components and symlinks leading outside of `dir` are rejected, so API clients
+/// can't load arbitrary local files.
+fn web_bundle_path(dir: &Path, path: &str) -> Result<PathBuf, ApiError> {
+    let is_relative = Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_relative {
+        return Err(ApiError::new(
+            "INVALID_WEB_BUNDLE_PATH",
+            format!("Web renderer path {path:?} has to be relative to {WEB_BUNDLES_DIR_ENV}, without \"..\" components."),
+            tiny_http::StatusCode(400),
+        ));
+    }
+
+    let dir = fs::canonicalize(dir).map_err(|err| {
+        ApiError::new(
+            "WEB_BUNDLES_DIR_NOT_FOUND",
+            format!("Failed to open web bundles directory {dir:?}: {err}"),
+            tiny_http::StatusCode(500),
+        )
+    })?;
+    let full_path = fs::canonicalize(dir.join(path)).map_err(|err| {
+        ApiError::new(
+            "WEB_BUNDLE_NOT_FOUND",
+            format!("Web renderer path {path:?} does not exist: {err}"),
+            tiny_http::StatusCode(400),
+        )
+    })?;
+    if !full_path.starts_with(&dir) {
+        return Err(ApiError::new(
+            "INVALID_WEB_BUNDLE_PATH",
+            format!("Web renderer path {path:?} leads outside of {WEB_BUNDLES_DIR_ENV}."),
+            tiny_http::StatusCode(400),
+        ));
+    }
+    Ok(full_path)
+}
+
+pub(crate) fn encode_snapshot(
+    snapshot: Snapshot,
+    format: SnapshotFormat,
+) -> Result<Vec<u8>, ApiError> {
+    let encoding_error = |err| {
+        ApiError::new(
+            "SNAPSHOT_ENCODING_FAILED",
+            format!("Failed to encode snapshot: {err}"),
+            tiny_http::StatusCode(500),
+        )
+    };
+    let image = image::RgbaImage::from_raw(
+        snapshot.resolution.width as u32,
+        snapshot.resolution.height as u32,
+        snapshot.data.to_vec(),
+    )
+    .ok_or_else(|| encoding_error("invalid frame size".to_string()))?;
+
+    let mut encoded = Cursor::new(Vec::new());
+    let result = match format {
+        SnapshotFormat::Png => image.write_to(&mut encoded, image::ImageOutputFormat::Png),
+        // JPEG does not support the alpha channel
+        SnapshotFormat::Jpeg => image::DynamicImage::ImageRgba8(image)
+            .to_rgb8()
+            .write_to(&mut encoded, image::ImageOutputFormat::Jpeg(90)),
+    };
+    result.map_err(|err| encoding_error(err.to_string()))?;
+    Ok(encoded.into_inner())
+}
+
+fn validate_playback_rate(playback_rate: f64) -> Result<(), ApiError> {
+    if playback_rate > 0.0 && playback_rate.is_finite() {
+        return Ok(());
+    }
+    Err(ApiError::new(
+        "INVALID_PLAYBACK_RATE",
+        format!("Playback rate has to be a positive number, received {playback_rate}."),
+        tiny_http::StatusCode(400),
+    ))
+}
+
+fn replay_buffer_duration(replay_buffer_ms: u64) -> Result<Duration, ApiError> {
+    // Buffered frames are not compressed, a minute of 1080p at 30 fps takes
+    // about 5.6 GB.
+    const MAX_REPLAY_BUFFER_MS: u64 = 300_000;
+    if replay_buffer_ms == 0 || replay_buffer_ms > MAX_REPLAY_BUFFER_MS {
+        return Err(ApiError::new(
+            "INVALID_REPLAY_BUFFER",
+            format!("Replay buffer duration has to be between 1 and {MAX_REPLAY_BUFFER_MS} ms, received {replay_buffer_ms}."),
+            StatusCode(400),
+        ));
+    }
+    Ok(Duration::from_millis(replay_buffer_ms))
+}
+
+fn scte35_splice(
+    event_id: u32,
+    splice_type: types::Scte35SpliceType,
+    preroll_ms: Option<f64>,
+    break_duration_ms: Option<f64>,
+    auto_return: Option<bool>,
+) -> Result<pipeline::scte35::Scte35Splice, ApiError> {
+    let duration = |field: &str, ms: f64| {
+        Duration::try_from_secs_f64(ms / 1000.0).map_err(|err| {
+            ApiError::new(
+                "INVALID_SCTE35_MARKER",
+                format!("Invalid \"{field}\" value: {err}"),
+                StatusCode(400),
+            )
+        })
+    };
+    let invalid_field = |field: &str| {
+        ApiError::new(
+            "INVALID_SCTE35_MARKER",
+            format!("\"{field}\" can only be set for \"splice_out\" markers."),
+            StatusCode(400),
+        )
+    };
+    let kind = match splice_type {
+        types::Scte35SpliceType::SpliceOut => {
+            let break_duration = break_duration_ms
+                .map(|ms| duration("break_duration_ms", ms))
+                .transpose()?;
+            if break_duration.is_none() && auto_return == Some(true) {
+                return Err(ApiError::new(
+                    "INVALID_SCTE35_MARKER",
+                    "\"auto_return\" requires \"break_duration_ms\".".to_string(),
+                    StatusCode(400),
+                ));
+            }
+            pipeline::scte35::SpliceKind::Out {
+                break_duration,
+                auto_return: auto_return.unwrap_or(false),
+            }
+        }
+        _ if break_duration_ms.is_some() => return Err(invalid_field("break_duration_ms")),
+        _ if auto_return.is_some() => return Err(invalid_field("auto_return")),
+        types::Scte35SpliceType::SpliceIn => pipeline::scte35::SpliceKind::In,
+        types::Scte35SpliceType::Cancel => pipeline::scte35::SpliceKind::Cancel,
+    };
+    Ok(pipeline::scte35::Scte35Splice {
+        event_id,
+        kind,
+        preroll: duration("preroll_ms", preroll_ms.unwrap_or(0.0))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn request(value: serde_json::Value) -> Request {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn register(value: serde_json::Value) -> (RegisteredEntity, RegisterRequest) {
+        let request: RegisterRequest = serde_json::from_value(value).unwrap();
+        (RegisteredEntity::from(&request), request)
+    }
+
+    /// Test pattern "bars", output "out" and shader "blur" are registered.
+    fn registered() -> HashMap<RegisteredEntity, RegisterRequest> {
+        HashMap::from([
+            register(json!({
+                "entity_type": "test_pattern",
+                "input_id": "bars",
+                "pattern": { "type": "smpte_bars" },
+            })),
+            register(json!({
+                "entity_type": "output_stream",
+                "output_id": "out",
+                "port": 9000,
+                "ip": "127.0.0.1",
+                "resolution": { "width": 1920, "height": 1080 },
+                "encoder_settings": {},
+            })),
+            register(json!({
+                "entity_type": "shader",
+                "shader_id": "blur",
+                "source": "",
+            })),
+        ])
+    }
+
+    fn scene(input_pad: &str) -> serde_json::Value {
+        json!({
+            "type": "update_scene",
+            "nodes": [{
+                "node_id": "shaded",
+                "type": "shader",
+                "shader_id": "blur",
+                "input_pads": [input_pad],
+                "resolution": { "width": 1920, "height": 1080 },
+            }],
+            "outputs": [{ "output_id": "out", "input_pad": "shaded" }],
+        })
+    }
+
+    fn plan(requests: Vec<serde_json::Value>) -> Result<BatchPlan, ApiError> {
+        let current_scene = Arc::new(SceneSpec {
+            nodes: vec![],
+            outputs: vec![],
+        });
+        let requests = requests.into_iter().map(request).collect();
+        BatchPlan::new(&registered(), &current_scene, requests)
+    }
+
+    #[test]
+    fn batch_registers_are_applied_first_and_unregisters_last() {
+        let Ok(plan) = plan(vec![
+            scene("bars"),
+            json!({ "type": "unregister", "entity_type": "input_stream", "input_id": "bars" }),
+            json!({
+                "type": "register",
+                "entity_type": "test_pattern",
+                "input_id": "checkerboard",
+                "pattern": { "type": "checkerboard" },
+            }),
+            scene("checkerboard"),
+        ]) else {
+            panic!("batch should be valid");
+        };
+        assert!(matches!(
+            plan.registers.as_slice(),
+            [RegisterRequest::TestPattern(_)]
+        ));
+        assert_eq!(plan.scenes.len(), 2);
+        assert!(matches!(
+            plan.unregisters.as_slice(),
+            [RegisteredEntity::Input(input_id)] if input_id.to_string() == "bars"
+        ));
+    }
+
+    #[test]
+    fn batch_is_rejected_before_anything_is_applied() {
+        let unregister_bars =
+            json!({ "type": "unregister", "entity_type": "input_stream", "input_id": "bars" });
+        let register_bars = json!({
+            "type": "register",
+            "entity_type": "test_pattern",
+            "input_id": "bars",
+            "pattern": { "type": "checkerboard" },
+        });
+
+        // entity does not exist
+        let missing =
+            json!({ "type": "unregister", "entity_type": "input_stream", "input_id": "camera" });
+        assert!(plan(vec![scene("bars"), missing]).is_err());
+        // entity already exists
+        assert!(plan(vec![register_bars.clone()]).is_err());
+        // removed entity would have to be registered while it still exists
+        assert!(plan(vec![unregister_bars.clone(), register_bars]).is_err());
+        // scene uses an input that is unregistered earlier in the batch
+        assert!(plan(vec![unregister_bars.clone(), scene("bars")]).is_err());
+        // final scene still uses the input
+        assert!(plan(vec![scene("bars"), unregister_bars]).is_err());
+        // final scene still uses the shader
+        let unregister_blur =
+            json!({ "type": "unregister", "entity_type": "shader", "shader_id": "blur" });
+        assert!(plan(vec![scene("bars"), unregister_blur]).is_err());
+        // only registry changes and scene updates can be batched
+        assert!(plan(vec![json!({ "type": "start" })]).is_err());
+    }
+
+    #[test]
+    fn web_bundle_path_stays_inside_bundles_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let bundles_dir = root.path().join("bundles");
+        fs::create_dir_all(bundles_dir.join("overlay")).unwrap();
+        fs::write(root.path().join("secret.html"), "").unwrap();
+        std::os::unix::fs::symlink(
+            root.path().join("secret.html"),
+            bundles_dir.join("link.html"),
+        )
+        .unwrap();
+
+        let Ok(path) = web_bundle_path(&bundles_dir, "overlay") else {
+            panic!("bundle inside of the directory was rejected");
+        };
+        assert!(path.ends_with("bundles/overlay"));
+        for path in [
+            "../secret.html",
+            "overlay/../../secret.html",
+            "link.html",
+            "missing",
+        ] {
+            assert!(web_bundle_path(&bundles_dir, path).is_err(), "{path}");
+        }
+        let absolute_path = root.path().join("secret.html");
+        assert!(web_bundle_path(&bundles_dir, &absolute_path.to_string_lossy()).is_err());
+    }
 }
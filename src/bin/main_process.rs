@@ -1,17 +1,22 @@
 use std::env;
 
 use log::info;
-use video_compositor::http::{self, API_PORT_ENV};
+use video_compositor::{
+    http::{self, API_PORT_ENV},
+    logger,
+    scene_file::SceneFile,
+};
 
 fn main() {
-    env_logger::init_from_env(
-        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "info"),
-    );
+    logger::init_logger();
+
+    let scene_file = SceneFile::from_args();
 
     ffmpeg_next::format::network::init();
+    ffmpeg_next::device::register_all();
 
     let port = env::var(API_PORT_ENV).unwrap_or_else(|_| "8001".to_string());
-    http::Server::new(port.parse::<u16>().unwrap()).run();
+    http::Server::new(port.parse::<u16>().unwrap()).run_with_scene_file(scene_file);
 
     info!("Received exit signal. Terminating...")
     // TODO: add graceful shutdown
@@ -3,7 +3,9 @@ use std::sync::{Arc, Mutex};
 
 use anyhow::{anyhow, Result};
 use compositor_chromium::cef;
-use compositor_render::{EMBED_SOURCE_FRAMES_MESSAGE, UNEMBED_SOURCE_FRAMES_MESSAGE};
+use compositor_render::{
+    EMBED_SOURCE_FRAMES_MESSAGE, POST_MESSAGE_MESSAGE, UNEMBED_SOURCE_FRAMES_MESSAGE,
+};
 use log::{debug, error};
 
 use crate::state::{FrameInfo, State};
@@ -187,6 +189,31 @@ impl RenderProcessHandler {
             ctx_entered,
         )?;
 
+        // Messages are forwarded to the browser process and can be retrieved with
+        // the "web_renderer_messages" query.
+        let func = cef::V8Function::new("post_message", |args| {
+            let [cef::V8Value::String(data)] = args else {
+                return Err("Expected a single string value".into());
+            };
+
+            let mut message = cef::ProcessMessage::new(POST_MESSAGE_MESSAGE);
+            message.write_string(0, data.get().unwrap().into());
+            let frame = cef::V8Context::current()
+                .frame()
+                .map_err(|err| format!("Failed to retrieve the frame: {err}"))?;
+            frame
+                .send_process_message(cef::ProcessId::Browser, message)
+                .map_err(|err| format!("Failed to post message: {err}"))?;
+            Ok(cef::V8Undefined::new().into())
+        });
+
+        global.set(
+            "post_message",
+            &cef::V8Value::from(func),
+            cef::V8PropertyAttribute::None,
+            ctx_entered,
+        )?;
+
         Ok(())
     }
 }
@@ -1,9 +1,21 @@
+// ImageData wrapping the shared memory of a source, recreated only when the
+// shared memory or the resolution changes.
+const sourceImages = {};
+
 function renderFrame(sourceId, buffer, width, height) {
     const canvas = document.getElementById(sourceId);
     const ctx = canvas.getContext("2d");
-    const imageData = new ImageData(new Uint8ClampedArray(buffer), width, height);
 
-    canvas.width = width;
-    canvas.height = height;
-    ctx.putImageData(imageData, 0, 0);
+    let image = sourceImages[sourceId];
+    if (!image || image.buffer !== buffer || image.data.width !== width || image.data.height !== height) {
+        image = { buffer, data: new ImageData(new Uint8ClampedArray(buffer), width, height) };
+        sourceImages[sourceId] = image;
+    }
+
+    // Resizing the canvas reallocates and clears it, so it's done only when needed
+    if (canvas.width !== width || canvas.height !== height) {
+        canvas.width = width;
+        canvas.height = height;
+    }
+    ctx.putImageData(image.data, 0, 0);
 }
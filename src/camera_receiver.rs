@@ -0,0 +1,185 @@
+use anyhow::Result;
+use compositor_common::{
+    scene::{InputId, Resolution},
+    Framerate,
+};
+use compositor_pipeline::pipeline::{
+    decoder::{Codec, DecoderParameters, SenderClock},
+    PipelineInput,
+};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::thread;
+
+use ffmpeg_next::{media::Type, Dictionary, Packet, Rational};
+
+use crate::rtp_receiver::{input_with_dictionary_and_interrupt, PacketIter};
+
+/// Demuxer used to capture video from locally attached devices.
+#[cfg(target_os = "linux")]
+const CAPTURE_FORMAT: &str = "video4linux2";
+#[cfg(target_os = "macos")]
+const CAPTURE_FORMAT: &str = "avfoundation";
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+const CAPTURE_FORMAT: &str = "";
+
+/// Decoder expects timestamps in 90kHz clock (the same as RTP video streams),
+/// so packets read from the device are rescaled to that time base.
+const PTS_TIME_BASE: Rational = Rational(1, 90000);
+
+pub struct CameraReceiver {
+    thread_finished: Receiver<()>,
+    should_close: Sender<()>,
+    decoder_parameters: DecoderParameters,
+    pub(crate) device: String,
+}
+
+pub struct Options {
+    pub input_id: InputId,
+    /// Device path on Linux (e.g. "/dev/video0") or device index/name on macOS.
+    pub device: String,
+    /// Requested capture resolution. Device default is used if not specified.
+    pub resolution: Option<Resolution>,
+    /// Requested capture framerate. Device default is used if not specified.
+    pub framerate: Option<Framerate>,
+}
+
+impl PipelineInput for CameraReceiver {
+    type Opts = Options;
+    type PacketIterator = crossbeam_channel::IntoIter<Packet>;
+
+    fn new(
+        opts: Self::Opts,
+    ) -> Result<(Self, Self::PacketIterator), Box<dyn std::error::Error + Send + Sync + 'static>>
+    {
+        let (drop_sender, drop_receiver) = bounded(0);
+        let (should_close_sender, should_close_receiver) = bounded(1);
+        let (decoder_params_sender, decoder_params_receiver) = bounded(1);
+
+        let device = opts.device.clone();
+        let (packet_sender, packet_receiver) = bounded(0);
+
+        thread::spawn(move || {
+            let result = CameraReceiver::start(
+                &opts,
+                should_close_receiver,
+                packet_sender,
+                &decoder_params_sender,
+            );
+            if let Err(err) = result {
+                // Error is only relevant if device failed to open, otherwise receiver
+                // is already gone and send will fail.
+                let _ = decoder_params_sender.send(Err(err));
+            }
+            drop_sender.send(())
+        });
+
+        let decoder_parameters = match decoder_params_receiver.recv()? {
+            Ok(decoder_parameters) => decoder_parameters,
+            Err(err) => {
+                drop_receiver.recv()?;
+                return Err(err.into());
+            }
+        };
+
+        Ok((
+            Self {
+                thread_finished: drop_receiver,
+                should_close: should_close_sender,
+                decoder_parameters,
+                device,
+            },
+            packet_receiver.into_iter(),
+        ))
+    }
+
+    fn decoder_parameters(&self) -> DecoderParameters {
+        self.decoder_parameters
+    }
+
+    fn sender_clock(&self) -> Option<SenderClock> {
+        None
+    }
+}
+
+impl Drop for CameraReceiver {
+    fn drop(&mut self) {
+        // - should_close signals to capture thread that it should abort
+        // - drop_receiver signals to drop method that capture thread finished cleanup
+        //
+        // Capture thread might have already finished on its own (e.g. device was
+        // disconnected), so failure to deliver should_close is expected.
+        let _ = self.should_close.send(());
+        self.thread_finished.recv().unwrap();
+    }
+}
+
+impl CameraReceiver {
+    fn start(
+        opts: &Options,
+        should_close: Receiver<()>,
+        packet_sender: Sender<Packet>,
+        decoder_params_sender: &Sender<Result<DecoderParameters>>,
+    ) -> Result<()> {
+        let mut options = Dictionary::new();
+        if let Some(resolution) = opts.resolution {
+            options.set(
+                "video_size",
+                &format!("{}x{}", resolution.width, resolution.height),
+            );
+        }
+        if let Some(framerate) = opts.framerate {
+            options.set("framerate", &format!("{}/{}", framerate.num, framerate.den));
+        }
+
+        let input_ctx = input_with_dictionary_and_interrupt(
+            &opts.device,
+            Some(CAPTURE_FORMAT),
+            options,
+            || should_close.try_recv().is_ok(),
+        )?;
+
+        let input = input_ctx
+            .streams()
+            .best(Type::Video)
+            .ok_or(ffmpeg_next::Error::StreamNotFound)?;
+        let input_index = input.index();
+        let input_time_base = input.time_base();
+
+        decoder_params_sender
+            .send(Ok(decoder_parameters(input.parameters())?))
+            .unwrap();
+
+        for mut packet in PacketIter::new(input_ctx, input_index) {
+            packet.rescale_ts(input_time_base, PTS_TIME_BASE);
+            if packet_sender.send(packet).is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Cameras usually produce either compressed (MJPEG, sometimes H264) or raw
+/// frames. Format of the raw frames is negotiated with the device when it's
+/// opened, so it needs to be passed explicitly to the decoder.
+fn decoder_parameters(parameters: ffmpeg_next::codec::Parameters) -> Result<DecoderParameters> {
+    let codec = match parameters.id() {
+        ffmpeg_next::codec::Id::H264 => Codec::H264,
+        ffmpeg_next::codec::Id::MJPEG => Codec::Mjpeg,
+        ffmpeg_next::codec::Id::RAWVIDEO => {
+            let video = ffmpeg_next::codec::Context::from_parameters(parameters)?
+                .decoder()
+                .video()?;
+            Codec::RawVideo {
+                pixel_format: video.format(),
+                resolution: Resolution {
+                    width: video.width() as usize,
+                    height: video.height() as usize,
+                },
+            }
+        }
+        codec => anyhow::bail!("Camera codec {codec:?} is not supported."),
+    };
+    Ok(DecoderParameters { codec })
+}
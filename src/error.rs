@@ -11,6 +11,11 @@ pub struct ApiError {
     pub message: String,
     pub stack: Vec<String>,
     pub http_status_code: tiny_http::StatusCode,
+    /// Ids of inputs, outputs, renderers or nodes the error refers to.
+    pub entity_ids: Vec<String>,
+    /// Node of the scene and its field that failed validation.
+    pub node_id: Option<String>,
+    pub field: Option<&'static str>,
 }
 
 impl ApiError {
@@ -24,6 +29,9 @@ impl ApiError {
             message: message.clone(),
             stack: vec![message],
             http_status_code,
+            entity_ids: vec![],
+            node_id: None,
+            field: None,
         }
     }
 
@@ -53,6 +61,9 @@ where
                 ErrorType::ServerError => StatusCode(500),
                 ErrorType::EntityNotFound => StatusCode(404),
             },
+            entity_ids: err_info.entity_ids,
+            node_id: err_info.node_id,
+            field: err_info.field,
         }
     }
 }
@@ -0,0 +1,409 @@
+use anyhow::Result;
+use compositor_common::scene::InputId;
+use compositor_pipeline::pipeline::{
+    decoder::{Codec, DecoderParameters, SenderClock},
+    PipelineInput,
+};
+use crossbeam_channel::{after, bounded, never, select, unbounded, Receiver, Sender};
+use std::{
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use ffmpeg_next::{media::Type, Dictionary, Packet, Rational};
+
+use crate::rtp_receiver::{input_with_dictionary_and_interrupt, PacketIter};
+
+/// Decoder expects timestamps in 90kHz clock (the same as RTP video streams),
+/// so packets read from the file are rescaled to that time base.
+const PTS_TIME_BASE: Rational = Rational(1, 90000);
+
+pub struct FileReceiver {
+    thread_finished: Receiver<()>,
+    should_close: Sender<()>,
+    playback_commands: Sender<PlaybackCommand>,
+    decoder_parameters: DecoderParameters,
+    pub(crate) path: PathBuf,
+}
+
+pub struct Options {
+    pub input_id: InputId,
+    pub path: PathBuf,
+    /// Read packets at the pace of their timestamps (like `ffmpeg -re`). Without it
+    /// packets are read as fast as the decoder consumes them, which is only
+    /// useful in offline mode.
+    pub realtime: bool,
+    /// Start from the beginning of the file when its end is reached. Looped input
+    /// never ends, so offline rendering does not finish until it's unregistered.
+    pub loop_playback: bool,
+    /// Speed of the playback, e.g. 2.0 plays the file twice as fast. Must be positive.
+    pub playback_rate: f64,
+}
+
+/// Changes playback of the file while it's being read. Timestamps of packets
+/// passed to the decoder stay continuous, so the queue sees a single stream
+/// regardless of seeks, pauses and rate changes.
+#[derive(Debug, Clone, Copy)]
+pub enum PlaybackCommand {
+    /// Continue from the keyframe at or before the timestamp (relative to the
+    /// start of the file).
+    Seek(Duration),
+    /// Stop reading the file. Queue keeps using the last frame of the input
+    /// (in offline mode rendering waits until playback is resumed).
+    Pause,
+    Resume,
+    SetLoop(bool),
+    SetPlaybackRate(f64),
+}
+
+impl PipelineInput for FileReceiver {
+    type Opts = Options;
+    type PacketIterator = crossbeam_channel::IntoIter<Packet>;
+
+    fn new(
+        opts: Self::Opts,
+    ) -> Result<(Self, Self::PacketIterator), Box<dyn std::error::Error + Send + Sync + 'static>>
+    {
+        let (drop_sender, drop_receiver) = bounded(0);
+        let (should_close_sender, should_close_receiver) = bounded(1);
+        let (decoder_params_sender, decoder_params_receiver) = bounded(1);
+
+        let (playback_commands_sender, playback_commands_receiver) = unbounded();
+
+        let path = opts.path.clone();
+        let (packet_sender, packet_receiver) = bounded(0);
+
+        thread::spawn(move || {
+            let result = FileReceiver::start(
+                &opts,
+                should_close_receiver,
+                playback_commands_receiver,
+                packet_sender,
+                &decoder_params_sender,
+            );
+            if let Err(err) = result {
+                // Error is only relevant if file failed to open, otherwise receiver
+                // is already gone and send will fail.
+                let _ = decoder_params_sender.send(Err(err));
+            }
+            drop_sender.send(())
+        });
+
+        let decoder_parameters = match decoder_params_receiver.recv()? {
+            Ok(decoder_parameters) => decoder_parameters,
+            Err(err) => {
+                drop_receiver.recv()?;
+                return Err(err.into());
+            }
+        };
+
+        Ok((
+            Self {
+                thread_finished: drop_receiver,
+                should_close: should_close_sender,
+                playback_commands: playback_commands_sender,
+                decoder_parameters,
+                path,
+            },
+            packet_receiver.into_iter(),
+        ))
+    }
+
+    fn decoder_parameters(&self) -> DecoderParameters {
+        self.decoder_parameters
+    }
+
+    fn sender_clock(&self) -> Option<SenderClock> {
+        None
+    }
+}
+
+impl Drop for FileReceiver {
+    fn drop(&mut self) {
+        // - should_close signals to reader thread that it should abort
+        // - drop_receiver signals to drop method that reader thread finished cleanup
+        //
+        // Reader thread finishes on its own at the end of the file, so failure
+        // to deliver should_close is expected.
+        let _ = self.should_close.send(());
+        self.thread_finished.recv().unwrap();
+    }
+}
+
+impl FileReceiver {
+    pub fn control_playback(&self, command: PlaybackCommand) {
+        // Fails only if the file was already read to the end.
+        let _ = self.playback_commands.send(command);
+    }
+
+    fn start(
+        opts: &Options,
+        should_close: Receiver<()>,
+        playback_commands: Receiver<PlaybackCommand>,
+        packet_sender: Sender<Packet>,
+        decoder_params_sender: &Sender<Result<DecoderParameters>>,
+    ) -> Result<()> {
+        // Message is not consumed by the interrupt callback, so it's still available
+        // to the reader loop.
+        let interrupt_receiver = should_close.clone();
+        let input_ctx =
+            input_with_dictionary_and_interrupt(&opts.path, None, Dictionary::new(), move || {
+                !interrupt_receiver.is_empty()
+            })?;
+
+        let input = input_ctx
+            .streams()
+            .best(Type::Video)
+            .ok_or(ffmpeg_next::Error::StreamNotFound)?;
+        let input_index = input.index();
+        let input_time_base = input.time_base();
+        let parameters = input.parameters();
+        let annexb = AnnexBConverter::new(&parameters);
+
+        decoder_params_sender
+            .send(Ok(decoder_parameters(parameters)?))
+            .unwrap();
+
+        let mut packets = PacketIter::new(input_ctx, input_index);
+        let mut playback = Playback::new(opts);
+        'reading: loop {
+            let Some(mut packet) = packets.next() else {
+                if playback.loop_playback && should_close.is_empty() {
+                    seek(&mut packets, Duration::ZERO)?;
+                    playback.reset_timeline();
+                    continue;
+                }
+                break;
+            };
+            packet.rescale_ts(input_time_base, PTS_TIME_BASE);
+
+            // Commands are applied before each packet. Waiting for the packet time
+            // or for resume is interrupted by new commands.
+            loop {
+                let timeout = match playback.wait_duration(&packet) {
+                    Some(wait) => after(wait),
+                    None => never(),
+                };
+                let command = select! {
+                    recv(playback_commands) -> command => command.ok(),
+                    recv(should_close) -> _ => break 'reading,
+                    recv(timeout) -> _ => None,
+                };
+                let Some(command) = command else {
+                    break;
+                };
+                match command {
+                    PlaybackCommand::Seek(timestamp) => {
+                        seek(&mut packets, timestamp)?;
+                        playback.reset_timeline();
+                        continue 'reading;
+                    }
+                    PlaybackCommand::Pause => playback.paused = true,
+                    PlaybackCommand::Resume => {
+                        playback.paused = false;
+                        playback.reset_timeline();
+                    }
+                    PlaybackCommand::SetLoop(loop_playback) => {
+                        playback.loop_playback = loop_playback
+                    }
+                    PlaybackCommand::SetPlaybackRate(rate) => {
+                        playback.rate = rate;
+                        playback.reset_timeline();
+                    }
+                }
+            }
+
+            playback.retimestamp(&mut packet);
+            if let Some(annexb) = &annexb {
+                packet = annexb.convert(&packet);
+            }
+            if packet_sender.send(packet).is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn seek(packets: &mut PacketIter, timestamp: Duration) -> Result<()> {
+    // Without a stream index, timestamp is in AV_TIME_BASE (microseconds).
+    let timestamp = timestamp.as_micros() as i64;
+    packets.context_mut().seek(timestamp, ..timestamp)?;
+    Ok(())
+}
+
+/// Maps timestamps of the file to the timestamps passed to the decoder (both in
+/// [`PTS_TIME_BASE`]). Mapping is linear between seeks, pauses and rate changes,
+/// after them it's anchored again after the last packet that was sent.
+struct Playback {
+    realtime: bool,
+    start: Instant,
+    paused: bool,
+    loop_playback: bool,
+    rate: f64,
+    /// File DTS and output DTS of the first packet since the last reset.
+    anchor: Option<(i64, i64)>,
+    /// Output timestamp right after the last sent packet.
+    last_output_end: i64,
+}
+
+impl Playback {
+    fn new(opts: &Options) -> Self {
+        Self {
+            realtime: opts.realtime,
+            start: Instant::now(),
+            paused: false,
+            loop_playback: opts.loop_playback,
+            rate: opts.playback_rate,
+            anchor: None,
+            last_output_end: 0,
+        }
+    }
+
+    fn reset_timeline(&mut self) {
+        self.anchor = None;
+    }
+
+    fn elapsed(&self) -> i64 {
+        (self.start.elapsed().as_secs_f64() * 90000.0) as i64
+    }
+
+    fn output_ts(&mut self, file_ts: i64, packet_dts: i64) -> i64 {
+        let (file_anchor, output_anchor) = match self.anchor {
+            Some(anchor) => anchor,
+            None => {
+                let output_anchor = match self.realtime {
+                    true => i64::max(self.last_output_end, self.elapsed()),
+                    false => self.last_output_end,
+                };
+                *self.anchor.insert((packet_dts, output_anchor))
+            }
+        };
+        output_anchor + ((file_ts - file_anchor) as f64 / self.rate).round() as i64
+    }
+
+    /// How long to wait before sending the packet. `None` means until a command
+    /// arrives.
+    fn wait_duration(&mut self, packet: &Packet) -> Option<Duration> {
+        if self.paused {
+            return None;
+        }
+        if !self.realtime {
+            return Some(Duration::ZERO);
+        }
+        // Packets are in decoding order, so DTS is monotonic unlike PTS.
+        let dts = packet_dts(packet);
+        let output_dts = self.output_ts(dts, dts);
+        let send_at = self.start + Duration::from_secs_f64(output_dts.max(0) as f64 / 90000.0);
+        Some(send_at.saturating_duration_since(Instant::now()))
+    }
+
+    fn retimestamp(&mut self, packet: &mut Packet) {
+        let dts = packet_dts(packet);
+        let output_dts = self.output_ts(dts, dts);
+        let output_pts = packet.pts().map(|pts| self.output_ts(pts, dts));
+        let output_duration = (packet.duration() as f64 / self.rate).round() as i64;
+
+        self.last_output_end = i64::max(
+            self.last_output_end,
+            output_pts.unwrap_or(output_dts) + output_duration.max(1),
+        );
+        packet.set_dts(Some(output_dts));
+        packet.set_pts(output_pts);
+        packet.set_duration(output_duration);
+    }
+}
+
+fn packet_dts(packet: &Packet) -> i64 {
+    packet.dts().or(packet.pts()).unwrap_or(0)
+}
+
+fn decoder_parameters(parameters: ffmpeg_next::codec::Parameters) -> Result<DecoderParameters> {
+    let codec = match parameters.id() {
+        ffmpeg_next::codec::Id::H264 => Codec::H264,
+        codec => anyhow::bail!("File codec {codec:?} is not supported."),
+    };
+    Ok(DecoderParameters { codec })
+}
+
+/// Containers like MP4 store H264 as length prefixed NAL units with parameter
+/// sets in the codec extradata (avcC), while the decoder is created without
+/// extradata and expects the Annex B stream used by RTP inputs.
+struct AnnexBConverter {
+    nal_length_size: usize,
+    /// SPS and PPS with start codes, prepended to every keyframe.
+    parameter_sets: Vec<u8>,
+}
+
+const START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+impl AnnexBConverter {
+    /// Returns `None` if the stream does not need conversion.
+    fn new(parameters: &ffmpeg_next::codec::Parameters) -> Option<Self> {
+        if parameters.id() != ffmpeg_next::codec::Id::H264 {
+            return None;
+        }
+        let extradata = unsafe {
+            let parameters = &*parameters.as_ptr();
+            if parameters.extradata.is_null() {
+                return None;
+            }
+            std::slice::from_raw_parts(parameters.extradata, parameters.extradata_size as usize)
+        };
+        Self::from_avcc(extradata)
+    }
+
+    fn from_avcc(avcc: &[u8]) -> Option<Self> {
+        // Annex B extradata starts with a start code instead of version 1.
+        if avcc.len() < 7 || avcc[0] != 1 {
+            return None;
+        }
+        let nal_length_size = (avcc[4] & 0b11) as usize + 1;
+
+        let mut parameter_sets = Vec::new();
+        let mut rest = &avcc[5..];
+        // Number of SPS is stored in 5 bits, number of PPS in a full byte.
+        for count_mask in [0b11111, 0xff] {
+            let (&count, tail) = rest.split_first()?;
+            rest = tail;
+            for _ in 0..(count & count_mask) {
+                let len = u16::from_be_bytes([*rest.first()?, *rest.get(1)?]) as usize;
+                let nal = rest.get(2..2 + len)?;
+                parameter_sets.extend_from_slice(&START_CODE);
+                parameter_sets.extend_from_slice(nal);
+                rest = &rest[2 + len..];
+            }
+        }
+
+        Some(Self {
+            nal_length_size,
+            parameter_sets,
+        })
+    }
+
+    fn convert(&self, packet: &Packet) -> Packet {
+        let mut data = Vec::new();
+        if packet.is_key() {
+            data.extend_from_slice(&self.parameter_sets);
+        }
+        let mut rest = packet.data().unwrap_or_default();
+        while rest.len() > self.nal_length_size {
+            let (len, tail) = rest.split_at(self.nal_length_size);
+            let len = len.iter().fold(0, |len, byte| (len << 8) | *byte as usize);
+            let nal = &tail[..len.min(tail.len())];
+            data.extend_from_slice(&START_CODE);
+            data.extend_from_slice(nal);
+            rest = &tail[nal.len()..];
+        }
+
+        let mut converted = Packet::copy(&data);
+        converted.set_pts(packet.pts());
+        converted.set_dts(packet.dts());
+        converted.set_duration(packet.duration());
+        converted.set_flags(packet.flags());
+        converted.set_stream(packet.stream());
+        converted
+    }
+}
@@ -0,0 +1,160 @@
+use log::error;
+use std::{path::Path, sync::Arc, time::Duration};
+
+use compositor_pipeline::pipeline::PipelineOutput;
+use ffmpeg_next::{
+    codec,
+    format::{self, context::Output},
+    Codec, Packet, Rational,
+};
+
+/// Time base of packets produced by the encoder.
+pub(crate) const PTS_TIME_BASE: Rational = Rational(1, 90000);
+/// Owner of the ID3 PRIV frame that carries SCTE-35 `splice_info_section`.
+const SCTE35_ID3_OWNER: &[u8] = b"urn:scte:scte35:2013:bin";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FileWriter {
+    pub(crate) path: Arc<Path>,
+}
+
+/// Trailer (e.g. MP4 index) is written when the context is dropped, so the file
+/// is complete only after the output is finished or unregistered.
+pub struct FileContext {
+    output_ctx: Output,
+    time_base: Rational,
+    /// Index and time base of the timed ID3 stream carrying SCTE-35 markers.
+    scte35_stream: Option<(usize, Rational)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Options {
+    /// Container format is deduced from the file extension.
+    pub path: Arc<Path>,
+    /// Add a timed ID3 stream with SCTE-35 markers, see [`supports_scte35`].
+    pub scte35: bool,
+}
+
+/// SCTE-35 markers are written as timed ID3 metadata, only the MPEG-TS muxer
+/// supports it.
+pub fn supports_scte35(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("ts"))
+}
+
+impl PipelineOutput for FileWriter {
+    type Opts = Options;
+    type Context = FileContext;
+
+    fn new(
+        options: Options,
+        codec: Codec,
+    ) -> Result<(Self, FileContext), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let mut output_ctx = format::output(&options.path)?;
+
+        let mut stream = output_ctx.add_stream(codec)?;
+        stream.set_time_base(PTS_TIME_BASE);
+        unsafe {
+            (*(*stream.as_mut_ptr()).codecpar).codec_id = codec::Id::H264.into();
+        }
+
+        let scte35_stream_index = match options.scte35 {
+            true => Some(add_scte35_stream(&mut output_ctx)?),
+            false => None,
+        };
+
+        output_ctx.write_header()?;
+        // Muxer can change the time base of the stream when writing the header.
+        let time_base = output_ctx.stream(0).unwrap().time_base();
+        let scte35_stream =
+            scte35_stream_index.map(|index| (index, output_ctx.stream(index).unwrap().time_base()));
+
+        Ok((
+            Self { path: options.path },
+            FileContext {
+                output_ctx,
+                time_base,
+                scte35_stream,
+            },
+        ))
+    }
+
+    fn send_packet(&self, context: &mut FileContext, mut packet: Packet) {
+        packet.set_stream(0);
+        packet.rescale_ts(PTS_TIME_BASE, context.time_base);
+        if let Err(err) = packet.write_interleaved(&mut context.output_ctx) {
+            error!("Failed to write packet to {:?}: {err}", self.path)
+        }
+    }
+
+    fn send_scte35(&self, context: &mut FileContext, section: &[u8], pts: Duration) {
+        let Some(stream) = context.scte35_stream else {
+            return;
+        };
+        if let Err(err) = write_scte35(&mut context.output_ctx, stream, section, pts) {
+            error!("Failed to write SCTE-35 marker to {:?}: {err}", self.path)
+        }
+    }
+}
+
+/// Adds a timed ID3 data stream for SCTE-35 markers. Returns index of the stream.
+pub(crate) fn add_scte35_stream(output_ctx: &mut Output) -> Result<usize, ffmpeg_next::Error> {
+    let mut stream = output_ctx.add_stream(codec::Id::TIMED_ID3)?;
+    stream.set_time_base(PTS_TIME_BASE);
+    unsafe {
+        let codecpar = (*stream.as_mut_ptr()).codecpar;
+        (*codecpar).codec_type = ffmpeg_next::ffi::AVMediaType::AVMEDIA_TYPE_DATA;
+        (*codecpar).codec_id = codec::Id::TIMED_ID3.into();
+    }
+    Ok(stream.index())
+}
+
+/// Writes `splice_info_section` to the stream added with [`add_scte35_stream`],
+/// identified by its index and time base.
+pub(crate) fn write_scte35(
+    output_ctx: &mut Output,
+    (stream_index, time_base): (usize, Rational),
+    section: &[u8],
+    pts: Duration,
+) -> Result<(), ffmpeg_next::Error> {
+    let mut packet = Packet::copy(&id3_priv_tag(SCTE35_ID3_OWNER, section));
+    let pts = (pts.as_secs_f64() * PTS_TIME_BASE.1 as f64) as i64;
+    packet.set_pts(Some(pts));
+    packet.set_dts(Some(pts));
+    packet.set_stream(stream_index);
+    packet.rescale_ts(PTS_TIME_BASE, time_base);
+    packet.write_interleaved(output_ctx)
+}
+
+/// ID3v2.4 tag with a single PRIV frame.
+fn id3_priv_tag(owner: &[u8], data: &[u8]) -> Vec<u8> {
+    let frame_size = owner.len() + 1 + data.len();
+    let mut tag = b"ID3\x04\x00\x00".to_vec();
+    tag.extend_from_slice(&syncsafe(10 + frame_size));
+    tag.extend_from_slice(b"PRIV");
+    tag.extend_from_slice(&syncsafe(frame_size));
+    // frame flags
+    tag.extend_from_slice(&[0, 0]);
+    tag.extend_from_slice(owner);
+    tag.push(0);
+    tag.extend_from_slice(data);
+    tag
+}
+
+/// 28 bit integer with the highest bit of every byte unset.
+fn syncsafe(value: usize) -> [u8; 4] {
+    [
+        (value >> 21) as u8 & 0x7F,
+        (value >> 14) as u8 & 0x7F,
+        (value >> 7) as u8 & 0x7F,
+        value as u8 & 0x7F,
+    ]
+}
+
+impl Drop for FileContext {
+    fn drop(&mut self) {
+        if let Err(err) = self.output_ctx.write_trailer() {
+            error!("Failed to write file trailer: {err}")
+        }
+    }
+}
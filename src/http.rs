@@ -1,35 +1,55 @@
+use bytes::Bytes;
 use compositor_common::error::ErrorStack;
-use compositor_pipeline::pipeline;
 use compositor_render::EventLoop;
 use crossbeam_channel::RecvTimeoutError;
-use log::{error, info};
+use log::{error, info, warn};
 
+use schemars::schema_for;
 use serde_json::json;
 use signal_hook::{consts, iterator::Signals};
 use std::{
+    env, fs,
     io::{Cursor, ErrorKind},
     net::SocketAddr,
     sync::Arc,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tiny_http::{Header, Response, StatusCode};
+use tiny_http::{Header, Method, Response, StatusCode};
 
 use crate::{
     api::{self, Api, Request, ResponseHandler},
     error::ApiError,
+    scene_file::SceneFile,
+    types::InitOptions,
 };
 
 pub const API_PORT_ENV: &str = "MEMBRANE_VIDEO_COMPOSITOR_API_PORT";
+/// If set, every request has to include "Authorization: Bearer <token>" header.
+pub const API_TOKEN_ENV: &str = "MEMBRANE_VIDEO_COMPOSITOR_API_TOKEN";
+/// Paths to PEM encoded certificate chain and private key. If both are set, the API
+/// is served over HTTPS.
+pub const API_TLS_CERT_PATH_ENV: &str = "MEMBRANE_VIDEO_COMPOSITOR_API_TLS_CERT_PATH";
+pub const API_TLS_KEY_PATH_ENV: &str = "MEMBRANE_VIDEO_COMPOSITOR_API_TLS_KEY_PATH";
 
 pub struct Server {
     server: tiny_http::Server,
     content_type_json: Header,
+    content_type_jpeg: Header,
+    api_token: Option<String>,
 }
 
 impl Server {
     pub fn new(port: u16) -> Arc<Self> {
-        match tiny_http::Server::http(SocketAddr::from(([0, 0, 0, 0], port))) {
+        let address = SocketAddr::from(([0, 0, 0, 0], port));
+        let server = match tls_config() {
+            Some(ssl_config) => tiny_http::Server::https(address, ssl_config),
+            None => tiny_http::Server::http(address),
+        };
+        let api_token = env::var(API_TOKEN_ENV)
+            .ok()
+            .filter(|token| !token.is_empty());
+        match server {
             Ok(server) => Self {
                 server,
                 content_type_json: Header::from_bytes(
@@ -37,6 +57,9 @@ impl Server {
                     &b"application/json"[..],
                 )
                 .unwrap(),
+                content_type_jpeg: Header::from_bytes(&b"Content-Type"[..], &b"image/jpeg"[..])
+                    .unwrap(),
+                api_token,
             }
             .into(),
             Err(err) => {
@@ -52,11 +75,42 @@ impl Server {
     }
 
     pub fn run(self: Arc<Self>) {
+        self.run_with_scene_file(None)
+    }
+
+    /// Compositor is initialized from `scene_file` if specified, otherwise
+    /// the server waits for the "init" request.
+    pub fn run_with_scene_file(self: Arc<Self>, scene_file: Option<SceneFile>) {
         info!("Listening on port {}", self.server.server_addr());
-        let (mut api, event_loop) = self.handle_init();
+        let (mut api, event_loop) = match scene_file {
+            Some(scene_file) => match scene_file.apply() {
+                Ok(initialized) => initialized,
+                Err(err) => panic!("Failed to apply scene file.\nError: {}", err.message),
+            },
+            None => self.handle_init(),
+        };
         thread::spawn(move || {
-            for mut raw_request in self.server.incoming_requests() {
-                let result = Self::handle_request_after_init(&mut api, &mut raw_request);
+            // Time of the last authorized request, `None` after the composition
+            // was torn down.
+            let mut last_request = Some(Instant::now());
+            while let Some(mut raw_request) = self.next_request(&mut api, &mut last_request) {
+                if is_schema_request(&raw_request) {
+                    self.send_schema_response(raw_request);
+                    continue;
+                }
+                if let Err(err) = self.authorize(&raw_request) {
+                    self.send_err_response(raw_request, err);
+                    continue;
+                }
+                if let Some(output_id) = preview_request_output_id(&raw_request) {
+                    match api.preview(&output_id) {
+                        Ok(image) => self.send_preview_response(raw_request, image),
+                        Err(err) => self.send_err_response(raw_request, err),
+                    }
+                    last_request = Some(Instant::now());
+                    continue;
+                }
+                let result = self.handle_request_after_init(&mut api, &mut raw_request);
                 match result {
                     Ok(ResponseHandler::Ok) => {
                         self.send_response(raw_request, api::Response::Ok {});
@@ -102,6 +156,7 @@ impl Server {
                         self.send_err_response(raw_request, err);
                     }
                 }
+                last_request = Some(Instant::now());
             }
         });
 
@@ -117,8 +172,55 @@ impl Server {
         }
     }
 
+    /// Blocks until the next request. If no authorized request was handled for
+    /// the keep-alive timeout since `last_request`, the composition is torn down
+    /// and the server keeps waiting for requests.
+    /// Loaded scripts are run on every iteration, so their ticks and input events
+    /// are not delayed by a steady stream of requests.
+    fn next_request(
+        &self,
+        api: &mut Api,
+        last_request: &mut Option<Instant>,
+    ) -> Option<tiny_http::Request> {
+        let keep_alive_timeout = api.keep_alive_timeout();
+        loop {
+            let deadline = keep_alive_timeout
+                .zip(*last_request)
+                .and_then(|(timeout, last_request)| last_request.checked_add(timeout));
+            api.run_scripts();
+            let keep_alive_remaining =
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            let timeout = match (keep_alive_remaining, api.time_to_next_script_run()) {
+                (None, None) => return self.server.recv().ok(),
+                (Some(remaining), None) | (None, Some(remaining)) => remaining,
+                (Some(keep_alive), Some(script)) => keep_alive.min(script),
+            };
+            match self.server.recv_timeout(timeout) {
+                Ok(Some(raw_request)) => return Some(raw_request),
+                Ok(None) => {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        warn!(
+                            "No requests received for {}ms, removing inputs, outputs and the scene.",
+                            keep_alive_timeout.unwrap_or_default().as_millis()
+                        );
+                        api.teardown();
+                        *last_request = None;
+                    }
+                }
+                Err(err) => {
+                    error!("Failed to receive request {}.", err);
+                    return None;
+                }
+            }
+        }
+    }
+
     fn handle_init(&self) -> (Api, EventLoop) {
         for mut raw_request in self.server.incoming_requests() {
+            if is_schema_request(&raw_request) {
+                self.send_schema_response(raw_request);
+                continue;
+            }
             let result = self
                 .handle_request_before_init(&mut raw_request)
                 .and_then(Api::new);
@@ -136,20 +238,22 @@ impl Server {
     }
 
     fn handle_request_after_init(
+        &self,
         api: &mut Api,
         raw_request: &mut tiny_http::Request,
     ) -> Result<ResponseHandler, ApiError> {
-        let request = Server::parse_request(raw_request)?;
+        let request = self.parse_request(raw_request)?;
         api.handle_request(request)
     }
 
     fn handle_request_before_init(
         &self,
         raw_request: &mut tiny_http::Request,
-    ) -> Result<pipeline::Options, ApiError> {
-        let request = Server::parse_request(raw_request)?;
+    ) -> Result<InitOptions, ApiError> {
+        self.authorize(raw_request)?;
+        let request = self.parse_request(raw_request)?;
         match request {
-            Request::Init(opts) => Ok(opts.try_into()?),
+            Request::Init(opts) => Ok(opts),
             _ => Err(ApiError::new(
                 "COMPOSITOR_NOT_INITIALIZED",
                 "Compositor was not initialized, send \"init\" request first.".to_string(),
@@ -175,11 +279,53 @@ impl Server {
         }
     }
 
+    /// JSON Schema of all requests and responses, for generating API clients.
+    fn send_schema_response(&self, raw_request: tiny_http::Request) {
+        if let Err(err) = self.authorize(&raw_request) {
+            self.send_err_response(raw_request, err);
+            return;
+        }
+        let schema = json!({
+            "request": schema_for!(Request),
+            "response": schema_for!(api::Response),
+        });
+        let response_result = serde_json::to_string(&schema)
+            .map_err(Into::into)
+            .and_then(|body| {
+                raw_request.respond(Response::new(
+                    StatusCode(200),
+                    vec![self.content_type_json.clone()],
+                    Cursor::new(&body),
+                    Some(body.len()),
+                    None,
+                ))
+            });
+        if let Err(err) = response_result {
+            error!("Failed to send response {}.", err);
+        }
+    }
+
+    fn send_preview_response(&self, raw_request: tiny_http::Request, image: Bytes) {
+        let response = Response::new(
+            StatusCode(200),
+            vec![self.content_type_jpeg.clone()],
+            Cursor::new(&image),
+            Some(image.len()),
+            None,
+        );
+        if let Err(err) = raw_request.respond(response) {
+            error!("Failed to send response {}.", err);
+        }
+    }
+
     fn send_err_response(&self, raw_request: tiny_http::Request, err: ApiError) {
         let response_result = serde_json::to_string(&json!({
             "msg": err.message,
             "stack": err.stack,
             "error_code": err.error_code,
+            "entity_ids": err.entity_ids,
+            "node_id": err.node_id,
+            "field": err.field,
         }))
         .map_err(Into::into)
         .and_then(|body| {
@@ -196,8 +342,81 @@ impl Server {
         }
     }
 
-    fn parse_request(request: &mut tiny_http::Request) -> Result<Request, ApiError> {
+    /// Requests have to be authorized before they are parsed.
+    fn parse_request(&self, request: &mut tiny_http::Request) -> Result<Request, ApiError> {
         serde_json::from_reader::<_, Request>(request.as_reader())
             .map_err(|err| ApiError::malformed_request(&err))
     }
+
+    fn authorize(&self, request: &tiny_http::Request) -> Result<(), ApiError> {
+        let Some(api_token) = &self.api_token else {
+            return Ok(());
+        };
+        let request_token = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .and_then(|header| header.value.as_str().strip_prefix("Bearer "));
+        match request_token {
+            Some(request_token) if constant_time_eq(request_token, api_token) => Ok(()),
+            Some(_) | None => Err(ApiError::new(
+                "UNAUTHORIZED",
+                "Missing or invalid API token in the \"Authorization: Bearer <token>\" header."
+                    .to_string(),
+                StatusCode(401),
+            )),
+        }
+    }
+}
+
+fn is_schema_request(raw_request: &tiny_http::Request) -> bool {
+    *raw_request.method() == Method::Get && raw_request.url() == "/schema"
+}
+
+/// Output id from the "GET /preview/<output_id>" request.
+fn preview_request_output_id(raw_request: &tiny_http::Request) -> Option<String> {
+    if *raw_request.method() != Method::Get {
+        return None;
+    }
+    raw_request
+        .url()
+        .strip_prefix("/preview/")
+        .filter(|output_id| !output_id.is_empty())
+        .map(ToString::to_string)
+}
+
+/// TLS is enabled only if both the certificate and the key are specified. If
+/// only one of them is set, the server refuses to start instead of silently
+/// falling back to plain HTTP.
+fn tls_config() -> Option<tiny_http::SslConfig> {
+    let (cert_path, key_path) = match (
+        env::var(API_TLS_CERT_PATH_ENV),
+        env::var(API_TLS_KEY_PATH_ENV),
+    ) {
+        (Ok(cert_path), Ok(key_path)) => (cert_path, key_path),
+        (Err(_), Err(_)) => return None,
+        (Ok(_), Err(_)) => panic!(
+            "{API_TLS_CERT_PATH_ENV} is set, but {API_TLS_KEY_PATH_ENV} is missing. Set both to enable TLS or neither to use plain HTTP."
+        ),
+        (Err(_), Ok(_)) => panic!(
+            "{API_TLS_KEY_PATH_ENV} is set, but {API_TLS_CERT_PATH_ENV} is missing. Set both to enable TLS or neither to use plain HTTP."
+        ),
+    };
+    let read = |path: &str| {
+        fs::read(path).unwrap_or_else(|err| panic!("Failed to read TLS file {path}: {err}"))
+    };
+    Some(tiny_http::SslConfig {
+        certificate: read(&cert_path),
+        private_key: read(&key_path),
+    })
+}
+
+/// Compares tokens without short-circuiting on the first difference, so response
+/// time does not reveal how much of the token was guessed.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
 }
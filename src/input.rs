@@ -0,0 +1,123 @@
+use compositor_pipeline::pipeline::{
+    decoder::{DecoderParameters, SenderClock},
+    PipelineInput,
+};
+use ffmpeg_next::Packet;
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    camera_receiver::{self, CameraReceiver},
+    file_receiver::{self, FileReceiver},
+    rtp_receiver::{self, RtpReceiver},
+    rtsp_receiver::{self, RtspReceiver},
+    sdp::StreamDescription,
+};
+
+pub enum Input {
+    Rtp(RtpReceiver),
+    Rtsp(RtspReceiver),
+    Camera(CameraReceiver),
+    File(FileReceiver),
+}
+
+pub enum Options {
+    Rtp(rtp_receiver::Options),
+    Rtsp(rtsp_receiver::Options),
+    Camera(camera_receiver::Options),
+    File(file_receiver::Options),
+}
+
+impl Input {
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            Input::Rtp(receiver) => Some(receiver.port),
+            Input::Rtsp(_) | Input::Camera(_) | Input::File(_) => None,
+        }
+    }
+
+    pub fn url(&self) -> Option<&Arc<str>> {
+        match self {
+            Input::Rtsp(receiver) => Some(&receiver.url),
+            Input::Rtp(_) | Input::Camera(_) | Input::File(_) => None,
+        }
+    }
+
+    pub fn device(&self) -> Option<&str> {
+        match self {
+            Input::Rtp(_) | Input::Rtsp(_) | Input::File(_) => None,
+            Input::Camera(receiver) => Some(&receiver.device),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Input::Rtp(_) | Input::Rtsp(_) | Input::Camera(_) => None,
+            Input::File(receiver) => Some(&receiver.path),
+        }
+    }
+
+    /// SDP describing the stream the input expects. Compositor listens on all
+    /// interfaces, so connection address is set to "0.0.0.0".
+    pub fn sdp(&self) -> Option<String> {
+        match self {
+            Input::Rtp(receiver) => Some(
+                StreamDescription {
+                    ip: "0.0.0.0",
+                    port: receiver.port,
+                    fec: receiver.fec,
+                    multicast_ttl: None,
+                    srtp_key: receiver.srtp_key.as_deref(),
+                }
+                .sdp(),
+            ),
+            Input::Rtsp(_) | Input::Camera(_) | Input::File(_) => None,
+        }
+    }
+}
+
+impl PipelineInput for Input {
+    type Opts = Options;
+    type PacketIterator = crossbeam_channel::IntoIter<Packet>;
+
+    fn new(
+        opts: Self::Opts,
+    ) -> Result<(Self, Self::PacketIterator), Box<dyn std::error::Error + Send + Sync + 'static>>
+    {
+        match opts {
+            Options::Rtp(opts) => {
+                let (receiver, packets) = RtpReceiver::new(opts)?;
+                Ok((Input::Rtp(receiver), packets))
+            }
+            Options::Rtsp(opts) => {
+                let (receiver, packets) = RtspReceiver::new(opts)?;
+                Ok((Input::Rtsp(receiver), packets))
+            }
+            Options::Camera(opts) => {
+                let (receiver, packets) = CameraReceiver::new(opts)?;
+                Ok((Input::Camera(receiver), packets))
+            }
+            Options::File(opts) => {
+                let (receiver, packets) = FileReceiver::new(opts)?;
+                Ok((Input::File(receiver), packets))
+            }
+        }
+    }
+
+    fn decoder_parameters(&self) -> DecoderParameters {
+        match self {
+            Input::Rtp(receiver) => receiver.decoder_parameters(),
+            Input::Rtsp(receiver) => receiver.decoder_parameters(),
+            Input::Camera(receiver) => receiver.decoder_parameters(),
+            Input::File(receiver) => receiver.decoder_parameters(),
+        }
+    }
+
+    fn sender_clock(&self) -> Option<SenderClock> {
+        match self {
+            Input::Rtp(receiver) => receiver.sender_clock(),
+            Input::Rtsp(receiver) => receiver.sender_clock(),
+            Input::Camera(receiver) => receiver.sender_clock(),
+            Input::File(receiver) => receiver.sender_clock(),
+        }
+    }
+}
@@ -1,6 +1,21 @@
 pub mod api;
+pub mod camera_receiver;
 pub mod error;
+pub mod file_receiver;
+pub mod file_writer;
 pub mod http;
+pub mod input;
+pub mod logger;
+pub mod mpegts_sender;
+pub mod multiviewer;
+pub mod output;
+pub mod preview;
+pub mod rtp_fec;
+pub mod rtp_proxy;
 pub mod rtp_receiver;
 pub mod rtp_sender;
+pub mod rtsp_receiver;
+pub mod scene_file;
+pub mod scripting;
+pub mod sdp;
 pub mod types;
@@ -0,0 +1,66 @@
+use std::{
+    env,
+    sync::{Mutex, OnceLock},
+};
+
+use tracing_subscriber::{
+    filter::ParseError, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter,
+    Registry,
+};
+
+/// Format of log lines, "json" prints one JSON object per line. Any other value
+/// (or no value) prints human-readable lines.
+pub const LOGGER_FORMAT_ENV: &str = "MEMBRANE_VIDEO_COMPOSITOR_LOGGER_FORMAT";
+
+/// Used if `RUST_LOG` is not set or is not valid.
+const DEFAULT_LOGGER_LEVEL: &str = "info";
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+struct Logger {
+    filter_handle: reload::Handle<EnvFilter, Registry>,
+    /// Levels of the current filter in the `RUST_LOG` syntax.
+    level: Mutex<String>,
+}
+
+/// Installs the global `tracing` subscriber. Logs of crates that use the `log`
+/// macros are forwarded to it. Initial levels are read from `RUST_LOG`.
+pub fn init_logger() {
+    let level = env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|level| EnvFilter::try_new(level).is_ok())
+        .unwrap_or_else(|| DEFAULT_LOGGER_LEVEL.to_string());
+    let (filter, filter_handle) = reload::Layer::new(EnvFilter::new(&level));
+
+    let registry = tracing_subscriber::registry().with(filter);
+    match env::var(LOGGER_FORMAT_ENV).as_deref() {
+        Ok("json") => registry.with(fmt::layer().json()).init(),
+        _ => registry.with(fmt::layer()).init(),
+    }
+
+    let _ = LOGGER.set(Logger {
+        filter_handle,
+        level: Mutex::new(level),
+    });
+}
+
+/// Replaces log levels of all modules. `level` has the same syntax as `RUST_LOG`,
+/// e.g. "info,compositor_pipeline=debug". Does nothing if the logger was not
+/// initialized with [`init_logger`].
+pub fn update_level(level: &str) -> Result<(), ParseError> {
+    let filter = EnvFilter::try_new(level)?;
+    if let Some(logger) = LOGGER.get() {
+        // Fails only if the subscriber was already dropped.
+        if logger.filter_handle.reload(filter).is_ok() {
+            *logger.level.lock().unwrap() = level.to_string();
+        }
+    }
+    Ok(())
+}
+
+/// Current levels in the `RUST_LOG` syntax, `None` if the logger was not initialized.
+pub fn current_level() -> Option<String> {
+    LOGGER
+        .get()
+        .map(|logger| logger.level.lock().unwrap().clone())
+}
@@ -3,22 +3,33 @@ use std::env;
 use compositor_chromium::cef::bundle_for_development;
 use log::info;
 
-use crate::http::API_PORT_ENV;
+use crate::{http::API_PORT_ENV, scene_file::SceneFile};
 
 mod api;
+mod camera_receiver;
 mod error;
+mod file_receiver;
+mod file_writer;
 mod http;
+mod input;
+mod logger;
+mod multiviewer;
+mod output;
+mod preview;
+mod rtp_fec;
+mod rtp_proxy;
 mod rtp_receiver;
 mod rtp_sender;
+mod rtsp_receiver;
+mod scene_file;
+mod sdp;
 mod types;
 
 #[cfg(test)]
 mod snapshot_tests;
 
 fn main() {
-    env_logger::init_from_env(
-        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "info"),
-    );
+    logger::init_logger();
 
     let target_path = std::env::current_exe()
         .unwrap()
@@ -29,10 +40,13 @@ fn main() {
         panic!("Build process helper first. For release profile use: cargo build -r --bin process_helper");
     }
 
+    let scene_file = SceneFile::from_args();
+
     ffmpeg_next::format::network::init();
+    ffmpeg_next::device::register_all();
 
     let port = env::var(API_PORT_ENV).unwrap_or_else(|_| "8001".to_string());
-    http::Server::new(port.parse::<u16>().unwrap()).run();
+    http::Server::new(port.parse::<u16>().unwrap()).run_with_scene_file(scene_file);
 
     info!("Received exit signal. Terminating...")
     // TODO: add graceful shutdown
@@ -0,0 +1,182 @@
+use log::error;
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use compositor_pipeline::pipeline::PipelineOutput;
+use ffmpeg_next::{
+    codec,
+    format::{self, context::Output},
+    Codec, Dictionary, Packet, Rational,
+};
+
+use crate::{
+    file_writer::{self, PTS_TIME_BASE},
+    rtp_sender::MulticastOptions,
+};
+
+/// Same as ffmpeg defaults.
+pub const DEFAULT_PMT_PID: u16 = 0x1000;
+pub const DEFAULT_VIDEO_PID: u16 = 0x0100;
+pub const DEFAULT_SERVICE_NAME: &str = "Service01";
+pub const DEFAULT_PCR_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Seven 188 byte TS packets, the most that fits in a datagram with 1500 bytes MTU.
+const UDP_PACKET_SIZE: usize = 1316;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MpegTsSender {
+    pub(crate) port: u16,
+    pub(crate) ip: Arc<str>,
+}
+
+/// Remaining TS packets are flushed when the context is dropped.
+pub struct MpegTsContext {
+    output_ctx: Output,
+    time_base: Rational,
+    /// Index and time base of the timed ID3 stream carrying SCTE-35 markers.
+    scte35_stream: Option<(usize, Rational)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transport {
+    /// TS packets sent directly in UDP datagrams.
+    Udp,
+    /// TS packets in RTP packets with the MP2T payload type (RFC 2250).
+    Rtp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Options {
+    pub port: u16,
+    pub ip: Arc<str>,
+    pub transport: Transport,
+    /// Only set if `ip` is a multicast group address.
+    pub multicast: Option<MulticastOptions>,
+    /// PID of the program map table.
+    pub pmt_pid: u16,
+    /// PID of the H264 stream. Streams added after it (e.g. SCTE-35 markers) get
+    /// the following PIDs.
+    pub video_pid: u16,
+    /// Sent in the service description table.
+    pub service_name: Arc<str>,
+    /// Maximum time between program clock references.
+    pub pcr_interval: Duration,
+    /// Add a timed ID3 stream with SCTE-35 markers, same as in MPEG-TS files.
+    pub scte35: bool,
+}
+
+impl PipelineOutput for MpegTsSender {
+    type Opts = Options;
+    type Context = MpegTsContext;
+
+    fn new(
+        options: Options,
+        codec: Codec,
+    ) -> Result<(Self, MpegTsContext), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let (format_name, mut url) = match options.transport {
+            Transport::Udp => (
+                "mpegts",
+                format!(
+                    "udp://{}:{}?pkt_size={UDP_PACKET_SIZE}",
+                    options.ip, options.port
+                ),
+            ),
+            Transport::Rtp => (
+                "rtp_mpegts",
+                format!(
+                    "rtp://{}:{}?rtcpport={}",
+                    options.ip, options.port, options.port
+                ),
+            ),
+        };
+        if let Some(multicast) = &options.multicast {
+            url.push_str(&format!("&ttl={}", multicast.ttl));
+            if let Some(interface) = &multicast.interface {
+                url.push_str(&format!("&localaddr={interface}"));
+            }
+        }
+
+        let mpegts_options = [
+            ("mpegts_pmt_start_pid", options.pmt_pid.to_string()),
+            ("mpegts_start_pid", options.video_pid.to_string()),
+            ("pcr_period", options.pcr_interval.as_millis().to_string()),
+        ];
+        let mut muxer_options = Dictionary::new();
+        match options.transport {
+            Transport::Udp => {
+                for (key, value) in &mpegts_options {
+                    muxer_options.set(key, value);
+                }
+            }
+            // RTP muxer passes options to the nested MPEG-TS muxer as a single
+            // "key=value:key=value" string.
+            Transport::Rtp => {
+                let mpegts_options = mpegts_options
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(":");
+                muxer_options.set("mpegts_muxer_options", &mpegts_options);
+            }
+        }
+
+        let mut output_ctx =
+            format::output_as_with(&PathBuf::from(url), format_name, Dictionary::new())?;
+        let mut metadata = Dictionary::new();
+        metadata.set("service_name", &options.service_name);
+        output_ctx.set_metadata(metadata);
+
+        let mut stream = output_ctx.add_stream(codec)?;
+        stream.set_time_base(PTS_TIME_BASE);
+        unsafe {
+            (*(*stream.as_mut_ptr()).codecpar).codec_id = codec::Id::H264.into();
+        }
+
+        let scte35_stream_index = match options.scte35 {
+            true => Some(file_writer::add_scte35_stream(&mut output_ctx)?),
+            false => None,
+        };
+
+        output_ctx.write_header_with(muxer_options)?;
+        // Muxer can change the time base of the stream when writing the header.
+        let time_base = output_ctx.stream(0).unwrap().time_base();
+        let scte35_stream =
+            scte35_stream_index.map(|index| (index, output_ctx.stream(index).unwrap().time_base()));
+
+        Ok((
+            Self {
+                port: options.port,
+                ip: options.ip,
+            },
+            MpegTsContext {
+                output_ctx,
+                time_base,
+                scte35_stream,
+            },
+        ))
+    }
+
+    fn send_packet(&self, context: &mut MpegTsContext, mut packet: Packet) {
+        packet.set_stream(0);
+        packet.rescale_ts(PTS_TIME_BASE, context.time_base);
+        if let Err(err) = packet.write_interleaved(&mut context.output_ctx) {
+            error!("Failed to send MPEG-TS packets: {err}")
+        }
+    }
+
+    fn send_scte35(&self, context: &mut MpegTsContext, section: &[u8], pts: Duration) {
+        let Some(stream) = context.scte35_stream else {
+            return;
+        };
+        if let Err(err) = file_writer::write_scte35(&mut context.output_ctx, stream, section, pts) {
+            error!("Failed to send SCTE-35 marker: {err}")
+        }
+    }
+}
+
+impl Drop for MpegTsContext {
+    fn drop(&mut self) {
+        if let Err(err) = self.output_ctx.write_trailer() {
+            error!("Failed to flush MPEG-TS stream: {err}")
+        }
+    }
+}
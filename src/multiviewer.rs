@@ -0,0 +1,231 @@
+use std::sync::Arc;
+
+use compositor_common::{
+    scene::{
+        builtin_transformations::{
+            BuiltinSpec, FitToResolutionSpec, FixedPositionLayoutSpec, HorizontalPosition,
+            ScalingQuality, TextureLayout, VerticalPosition,
+            FIXED_POSITION_LAYOUT_MAX_INPUTS_COUNT,
+        },
+        text_spec::{Style, TextDimensions, TextSpec, Weight, Wrap},
+        InputId, NodeId, NodeParams, NodeSpec, OutputId, OutputSpec, Resolution, SceneSpec,
+    },
+    util::{
+        align::{HorizontalAlign, VerticalAlign},
+        colors::RGBAColor,
+        coord::Coord,
+        degree::Degree,
+    },
+};
+use log::warn;
+
+/// Nodes generated for the multiviewer start with this prefix.
+const NODE_ID_PREFIX: &str = "__multiviewer";
+pub const MAX_MULTIVIEWER_INPUTS: usize = FIXED_POSITION_LAYOUT_MAX_INPUTS_COUNT as usize;
+
+const BACKGROUND_COLOR: RGBAColor = RGBAColor(0, 0, 0, 255);
+const LABEL_BACKGROUND_COLOR: RGBAColor = RGBAColor(0, 0, 0, 160);
+const LABEL_COLOR: RGBAColor = RGBAColor(255, 255, 255, 255);
+
+#[derive(Debug, Clone)]
+pub struct MultiviewerOptions {
+    pub output_id: OutputId,
+    pub resolution: Resolution,
+    /// Derived from the number of inputs to make the grid close to square if not set.
+    pub columns: Option<usize>,
+}
+
+/// Returns the scene with the multiviewer of `inputs` rendered to its output. Output
+/// of the scene with the same id is replaced.
+pub fn with_multiviewer(
+    scene: &SceneSpec,
+    opts: &MultiviewerOptions,
+    inputs: &[InputId],
+) -> SceneSpec {
+    if inputs.len() > MAX_MULTIVIEWER_INPUTS {
+        warn!(
+            "Multiviewer shows only the first {MAX_MULTIVIEWER_INPUTS} of {} inputs.",
+            inputs.len()
+        );
+    }
+    let inputs = &inputs[..usize::min(inputs.len(), MAX_MULTIVIEWER_INPUTS)];
+
+    let mut nodes = scene.nodes.clone();
+    let root_id = match inputs.is_empty() {
+        true => {
+            let node = empty_grid_node(opts.resolution);
+            let node_id = node.node_id.clone();
+            nodes.push(node);
+            node_id
+        }
+        false => {
+            let (grid_nodes, root_id) = grid_nodes(opts, inputs);
+            nodes.extend(grid_nodes);
+            root_id
+        }
+    };
+
+    let mut outputs: Vec<_> = scene
+        .outputs
+        .iter()
+        .filter(|output| output.output_id != opts.output_id)
+        .cloned()
+        .collect();
+    outputs.push(OutputSpec {
+        output_id: opts.output_id.clone(),
+        input_pad: root_id,
+    });
+
+    SceneSpec { nodes, outputs }
+}
+
+fn grid_nodes(opts: &MultiviewerOptions, inputs: &[InputId]) -> (Vec<NodeSpec>, NodeId) {
+    let columns = opts
+        .columns
+        .unwrap_or_else(|| (inputs.len() as f64).sqrt().ceil() as usize)
+        .clamp(1, inputs.len());
+    let rows = (inputs.len() + columns - 1) / columns;
+    let tile = Resolution {
+        width: opts.resolution.width / columns,
+        height: opts.resolution.height / rows,
+    };
+
+    let mut nodes = Vec::with_capacity(inputs.len() * 3 + 1);
+    let mut tile_ids = Vec::with_capacity(inputs.len());
+    let mut tile_layouts = Vec::with_capacity(inputs.len());
+    for (index, input_id) in inputs.iter().enumerate() {
+        let tile_nodes = tile_nodes(input_id, tile);
+        tile_ids.push(tile_nodes[2].node_id.clone());
+        nodes.extend(tile_nodes);
+
+        let (column, row) = (index % columns, index / columns);
+        tile_layouts.push(layout(
+            HorizontalPosition::Left(Coord::Pixel((column * tile.width) as i32)),
+            VerticalPosition::Top(Coord::Pixel((row * tile.height) as i32)),
+        ));
+    }
+
+    let root_id = node_id("grid");
+    nodes.push(NodeSpec {
+        node_id: root_id.clone(),
+        input_pads: tile_ids,
+        fallback_id: None,
+        input_fallback_timeout: None,
+        params: NodeParams::Builtin(BuiltinSpec::FixedPositionLayout(FixedPositionLayoutSpec {
+            resolution: opts.resolution,
+            texture_layouts: tile_layouts,
+            background_color_rgba: BACKGROUND_COLOR,
+            mipmaps: false,
+        })),
+    });
+    (nodes, root_id)
+}
+
+/// Input scaled to the tile with its id displayed at the bottom. The last node
+/// is the tile.
+fn tile_nodes(input_id: &InputId, tile: Resolution) -> [NodeSpec; 3] {
+    let font_size = f32::max(12.0, tile.height as f32 / 12.0);
+    let label_height = (font_size * 1.5) as usize;
+
+    let fit_id = node_id(&format!("{input_id}_fit"));
+    let fit = NodeSpec {
+        node_id: fit_id.clone(),
+        input_pads: vec![input_id.0.clone()],
+        fallback_id: None,
+        input_fallback_timeout: None,
+        params: NodeParams::Builtin(BuiltinSpec::FitToResolution(FitToResolutionSpec {
+            resolution: tile,
+            background_color_rgba: BACKGROUND_COLOR,
+            horizontal_alignment: HorizontalAlign::Center,
+            vertical_alignment: VerticalAlign::Center,
+            mipmaps: true,
+            scaling_quality: ScalingQuality::Bilinear,
+        })),
+    };
+
+    let label_id = node_id(&format!("{input_id}_label"));
+    let label = NodeSpec {
+        node_id: label_id.clone(),
+        input_pads: vec![],
+        fallback_id: None,
+        input_fallback_timeout: None,
+        params: NodeParams::Text(TextSpec {
+            content: input_id.to_string().into(),
+            font_size,
+            line_height: Some(label_height as f32),
+            color_rgba: LABEL_COLOR,
+            font_family: String::from("Verdana"),
+            style: Style::Normal,
+            align: HorizontalAlign::Center,
+            weight: Weight::Normal,
+            wrap: Wrap::None,
+            background_color_rgba: LABEL_BACKGROUND_COLOR,
+            dimensions: TextDimensions::Fixed {
+                width: tile.width as u32,
+                height: usize::min(label_height, tile.height) as u32,
+            },
+        }),
+    };
+
+    let tile = NodeSpec {
+        node_id: node_id(&format!("{input_id}_tile")),
+        input_pads: vec![fit_id, label_id],
+        fallback_id: None,
+        input_fallback_timeout: None,
+        params: NodeParams::Builtin(BuiltinSpec::FixedPositionLayout(FixedPositionLayoutSpec {
+            resolution: tile,
+            texture_layouts: vec![
+                layout(
+                    HorizontalPosition::Left(Coord::Pixel(0)),
+                    VerticalPosition::Top(Coord::Pixel(0)),
+                ),
+                layout(
+                    HorizontalPosition::Left(Coord::Pixel(0)),
+                    VerticalPosition::Bottom(Coord::Pixel(0)),
+                ),
+            ],
+            background_color_rgba: BACKGROUND_COLOR,
+            mipmaps: false,
+        })),
+    };
+
+    [fit, label, tile]
+}
+
+fn empty_grid_node(resolution: Resolution) -> NodeSpec {
+    NodeSpec {
+        node_id: node_id("grid"),
+        input_pads: vec![],
+        fallback_id: None,
+        input_fallback_timeout: None,
+        params: NodeParams::Text(TextSpec {
+            content: "No inputs".into(),
+            font_size: 48.0,
+            line_height: Some(resolution.height as f32),
+            color_rgba: LABEL_COLOR,
+            font_family: String::from("Verdana"),
+            style: Style::Normal,
+            align: HorizontalAlign::Center,
+            weight: Weight::Normal,
+            wrap: Wrap::None,
+            background_color_rgba: BACKGROUND_COLOR,
+            dimensions: TextDimensions::Fixed {
+                width: resolution.width as u32,
+                height: resolution.height as u32,
+            },
+        }),
+    }
+}
+
+fn layout(horizontal: HorizontalPosition, vertical: VerticalPosition) -> TextureLayout {
+    TextureLayout {
+        horizontal_position: horizontal,
+        vertical_position: vertical,
+        scale: 1.0,
+        rotation: Degree(0.0),
+    }
+}
+
+fn node_id(name: &str) -> NodeId {
+    NodeId(Arc::from(format!("{NODE_ID_PREFIX}_{name}")))
+}
@@ -0,0 +1,120 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use compositor_pipeline::pipeline::PipelineOutput;
+use ffmpeg_next::{Codec, Packet};
+
+use crate::{
+    file_writer::{self, FileContext, FileWriter},
+    mpegts_sender::{self, MpegTsContext, MpegTsSender},
+    rtp_sender::{self, RtpContext, RtpSender},
+    sdp::StreamDescription,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Output {
+    Rtp(RtpSender),
+    File(FileWriter),
+    MpegTs(MpegTsSender),
+}
+
+pub enum Options {
+    Rtp(rtp_sender::Options),
+    File(file_writer::Options),
+    MpegTs(mpegts_sender::Options),
+}
+
+pub enum Context {
+    Rtp(RtpContext),
+    File(FileContext),
+    MpegTs(MpegTsContext),
+}
+
+impl Output {
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            Output::Rtp(sender) => Some(sender.port),
+            Output::MpegTs(sender) => Some(sender.port),
+            Output::File(_) => None,
+        }
+    }
+
+    pub fn ip(&self) -> Option<&Arc<str>> {
+        match self {
+            Output::Rtp(sender) => Some(&sender.ip),
+            Output::MpegTs(sender) => Some(&sender.ip),
+            Output::File(_) => None,
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Output::Rtp(_) | Output::MpegTs(_) => None,
+            Output::File(writer) => Some(&writer.path),
+        }
+    }
+
+    /// SDP that can be used to receive the stream at the output destination.
+    pub fn sdp(&self) -> Option<String> {
+        match self {
+            Output::Rtp(sender) => Some(
+                StreamDescription {
+                    ip: &sender.ip,
+                    port: sender.port,
+                    fec: sender.fec_overhead_percentage.is_some(),
+                    multicast_ttl: sender.multicast_ttl,
+                    srtp_key: sender.srtp_key.as_deref(),
+                }
+                .sdp(),
+            ),
+            Output::File(_) | Output::MpegTs(_) => None,
+        }
+    }
+}
+
+impl PipelineOutput for Output {
+    type Opts = Options;
+    type Context = Context;
+
+    fn new(
+        opts: Self::Opts,
+        codec: Codec,
+    ) -> Result<(Self, Self::Context), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        match opts {
+            Options::Rtp(opts) => {
+                let (sender, context) = RtpSender::new(opts, codec)?;
+                Ok((Output::Rtp(sender), Context::Rtp(context)))
+            }
+            Options::File(opts) => {
+                let (writer, context) = FileWriter::new(opts, codec)?;
+                Ok((Output::File(writer), Context::File(context)))
+            }
+            Options::MpegTs(opts) => {
+                let (sender, context) = MpegTsSender::new(opts, codec)?;
+                Ok((Output::MpegTs(sender), Context::MpegTs(context)))
+            }
+        }
+    }
+
+    fn send_packet(&self, context: &mut Self::Context, packet: Packet) {
+        match (self, context) {
+            (Output::Rtp(sender), Context::Rtp(context)) => sender.send_packet(context, packet),
+            (Output::File(writer), Context::File(context)) => writer.send_packet(context, packet),
+            (Output::MpegTs(sender), Context::MpegTs(context)) => {
+                sender.send_packet(context, packet)
+            }
+            _ => unreachable!("Output and its context are always created together."),
+        }
+    }
+
+    fn send_scte35(&self, context: &mut Self::Context, section: &[u8], pts: Duration) {
+        match (self, context) {
+            (Output::File(writer), Context::File(context)) => {
+                writer.send_scte35(context, section, pts)
+            }
+            (Output::MpegTs(sender), Context::MpegTs(context)) => {
+                sender.send_scte35(context, section, pts)
+            }
+            _ => {}
+        }
+    }
+}
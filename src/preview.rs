@@ -0,0 +1,142 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use bytes::Bytes;
+use compositor_common::scene::{OutputId, Resolution};
+use compositor_render::{error::SnapshotError, renderer::SnapshotTarget, Renderer};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use log::{error, warn};
+
+use crate::api::{encode_snapshot, SnapshotFormat};
+
+pub struct PreviewOptions {
+    pub interval: Duration,
+    /// Height is derived from the output resolution to keep the aspect ratio.
+    pub width: usize,
+    /// Latest preview is written to "<directory>/<output_id>.jpg" if specified.
+    pub directory: Option<PathBuf>,
+}
+
+/// Downscaled JPEG frames of outputs, refreshed periodically by a thread per
+/// output. They are cheap to fetch, so monitoring pages don't need to decode
+/// the full streams.
+#[derive(Default)]
+pub struct Previews {
+    images: Arc<Mutex<HashMap<OutputId, Bytes>>>,
+    /// Dropping the sender stops the thread of the output.
+    workers: HashMap<OutputId, Sender<()>>,
+}
+
+impl Previews {
+    pub fn start(
+        &mut self,
+        renderer: Renderer,
+        output_id: OutputId,
+        output_resolution: Resolution,
+        opts: PreviewOptions,
+    ) {
+        self.stop(&output_id);
+
+        let (stop_sender, stop_receiver) = bounded(0);
+        let worker = PreviewWorker {
+            renderer,
+            output_id: output_id.clone(),
+            resolution: preview_resolution(output_resolution, opts.width),
+            directory: opts.directory,
+            images: self.images.clone(),
+            stop_receiver,
+        };
+        let interval = opts.interval;
+        thread::Builder::new()
+            .name(format!("preview thread for output {output_id}"))
+            .spawn(move || worker.run(interval))
+            .unwrap();
+        self.workers.insert(output_id, stop_sender);
+    }
+
+    pub fn stop(&mut self, output_id: &OutputId) {
+        // Worker checks if it was stopped while holding the lock, so the image can't
+        // be inserted again after it's removed here.
+        self.workers.remove(output_id);
+        self.images.lock().unwrap().remove(output_id);
+    }
+
+    /// Latest preview of the output encoded as JPEG.
+    pub fn get(&self, output_id: &OutputId) -> Option<Bytes> {
+        self.images.lock().unwrap().get(output_id).cloned()
+    }
+}
+
+struct PreviewWorker {
+    renderer: Renderer,
+    output_id: OutputId,
+    resolution: Resolution,
+    directory: Option<PathBuf>,
+    images: Arc<Mutex<HashMap<OutputId, Bytes>>>,
+    stop_receiver: Receiver<()>,
+}
+
+impl PreviewWorker {
+    fn run(self, interval: Duration) {
+        loop {
+            match self.stop_receiver.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+            }
+            let Some(image) = self.render() else {
+                continue;
+            };
+
+            if let Some(directory) = &self.directory {
+                let path = directory.join(format!("{}.jpg", self.output_id));
+                if let Err(err) = fs::write(&path, &image) {
+                    warn!("Failed to write preview to {path:?}: {err}");
+                }
+            }
+
+            let mut images = self.images.lock().unwrap();
+            if let Err(TryRecvError::Disconnected) = self.stop_receiver.try_recv() {
+                return;
+            }
+            images.insert(self.output_id.clone(), image);
+        }
+    }
+
+    fn render(&self) -> Option<Bytes> {
+        let target = SnapshotTarget::Output(self.output_id.clone());
+        let snapshot = match self.renderer.snapshot(&target, Some(self.resolution)) {
+            Ok(snapshot) => snapshot,
+            // Output is not used in the scene yet or nothing was rendered
+            Err(SnapshotError::OutputNotFound(_) | SnapshotError::NoFrame) => return None,
+            Err(err) => {
+                error!(
+                    "Failed to render preview of output \"{}\": {err}",
+                    self.output_id
+                );
+                return None;
+            }
+        };
+        match encode_snapshot(snapshot, SnapshotFormat::Jpeg) {
+            Ok(image) => Some(image.into()),
+            Err(err) => {
+                error!("{}", err.message);
+                None
+            }
+        }
+    }
+}
+
+fn preview_resolution(output_resolution: Resolution, width: usize) -> Resolution {
+    let width = usize::clamp(width, 1, output_resolution.width);
+    let height = output_resolution.height * width / output_resolution.width;
+    Resolution {
+        width,
+        height: height.max(1),
+    }
+}
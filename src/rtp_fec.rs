@@ -0,0 +1,381 @@
+use std::collections::{HashMap, VecDeque};
+
+use log::warn;
+
+/// Payload type of ULPFEC (RFC 5109) packets. FEC packets are sent on the same port
+/// as the media, with their own SSRC and sequence numbers.
+pub const ULPFEC_PAYLOAD_TYPE: u8 = 117;
+
+/// Max size of RTP packets produced by the muxer when FEC is enabled. FEC packet has
+/// to fit the same UDP payload size (1472 bytes) as the largest media packet it protects.
+pub const MAX_PROTECTED_PACKET_SIZE: usize = 1472 - FEC_HEADER_LEN - LONG_ULP_HEADER_LEN;
+
+const RTP_HEADER_LEN: usize = 12;
+const FEC_HEADER_LEN: usize = 10;
+const SHORT_ULP_HEADER_LEN: usize = 4;
+const LONG_ULP_HEADER_LEN: usize = 8;
+
+/// Max number of media packets protected by one FEC packet (size of the long mask).
+const MAX_GROUP_SIZE: usize = 48;
+const MEDIA_PACKETS_HISTORY: usize = 1024;
+const FEC_PACKETS_HISTORY: usize = 64;
+
+/// Generates ULPFEC packets with level 0 protection. Each FEC packet protects a group
+/// of consecutive media packets, so a single lost packet in a group can be recovered.
+pub struct FecEncoder {
+    group_size: usize,
+    group: Vec<Vec<u8>>,
+    ssrc: u32,
+    sequence_number: u16,
+}
+
+impl FecEncoder {
+    /// `overhead_percentage` is a ratio of FEC packets to media packets (3-100).
+    pub fn new(overhead_percentage: u8) -> Self {
+        let overhead_percentage = overhead_percentage.max(1) as usize;
+        let group_size = (100 + overhead_percentage / 2) / overhead_percentage;
+        Self {
+            group_size: group_size.clamp(1, MAX_GROUP_SIZE),
+            group: Vec::with_capacity(group_size),
+            ssrc: 0,
+            sequence_number: 0,
+        }
+    }
+
+    /// Adds media packet to the current group. Returns FEC packet if the group is complete.
+    pub fn push(&mut self, packet: &[u8]) -> Option<Vec<u8>> {
+        if packet.len() < RTP_HEADER_LEN {
+            return None;
+        }
+        if let Some(last_packet) = self.group.last() {
+            if sequence_number(packet) != sequence_number(last_packet).wrapping_add(1) {
+                self.group.clear();
+            }
+        }
+        if self.group.is_empty() {
+            // FEC stream needs a different SSRC than the media stream it protects.
+            self.ssrc = !ssrc(packet);
+        }
+        self.group.push(packet.to_vec());
+        if self.group.len() < self.group_size {
+            return None;
+        }
+
+        let fec_packet = self.fec_packet();
+        self.group.clear();
+        self.sequence_number = self.sequence_number.wrapping_add(1);
+        Some(fec_packet)
+    }
+
+    fn fec_packet(&self) -> Vec<u8> {
+        let long_mask = self.group.len() > 16;
+        let protection_len = self
+            .group
+            .iter()
+            .map(|packet| packet.len() - RTP_HEADER_LEN)
+            .max()
+            .unwrap_or(0);
+
+        let mut recovery = RecoveryFields::default();
+        let mut payload = vec![0; protection_len];
+        for packet in self.group.iter() {
+            recovery.xor_packet(packet);
+            xor(&mut payload, &packet[RTP_HEADER_LEN..]);
+        }
+
+        let last_packet = self.group.last().unwrap();
+        let mut fec_packet = Vec::with_capacity(
+            RTP_HEADER_LEN + FEC_HEADER_LEN + LONG_ULP_HEADER_LEN + protection_len,
+        );
+        fec_packet.extend_from_slice(&[0x80, ULPFEC_PAYLOAD_TYPE]);
+        fec_packet.extend_from_slice(&self.sequence_number.to_be_bytes());
+        fec_packet.extend_from_slice(&last_packet[4..8]);
+        fec_packet.extend_from_slice(&self.ssrc.to_be_bytes());
+
+        fec_packet.push((recovery.first_byte & 0x3f) | ((long_mask as u8) << 6));
+        fec_packet.push(recovery.second_byte);
+        fec_packet.extend_from_slice(&sequence_number(&self.group[0]).to_be_bytes());
+        fec_packet.extend_from_slice(&recovery.timestamp.to_be_bytes());
+        fec_packet.extend_from_slice(&recovery.length.to_be_bytes());
+
+        fec_packet.extend_from_slice(&(protection_len as u16).to_be_bytes());
+        let mask = u64::MAX << (64 - self.group.len());
+        match long_mask {
+            true => fec_packet.extend_from_slice(&mask.to_be_bytes()[0..6]),
+            false => fec_packet.extend_from_slice(&mask.to_be_bytes()[0..2]),
+        }
+
+        fec_packet.extend_from_slice(&payload);
+        fec_packet
+    }
+}
+
+/// Recovers lost media packets from ULPFEC packets generated by [`FecEncoder`].
+#[derive(Default)]
+pub struct FecDecoder {
+    media_packets: HashMap<u16, Vec<u8>>,
+    media_packets_order: VecDeque<u16>,
+    fec_packets: VecDeque<FecPacket>,
+    media_ssrc: Option<u32>,
+}
+
+struct FecPacket {
+    sequence_number_base: u16,
+    mask: u64,
+    recovery: RecoveryFields,
+    payload: Vec<u8>,
+}
+
+impl FecDecoder {
+    /// Returns media packets that should be passed to the depayloader: the packet itself
+    /// if it's a media packet and any packets recovered thanks to it.
+    pub fn push(&mut self, packet: &[u8]) -> Vec<Vec<u8>> {
+        if packet.len() < RTP_HEADER_LEN {
+            return vec![];
+        }
+        if packet[1] & 0x7f == ULPFEC_PAYLOAD_TYPE {
+            match FecPacket::parse(packet) {
+                Some(fec_packet) => {
+                    if self.fec_packets.len() == FEC_PACKETS_HISTORY {
+                        self.fec_packets.pop_front();
+                    }
+                    self.fec_packets.push_back(fec_packet);
+                }
+                None => warn!("Received malformed FEC packet, skipping"),
+            }
+            return self.recover();
+        }
+
+        self.media_ssrc = Some(ssrc(packet));
+        self.insert_media_packet(packet.to_vec());
+        let mut packets = vec![packet.to_vec()];
+        packets.extend(self.recover());
+        packets
+    }
+
+    fn insert_media_packet(&mut self, packet: Vec<u8>) {
+        let sequence_number = sequence_number(&packet);
+        if self.media_packets.insert(sequence_number, packet).is_some() {
+            return;
+        }
+        self.media_packets_order.push_back(sequence_number);
+        if self.media_packets_order.len() > MEDIA_PACKETS_HISTORY {
+            if let Some(oldest) = self.media_packets_order.pop_front() {
+                self.media_packets.remove(&oldest);
+            }
+        }
+    }
+
+    fn recover(&mut self) -> Vec<Vec<u8>> {
+        let mut recovered_packets = vec![];
+        // Recovered packet can complete a group protected by a different FEC packet,
+        // so the search is repeated until nothing new is recovered.
+        loop {
+            let mut recovered = None;
+            self.fec_packets.retain(|fec_packet| {
+                if recovered.is_some() {
+                    return true;
+                }
+                let missing: Vec<u16> = fec_packet
+                    .protected_sequence_numbers()
+                    .filter(|sequence_number| !self.media_packets.contains_key(sequence_number))
+                    .collect();
+                match missing.as_slice() {
+                    [] => false,
+                    [missing] => {
+                        recovered = fec_packet.recover(
+                            *missing,
+                            &self.media_packets,
+                            self.media_ssrc.unwrap_or_default(),
+                        );
+                        false
+                    }
+                    _ => true,
+                }
+            });
+            match recovered {
+                Some(packet) => {
+                    self.insert_media_packet(packet.clone());
+                    recovered_packets.push(packet);
+                }
+                None => return recovered_packets,
+            }
+        }
+    }
+}
+
+impl FecPacket {
+    fn parse(packet: &[u8]) -> Option<Self> {
+        let header_len = rtp_header_len(packet)?;
+        let fec_header = packet.get(header_len..header_len + FEC_HEADER_LEN)?;
+        let long_mask = fec_header[0] & 0x40 != 0;
+        let ulp_header_len = match long_mask {
+            true => LONG_ULP_HEADER_LEN,
+            false => SHORT_ULP_HEADER_LEN,
+        };
+        let ulp_header_start = header_len + FEC_HEADER_LEN;
+        let ulp_header = packet.get(ulp_header_start..ulp_header_start + ulp_header_len)?;
+
+        let protection_len = u16::from_be_bytes([ulp_header[0], ulp_header[1]]) as usize;
+        let mut mask_bytes = [0; 8];
+        mask_bytes[..ulp_header_len - 2].copy_from_slice(&ulp_header[2..]);
+        let payload_start = ulp_header_start + ulp_header_len;
+        let payload = packet.get(payload_start..payload_start + protection_len)?;
+
+        Some(Self {
+            sequence_number_base: u16::from_be_bytes([fec_header[2], fec_header[3]]),
+            mask: u64::from_be_bytes(mask_bytes),
+            recovery: RecoveryFields {
+                first_byte: fec_header[0],
+                second_byte: fec_header[1],
+                timestamp: u32::from_be_bytes(fec_header[4..8].try_into().unwrap()),
+                length: u16::from_be_bytes([fec_header[8], fec_header[9]]),
+            },
+            payload: payload.to_vec(),
+        })
+    }
+
+    fn protected_sequence_numbers(&self) -> impl Iterator<Item = u16> + '_ {
+        (0..MAX_GROUP_SIZE as u16)
+            .filter(|offset| self.mask & (1 << (63 - offset)) != 0)
+            .map(|offset| self.sequence_number_base.wrapping_add(offset))
+    }
+
+    fn recover(
+        &self,
+        missing: u16,
+        media_packets: &HashMap<u16, Vec<u8>>,
+        ssrc: u32,
+    ) -> Option<Vec<u8>> {
+        let mut recovery = self.recovery.clone();
+        let mut payload = self.payload.clone();
+        for sequence_number in self.protected_sequence_numbers() {
+            if let Some(packet) = media_packets.get(&sequence_number) {
+                recovery.xor_packet(packet);
+                xor(&mut payload, &packet[RTP_HEADER_LEN..]);
+            }
+        }
+        let length = recovery.length as usize;
+        if length > payload.len() {
+            warn!("Failed to recover RTP packet, FEC packet does not match the media stream");
+            return None;
+        }
+
+        let mut packet = Vec::with_capacity(RTP_HEADER_LEN + length);
+        packet.push(0x80 | (recovery.first_byte & 0x3f));
+        packet.push(recovery.second_byte);
+        packet.extend_from_slice(&missing.to_be_bytes());
+        packet.extend_from_slice(&recovery.timestamp.to_be_bytes());
+        packet.extend_from_slice(&ssrc.to_be_bytes());
+        packet.extend_from_slice(&payload[..length]);
+        Some(packet)
+    }
+}
+
+/// Fields of the RTP header (and payload length) protected by the FEC packet.
+#[derive(Debug, Clone, Default)]
+struct RecoveryFields {
+    first_byte: u8,
+    second_byte: u8,
+    timestamp: u32,
+    length: u16,
+}
+
+impl RecoveryFields {
+    fn xor_packet(&mut self, packet: &[u8]) {
+        self.first_byte ^= packet[0];
+        self.second_byte ^= packet[1];
+        self.timestamp ^= u32::from_be_bytes(packet[4..8].try_into().unwrap());
+        self.length ^= (packet.len() - RTP_HEADER_LEN) as u16;
+    }
+}
+
+fn rtp_header_len(packet: &[u8]) -> Option<usize> {
+    let csrc_count = (*packet.first()? & 0x0f) as usize;
+    let mut header_len = RTP_HEADER_LEN + 4 * csrc_count;
+    if packet[0] & 0x10 != 0 {
+        let extension_header = packet.get(header_len..header_len + 4)?;
+        let extension_len = u16::from_be_bytes([extension_header[2], extension_header[3]]);
+        header_len += 4 + 4 * extension_len as usize;
+    }
+    (header_len <= packet.len()).then_some(header_len)
+}
+
+fn sequence_number(packet: &[u8]) -> u16 {
+    u16::from_be_bytes([packet[2], packet[3]])
+}
+
+fn ssrc(packet: &[u8]) -> u32 {
+    u32::from_be_bytes(packet[8..12].try_into().unwrap())
+}
+
+fn xor(target: &mut [u8], source: &[u8]) {
+    target
+        .iter_mut()
+        .zip(source)
+        .for_each(|(target, source)| *target ^= source);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn media_packet(sequence_number: u16, payload_len: usize) -> Vec<u8> {
+        let mut packet = vec![0x80, 96 | if payload_len % 2 == 0 { 0x80 } else { 0 }];
+        packet.extend_from_slice(&sequence_number.to_be_bytes());
+        packet.extend_from_slice(&(sequence_number as u32 * 3000).to_be_bytes());
+        packet.extend_from_slice(&0x1234_5678u32.to_be_bytes());
+        packet.extend((0..payload_len).map(|i| (i as u8).wrapping_mul(sequence_number as u8)));
+        packet
+    }
+
+    fn test_recovery(overhead_percentage: u8, first_sequence_number: u16, lost: u16) {
+        let mut encoder = FecEncoder::new(overhead_percentage);
+        let mut decoder = FecDecoder::default();
+        let packets: Vec<_> = (0..encoder.group_size as u16)
+            .map(|i| {
+                let sequence_number = first_sequence_number.wrapping_add(i);
+                media_packet(sequence_number, 100 + 37 * i as usize)
+            })
+            .collect();
+
+        let mut received = vec![];
+        let mut fec_packet = None;
+        for (i, packet) in packets.iter().enumerate() {
+            fec_packet = encoder.push(packet);
+            if i as u16 != lost {
+                received.extend(decoder.push(packet));
+            }
+        }
+        received.extend(decoder.push(&fec_packet.unwrap()));
+
+        assert_eq!(received.len(), packets.len());
+        assert_eq!(received.last(), Some(&packets[lost as usize]));
+    }
+
+    #[test]
+    fn recovers_lost_packet() {
+        test_recovery(10, 0, 3);
+        test_recovery(10, 65530, 9);
+        test_recovery(50, 42, 0);
+        test_recovery(3, 1000, 20);
+    }
+
+    #[test]
+    fn does_not_recover_two_lost_packets() {
+        let mut encoder = FecEncoder::new(25);
+        let mut decoder = FecDecoder::default();
+        let mut received = vec![];
+        for sequence_number in 0..4 {
+            let packet = media_packet(sequence_number, 200);
+            let fec_packet = encoder.push(&packet);
+            if sequence_number >= 2 {
+                received.extend(decoder.push(&packet));
+            }
+            if let Some(fec_packet) = fec_packet {
+                received.extend(decoder.push(&fec_packet));
+            }
+        }
+        assert_eq!(received.len(), 2);
+    }
+}
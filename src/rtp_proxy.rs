@@ -0,0 +1,180 @@
+use std::{
+    io,
+    net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket},
+    thread,
+    time::Duration,
+};
+
+use crossbeam_channel::{bounded, Receiver, Sender};
+use log::{debug, error};
+
+use crate::{
+    rtp_fec::{FecDecoder, FecEncoder},
+    rtp_sender::MulticastOptions,
+};
+
+const SOCKET_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Relays RTP packets produced by ffmpeg to the destination and adds FEC packets
+/// to the stream. ffmpeg sends packets to `local_port` instead of the destination.
+pub struct SenderProxy {
+    pub local_port: u16,
+    should_close: Sender<()>,
+}
+
+impl SenderProxy {
+    pub fn new(
+        ip: &str,
+        port: u16,
+        fec_overhead_percentage: u8,
+        multicast: Option<&MulticastOptions>,
+    ) -> io::Result<Self> {
+        let destination = (ip, port).to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("Failed to resolve {ip}"))
+        })?;
+        let local_socket = UdpSocket::bind(("127.0.0.1", 0))?;
+        local_socket.set_read_timeout(Some(SOCKET_READ_TIMEOUT))?;
+        // Multicast interface is selected by binding to its address, std does not
+        // expose IP_MULTICAST_IF.
+        let interface = multicast.and_then(|multicast| multicast.interface.as_deref());
+        let remote_socket = match (destination, interface) {
+            (_, Some(interface)) => UdpSocket::bind((interface, 0))?,
+            (SocketAddr::V4(_), None) => UdpSocket::bind(("0.0.0.0", 0))?,
+            (SocketAddr::V6(_), None) => UdpSocket::bind(("::", 0))?,
+        };
+        if let (Some(multicast), SocketAddr::V4(_)) = (multicast, destination) {
+            remote_socket.set_multicast_ttl_v4(multicast.ttl as u32)?;
+        }
+        let local_port = local_socket.local_addr()?.port();
+
+        let (should_close_sender, should_close_receiver) = bounded(1);
+        thread::spawn(move || {
+            let mut encoder = FecEncoder::new(fec_overhead_percentage);
+            run_proxy(local_socket, should_close_receiver, |packet, _| {
+                send(&remote_socket, packet, destination);
+                if is_rtcp(packet) {
+                    return;
+                }
+                if let Some(fec_packet) = encoder.push(packet) {
+                    send(&remote_socket, &fec_packet, destination);
+                }
+            })
+        });
+
+        Ok(Self {
+            local_port,
+            should_close: should_close_sender,
+        })
+    }
+}
+
+impl Drop for SenderProxy {
+    fn drop(&mut self) {
+        let _ = self.should_close.send(());
+    }
+}
+
+/// Source of packets that is allowed to send to the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllowedSource {
+    /// Any port on that host.
+    Ip(IpAddr),
+    Address(SocketAddr),
+}
+
+impl AllowedSource {
+    fn matches(&self, source: &SocketAddr) -> bool {
+        match self {
+            AllowedSource::Ip(ip) => source.ip() == *ip,
+            AllowedSource::Address(address) => source == address,
+        }
+    }
+}
+
+/// Receives RTP stream on `port` and passes it to ffmpeg listening on `local_port`.
+/// Packets from sources that are not allowed are dropped. With FEC enabled, lost
+/// packets are recovered and FEC packets are not passed further.
+pub struct ReceiverProxy {
+    pub local_port: u16,
+    should_close: Sender<()>,
+}
+
+impl ReceiverProxy {
+    pub fn new(port: u16, fec: bool, allowed_sources: Vec<AllowedSource>) -> io::Result<Self> {
+        let remote_socket = UdpSocket::bind(("0.0.0.0", port))?;
+        remote_socket.set_read_timeout(Some(SOCKET_READ_TIMEOUT))?;
+        let local_socket = UdpSocket::bind(("127.0.0.1", 0))?;
+        // ffmpeg binds the port on its own, so it's only possible to find a free port
+        // and release it before the input is opened.
+        let local_port = UdpSocket::bind(("127.0.0.1", 0))?.local_addr()?.port();
+        let destination = SocketAddr::from(([127, 0, 0, 1], local_port));
+
+        let (should_close_sender, should_close_receiver) = bounded(1);
+        thread::spawn(move || {
+            let mut fec_decoder = fec.then(FecDecoder::default);
+            run_proxy(remote_socket, should_close_receiver, |packet, source| {
+                let is_allowed = allowed_sources.is_empty()
+                    || allowed_sources
+                        .iter()
+                        .any(|allowed_source| allowed_source.matches(&source));
+                if !is_allowed {
+                    debug!("Dropped RTP packet from {source}, source is not allowed");
+                    return;
+                }
+                match (&mut fec_decoder, is_rtcp(packet)) {
+                    (Some(fec_decoder), false) => {
+                        for packet in fec_decoder.push(packet) {
+                            send(&local_socket, &packet, destination);
+                        }
+                    }
+                    _ => send(&local_socket, packet, destination),
+                }
+            })
+        });
+
+        Ok(Self {
+            local_port,
+            should_close: should_close_sender,
+        })
+    }
+}
+
+impl Drop for ReceiverProxy {
+    fn drop(&mut self) {
+        let _ = self.should_close.send(());
+    }
+}
+
+fn run_proxy<F: FnMut(&[u8], SocketAddr)>(
+    socket: UdpSocket,
+    should_close: Receiver<()>,
+    mut on_packet: F,
+) {
+    let mut buffer = [0; 65536];
+    while should_close.try_recv().is_err() {
+        match socket.recv_from(&mut buffer) {
+            Ok((len, source)) => on_packet(&buffer[..len], source),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(err) => {
+                error!("Failed to receive RTP packet: {err}");
+                return;
+            }
+        }
+    }
+}
+
+fn send(socket: &UdpSocket, packet: &[u8], destination: SocketAddr) {
+    if let Err(err) = socket.send_to(packet, destination) {
+        error!("Failed to send RTP packet: {err}")
+    }
+}
+
+/// RTCP packet types (192-223) overlap with marker bit + payload type of RTP packets,
+/// which is how RTP and RTCP multiplexed on the same port are told apart (RFC 5761).
+fn is_rtcp(packet: &[u8]) -> bool {
+    packet.len() >= 2 && (192..=223).contains(&packet[1])
+}
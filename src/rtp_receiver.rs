@@ -1,20 +1,28 @@
 use anyhow::Result;
 use compositor_common::scene::InputId;
-use compositor_pipeline::pipeline::{decoder::DecoderParameters, PipelineInput};
+use compositor_pipeline::pipeline::{
+    decoder::{DecoderParameters, SenderClock},
+    PipelineInput,
+};
 use crossbeam_channel::{bounded, Receiver, Sender};
 use log::warn;
 use std::{
     ffi::CString,
-    fs::File,
+    fs::OpenOptions,
     io::Write,
-    path::{Path, PathBuf},
-    ptr, thread,
+    os::unix::fs::OpenOptionsExt,
+    path::Path,
+    ptr,
+    sync::Arc,
+    thread,
+    time::{Duration, SystemTime},
 };
 
 use ffmpeg_next::{
     ffi::{
-        avformat_alloc_context, avformat_close_input, avformat_find_stream_info,
-        avformat_open_input,
+        av_find_input_format, avformat_alloc_context, avformat_close_input,
+        avformat_find_stream_info, avformat_free_context, avformat_open_input, AVInputFormat,
+        AV_NOPTS_VALUE,
     },
     format::context,
     media::Type,
@@ -22,55 +30,99 @@ use ffmpeg_next::{
     Dictionary, Packet,
 };
 
+use crate::{
+    rtp_proxy::{AllowedSource, ReceiverProxy},
+    sdp::StreamDescription,
+};
+
 pub struct RtpReceiver {
     thread_finished: Receiver<()>,
     should_close: Sender<()>,
     decoder_parameters: DecoderParameters,
+    sender_clock: SenderClock,
+    proxy: Option<ReceiverProxy>,
     pub(crate) port: u16,
+    pub(crate) fec: bool,
+    pub(crate) srtp_key: Option<Arc<str>>,
 }
 
 pub struct Options {
     pub port: u16,
     pub input_id: InputId,
+    /// Recover lost packets using ULPFEC packets sent alongside the stream.
+    pub fec: bool,
+    /// Packets from other sources are dropped. All sources are allowed if empty.
+    pub allowed_sources: Vec<AllowedSource>,
+    /// Base64 encoded SRTP master key and salt (AES_CM_128_HMAC_SHA1_80).
+    pub srtp_key: Option<Arc<str>>,
 }
 
 impl PipelineInput for RtpReceiver {
     type Opts = Options;
     type PacketIterator = crossbeam_channel::IntoIter<Packet>;
 
-    fn new(opts: Self::Opts) -> (Self, Self::PacketIterator) {
+    fn new(
+        opts: Self::Opts,
+    ) -> Result<(Self, Self::PacketIterator), Box<dyn std::error::Error + Send + Sync + 'static>>
+    {
         let (drop_sender, drop_receiver) = bounded(0);
         let (should_close_sender, should_close_receiver) = bounded(1);
         let (decoder_params_sender, decoder_params_receiver) = bounded(0);
 
         let port = opts.port;
         let (packet_sender, packet_receiver) = bounded(0);
+        let sender_clock = SenderClock::default();
+
+        // FEC and source filtering are handled by a proxy that receives the stream
+        // and passes it to ffmpeg on a local port.
+        let proxy = match opts.fec || !opts.allowed_sources.is_empty() {
+            true => Some(ReceiverProxy::new(
+                port,
+                opts.fec,
+                opts.allowed_sources.clone(),
+            )?),
+            false => None,
+        };
+        let ffmpeg_port = proxy.as_ref().map(|proxy| proxy.local_port).unwrap_or(port);
 
+        let fec = opts.fec;
+        let srtp_key = opts.srtp_key.clone();
+        let thread_sender_clock = sender_clock.clone();
         thread::spawn(move || {
             RtpReceiver::start(
-                opts.port,
+                ffmpeg_port,
+                opts.srtp_key.as_deref(),
                 should_close_receiver,
                 packet_sender,
                 decoder_params_sender,
+                thread_sender_clock,
             )
             .unwrap();
             drop_sender.send(())
         });
 
-        (
+        Ok((
             Self {
                 thread_finished: drop_receiver,
                 should_close: should_close_sender,
                 decoder_parameters: decoder_params_receiver.recv().unwrap(),
+                sender_clock,
+                proxy,
                 port,
+                fec,
+                srtp_key,
             },
             packet_receiver.into_iter(),
-        )
+        ))
     }
 
     fn decoder_parameters(&self) -> DecoderParameters {
         self.decoder_parameters
     }
+
+    fn sender_clock(&self) -> Option<SenderClock> {
+        Some(self.sender_clock.clone())
+    }
 }
 
 impl Drop for RtpReceiver {
@@ -97,33 +149,40 @@ impl From<ParamsWrapper> for DecoderParameters {
 impl RtpReceiver {
     fn start(
         port: u16,
+        srtp_key: Option<&str>,
         should_close: Receiver<()>,
         packet_sender: Sender<Packet>,
         decoder_params_sender: Sender<DecoderParameters>,
+        sender_clock: SenderClock,
     ) -> Result<()> {
-        let sdp_filepath = PathBuf::from(format!("/tmp/sdp_input_{}.sdp", port));
-        let mut file = File::create(&sdp_filepath)?;
-        file.write_all(
-            format!(
-                "\
-                    v=0\n\
-                    o=- 0 0 IN IP4 127.0.0.1\n\
-                    s=No Name\n\
-                    c=IN IP4 127.0.0.1\n\
-                    m=video {} RTP/AVP 96\n\
-                    a=rtpmap:96 H264/90000\n\
-                    a=fmtp:96 packetization-mode=1\n\
-                    a=rtcp-mux\n\
-                ",
-                port
-            )
-            .as_bytes(),
-        )?;
+        // SDP may contain the SRTP key, so it's only readable by this process
+        // and removed together with the directory once the input is opened.
+        let sdp_dir = tempfile::Builder::new().prefix("sdp_input_").tempdir()?;
+        let sdp_filepath = sdp_dir.path().join(format!("input_{port}.sdp"));
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&sdp_filepath)?;
+        // FEC packets are handled by the proxy, so ffmpeg only receives the media stream.
+        let sdp = StreamDescription {
+            ip: "127.0.0.1",
+            port,
+            fec: false,
+            multicast_ttl: None,
+            srtp_key,
+        }
+        .sdp();
+        file.write_all(sdp.as_bytes())?;
+        drop(file);
         let input_ctx = input_with_dictionary_and_interrupt(
             &sdp_filepath,
-            Dictionary::from_iter([("protocol_whitelist", "file,udp,rtp")]),
+            None,
+            Dictionary::from_iter([("protocol_whitelist", "file,udp,rtp,srtp")]),
             || should_close.try_recv().is_ok(),
-        )?;
+        );
+        sdp_dir.close()?;
+        let input_ctx = input_ctx?;
 
         let input = input_ctx
             .streams()
@@ -135,7 +194,13 @@ impl RtpReceiver {
             .send(ParamsWrapper(input.parameters()).into())
             .unwrap();
 
-        for packet in PacketIter::new(input_ctx, input_index) {
+        let mut packets = PacketIter::new(input_ctx, input_index);
+        while let Some(packet) = packets.next() {
+            if sender_clock.get().is_none() {
+                if let Some(pts_zero_time) = packets.start_time_realtime() {
+                    sender_clock.set(pts_zero_time);
+                }
+            }
             packet_sender.send(packet).unwrap();
         }
 
@@ -145,9 +210,11 @@ impl RtpReceiver {
 
 /// Combined implementation of ffmpeg_next::format:input_with_interrupt and
 /// ffmpeg_next::format::input_with_dictionary that allows passing both interrupt
-/// callback and Dictionary with options
+/// callback and Dictionary with options. `format` can be used to force a specific
+/// demuxer (e.g. "video4linux2" for capture devices) instead of probing the input.
 pub fn input_with_dictionary_and_interrupt<P, F>(
     path: &P,
+    format: Option<&str>,
     options: Dictionary,
     closure: F,
 ) -> Result<context::Input, ffmpeg_next::Error>
@@ -163,9 +230,22 @@ where
 
         (*ps).interrupt_callback = interrupt::new(Box::new(closure)).interrupt;
 
+        let format = match format {
+            Some(format) => {
+                let format = CString::new(format).unwrap();
+                let format = av_find_input_format(format.as_ptr()) as *const AVInputFormat;
+                if format.is_null() {
+                    avformat_free_context(ps);
+                    return Err(ffmpeg_next::Error::DemuxerNotFound);
+                }
+                format
+            }
+            None => ptr::null(),
+        };
+
         let path = from_path(path);
         let mut opts = options.disown();
-        let res = avformat_open_input(&mut ps, path.as_ptr(), ptr::null_mut(), &mut opts);
+        let res = avformat_open_input(&mut ps, path.as_ptr(), format as *mut _, &mut opts);
 
         Dictionary::own(opts);
 
@@ -198,6 +278,25 @@ impl PacketIter {
             stream_index,
         }
     }
+
+    pub fn context_mut(&mut self) -> &mut context::Input {
+        &mut self.context
+    }
+
+    /// Wall clock time of the packet with PTS=0. RTP demuxer sets it based on
+    /// the first RTCP sender report, so it's not known until the report arrives.
+    pub fn start_time_realtime(&self) -> Option<SystemTime> {
+        let micros = unsafe { (*self.context.as_ptr()).start_time_realtime };
+        if micros == AV_NOPTS_VALUE {
+            return None;
+        }
+        let since_epoch = Duration::from_micros(micros.unsigned_abs());
+        if micros >= 0 {
+            SystemTime::UNIX_EPOCH.checked_add(since_epoch)
+        } else {
+            SystemTime::UNIX_EPOCH.checked_sub(since_epoch)
+        }
+    }
 }
 
 impl Iterator for PacketIter {
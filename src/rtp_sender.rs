@@ -5,21 +5,45 @@ use compositor_pipeline::pipeline::PipelineOutput;
 use ffmpeg_next::{
     codec,
     format::{self, context::Output},
-    Codec, Packet,
+    Codec, Dictionary, Packet,
 };
 
+use crate::{rtp_fec, rtp_proxy::SenderProxy, sdp};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RtpSender {
     pub(crate) port: u16,
     pub(crate) ip: Arc<str>,
+    pub(crate) fec_overhead_percentage: Option<u8>,
+    pub(crate) multicast_ttl: Option<u8>,
+    pub(crate) srtp_key: Option<Arc<str>>,
 }
 
-pub struct RtpContext(Output);
+pub struct RtpContext {
+    output_ctx: Output,
+    _proxy: Option<SenderProxy>,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Options {
     pub port: u16,
     pub ip: Arc<str>,
+    /// Ratio of ULPFEC packets to media packets in percents. FEC is disabled if not set.
+    pub fec_overhead_percentage: Option<u8>,
+    /// Only set if `ip` is a multicast group address.
+    pub multicast: Option<MulticastOptions>,
+    /// Base64 encoded SRTP master key and salt (AES_CM_128_HMAC_SHA1_80). Stream is
+    /// not encrypted if not set.
+    pub srtp_key: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MulticastOptions {
+    /// Number of routers the packets can pass through.
+    pub ttl: u8,
+    /// Address of the local network interface used to send packets. Interface is
+    /// picked based on the routing table if not set.
+    pub interface: Option<Arc<str>>,
 }
 
 impl PipelineOutput for RtpSender {
@@ -33,13 +57,49 @@ impl PipelineOutput for RtpSender {
         let port = options.port;
         let ip = options.ip.clone();
 
-        let mut output_ctx = format::output_as(
-            &PathBuf::from(format!(
-                "rtp://{}:{}?rtcpport={}",
-                options.ip, options.port, options.port
-            )),
-            "rtp",
-        )?;
+        // With FEC enabled, ffmpeg sends packets to the local proxy that forwards
+        // them to the destination together with generated FEC packets.
+        let scheme = match options.srtp_key {
+            Some(_) => "srtp",
+            None => "rtp",
+        };
+        let (url, proxy) = match options.fec_overhead_percentage {
+            Some(overhead_percentage) => {
+                let proxy = SenderProxy::new(
+                    &options.ip,
+                    options.port,
+                    overhead_percentage,
+                    options.multicast.as_ref(),
+                )?;
+                let url = format!(
+                    "{scheme}://127.0.0.1:{}?rtcpport={}&pkt_size={}",
+                    proxy.local_port,
+                    proxy.local_port,
+                    rtp_fec::MAX_PROTECTED_PACKET_SIZE
+                );
+                (url, Some(proxy))
+            }
+            None => {
+                let mut url = format!(
+                    "{scheme}://{}:{}?rtcpport={}",
+                    options.ip, options.port, options.port
+                );
+                if let Some(multicast) = &options.multicast {
+                    url.push_str(&format!("&ttl={}", multicast.ttl));
+                    if let Some(interface) = &multicast.interface {
+                        url.push_str(&format!("&localaddr={interface}"));
+                    }
+                }
+                (url, None)
+            }
+        };
+
+        let mut url_options = Dictionary::new();
+        if let Some(srtp_key) = &options.srtp_key {
+            url_options.set("srtp_out_suite", sdp::SRTP_SUITE);
+            url_options.set("srtp_out_params", srtp_key);
+        }
+        let mut output_ctx = format::output_as_with(&PathBuf::from(url), "rtp", url_options)?;
 
         let mut stream = output_ctx.add_stream(codec)?;
         unsafe {
@@ -48,11 +108,23 @@ impl PipelineOutput for RtpSender {
 
         output_ctx.write_header()?;
 
-        Ok((Self { port, ip }, RtpContext(output_ctx)))
+        Ok((
+            Self {
+                port,
+                ip,
+                fec_overhead_percentage: options.fec_overhead_percentage,
+                multicast_ttl: options.multicast.map(|multicast| multicast.ttl),
+                srtp_key: options.srtp_key,
+            },
+            RtpContext {
+                output_ctx,
+                _proxy: proxy,
+            },
+        ))
     }
 
     fn send_packet(&self, context: &mut RtpContext, packet: Packet) {
-        if let Err(err) = packet.write_interleaved(&mut context.0) {
+        if let Err(err) = packet.write_interleaved(&mut context.output_ctx) {
             error!("Failed to send rtp packets: {err}")
         }
     }
@@ -0,0 +1,80 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use compositor_render::EventLoop;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{Api, Request},
+    error::ApiError,
+    types::{InitOptions, RegisterRequest, Scene},
+};
+
+/// Compositor setup passed with the `--scene <path>` flag. It is applied on
+/// startup instead of waiting for the "init" request, so simple deployments
+/// don't need a client that configures the compositor over HTTP. The API is
+/// available afterwards as usual.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SceneFile {
+    pub init: InitOptions,
+    /// Inputs, outputs and renderers, registered in this order.
+    #[serde(default)]
+    pub register: Vec<RegisterRequest>,
+    /// Scene set after everything is registered.
+    pub scene: Option<Scene>,
+    /// Start the pipeline after the scene is set. Defaults to true.
+    pub start: Option<bool>,
+}
+
+impl SceneFile {
+    /// Loads the file passed with `--scene <path>` or `--scene=<path>`. Panics if
+    /// the file can't be loaded, the compositor should not start without it.
+    pub fn from_args() -> Option<Self> {
+        let path = scene_file_path()?;
+        match Self::load(&path) {
+            Ok(scene_file) => Some(scene_file),
+            Err(err) => panic!("{err:#}"),
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scene file {path:?}."))?;
+        serde_json::from_str(&content).with_context(|| format!("Scene file {path:?} is not valid."))
+    }
+
+    /// Initializes the compositor and sends requests described by the file.
+    pub fn apply(self) -> Result<(Api, EventLoop), ApiError> {
+        let (mut api, event_loop) = Api::new(self.init)?;
+
+        let requests = self
+            .register
+            .into_iter()
+            .map(Request::Register)
+            .chain(self.scene.map(Request::UpdateScene))
+            .chain(self.start.unwrap_or(true).then_some(Request::Start));
+        for request in requests {
+            api.handle_request(request)?;
+        }
+        Ok((api, event_loop))
+    }
+}
+
+fn scene_file_path() -> Option<PathBuf> {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--scene" {
+            let path = args.next().expect("Missing path after the --scene flag.");
+            return Some(path.into());
+        }
+        if let Some(path) = arg.strip_prefix("--scene=") {
+            return Some(path.into());
+        }
+    }
+    None
+}
@@ -0,0 +1,345 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info};
+use rhai::{CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+use serde::Serialize;
+use tiny_http::StatusCode;
+
+use crate::{error::ApiError, types};
+
+/// Scripts are aborted after executing that many operations, so a buggy script
+/// can't block the API thread.
+const MAX_OPERATIONS: u64 = 1_000_000;
+/// Limits of values created by scripts, so a script can't exhaust memory while
+/// staying below `MAX_OPERATIONS` (e.g. by doubling a string in a loop).
+const MAX_STRING_SIZE: usize = 1 << 20;
+const MAX_ARRAY_SIZE: usize = 10_000;
+const MAX_MAP_SIZE: usize = 10_000;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_FUNCTION_EXPR_DEPTH: usize = 32;
+/// Function of the script called for every event.
+const EVENT_HANDLER: &str = "on_event";
+
+pub const DEFAULT_TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Event passed to the script as the first argument of `on_event`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptEvent {
+    /// Script was loaded, called before any other event.
+    Load,
+    /// Input started delivering frames.
+    InputLive { input_id: String },
+    /// Input did not deliver frames for longer than `stream_fallback_timeout_ms`
+    /// or it was unregistered.
+    InputLost { input_id: String },
+    /// Sent every `tick_interval_ms`.
+    Tick,
+    /// Sent by the "trigger_script" request.
+    Trigger {
+        name: String,
+        payload: serde_json::Value,
+    },
+}
+
+/// Current state of the compositor, passed to the script as the second argument
+/// of `on_event`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptState {
+    pub inputs: Vec<String>,
+    pub live_inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    /// The last scene set by the "update_scene" request or a script.
+    pub scene: types::Scene,
+}
+
+pub struct Script {
+    ast: AST,
+    /// Bound to `this` in `on_event`, so the script can keep state between events.
+    this: Dynamic,
+    tick_interval: Duration,
+    /// `None` if the tick interval is too long to be represented as `Instant`,
+    /// the script never receives the tick event then.
+    next_tick: Option<Instant>,
+}
+
+/// Rhai scripts that react to events with scene updates, so simple automation
+/// (e.g. switching layouts when inputs connect) runs without a round trip to
+/// an external controller. Scripts are run on the API thread, one at a time.
+///
+/// A script defines `fn on_event(event, state)`. If it returns a scene (a map
+/// with the same structure as the "update_scene" request), the scene is applied.
+/// Returning `()` leaves the scene unchanged.
+pub struct Scripts {
+    engine: Engine,
+    scripts: BTreeMap<String, Script>,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_string_size(MAX_STRING_SIZE);
+        engine.set_max_array_size(MAX_ARRAY_SIZE);
+        engine.set_max_map_size(MAX_MAP_SIZE);
+        engine.set_max_expr_depths(MAX_EXPR_DEPTH, MAX_FUNCTION_EXPR_DEPTH);
+        engine.on_print(|text| info!(target: "script", "{text}"));
+        engine.on_debug(|text, _, _| debug!(target: "script", "{text}"));
+        Self {
+            engine,
+            scripts: BTreeMap::new(),
+        }
+    }
+}
+
+impl Scripts {
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    /// Replaces the script with the same id and returns the replaced script, so
+    /// it can be restored with `restore`. Returns an error if the script does not
+    /// compile or does not define `on_event`.
+    pub fn load(
+        &mut self,
+        script_id: String,
+        source: &str,
+        tick_interval: Duration,
+    ) -> Result<Option<Script>, ApiError> {
+        let ast = self.engine.compile(source).map_err(|err| {
+            ApiError::new(
+                "SCRIPT_COMPILATION_FAILED",
+                format!("Failed to compile script \"{script_id}\": {err}"),
+                StatusCode(400),
+            )
+        })?;
+        if !ast.iter_functions().any(|f| f.name == EVENT_HANDLER) {
+            return Err(ApiError::new(
+                "SCRIPT_COMPILATION_FAILED",
+                format!("Script \"{script_id}\" does not define the \"{EVENT_HANDLER}\" function."),
+                StatusCode(400),
+            ));
+        }
+        let replaced = self.scripts.insert(
+            script_id,
+            Script {
+                ast,
+                this: Map::new().into(),
+                tick_interval,
+                next_tick: Instant::now().checked_add(tick_interval),
+            },
+        );
+        Ok(replaced)
+    }
+
+    /// Reverts `load`, e.g. when the loaded script failed to handle the load event.
+    pub fn restore(&mut self, script_id: String, replaced: Option<Script>) {
+        match replaced {
+            Some(script) => self.scripts.insert(script_id, script),
+            None => self.scripts.remove(&script_id),
+        };
+    }
+
+    pub fn unload(&mut self, script_id: &str) -> Result<(), ApiError> {
+        match self.scripts.remove(script_id) {
+            Some(_) => Ok(()),
+            None => Err(ApiError::new(
+                "SCRIPT_NOT_FOUND",
+                format!("Script \"{script_id}\" is not loaded."),
+                StatusCode(404),
+            )),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.scripts.clear();
+    }
+
+    pub fn script_ids(&self) -> Vec<String> {
+        self.scripts.keys().cloned().collect()
+    }
+
+    /// Time until the earliest tick of any script, `None` if no script is loaded.
+    pub fn time_to_next_tick(&self) -> Option<Duration> {
+        self.scripts
+            .values()
+            .map(|script| match script.next_tick {
+                Some(next_tick) => next_tick.saturating_duration_since(Instant::now()),
+                None => Duration::MAX,
+            })
+            .min()
+    }
+
+    /// Ids of scripts that should receive the tick event now. Their next tick is
+    /// scheduled one interval later.
+    pub fn take_due_ticks(&mut self) -> Vec<String> {
+        let now = Instant::now();
+        self.scripts
+            .iter_mut()
+            .filter(|(_, script)| script.next_tick.is_some_and(|next_tick| next_tick <= now))
+            .map(|(script_id, script)| {
+                script.next_tick = now.checked_add(script.tick_interval);
+                script_id.clone()
+            })
+            .collect()
+    }
+
+    /// Calls `on_event` of the script. Returns the scene to apply, if the script
+    /// returned one.
+    pub fn handle_event(
+        &mut self,
+        script_id: &str,
+        event: &ScriptEvent,
+        state: &ScriptState,
+    ) -> Result<Option<types::Scene>, ApiError> {
+        let Some(script) = self.scripts.get_mut(script_id) else {
+            return Ok(None);
+        };
+        let script_error = |err: &dyn std::fmt::Display| {
+            ApiError::new(
+                "SCRIPT_FAILED",
+                format!("Script \"{script_id}\" failed: {err}"),
+                StatusCode(400),
+            )
+        };
+        let event = rhai::serde::to_dynamic(event).map_err(|err| script_error(&err))?;
+        let state = rhai::serde::to_dynamic(state).map_err(|err| script_error(&err))?;
+
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut script.this);
+        let result = self
+            .engine
+            .call_fn_with_options::<Dynamic>(
+                options,
+                &mut Scope::new(),
+                &script.ast,
+                EVENT_HANDLER,
+                (event, state),
+            )
+            .map_err(|err| script_error(&err))?;
+        if result.is_unit() {
+            return Ok(None);
+        }
+        let scene = rhai::serde::from_dynamic::<types::Scene>(&result)
+            .map_err(|err| script_error(&format!("returned value is not a valid scene: {err}")))?;
+        Ok(Some(scene))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn state() -> ScriptState {
+        ScriptState {
+            inputs: vec!["bars".to_string()],
+            live_inputs: vec![],
+            outputs: vec!["out".to_string()],
+            scene: types::Scene {
+                nodes: vec![],
+                outputs: vec![],
+            },
+        }
+    }
+
+    fn load(scripts: &mut Scripts, source: &str) -> Result<Option<Script>, ApiError> {
+        scripts.load("script".to_string(), source, DEFAULT_TICK_INTERVAL)
+    }
+
+    fn handle_load(scripts: &mut Scripts) -> Result<Option<types::Scene>, ApiError> {
+        scripts.handle_event("script", &ScriptEvent::Load, &state())
+    }
+
+    #[test]
+    fn script_has_to_compile() {
+        let mut scripts = Scripts::default();
+        let Err(err) = load(&mut scripts, "fn on_event(event, state) {") else {
+            panic!("script with a syntax error was loaded");
+        };
+        assert_eq!(err.error_code, "SCRIPT_COMPILATION_FAILED");
+        assert!(scripts.is_empty());
+    }
+
+    #[test]
+    fn script_has_to_define_event_handler() {
+        let mut scripts = Scripts::default();
+        let Err(err) = load(&mut scripts, "fn on_tick(event, state) {}") else {
+            panic!("script without \"on_event\" was loaded");
+        };
+        assert_eq!(err.error_code, "SCRIPT_COMPILATION_FAILED");
+        assert!(scripts.is_empty());
+    }
+
+    #[test]
+    fn returned_scene_is_deserialized() {
+        let mut scripts = Scripts::default();
+        let source = r#"
+            fn on_event(event, state) {
+                if event["type"] != "load" {
+                    return;
+                }
+                #{
+                    nodes: [],
+                    outputs: [#{ output_id: state.outputs[0], input_pad: state.inputs[0] }],
+                }
+            }
+        "#;
+        assert!(load(&mut scripts, source).is_ok());
+
+        let Ok(Some(scene)) = handle_load(&mut scripts) else {
+            panic!("script did not return a scene");
+        };
+        assert_eq!(
+            serde_json::to_value(scene).unwrap(),
+            json!({ "nodes": [], "outputs": [{ "output_id": "out", "input_pad": "bars" }] })
+        );
+        let tick = scripts.handle_event("script", &ScriptEvent::Tick, &state());
+        assert!(matches!(tick, Ok(None)));
+    }
+
+    #[test]
+    fn script_is_aborted_after_max_operations() {
+        let mut scripts = Scripts::default();
+        assert!(load(&mut scripts, "fn on_event(event, state) { loop {} }").is_ok());
+
+        let Err(err) = handle_load(&mut scripts) else {
+            panic!("infinite loop was not aborted");
+        };
+        assert_eq!(err.error_code, "SCRIPT_FAILED");
+    }
+
+    #[test]
+    fn script_is_aborted_after_max_string_size() {
+        let mut scripts = Scripts::default();
+        let source = "fn on_event(event, state) { let s = \"a\"; loop { s += s; } }";
+        assert!(load(&mut scripts, source).is_ok());
+
+        let Err(err) = handle_load(&mut scripts) else {
+            panic!("growing string was not aborted");
+        };
+        assert_eq!(err.error_code, "SCRIPT_FAILED");
+    }
+
+    #[test]
+    fn replaced_script_is_restored() {
+        let mut scripts = Scripts::default();
+        assert!(load(&mut scripts, "fn on_event(event, state) {}").is_ok());
+        let Ok(replaced) = load(
+            &mut scripts,
+            "fn on_event(event, state) { throw \"failed\"; }",
+        ) else {
+            panic!("script was not replaced");
+        };
+        assert!(replaced.is_some());
+        assert!(handle_load(&mut scripts).is_err());
+
+        scripts.restore("script".to_string(), replaced);
+        assert!(matches!(handle_load(&mut scripts), Ok(None)));
+    }
+}
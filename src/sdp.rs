@@ -0,0 +1,64 @@
+use crate::rtp_fec::ULPFEC_PAYLOAD_TYPE;
+
+/// Payload type of H264 packets produced by ffmpeg RTP muxer and expected on inputs.
+pub const H264_PAYLOAD_TYPE: u8 = 96;
+
+/// The only SRTP crypto suite supported on inputs and outputs.
+pub const SRTP_SUITE: &str = "AES_CM_128_HMAC_SHA1_80";
+
+/// H264 RTP stream with RTCP multiplexed on the same port.
+pub struct StreamDescription<'a> {
+    pub ip: &'a str,
+    pub port: u16,
+    /// ULPFEC packets are listed as a second payload type of the stream.
+    pub fec: bool,
+    /// Added to the connection address of IPv4 multicast streams.
+    pub multicast_ttl: Option<u8>,
+    /// Base64 encoded SRTP master key and salt.
+    pub srtp_key: Option<&'a str>,
+}
+
+impl StreamDescription<'_> {
+    pub fn sdp(&self) -> String {
+        let StreamDescription {
+            ip,
+            port,
+            fec,
+            multicast_ttl,
+            srtp_key,
+        } = *self;
+        let (address_type, connection_address) = match (ip.contains(':'), multicast_ttl) {
+            (true, _) => ("IP6", ip.to_string()),
+            (false, Some(ttl)) => ("IP4", format!("{ip}/{ttl}")),
+            (false, None) => ("IP4", ip.to_string()),
+        };
+        let transport = match srtp_key {
+            Some(_) => "RTP/SAVP",
+            None => "RTP/AVP",
+        };
+        let payload_types = match fec {
+            true => format!("{H264_PAYLOAD_TYPE} {ULPFEC_PAYLOAD_TYPE}"),
+            false => H264_PAYLOAD_TYPE.to_string(),
+        };
+
+        let mut sdp = format!(
+            "\
+                v=0\n\
+                o=- 0 0 IN {address_type} {ip}\n\
+                s=No Name\n\
+                c=IN {address_type} {connection_address}\n\
+                m=video {port} {transport} {payload_types}\n\
+                a=rtpmap:{H264_PAYLOAD_TYPE} H264/90000\n\
+                a=fmtp:{H264_PAYLOAD_TYPE} packetization-mode=1\n\
+            "
+        );
+        if fec {
+            sdp.push_str(&format!("a=rtpmap:{ULPFEC_PAYLOAD_TYPE} ulpfec/90000\n"));
+        }
+        if let Some(srtp_key) = srtp_key {
+            sdp.push_str(&format!("a=crypto:1 {SRTP_SUITE} inline:{srtp_key}\n"));
+        }
+        sdp.push_str("a=rtcp-mux\n");
+        sdp
+    }
+}
@@ -2,25 +2,27 @@ use std::{
     collections::HashSet, fmt::Display, fs, ops::Deref, path::PathBuf, sync::Arc, time::Duration,
 };
 
-use super::utils::{are_snapshots_equal, create_renderer, frame_to_rgba, SNAPSHOTS_DIR_NAME};
+use super::utils::snapshots_dir;
 
 use anyhow::Result;
 use compositor_common::{
-    frame::YuvData,
+    frame::{FrameData, FrameFormat, YuvData},
     renderer_spec::RendererSpec,
-    scene::{InputId, NodeId, OutputId, Resolution, SceneSpec},
+    scene::{InputId, NodeId, Resolution, SceneSpec},
     Frame,
 };
-use compositor_render::{FrameSet, Renderer};
+use compositor_render::testing::{RenderedSnapshot, SnapshotTest, Tolerance};
 use video_compositor::types::{RegisterRequest, Scene};
 
 pub struct TestCase {
     pub name: &'static str,
     pub inputs: Vec<TestInput>,
     pub renderers: Vec<&'static str>,
+    pub test_patterns: Vec<&'static str>,
     pub scene_json: &'static str,
     pub timestamps: Vec<Duration>,
     pub outputs: Vec<&'static str>,
+    pub tolerance: Tolerance,
 }
 
 impl Default for TestCase {
@@ -29,9 +31,11 @@ impl Default for TestCase {
             name: "",
             inputs: Vec::new(),
             renderers: Vec::new(),
+            test_patterns: Vec::new(),
             scene_json: "",
             timestamps: vec![Duration::from_secs(0)],
             outputs: vec!["output_1"],
+            tolerance: Tolerance::default(),
         }
     }
 }
@@ -42,16 +46,7 @@ impl TestCase {
         let snapshots = self.generate_snapshots()?;
         for snapshot in snapshots.iter() {
             produced_outputs.insert(snapshot.output_id.to_string());
-
-            let save_path = snapshot.save_path();
-            if !save_path.exists() {
-                return Err(TestCaseError::SnapshotNotFound(snapshot.clone()).into());
-            }
-
-            let snapshot_from_disk = image::open(&save_path)?.to_rgba8();
-            if !are_snapshots_equal(&snapshot_from_disk, &snapshot.data) {
-                return Err(TestCaseError::Mismatch(snapshot.clone()).into());
-            }
+            snapshot.check(&snapshots_dir(), self.name, self.tolerance)?;
         }
 
         // Check if every output was produced
@@ -73,58 +68,13 @@ impl TestCase {
         Ok(())
     }
 
-    pub fn generate_snapshots(&self) -> Result<Vec<Snapshot>> {
-        let (renderer, scene) = self.prepare_renderer_and_scene();
-        let snapshots = self
-            .timestamps
-            .iter()
-            .cloned()
-            .map(|pts| self.snapshots_for_pts(&renderer, &scene, pts))
-            .collect::<Result<Vec<_>>>()?;
-
-        Ok(snapshots.into_iter().flatten().collect())
-    }
-
-    fn snapshots_for_pts(
-        &self,
-        renderer: &Renderer,
-        scene: &SceneSpec,
-        pts: Duration,
-    ) -> Result<Vec<Snapshot>> {
-        let mut frame_set = FrameSet::new(pts);
-        for input in self.inputs.iter() {
-            let input_id = InputId(NodeId(input.name.clone().into()));
-            let frame = Frame {
-                data: input.data.clone(),
-                resolution: input.resolution,
-                pts,
-            };
-            frame_set.frames.insert(input_id, frame);
-        }
-
-        let outputs = renderer.render(frame_set)?;
-        let output_specs = &scene.outputs;
-        let mut snapshots = Vec::new();
-
-        for spec in output_specs {
-            let output_frame = outputs.frames.get(&spec.output_id).unwrap();
-            let new_snapshot = frame_to_rgba(output_frame);
-            snapshots.push(Snapshot {
-                test_name: self.name.to_owned(),
-                output_id: spec.output_id.clone(),
-                pts,
-                resolution: output_frame.resolution,
-                data: new_snapshot,
-            });
-        }
-
-        Ok(snapshots)
+    pub fn generate_snapshots(&self) -> Result<Vec<RenderedSnapshot>> {
+        Ok(self.snapshot_test().render()?)
     }
 
-    fn check_for_unused_snapshots(&self, snapshots: &[Snapshot]) -> Result<()> {
-        let snapshot_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join(SNAPSHOTS_DIR_NAME)
-            .join(self.name);
+    fn check_for_unused_snapshots(&self, snapshots: &[RenderedSnapshot]) -> Result<()> {
+        let snapshots_dir = snapshots_dir();
+        let snapshot_path = snapshots_dir.join(self.name);
         let parent_path = snapshot_path.parent().unwrap();
         let snapshot_name_prefix =
             format!("{}_", snapshot_path.file_name().unwrap().to_string_lossy());
@@ -142,22 +92,33 @@ impl TestCase {
                 continue;
             }
 
-            if !snapshots.iter().any(|s| s.save_path() == entry.path()) {
+            if !snapshots
+                .iter()
+                .any(|s| s.path(&snapshots_dir, self.name) == entry.path())
+            {
                 return Err(TestCaseError::UnusedSnapshot(entry.path()).into());
             }
         }
         Ok(())
     }
 
-    pub fn prepare_renderer_and_scene(&self) -> (Renderer, Arc<SceneSpec>) {
+    fn snapshot_test(&self) -> SnapshotTest {
         fn register_requests_to_renderers(register_request: RegisterRequest) -> RendererSpec {
             match register_request {
-                RegisterRequest::InputStream(_) | RegisterRequest::OutputStream(_) => {
+                RegisterRequest::InputStream(_)
+                | RegisterRequest::RtspInput(_)
+                | RegisterRequest::Camera(_)
+                | RegisterRequest::InputFile(_)
+                | RegisterRequest::TestPattern(_)
+                | RegisterRequest::OutputStream(_)
+                | RegisterRequest::OutputFile(_)
+                | RegisterRequest::OutputMpegTs(_) => {
                     panic!("Input and output streams are not supported in snapshot tests")
                 }
                 RegisterRequest::Shader(shader) => shader.try_into().unwrap(),
                 RegisterRequest::WebRenderer(web_renderer) => web_renderer.try_into().unwrap(),
                 RegisterRequest::Image(img) => img.try_into().unwrap(),
+                RegisterRequest::Mesh(mesh) => mesh.into(),
             }
         }
 
@@ -172,11 +133,42 @@ impl TestCase {
             .map(register_requests_to_renderers)
             .collect();
 
+        let test_patterns = self
+            .test_patterns
+            .iter()
+            .map(|json| match serde_json::from_str(json).unwrap() {
+                RegisterRequest::TestPattern(request) => {
+                    (request.input_id.clone().into(), request.try_into().unwrap())
+                }
+                _ => panic!("Only test pattern inputs can be registered as test patterns"),
+            })
+            .collect();
+
         let scene: Scene = serde_json::from_str(self.scene_json).unwrap();
         let scene: Arc<SceneSpec> = Arc::new(scene.try_into().unwrap());
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| {
+                let frame = Frame {
+                    data: input.data.clone(),
+                    resolution: input.resolution,
+                    format: FrameFormat::default(),
+                    pts: Duration::ZERO,
+                };
+                (InputId(NodeId(input.name.clone().into())), frame)
+            })
+            .collect();
 
-        let renderer = create_renderer(renderers, scene.clone());
-        (renderer, scene)
+        SnapshotTest {
+            name: self.name.to_owned(),
+            renderers,
+            scene,
+            inputs,
+            test_patterns,
+            timestamps: self.timestamps.clone(),
+            tolerance: self.tolerance,
+        }
     }
 }
 
@@ -184,7 +176,7 @@ impl TestCase {
 pub struct TestInput {
     pub name: String,
     pub resolution: Resolution,
-    pub data: YuvData,
+    pub data: FrameData,
 }
 
 impl TestInput {
@@ -248,11 +240,11 @@ impl TestInput {
         let y = (r * 0.299 + g * 0.587 + b * 0.144).clamp(0.0, 255.0);
         let u = (r * -0.168736 + g * -0.331264 + b * 0.5 + 128.0).clamp(0.0, 255.0);
         let v = (r * 0.5 + g * -0.418688 + b * -0.081312 + 128.0).clamp(0.0, 255.0);
-        let data = YuvData {
+        let data = FrameData::PlanarYuv(YuvData {
             y_plane: vec![y as u8; resolution.width * resolution.height].into(),
             u_plane: vec![u as u8; (resolution.width * resolution.height) / 4].into(),
             v_plane: vec![v as u8; (resolution.width * resolution.height) / 4].into(),
-        };
+        });
 
         Self {
             name: format!("input_{index}"),
@@ -262,33 +254,8 @@ impl TestInput {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Snapshot {
-    pub test_name: String,
-    pub output_id: OutputId,
-    pub pts: Duration,
-    pub resolution: Resolution,
-    pub data: Vec<u8>,
-}
-
-impl Snapshot {
-    pub fn save_path(&self) -> PathBuf {
-        let snapshots_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(SNAPSHOTS_DIR_NAME);
-
-        let out_file_name = format!(
-            "{}_{}_{}.png",
-            self.test_name,
-            self.pts.as_millis(),
-            self.output_id
-        );
-        snapshots_path.join(out_file_name)
-    }
-}
-
 #[derive(Debug)]
 pub enum TestCaseError {
-    SnapshotNotFound(Snapshot),
-    Mismatch(Snapshot),
     UnusedSnapshot(PathBuf),
     OutputNotFound(&'static str),
     UnknownOutputs {
@@ -302,33 +269,16 @@ impl std::error::Error for TestCaseError {}
 impl Display for TestCaseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let err_msg = match self {
-            TestCaseError::SnapshotNotFound(Snapshot {
-                test_name,
-                output_id,
-                pts,
-                ..
-            }) => format!(
-                "Test \"{}\": OutputId({}) & PTS({}). Snapshot file not found. Generate snapshots first",
-                test_name,
-                output_id,
-                pts.as_secs()
+            TestCaseError::UnusedSnapshot(path) => format!(
+                "Snapshot \"{}\" was not used during testing",
+                path.to_string_lossy()
             ),
-            TestCaseError::Mismatch(Snapshot {
-                test_name,
-                output_id,
-                pts,
-                ..
-            }) => {
-                format!(
-                    "Test \"{}\": OutputId({}) & PTS({}). Snapshots are different",
-                    test_name,
-                    output_id,
-                    pts.as_secs_f32()
-                )
+            TestCaseError::OutputNotFound(output_id) => {
+                format!("Output \"{output_id}\" is missing")
+            }
+            TestCaseError::UnknownOutputs { expected, unknown } => {
+                format!("Unknown outputs: {unknown:?}. Expected: {expected:?}")
             }
-            TestCaseError::UnusedSnapshot(path) => format!("Snapshot \"{}\" was not used during testing", path.to_string_lossy()),
-            TestCaseError::OutputNotFound(output_id) => format!("Output \"{output_id}\" is missing"),
-            TestCaseError::UnknownOutputs { expected, unknown } => format!("Unknown outputs: {unknown:?}. Expected: {expected:?}"),
         };
 
         f.write_str(&err_msg)
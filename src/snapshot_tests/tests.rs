@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use compositor_common::scene::Resolution;
 
 use super::test_case::{TestCase, TestInput};
@@ -12,9 +14,214 @@ pub fn snapshot_tests() -> Vec<TestCase> {
     tests.append(&mut fixed_position_layout_tests());
     tests.append(&mut corners_rounding_tests());
     tests.append(&mut mirror_image());
+    tests.append(&mut nine_slice_tests());
+    tests.append(&mut privacy_mask_tests());
+    tests.append(&mut stylize_tests());
+    tests.append(&mut scope_tests());
+    tests.append(&mut transform_2d_tests());
+    tests.append(&mut perspective_tests());
+    tests.append(&mut border_tests());
+    tests.append(&mut test_pattern_tests());
     tests
 }
 
+fn test_pattern_tests() -> Vec<TestCase> {
+    Vec::from([
+        TestCase {
+            name: "test_pattern/smpte_bars",
+            scene_json: include_str!("../../snapshot_tests/test_pattern/smpte_bars.scene.json"),
+            test_patterns: vec![include_str!(
+                "../../snapshot_tests/register/smpte_bars.register.json"
+            )],
+            ..Default::default()
+        },
+        TestCase {
+            name: "test_pattern/red_color",
+            scene_json: include_str!("../../snapshot_tests/test_pattern/red_color.scene.json"),
+            test_patterns: vec![include_str!(
+                "../../snapshot_tests/register/red_color.register.json"
+            )],
+            ..Default::default()
+        },
+        TestCase {
+            name: "test_pattern/checkerboard_with_timecode",
+            scene_json: include_str!(
+                "../../snapshot_tests/test_pattern/checkerboard_with_timecode.scene.json"
+            ),
+            test_patterns: vec![include_str!(
+                "../../snapshot_tests/register/checkerboard_with_timecode.register.json"
+            )],
+            timestamps: vec![Duration::ZERO, Duration::from_millis(1500)],
+            ..Default::default()
+        },
+    ])
+}
+
+fn border_tests() -> Vec<TestCase> {
+    let input1 = TestInput::new(1);
+    Vec::from([
+        TestCase {
+            name: "border/rounded_corners",
+            scene_json: include_str!("../../snapshot_tests/border/rounded_corners.scene.json"),
+            inputs: vec![input1.clone()],
+            ..Default::default()
+        },
+        TestCase {
+            name: "border/shadow",
+            scene_json: include_str!("../../snapshot_tests/border/shadow.scene.json"),
+            inputs: vec![input1.clone()],
+            ..Default::default()
+        },
+        TestCase {
+            name: "border/glow",
+            scene_json: include_str!("../../snapshot_tests/border/glow.scene.json"),
+            inputs: vec![input1.clone()],
+            ..Default::default()
+        },
+    ])
+}
+
+fn perspective_tests() -> Vec<TestCase> {
+    let input1 = TestInput::new(1);
+    Vec::from([
+        TestCase {
+            name: "perspective/rotation_y",
+            scene_json: include_str!("../../snapshot_tests/perspective/rotation_y.scene.json"),
+            inputs: vec![input1.clone()],
+            ..Default::default()
+        },
+        TestCase {
+            name: "perspective/rotation_x_short_distance",
+            scene_json: include_str!(
+                "../../snapshot_tests/perspective/rotation_x_short_distance.scene.json"
+            ),
+            inputs: vec![input1.clone()],
+            ..Default::default()
+        },
+    ])
+}
+
+fn transform_2d_tests() -> Vec<TestCase> {
+    let input1 = TestInput::new(1);
+    Vec::from([
+        TestCase {
+            name: "transform_2d/rotation_around_center",
+            scene_json: include_str!(
+                "../../snapshot_tests/transform_2d/rotation_around_center.scene.json"
+            ),
+            inputs: vec![input1.clone()],
+            ..Default::default()
+        },
+        TestCase {
+            name: "transform_2d/scale_from_top_left_corner",
+            scene_json: include_str!(
+                "../../snapshot_tests/transform_2d/scale_from_top_left_corner.scene.json"
+            ),
+            inputs: vec![input1.clone()],
+            ..Default::default()
+        },
+    ])
+}
+
+fn scope_tests() -> Vec<TestCase> {
+    let image_renderer = include_str!("../../snapshot_tests/register/image_jpeg.register.json");
+
+    Vec::from([
+        TestCase {
+            name: "scope/luma_waveform",
+            scene_json: include_str!("../../snapshot_tests/scope/luma_waveform.scene.json"),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+        TestCase {
+            name: "scope/rgb_parade",
+            scene_json: include_str!("../../snapshot_tests/scope/rgb_parade.scene.json"),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+        TestCase {
+            name: "scope/vectorscope",
+            scene_json: include_str!("../../snapshot_tests/scope/vectorscope.scene.json"),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+    ])
+}
+
+fn stylize_tests() -> Vec<TestCase> {
+    let image_renderer = include_str!("../../snapshot_tests/register/image_jpeg.register.json");
+
+    Vec::from([
+        TestCase {
+            name: "stylize/edge_detect",
+            scene_json: include_str!("../../snapshot_tests/stylize/edge_detect.scene.json"),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+        TestCase {
+            name: "stylize/posterize",
+            scene_json: include_str!("../../snapshot_tests/stylize/posterize.scene.json"),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+        TestCase {
+            name: "stylize/vignette",
+            scene_json: include_str!("../../snapshot_tests/stylize/vignette.scene.json"),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+        TestCase {
+            name: "stylize/chromatic_aberration",
+            scene_json: include_str!(
+                "../../snapshot_tests/stylize/chromatic_aberration.scene.json"
+            ),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+    ])
+}
+
+fn privacy_mask_tests() -> Vec<TestCase> {
+    let image_renderer = include_str!("../../snapshot_tests/register/image_jpeg.register.json");
+
+    Vec::from([
+        TestCase {
+            name: "privacy_mask/blur",
+            scene_json: include_str!("../../snapshot_tests/privacy_mask/blur.scene.json"),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+        TestCase {
+            name: "privacy_mask/pixelate_multiple_regions",
+            scene_json: include_str!(
+                "../../snapshot_tests/privacy_mask/pixelate_multiple_regions.scene.json"
+            ),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+    ])
+}
+
+fn nine_slice_tests() -> Vec<TestCase> {
+    let image_renderer =
+        include_str!("../../snapshot_tests/register/image_jpeg_nine_slice.register.json");
+
+    Vec::from([
+        TestCase {
+            name: "nine_slice/wide",
+            scene_json: include_str!("../../snapshot_tests/nine_slice/wide.scene.json"),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+        TestCase {
+            name: "nine_slice/narrow",
+            scene_json: include_str!("../../snapshot_tests/nine_slice/narrow.scene.json"),
+            renderers: vec![image_renderer],
+            ..Default::default()
+        },
+    ])
+}
+
 fn mirror_image() -> Vec<TestCase> {
     let image_renderer = include_str!("../../snapshot_tests/register/image_jpeg.register.json");
 
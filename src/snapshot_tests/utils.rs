@@ -1,59 +1,7 @@
-use std::{sync::Arc, time::Duration};
-
-use compositor_common::{
-    frame::YuvData, renderer_spec::RendererSpec, scene::SceneSpec, Frame, Framerate,
-};
-use compositor_render::{renderer::RendererOptions, Renderer, WebRendererOptions};
+use std::path::PathBuf;
 
 pub const SNAPSHOTS_DIR_NAME: &str = "snapshot_tests/snapshots/render_snapshots";
 
-pub(super) fn frame_to_rgba(frame: &Frame) -> Vec<u8> {
-    let YuvData {
-        y_plane,
-        u_plane,
-        v_plane,
-    } = &frame.data;
-
-    let mut rgba_data = Vec::with_capacity(y_plane.len() * 4);
-    for (i, y_plane) in y_plane.chunks(frame.resolution.width).enumerate() {
-        for (j, y) in y_plane.iter().enumerate() {
-            let y = (*y) as f32;
-            let u = u_plane[(i / 2) * (frame.resolution.width / 2) + (j / 2)] as f32;
-            let v = v_plane[(i / 2) * (frame.resolution.width / 2) + (j / 2)] as f32;
-
-            let r = (y + 1.40200 * (v - 128.0)).clamp(0.0, 255.0);
-            let g = (y - 0.34414 * (u - 128.0) - 0.71414 * (v - 128.0)).clamp(0.0, 255.0);
-            let b = (y + 1.77200 * (u - 128.0)).clamp(0.0, 255.0);
-            rgba_data.extend_from_slice(&[r as u8, g as u8, b as u8, 255]);
-        }
-    }
-
-    rgba_data
-}
-
-// TODO: Results may slightly differ depending on the platform. There should be an accepted margin of error here
-pub(super) fn are_snapshots_equal(old_snapshot: &[u8], new_snapshot: &[u8]) -> bool {
-    old_snapshot == new_snapshot
-}
-
-pub(super) fn create_renderer(renderers: Vec<RendererSpec>, scene: Arc<SceneSpec>) -> Renderer {
-    let (mut renderer, _event_loop) = Renderer::new(RendererOptions {
-        web_renderer: WebRendererOptions {
-            init: false,
-            disable_gpu: false,
-        },
-        framerate: Framerate { num: 30, den: 1 },
-        stream_fallback_timeout: Duration::from_secs(3),
-    })
-    .unwrap();
-
-    for spec in renderers {
-        if matches!(spec, RendererSpec::WebRenderer(_)) {
-            panic!("Tests with web renderer are not supported");
-        }
-        renderer.register_renderer(spec).unwrap();
-    }
-    renderer.update_scene(scene.clone()).unwrap();
-
-    renderer
+pub(super) fn snapshots_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(SNAPSHOTS_DIR_NAME)
 }
@@ -9,6 +9,7 @@ mod convert_util;
 mod from_node;
 mod from_renderer;
 mod into_node;
+mod into_renderer;
 mod node;
 mod register_request;
 mod renderer;
@@ -19,13 +20,28 @@ mod convert_util_test;
 
 pub use node::Node;
 pub use node::WebRenderer;
+pub use register_request::decoder_options;
+pub use register_request::EncoderSettings;
+pub use register_request::MulticastOptions;
+pub use register_request::OutputOptions;
+pub use register_request::RegisterCameraRequest;
+pub use register_request::RegisterInputFileRequest;
 pub use register_request::RegisterInputRequest;
+pub use register_request::RegisterOutputFileRequest;
+pub use register_request::RegisterOutputMpegTsRequest;
 pub use register_request::RegisterOutputRequest;
 pub use register_request::RegisterRequest;
+pub use register_request::RegisterRtspInputRequest;
+pub use register_request::RegisterTestPatternRequest;
+pub use renderer::FallbackStrategy;
+pub use renderer::NodeConstraints;
+pub use renderer::ShaderSpec;
+pub use renderer::WebRendererEvent;
+pub use util::Framerate;
 pub use util::Resolution;
 pub use util::TypeError;
 
-use self::util::Framerate;
+use self::util::RGBColor;
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct NodeId(Arc<str>);
@@ -56,6 +72,207 @@ pub struct InitOptions {
     pub framerate: Framerate,
     pub stream_fallback_timeout_ms: Option<f64>,
     pub web_renderer: Option<WebRendererOptions>,
+    /// GPU used for rendering. Use the "gpu_adapters" query to list available adapters.
+    pub gpu: Option<GpuOptions>,
+    /// Start the pipeline when the first scene is set, without waiting for
+    /// the "start" request. Defaults to false.
+    pub auto_start: Option<bool>,
+    /// What outputs should send before the pipeline is started. Defaults to nothing.
+    pub pre_start_output: Option<PreStartOutput>,
+    /// What happens to outputs when rendering fails repeatedly (e.g. because of
+    /// GPU errors or a panic in the renderer). Defaults to skipping failed frames.
+    pub render_failure_policy: Option<RenderFailurePolicy>,
+    /// Slate sent to all outputs when rendering fails repeatedly. Same as
+    /// `render_failure_policy` with the "slate" action, kept for compatibility.
+    pub render_failure_slate: Option<RenderFailureSlate>,
+    /// If set, all inputs, outputs and the scene are removed when no request is received
+    /// for that long (e.g. because the controlling process died). Send "keep_alive"
+    /// requests to prevent the teardown while there is nothing else to send.
+    pub keep_alive_timeout_ms: Option<f64>,
+    /// Buffering and synchronization of inputs.
+    pub queue: Option<QueueOptions>,
+    /// What happens when rendering is slower than realtime. Defaults to `drop_oldest`.
+    pub backpressure: Option<Backpressure>,
+    /// Render as fast as inputs can be decoded instead of in realtime, e.g. to render
+    /// file inputs into an output file. Output timestamps start at 0, rendering waits
+    /// for every input and finishes (closing all outputs) when all inputs end.
+    /// `backpressure` is ignored, frames are never dropped. Defaults to false.
+    pub offline: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Backpressure {
+    /// Stop reading inputs until the renderer catches up. Use for non-realtime
+    /// inputs (e.g. files) where dropping frames is unacceptable.
+    Block,
+    /// Drop the oldest frames waiting for rendering.
+    DropOldest,
+    /// Drop newly produced frames until the renderer catches up.
+    DropNewest,
+    /// Skip more frames the longer the render queue gets, so output framerate
+    /// degrades gradually.
+    Adaptive,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QueueOptions {
+    /// Time each input is buffered for before its first frame is used. Larger values
+    /// absorb more network jitter at the cost of latency. Defaults to 80ms.
+    pub buffer_duration_ms: Option<f64>,
+    /// Defaults to `render_available`.
+    pub sync_policy: Option<SyncPolicy>,
+    /// Frames that arrive later than that after their presentation time are dropped.
+    /// Late frames are not dropped if not set.
+    pub max_input_lateness_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPolicy {
+    /// Produce frames on schedule with whatever frames already arrived. Late inputs
+    /// stutter, but do not delay other inputs.
+    RenderAvailable,
+    /// Wait until every input delivers a frame, but no longer than max lateness of
+    /// that input (or its buffer duration if not set). Late inputs delay all outputs.
+    WaitForAllInputs,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RenderFailurePolicy {
+    /// Defaults to "skip_frames".
+    pub action: Option<RenderFailureAction>,
+    /// Color of the slate if `action` is "slate". Defaults to black.
+    pub color_rgb: Option<RGBColor>,
+    /// Number of consecutive failed frames after which the action is taken and the
+    /// "render_failing" event is emitted. Defaults to 10.
+    pub max_consecutive_failures: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderFailureAction {
+    /// Outputs don't receive frames until rendering recovers. Rendering is
+    /// retried on every frame.
+    SkipFrames,
+    /// Outputs receive frames filled with `color_rgb` until rendering recovers.
+    Slate,
+    /// Rendering stops and all outputs are finished (e.g. files are closed). The
+    /// compositor has to be restarted to render again.
+    Stop,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RenderFailureSlate {
+    /// Defaults to black.
+    pub color_rgb: Option<RGBColor>,
+    /// Number of consecutive failed frames after which outputs switch to the slate.
+    /// Defaults to 10.
+    pub max_consecutive_failures: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StoppedOutputFrames {
+    Nothing,
+    /// Repeat the last frame sent before the output was stopped.
+    FreezeFrame,
+    /// Frames filled with a solid color. Defaults to black.
+    Slate {
+        color_rgb: Option<RGBColor>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SlateContent {
+    /// Frames filled with a solid color. Defaults to black.
+    Color { color_rgb: Option<RGBColor> },
+    /// Registered image scaled to fit the output, the remaining area is black.
+    Image { image_id: RendererId },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SlateMode {
+    /// Slate replaces the scene until it's turned off.
+    On,
+    /// Scene is sent to the output.
+    Off,
+    /// Slate is shown only while all inputs are missing, i.e. none of them
+    /// delivered frames for longer than `stream_fallback_timeout_ms`.
+    WhenInputsMissing,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PreStartOutput {
+    Nothing,
+    BlackFrames,
+    Slate { color_rgb: RGBColor },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GpuOptions {
+    /// Adapters of all backends are considered if not set.
+    pub backend: Option<GpuBackend>,
+    /// Ignored if the adapter is selected by name or index. Defaults to "high_performance".
+    pub power_preference: Option<GpuPowerPreference>,
+    /// Use the first adapter whose name contains this string (case insensitive).
+    pub adapter_name: Option<String>,
+    /// Use the adapter at this position on the list returned by the "gpu_adapters" query.
+    pub adapter_index: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuBackend {
+    Vulkan,
+    Metal,
+    Dx12,
+    Gl,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuPowerPreference {
+    HighPerformance,
+    LowPower,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Scte35SpliceType {
+    /// Start of an ad break (leaving the network feed).
+    SpliceOut,
+    /// End of an ad break (returning to the network feed).
+    SpliceIn,
+    /// Cancels a previously sent marker with the same `event_id`.
+    Cancel,
+}
+
+/// Scene shown for `duration_ms` as a part of a timeline.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TimelineEntry {
+    pub scene: Scene,
+    pub duration_ms: f64,
+    /// Nodes with the "fixed_position_layout", "transform_2d" or "perspective"
+    /// transformation that exist in the previous entry with the same id are animated
+    /// from their previous params. Other nodes change immediately.
+    pub transition: Option<TimelineTransition>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TimelineTransition {
+    pub duration_ms: f64,
+    /// Defaults to "linear".
+    pub interpolation: Option<node::Interpolation>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -1,7 +1,7 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use compositor_common::{renderer_spec, scene};
-use compositor_pipeline::pipeline;
+use compositor_common::{renderer_spec, scene, util::colors};
+use compositor_pipeline::{pipeline, queue};
 
 use super::util::*;
 use super::*;
@@ -111,6 +111,219 @@ impl TryFrom<InitOptions> for pipeline::Options {
                     .and_then(|r| r.disable_gpu)
                     .unwrap_or(false),
             },
+            gpu: opts
+                .gpu
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
+            auto_start: opts.auto_start.unwrap_or(false),
+            pre_start_output: opts
+                .pre_start_output
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
+            render_failure_policy: match (opts.render_failure_policy, opts.render_failure_slate) {
+                (Some(_), Some(_)) => {
+                    return Err(TypeError::new(
+                        "Fields \"render_failure_policy\" and \"render_failure_slate\" are mutually exclusive.",
+                    ))
+                }
+                (Some(policy), None) => policy.try_into()?,
+                (None, Some(slate)) => slate.try_into()?,
+                (None, None) => Default::default(),
+            },
+            queue: queue::Options {
+                offline: opts.offline.unwrap_or(false),
+                ..opts.queue.map(Into::into).unwrap_or_default()
+            },
+            backpressure: match opts.backpressure {
+                Some(Backpressure::Block) => pipeline::Backpressure::Block,
+                Some(Backpressure::DropOldest) | None => pipeline::Backpressure::DropOldest,
+                Some(Backpressure::DropNewest) => pipeline::Backpressure::DropNewest,
+                Some(Backpressure::Adaptive) => pipeline::Backpressure::Adaptive,
+            },
+        };
+        Ok(result)
+    }
+}
+
+impl TryFrom<GpuOptions> for compositor_render::GpuOptions {
+    type Error = TypeError;
+
+    fn try_from(opts: GpuOptions) -> Result<Self, Self::Error> {
+        let adapter = match (opts.adapter_name, opts.adapter_index) {
+            (Some(_), Some(_)) => {
+                return Err(TypeError::new(
+                    "Fields \"adapter_name\" and \"adapter_index\" are mutually exclusive.",
+                ))
+            }
+            (Some(name), None) => Some(compositor_render::AdapterSelector::Name(name)),
+            (None, Some(index)) => Some(compositor_render::AdapterSelector::Index(index)),
+            (None, None) => None,
+        };
+        Ok(Self {
+            backend: opts.backend.map(|backend| match backend {
+                GpuBackend::Vulkan => compositor_render::GpuBackend::Vulkan,
+                GpuBackend::Metal => compositor_render::GpuBackend::Metal,
+                GpuBackend::Dx12 => compositor_render::GpuBackend::Dx12,
+                GpuBackend::Gl => compositor_render::GpuBackend::Gl,
+            }),
+            power_preference: match opts.power_preference {
+                Some(GpuPowerPreference::HighPerformance) | None => {
+                    compositor_render::GpuPowerPreference::HighPerformance
+                }
+                Some(GpuPowerPreference::LowPower) => {
+                    compositor_render::GpuPowerPreference::LowPower
+                }
+            },
+            adapter,
+        })
+    }
+}
+
+impl From<QueueOptions> for queue::Options {
+    fn from(opts: QueueOptions) -> Self {
+        let defaults = queue::Options::default();
+        Self {
+            buffer_duration: opts
+                .buffer_duration_ms
+                .map(|ms| Duration::from_millis(ms as u64))
+                .unwrap_or(defaults.buffer_duration),
+            sync_policy: match opts.sync_policy {
+                Some(SyncPolicy::RenderAvailable) | None => queue::SyncPolicy::RenderAvailable,
+                Some(SyncPolicy::WaitForAllInputs) => queue::SyncPolicy::WaitForAllInputs,
+            },
+            max_input_lateness: opts
+                .max_input_lateness_ms
+                .map(|ms| Duration::from_millis(ms as u64)),
+            offline: defaults.offline,
+        }
+    }
+}
+
+impl TryFrom<RenderFailurePolicy> for pipeline::RenderFailurePolicy {
+    type Error = TypeError;
+
+    fn try_from(value: RenderFailurePolicy) -> Result<Self, Self::Error> {
+        let action = match value.action {
+            Some(RenderFailureAction::SkipFrames) | None => {
+                pipeline::RenderFailureAction::SkipFrames
+            }
+            Some(RenderFailureAction::Slate) => pipeline::RenderFailureAction::Slate {
+                color: slate_color(value.color_rgb)?,
+            },
+            Some(RenderFailureAction::Stop) => pipeline::RenderFailureAction::Stop,
+        };
+        Ok(Self {
+            max_consecutive_failures: max_consecutive_failures(value.max_consecutive_failures)?,
+            action,
+        })
+    }
+}
+
+impl TryFrom<RenderFailureSlate> for pipeline::RenderFailurePolicy {
+    type Error = TypeError;
+
+    fn try_from(value: RenderFailureSlate) -> Result<Self, Self::Error> {
+        Ok(Self {
+            max_consecutive_failures: max_consecutive_failures(value.max_consecutive_failures)?,
+            action: pipeline::RenderFailureAction::Slate {
+                color: slate_color(value.color_rgb)?,
+            },
+        })
+    }
+}
+
+fn slate_color(color: Option<RGBColor>) -> Result<colors::RGBColor, TypeError> {
+    match color {
+        Some(color) => color.try_into(),
+        None => Ok(colors::RGBColor::BLACK),
+    }
+}
+
+fn max_consecutive_failures(value: Option<u32>) -> Result<u32, TypeError> {
+    let max_consecutive_failures =
+        value.unwrap_or(pipeline::RenderFailurePolicy::default().max_consecutive_failures);
+    if max_consecutive_failures == 0 {
+        return Err(TypeError::new(
+            "Field \"max_consecutive_failures\" has to be greater than 0.",
+        ));
+    }
+    Ok(max_consecutive_failures)
+}
+
+impl TryFrom<StoppedOutputFrames> for pipeline::encoder::StoppedOutputFrames {
+    type Error = TypeError;
+
+    fn try_from(value: StoppedOutputFrames) -> Result<Self, Self::Error> {
+        let result = match value {
+            StoppedOutputFrames::Nothing => Self::Nothing,
+            StoppedOutputFrames::FreezeFrame => Self::FreezeFrame,
+            StoppedOutputFrames::Slate { color_rgb } => Self::Slate {
+                color: slate_color(color_rgb)?,
+            },
+        };
+        Ok(result)
+    }
+}
+
+impl TryFrom<SlateContent> for scene::slate::SlateContent {
+    type Error = TypeError;
+
+    fn try_from(value: SlateContent) -> Result<Self, Self::Error> {
+        let result = match value {
+            SlateContent::Color { color_rgb } => Self::Color(slate_color(color_rgb)?),
+            SlateContent::Image { image_id } => Self::Image(image_id.into()),
+        };
+        Ok(result)
+    }
+}
+
+/// `None` if the slate is turned off.
+impl From<SlateMode> for Option<scene::slate::SlateMode> {
+    fn from(value: SlateMode) -> Self {
+        match value {
+            SlateMode::On => Some(scene::slate::SlateMode::On),
+            SlateMode::Off => None,
+            SlateMode::WhenInputsMissing => Some(scene::slate::SlateMode::WhenInputsMissing),
+        }
+    }
+}
+
+impl TryFrom<TimelineEntry> for pipeline::TimelineEntry {
+    type Error = TypeError;
+
+    fn try_from(entry: TimelineEntry) -> Result<Self, Self::Error> {
+        let transition = entry
+            .transition
+            .map(|transition| -> Result<_, TypeError> {
+                Ok(pipeline::EntryTransition {
+                    duration: Duration::try_from_secs_f64(transition.duration_ms / 1000.0)?,
+                    interpolation: transition
+                        .interpolation
+                        .map(Into::into)
+                        .unwrap_or(scene::transition::Interpolation::Linear),
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            scene: Arc::new(entry.scene.try_into()?),
+            duration: Duration::try_from_secs_f64(entry.duration_ms / 1000.0)?,
+            transition,
+        })
+    }
+}
+
+impl TryFrom<PreStartOutput> for pipeline::PreStartOutput {
+    type Error = TypeError;
+
+    fn try_from(value: PreStartOutput) -> Result<Self, Self::Error> {
+        let result = match value {
+            PreStartOutput::Nothing => Self::Nothing,
+            PreStartOutput::BlackFrames => Self::BlackFrames,
+            PreStartOutput::Slate { color_rgb } => Self::Slate {
+                color: color_rgb.try_into()?,
+            },
         };
         Ok(result)
     }
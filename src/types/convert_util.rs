@@ -83,7 +83,7 @@ impl TryFrom<Framerate> for compositor_common::Framerate {
     type Error = TypeError;
 
     fn try_from(framerate: Framerate) -> Result<Self, Self::Error> {
-        const ERROR_MESSAGE: &str = "Framerate needs to be an unsigned integer or a string in the \"NUM/DEN\" format, where NUM and DEN are both unsigned integers.";
+        const ERROR_MESSAGE: &str = "Framerate needs to be a positive integer or a string in the \"NUM/DEN\" format, where NUM and DEN are both positive integers.";
         match framerate {
             Framerate::String(text) => {
                 let Some((num_str, den_str)) = text.split_once('/') else {
@@ -95,8 +95,12 @@ impl TryFrom<Framerate> for compositor_common::Framerate {
                 let den = den_str
                     .parse::<u32>()
                     .or(Err(TypeError::new(ERROR_MESSAGE)))?;
+                if num == 0 || den == 0 {
+                    return Err(TypeError::new(ERROR_MESSAGE));
+                }
                 Ok(compositor_common::Framerate { num, den })
             }
+            Framerate::U32(0) => Err(TypeError::new(ERROR_MESSAGE)),
             Framerate::U32(num) => Ok(compositor_common::Framerate { num, den: 1 }),
         }
     }
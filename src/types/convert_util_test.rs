@@ -1,5 +1,5 @@
 use crate::types::{
-    util::{Coord, RGBAColor, RGBColor},
+    util::{Coord, Framerate, RGBAColor, RGBColor},
     TypeError,
 };
 use compositor_common::util::{colors, coord};
@@ -106,3 +106,30 @@ fn test_coords_deserialization() {
     test_case_str("-1-337%", Err(TypeError::new(ERROR_MESSAGE.to_string())));
     test_case_str("1x", Err(TypeError::new(ERROR_MESSAGE.to_string())));
 }
+
+#[test]
+fn test_framerate_deserialization() {
+    fn test_case(framerate: Framerate, expected: Result<compositor_common::Framerate, TypeError>) {
+        assert_eq!(compositor_common::Framerate::try_from(framerate), expected);
+    }
+    let error = || {
+        Err(TypeError::new(
+            "Framerate needs to be a positive integer or a string in the \"NUM/DEN\" format, where NUM and DEN are both positive integers.",
+        ))
+    };
+
+    test_case(
+        Framerate::U32(30),
+        Ok(compositor_common::Framerate { num: 30, den: 1 }),
+    );
+    test_case(
+        Framerate::String("30000/1001".to_string()),
+        Ok(compositor_common::Framerate {
+            num: 30000,
+            den: 1001,
+        }),
+    );
+    test_case(Framerate::U32(0), error());
+    test_case(Framerate::String("30/0".to_string()), error());
+    test_case(Framerate::String("30".to_string()), error());
+}
@@ -1,15 +1,15 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 use compositor_common::{
     scene::{
         self,
         builtin_transformations::{
             self, tiled_layout::TiledLayoutSpec, BuiltinSpec, FixedPositionLayoutSpec,
-            HorizontalPosition, VerticalPosition,
+            HorizontalPosition, PerspectiveSpec, Transform2dSpec, VerticalPosition,
         },
-        shader,
+        captions, replay, shader,
         text_spec::{self, TextSpec},
-        transition, NodeSpec, MAX_NODE_RESOLUTION,
+        timecode, transition, NodeSpec, MAX_NODE_RESOLUTION,
     },
     util::colors::{self, RGBAColor},
 };
@@ -23,25 +23,47 @@ impl TryFrom<Node> for NodeSpec {
     fn try_from(node: Node) -> Result<Self, Self::Error> {
         let params = match node.params {
             NodeParams::WebRenderer(node) => node.into(),
-            NodeParams::Shader(node) => node.into(),
+            NodeParams::Shader(node) => node.try_into()?,
             NodeParams::Image(node) => node.into(),
             NodeParams::Text(node) => node.try_into()?,
+            NodeParams::Captions(node) => node.try_into()?,
+            NodeParams::Timecode(node) => node.try_into()?,
+            NodeParams::Replay(node) => scene::NodeParams::Replay(node.into()),
             NodeParams::Transition(node) => node.try_into()?,
             NodeParams::FixedPositionLayout(node) => scene::NodeParams::Builtin(node.try_into()?),
             NodeParams::TiledLayout(node) => scene::NodeParams::Builtin(node.try_into()?),
             NodeParams::MirrorImage(node) => scene::NodeParams::Builtin(node.into()),
             NodeParams::CornersRounding(node) => scene::NodeParams::Builtin(node.try_into()?),
+            NodeParams::PrivacyMask(node) => scene::NodeParams::Builtin(node.try_into()?),
+            NodeParams::Stylize(node) => scene::NodeParams::Builtin(node.into()),
+            NodeParams::Scope(node) => scene::NodeParams::Builtin(node.into()),
+            NodeParams::Transform2d(node) => scene::NodeParams::Builtin(node.try_into()?),
+            NodeParams::Perspective(node) => scene::NodeParams::Builtin(node.try_into()?),
+            NodeParams::Border(node) => scene::NodeParams::Builtin(node.try_into()?),
             NodeParams::FitToResolution(node) => scene::NodeParams::Builtin(node.try_into()?),
-            NodeParams::FillToResolution { resolution } => {
-                scene::NodeParams::Builtin(BuiltinSpec::FillToResolution {
-                    resolution: resolution.into(),
-                })
-            }
-            NodeParams::StretchToResolution { resolution } => {
-                scene::NodeParams::Builtin(BuiltinSpec::StretchToResolution {
-                    resolution: resolution.into(),
-                })
+            NodeParams::FillToResolution {
+                resolution,
+                scaling_quality,
+            } => scene::NodeParams::Builtin(BuiltinSpec::FillToResolution {
+                resolution: resolution.into(),
+                scaling_quality: scaling_quality.map(Into::into).unwrap_or_default(),
+            }),
+            NodeParams::StretchToResolution {
+                resolution,
+                scaling_quality,
+            } => scene::NodeParams::Builtin(BuiltinSpec::StretchToResolution {
+                resolution: resolution.into(),
+                scaling_quality: scaling_quality.map(Into::into).unwrap_or_default(),
+            }),
+        };
+        let input_fallback_timeout = match node.input_fallback_timeout_ms {
+            Some(timeout_ms) if !(timeout_ms >= 0.0 && timeout_ms.is_finite()) => {
+                return Err(TypeError::new(
+                    "\"input_fallback_timeout_ms\" has to be a non-negative number.",
+                ));
             }
+            Some(timeout_ms) => Some(Duration::try_from_secs_f64(timeout_ms / 1000.0)?),
+            None => None,
         };
         let spec = Self {
             node_id: node.node_id.into(),
@@ -52,6 +74,7 @@ impl TryFrom<Node> for NodeSpec {
                 .map(Into::into)
                 .collect(),
             fallback_id: node.fallback_id.map(Into::into),
+            input_fallback_timeout,
             params,
         };
         Ok(spec)
@@ -66,35 +89,152 @@ impl From<WebRenderer> for scene::NodeParams {
     }
 }
 
-impl From<Shader> for scene::NodeParams {
-    fn from(node: Shader) -> Self {
-        Self::Shader {
+impl TryFrom<Shader> for scene::NodeParams {
+    type Error = TypeError;
+
+    fn try_from(node: Shader) -> Result<Self, Self::Error> {
+        Ok(Self::Shader {
             shader_id: node.shader_id.into(),
-            shader_params: node.shader_params.map(Into::into),
+            shader_params: node.shader_params.map(TryInto::try_into).transpose()?,
+            storage_params: node.storage_params.map(TryInto::try_into).transpose()?,
+            image_ids: node
+                .image_ids
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
             resolution: node.resolution.into(),
+            sampler: node.sampler.map(Into::into).unwrap_or_default(),
+            geometry: node.geometry.map(Into::into).unwrap_or_default(),
+        })
+    }
+}
+
+impl From<ShaderGeometry> for shader::ShaderGeometry {
+    fn from(geometry: ShaderGeometry) -> Self {
+        match geometry {
+            ShaderGeometry::Mesh { mesh_id } => Self::Mesh(mesh_id.into()),
+            ShaderGeometry::Procedural { vertex_count } => Self::Procedural { vertex_count },
         }
     }
 }
 
-impl From<ShaderParam> for scene::shader::ShaderParam {
-    fn from(param: ShaderParam) -> Self {
-        fn from_struct_field(field: ShaderParamStructField) -> shader::ShaderParamStructField {
-            shader::ShaderParamStructField {
+impl From<Sampler> for shader::SamplerSpec {
+    fn from(sampler: Sampler) -> Self {
+        let default = Self::default();
+        let filter = match sampler.filter {
+            Some(SamplerFilter::Linear) => shader::SamplerFilter::Linear,
+            Some(SamplerFilter::Nearest) => shader::SamplerFilter::Nearest,
+            None => default.filter,
+        };
+        let address_mode = match sampler.address_mode {
+            Some(SamplerAddressMode::ClampToEdge) => shader::SamplerAddressMode::ClampToEdge,
+            Some(SamplerAddressMode::Repeat) => shader::SamplerAddressMode::Repeat,
+            Some(SamplerAddressMode::MirrorRepeat) => shader::SamplerAddressMode::MirrorRepeat,
+            None => default.address_mode,
+        };
+        Self {
+            filter,
+            address_mode,
+            anisotropy: sampler.anisotropy.unwrap_or(default.anisotropy),
+        }
+    }
+}
+
+fn from_object(
+    fields: HashMap<String, serde_json::Value>,
+) -> Result<HashMap<String, shader::ShaderParamValue>, TypeError> {
+    fields
+        .into_iter()
+        .map(|(name, value)| {
+            let value = shader_param_value(value).map_err(|err| {
+                TypeError::new(format!(
+                    "Invalid value of shader param field \"{name}\". {err}"
+                ))
+            })?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn shader_param_value(value: serde_json::Value) -> Result<shader::ShaderParamValue, TypeError> {
+    match value {
+        serde_json::Value::Number(number) => match number.as_f64() {
+            Some(number) => Ok(shader::ShaderParamValue::Number(number)),
+            None => Err(TypeError::new(format!("Number {number} is out of range."))),
+        },
+        serde_json::Value::Array(list) => Ok(shader::ShaderParamValue::List(
+            list.into_iter()
+                .map(shader_param_value)
+                .collect::<Result<_, _>>()?,
+        )),
+        serde_json::Value::Object(fields) if fields.contains_key("type") => {
+            let param: ShaderParam = serde_json::from_value(serde_json::Value::Object(fields))?;
+            Ok(shader::ShaderParamValue::Typed(param.try_into()?))
+        }
+        serde_json::Value::Object(fields) => Ok(shader::ShaderParamValue::Object(from_object(
+            fields.into_iter().collect(),
+        )?)),
+        serde_json::Value::Null | serde_json::Value::Bool(_) | serde_json::Value::String(_) => {
+            Err(TypeError::new("Expected a number, a list or an object."))
+        }
+    }
+}
+
+impl TryFrom<ShaderParam> for scene::shader::ShaderParam {
+    type Error = TypeError;
+
+    fn try_from(param: ShaderParam) -> Result<Self, Self::Error> {
+        fn from_struct_field(
+            field: ShaderParamStructField,
+        ) -> Result<shader::ShaderParamStructField, TypeError> {
+            Ok(shader::ShaderParamStructField {
                 field_name: field.field_name,
-                value: field.value.into(),
-            }
+                value: field.value.try_into()?,
+            })
+        }
+        fn from_keyframe(
+            keyframe: ShaderParamKeyframe,
+        ) -> Result<shader::ShaderParamKeyframe, TypeError> {
+            Ok(shader::ShaderParamKeyframe {
+                time: Duration::try_from_secs_f64(keyframe.time_ms / 1000.0)?,
+                value: keyframe.value,
+                interpolation: keyframe
+                    .interpolation
+                    .map(Into::into)
+                    .unwrap_or(transition::Interpolation::Linear),
+            })
         }
-        match param {
+        let result = match param {
             ShaderParam::F32(v) => shader::ShaderParam::F32(v),
             ShaderParam::U32(v) => shader::ShaderParam::U32(v),
             ShaderParam::I32(v) => shader::ShaderParam::I32(v),
-            ShaderParam::List(v) => {
-                shader::ShaderParam::List(v.into_iter().map(Into::into).collect())
-            }
-            ShaderParam::Struct(v) => {
-                shader::ShaderParam::Struct(v.into_iter().map(from_struct_field).collect())
+            ShaderParam::List(v) => shader::ShaderParam::List(
+                v.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+            ),
+            ShaderParam::Struct(v) => shader::ShaderParam::Struct(
+                v.into_iter()
+                    .map(from_struct_field)
+                    .collect::<Result<_, _>>()?,
+            ),
+            ShaderParam::Keyframes(v) => {
+                let keyframes = v
+                    .into_iter()
+                    .map(from_keyframe)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if keyframes.is_empty() {
+                    return Err(TypeError::new("Keyframes list can not be empty."));
+                }
+                if keyframes.windows(2).any(|pair| pair[0].time > pair[1].time) {
+                    return Err(TypeError::new("Keyframes have to be sorted by time."));
+                }
+                shader::ShaderParam::Keyframes(keyframes)
             }
-        }
+            ShaderParam::Object(fields) => shader::ShaderParam::Object(from_object(fields)?),
+        };
+        Ok(result)
     }
 }
 
@@ -102,6 +242,7 @@ impl From<Image> for scene::NodeParams {
     fn from(node: Image) -> Self {
         Self::Image {
             image_id: node.image_id.into(),
+            resolution: node.resolution.map(Into::into),
         }
     }
 }
@@ -122,18 +263,10 @@ impl TryFrom<Text> for scene::NodeParams {
             Some(TextWrapMode::Glyph) => text_spec::Wrap::Glyph,
             None => text_spec::Wrap::None,
         };
-        let weight = match node.weight {
-            Some(TextWeight::Thin) => text_spec::Weight::Thin,
-            Some(TextWeight::ExtraLight) => text_spec::Weight::ExtraLight,
-            Some(TextWeight::Light) => text_spec::Weight::Light,
-            Some(TextWeight::Normal) => text_spec::Weight::Normal,
-            Some(TextWeight::Medium) => text_spec::Weight::Medium,
-            Some(TextWeight::SemiBold) => text_spec::Weight::SemiBold,
-            Some(TextWeight::Bold) => text_spec::Weight::Bold,
-            Some(TextWeight::ExtraBold) => text_spec::Weight::ExtraBold,
-            Some(TextWeight::Black) => text_spec::Weight::Black,
-            None => text_spec::Weight::Normal,
-        };
+        let weight = node
+            .weight
+            .map(Into::into)
+            .unwrap_or(text_spec::Weight::Normal);
         let dimensions = match node.dimensions {
             TextDimensions::Fitted {
                 max_width,
@@ -175,6 +308,107 @@ impl TryFrom<Text> for scene::NodeParams {
     }
 }
 
+impl From<TextWeight> for text_spec::Weight {
+    fn from(weight: TextWeight) -> Self {
+        match weight {
+            TextWeight::Thin => Self::Thin,
+            TextWeight::ExtraLight => Self::ExtraLight,
+            TextWeight::Light => Self::Light,
+            TextWeight::Normal => Self::Normal,
+            TextWeight::Medium => Self::Medium,
+            TextWeight::SemiBold => Self::SemiBold,
+            TextWeight::Bold => Self::Bold,
+            TextWeight::ExtraBold => Self::ExtraBold,
+            TextWeight::Black => Self::Black,
+        }
+    }
+}
+
+impl TryFrom<Captions> for scene::NodeParams {
+    type Error = TypeError;
+
+    fn try_from(node: Captions) -> Result<Self, Self::Error> {
+        let source = match node.source {
+            CaptionsSource::File { path } => captions::CaptionsSource::File { path },
+            CaptionsSource::Url {
+                url,
+                refresh_interval_ms,
+            } => {
+                let refresh_interval_ms = refresh_interval_ms.unwrap_or(1000.0);
+                if refresh_interval_ms <= 0.0 {
+                    return Err(TypeError::new(
+                        "Captions refresh interval has to be a positive number.",
+                    ));
+                }
+                captions::CaptionsSource::Url {
+                    url,
+                    refresh_interval: Duration::try_from_secs_f64(refresh_interval_ms / 1000.0)?,
+                }
+            }
+        };
+        let resolution: scene::Resolution = node.resolution.into();
+        let position = match node.position {
+            Some(CaptionsPosition::Top) => captions::CaptionsPosition::Top,
+            Some(CaptionsPosition::Bottom) | None => captions::CaptionsPosition::Bottom,
+        };
+        Ok(Self::Captions(captions::CaptionsSpec {
+            source,
+            resolution,
+            font_size: node.font_size,
+            line_height: node.line_height,
+            font_family: node.font_family.unwrap_or_else(|| String::from("Verdana")),
+            weight: node
+                .weight
+                .map(Into::into)
+                .unwrap_or(text_spec::Weight::Normal),
+            color_rgba: node
+                .color_rgba
+                .map(TryInto::try_into)
+                .unwrap_or(Ok(colors::RGBAColor(255, 255, 255, 255)))?,
+            align: node.align.unwrap_or(HorizontalAlign::Center).into(),
+            position,
+            safe_area_margin: node
+                .safe_area_margin
+                .unwrap_or(resolution.height as u32 / 20),
+            offset: Duration::try_from_secs_f64(node.offset_ms.unwrap_or(0.0) / 1000.0)?,
+        }))
+    }
+}
+
+impl TryFrom<Timecode> for scene::NodeParams {
+    type Error = TypeError;
+
+    fn try_from(node: Timecode) -> Result<Self, Self::Error> {
+        let source = match node.source {
+            Some(TimecodeSource::Pts) | None => timecode::TimecodeSource::Pts,
+            Some(TimecodeSource::WallClock) => timecode::TimecodeSource::WallClock,
+        };
+        Ok(Self::Timecode(timecode::TimecodeSpec {
+            source,
+            resolution: node.resolution.into(),
+            font_size: node.font_size,
+            font_family: node.font_family.unwrap_or_else(|| String::from("Verdana")),
+            color_rgba: node
+                .color_rgba
+                .map(TryInto::try_into)
+                .unwrap_or(Ok(colors::RGBAColor(255, 255, 255, 255)))?,
+            background_color_rgba: node
+                .background_color_rgba
+                .map(TryInto::try_into)
+                .unwrap_or(Ok(colors::RGBAColor(0, 0, 0, 0)))?,
+            align: node.align.unwrap_or(HorizontalAlign::Center).into(),
+        }))
+    }
+}
+
+impl From<Replay> for replay::ReplaySpec {
+    fn from(node: Replay) -> Self {
+        Self {
+            input_id: node.input_id.into(),
+        }
+    }
+}
+
 impl TryFrom<FitToResolution> for BuiltinSpec {
     type Error = TypeError;
 
@@ -193,6 +427,8 @@ impl TryFrom<FitToResolution> for BuiltinSpec {
                 .vertical_alignment
                 .unwrap_or(VerticalAlign::Center)
                 .into(),
+            mipmaps: node.mipmaps.unwrap_or(false),
+            scaling_quality: node.scaling_quality.map(Into::into).unwrap_or_default(),
         });
         Ok(result)
     }
@@ -218,6 +454,8 @@ impl TryFrom<TransitionState> for BuiltinSpec {
     fn try_from(state: TransitionState) -> Result<Self, Self::Error> {
         match state {
             TransitionState::FixedPositionLayout(state) => state.try_into(),
+            TransitionState::Transform2d(state) => state.try_into(),
+            TransitionState::Perspective(state) => state.try_into(),
         }
     }
 }
@@ -246,6 +484,7 @@ impl TryFrom<FixedPositionLayout> for BuiltinSpec {
                 .background_color_rgba
                 .map(TryInto::try_into)
                 .unwrap_or(Ok(colors::RGBAColor(0, 0, 0, 0)))?,
+            mipmaps: node.mipmaps.unwrap_or(false),
         });
         Ok(result)
     }
@@ -281,6 +520,52 @@ impl TryFrom<TextureLayout> for builtin_transformations::TextureLayout {
     }
 }
 
+impl TryFrom<Transform2d> for BuiltinSpec {
+    type Error = TypeError;
+
+    fn try_from(node: Transform2d) -> Result<Self, Self::Error> {
+        let scale = node.scale.unwrap_or(1.0);
+        let result = Self::Transform2d(Transform2dSpec {
+            resolution: node.resolution.map(Into::into),
+            rotation: node.rotation.unwrap_or(Degree(0.0)).into(),
+            scale_x: node.scale_x.unwrap_or(scale),
+            scale_y: node.scale_y.unwrap_or(scale),
+            translate_x: node.translate_x.unwrap_or(Coord::Number(0)).try_into()?,
+            translate_y: node.translate_y.unwrap_or(Coord::Number(0)).try_into()?,
+            anchor: (node.anchor_x.unwrap_or(0.5), node.anchor_y.unwrap_or(0.5)),
+            background_color_rgba: node
+                .background_color_rgba
+                .map(TryInto::try_into)
+                .unwrap_or(Ok(colors::RGBAColor(0, 0, 0, 0)))?,
+        });
+        Ok(result)
+    }
+}
+
+impl TryFrom<Perspective> for BuiltinSpec {
+    type Error = TypeError;
+
+    fn try_from(node: Perspective) -> Result<Self, Self::Error> {
+        let result = Self::Perspective(PerspectiveSpec {
+            resolution: node.resolution.map(Into::into),
+            rotation_x: node.rotation_x.unwrap_or(Degree(0.0)).into(),
+            rotation_y: node.rotation_y.unwrap_or(Degree(0.0)).into(),
+            rotation_z: node.rotation_z.unwrap_or(Degree(0.0)).into(),
+            distance: node.distance,
+            scale: node.scale.unwrap_or(1.0),
+            translate_x: node.translate_x.unwrap_or(Coord::Number(0)).try_into()?,
+            translate_y: node.translate_y.unwrap_or(Coord::Number(0)).try_into()?,
+            translate_z: node.translate_z.unwrap_or(0.0),
+            anchor: (node.anchor_x.unwrap_or(0.5), node.anchor_y.unwrap_or(0.5)),
+            background_color_rgba: node
+                .background_color_rgba
+                .map(TryInto::try_into)
+                .unwrap_or(Ok(colors::RGBAColor(0, 0, 0, 0)))?,
+        });
+        Ok(result)
+    }
+}
+
 impl TryFrom<TiledLayout> for BuiltinSpec {
     type Error = TypeError;
 
@@ -302,6 +587,7 @@ impl TryFrom<TiledLayout> for BuiltinSpec {
                 .vertical_alignment
                 .unwrap_or(VerticalAlign::Center)
                 .into(),
+            mipmaps: layout.mipmaps.unwrap_or(false),
         });
         Ok(result)
     }
@@ -331,3 +617,114 @@ impl TryFrom<CornersRounding> for BuiltinSpec {
         Ok(result)
     }
 }
+
+impl TryFrom<Border> for BuiltinSpec {
+    type Error = TypeError;
+
+    fn try_from(node: Border) -> Result<Self, Self::Error> {
+        let shadow = match node.shadow {
+            Some(shadow) => Some(builtin_transformations::BorderShadow {
+                color: shadow
+                    .color_rgba
+                    .map(TryInto::try_into)
+                    .unwrap_or(Ok(colors::RGBAColor(0, 0, 0, 128)))?,
+                blur_radius: shadow.blur_radius.unwrap_or(8),
+                offset_x: shadow.offset_x.unwrap_or(0),
+                offset_y: shadow.offset_y.unwrap_or(0),
+            }),
+            None => None,
+        };
+        let result = Self::Border(builtin_transformations::BorderSpec {
+            width: node.width.unwrap_or(2),
+            color: node
+                .color_rgba
+                .map(TryInto::try_into)
+                .unwrap_or(Ok(colors::RGBAColor(255, 255, 255, 255)))?,
+            corner_radius: node.corner_radius.unwrap_or(Coord::Number(0)).try_into()?,
+            shadow,
+        });
+        Ok(result)
+    }
+}
+
+impl TryFrom<PrivacyMask> for BuiltinSpec {
+    type Error = TypeError;
+
+    fn try_from(node: PrivacyMask) -> Result<Self, Self::Error> {
+        let effect = match node.effect {
+            PrivacyMaskEffect::Blur { radius } => {
+                builtin_transformations::PrivacyMaskEffect::Blur { radius }
+            }
+            PrivacyMaskEffect::Pixelate { block_size } => {
+                builtin_transformations::PrivacyMaskEffect::Pixelate { block_size }
+            }
+        };
+        let regions = node
+            .regions
+            .into_iter()
+            .map(|region| {
+                Ok(builtin_transformations::MaskRegion {
+                    top: region.top.try_into()?,
+                    left: region.left.try_into()?,
+                    width: region.width.try_into()?,
+                    height: region.height.try_into()?,
+                })
+            })
+            .collect::<Result<Vec<_>, TypeError>>()?;
+        Ok(Self::PrivacyMask(
+            builtin_transformations::PrivacyMaskSpec { effect, regions },
+        ))
+    }
+}
+
+impl From<Stylize> for BuiltinSpec {
+    fn from(node: Stylize) -> Self {
+        let effect = match node.effect {
+            StylizeEffect::EdgeDetect => builtin_transformations::StylizeEffect::EdgeDetect,
+            StylizeEffect::Posterize { levels } => {
+                builtin_transformations::StylizeEffect::Posterize { levels }
+            }
+            StylizeEffect::Vignette { strength } => {
+                builtin_transformations::StylizeEffect::Vignette {
+                    strength: strength.unwrap_or(0.5),
+                }
+            }
+            StylizeEffect::FilmGrain { strength } => {
+                builtin_transformations::StylizeEffect::FilmGrain {
+                    strength: strength.unwrap_or(0.1),
+                }
+            }
+            StylizeEffect::ChromaticAberration { offset } => {
+                builtin_transformations::StylizeEffect::ChromaticAberration {
+                    offset: offset.unwrap_or(4),
+                }
+            }
+        };
+        Self::Stylize { effect }
+    }
+}
+
+impl From<Scope> for BuiltinSpec {
+    fn from(node: Scope) -> Self {
+        let mode = match node.mode {
+            ScopeMode::LumaWaveform => builtin_transformations::ScopeMode::LumaWaveform,
+            ScopeMode::RgbParade => builtin_transformations::ScopeMode::RgbParade,
+            ScopeMode::Vectorscope => builtin_transformations::ScopeMode::Vectorscope,
+        };
+        Self::Scope {
+            mode,
+            resolution: node.resolution.into(),
+        }
+    }
+}
+
+impl From<ScalingQuality> for builtin_transformations::ScalingQuality {
+    fn from(quality: ScalingQuality) -> Self {
+        match quality {
+            ScalingQuality::Nearest => Self::Nearest,
+            ScalingQuality::Bilinear => Self::Bilinear,
+            ScalingQuality::Bicubic => Self::Bicubic,
+            ScalingQuality::Lanczos => Self::Lanczos,
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use compositor_chromium::cef;
 use compositor_common::{
     renderer_spec,
     scene::constraints::{self, input_count},
@@ -20,6 +21,16 @@ impl From<FallbackStrategy> for renderer_spec::FallbackStrategy {
     }
 }
 
+impl From<WebCompositingMode> for renderer_spec::WebCompositingMode {
+    fn from(mode: WebCompositingMode) -> Self {
+        match mode {
+            WebCompositingMode::Embed => renderer_spec::WebCompositingMode::Embed,
+            WebCompositingMode::Overlay => renderer_spec::WebCompositingMode::Overlay,
+            WebCompositingMode::Underlay => renderer_spec::WebCompositingMode::Underlay,
+        }
+    }
+}
+
 impl TryFrom<NodeConstraints> for constraints::NodeConstraints {
     type Error = TypeError;
 
@@ -66,7 +77,15 @@ impl TryFrom<ShaderSpec> for renderer_spec::RendererSpec {
     type Error = TypeError;
 
     fn try_from(spec: ShaderSpec) -> Result<Self, Self::Error> {
-        let spec = renderer_spec::ShaderSpec {
+        Ok(Self::Shader(spec.try_into()?))
+    }
+}
+
+impl TryFrom<ShaderSpec> for renderer_spec::ShaderSpec {
+    type Error = TypeError;
+
+    fn try_from(spec: ShaderSpec) -> Result<Self, Self::Error> {
+        let spec = Self {
             shader_id: spec.shader_id.into(),
             source: spec.source,
             fallback_strategy: spec
@@ -84,7 +103,7 @@ impl TryFrom<ShaderSpec> for renderer_spec::RendererSpec {
                 })
                 .try_into()?,
         };
-        Ok(Self::Shader(spec))
+        Ok(spec)
     }
 }
 
@@ -92,9 +111,21 @@ impl TryFrom<WebRendererSpec> for renderer_spec::RendererSpec {
     type Error = TypeError;
 
     fn try_from(spec: WebRendererSpec) -> Result<Self, Self::Error> {
+        let src = match (spec.url, spec.path) {
+            (None, None) => {
+                return Err(TypeError::new(
+                    "\"url\" or \"path\" field is required when registering a web renderer.",
+                ))
+            }
+            (None, Some(path)) => renderer_spec::WebRendererSrc::LocalPath { path },
+            (Some(url), None) => renderer_spec::WebRendererSrc::Url { url },
+            (Some(_), Some(_)) => return Err(TypeError::new(
+                "\"url\" and \"path\" fields are mutually exclusive when registering a web renderer.",
+            )),
+        };
         let spec = renderer_spec::WebRendererSpec {
             instance_id: spec.instance_id.into(),
-            url: spec.url,
+            src,
             resolution: spec.resolution.into(),
             fallback_strategy: spec
                 .fallback_strategy
@@ -110,11 +141,48 @@ impl TryFrom<WebRendererSpec> for renderer_spec::RendererSpec {
                     })])
                 })
                 .try_into()?,
+            transparent_background: spec.transparent_background.unwrap_or(false),
+            mute_audio: spec.mute_audio.unwrap_or(false),
+            compositing_mode: spec
+                .compositing_mode
+                .map(Into::into)
+                .unwrap_or(renderer_spec::WebCompositingMode::Embed),
         };
         Ok(Self::WebRenderer(spec))
     }
 }
 
+impl From<NineSliceInsets> for renderer_spec::NineSliceInsets {
+    fn from(insets: NineSliceInsets) -> Self {
+        Self {
+            top: insets.top,
+            right: insets.right,
+            bottom: insets.bottom,
+            left: insets.left,
+        }
+    }
+}
+
+impl From<MeshSpec> for renderer_spec::RendererSpec {
+    fn from(spec: MeshSpec) -> Self {
+        let vertices = spec
+            .vertices
+            .into_iter()
+            .map(|vertex| renderer_spec::MeshVertex {
+                position: vertex.position,
+                texture_coords: vertex.texture_coords.unwrap_or_default(),
+                input_id: vertex.input_id.unwrap_or(-1),
+                data: vertex.data.unwrap_or_default(),
+            })
+            .collect();
+        Self::Mesh(renderer_spec::MeshSpec {
+            mesh_id: spec.mesh_id.into(),
+            vertices,
+            indices: spec.indices,
+        })
+    }
+}
+
 impl TryFrom<ImageSpec> for renderer_spec::RendererSpec {
     type Error = TypeError;
 
@@ -134,24 +202,46 @@ impl TryFrom<ImageSpec> for renderer_spec::RendererSpec {
                 )),
             }
         }
+        fn animation_options(
+            loop_count: Option<u32>,
+            hold_last_frame: Option<bool>,
+        ) -> Result<renderer_spec::AnimationOptions, TypeError> {
+            if loop_count == Some(0) {
+                return Err(TypeError::new(
+                    "\"loop_count\" has to be a positive integer when registering an image.",
+                ));
+            }
+            Ok(renderer_spec::AnimationOptions {
+                loop_count,
+                hold_last_frame: hold_last_frame.unwrap_or(true),
+            })
+        }
         let image = match spec {
             ImageSpec::Png {
                 image_id,
                 url,
                 path,
+                loop_count,
+                hold_last_frame,
+                nine_slice,
             } => renderer_spec::ImageSpec {
                 src: from_url_or_path(url, path)?,
                 image_id: image_id.into(),
-                image_type: renderer_spec::ImageType::Png,
+                image_type: renderer_spec::ImageType::Png {
+                    animation: animation_options(loop_count, hold_last_frame)?,
+                },
+                nine_slice: nine_slice.map(Into::into),
             },
             ImageSpec::Jpeg {
                 image_id,
                 url,
                 path,
+                nine_slice,
             } => renderer_spec::ImageSpec {
                 src: from_url_or_path(url, path)?,
                 image_id: image_id.into(),
                 image_type: renderer_spec::ImageType::Jpeg,
+                nine_slice: nine_slice.map(Into::into),
             },
             ImageSpec::Svg {
                 image_id,
@@ -164,17 +254,120 @@ impl TryFrom<ImageSpec> for renderer_spec::RendererSpec {
                 image_type: renderer_spec::ImageType::Svg {
                     resolution: resolution.map(Into::into),
                 },
+                nine_slice: None,
             },
             ImageSpec::Gif {
                 image_id,
                 url,
                 path,
+                loop_count,
+                hold_last_frame,
+                nine_slice,
             } => renderer_spec::ImageSpec {
                 src: from_url_or_path(url, path)?,
                 image_id: image_id.into(),
-                image_type: renderer_spec::ImageType::Gif,
+                image_type: renderer_spec::ImageType::Gif {
+                    animation: animation_options(loop_count, hold_last_frame)?,
+                },
+                nine_slice: nine_slice.map(Into::into),
+            },
+            ImageSpec::Webp {
+                image_id,
+                url,
+                path,
+                loop_count,
+                hold_last_frame,
+                nine_slice,
+            } => renderer_spec::ImageSpec {
+                src: from_url_or_path(url, path)?,
+                image_id: image_id.into(),
+                image_type: renderer_spec::ImageType::Webp {
+                    animation: animation_options(loop_count, hold_last_frame)?,
+                },
+                nine_slice: nine_slice.map(Into::into),
             },
         };
         Ok(Self::Image(image))
     }
 }
+
+/// Typed text is split into an event per character.
+impl From<WebRendererEvent> for Vec<compositor_render::WebRendererEvent> {
+    fn from(event: WebRendererEvent) -> Self {
+        use compositor_render::WebRendererEvent as Event;
+
+        let button = |button: Option<MouseButton>| match button.unwrap_or(MouseButton::Left) {
+            MouseButton::Left => cef::MouseButton::Left,
+            MouseButton::Middle => cef::MouseButton::Middle,
+            MouseButton::Right => cef::MouseButton::Right,
+        };
+        match event {
+            WebRendererEvent::MouseMove { x, y } => vec![Event::MouseMove { x, y }],
+            WebRendererEvent::MouseDown {
+                x,
+                y,
+                button: mouse_button,
+                click_count,
+            } => vec![Event::MouseDown {
+                x,
+                y,
+                button: button(mouse_button),
+                click_count: click_count.unwrap_or(1),
+            }],
+            WebRendererEvent::MouseUp {
+                x,
+                y,
+                button: mouse_button,
+                click_count,
+            } => vec![Event::MouseUp {
+                x,
+                y,
+                button: button(mouse_button),
+                click_count: click_count.unwrap_or(1),
+            }],
+            WebRendererEvent::MouseWheel {
+                x,
+                y,
+                delta_x,
+                delta_y,
+            } => vec![Event::MouseWheel {
+                x,
+                y,
+                delta_x: delta_x.unwrap_or(0),
+                delta_y: delta_y.unwrap_or(0),
+            }],
+            WebRendererEvent::KeyDown {
+                key_code,
+                modifiers,
+            } => vec![Event::KeyDown {
+                key_code,
+                modifiers: modifiers.map(Into::into).unwrap_or_default(),
+            }],
+            WebRendererEvent::KeyUp {
+                key_code,
+                modifiers,
+            } => vec![Event::KeyUp {
+                key_code,
+                modifiers: modifiers.map(Into::into).unwrap_or_default(),
+            }],
+            WebRendererEvent::Text { text } => text
+                .chars()
+                .map(|character| Event::Char {
+                    character,
+                    modifiers: cef::EventModifiers::default(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<KeyModifiers> for cef::EventModifiers {
+    fn from(modifiers: KeyModifiers) -> Self {
+        Self {
+            shift: modifiers.shift.unwrap_or(false),
+            control: modifiers.control.unwrap_or(false),
+            alt: modifiers.alt.unwrap_or(false),
+            command: modifiers.meta.unwrap_or(false),
+        }
+    }
+}
@@ -1,8 +1,12 @@
+use std::collections::HashMap;
+
 use compositor_common::scene;
 use compositor_common::scene::builtin_transformations;
 use compositor_common::scene::builtin_transformations::BuiltinSpec;
+use compositor_common::scene::captions;
 use compositor_common::scene::shader;
 use compositor_common::scene::text_spec;
+use compositor_common::scene::timecode;
 use compositor_common::scene::transition;
 use compositor_common::scene::NodeSpec;
 
@@ -20,15 +24,40 @@ impl From<NodeSpec> for Node {
             scene::NodeParams::Shader {
                 shader_id,
                 shader_params,
+                storage_params,
+                image_ids,
                 resolution,
+                sampler,
+                geometry,
             } => NodeParams::Shader(Shader {
                 shader_id: shader_id.into(),
                 shader_params: shader_params.map(Into::into),
+                storage_params: storage_params.map(Into::into),
+                image_ids: Some(image_ids.into_iter().map(Into::into).collect()),
                 resolution: resolution.into(),
+                sampler: Some(sampler.into()),
+                geometry: match geometry {
+                    shader::ShaderGeometry::InputPlanes => None,
+                    shader::ShaderGeometry::Mesh(mesh_id) => Some(ShaderGeometry::Mesh {
+                        mesh_id: mesh_id.into(),
+                    }),
+                    shader::ShaderGeometry::Procedural { vertex_count } => {
+                        Some(ShaderGeometry::Procedural { vertex_count })
+                    }
+                },
             }),
             scene::NodeParams::Text(spec) => NodeParams::Text(spec.into()),
-            scene::NodeParams::Image { image_id } => NodeParams::Image(Image {
+            scene::NodeParams::Captions(spec) => NodeParams::Captions(spec.into()),
+            scene::NodeParams::Timecode(spec) => NodeParams::Timecode(spec.into()),
+            scene::NodeParams::Replay(spec) => NodeParams::Replay(Replay {
+                input_id: spec.input_id.into(),
+            }),
+            scene::NodeParams::Image {
+                image_id,
+                resolution,
+            } => NodeParams::Image(Image {
                 image_id: image_id.into(),
+                resolution: resolution.map(Into::into),
             }),
             scene::NodeParams::Transition(spec) => NodeParams::Transition(spec.into()),
             scene::NodeParams::Builtin(transformation) => match transformation {
@@ -44,31 +73,55 @@ impl From<NodeSpec> for Node {
                         border_radius: border_radius.into(),
                     })
                 }
+                BuiltinSpec::PrivacyMask(spec) => NodeParams::PrivacyMask(spec.into()),
+                BuiltinSpec::Stylize { effect } => NodeParams::Stylize(Stylize {
+                    effect: effect.into(),
+                }),
+                BuiltinSpec::Scope { mode, resolution } => NodeParams::Scope(Scope {
+                    mode: mode.into(),
+                    resolution: resolution.into(),
+                }),
+                BuiltinSpec::Transform2d(spec) => NodeParams::Transform2d(spec.into()),
+                BuiltinSpec::Perspective(spec) => NodeParams::Perspective(spec.into()),
+                BuiltinSpec::Border(spec) => NodeParams::Border(spec.into()),
                 BuiltinSpec::FitToResolution(builtin_transformations::FitToResolutionSpec {
                     resolution,
                     background_color_rgba,
                     horizontal_alignment,
                     vertical_alignment,
+                    mipmaps,
+                    scaling_quality,
                 }) => NodeParams::FitToResolution(FitToResolution {
                     resolution: resolution.into(),
                     background_color_rgba: Some(background_color_rgba.into()),
                     horizontal_alignment: Some(horizontal_alignment.into()),
                     vertical_alignment: Some(vertical_alignment.into()),
+                    mipmaps: Some(mipmaps),
+                    scaling_quality: Some(scaling_quality.into()),
                 }),
-                BuiltinSpec::FillToResolution { resolution } => NodeParams::FillToResolution {
+                BuiltinSpec::FillToResolution {
+                    resolution,
+                    scaling_quality,
+                } => NodeParams::FillToResolution {
                     resolution: resolution.into(),
+                    scaling_quality: Some(scaling_quality.into()),
+                },
+                BuiltinSpec::StretchToResolution {
+                    resolution,
+                    scaling_quality,
+                } => NodeParams::StretchToResolution {
+                    resolution: resolution.into(),
+                    scaling_quality: Some(scaling_quality.into()),
                 },
-                BuiltinSpec::StretchToResolution { resolution } => {
-                    NodeParams::StretchToResolution {
-                        resolution: resolution.into(),
-                    }
-                }
             },
         };
         Self {
             node_id: node.node_id.into(),
             input_pads: Some(node.input_pads.into_iter().map(Into::into).collect()),
             fallback_id: node.fallback_id.map(Into::into),
+            input_fallback_timeout_ms: node
+                .input_fallback_timeout
+                .map(|timeout| timeout.as_secs_f64() * 1000.0),
             params,
         }
     }
@@ -92,6 +145,41 @@ impl From<shader::ShaderParam> for ShaderParam {
             shader::ShaderParam::Struct(value) => {
                 ShaderParam::Struct(value.into_iter().map(from_struct_field).collect())
             }
+            shader::ShaderParam::Keyframes(value) => ShaderParam::Keyframes(
+                value
+                    .into_iter()
+                    .map(|keyframe| ShaderParamKeyframe {
+                        time_ms: keyframe.time.as_secs_f64() * 1000.0,
+                        value: keyframe.value,
+                        interpolation: Some(keyframe.interpolation.into()),
+                    })
+                    .collect(),
+            ),
+            shader::ShaderParam::Object(fields) => ShaderParam::Object(into_object(fields)),
+        }
+    }
+}
+
+fn into_object(
+    fields: HashMap<String, shader::ShaderParamValue>,
+) -> HashMap<String, serde_json::Value> {
+    fields
+        .into_iter()
+        .map(|(name, value)| (name, shader_param_value(value)))
+        .collect()
+}
+
+fn shader_param_value(value: shader::ShaderParamValue) -> serde_json::Value {
+    match value {
+        shader::ShaderParamValue::Number(number) => number.into(),
+        shader::ShaderParamValue::List(list) => {
+            serde_json::Value::Array(list.into_iter().map(shader_param_value).collect())
+        }
+        shader::ShaderParamValue::Object(fields) => {
+            serde_json::Value::Object(into_object(fields).into_iter().collect())
+        }
+        shader::ShaderParamValue::Typed(param) => {
+            serde_json::to_value(ShaderParam::from(param)).unwrap_or(serde_json::Value::Null)
         }
     }
 }
@@ -108,17 +196,6 @@ impl From<text_spec::TextSpec> for Text {
             text_spec::Wrap::Glyph => TextWrapMode::Glyph,
             text_spec::Wrap::Word => TextWrapMode::Word,
         };
-        let weight = match spec.weight {
-            text_spec::Weight::Thin => TextWeight::Thin,
-            text_spec::Weight::ExtraLight => TextWeight::ExtraLight,
-            text_spec::Weight::Light => TextWeight::Light,
-            text_spec::Weight::Normal => TextWeight::Normal,
-            text_spec::Weight::Medium => TextWeight::Medium,
-            text_spec::Weight::SemiBold => TextWeight::SemiBold,
-            text_spec::Weight::Bold => TextWeight::Bold,
-            text_spec::Weight::ExtraBold => TextWeight::ExtraBold,
-            text_spec::Weight::Black => TextWeight::Black,
-        };
         Self {
             content: spec.content,
             font_size: spec.font_size,
@@ -130,7 +207,73 @@ impl From<text_spec::TextSpec> for Text {
             style: Some(style),
             align: Some(spec.align.into()),
             wrap: Some(wrap),
-            weight: Some(weight),
+            weight: Some(spec.weight.into()),
+        }
+    }
+}
+
+impl From<text_spec::Weight> for TextWeight {
+    fn from(weight: text_spec::Weight) -> Self {
+        match weight {
+            text_spec::Weight::Thin => Self::Thin,
+            text_spec::Weight::ExtraLight => Self::ExtraLight,
+            text_spec::Weight::Light => Self::Light,
+            text_spec::Weight::Normal => Self::Normal,
+            text_spec::Weight::Medium => Self::Medium,
+            text_spec::Weight::SemiBold => Self::SemiBold,
+            text_spec::Weight::Bold => Self::Bold,
+            text_spec::Weight::ExtraBold => Self::ExtraBold,
+            text_spec::Weight::Black => Self::Black,
+        }
+    }
+}
+
+impl From<captions::CaptionsSpec> for Captions {
+    fn from(spec: captions::CaptionsSpec) -> Self {
+        let source = match spec.source {
+            captions::CaptionsSource::File { path } => CaptionsSource::File { path },
+            captions::CaptionsSource::Url {
+                url,
+                refresh_interval,
+            } => CaptionsSource::Url {
+                url,
+                refresh_interval_ms: Some(refresh_interval.as_secs_f64() * 1000.0),
+            },
+        };
+        let position = match spec.position {
+            captions::CaptionsPosition::Top => CaptionsPosition::Top,
+            captions::CaptionsPosition::Bottom => CaptionsPosition::Bottom,
+        };
+        Self {
+            source,
+            resolution: spec.resolution.into(),
+            font_size: spec.font_size,
+            line_height: spec.line_height,
+            font_family: Some(spec.font_family),
+            weight: Some(spec.weight.into()),
+            color_rgba: Some(spec.color_rgba.into()),
+            align: Some(spec.align.into()),
+            position: Some(position),
+            safe_area_margin: Some(spec.safe_area_margin),
+            offset_ms: Some(spec.offset.as_secs_f64() * 1000.0),
+        }
+    }
+}
+
+impl From<timecode::TimecodeSpec> for Timecode {
+    fn from(spec: timecode::TimecodeSpec) -> Self {
+        let source = match spec.source {
+            timecode::TimecodeSource::Pts => TimecodeSource::Pts,
+            timecode::TimecodeSource::WallClock => TimecodeSource::WallClock,
+        };
+        Self {
+            source: Some(source),
+            resolution: spec.resolution.into(),
+            font_size: spec.font_size,
+            font_family: Some(spec.font_family),
+            color_rgba: Some(spec.color_rgba.into()),
+            background_color_rgba: Some(spec.background_color_rgba.into()),
+            align: Some(spec.align.into()),
         }
     }
 }
@@ -182,9 +325,15 @@ impl From<BuiltinSpec> for TransitionState {
     fn from(spec: BuiltinSpec) -> Self {
         match spec {
             BuiltinSpec::FixedPositionLayout(spec) => Self::FixedPositionLayout(spec.into()),
+            BuiltinSpec::Transform2d(spec) => Self::Transform2d(spec.into()),
+            BuiltinSpec::Perspective(spec) => Self::Perspective(spec.into()),
+            BuiltinSpec::Border(_) => panic!("not supported"),
             BuiltinSpec::TiledLayout(_) => panic!("not supported"),
             BuiltinSpec::MirrorImage { .. } => panic!("not supported"),
             BuiltinSpec::CornersRounding { .. } => panic!("not supported"),
+            BuiltinSpec::PrivacyMask(_) => panic!("not supported"),
+            BuiltinSpec::Stylize { .. } => panic!("not supported"),
+            BuiltinSpec::Scope { .. } => panic!("not supported"),
             BuiltinSpec::FitToResolution(_) => panic!("not supported"),
             BuiltinSpec::FillToResolution { .. } => panic!("not supported"),
             BuiltinSpec::StretchToResolution { .. } => panic!("not supported"),
@@ -226,6 +375,59 @@ impl From<builtin_transformations::FixedPositionLayoutSpec> for FixedPositionLay
                 .map(from_texture_layout)
                 .collect(),
             background_color_rgba: Some(spec.background_color_rgba.into()),
+            mipmaps: Some(spec.mipmaps),
+        }
+    }
+}
+
+impl From<builtin_transformations::Transform2dSpec> for Transform2d {
+    fn from(spec: builtin_transformations::Transform2dSpec) -> Self {
+        Self {
+            resolution: spec.resolution.map(Into::into),
+            rotation: Some(spec.rotation.into()),
+            scale: None,
+            scale_x: Some(spec.scale_x),
+            scale_y: Some(spec.scale_y),
+            translate_x: Some(spec.translate_x.into()),
+            translate_y: Some(spec.translate_y.into()),
+            anchor_x: Some(spec.anchor.0),
+            anchor_y: Some(spec.anchor.1),
+            background_color_rgba: Some(spec.background_color_rgba.into()),
+        }
+    }
+}
+
+impl From<builtin_transformations::PerspectiveSpec> for Perspective {
+    fn from(spec: builtin_transformations::PerspectiveSpec) -> Self {
+        Self {
+            resolution: spec.resolution.map(Into::into),
+            rotation_x: Some(spec.rotation_x.into()),
+            rotation_y: Some(spec.rotation_y.into()),
+            rotation_z: Some(spec.rotation_z.into()),
+            distance: spec.distance,
+            scale: Some(spec.scale),
+            translate_x: Some(spec.translate_x.into()),
+            translate_y: Some(spec.translate_y.into()),
+            translate_z: Some(spec.translate_z),
+            anchor_x: Some(spec.anchor.0),
+            anchor_y: Some(spec.anchor.1),
+            background_color_rgba: Some(spec.background_color_rgba.into()),
+        }
+    }
+}
+
+impl From<builtin_transformations::BorderSpec> for Border {
+    fn from(spec: builtin_transformations::BorderSpec) -> Self {
+        Self {
+            width: Some(spec.width),
+            color_rgba: Some(spec.color.into()),
+            corner_radius: Some(spec.corner_radius.into()),
+            shadow: spec.shadow.map(|shadow| BorderShadow {
+                color_rgba: Some(shadow.color.into()),
+                blur_radius: Some(shadow.blur_radius),
+                offset_x: Some(shadow.offset_x),
+                offset_y: Some(shadow.offset_y),
+            }),
         }
     }
 }
@@ -240,6 +442,18 @@ impl From<builtin_transformations::tiled_layout::TiledLayoutSpec> for TiledLayou
             padding: Some(layout.padding),
             horizontal_alignment: Some(layout.horizontal_alignment.into()),
             vertical_alignment: Some(layout.vertical_alignment.into()),
+            mipmaps: Some(layout.mipmaps),
+        }
+    }
+}
+
+impl From<builtin_transformations::ScalingQuality> for ScalingQuality {
+    fn from(quality: builtin_transformations::ScalingQuality) -> Self {
+        match quality {
+            builtin_transformations::ScalingQuality::Nearest => Self::Nearest,
+            builtin_transformations::ScalingQuality::Bilinear => Self::Bilinear,
+            builtin_transformations::ScalingQuality::Bicubic => Self::Bicubic,
+            builtin_transformations::ScalingQuality::Lanczos => Self::Lanczos,
         }
     }
 }
@@ -255,3 +469,78 @@ impl From<builtin_transformations::MirrorMode> for MirrorMode {
         }
     }
 }
+
+impl From<builtin_transformations::PrivacyMaskSpec> for PrivacyMask {
+    fn from(spec: builtin_transformations::PrivacyMaskSpec) -> Self {
+        let effect = match spec.effect {
+            builtin_transformations::PrivacyMaskEffect::Blur { radius } => {
+                PrivacyMaskEffect::Blur { radius }
+            }
+            builtin_transformations::PrivacyMaskEffect::Pixelate { block_size } => {
+                PrivacyMaskEffect::Pixelate { block_size }
+            }
+        };
+        let regions = spec
+            .regions
+            .into_iter()
+            .map(|region| MaskRegion {
+                top: region.top.into(),
+                left: region.left.into(),
+                width: region.width.into(),
+                height: region.height.into(),
+            })
+            .collect();
+        Self { effect, regions }
+    }
+}
+
+impl From<builtin_transformations::StylizeEffect> for StylizeEffect {
+    fn from(effect: builtin_transformations::StylizeEffect) -> Self {
+        match effect {
+            builtin_transformations::StylizeEffect::EdgeDetect => Self::EdgeDetect,
+            builtin_transformations::StylizeEffect::Posterize { levels } => {
+                Self::Posterize { levels }
+            }
+            builtin_transformations::StylizeEffect::Vignette { strength } => Self::Vignette {
+                strength: Some(strength),
+            },
+            builtin_transformations::StylizeEffect::FilmGrain { strength } => Self::FilmGrain {
+                strength: Some(strength),
+            },
+            builtin_transformations::StylizeEffect::ChromaticAberration { offset } => {
+                Self::ChromaticAberration {
+                    offset: Some(offset),
+                }
+            }
+        }
+    }
+}
+
+impl From<builtin_transformations::ScopeMode> for ScopeMode {
+    fn from(mode: builtin_transformations::ScopeMode) -> Self {
+        match mode {
+            builtin_transformations::ScopeMode::LumaWaveform => Self::LumaWaveform,
+            builtin_transformations::ScopeMode::RgbParade => Self::RgbParade,
+            builtin_transformations::ScopeMode::Vectorscope => Self::Vectorscope,
+        }
+    }
+}
+
+impl From<shader::SamplerSpec> for Sampler {
+    fn from(spec: shader::SamplerSpec) -> Self {
+        let filter = match spec.filter {
+            shader::SamplerFilter::Linear => SamplerFilter::Linear,
+            shader::SamplerFilter::Nearest => SamplerFilter::Nearest,
+        };
+        let address_mode = match spec.address_mode {
+            shader::SamplerAddressMode::ClampToEdge => SamplerAddressMode::ClampToEdge,
+            shader::SamplerAddressMode::Repeat => SamplerAddressMode::Repeat,
+            shader::SamplerAddressMode::MirrorRepeat => SamplerAddressMode::MirrorRepeat,
+        };
+        Self {
+            filter: Some(filter),
+            address_mode: Some(address_mode),
+            anisotropy: Some(spec.anisotropy),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use compositor_common::{
+    renderer_spec,
+    scene::constraints::{self, input_count},
+};
+
+use super::renderer::*;
+
+impl From<renderer_spec::FallbackStrategy> for FallbackStrategy {
+    fn from(strategy: renderer_spec::FallbackStrategy) -> Self {
+        match strategy {
+            renderer_spec::FallbackStrategy::NeverFallback => FallbackStrategy::NeverFallback,
+            renderer_spec::FallbackStrategy::FallbackIfAllInputsMissing => {
+                FallbackStrategy::FallbackIfAllInputsMissing
+            }
+            renderer_spec::FallbackStrategy::FallbackIfAnyInputMissing => {
+                FallbackStrategy::FallbackIfAnyInputMissing
+            }
+        }
+    }
+}
+
+impl From<constraints::NodeConstraints> for NodeConstraints {
+    fn from(constraints: constraints::NodeConstraints) -> Self {
+        Self(constraints.0.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<constraints::Constraint> for Constraint {
+    fn from(constraint: constraints::Constraint) -> Self {
+        match constraint {
+            constraints::Constraint::InputCount(constraint) => {
+                let constraint = match constraint {
+                    input_count::InputCountConstraint::Exact { fixed_count } => {
+                        InputCountConstraint {
+                            fixed_count: Some(fixed_count),
+                            lower_bound: None,
+                            upper_bound: None,
+                        }
+                    }
+                    input_count::InputCountConstraint::Range {
+                        lower_bound,
+                        upper_bound,
+                    } => InputCountConstraint {
+                        fixed_count: None,
+                        lower_bound: Some(lower_bound),
+                        upper_bound: Some(upper_bound),
+                    },
+                };
+                Self::InputCount(constraint)
+            }
+        }
+    }
+}
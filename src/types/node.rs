@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -11,6 +11,10 @@ pub struct Node {
     pub node_id: NodeId,
     pub input_pads: Option<Vec<NodeId>>,
     pub fallback_id: Option<NodeId>,
+    /// Overrides `stream_fallback_timeout_ms` for input streams used directly by this
+    /// node. Frames of the input are kept as long as any node that uses it considers
+    /// them fresh, nodes with a shorter timeout treat the input as missing.
+    pub input_fallback_timeout_ms: Option<f64>,
 
     #[serde(flatten)]
     pub params: NodeParams,
@@ -23,16 +27,23 @@ pub enum NodeParams {
     Shader(Shader),
     Image(Image),
     Text(Text),
+    Captions(Captions),
+    Timecode(Timecode),
+    Replay(Replay),
     Transition(Transition),
     #[serde(rename = "builtin:fit_to_resolution")]
     FitToResolution(FitToResolution),
     #[serde(rename = "builtin:fill_to_resolution")]
     FillToResolution {
         resolution: Resolution,
+        /// Defaults to "bilinear".
+        scaling_quality: Option<ScalingQuality>,
     },
     #[serde(rename = "builtin:stretch_to_resolution")]
     StretchToResolution {
         resolution: Resolution,
+        /// Defaults to "bilinear".
+        scaling_quality: Option<ScalingQuality>,
     },
     #[serde(rename = "builtin:fixed_position_layout")]
     FixedPositionLayout(FixedPositionLayout),
@@ -42,6 +53,18 @@ pub enum NodeParams {
     MirrorImage(MirrorImage),
     #[serde(rename = "builtin:corners_rounding")]
     CornersRounding(CornersRounding),
+    #[serde(rename = "builtin:privacy_mask")]
+    PrivacyMask(PrivacyMask),
+    #[serde(rename = "builtin:stylize")]
+    Stylize(Stylize),
+    #[serde(rename = "builtin:scope")]
+    Scope(Scope),
+    #[serde(rename = "builtin:transform_2d")]
+    Transform2d(Transform2d),
+    #[serde(rename = "builtin:perspective")]
+    Perspective(Perspective),
+    #[serde(rename = "builtin:border")]
+    Border(Border),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -54,6 +77,11 @@ pub struct WebRenderer {
 #[serde(deny_unknown_fields)]
 pub struct Image {
     pub image_id: RendererId,
+    /// Resolution used to rasterize SVG images. Defaults to the resolution provided
+    /// when registering the image or the intrinsic size of the SVG. For images
+    /// registered with `nine_slice` it defines the output size. Other images are always
+    /// rendered in their original resolution.
+    pub resolution: Option<Resolution>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -61,7 +89,57 @@ pub struct Image {
 pub struct Shader {
     pub shader_id: RendererId,
     pub shader_params: Option<ShaderParam>,
+    /// Bound as a read-only storage buffer (`var<storage, read>`) at
+    /// `@group(1) @binding(1)`. Can contain a runtime-sized array.
+    pub storage_params: Option<ShaderParam>,
+    /// Registered images bound as `binding_array<texture_2d<f32>, 8>` at
+    /// `@group(1) @binding(2)`, in the same order. Unused slots are empty.
+    pub image_ids: Option<Vec<RendererId>>,
     pub resolution: Resolution,
+    /// Sampler used to read input textures. Defaults to linear filtering
+    /// with clamp-to-edge addressing.
+    pub sampler: Option<Sampler>,
+    /// Triangles drawn by the shader. Defaults to a plane covering the whole
+    /// output for every input.
+    pub geometry: Option<ShaderGeometry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ShaderGeometry {
+    /// Triangles of a mesh registered with `"entity_type": "mesh"`.
+    Mesh { mesh_id: RendererId },
+    /// List of triangles with `vertex_count` vertices (multiple of 3, at most
+    /// 262144). All fields of `VertexInput` are zero, positions are expected to
+    /// be computed from `@builtin(vertex_index)`.
+    Procedural { vertex_count: u32 },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Sampler {
+    /// Defaults to `linear`. Use `nearest` for pixel-art sources.
+    pub filter: Option<SamplerFilter>,
+    /// Defaults to `clamp_to_edge`.
+    pub address_mode: Option<SamplerAddressMode>,
+    /// Max anisotropy, one of 1, 2, 4, 8 or 16. Values other than 1 require
+    /// linear filtering. Defaults to 1.
+    pub anisotropy: Option<u16>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplerFilter {
+    Linear,
+    Nearest,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplerAddressMode {
+    ClampToEdge,
+    Repeat,
+    MirrorRepeat,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -77,6 +155,13 @@ pub enum ShaderParam {
     I32(i32),
     List(Vec<ShaderParam>),
     Struct(Vec<ShaderParamStructField>),
+    /// f32 value animated over time. Keyframes have to be sorted by time.
+    Keyframes(Vec<ShaderParamKeyframe>),
+    /// Struct fields keyed by name, in any order. Values are numbers, lists and
+    /// nested objects, converted to the types declared in the shader. Objects with
+    /// a "type" key are parsed as typed params (e.g. keyframes). Fields that are
+    /// not specified are filled with zeros.
+    Object(HashMap<String, serde_json::Value>),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -86,6 +171,17 @@ pub struct ShaderParamStructField {
     pub value: ShaderParam,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ShaderParamKeyframe {
+    /// Time relative to the first frame rendered by the node. Node is recreated
+    /// on every scene update, so animation starts from the beginning after each update.
+    pub time_ms: f64,
+    pub value: f32,
+    /// Interpolation used between this keyframe and the next one. Defaults to linear.
+    pub interpolation: Option<Interpolation>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Text {
@@ -134,6 +230,89 @@ pub enum TextWeight {
     Black,
 }
 
+/// Subtitles from an SRT or WebVTT document, displayed when their timestamps
+/// match the PTS of rendered frames.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Captions {
+    pub source: CaptionsSource,
+    pub resolution: Resolution,
+    pub font_size: f32,
+    pub line_height: Option<f32>,
+    /// default: "Verdana"
+    pub font_family: Option<String>,
+    pub weight: Option<TextWeight>,
+    /// default: "#FFFFFFFF"
+    pub color_rgba: Option<RGBAColor>,
+    /// default: center
+    pub align: Option<HorizontalAlign>,
+    /// default: bottom
+    pub position: Option<CaptionsPosition>,
+    /// Distance in pixels from the edges of the node, default: 5% of the height.
+    pub safe_area_margin: Option<u32>,
+    /// Delays all cues, default: 0
+    pub offset_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CaptionsSource {
+    File {
+        path: String,
+    },
+    /// Live captions, the document is downloaded again every `refresh_interval_ms`.
+    Url {
+        url: String,
+        /// default: 1000
+        refresh_interval_ms: Option<f64>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionsPosition {
+    Top,
+    Bottom,
+}
+
+/// Time of the rendered frame as `HH:MM:SS.mmm`, e.g. to compare timestamps of
+/// the same frame at different points of the distribution chain.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Timecode {
+    /// default: pts
+    pub source: Option<TimecodeSource>,
+    pub resolution: Resolution,
+    pub font_size: f32,
+    /// default: "Verdana"
+    pub font_family: Option<String>,
+    /// default: "#FFFFFFFF"
+    pub color_rgba: Option<RGBAColor>,
+    /// default: "#00000000"
+    pub background_color_rgba: Option<RGBAColor>,
+    /// default: center
+    pub align: Option<HorizontalAlign>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TimecodeSource {
+    /// PTS of the output frame.
+    Pts,
+    /// UTC time when the frame is rendered.
+    WallClock,
+}
+
+/// Frames from the replay buffer of the input, played back after the
+/// "start_replay" request. Node is empty when no replay is playing, so its
+/// fallback is shown instead. Resolution follows the frames of the input.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Replay {
+    /// Input registered with `replay_buffer_ms`.
+    pub input_id: InputId,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
 pub enum TextDimensions {
@@ -165,6 +344,10 @@ pub struct Transition {
 pub enum TransitionState {
     #[serde(rename = "builtin:fixed_position_layout")]
     FixedPositionLayout(FixedPositionLayout),
+    #[serde(rename = "builtin:transform_2d")]
+    Transform2d(Transform2d),
+    #[serde(rename = "builtin:perspective")]
+    Perspective(Perspective),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -181,6 +364,26 @@ pub struct FitToResolution {
     pub background_color_rgba: Option<RGBAColor>,
     pub horizontal_alignment: Option<HorizontalAlign>,
     pub vertical_alignment: Option<VerticalAlign>,
+    /// Generate mipmaps for input streams to reduce aliasing when they are downscaled.
+    /// Defaults to false.
+    pub mipmaps: Option<bool>,
+    /// Defaults to "bilinear".
+    pub scaling_quality: Option<ScalingQuality>,
+}
+
+/// Filter used to resample the input when it's scaled.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingQuality {
+    /// Blocky, but keeps hard edges of pixel art and text.
+    Nearest,
+    /// Cheapest smooth filter, uses mipmaps when they are enabled.
+    Bilinear,
+    /// Sharper than bilinear, reads all pixels covered by an output pixel, so it
+    /// doesn't shimmer when downscaling.
+    Bicubic,
+    /// Sharpest and most expensive, may add slight halos around hard edges.
+    Lanczos,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -189,6 +392,9 @@ pub struct FixedPositionLayout {
     pub resolution: Resolution,
     pub texture_layouts: Vec<TextureLayout>,
     pub background_color_rgba: Option<RGBAColor>,
+    /// Generate mipmaps for input streams to reduce aliasing when they are downscaled.
+    /// Defaults to false.
+    pub mipmaps: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -202,6 +408,77 @@ pub struct TextureLayout {
     pub rotation: Option<Degree>,
 }
 
+/// Rotates, scales and moves the input around the anchor point. Without translation
+/// the input is placed in the top-left corner of the output. Use it as a transition
+/// state to animate the parameters.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Transform2d {
+    /// Defaults to the resolution of the input.
+    pub resolution: Option<Resolution>,
+    /// Rotation around the anchor point. Defaults to 0.
+    pub rotation: Option<Degree>,
+    /// Scale in both directions. Defaults to 1.
+    pub scale: Option<f32>,
+    /// Horizontal scale, overrides `scale`.
+    pub scale_x: Option<f32>,
+    /// Vertical scale, overrides `scale`.
+    pub scale_y: Option<f32>,
+    /// Offset of the anchor point to the right. Percentages are relative to the
+    /// output width. Defaults to 0.
+    pub translate_x: Option<Coord>,
+    /// Offset of the anchor point to the bottom. Percentages are relative to the
+    /// output height. Defaults to 0.
+    pub translate_y: Option<Coord>,
+    /// Horizontal position of the anchor point relative to the input width, 0 is
+    /// the left edge and 1 is the right edge. Defaults to 0.5.
+    pub anchor_x: Option<f32>,
+    /// Vertical position of the anchor point relative to the input height, 0 is
+    /// the top edge and 1 is the bottom edge. Defaults to 0.5.
+    pub anchor_y: Option<f32>,
+    pub background_color_rgba: Option<RGBAColor>,
+}
+
+/// Rotates the input in 3D around the anchor point and projects it with perspective,
+/// e.g. for "flying window" transitions. Without rotation and translation the input
+/// is placed in the top-left corner of the output. Back side of the input is visible
+/// (mirrored) when it's turned away from the viewer.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Perspective {
+    /// Defaults to the resolution of the input.
+    pub resolution: Option<Resolution>,
+    /// Rotation around the horizontal axis, positive values move the top edge
+    /// toward the viewer. Defaults to 0.
+    pub rotation_x: Option<Degree>,
+    /// Rotation around the vertical axis, positive values move the right edge
+    /// away from the viewer. Defaults to 0.
+    pub rotation_y: Option<Degree>,
+    /// Rotation in the output plane, the same as `rotation` in "builtin:transform_2d".
+    /// Defaults to 0.
+    pub rotation_z: Option<Degree>,
+    /// Distance from the viewer to the output plane in pixels. Smaller values
+    /// exaggerate perspective. Defaults to the output width.
+    pub distance: Option<f32>,
+    /// Defaults to 1.
+    pub scale: Option<f32>,
+    /// Offset of the anchor point to the right. Percentages are relative to the
+    /// output width. Defaults to 0.
+    pub translate_x: Option<Coord>,
+    /// Offset of the anchor point to the bottom. Percentages are relative to the
+    /// output height. Defaults to 0.
+    pub translate_y: Option<Coord>,
+    /// Offset of the anchor point toward the viewer in pixels. Defaults to 0.
+    pub translate_z: Option<f32>,
+    /// Horizontal position of the anchor point relative to the input width, 0 is
+    /// the left edge and 1 is the right edge. Defaults to 0.5.
+    pub anchor_x: Option<f32>,
+    /// Vertical position of the anchor point relative to the input height, 0 is
+    /// the top edge and 1 is the bottom edge. Defaults to 0.5.
+    pub anchor_y: Option<f32>,
+    pub background_color_rgba: Option<RGBAColor>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct TiledLayout {
@@ -212,6 +489,9 @@ pub struct TiledLayout {
     pub padding: Option<u32>,
     pub horizontal_alignment: Option<HorizontalAlign>,
     pub vertical_alignment: Option<VerticalAlign>,
+    /// Generate mipmaps for input streams to reduce aliasing when they are downscaled.
+    /// Defaults to false.
+    pub mipmaps: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -235,3 +515,112 @@ pub enum MirrorMode {
 pub struct CornersRounding {
     pub border_radius: Coord,
 }
+
+/// Draws a border around the input, optionally with a shadow or an outer glow.
+/// Output is larger than the input by the border width and the space needed by
+/// the shadow on every side.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Border {
+    /// Width in pixels, at most 512. Defaults to 2.
+    pub width: Option<u32>,
+    /// Defaults to white.
+    pub color_rgba: Option<RGBAColor>,
+    /// Outer radius of the border, the input is rounded with the radius reduced by
+    /// the border width. Percentages are relative to the smaller side of the border.
+    /// Defaults to 0.
+    pub corner_radius: Option<Coord>,
+    pub shadow: Option<BorderShadow>,
+}
+
+/// Shadow of the border, use zero offset and a light color for an outer glow.
+/// Blur radius and offset together can not exceed 512 pixels.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BorderShadow {
+    /// Defaults to semi-transparent black.
+    pub color_rgba: Option<RGBAColor>,
+    /// Defaults to 8 pixels.
+    pub blur_radius: Option<u32>,
+    /// Offset to the right in pixels. Defaults to 0.
+    pub offset_x: Option<i32>,
+    /// Offset to the bottom in pixels. Defaults to 0.
+    pub offset_y: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PrivacyMask {
+    pub effect: PrivacyMaskEffect,
+    /// Regions of the input that should be hidden. At most 16 regions are supported.
+    pub regions: Vec<MaskRegion>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PrivacyMaskEffect {
+    Blur {
+        /// Blur radius in pixels.
+        radius: u32,
+    },
+    Pixelate {
+        /// Size of a single block in pixels.
+        block_size: u32,
+    },
+}
+
+/// Rectangle positioned relative to the top-left corner of the input.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MaskRegion {
+    pub top: Coord,
+    pub left: Coord,
+    pub width: Coord,
+    pub height: Coord,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Stylize {
+    pub effect: StylizeEffect,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum StylizeEffect {
+    EdgeDetect,
+    Posterize {
+        /// Number of values per color channel, at least 2.
+        levels: u32,
+    },
+    Vignette {
+        /// Value in range [0, 1]. Defaults to 0.5.
+        strength: Option<f32>,
+    },
+    FilmGrain {
+        /// Value in range [0, 1]. Defaults to 0.1.
+        strength: Option<f32>,
+    },
+    ChromaticAberration {
+        /// Offset of red and blue channels in pixels at the edges of the image.
+        /// Defaults to 4.
+        offset: Option<u32>,
+    },
+}
+
+/// Renders waveform, RGB parade or vectorscope of the input. Connect it to a
+/// dedicated output to monitor exposure and colors of any node.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Scope {
+    pub mode: ScopeMode,
+    pub resolution: Resolution,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScopeMode {
+    LumaWaveform,
+    RgbParade,
+    Vectorscope,
+}
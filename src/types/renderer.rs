@@ -42,10 +42,98 @@ pub struct ShaderSpec {
 #[serde(deny_unknown_fields)]
 pub struct WebRendererSpec {
     pub instance_id: RendererId,
-    pub url: String,
+    pub url: Option<String>,
+    /// Local HTML file, directory with "index.html" or a zip archive of such
+    /// directory, relative to the directory set in
+    /// `MEMBRANE_VIDEO_COMPOSITOR_WEB_BUNDLES_DIR`. Pages loaded this way can
+    /// fetch other files from the bundle.
+    pub path: Option<String>,
     pub resolution: Resolution,
     pub fallback_strategy: Option<FallbackStrategy>,
     pub constraints: Option<NodeConstraints>,
+    /// Areas of the page without a background are transparent in the node output,
+    /// instead of white. Defaults to false.
+    pub transparent_background: Option<bool>,
+    /// Defaults to false.
+    pub mute_audio: Option<bool>,
+    /// Defaults to "embed".
+    pub compositing_mode: Option<WebCompositingMode>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebCompositingMode {
+    /// Input sources are embedded in the page and can be placed and styled by it.
+    Embed,
+    /// Input sources are stretched to the node resolution and stacked in order,
+    /// the page is drawn on top of them (use with `transparent_background`).
+    /// Sources are not sent to the page, which is much cheaper than embedding.
+    Overlay,
+    /// Same as "overlay", but the page is drawn below the input sources.
+    Underlay,
+}
+
+/// Input event forwarded to a web renderer instance. Coordinates are in pixels,
+/// relative to the top-left corner of the page.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum WebRendererEvent {
+    MouseMove {
+        x: i32,
+        y: i32,
+    },
+    MouseDown {
+        x: i32,
+        y: i32,
+        /// Defaults to "left".
+        button: Option<MouseButton>,
+        /// Defaults to 1, set to 2 for the second click of a double click.
+        click_count: Option<i32>,
+    },
+    MouseUp {
+        x: i32,
+        y: i32,
+        button: Option<MouseButton>,
+        click_count: Option<i32>,
+    },
+    /// Scrolls the element under (x, y) by the delta in pixels.
+    MouseWheel {
+        x: i32,
+        y: i32,
+        delta_x: Option<i32>,
+        delta_y: Option<i32>,
+    },
+    /// Windows virtual key code, e.g. 13 for Enter or 37 for the left arrow.
+    KeyDown {
+        key_code: i32,
+        modifiers: Option<KeyModifiers>,
+    },
+    KeyUp {
+        key_code: i32,
+        modifiers: Option<KeyModifiers>,
+    },
+    /// Types the text into the focused element.
+    Text {
+        text: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct KeyModifiers {
+    pub shift: Option<bool>,
+    pub control: Option<bool>,
+    pub alt: Option<bool>,
+    /// Command key on macOS.
+    pub meta: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
@@ -55,11 +143,22 @@ pub enum ImageSpec {
         image_id: RendererId,
         url: Option<String>,
         path: Option<String>,
+        /// Number of times an animated PNG (APNG) should be played. Loops indefinitely
+        /// if not specified.
+        loop_count: Option<u32>,
+        /// Keep the last frame visible after the animation finishes. Defaults to true.
+        hold_last_frame: Option<bool>,
+        /// Render the image as a nine-slice. Corners keep their original size, while
+        /// edges and the center are stretched to the node resolution.
+        nine_slice: Option<NineSliceInsets>,
     },
     Jpeg {
         image_id: RendererId,
         url: Option<String>,
         path: Option<String>,
+        /// Render the image as a nine-slice. Corners keep their original size, while
+        /// edges and the center are stretched to the node resolution.
+        nine_slice: Option<NineSliceInsets>,
     },
     Svg {
         image_id: RendererId,
@@ -71,5 +170,77 @@ pub enum ImageSpec {
         image_id: RendererId,
         url: Option<String>,
         path: Option<String>,
+        /// Number of times the animation should be played. Loops indefinitely
+        /// if not specified.
+        loop_count: Option<u32>,
+        /// Keep the last frame visible after the animation finishes. Defaults to true.
+        hold_last_frame: Option<bool>,
+        /// Render the image as a nine-slice. Corners keep their original size, while
+        /// edges and the center are stretched to the node resolution.
+        nine_slice: Option<NineSliceInsets>,
+    },
+    Webp {
+        image_id: RendererId,
+        url: Option<String>,
+        path: Option<String>,
+        /// Number of times the animation should be played. Loops indefinitely
+        /// if not specified.
+        loop_count: Option<u32>,
+        /// Keep the last frame visible after the animation finishes. Defaults to true.
+        hold_last_frame: Option<bool>,
+        /// Render the image as a nine-slice. Corners keep their original size, while
+        /// edges and the center are stretched to the node resolution.
+        nine_slice: Option<NineSliceInsets>,
     },
 }
+
+impl ImageSpec {
+    pub fn image_id(&self) -> &RendererId {
+        match self {
+            ImageSpec::Png { image_id, .. }
+            | ImageSpec::Jpeg { image_id, .. }
+            | ImageSpec::Svg { image_id, .. }
+            | ImageSpec::Gif { image_id, .. }
+            | ImageSpec::Webp { image_id, .. } => image_id,
+        }
+    }
+}
+
+/// Geometry that shader nodes can draw instead of the planes of their inputs,
+/// selected with `"geometry": { "type": "mesh", "mesh_id": ... }`.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MeshSpec {
+    pub mesh_id: RendererId,
+    pub vertices: Vec<MeshVertex>,
+    /// List of triangles, 3 indices of `vertices` per triangle. If not specified,
+    /// every 3 consecutive vertices form a triangle.
+    pub indices: Option<Vec<u32>>,
+}
+
+/// Passed to the vertex shader as `VertexInput`.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MeshVertex {
+    /// Clip space position. Triangles facing away from the viewer (clockwise)
+    /// are not drawn.
+    pub position: [f32; 3],
+    /// Defaults to [0, 0].
+    pub texture_coords: Option<[f32; 2]>,
+    /// Index of the input texture. Defaults to -1 (no texture).
+    pub input_id: Option<i32>,
+    /// Any values passed to the shader as `data`, e.g. per-particle attributes.
+    /// Defaults to zeros.
+    pub data: Option<[f32; 4]>,
+}
+
+/// Size of the image borders (in pixels) that are not stretched when the image
+/// is rendered as a nine-slice.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NineSliceInsets {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}